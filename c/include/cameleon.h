@@ -0,0 +1,347 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+#ifndef CAMELEON_H
+#define CAMELEON_H
+
+/* This file is generated by cbindgen, don't edit it manually. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/// Type of a frame.
+typedef enum CameleonPayloadType {
+  /// The frame contains only an image.
+  CAMELEON_PAYLOAD_TYPE_IMAGE,
+  /// The frame contains an image and chunk data following the image.
+  CAMELEON_PAYLOAD_TYPE_IMAGE_EXTENDED_CHUNK,
+  /// The frame contains only chunk data.
+  CAMELEON_PAYLOAD_TYPE_CHUNK,
+  /// The frame contains a GenDC container.
+  CAMELEON_PAYLOAD_TYPE_GEN_DC,
+} CameleonPayloadType;
+
+/// Status code returned from fallible functions.
+///
+/// Call [`cameleon_last_error_message`] to get the detail of an error.
+typedef enum CameleonStatus {
+  /// The function succeeded.
+  CAMELEON_STATUS_OK = 0,
+  /// An argument is null or invalid, e.g. a string which is not valid UTF-8.
+  CAMELEON_STATUS_INVALID_ARGUMENT = -1,
+  /// The camera is not opened.
+  CAMELEON_STATUS_NOT_OPENED = -2,
+  /// The camera is busy, or opened by another application.
+  CAMELEON_STATUS_BUSY = -3,
+  /// The camera is disconnected from the host.
+  CAMELEON_STATUS_DISCONNECTED = -4,
+  /// The operation timed out.
+  CAMELEON_STATUS_TIMEOUT = -5,
+  /// The feature doesn't exist, or doesn't have the requested interface.
+  CAMELEON_STATUS_INVALID_FEATURE = -6,
+  /// The buffer given by the caller is too small. The required size is written back to the
+  /// size argument.
+  CAMELEON_STATUS_BUFFER_TOO_SMALL = -7,
+  /// Streaming is already started.
+  CAMELEON_STATUS_IN_STREAMING = -8,
+  /// Streaming is not started.
+  CAMELEON_STATUS_NOT_STREAMING = -9,
+  /// Other errors.
+  CAMELEON_STATUS_ERROR = -100,
+  /// The library panicked.
+  CAMELEON_STATUS_PANIC = -101,
+} CameleonStatus;
+
+/// Opaque handle of a camera.
+typedef struct CameleonCamera CameleonCamera;
+
+/// Opaque list of cameras returned from [`cameleon_enumerate_cameras`].
+typedef struct CameleonCameraList CameleonCameraList;
+
+/// Opaque handle of a frame received from a camera.
+typedef struct CameleonFrame CameleonFrame;
+
+/// Information of a frame.
+///
+/// Image related fields are valid only if `has_image` is `true`.
+typedef struct CameleonFrameInfo {
+  /// ID of the frame assigned by the camera.
+  uint64_t id;
+  /// Timestamp of the frame in nanoseconds. The origin depends on the camera.
+  uint64_t timestamp_ns;
+  /// Type of the frame.
+  enum CameleonPayloadType payload_type;
+  /// `true` if the frame contains an image.
+  bool has_image;
+  /// Width of the image.
+  size_t width;
+  /// Height of the image.
+  size_t height;
+  /// X offset of the image in pixels.
+  size_t x_offset;
+  /// Y offset of the image in pixels.
+  size_t y_offset;
+  /// `PFNC` code of the pixel format of the image.
+  uint32_t pixel_format;
+  /// Size of the image in bytes.
+  size_t image_size;
+} CameleonFrameInfo;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/// Enumerates all cameras connected to the host.
+///
+/// The list must be freed with [`cameleon_camera_list_free`].
+///
+/// # Safety
+/// `list` must be a valid pointer.
+enum CameleonStatus cameleon_enumerate_cameras(struct CameleonCameraList **list);
+
+/// Returns the number of cameras in the list, or 0 if `list` is null.
+///
+/// # Safety
+/// `list` must be null or a list returned from [`cameleon_enumerate_cameras`].
+size_t cameleon_camera_list_len(const struct CameleonCameraList *list);
+
+/// Takes the camera at `index` out of the list.
+///
+/// The camera must be freed with [`cameleon_camera_free`]. Taking the same camera twice fails
+/// with [`CameleonStatus::InvalidArgument`].
+///
+/// # Safety
+/// `list` must be a list returned from [`cameleon_enumerate_cameras`], and `camera` must be a
+/// valid pointer.
+enum CameleonStatus cameleon_camera_list_take(struct CameleonCameraList *list,
+                                              size_t index,
+                                              struct CameleonCamera **camera);
+
+/// Frees the list and the cameras which are not taken.
+///
+/// # Safety
+/// `list` must be null or a list returned from [`cameleon_enumerate_cameras`].
+void cameleon_camera_list_free(struct CameleonCameraList *list);
+
+/// Closes the camera if it's opened, then frees it.
+///
+/// # Safety
+/// `camera` must be null or a camera returned from [`cameleon_camera_list_take`].
+void cameleon_camera_free(struct CameleonCamera *camera);
+
+/// Opens the camera and loads its `GenApi` context.
+///
+/// # Safety
+/// `camera` must be a camera returned from [`cameleon_camera_list_take`].
+enum CameleonStatus cameleon_camera_open(struct CameleonCamera *camera);
+
+/// Stops streaming if it's started, then closes the camera.
+///
+/// # Safety
+/// `camera` must be a camera returned from [`cameleon_camera_list_take`].
+enum CameleonStatus cameleon_camera_close(struct CameleonCamera *camera);
+
+/// Returns the vendor name of the camera, or null if `camera` is null.
+///
+/// The returned string is valid while the camera is alive.
+///
+/// # Safety
+/// `camera` must be null or a camera returned from [`cameleon_camera_list_take`].
+const char *cameleon_camera_vendor_name(const struct CameleonCamera *camera);
+
+/// Returns the model name of the camera, or null if `camera` is null.
+///
+/// The returned string is valid while the camera is alive.
+///
+/// # Safety
+/// `camera` must be null or a camera returned from [`cameleon_camera_list_take`].
+const char *cameleon_camera_model_name(const struct CameleonCamera *camera);
+
+/// Returns the serial number of the camera, or null if `camera` is null.
+///
+/// The returned string is valid while the camera is alive.
+///
+/// # Safety
+/// `camera` must be null or a camera returned from [`cameleon_camera_list_take`].
+const char *cameleon_camera_serial_number(const struct CameleonCamera *camera);
+
+/// Starts streaming. At most `capacity` frames are kept until they are received.
+///
+/// # Safety
+/// `camera` must be a camera returned from [`cameleon_camera_list_take`].
+enum CameleonStatus cameleon_camera_start_streaming(struct CameleonCamera *camera, size_t capacity);
+
+/// Stops streaming.
+///
+/// # Safety
+/// `camera` must be a camera returned from [`cameleon_camera_list_take`].
+enum CameleonStatus cameleon_camera_stop_streaming(struct CameleonCamera *camera);
+
+/// Waits for a frame at most `timeout_ms` milliseconds.
+///
+/// The frame must be released with [`cameleon_camera_release_frame`].
+///
+/// # Safety
+/// `camera` must be a camera returned from [`cameleon_camera_list_take`], and `frame` must be a
+/// valid pointer.
+enum CameleonStatus cameleon_camera_receive_frame(struct CameleonCamera *camera,
+                                                  uint32_t timeout_ms,
+                                                  struct CameleonFrame **frame);
+
+/// Releases the frame. The buffer of the frame is reused for following frames if streaming is
+/// still running.
+///
+/// # Safety
+/// `camera` must be a camera returned from [`cameleon_camera_list_take`], and `frame` must be
+/// null or a frame returned from [`cameleon_camera_receive_frame`] of the camera.
+void cameleon_camera_release_frame(struct CameleonCamera *camera, struct CameleonFrame *frame);
+
+/// Returns the message of the last error occurred in the calling thread, or null if no error
+/// has occurred.
+///
+/// The returned string is valid until the next call of any function of this library in the
+/// same thread.
+const char *cameleon_last_error_message(void);
+
+/// Reads the value of a feature which has `IInteger` interface.
+///
+/// # Safety
+/// `camera` must be an opened camera, `name` must be a NUL terminated string, and
+/// `value` must be a valid pointer.
+enum CameleonStatus cameleon_camera_get_integer(struct CameleonCamera *camera,
+                                                const char *name,
+                                                int64_t *value);
+
+/// Writes the value to a feature which has `IInteger` interface.
+///
+/// # Safety
+/// `camera` must be an opened camera, and `name` must be a NUL terminated string.
+enum CameleonStatus cameleon_camera_set_integer(struct CameleonCamera *camera,
+                                                const char *name,
+                                                int64_t value);
+
+/// Reads the value of a feature which has `IFloat` interface.
+///
+/// # Safety
+/// `camera` must be an opened camera, `name` must be a NUL terminated string, and
+/// `value` must be a valid pointer.
+enum CameleonStatus cameleon_camera_get_float(struct CameleonCamera *camera,
+                                              const char *name,
+                                              double *value);
+
+/// Writes the value to a feature which has `IFloat` interface.
+///
+/// # Safety
+/// `camera` must be an opened camera, and `name` must be a NUL terminated string.
+enum CameleonStatus cameleon_camera_set_float(struct CameleonCamera *camera,
+                                              const char *name,
+                                              double value);
+
+/// Reads the value of a feature which has `IBoolean` interface.
+///
+/// # Safety
+/// `camera` must be an opened camera, `name` must be a NUL terminated string, and
+/// `value` must be a valid pointer.
+enum CameleonStatus cameleon_camera_get_boolean(struct CameleonCamera *camera,
+                                                const char *name,
+                                                bool *value);
+
+/// Writes the value to a feature which has `IBoolean` interface.
+///
+/// # Safety
+/// `camera` must be an opened camera, and `name` must be a NUL terminated string.
+enum CameleonStatus cameleon_camera_set_boolean(struct CameleonCamera *camera,
+                                                const char *name,
+                                                bool value);
+
+/// Reads the value of a feature which has `IString` interface as a NUL terminated string.
+///
+/// If `buf` is null, the required size of the buffer including the NUL is written to `size`.
+///
+/// # Safety
+/// `camera` must be an opened camera, `name` must be a NUL terminated string, `buf` must be null
+/// or a buffer of at least `*size` bytes, and `size` must be a valid pointer.
+enum CameleonStatus cameleon_camera_get_string(struct CameleonCamera *camera,
+                                               const char *name,
+                                               char *buf,
+                                               size_t *size);
+
+/// Writes the value to a feature which has `IString` interface.
+///
+/// # Safety
+/// `camera` must be an opened camera, and `name` and `value` must be NUL terminated strings.
+enum CameleonStatus cameleon_camera_set_string(struct CameleonCamera *camera,
+                                               const char *name,
+                                               const char *value);
+
+/// Reads the symbolic name of the current entry of a feature which has `IEnumeration`
+/// interface as a NUL terminated string.
+///
+/// If `buf` is null, the required size of the buffer including the NUL is written to `size`.
+///
+/// # Safety
+/// `camera` must be an opened camera, `name` must be a NUL terminated string, `buf` must be null
+/// or a buffer of at least `*size` bytes, and `size` must be a valid pointer.
+enum CameleonStatus cameleon_camera_get_enumeration(struct CameleonCamera *camera,
+                                                    const char *name,
+                                                    char *buf,
+                                                    size_t *size);
+
+/// Sets the entry of a feature which has `IEnumeration` interface by its symbolic name.
+///
+/// # Safety
+/// `camera` must be an opened camera, and `name` and `entry` must be NUL terminated strings.
+enum CameleonStatus cameleon_camera_set_enumeration(struct CameleonCamera *camera,
+                                                    const char *name,
+                                                    const char *entry);
+
+/// Executes a feature which has `ICommand` interface.
+///
+/// # Safety
+/// `camera` must be an opened camera, and `name` must be a NUL terminated string.
+enum CameleonStatus cameleon_camera_execute_command(struct CameleonCamera *camera,
+                                                    const char *name);
+
+/// Writes information of the frame to `info`.
+///
+/// # Safety
+/// `frame` must be a frame returned from [`cameleon_camera_receive_frame`], and `info` must be a
+/// valid pointer.
+///
+/// [`cameleon_camera_receive_frame`]: crate::cameleon_camera_receive_frame
+enum CameleonStatus cameleon_frame_info(const struct CameleonFrame *frame,
+                                        struct CameleonFrameInfo *info);
+
+/// Returns the whole payload of the frame including chunk data, or null if `frame` is null.
+///
+/// The size of the payload is written to `size` if it's not null. The returned data is valid
+/// until the frame is released.
+///
+/// # Safety
+/// `frame` must be null or a frame returned from [`cameleon_camera_receive_frame`], and `size`
+/// must be null or a valid pointer.
+///
+/// [`cameleon_camera_receive_frame`]: crate::cameleon_camera_receive_frame
+const uint8_t *cameleon_frame_data(const struct CameleonFrame *frame, size_t *size);
+
+/// Returns the image of the frame, or null if the frame doesn't contain an image.
+///
+/// The size of the image is written to `size` if it's not null. The returned data is valid
+/// until the frame is released.
+///
+/// # Safety
+/// `frame` must be null or a frame returned from [`cameleon_camera_receive_frame`], and `size`
+/// must be null or a valid pointer.
+///
+/// [`cameleon_camera_receive_frame`]: crate::cameleon_camera_receive_frame
+const uint8_t *cameleon_frame_image(const struct CameleonFrame *frame, size_t *size);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* CAMELEON_H */
@@ -0,0 +1,379 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{ffi::CString, os::raw::c_char, ptr, time::Duration};
+
+use async_std::{future, task};
+use cameleon::{
+    payload::{Payload, PayloadReceiver},
+    u3v, Camera, DeviceControl, PayloadStream,
+};
+
+use super::{
+    error::{as_mut, as_ref, guard, CameleonStatus, Error, Result},
+    frame::CameleonFrame,
+};
+
+pub(crate) type DynCamera = Camera<Box<dyn DeviceControl>, Box<dyn PayloadStream>>;
+
+/// Opaque handle of a camera.
+pub struct CameleonCamera {
+    pub(crate) inner: DynCamera,
+    receiver: Option<PayloadReceiver>,
+    vendor_name: CString,
+    model_name: CString,
+    serial_number: CString,
+}
+
+impl CameleonCamera {
+    fn new(inner: DynCamera) -> Self {
+        // Interior NUL can't be represented in C strings.
+        let c_string = |s: &str| CString::new(s.replace('\0', "")).unwrap();
+        let info = inner.info();
+        Self {
+            vendor_name: c_string(&info.vendor_name),
+            model_name: c_string(&info.model_name),
+            serial_number: c_string(&info.serial_number),
+            inner,
+            receiver: None,
+        }
+    }
+}
+
+/// Opaque list of cameras returned from [`cameleon_enumerate_cameras`].
+pub struct CameleonCameraList {
+    cameras: Vec<Option<CameleonCamera>>,
+}
+
+/// Enumerates all cameras connected to the host.
+///
+/// The list must be freed with [`cameleon_camera_list_free`].
+///
+/// # Safety
+/// `list` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn cameleon_enumerate_cameras(
+    list: *mut *mut CameleonCameraList,
+) -> CameleonStatus {
+    guard(|| {
+        let list = as_mut(list, "list")?;
+        let cameras = u3v::enumerate_cameras()?
+            .into_iter()
+            .map(|camera| Some(CameleonCamera::new(camera.convert_into())))
+            .collect();
+        *list = Box::into_raw(Box::new(CameleonCameraList { cameras }));
+        Ok(())
+    })
+}
+
+/// Returns the number of cameras in the list, or 0 if `list` is null.
+///
+/// # Safety
+/// `list` must be null or a list returned from [`cameleon_enumerate_cameras`].
+#[no_mangle]
+pub unsafe extern "C" fn cameleon_camera_list_len(list: *const CameleonCameraList) -> usize {
+    list.as_ref().map_or(0, |list| list.cameras.len())
+}
+
+/// Takes the camera at `index` out of the list.
+///
+/// The camera must be freed with [`cameleon_camera_free`]. Taking the same camera twice fails
+/// with [`CameleonStatus::InvalidArgument`].
+///
+/// # Safety
+/// `list` must be a list returned from [`cameleon_enumerate_cameras`], and `camera` must be a
+/// valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn cameleon_camera_list_take(
+    list: *mut CameleonCameraList,
+    index: usize,
+    camera: *mut *mut CameleonCamera,
+) -> CameleonStatus {
+    guard(|| {
+        let list = as_mut(list, "list")?;
+        let camera = as_mut(camera, "camera")?;
+        let taken = list
+            .cameras
+            .get_mut(index)
+            .ok_or_else(|| Error::invalid_argument(format!("index {} is out of range", index)))?
+            .take()
+            .ok_or_else(|| {
+                Error::invalid_argument(format!("camera at {} is already taken", index))
+            })?;
+        *camera = Box::into_raw(Box::new(taken));
+        Ok(())
+    })
+}
+
+/// Frees the list and the cameras which are not taken.
+///
+/// # Safety
+/// `list` must be null or a list returned from [`cameleon_enumerate_cameras`].
+#[no_mangle]
+pub unsafe extern "C" fn cameleon_camera_list_free(list: *mut CameleonCameraList) {
+    if !list.is_null() {
+        guard(|| {
+            drop(Box::from_raw(list));
+            Ok(())
+        });
+    }
+}
+
+/// Closes the camera if it's opened, then frees it.
+///
+/// # Safety
+/// `camera` must be null or a camera returned from [`cameleon_camera_list_take`].
+#[no_mangle]
+pub unsafe extern "C" fn cameleon_camera_free(camera: *mut CameleonCamera) {
+    if !camera.is_null() {
+        guard(|| {
+            let mut camera = Box::from_raw(camera);
+            camera.inner.close()?;
+            Ok(())
+        });
+    }
+}
+
+/// Opens the camera and loads its `GenApi` context.
+///
+/// # Safety
+/// `camera` must be a camera returned from [`cameleon_camera_list_take`].
+#[no_mangle]
+pub unsafe extern "C" fn cameleon_camera_open(camera: *mut CameleonCamera) -> CameleonStatus {
+    guard(|| {
+        let camera = as_mut(camera, "camera")?;
+        camera.inner.open()?;
+        camera.inner.load_context()?;
+        Ok(())
+    })
+}
+
+/// Stops streaming if it's started, then closes the camera.
+///
+/// # Safety
+/// `camera` must be a camera returned from [`cameleon_camera_list_take`].
+#[no_mangle]
+pub unsafe extern "C" fn cameleon_camera_close(camera: *mut CameleonCamera) -> CameleonStatus {
+    guard(|| {
+        let camera = as_mut(camera, "camera")?;
+        camera.receiver = None;
+        camera.inner.close()?;
+        Ok(())
+    })
+}
+
+/// Returns the vendor name of the camera, or null if `camera` is null.
+///
+/// The returned string is valid while the camera is alive.
+///
+/// # Safety
+/// `camera` must be null or a camera returned from [`cameleon_camera_list_take`].
+#[no_mangle]
+pub unsafe extern "C" fn cameleon_camera_vendor_name(
+    camera: *const CameleonCamera,
+) -> *const c_char {
+    camera
+        .as_ref()
+        .map_or(ptr::null(), |camera| camera.vendor_name.as_ptr())
+}
+
+/// Returns the model name of the camera, or null if `camera` is null.
+///
+/// The returned string is valid while the camera is alive.
+///
+/// # Safety
+/// `camera` must be null or a camera returned from [`cameleon_camera_list_take`].
+#[no_mangle]
+pub unsafe extern "C" fn cameleon_camera_model_name(
+    camera: *const CameleonCamera,
+) -> *const c_char {
+    camera
+        .as_ref()
+        .map_or(ptr::null(), |camera| camera.model_name.as_ptr())
+}
+
+/// Returns the serial number of the camera, or null if `camera` is null.
+///
+/// The returned string is valid while the camera is alive.
+///
+/// # Safety
+/// `camera` must be null or a camera returned from [`cameleon_camera_list_take`].
+#[no_mangle]
+pub unsafe extern "C" fn cameleon_camera_serial_number(
+    camera: *const CameleonCamera,
+) -> *const c_char {
+    camera
+        .as_ref()
+        .map_or(ptr::null(), |camera| camera.serial_number.as_ptr())
+}
+
+/// Starts streaming. At most `capacity` frames are kept until they are received.
+///
+/// # Safety
+/// `camera` must be a camera returned from [`cameleon_camera_list_take`].
+#[no_mangle]
+pub unsafe extern "C" fn cameleon_camera_start_streaming(
+    camera: *mut CameleonCamera,
+    capacity: usize,
+) -> CameleonStatus {
+    guard(|| {
+        let camera = as_mut(camera, "camera")?;
+        if capacity == 0 {
+            return Err(Error::invalid_argument("`capacity` must be larger than 0"));
+        }
+        camera.receiver = Some(camera.inner.start_streaming(capacity)?);
+        Ok(())
+    })
+}
+
+/// Stops streaming.
+///
+/// # Safety
+/// `camera` must be a camera returned from [`cameleon_camera_list_take`].
+#[no_mangle]
+pub unsafe extern "C" fn cameleon_camera_stop_streaming(
+    camera: *mut CameleonCamera,
+) -> CameleonStatus {
+    guard(|| {
+        let camera = as_mut(camera, "camera")?;
+        camera.receiver = None;
+        camera.inner.stop_streaming()?;
+        Ok(())
+    })
+}
+
+/// Waits for a frame at most `timeout_ms` milliseconds.
+///
+/// The frame must be released with [`cameleon_camera_release_frame`].
+///
+/// # Safety
+/// `camera` must be a camera returned from [`cameleon_camera_list_take`], and `frame` must be a
+/// valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn cameleon_camera_receive_frame(
+    camera: *mut CameleonCamera,
+    timeout_ms: u32,
+    frame: *mut *mut CameleonFrame,
+) -> CameleonStatus {
+    guard(|| {
+        let camera = as_ref(camera, "camera")?;
+        let frame = as_mut(frame, "frame")?;
+        let payload = receive(camera, Duration::from_millis(timeout_ms.into()))?;
+        *frame = Box::into_raw(Box::new(CameleonFrame { payload }));
+        Ok(())
+    })
+}
+
+/// Releases the frame. The buffer of the frame is reused for following frames if streaming is
+/// still running.
+///
+/// # Safety
+/// `camera` must be a camera returned from [`cameleon_camera_list_take`], and `frame` must be
+/// null or a frame returned from [`cameleon_camera_receive_frame`] of the camera.
+#[no_mangle]
+pub unsafe extern "C" fn cameleon_camera_release_frame(
+    camera: *mut CameleonCamera,
+    frame: *mut CameleonFrame,
+) {
+    if frame.is_null() {
+        return;
+    }
+    guard(|| {
+        let frame = Box::from_raw(frame);
+        if let Some(receiver) = camera.as_ref().and_then(|camera| camera.receiver.as_ref()) {
+            receiver.send_back(frame.payload);
+        }
+        Ok(())
+    });
+}
+
+fn receive(camera: &CameleonCamera, timeout: Duration) -> Result<Payload> {
+    let receiver = camera
+        .receiver
+        .as_ref()
+        .ok_or_else(|| Error::new(CameleonStatus::NotStreaming, "streaming is not started"))?;
+    let payload = task::block_on(future::timeout(timeout, receiver.recv()))
+        .map_err(|_| Error::new(CameleonStatus::Timeout, "no frame arrived in time"))??;
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_null_camera() {
+        unsafe {
+            assert_eq!(
+                cameleon_enumerate_cameras(ptr::null_mut()),
+                CameleonStatus::InvalidArgument
+            );
+            assert_eq!(
+                cameleon_camera_open(ptr::null_mut()),
+                CameleonStatus::InvalidArgument
+            );
+            assert_eq!(
+                cameleon_camera_close(ptr::null_mut()),
+                CameleonStatus::InvalidArgument
+            );
+            assert_eq!(
+                cameleon_camera_start_streaming(ptr::null_mut(), 3),
+                CameleonStatus::InvalidArgument
+            );
+            assert_eq!(
+                cameleon_camera_stop_streaming(ptr::null_mut()),
+                CameleonStatus::InvalidArgument
+            );
+            let mut frame = ptr::null_mut();
+            assert_eq!(
+                cameleon_camera_receive_frame(ptr::null_mut(), 0, &mut frame),
+                CameleonStatus::InvalidArgument
+            );
+            assert!(frame.is_null());
+
+            assert!(cameleon_camera_vendor_name(ptr::null()).is_null());
+            assert!(cameleon_camera_model_name(ptr::null()).is_null());
+            assert!(cameleon_camera_serial_number(ptr::null()).is_null());
+
+            // Freeing null is a no-op.
+            cameleon_camera_free(ptr::null_mut());
+            cameleon_camera_release_frame(ptr::null_mut(), ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_camera_list() {
+        unsafe {
+            assert_eq!(cameleon_camera_list_len(ptr::null()), 0);
+            cameleon_camera_list_free(ptr::null_mut());
+
+            let list = Box::into_raw(Box::new(CameleonCameraList {
+                cameras: vec![None],
+            }));
+            assert_eq!(cameleon_camera_list_len(list), 1);
+
+            let mut camera = ptr::null_mut();
+            assert_eq!(
+                cameleon_camera_list_take(list, 0, ptr::null_mut()),
+                CameleonStatus::InvalidArgument
+            );
+            assert_eq!(
+                cameleon_camera_list_take(ptr::null_mut(), 0, &mut camera),
+                CameleonStatus::InvalidArgument
+            );
+            // The camera at 0 is already taken.
+            assert_eq!(
+                cameleon_camera_list_take(list, 0, &mut camera),
+                CameleonStatus::InvalidArgument
+            );
+            assert_eq!(
+                cameleon_camera_list_take(list, 1, &mut camera),
+                CameleonStatus::InvalidArgument
+            );
+            assert!(camera.is_null());
+
+            cameleon_camera_list_free(list);
+        }
+    }
+}
@@ -0,0 +1,290 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{
+    cell::RefCell,
+    ffi::{CStr, CString},
+    os::raw::c_char,
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+use cameleon::{genapi::GenApiError, CameleonError, ControlError, StreamError};
+
+/// Status code returned from fallible functions.
+///
+/// Call [`cameleon_last_error_message`] to get the detail of an error.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameleonStatus {
+    /// The function succeeded.
+    Ok = 0,
+    /// An argument is null or invalid, e.g. a string which is not valid UTF-8.
+    InvalidArgument = -1,
+    /// The camera is not opened.
+    NotOpened = -2,
+    /// The camera is busy, or opened by another application.
+    Busy = -3,
+    /// The camera is disconnected from the host.
+    Disconnected = -4,
+    /// The operation timed out.
+    Timeout = -5,
+    /// The feature doesn't exist, or doesn't have the requested interface.
+    InvalidFeature = -6,
+    /// The buffer given by the caller is too small. The required size is written back to the
+    /// size argument.
+    BufferTooSmall = -7,
+    /// Streaming is already started.
+    InStreaming = -8,
+    /// Streaming is not started.
+    NotStreaming = -9,
+    /// Other errors.
+    Error = -100,
+    /// The library panicked.
+    Panic = -101,
+}
+
+pub(crate) type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub(crate) struct Error {
+    status: CameleonStatus,
+    message: String,
+}
+
+impl Error {
+    pub(crate) fn new(status: CameleonStatus, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn invalid_argument(message: impl Into<String>) -> Self {
+        Self::new(CameleonStatus::InvalidArgument, message)
+    }
+}
+
+impl From<CameleonError> for Error {
+    fn from(err: CameleonError) -> Self {
+        let status = match &err {
+            CameleonError::ControlError(e) => match e {
+                ControlError::NotOpened => CameleonStatus::NotOpened,
+                ControlError::Busy => CameleonStatus::Busy,
+                ControlError::Disconnected => CameleonStatus::Disconnected,
+                ControlError::Timeout => CameleonStatus::Timeout,
+                _ => CameleonStatus::Error,
+            },
+            CameleonError::StreamError(e) => match e {
+                StreamError::Disconnected => CameleonStatus::Disconnected,
+                StreamError::Timeout => CameleonStatus::Timeout,
+                StreamError::InStreaming => CameleonStatus::InStreaming,
+                _ => CameleonStatus::Error,
+            },
+            CameleonError::GenApiError(e) => match e {
+                GenApiError::InvalidNode(..) => CameleonStatus::InvalidFeature,
+                _ => CameleonStatus::Error,
+            },
+            _ => CameleonStatus::Error,
+        };
+        Self::new(status, err.to_string())
+    }
+}
+
+impl From<GenApiError> for Error {
+    fn from(err: GenApiError) -> Self {
+        CameleonError::from(err).into()
+    }
+}
+
+impl From<StreamError> for Error {
+    fn from(err: StreamError) -> Self {
+        CameleonError::from(err).into()
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+/// Returns the message of the last error occurred in the calling thread, or null if no error
+/// has occurred.
+///
+/// The returned string is valid until the next call of any function of this library in the
+/// same thread.
+#[no_mangle]
+pub extern "C" fn cameleon_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+fn set_last_error(message: String) {
+    // Interior NUL can't be represented in C strings.
+    let message = CString::new(message.replace('\0', "")).unwrap();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Runs `f` and converts its result into [`CameleonStatus`], catching a panic so that it doesn't
+/// unwind across the FFI boundary.
+pub(crate) fn guard(f: impl FnOnce() -> Result<()>) -> CameleonStatus {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => CameleonStatus::Ok,
+        Ok(Err(err)) => {
+            set_last_error(err.message);
+            err.status
+        }
+        Err(_) => {
+            set_last_error("cameleon panicked".into());
+            CameleonStatus::Panic
+        }
+    }
+}
+
+pub(crate) unsafe fn as_ref<'a, T>(ptr: *const T, name: &str) -> Result<&'a T> {
+    ptr.as_ref()
+        .ok_or_else(|| Error::invalid_argument(format!("`{}` is null", name)))
+}
+
+pub(crate) unsafe fn as_mut<'a, T>(ptr: *mut T, name: &str) -> Result<&'a mut T> {
+    ptr.as_mut()
+        .ok_or_else(|| Error::invalid_argument(format!("`{}` is null", name)))
+}
+
+pub(crate) unsafe fn as_str<'a>(ptr: *const c_char, name: &str) -> Result<&'a str> {
+    if ptr.is_null() {
+        return Err(Error::invalid_argument(format!("`{}` is null", name)));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|_| Error::invalid_argument(format!("`{}` is not valid UTF-8", name)))
+}
+
+/// Copies `s` into `buf` as a NUL terminated string.
+///
+/// If `buf` is null, only the required size including the NUL is written to `size`.
+pub(crate) unsafe fn copy_str(s: &str, buf: *mut c_char, size: *mut usize) -> Result<()> {
+    let size = as_mut(size, "size")?;
+    let required = s.len() + 1;
+    if buf.is_null() {
+        *size = required;
+        return Ok(());
+    }
+    if *size < required {
+        *size = required;
+        return Err(Error::new(
+            CameleonStatus::BufferTooSmall,
+            format!("buffer size must be at least {} bytes", required),
+        ));
+    }
+
+    ptr::copy_nonoverlapping(s.as_ptr(), buf.cast(), s.len());
+    *buf.add(s.len()) = 0;
+    *size = required;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_error_message() -> String {
+        let message = cameleon_last_error_message();
+        assert!(!message.is_null());
+        unsafe { CStr::from_ptr(message) }
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_guard() {
+        assert_eq!(guard(|| Ok(())), CameleonStatus::Ok);
+
+        let status = guard(|| Err(Error::new(CameleonStatus::Timeout, "timed\0 out")));
+        assert_eq!(status, CameleonStatus::Timeout);
+        assert_eq!(last_error_message(), "timed out");
+
+        let status = guard(|| panic!("boom"));
+        assert_eq!(status, CameleonStatus::Panic);
+        assert_eq!(last_error_message(), "cameleon panicked");
+    }
+
+    #[test]
+    fn test_status_from_error() {
+        let status = |err: CameleonError| Error::from(err).status;
+        assert_eq!(
+            status(ControlError::NotOpened.into()),
+            CameleonStatus::NotOpened
+        );
+        assert_eq!(status(ControlError::Busy.into()), CameleonStatus::Busy);
+        assert_eq!(
+            status(ControlError::Disconnected.into()),
+            CameleonStatus::Disconnected
+        );
+        assert_eq!(
+            status(ControlError::Timeout.into()),
+            CameleonStatus::Timeout
+        );
+        assert_eq!(
+            status(ControlError::InvalidData("invalid".into()).into()),
+            CameleonStatus::Error
+        );
+        assert_eq!(
+            status(StreamError::InStreaming.into()),
+            CameleonStatus::InStreaming
+        );
+        assert_eq!(status(StreamError::Timeout.into()), CameleonStatus::Timeout);
+        assert_eq!(
+            status(GenApiError::InvalidNode("invalid".into()).into()),
+            CameleonStatus::InvalidFeature
+        );
+        assert_eq!(
+            status(GenApiError::InvalidData("invalid".into()).into()),
+            CameleonStatus::Error
+        );
+    }
+
+    #[test]
+    fn test_null_arguments() {
+        unsafe {
+            let err = as_ref(ptr::null::<u8>(), "value").unwrap_err();
+            assert_eq!(err.status, CameleonStatus::InvalidArgument);
+            assert_eq!(err.message, "`value` is null");
+            assert!(as_mut(ptr::null_mut::<u8>(), "value").is_err());
+
+            assert!(as_str(ptr::null(), "name").is_err());
+            let invalid = b"\xff\0";
+            let err = as_str(invalid.as_ptr().cast(), "name").unwrap_err();
+            assert_eq!(err.message, "`name` is not valid UTF-8");
+            assert_eq!(as_str(b"Width\0".as_ptr().cast(), "name").unwrap(), "Width");
+        }
+    }
+
+    #[test]
+    fn test_copy_str() {
+        unsafe {
+            // Only the required size is written if `buf` is null.
+            let mut size = 0;
+            copy_str("Mono8", ptr::null_mut(), &mut size).unwrap();
+            assert_eq!(size, 6);
+
+            let mut buf = [1 as c_char; 6];
+            let mut size = 5;
+            let err = copy_str("Mono8", buf.as_mut_ptr(), &mut size).unwrap_err();
+            assert_eq!(err.status, CameleonStatus::BufferTooSmall);
+            assert_eq!(size, 6);
+            assert!(buf.iter().all(|b| *b == 1));
+
+            let mut size = buf.len();
+            copy_str("Mono8", buf.as_mut_ptr(), &mut size).unwrap();
+            assert_eq!(size, 6);
+            assert_eq!(CStr::from_ptr(buf.as_ptr()).to_str().unwrap(), "Mono8");
+
+            assert!(copy_str("Mono8", buf.as_mut_ptr(), ptr::null_mut()).is_err());
+        }
+    }
+}
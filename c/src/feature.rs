@@ -0,0 +1,273 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! `GenApi` feature access by name.
+
+use std::os::raw::c_char;
+
+use cameleon::{
+    genapi::{DefaultGenApiCtxt, Node, ParamsCtxt},
+    DeviceControl,
+};
+
+use super::{
+    camera::CameleonCamera,
+    error::{as_mut, as_str, copy_str, guard, CameleonStatus, Error, Result},
+};
+
+type Ctxt<'a> = ParamsCtxt<&'a mut Box<dyn DeviceControl>, &'a mut DefaultGenApiCtxt>;
+
+unsafe fn with_feature<R>(
+    camera: *mut CameleonCamera,
+    name: *const c_char,
+    f: impl FnOnce(&str, Node, &mut Ctxt) -> Result<R>,
+) -> Result<R> {
+    let camera = as_mut(camera, "camera")?;
+    let name = as_str(name, "name")?;
+    let mut ctxt = camera.inner.params_ctxt()?;
+    let node = ctxt.node(name).ok_or_else(|| {
+        Error::new(
+            CameleonStatus::InvalidFeature,
+            format!("feature `{}` doesn't exist", name),
+        )
+    })?;
+    f(name, node, &mut ctxt)
+}
+
+fn interface_mismatch(name: &str, interface: &str) -> Error {
+    Error::new(
+        CameleonStatus::InvalidFeature,
+        format!("feature `{}` doesn't have `{}` interface", name, interface),
+    )
+}
+
+macro_rules! feature_accessor {
+    (
+        $(#[$get_meta:meta])* get: $get:ident,
+        $(#[$set_meta:meta])* set: $set:ident,
+        $as_kind:ident, $interface:literal, $ty:ty
+    ) => {
+        $(#[$get_meta])*
+        ///
+        /// # Safety
+        /// `camera` must be an opened camera, `name` must be a NUL terminated string, and
+        /// `value` must be a valid pointer.
+        #[no_mangle]
+        pub unsafe extern "C" fn $get(
+            camera: *mut CameleonCamera,
+            name: *const c_char,
+            value: *mut $ty,
+        ) -> CameleonStatus {
+            guard(|| {
+                let value = as_mut(value, "value")?;
+                *value = with_feature(camera, name, |name, node, ctxt| {
+                    let node = node
+                        .$as_kind(ctxt)
+                        .ok_or_else(|| interface_mismatch(name, $interface))?;
+                    Ok(node.value(ctxt)?)
+                })?;
+                Ok(())
+            })
+        }
+
+        $(#[$set_meta])*
+        ///
+        /// # Safety
+        /// `camera` must be an opened camera, and `name` must be a NUL terminated string.
+        #[no_mangle]
+        pub unsafe extern "C" fn $set(
+            camera: *mut CameleonCamera,
+            name: *const c_char,
+            value: $ty,
+        ) -> CameleonStatus {
+            guard(|| {
+                with_feature(camera, name, |name, node, ctxt| {
+                    let node = node
+                        .$as_kind(ctxt)
+                        .ok_or_else(|| interface_mismatch(name, $interface))?;
+                    Ok(node.set_value(ctxt, value)?)
+                })
+            })
+        }
+    };
+}
+
+feature_accessor! {
+    /// Reads the value of a feature which has `IInteger` interface.
+    get: cameleon_camera_get_integer,
+    /// Writes the value to a feature which has `IInteger` interface.
+    set: cameleon_camera_set_integer,
+    as_integer, "IInteger", i64
+}
+
+feature_accessor! {
+    /// Reads the value of a feature which has `IFloat` interface.
+    get: cameleon_camera_get_float,
+    /// Writes the value to a feature which has `IFloat` interface.
+    set: cameleon_camera_set_float,
+    as_float, "IFloat", f64
+}
+
+feature_accessor! {
+    /// Reads the value of a feature which has `IBoolean` interface.
+    get: cameleon_camera_get_boolean,
+    /// Writes the value to a feature which has `IBoolean` interface.
+    set: cameleon_camera_set_boolean,
+    as_boolean, "IBoolean", bool
+}
+
+/// Reads the value of a feature which has `IString` interface as a NUL terminated string.
+///
+/// If `buf` is null, the required size of the buffer including the NUL is written to `size`.
+///
+/// # Safety
+/// `camera` must be an opened camera, `name` must be a NUL terminated string, `buf` must be null
+/// or a buffer of at least `*size` bytes, and `size` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn cameleon_camera_get_string(
+    camera: *mut CameleonCamera,
+    name: *const c_char,
+    buf: *mut c_char,
+    size: *mut usize,
+) -> CameleonStatus {
+    guard(|| {
+        let value = with_feature(camera, name, |name, node, ctxt| {
+            let node = node
+                .as_string(ctxt)
+                .ok_or_else(|| interface_mismatch(name, "IString"))?;
+            Ok(node.value(ctxt)?)
+        })?;
+        copy_str(&value, buf, size)
+    })
+}
+
+/// Writes the value to a feature which has `IString` interface.
+///
+/// # Safety
+/// `camera` must be an opened camera, and `name` and `value` must be NUL terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn cameleon_camera_set_string(
+    camera: *mut CameleonCamera,
+    name: *const c_char,
+    value: *const c_char,
+) -> CameleonStatus {
+    guard(|| {
+        let value = as_str(value, "value")?;
+        with_feature(camera, name, |name, node, ctxt| {
+            let node = node
+                .as_string(ctxt)
+                .ok_or_else(|| interface_mismatch(name, "IString"))?;
+            Ok(node.set_value(ctxt, value.to_string())?)
+        })
+    })
+}
+
+/// Reads the symbolic name of the current entry of a feature which has `IEnumeration`
+/// interface as a NUL terminated string.
+///
+/// If `buf` is null, the required size of the buffer including the NUL is written to `size`.
+///
+/// # Safety
+/// `camera` must be an opened camera, `name` must be a NUL terminated string, `buf` must be null
+/// or a buffer of at least `*size` bytes, and `size` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn cameleon_camera_get_enumeration(
+    camera: *mut CameleonCamera,
+    name: *const c_char,
+    buf: *mut c_char,
+    size: *mut usize,
+) -> CameleonStatus {
+    guard(|| {
+        let value = with_feature(camera, name, |name, node, ctxt| {
+            let node = node
+                .as_enumeration(ctxt)
+                .ok_or_else(|| interface_mismatch(name, "IEnumeration"))?;
+            let entry = node.current_entry(ctxt)?;
+            Ok(entry.symbolic(ctxt).to_string())
+        })?;
+        copy_str(&value, buf, size)
+    })
+}
+
+/// Sets the entry of a feature which has `IEnumeration` interface by its symbolic name.
+///
+/// # Safety
+/// `camera` must be an opened camera, and `name` and `entry` must be NUL terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn cameleon_camera_set_enumeration(
+    camera: *mut CameleonCamera,
+    name: *const c_char,
+    entry: *const c_char,
+) -> CameleonStatus {
+    guard(|| {
+        let entry = as_str(entry, "entry")?;
+        with_feature(camera, name, |name, node, ctxt| {
+            let node = node
+                .as_enumeration(ctxt)
+                .ok_or_else(|| interface_mismatch(name, "IEnumeration"))?;
+            Ok(node.set_entry_by_symbolic(ctxt, entry)?)
+        })
+    })
+}
+
+/// Executes a feature which has `ICommand` interface.
+///
+/// # Safety
+/// `camera` must be an opened camera, and `name` must be a NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn cameleon_camera_execute_command(
+    camera: *mut CameleonCamera,
+    name: *const c_char,
+) -> CameleonStatus {
+    guard(|| {
+        with_feature(camera, name, |name, node, ctxt| {
+            let node = node
+                .as_command(ctxt)
+                .ok_or_else(|| interface_mismatch(name, "ICommand"))?;
+            Ok(node.execute(ctxt)?)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use super::*;
+
+    #[test]
+    fn test_null_arguments() {
+        let name = b"Width\0".as_ptr().cast();
+        unsafe {
+            let mut value = 0;
+            assert_eq!(
+                cameleon_camera_get_integer(ptr::null_mut(), name, &mut value),
+                CameleonStatus::InvalidArgument
+            );
+            assert_eq!(
+                cameleon_camera_set_float(ptr::null_mut(), name, 1.0),
+                CameleonStatus::InvalidArgument
+            );
+            assert_eq!(
+                cameleon_camera_set_string(ptr::null_mut(), name, ptr::null()),
+                CameleonStatus::InvalidArgument
+            );
+            assert_eq!(
+                cameleon_camera_execute_command(ptr::null_mut(), ptr::null()),
+                CameleonStatus::InvalidArgument
+            );
+
+            assert_eq!(
+                cameleon_camera_get_boolean(ptr::null_mut(), name, ptr::null_mut()),
+                CameleonStatus::InvalidArgument
+            );
+            let mut size = 0;
+            assert_eq!(
+                cameleon_camera_get_enumeration(ptr::null_mut(), name, ptr::null_mut(), &mut size),
+                CameleonStatus::InvalidArgument
+            );
+            assert_eq!(size, 0);
+        }
+    }
+}
@@ -0,0 +1,178 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{convert::TryInto, ptr};
+
+use cameleon::payload::{Payload, PayloadType};
+
+use super::error::{as_mut, as_ref, guard, CameleonStatus};
+
+/// Opaque handle of a frame received from a camera.
+pub struct CameleonFrame {
+    pub(crate) payload: Payload,
+}
+
+/// Type of a frame.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameleonPayloadType {
+    /// The frame contains only an image.
+    Image,
+    /// The frame contains an image and chunk data following the image.
+    ImageExtendedChunk,
+    /// The frame contains only chunk data.
+    Chunk,
+    /// The frame contains a GenDC container.
+    GenDC,
+}
+
+impl From<PayloadType> for CameleonPayloadType {
+    fn from(ty: PayloadType) -> Self {
+        match ty {
+            PayloadType::Image => Self::Image,
+            PayloadType::ImageExtendedChunk => Self::ImageExtendedChunk,
+            PayloadType::Chunk => Self::Chunk,
+            PayloadType::GenDC => Self::GenDC,
+        }
+    }
+}
+
+/// Information of a frame.
+///
+/// Image related fields are valid only if `has_image` is `true`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CameleonFrameInfo {
+    /// ID of the frame assigned by the camera.
+    pub id: u64,
+    /// Timestamp of the frame in nanoseconds. The origin depends on the camera.
+    pub timestamp_ns: u64,
+    /// Type of the frame.
+    pub payload_type: CameleonPayloadType,
+    /// `true` if the frame contains an image.
+    pub has_image: bool,
+    /// Width of the image.
+    pub width: usize,
+    /// Height of the image.
+    pub height: usize,
+    /// X offset of the image in pixels.
+    pub x_offset: usize,
+    /// Y offset of the image in pixels.
+    pub y_offset: usize,
+    /// `PFNC` code of the pixel format of the image.
+    pub pixel_format: u32,
+    /// Size of the image in bytes.
+    pub image_size: usize,
+}
+
+/// Writes information of the frame to `info`.
+///
+/// # Safety
+/// `frame` must be a frame returned from [`cameleon_camera_receive_frame`], and `info` must be a
+/// valid pointer.
+///
+/// [`cameleon_camera_receive_frame`]: crate::cameleon_camera_receive_frame
+#[no_mangle]
+pub unsafe extern "C" fn cameleon_frame_info(
+    frame: *const CameleonFrame,
+    info: *mut CameleonFrameInfo,
+) -> CameleonStatus {
+    guard(|| {
+        let payload = &as_ref(frame, "frame")?.payload;
+        let info = as_mut(info, "info")?;
+        let image_info = payload.image_info();
+        *info = CameleonFrameInfo {
+            id: payload.id(),
+            timestamp_ns: payload
+                .timestamp()
+                .as_nanos()
+                .try_into()
+                .unwrap_or(u64::MAX),
+            payload_type: payload.payload_type().into(),
+            has_image: image_info.is_some(),
+            width: image_info.map_or(0, |i| i.width),
+            height: image_info.map_or(0, |i| i.height),
+            x_offset: image_info.map_or(0, |i| i.x_offset),
+            y_offset: image_info.map_or(0, |i| i.y_offset),
+            pixel_format: image_info.map_or(0, |i| i.pixel_format.into()),
+            image_size: image_info.map_or(0, |i| i.image_size),
+        };
+        Ok(())
+    })
+}
+
+/// Returns the whole payload of the frame including chunk data, or null if `frame` is null.
+///
+/// The size of the payload is written to `size` if it's not null. The returned data is valid
+/// until the frame is released.
+///
+/// # Safety
+/// `frame` must be null or a frame returned from [`cameleon_camera_receive_frame`], and `size`
+/// must be null or a valid pointer.
+///
+/// [`cameleon_camera_receive_frame`]: crate::cameleon_camera_receive_frame
+#[no_mangle]
+pub unsafe extern "C" fn cameleon_frame_data(
+    frame: *const CameleonFrame,
+    size: *mut usize,
+) -> *const u8 {
+    frame_bytes(frame, size, |payload| Some(payload.payload()))
+}
+
+/// Returns the image of the frame, or null if the frame doesn't contain an image.
+///
+/// The size of the image is written to `size` if it's not null. The returned data is valid
+/// until the frame is released.
+///
+/// # Safety
+/// `frame` must be null or a frame returned from [`cameleon_camera_receive_frame`], and `size`
+/// must be null or a valid pointer.
+///
+/// [`cameleon_camera_receive_frame`]: crate::cameleon_camera_receive_frame
+#[no_mangle]
+pub unsafe extern "C" fn cameleon_frame_image(
+    frame: *const CameleonFrame,
+    size: *mut usize,
+) -> *const u8 {
+    frame_bytes(frame, size, Payload::image)
+}
+
+unsafe fn frame_bytes(
+    frame: *const CameleonFrame,
+    size: *mut usize,
+    f: impl FnOnce(&Payload) -> Option<&[u8]>,
+) -> *const u8 {
+    let bytes = frame.as_ref().and_then(|frame| f(&frame.payload));
+    if let Some(size) = size.as_mut() {
+        *size = bytes.map_or(0, <[u8]>::len);
+    }
+    bytes.map_or(ptr::null(), <[u8]>::as_ptr)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::MaybeUninit;
+
+    use super::*;
+
+    #[test]
+    fn test_null_frame() {
+        unsafe {
+            let mut info = MaybeUninit::uninit();
+            assert_eq!(
+                cameleon_frame_info(ptr::null(), info.as_mut_ptr()),
+                CameleonStatus::InvalidArgument
+            );
+
+            let mut size = 1;
+            assert!(cameleon_frame_data(ptr::null(), &mut size).is_null());
+            assert_eq!(size, 0);
+
+            let mut size = 1;
+            assert!(cameleon_frame_image(ptr::null(), &mut size).is_null());
+            assert_eq!(size, 0);
+            assert!(cameleon_frame_image(ptr::null(), ptr::null_mut()).is_null());
+        }
+    }
+}
@@ -0,0 +1,65 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! C bindings of `cameleon`.
+//!
+//! The crate is built as a shared and a static library, and `include/cameleon.h` is generated
+//! from this crate by `cbindgen` on build.
+//!
+//! All fallible functions return [`CameleonStatus`], and the detail of the last error is
+//! available from [`cameleon_last_error_message`]. Functions never unwind across the FFI
+//! boundary, a panic is reported as [`CameleonStatus::Panic`].
+//!
+//! # Examples
+//! ```c
+//! #include <stdio.h>
+//! #include "cameleon.h"
+//!
+//! int main(void) {
+//!     CameleonCameraList *list;
+//!     CameleonCamera *camera;
+//!     CameleonFrame *frame;
+//!     CameleonFrameInfo info;
+//!
+//!     if (cameleon_enumerate_cameras(&list) != CAMELEON_STATUS_OK) {
+//!         return 1;
+//!     }
+//!     if (cameleon_camera_list_len(list) == 0) {
+//!         cameleon_camera_list_free(list);
+//!         return 0;
+//!     }
+//!     cameleon_camera_list_take(list, 0, &camera);
+//!     cameleon_camera_list_free(list);
+//!
+//!     if (cameleon_camera_open(camera) != CAMELEON_STATUS_OK) {
+//!         fprintf(stderr, "%s\n", cameleon_last_error_message());
+//!         cameleon_camera_free(camera);
+//!         return 1;
+//!     }
+//!     cameleon_camera_set_float(camera, "ExposureTime", 10000.0);
+//!
+//!     cameleon_camera_start_streaming(camera, 3);
+//!     for (int i = 0; i < 10; i++) {
+//!         if (cameleon_camera_receive_frame(camera, 1000, &frame) != CAMELEON_STATUS_OK) {
+//!             continue;
+//!         }
+//!         cameleon_frame_info(frame, &info);
+//!         printf("%zux%zu\n", info.width, info.height);
+//!         cameleon_camera_release_frame(camera, frame);
+//!     }
+//!
+//!     cameleon_camera_free(camera);
+//!     return 0;
+//! }
+//! ```
+
+mod camera;
+mod error;
+mod feature;
+mod frame;
+
+pub use camera::*;
+pub use error::{cameleon_last_error_message, CameleonStatus};
+pub use feature::*;
+pub use frame::*;
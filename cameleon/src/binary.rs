@@ -0,0 +1,147 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Binary encoding shared by trace files and sequence files.
+//!
+//! All integers are little endian, and byte sequences and strings are prefixed with their length
+//! as `u32`.
+//!
+//! A payload is encoded as follows.
+//!
+//! | Field                  | Type                                    |
+//! |------------------------|-----------------------------------------|
+//! | ID                     | `u64`                                   |
+//! | Payload type           | `u8`                                    |
+//! | Timestamp              | `u64` in nanoseconds                    |
+//! | Image info flag        | `u8`, `1` if image info follows         |
+//! | Image info (optional)  | width, height, x offset, y offset as `u64`, pixel format as `u32`, image size as `u64` |
+//! | Data                   | bytes                                   |
+
+use std::{
+    convert::TryFrom,
+    io::{self, Read, Write},
+    time::{Duration, Instant},
+};
+
+use crate::payload::{parse_chunks, ImageInfo, Payload, PayloadType, PixelFormat};
+
+pub(crate) fn write_payload(w: &mut impl Write, payload: &Payload) -> io::Result<()> {
+    write_u64(w, payload.id)?;
+    w.write_all(&[payload_type_to_u8(payload.payload_type)])?;
+    write_u64(w, payload.timestamp.as_nanos() as u64)?;
+    match &payload.image_info {
+        Some(info) => {
+            w.write_all(&[1])?;
+            write_u64(w, info.width as u64)?;
+            write_u64(w, info.height as u64)?;
+            write_u64(w, info.x_offset as u64)?;
+            write_u64(w, info.y_offset as u64)?;
+            write_u32(w, info.pixel_format.into())?;
+            write_u64(w, info.image_size as u64)?;
+        }
+        None => w.write_all(&[0])?,
+    }
+    write_bytes(w, payload.payload())
+}
+
+pub(crate) fn read_payload(r: &mut impl Read) -> io::Result<Payload> {
+    let id = read_u64(r)?;
+    let payload_type = payload_type_from_u8(read_u8(r)?)?;
+    let timestamp = Duration::from_nanos(read_u64(r)?);
+    let image_info = match read_u8(r)? {
+        0 => None,
+        _ => Some(ImageInfo {
+            width: read_u64(r)? as usize,
+            height: read_u64(r)? as usize,
+            x_offset: read_u64(r)? as usize,
+            y_offset: read_u64(r)? as usize,
+            pixel_format: PixelFormat::try_from(read_u32(r)?).map_err(invalid_data)?,
+            image_size: read_u64(r)? as usize,
+        }),
+    };
+    let data = read_bytes(r)?;
+    // The chunk layout is not recorded but parsed again from the data.
+    let chunks = match payload_type {
+        PayloadType::Chunk | PayloadType::ImageExtendedChunk => parse_chunks(&data).ok(),
+        PayloadType::Image | PayloadType::GenDC => None,
+    };
+    Ok(Payload {
+        id,
+        payload_type,
+        image_info,
+        valid_payload_size: data.len(),
+        payload: data,
+        timestamp,
+        // Host timestamps are not recorded, the payload is regarded as received when it's read.
+        host_timestamp: Instant::now(),
+        chunks,
+    })
+}
+
+fn payload_type_to_u8(payload_type: PayloadType) -> u8 {
+    match payload_type {
+        PayloadType::Image => 0,
+        PayloadType::ImageExtendedChunk => 1,
+        PayloadType::Chunk => 2,
+        PayloadType::GenDC => 3,
+    }
+}
+
+fn payload_type_from_u8(value: u8) -> io::Result<PayloadType> {
+    match value {
+        0 => Ok(PayloadType::Image),
+        1 => Ok(PayloadType::ImageExtendedChunk),
+        2 => Ok(PayloadType::Chunk),
+        3 => Ok(PayloadType::GenDC),
+        _ => Err(invalid_data(format!("unknown payload type: {}", value))),
+    }
+}
+
+pub(crate) fn write_u32(w: &mut impl Write, value: u32) -> io::Result<()> {
+    w.write_all(&value.to_le_bytes())
+}
+
+pub(crate) fn write_u64(w: &mut impl Write, value: u64) -> io::Result<()> {
+    w.write_all(&value.to_le_bytes())
+}
+
+pub(crate) fn write_bytes(w: &mut impl Write, data: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(data.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "record is too large"))?;
+    write_u32(w, len)?;
+    w.write_all(data)
+}
+
+pub(crate) fn read_u8(r: &mut impl Read) -> io::Result<u8> {
+    let mut buf = [0; 1];
+    r.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+pub(crate) fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+pub(crate) fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+pub(crate) fn read_bytes(r: &mut impl Read) -> io::Result<Vec<u8>> {
+    let len = read_u32(r)? as usize;
+    let mut buf = vec![0; len];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+pub(crate) fn read_string(r: &mut impl Read) -> io::Result<String> {
+    String::from_utf8(read_bytes(r)?).map_err(|e| invalid_data(e.to_string()))
+}
+
+pub(crate) fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
@@ -57,13 +57,27 @@
 //! camera.close().unwrap();
 //! ```
 
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use async_std::{future, task};
 use auto_impl::auto_impl;
+use cameleon_genapi::GenApiResult;
 use tracing::info;
 
 use super::{
-    genapi::{DefaultGenApiCtxt, FromXml, GenApiCtxt, ParamsCtxt},
-    payload::{channel, PayloadReceiver, PayloadSender},
-    CameleonError, CameleonResult, ControlResult, StreamError, StreamResult,
+    cancel::CancellationToken,
+    genapi::{
+        DefaultGenApiCtxt, FromNodeStoreCache, FromXml, GenApiCtxt, NodeStoreCache, ParamsCtxt,
+    },
+    payload::{channel, Payload, PayloadReceiver, PayloadSender, SharedStreamCounters},
+    sfnc::{
+        self, AcquisitionMode, CommandFeature, TriggerMode, TriggerSelector, TriggerSource,
+        UserSetSelector,
+    },
+    CameleonError, CameleonResult, ControlError, ControlResult, StreamError, StreamResult,
 };
 
 /// Provides easy-to-use access to a `GenICam` compatible camera.
@@ -123,10 +137,18 @@ pub struct Camera<Ctrl, Strm, Ctxt = DefaultGenApiCtxt> {
     pub ctrl: Ctrl,
     /// Payload stream handle of the camera.
     pub strm: Strm,
+    /// Payload stream handles of the stream channels other than the first one.
+    ///
+    /// `extra_strms[n]` receives the stream channel of index `n + 1`, see
+    /// [`Self::start_streaming_channel`].
+    pub extra_strms: Vec<Strm>,
     /// `GenApi context` of the camera.
     pub ctxt: Option<Ctxt>,
     /// Information of the camera.
     info: CameraInfo,
+    /// Statistics of the last streaming of each stream channel, which are reported by
+    /// [`Self::diagnostics`].
+    pub(crate) stream_counters: BTreeMap<usize, SharedStreamCounters>,
 }
 
 macro_rules! expect_node {
@@ -171,6 +193,9 @@ impl<Ctrl, Strm, Ctxt> Camera<Ctrl, Strm, Ctxt> {
         info!("try opening the device");
         self.ctrl.open()?;
         self.strm.open()?;
+        for strm in &mut self.extra_strms {
+            strm.open()?;
+        }
         info!("opened the device successfully");
         Ok(())
     }
@@ -207,6 +232,9 @@ impl<Ctrl, Strm, Ctxt> Camera<Ctrl, Strm, Ctxt> {
         self.stop_streaming()?;
         self.ctrl.close()?;
         self.strm.close()?;
+        for strm in &mut self.extra_strms {
+            strm.close()?;
+        }
         if let Some(ctxt) = &mut self.ctxt {
             ctxt.clear_cache()
         }
@@ -249,13 +277,87 @@ impl<Ctrl, Strm, Ctxt> Camera<Ctrl, Strm, Ctxt> {
         Ok(xml)
     }
 
+    /// Same as [`Self::load_context`], but aborts retrieval of `GenApi` xml when `token` is
+    /// cancelled.
+    ///
+    /// Returns [`ControlError::Cancelled`](crate::ControlError::Cancelled) on cancellation, the camera remains opened and the
+    /// previously loaded context, if any, is kept.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use cameleon::u3v;
+    /// use cameleon::cancel::CancellationToken;
+    ///
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// camera.open().unwrap();
+    ///
+    /// let token = CancellationToken::new();
+    /// // Call `token.cancel()` from another thread to abort loading.
+    /// camera.load_context_with_cancel(&token).unwrap();
+    ///
+    /// camera.close().unwrap();
+    /// ```
+    pub fn load_context_with_cancel(&mut self, token: &CancellationToken) -> CameleonResult<String>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt + FromXml,
+    {
+        let xml = self.ctrl.genapi_with_cancel(token)?;
+        self.ctxt = Some(Ctxt::from_xml(&xml)?);
+        Ok(xml)
+    }
+
+    /// Same as [`Self::load_context`], but shares the parsed node store with other cameras
+    /// through `cache`.
+    ///
+    /// The xml is parsed only when `cache` doesn't have an entry for it yet, this reduces startup
+    /// time and memory usage when opening many cameras of the same model.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use cameleon::u3v;
+    /// use cameleon::{
+    ///     genapi::{NodeStoreCache, SharedDefaultGenApiCtxt},
+    ///     Camera,
+    /// };
+    ///
+    /// let cache = NodeStoreCache::new();
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let camera = cameras.pop().unwrap();
+    /// let mut camera: Camera<u3v::ControlHandle, u3v::StreamHandle, SharedDefaultGenApiCtxt> =
+    ///     camera.convert_into();
+    /// camera.open().unwrap();
+    /// camera.load_context_with_cache(&cache).unwrap();
+    ///
+    /// camera.close().unwrap();
+    /// ```
+    pub fn load_context_with_cache(&mut self, cache: &NodeStoreCache) -> CameleonResult<String>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt + FromNodeStoreCache,
+    {
+        let xml = self.ctrl.genapi()?;
+        self.ctxt = Some(cache.load(&xml)?);
+        Ok(xml)
+    }
+
     /// Starts streaming and returns the receiver for the `Payload`.
     ///
     /// Make sure to load `GenApi` context before calling this method.
     /// See [`load_context`](Self::load_context) and [`set_context`](Self::set_context) how to configure `GenApi` context.
     ///
     /// NOTE: This method doesn't change `AcquisitionMode` which defined in `GenICam SFNC`.  
-    /// We recommend you to set the node to `Continuous` if you don't know which mode is the best.
+    /// We recommend you to set the node to `Continuous` if you don't know which mode is the best,
+    /// or to use [`Self::start_continuous`] which sets it.
     ///
     /// See the `GenICam SFNC` specification for more details.
     ///
@@ -291,6 +393,50 @@ impl<Ctrl, Strm, Ctxt> Camera<Ctrl, Strm, Ctxt> {
                           level = "info",
                           fields(camera = ?self.info()))]
     pub fn start_streaming(&mut self, cap: usize) -> CameleonResult<PayloadReceiver>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        self.start_streaming_channel(0, cap)
+    }
+
+    /// Starts streaming of the stream channel of `index`, then returns the receiver of the
+    /// channel.
+    ///
+    /// Each stream channel has its own streaming loop and receiver, so channels can be started
+    /// and stopped independently. Acquisition of the camera is started along with the first
+    /// channel, and stopped along with the last channel.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// // Start streaming of all channels.
+    /// let receivers: Vec<_> = (0..camera.stream_channel_len())
+    ///     .map(|index| camera.start_streaming_channel(index, 3).unwrap())
+    ///     .collect();
+    ///
+    /// camera.close().unwrap();
+    /// ```
+    ///
+    /// # Panics
+    /// If `cap` is zero, this method will panic.
+    #[tracing::instrument(skip(self),
+                          level = "info",
+                          fields(camera = ?self.info()))]
+    pub fn start_streaming_channel(
+        &mut self,
+        index: usize,
+        cap: usize,
+    ) -> CameleonResult<PayloadReceiver>
     where
         Ctrl: DeviceControl,
         Strm: PayloadStream,
@@ -299,20 +445,39 @@ impl<Ctrl, Strm, Ctxt> Camera<Ctrl, Strm, Ctxt> {
         const DEFAULT_BUFFER_CAP: usize = 5;
         info!("try starting streaming");
 
-        if self.strm.is_loop_running() {
+        if self.strm_mut(index)?.is_loop_running() {
             return Err(StreamError::InStreaming.into());
         }
+        let is_acquiring = self.is_streaming();
 
         // Enable streaimng.
-        self.ctrl.enable_streaming()?;
-        let mut ctxt = self.params_ctxt()?;
-        expect_node!(&ctxt, "TLParamsLocked", as_integer).set_value(&mut ctxt, 1)?;
-        expect_node!(&ctxt, "AcquisitionStart", as_command).execute(&mut ctxt)?;
+        self.ctrl.enable_streaming_channel(index)?;
+        if !is_acquiring {
+            let mut ctxt = self.params_ctxt()?;
+            expect_node!(&ctxt, "TLParamsLocked", as_integer).set_value(&mut ctxt, 1)?;
+        }
 
-        // Start streaming loop.
+        // Start streaming loop before starting acquisition so that no payload is missed.
         let (sender, receiver) = channel(cap, DEFAULT_BUFFER_CAP);
-        self.strm.start_streaming_loop(sender, &mut self.ctrl)?;
+        let strm = match index {
+            0 => &mut self.strm,
+            _ => &mut self.extra_strms[index - 1],
+        };
+        strm.start_streaming_loop(sender, &mut self.ctrl)?;
 
+        if !is_acquiring {
+            let res = self.params_ctxt().and_then(|mut ctxt| {
+                expect_node!(&ctxt, "AcquisitionStart", as_command).execute(&mut ctxt)?;
+                Ok(())
+            });
+            if let Err(e) = res {
+                self.strm_mut(index)?.stop_streaming_loop()?;
+                return Err(e);
+            }
+        }
+
+        self.stream_counters
+            .insert(index, receiver.shared_counters());
         info!("start streaming successfully");
         Ok(receiver)
     }
@@ -348,29 +513,566 @@ impl<Ctrl, Strm, Ctxt> Camera<Ctrl, Strm, Ctxt> {
                           level = "info",
                           fields(camera = ?self.info()))]
     pub fn stop_streaming(&mut self) -> CameleonResult<()>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        for index in 0..self.stream_channel_len() {
+            self.stop_streaming_channel(index)?;
+        }
+        Ok(())
+    }
+
+    /// Stops the streaming of the stream channel of `index`.
+    ///
+    /// The receiver returned from the previous [`Self::start_streaming_channel`] call with the
+    /// same `index` will be invalidated.
+    #[tracing::instrument(skip(self),
+                          level = "info",
+                          fields(camera = ?self.info()))]
+    pub fn stop_streaming_channel(&mut self, index: usize) -> CameleonResult<()>
     where
         Ctrl: DeviceControl,
         Strm: PayloadStream,
         Ctxt: GenApiCtxt,
     {
         info!("try stopping streaming");
-        if !self.strm.is_loop_running() {
+        if !self.strm_mut(index)?.is_loop_running() {
             return Ok(());
         }
+        let is_last_channel = std::iter::once(&self.strm)
+            .chain(&self.extra_strms)
+            .enumerate()
+            .all(|(i, strm)| i == index || !strm.is_loop_running());
+
+        // Stop acquisition before stopping streaming loop so that the payload in flight is
+        // received by the loop.
+        if is_last_channel {
+            let mut ctxt = self.params_ctxt()?;
+            expect_node!(&ctxt, "AcquisitionStop", as_command).execute(&mut ctxt)?;
+        }
 
         // Stop streaming loop.
-        self.strm.stop_streaming_loop()?;
+        self.strm_mut(index)?.stop_streaming_loop()?;
 
         // Disable streaming.
-        let mut ctxt = self.params_ctxt()?;
-        expect_node!(&ctxt, "AcquisitionStop", as_command).execute(&mut ctxt)?;
-        expect_node!(&ctxt, "TLParamsLocked", as_integer).set_value(&mut ctxt, 0)?;
-        self.ctrl.disable_streaming()?;
+        if is_last_channel {
+            let mut ctxt = self.params_ctxt()?;
+            expect_node!(&ctxt, "TLParamsLocked", as_integer).set_value(&mut ctxt, 0)?;
+        }
+        self.ctrl.disable_streaming_channel(index)?;
 
         info!("stop streaming successfully");
         Ok(())
     }
 
+    /// Acquires a single frame in `SingleFrame` acquisition mode, then returns the payload of the
+    /// frame.
+    ///
+    /// Streaming is started and stopped inside this method, so the camera must not be streaming
+    /// when this method is called.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use std::time::Duration;
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// let payload = camera.grab_one(Duration::from_secs(1)).unwrap();
+    /// println!("{:?}", payload.image_info());
+    ///
+    /// camera.close().unwrap();
+    /// ```
+    ///
+    /// # Arguments
+    /// * `timeout` - The maximum time to wait for the frame.
+    pub fn grab_one(&mut self, timeout: Duration) -> CameleonResult<Payload>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        let mut payloads = self.grab_n(1, timeout)?;
+        Ok(payloads.pop().unwrap())
+    }
+
+    /// Acquires `n` frames, then returns the payloads of the frames in the order of arrival.
+    ///
+    /// `AcquisitionMode` is set to `SingleFrame` if `n` is `1`, otherwise to `MultiFrame` with
+    /// `AcquisitionFrameCount` set to `n`. If the camera doesn't have `AcquisitionFrameCount`,
+    /// frames are acquired in `Continuous` mode and the acquisition is stopped after `n` frames
+    /// are received.
+    ///
+    /// Streaming is started and stopped inside this method, so the camera must not be streaming
+    /// when this method is called.
+    ///
+    /// # Arguments
+    /// * `n` - The number of frames to acquire.
+    /// * `timeout` - The maximum time to wait for each frame.
+    pub fn grab_n(&mut self, n: usize, timeout: Duration) -> CameleonResult<Vec<Payload>>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        if n == 0 {
+            return Ok(vec![]);
+        }
+        if self.is_streaming() {
+            return Err(StreamError::InStreaming.into());
+        }
+
+        let mut ctxt = self.params_ctxt()?;
+        if n == 1 {
+            sfnc::ACQUISITION_MODE.set_value(&mut ctxt, AcquisitionMode::SingleFrame)?;
+        } else if sfnc::ACQUISITION_FRAME_COUNT.is_available(&ctxt) {
+            sfnc::ACQUISITION_MODE.set_value(&mut ctxt, AcquisitionMode::MultiFrame)?;
+            sfnc::ACQUISITION_FRAME_COUNT.set_value(&mut ctxt, n as i64)?;
+        } else {
+            sfnc::ACQUISITION_MODE.set_value(&mut ctxt, AcquisitionMode::Continuous)?;
+        }
+
+        let payload_rx = self.start_streaming(n)?;
+        let payloads: StreamResult<Vec<_>> =
+            (0..n).map(|_| recv_timeout(&payload_rx, timeout)).collect();
+        // Stop streaming even if receiving failed so that the camera is left in a usable state.
+        self.stop_streaming()?;
+        Ok(payloads?)
+    }
+
+    /// Sets `AcquisitionMode` to `Continuous`, then starts streaming and returns the receiver for
+    /// the `Payload`.
+    ///
+    /// Call [`Self::stop_streaming`] to stop the acquisition.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// let payload_rx = camera.start_continuous(3).unwrap();
+    /// let payload = async_std::task::block_on(payload_rx.recv()).unwrap();
+    /// payload_rx.send_back(payload);
+    ///
+    /// camera.stop_streaming().unwrap();
+    /// camera.close().unwrap();
+    /// ```
+    ///
+    /// # Panics
+    /// If `cap` is zero, this method will panic.
+    pub fn start_continuous(&mut self, cap: usize) -> CameleonResult<PayloadReceiver>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        if self.is_streaming() {
+            return Err(StreamError::InStreaming.into());
+        }
+
+        let mut ctxt = self.params_ctxt()?;
+        sfnc::ACQUISITION_MODE.set_value(&mut ctxt, AcquisitionMode::Continuous)?;
+        self.start_streaming(cap)
+    }
+
+    /// Generates a software trigger, then waits for the frame captured by the trigger.
+    ///
+    /// `TriggerMode` of `FrameStart` trigger is set to `On` and `TriggerSource` is set to
+    /// `Software` if they are not yet. Streaming must be started in advance, e.g. by
+    /// [`Self::start_continuous`], and `payload_rx` must be the receiver returned from it.
+    ///
+    /// Payloads remaining in `payload_rx` are discarded before the trigger. If the camera has
+    /// `TimestampLatch`, payloads whose timestamps are older than the trigger are also discarded,
+    /// so that a payload which has been in flight when the trigger is generated is not mistaken
+    /// for the triggered one.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use std::time::Duration;
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// let payload_rx = camera.start_continuous(3).unwrap();
+    /// for _ in 0..10 {
+    ///     let payload = camera
+    ///         .trigger_and_wait(&payload_rx, Duration::from_secs(1))
+    ///         .unwrap();
+    ///     // Do something with the payload.
+    ///     payload_rx.send_back(payload);
+    /// }
+    ///
+    /// camera.close().unwrap();
+    /// ```
+    ///
+    /// # Arguments
+    /// * `payload_rx` - The receiver of the streaming payloads.
+    /// * `timeout` - The maximum time to wait for the frame, including the time to configure the
+    ///   trigger.
+    pub fn trigger_and_wait(
+        &mut self,
+        payload_rx: &PayloadReceiver,
+        timeout: Duration,
+    ) -> CameleonResult<Payload>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        self.trigger_and_wait_with_cancel(payload_rx, timeout, &CancellationToken::new())
+    }
+
+    /// Same as [`Self::trigger_and_wait`], but stops waiting for the frame when `token` is
+    /// cancelled.
+    ///
+    /// Returns [`StreamError::Cancelled`] if `token` is cancelled while waiting, or
+    /// [`ControlError::Cancelled`](crate::ControlError::Cancelled) if it's cancelled before the
+    /// trigger is generated. Streaming is not stopped in either case.
+    pub fn trigger_and_wait_with_cancel(
+        &mut self,
+        payload_rx: &PayloadReceiver,
+        timeout: Duration,
+        token: &CancellationToken,
+    ) -> CameleonResult<Payload>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        let deadline = Instant::now() + timeout;
+        token.check_control()?;
+        if !self.is_streaming() {
+            return Err(StreamError::ReceiveError("streaming is not started".into()).into());
+        }
+
+        let mut ctxt = self.params_ctxt()?;
+        if sfnc::TRIGGER_SELECTOR.is_available(&ctxt)
+            && sfnc::TRIGGER_SELECTOR.value(&mut ctxt)? != TriggerSelector::FrameStart
+        {
+            sfnc::TRIGGER_SELECTOR.set_value(&mut ctxt, TriggerSelector::FrameStart)?;
+        }
+        if sfnc::TRIGGER_MODE.value(&mut ctxt)? != TriggerMode::On {
+            sfnc::TRIGGER_MODE.set_value(&mut ctxt, TriggerMode::On)?;
+        }
+        if sfnc::TRIGGER_SOURCE.value(&mut ctxt)? != TriggerSource::Software {
+            sfnc::TRIGGER_SOURCE.set_value(&mut ctxt, TriggerSource::Software)?;
+        }
+
+        // Discard stale payloads including errors.
+        while payload_rx.occupancy().len > 0 {
+            if let Ok(payload) = payload_rx.try_recv() {
+                payload_rx.send_back(payload);
+            }
+        }
+
+        let trigger_timestamp = if sfnc::TIMESTAMP_LATCH.is_available(&ctxt)
+            && sfnc::TIMESTAMP_LATCH_VALUE.is_available(&ctxt)
+        {
+            sfnc::TIMESTAMP_LATCH.execute(&mut ctxt)?;
+            let value = sfnc::TIMESTAMP_LATCH_VALUE.value(&mut ctxt)?;
+            Some(Duration::from_nanos(value as u64))
+        } else {
+            None
+        };
+        token.check_control()?;
+        sfnc::TRIGGER_SOFTWARE.execute(&mut ctxt)?;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let payload = recv_timeout_with_cancel(payload_rx, remaining, token)?;
+            match trigger_timestamp {
+                Some(timestamp) if payload.timestamp() < timestamp => payload_rx.send_back(payload),
+                _ => return Ok(payload),
+            }
+        }
+    }
+
+    /// Saves the current settings of the camera to `user_set`, then waits for the camera to
+    /// complete saving.
+    ///
+    /// Streaming must be stopped, because cameras don't allow to save user sets during
+    /// acquisition.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// use cameleon::sfnc::UserSetSelector;
+    ///
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// // Save the settings to `UserSet1`, and use them after the next power up.
+    /// camera.save_user_set(UserSetSelector::from_index(1)).unwrap();
+    /// camera
+    ///     .set_default_user_set(UserSetSelector::from_index(1))
+    ///     .unwrap();
+    ///
+    /// // Restore the factory default settings.
+    /// camera.load_user_set(UserSetSelector::Default).unwrap();
+    ///
+    /// camera.close().unwrap();
+    /// ```
+    pub fn save_user_set(&mut self, user_set: UserSetSelector) -> CameleonResult<()>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        self.execute_user_set_command(sfnc::USER_SET_SAVE, user_set)
+    }
+
+    /// Loads `user_set` to the current settings of the camera, then waits for the camera to
+    /// complete loading.
+    ///
+    /// Streaming must be stopped, because cameras don't allow to load user sets during
+    /// acquisition. The cache of `GenApi` context is cleared, since values of any features may be
+    /// changed by loading.
+    pub fn load_user_set(&mut self, user_set: UserSetSelector) -> CameleonResult<()>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        self.execute_user_set_command(sfnc::USER_SET_LOAD, user_set)?;
+        if let Some(ctxt) = self.ctxt.as_mut() {
+            ctxt.clear_cache();
+        }
+        Ok(())
+    }
+
+    /// Sets `user_set` to the user set which is loaded when the camera is powered up or reset.
+    ///
+    /// `UserSetDefaultSelector` is used instead of `UserSetDefault` if the camera doesn't have
+    /// `UserSetDefault`.
+    pub fn set_default_user_set(&mut self, user_set: UserSetSelector) -> CameleonResult<()>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        let mut ctxt = self.params_ctxt()?;
+        if sfnc::USER_SET_DEFAULT.is_available(&ctxt) {
+            sfnc::USER_SET_DEFAULT.set_value(&mut ctxt, user_set)?;
+        } else {
+            sfnc::USER_SET_DEFAULT_SELECTOR.set_value(&mut ctxt, user_set)?;
+        }
+        Ok(())
+    }
+
+    fn execute_user_set_command(
+        &mut self,
+        command: CommandFeature,
+        user_set: UserSetSelector,
+    ) -> CameleonResult<()>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        // Saving a user set writes to the non-volatile memory of the camera, which may take a
+        // while.
+        const USER_SET_TIMEOUT: Duration = Duration::from_secs(10);
+
+        if self.is_streaming() {
+            return Err(StreamError::InStreaming.into());
+        }
+        let mut ctxt = self.params_ctxt()?;
+        sfnc::USER_SET_SELECTOR.set_value(&mut ctxt, user_set)?;
+        command.execute_and_wait(&mut ctxt, USER_SET_TIMEOUT)?;
+        Ok(())
+    }
+
+    /// Returns the number of stream channels which have their own payload stream handle, i.e.
+    /// `1 + extra_strms.len()`.
+    ///
+    /// Unlike [`Self::stream_channel_count`], this method doesn't need `GenApi` context.
+    pub fn stream_channel_len(&self) -> usize {
+        1 + self.extra_strms.len()
+    }
+
+    /// Returns `true` if the streaming loop of any stream channel is running.
+    pub fn is_streaming(&self) -> bool
+    where
+        Strm: PayloadStream,
+    {
+        self.strm.is_loop_running() || self.extra_strms.iter().any(Strm::is_loop_running)
+    }
+
+    fn strm_mut(&mut self, index: usize) -> CameleonResult<&mut Strm> {
+        match index {
+            0 => Ok(&mut self.strm),
+            _ => self.extra_strms.get_mut(index - 1).ok_or_else(|| {
+                ControlError::InvalidDevice(
+                    format!("the camera doesn't have stream channel {}", index).into(),
+                )
+                .into()
+            }),
+        }
+    }
+
+    /// Returns the number of stream channels of the camera, which is the value of
+    /// `DeviceStreamChannelCount` defined in `GenICam SFNC`.
+    ///
+    /// Returns `1` if the camera doesn't have `DeviceStreamChannelCount`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// let count = camera.stream_channel_count().unwrap();
+    /// println!("the camera has {} stream channels", count);
+    ///
+    /// # camera.close().unwrap();
+    /// ```
+    pub fn stream_channel_count(&mut self) -> CameleonResult<i64>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        let mut ctxt = self.params_ctxt()?;
+        match ctxt
+            .node("DeviceStreamChannelCount")
+            .and_then(|node| node.as_integer(&ctxt))
+        {
+            Some(node) => Ok(node.value(&mut ctxt)?),
+            None => Ok(1),
+        }
+    }
+
+    /// Returns information of all stream channels of the camera.
+    ///
+    /// The channel whose index is `0` is the one streamed by [`Self::strm`], i.e.
+    /// [`Self::start_streaming`] always streams payloads of the channel.
+    pub fn stream_channels(&mut self) -> CameleonResult<Vec<StreamChannel>>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        let count = self.stream_channel_count()?;
+        (0..count)
+            .map(|index| {
+                let channel = self.configure_stream_channel(index, |ctxt| -> GenApiResult<_> {
+                    let channel_type = match ctxt
+                        .node("DeviceStreamChannelType")
+                        .and_then(|node| node.as_enumeration(ctxt))
+                    {
+                        Some(node) if node.is_readable(ctxt)? => {
+                            let entry = node.current_entry(ctxt)?;
+                            Some(entry.symbolic(ctxt).to_string())
+                        }
+                        _ => None,
+                    };
+                    let packet_size = match ctxt
+                        .node("DeviceStreamChannelPacketSize")
+                        .and_then(|node| node.as_integer(ctxt))
+                    {
+                        Some(node) if node.is_readable(ctxt)? => Some(node.value(ctxt)?),
+                        _ => None,
+                    };
+                    Ok(StreamChannel {
+                        index,
+                        channel_type,
+                        packet_size,
+                    })
+                })?;
+                Ok(channel?)
+            })
+            .collect()
+    }
+
+    /// Selects the stream channel by setting `index` to `DeviceStreamChannelSelector`, then calls
+    /// `f` to configure features of the channel, e.g. `DeviceStreamChannelPacketSize`.
+    ///
+    /// The selector is restored to the previous value after `f` returns. If the camera doesn't
+    /// have `DeviceStreamChannelSelector`, only `0` is allowed as `index`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// // Configure packet size of the first stream channel.
+    /// let res = camera.configure_stream_channel(0, |ctxt| {
+    ///     let node = ctxt
+    ///         .node("DeviceStreamChannelPacketSize")
+    ///         .and_then(|node| node.as_integer(ctxt));
+    ///     match node {
+    ///         Some(node) => node.set_value(ctxt, 1024),
+    ///         None => Ok(()),
+    ///     }
+    /// });
+    ///
+    /// # camera.close().unwrap();
+    /// ```
+    pub fn configure_stream_channel<F, R>(&mut self, index: i64, f: F) -> CameleonResult<R>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+        F: FnOnce(&mut ParamsCtxt<&mut Ctrl, &mut Ctxt>) -> R,
+    {
+        let mut ctxt = self.params_ctxt()?;
+        let selector = ctxt
+            .node("DeviceStreamChannelSelector")
+            .and_then(|node| node.as_integer(&ctxt));
+
+        let selector = match selector {
+            Some(selector) => selector,
+            None if index == 0 => return Ok(f(&mut ctxt)),
+            None => {
+                return Err(CameleonError::InvalidGenApiXml(
+                    "missing DeviceStreamChannelSelector".into(),
+                ))
+            }
+        };
+
+        let prev = selector.value(&mut ctxt)?;
+        selector.set_value(&mut ctxt, index)?;
+        let res = f(&mut ctxt);
+        if prev != index {
+            selector.set_value(&mut ctxt, prev)?;
+        }
+        Ok(res)
+    }
+
     /// Returns the context of the camera params.
     ///
     /// Make sure to load `GenApi` context before calling this method.
@@ -447,8 +1149,10 @@ impl<Ctrl, Strm, Ctxt> Camera<Ctrl, Strm, Ctxt> {
         Self {
             ctrl,
             strm,
+            extra_strms: vec![],
             ctxt,
             info,
+            stream_counters: BTreeMap::new(),
         }
     }
 
@@ -462,12 +1166,14 @@ impl<Ctrl, Strm, Ctxt> Camera<Ctrl, Strm, Ctxt> {
         Strm: From<Strm2>,
         Ctxt: From<Ctxt2>,
     {
-        Camera::new(
-            from.ctrl.into(),
-            from.strm.into(),
-            from.ctxt.map(|ctxt| ctxt.into()),
-            from.info,
-        )
+        Camera {
+            ctrl: from.ctrl.into(),
+            strm: from.strm.into(),
+            extra_strms: from.extra_strms.into_iter().map(Into::into).collect(),
+            ctxt: from.ctxt.map(|ctxt| ctxt.into()),
+            info: from.info,
+            stream_counters: from.stream_counters,
+        }
     }
 
     /// Converts internal types. This method work same as `std::convert::Into`, just hack to avoid
@@ -494,12 +1200,14 @@ impl<Ctrl, Strm, Ctxt> Camera<Ctrl, Strm, Ctxt> {
         Strm: Into<Strm2>,
         Ctxt: Into<Ctxt2>,
     {
-        Camera::new(
-            self.ctrl.into(),
-            self.strm.into(),
-            self.ctxt.map(|ctxt| ctxt.into()),
-            self.info,
-        )
+        Camera {
+            ctrl: self.ctrl.into(),
+            strm: self.strm.into(),
+            extra_strms: self.extra_strms.into_iter().map(Into::into).collect(),
+            ctxt: self.ctxt.map(|ctxt| ctxt.into()),
+            info: self.info,
+            stream_counters: self.stream_counters,
+        }
     }
 
     /// Set a context to the camera. It's recommended to use [`Self::load_context`] instead if `Self::Ctxt`
@@ -508,14 +1216,34 @@ impl<Ctrl, Strm, Ctxt> Camera<Ctrl, Strm, Ctxt> {
         Camera {
             ctrl: self.ctrl,
             strm: self.strm,
+            extra_strms: self.extra_strms,
             ctxt: Some(ctxt),
             info: self.info,
+            stream_counters: self.stream_counters,
         }
     }
 }
 
+/// Information of a stream channel of the camera.
+///
+/// See [`Camera::stream_channels`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct StreamChannel {
+    /// Index of the channel, which is used as the value of `DeviceStreamChannelSelector`.
+    pub index: i64,
+    /// Symbolic name of `DeviceStreamChannelType` of the channel, e.g. `Transmitter`.
+    pub channel_type: Option<String>,
+    /// Value of `DeviceStreamChannelPacketSize` of the channel.
+    pub packet_size: Option<i64>,
+}
+
 /// Information of the camera.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct CameraInfo {
     /// Vendor name of the camera.
     pub vendor_name: String,
@@ -539,20 +1267,110 @@ pub trait DeviceControl {
 
     /// Reads data from the device's memory.
     ///
-    /// Reads length is same as `buf.len()`.
+    /// Reads length is same as `buf.len()`. Implementors split the read into multiple
+    /// transactions if it exceeds the maximum length the device can handle at once.
     fn read(&mut self, address: u64, buf: &mut [u8]) -> ControlResult<()>;
 
     /// Writes data to the device's memory.
+    ///
+    /// Implementors split the write into multiple transactions if it exceeds the maximum length
+    /// the device can handle at once.
     fn write(&mut self, address: u64, data: &[u8]) -> ControlResult<()>;
 
+    /// Reads data from multiple regions of the device's memory.
+    ///
+    /// Each entry is a pair of an address and a buffer, reads length of each entry is same as
+    /// the length of the buffer.
+    ///
+    /// The default implementation calls [`Self::read`] for each entry. Implementors which can
+    /// batch multiple reads into a single transaction should override this method.
+    fn read_stacked(&mut self, entries: &mut [(u64, &mut [u8])]) -> ControlResult<()> {
+        for (address, buf) in entries {
+            self.read(*address, buf)?;
+        }
+        Ok(())
+    }
+
+    /// Writes data to multiple regions of the device's memory.
+    ///
+    /// Entries are written in order.
+    ///
+    /// The default implementation calls [`Self::write`] for each entry. Implementors which can
+    /// batch multiple writes into a single transaction should override this method.
+    fn write_stacked(&mut self, entries: &[(u64, &[u8])]) -> ControlResult<()> {
+        for (address, data) in entries {
+            self.write(*address, data)?;
+        }
+        Ok(())
+    }
+
     /// Returns `GenICam` xml string.
     fn genapi(&mut self) -> ControlResult<String>;
 
+    /// Returns `GenICam` xml string, aborting retrieval with [`ControlError::Cancelled`](crate::ControlError::Cancelled) when
+    /// `token` is cancelled.
+    ///
+    /// The default implementation only checks `token` before calling [`Self::genapi`].
+    /// Implementors which retrieve the xml in multiple transactions should override this method
+    /// to check `token` between them.
+    fn genapi_with_cancel(&mut self, token: &CancellationToken) -> ControlResult<String> {
+        token.check_control()?;
+        self.genapi()
+    }
+
     /// Enables streaming.
     fn enable_streaming(&mut self) -> ControlResult<()>;
 
     /// Disables streaming.
     fn disable_streaming(&mut self) -> ControlResult<()>;
+
+    /// Enables streaming of the stream channel of `index`.
+    ///
+    /// The default implementation supports only the first channel and calls
+    /// [`Self::enable_streaming`].
+    fn enable_streaming_channel(&mut self, index: usize) -> ControlResult<()> {
+        if index == 0 {
+            self.enable_streaming()
+        } else {
+            Err(missing_stream_channel(index))
+        }
+    }
+
+    /// Disables streaming of the stream channel of `index`.
+    ///
+    /// The default implementation supports only the first channel and calls
+    /// [`Self::disable_streaming`].
+    fn disable_streaming_channel(&mut self, index: usize) -> ControlResult<()> {
+        if index == 0 {
+            self.disable_streaming()
+        } else {
+            Err(missing_stream_channel(index))
+        }
+    }
+}
+
+fn missing_stream_channel(index: usize) -> ControlError {
+    ControlError::InvalidDevice(format!("the device doesn't have stream channel {}", index).into())
+}
+
+/// Blocks until a payload is received, or returns [`StreamError::Timeout`] if no payload arrives
+/// within `timeout`.
+pub(crate) fn recv_timeout(
+    payload_rx: &PayloadReceiver,
+    timeout: Duration,
+) -> StreamResult<Payload> {
+    task::block_on(future::timeout(timeout, payload_rx.recv())).map_err(|_| StreamError::Timeout)?
+}
+
+/// Same as [`recv_timeout`], but returns [`StreamError::Cancelled`] as soon as `token` is
+/// cancelled.
+pub(crate) fn recv_timeout_with_cancel(
+    payload_rx: &PayloadReceiver,
+    timeout: Duration,
+    token: &CancellationToken,
+) -> StreamResult<Payload> {
+    task::block_on(future::timeout(timeout, payload_rx.recv_with_cancel(token)))
+        .map_err(|_| StreamError::Timeout)?
 }
 
 /// This trait provides streaming capability.
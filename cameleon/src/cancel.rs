@@ -0,0 +1,111 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains [`CancellationToken`] to abort long-running operations.
+//!
+//! Long-running operations, e.g. `GenApi` xml retrieval or waiting for a payload, have variants
+//! that accept a token. Once [`CancellationToken::cancel`] is called, the operations return
+//! `Cancelled` error promptly without closing or abandoning the handles, so that the handles
+//! can be used again.
+//!
+//! # Examples
+//! ```rust
+//! # use cameleon::u3v;
+//! use cameleon::cancel::CancellationToken;
+//!
+//! # let mut cameras = u3v::enumerate_cameras().unwrap();
+//! # if cameras.is_empty() {
+//! #     return;
+//! # }
+//! # let mut camera = cameras.pop().unwrap();
+//! camera.open().unwrap();
+//!
+//! let token = CancellationToken::new();
+//! // Pass the clone of the token to e.g. a cancel button of GUI.
+//! let cancel_button = token.clone();
+//! std::thread::spawn(move || cancel_button.cancel());
+//!
+//! match camera.load_context_with_cancel(&token) {
+//!     Ok(_) => println!("context is loaded"),
+//!     Err(e) if e.is_cancelled() => println!("loading is cancelled"),
+//!     Err(e) => panic!("{}", e),
+//! }
+//!
+//! camera.close().unwrap();
+//! ```
+
+use async_std::channel::{bounded, Receiver, Sender};
+use cameleon_genapi::{GenApiError, GenApiResult};
+
+use super::{ControlError, ControlResult, StreamError, StreamResult};
+
+/// A token to cancel long-running operations.
+///
+/// Cloned tokens share the state, i.e. cancelling one of them cancels all of them.
+/// A cancelled token can't be reset, create a new token for subsequent operations.
+#[derive(Debug, Clone)]
+pub struct CancellationToken {
+    /// Nothing is sent through the channel, closing it notifies cancellation to waiters.
+    tx: Sender<()>,
+    rx: Receiver<()>,
+}
+
+impl CancellationToken {
+    /// Creates a new token.
+    #[must_use]
+    pub fn new() -> Self {
+        let (tx, rx) = bounded(1);
+        Self { tx, rx }
+    }
+
+    /// Cancels all operations that the token (or its clones) is passed to.
+    pub fn cancel(&self) {
+        self.tx.close();
+    }
+
+    /// Returns `true` if the token is already cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.tx.is_closed()
+    }
+
+    /// Waits until the token is cancelled.
+    pub async fn cancelled(&self) {
+        // `recv` returns only when the channel is closed since nothing is sent.
+        self.rx.recv().await.ok();
+    }
+
+    /// Returns [`ControlError::Cancelled`] if the token is already cancelled.
+    pub fn check_control(&self) -> ControlResult<()> {
+        if self.is_cancelled() {
+            Err(ControlError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns [`ControlError::Cancelled`] wrapped in [`GenApiError::Device`] if the token is
+    /// already cancelled, which is how `GenApi` operations report cancellation.
+    ///
+    /// [`CameleonError::is_cancelled`](crate::CameleonError::is_cancelled) detects the wrapped
+    /// error.
+    pub fn check_genapi(&self) -> GenApiResult<()> {
+        self.check_control()
+            .map_err(|e| GenApiError::Device(Box::new(e)))
+    }
+
+    /// Returns [`StreamError::Cancelled`] if the token is already cancelled.
+    pub fn check_stream(&self) -> StreamResult<()> {
+        if self.is_cancelled() {
+            Err(StreamError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,459 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains a utility to compare capabilities of multiple cameras.
+//!
+//! [`CapabilityMatrix`] is a table whose rows are cameras and columns are features. Each cell
+//! describes whether the camera supports the feature and which values the feature can take.
+//! It's useful to verify that all cameras of a multi camera system support the identical
+//! configuration before deployment.
+//!
+//! # Examples
+//! ```rust
+//! # use cameleon::u3v;
+//! use cameleon::capability::{CapabilityMatrix, DEFAULT_FEATURES};
+//!
+//! let mut cameras = u3v::enumerate_cameras().unwrap();
+//! for camera in &mut cameras {
+//!     camera.open().unwrap();
+//!     camera.load_context().unwrap();
+//! }
+//!
+//! let matrix = CapabilityMatrix::from_cameras(&mut cameras, DEFAULT_FEATURES).unwrap();
+//! if !matrix.is_uniform() {
+//!     println!("mismatched features: {:?}", matrix.mismatched_features());
+//! }
+//! // Export the matrix as csv.
+//! println!("{}", matrix.to_csv());
+//!
+//! for mut camera in cameras {
+//!     camera.close().unwrap();
+//! }
+//! ```
+
+use std::fmt;
+
+use super::{
+    genapi::{GenApiCtxt, Node, ParamsCtxt},
+    CameleonResult, Camera, CameraInfo, DeviceControl, PayloadStream,
+};
+
+use cameleon_genapi::GenApiResult;
+
+/// Features defined in `SFNC` that are commonly used to configure acquisition.
+pub const DEFAULT_FEATURES: &[&str] = &[
+    "Width",
+    "Height",
+    "OffsetX",
+    "OffsetY",
+    "BinningHorizontal",
+    "BinningVertical",
+    "PixelFormat",
+    "AcquisitionMode",
+    "AcquisitionFrameRate",
+    "ExposureMode",
+    "ExposureTime",
+    "ExposureAuto",
+    "GainSelector",
+    "Gain",
+    "GainAuto",
+    "BlackLevel",
+    "Gamma",
+    "TriggerSelector",
+    "TriggerMode",
+    "TriggerSource",
+    "TriggerActivation",
+    "TriggerSoftware",
+    "LineSelector",
+    "LineMode",
+    "DeviceLinkThroughputLimit",
+];
+
+/// Capability of a camera for a feature.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub enum Capability {
+    /// The camera doesn't have the feature.
+    Unsupported,
+    /// The camera has the feature, but the feature is not readable in the current state.
+    Unavailable,
+    /// The feature has `IInteger` interface.
+    Integer {
+        /// Minimum value of the feature.
+        min: i64,
+        /// Maximum value of the feature.
+        max: i64,
+        /// Increment of the feature if exists.
+        inc: Option<i64>,
+    },
+    /// The feature has `IFloat` interface.
+    Float {
+        /// Minimum value of the feature.
+        min: f64,
+        /// Maximum value of the feature.
+        max: f64,
+        /// Increment of the feature if exists.
+        inc: Option<f64>,
+    },
+    /// The feature has `IEnumeration` interface. Contains symbolic names of entries that are
+    /// implemented and available.
+    Enumeration(Vec<String>),
+    /// The feature has `IBoolean` interface.
+    Boolean,
+    /// The feature has `IString` interface.
+    String,
+    /// The feature has `ICommand` interface.
+    Command,
+}
+
+impl Capability {
+    /// Returns `true` if the camera has the feature.
+    pub fn is_supported(&self) -> bool {
+        !matches!(self, Self::Unsupported)
+    }
+
+    /// Probes capability of the feature with the given name.
+    pub fn probe<Ctrl, Ctxt>(ctxt: &mut ParamsCtxt<Ctrl, Ctxt>, name: &str) -> GenApiResult<Self>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let node = match ctxt.node(name) {
+            Some(node) => node,
+            None => return Ok(Self::Unsupported),
+        };
+
+        if let Some(node) = node.as_command(ctxt) {
+            return Ok(if node.is_writable(ctxt)? {
+                Self::Command
+            } else {
+                Self::Unavailable
+            });
+        }
+        if !node.is_readable(ctxt)? {
+            return Ok(Self::Unavailable);
+        }
+
+        Self::probe_readable(ctxt, node)
+    }
+
+    fn probe_readable<Ctrl, Ctxt>(
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        node: Node,
+    ) -> GenApiResult<Self>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        if let Some(node) = node.as_integer(ctxt) {
+            Ok(Self::Integer {
+                min: node.min(ctxt)?,
+                max: node.max(ctxt)?,
+                inc: node.inc(ctxt)?,
+            })
+        } else if let Some(node) = node.as_float(ctxt) {
+            Ok(Self::Float {
+                min: node.min(ctxt)?,
+                max: node.max(ctxt)?,
+                inc: node.inc(ctxt)?,
+            })
+        } else if let Some(node) = node.as_enumeration(ctxt) {
+            let mut entries = vec![];
+            for entry in node.entries(ctxt) {
+                if entry.is_implemented(ctxt)? && entry.is_available(ctxt)? {
+                    entries.push(entry.symbolic(ctxt).to_string());
+                }
+            }
+            Ok(Self::Enumeration(entries))
+        } else if node.as_boolean(ctxt).is_some() {
+            Ok(Self::Boolean)
+        } else if node.as_string(ctxt).is_some() {
+            Ok(Self::String)
+        } else {
+            Ok(Self::Unavailable)
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsupported => write!(f, "unsupported"),
+            Self::Unavailable => write!(f, "unavailable"),
+            Self::Integer { min, max, inc } => {
+                write!(f, "integer[{}..={}", min, max)?;
+                if let Some(inc) = inc {
+                    write!(f, "; {}", inc)?;
+                }
+                write!(f, "]")
+            }
+            Self::Float { min, max, inc } => {
+                write!(f, "float[{}..={}", min, max)?;
+                if let Some(inc) = inc {
+                    write!(f, "; {}", inc)?;
+                }
+                write!(f, "]")
+            }
+            Self::Enumeration(entries) => write!(f, "enumeration{{{}}}", entries.join("|")),
+            Self::Boolean => write!(f, "boolean"),
+            Self::String => write!(f, "string"),
+            Self::Command => write!(f, "command"),
+        }
+    }
+}
+
+/// A row of [`CapabilityMatrix`], which corresponds to a camera.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct CapabilityRow {
+    /// Information of the camera.
+    pub info: CameraInfo,
+    /// Capabilities of the camera, in the same order as [`CapabilityMatrix::features`].
+    pub capabilities: Vec<Capability>,
+}
+
+/// A table of capabilities, rows are cameras and columns are features.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct CapabilityMatrix {
+    features: Vec<String>,
+    rows: Vec<CapabilityRow>,
+}
+
+impl CapabilityMatrix {
+    /// Constructs an empty matrix with the given features as columns.
+    pub fn new<S: AsRef<str>>(features: &[S]) -> Self {
+        Self {
+            features: features.iter().map(|f| f.as_ref().to_string()).collect(),
+            rows: vec![],
+        }
+    }
+
+    /// Constructs a matrix by probing all `cameras`.
+    ///
+    /// All cameras must be opened and their contexts must be loaded.
+    pub fn from_cameras<Ctrl, Strm, Ctxt, S>(
+        cameras: &mut [Camera<Ctrl, Strm, Ctxt>],
+        features: &[S],
+    ) -> CameleonResult<Self>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+        S: AsRef<str>,
+    {
+        let mut matrix = Self::new(features);
+        for camera in cameras {
+            matrix.probe(camera)?;
+        }
+        Ok(matrix)
+    }
+
+    /// Probes the `camera` and appends the result as a row.
+    ///
+    /// The camera must be opened and its context must be loaded.
+    pub fn probe<Ctrl, Strm, Ctxt>(
+        &mut self,
+        camera: &mut Camera<Ctrl, Strm, Ctxt>,
+    ) -> CameleonResult<()>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        let info = camera.info().clone();
+        let mut ctxt = camera.params_ctxt()?;
+        let capabilities = self
+            .features
+            .iter()
+            .map(|feature| Capability::probe(&mut ctxt, feature))
+            .collect::<GenApiResult<_>>()?;
+        self.rows.push(CapabilityRow { info, capabilities });
+        Ok(())
+    }
+
+    /// Returns features of the matrix.
+    pub fn features(&self) -> &[String] {
+        &self.features
+    }
+
+    /// Returns rows of the matrix.
+    pub fn rows(&self) -> &[CapabilityRow] {
+        &self.rows
+    }
+
+    /// Returns capabilities of all cameras for the feature.
+    ///
+    /// Returns `None` if the matrix doesn't contain the feature.
+    pub fn column(&self, feature: &str) -> Option<Vec<&Capability>> {
+        let idx = self.features.iter().position(|f| f == feature)?;
+        Some(self.rows.iter().map(|row| &row.capabilities[idx]).collect())
+    }
+
+    /// Returns features whose capabilities differ among cameras.
+    pub fn mismatched_features(&self) -> Vec<&str> {
+        self.features
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| {
+                let mut column = self.rows.iter().map(|row| &row.capabilities[*idx]);
+                match column.next() {
+                    Some(first) => column.any(|cap| cap != first),
+                    None => false,
+                }
+            })
+            .map(|(_, feature)| feature.as_str())
+            .collect()
+    }
+
+    /// Returns `true` if all cameras have identical capabilities.
+    pub fn is_uniform(&self) -> bool {
+        self.mismatched_features().is_empty()
+    }
+
+    /// Serializes the matrix in csv format.
+    ///
+    /// The header consists of `vendor_name`, `model_name`, `serial_number` and the features,
+    /// each capability is serialized in the format of its `Display` implementation.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+
+        let header = ["vendor_name", "model_name", "serial_number"]
+            .iter()
+            .map(|s| (*s).to_string())
+            .chain(self.features.iter().cloned());
+        push_csv_record(&mut csv, header);
+
+        for row in &self.rows {
+            let record = vec![
+                row.info.vendor_name.clone(),
+                row.info.model_name.clone(),
+                row.info.serial_number.clone(),
+            ]
+            .into_iter()
+            .chain(row.capabilities.iter().map(ToString::to_string));
+            push_csv_record(&mut csv, record);
+        }
+
+        csv
+    }
+}
+
+fn push_csv_record(csv: &mut String, fields: impl Iterator<Item = String>) {
+    for (i, field) in fields.enumerate() {
+        if i != 0 {
+            csv.push(',');
+        }
+        if field.contains(&[',', '"', '\n', '\r'][..]) {
+            csv.push('"');
+            csv.push_str(&field.replace('"', "\"\""));
+            csv.push('"');
+        } else {
+            csv.push_str(&field);
+        }
+    }
+    csv.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(serial_number: &str, capabilities: Vec<Capability>) -> CapabilityRow {
+        CapabilityRow {
+            info: CameraInfo {
+                vendor_name: "Vendor".into(),
+                model_name: "Model".into(),
+                serial_number: serial_number.into(),
+            },
+            capabilities,
+        }
+    }
+
+    fn width(max: i64) -> Capability {
+        Capability::Integer {
+            min: 16,
+            max,
+            inc: Some(16),
+        }
+    }
+
+    #[test]
+    fn test_mismatched_features() {
+        let mut matrix = CapabilityMatrix::new(&["Width", "PixelFormat", "Gain"]);
+        assert!(matrix.is_uniform());
+
+        let formats = Capability::Enumeration(vec!["Mono8".into(), "Mono12".into()]);
+        matrix.rows.push(row(
+            "0",
+            vec![width(1920), formats.clone(), Capability::Unsupported],
+        ));
+        assert!(matrix.is_uniform());
+
+        matrix.rows.push(row(
+            "1",
+            vec![width(1280), formats, Capability::Unsupported],
+        ));
+        matrix.rows.push(row(
+            "2",
+            vec![
+                width(1920),
+                Capability::Enumeration(vec!["Mono8".into(), "Mono12".into()]),
+                Capability::Unavailable,
+            ],
+        ));
+        assert_eq!(matrix.mismatched_features(), ["Width", "Gain"]);
+        assert!(!matrix.is_uniform());
+        assert_eq!(
+            matrix.column("Gain").unwrap(),
+            [
+                &Capability::Unsupported,
+                &Capability::Unsupported,
+                &Capability::Unavailable
+            ]
+        );
+        assert!(matrix.column("Height").is_none());
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let mut matrix = CapabilityMatrix::new(&["Width", "PixelFormat"]);
+        matrix.rows.push(row(
+            "0",
+            vec![
+                width(1920),
+                Capability::Enumeration(vec!["Mono8".into(), "Mono12".into()]),
+            ],
+        ));
+
+        assert_eq!(
+            matrix.to_csv(),
+            "vendor_name,model_name,serial_number,Width,PixelFormat\n\
+             Vendor,Model,0,integer[16..=1920; 16],enumeration{Mono8|Mono12}\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_escape() {
+        let mut csv = String::new();
+        let fields = ["plain", "a,b", "say \"hi\"", "multi\nline", "cr\r"];
+        push_csv_record(&mut csv, fields.iter().map(|f| (*f).to_string()));
+        assert_eq!(
+            csv,
+            "plain,\"a,b\",\"say \"\"hi\"\"\",\"multi\nline\",\"cr\r\"\n"
+        );
+    }
+}
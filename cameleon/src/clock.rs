@@ -0,0 +1,231 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains [`ClockCorrelation`] which converts device timestamps to host time.
+//!
+//! A device timestamp, e.g. [`Payload::timestamp`](crate::payload::Payload::timestamp), is
+//! measured by the device internal clock, which has an unknown offset from the host clock and
+//! drifts from it. `ClockCorrelation` estimates the offset and the drift by latching the device
+//! timestamp several times, so that timestamps of multiple devices can be compared on the host
+//! clock.
+//!
+//! # Examples
+//! ```no_run
+//! use cameleon::u3v;
+//!
+//! let mut cameras = u3v::enumerate_cameras().unwrap();
+//! if cameras.is_empty() {
+//!     return;
+//! }
+//! let mut camera = cameras.pop().unwrap();
+//! camera.open().unwrap();
+//!
+//! let correlation = camera.ctrl.correlate_clock(16).unwrap();
+//!
+//! let payload_rx = camera.start_streaming(3).unwrap();
+//! let payload = async_std::task::block_on(payload_rx.recv()).unwrap();
+//! let host_time = correlation.to_host(payload.timestamp());
+//! println!("payload was generated at {:?}", host_time);
+//! payload_rx.send_back(payload);
+//!
+//! camera.close().unwrap();
+//! ```
+
+use std::time::{Duration, Instant};
+
+use super::ControlResult;
+
+/// Linear model of the device clock against the host monotonic clock.
+///
+/// The host time of a device timestamp `t` is estimated as
+/// `host_base + offset + (t - device_base) * (1 + drift)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockCorrelation {
+    host_base: Instant,
+    device_base: Duration,
+    /// Offset in ns from `host_base` to the host time of `device_base`.
+    offset: f64,
+    /// Host ns elapsed per device ns.
+    rate: f64,
+    uncertainty: Duration,
+}
+
+impl ClockCorrelation {
+    /// Estimates the correlation by calling `latch` `samples` times.
+    ///
+    /// `latch` must latch and return the current device timestamp. The host time of each sample
+    /// is taken as the midpoint of the call, so a shorter round trip yields a better estimation.
+    ///
+    /// # Panics
+    /// If `samples` is zero.
+    pub fn sample(
+        samples: usize,
+        mut latch: impl FnMut() -> ControlResult<Duration>,
+    ) -> ControlResult<Self> {
+        assert!(samples > 0, "at least one sample is required");
+
+        let mut points = Vec::with_capacity(samples);
+        for _ in 0..samples {
+            let before = Instant::now();
+            let device_time = latch()?;
+            let after = Instant::now();
+            points.push(Sample {
+                host_time: before + (after - before) / 2,
+                device_time,
+                half_round_trip: (after - before) / 2,
+            });
+        }
+
+        Ok(Self::from_samples(&points))
+    }
+
+    /// Returns the estimated host time of the device timestamp.
+    ///
+    /// Returns `None` if the result can't be represented by [`Instant`].
+    #[must_use]
+    pub fn to_host(&self, device_timestamp: Duration) -> Option<Instant> {
+        let elapsed = nanos(device_timestamp) - nanos(self.device_base);
+        let host = self.offset + elapsed * self.rate;
+        if host >= 0.0 {
+            self.host_base
+                .checked_add(Duration::from_nanos(host as u64))
+        } else {
+            self.host_base
+                .checked_sub(Duration::from_nanos((-host) as u64))
+        }
+    }
+
+    /// Returns the relative drift of the device clock against the host clock.
+    ///
+    /// A positive value means the device clock runs slower than the host clock, e.g. `1e-6`
+    /// means the host clock advances 1.000001s while the device clock advances 1s.
+    #[must_use]
+    pub fn drift(&self) -> f64 {
+        self.rate - 1.0
+    }
+
+    /// Returns the upper bound of the sampling error, which is the largest half round trip time
+    /// of latching.
+    #[must_use]
+    pub fn uncertainty(&self) -> Duration {
+        self.uncertainty
+    }
+
+    /// Fits a line to samples by least squares.
+    fn from_samples(samples: &[Sample]) -> Self {
+        let host_base = samples[0].host_time;
+        let device_base = samples[0].device_time;
+        let points: Vec<(f64, f64)> = samples
+            .iter()
+            .map(|s| {
+                (
+                    nanos(s.device_time) - nanos(device_base),
+                    signed_nanos(host_base, s.host_time),
+                )
+            })
+            .collect();
+
+        let len = points.len() as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / len;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / len;
+        let (cov, var) = points.iter().fold((0.0, 0.0), |(cov, var), (x, y)| {
+            (
+                cov + (x - mean_x) * (y - mean_y),
+                var + (x - mean_x) * (x - mean_x),
+            )
+        });
+
+        // Drift can't be estimated from a single point in time.
+        let rate = if var > 0.0 { cov / var } else { 1.0 };
+        let offset = mean_y - rate * mean_x;
+        let uncertainty = samples
+            .iter()
+            .map(|s| s.half_round_trip)
+            .max()
+            .unwrap_or_default();
+
+        Self {
+            host_base,
+            device_base,
+            offset,
+            rate,
+            uncertainty,
+        }
+    }
+}
+
+struct Sample {
+    host_time: Instant,
+    device_time: Duration,
+    half_round_trip: Duration,
+}
+
+fn nanos(d: Duration) -> f64 {
+    d.as_nanos() as f64
+}
+
+fn signed_nanos(base: Instant, time: Instant) -> f64 {
+    if time >= base {
+        nanos(time - base)
+    } else {
+        -nanos(base - time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Instant, b: Instant) {
+        let diff = if a > b { a - b } else { b - a };
+        assert!(diff < Duration::from_micros(1), "{:?}", diff);
+    }
+
+    #[test]
+    fn test_offset() {
+        let host_base = Instant::now();
+        let samples: Vec<_> = (0..4)
+            .map(|i| Sample {
+                host_time: host_base + Duration::from_millis(i * 10),
+                device_time: Duration::from_secs(100) + Duration::from_millis(i * 10),
+                half_round_trip: Duration::from_micros(i),
+            })
+            .collect();
+
+        let correlation = ClockCorrelation::from_samples(&samples);
+        assert!(correlation.drift().abs() < 1e-9);
+        assert_eq!(correlation.uncertainty(), Duration::from_micros(3));
+        assert_close(
+            correlation.to_host(Duration::from_secs(101)).unwrap(),
+            host_base + Duration::from_secs(1),
+        );
+    }
+
+    #[test]
+    fn test_drift() {
+        let host_base = Instant::now();
+        // Device clock runs at 0.5x speed of the host clock.
+        let samples: Vec<_> = (0..4)
+            .map(|i| Sample {
+                host_time: host_base + Duration::from_millis(i * 20),
+                device_time: Duration::from_millis(i * 10),
+                half_round_trip: Duration::from_secs(0),
+            })
+            .collect();
+
+        let correlation = ClockCorrelation::from_samples(&samples);
+        assert!((correlation.drift() - 1.0).abs() < 1e-9);
+        assert_close(
+            correlation.to_host(Duration::from_secs(1)).unwrap(),
+            host_base + Duration::from_secs(2),
+        );
+    }
+
+    #[test]
+    fn test_single_sample() {
+        let correlation = ClockCorrelation::sample(1, || Ok(Duration::from_secs(10))).unwrap();
+        assert!(correlation.drift().abs() < f64::EPSILON);
+        assert!(correlation.to_host(Duration::from_secs(10)).is_some());
+    }
+}
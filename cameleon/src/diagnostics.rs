@@ -0,0 +1,167 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains [`Diagnostics`], a report of the health of a camera.
+//!
+//! The report gathers the state of the device read through `GenApi` and statistics counted by
+//! the host into one place, so that it can be collected periodically from each camera of a
+//! fleet. With `serde` feature, the report can be serialized to e.g. JSON and sent to a
+//! monitoring dashboard.
+//!
+//! # Examples
+//! ```rust
+//! # use cameleon::u3v;
+//! # let mut cameras = u3v::enumerate_cameras().unwrap();
+//! # if cameras.is_empty() {
+//! #     return;
+//! # }
+//! # let mut camera = cameras.pop().unwrap();
+//! camera.open().unwrap();
+//! camera.load_context().unwrap();
+//! let payload_rx = camera.start_streaming(3).unwrap();
+//!
+//! // ...
+//!
+//! let diagnostics = camera.diagnostics().unwrap();
+//! if let Some(temperature) = diagnostics.device_temperature {
+//!     println!("temperature: {} C", temperature);
+//! }
+//! for stream in &diagnostics.streams {
+//!     println!(
+//!         "channel {}: {} dropped, {} errors",
+//!         stream.channel, stream.statistics.num_dropped, stream.statistics.num_errors
+//!     );
+//! }
+//!
+//! camera.close().unwrap();
+//! ```
+
+use tracing::warn;
+
+use super::{
+    genapi::{CacheStats, GenApiCtxt, ParamsCtxt},
+    payload::StreamStatistics,
+    sfnc::{self, Feature, FeatureType},
+    CameleonResult, Camera, CameraInfo, DeviceControl, PayloadStream,
+};
+
+/// A report of the health of a camera returned from [`Camera::diagnostics`].
+///
+/// Device features which are not implemented or not readable are `None`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct Diagnostics {
+    /// Vendor name of the camera.
+    pub vendor_name: String,
+    /// Model name of the camera.
+    pub model_name: String,
+    /// Serial number of the camera.
+    pub serial_number: String,
+    /// `DeviceFirmwareVersion`.
+    pub firmware_version: Option<String>,
+    /// `DeviceTemperature` in degrees Celsius, measured at the sensor selected by
+    /// `DeviceTemperatureSelector`.
+    pub device_temperature: Option<f64>,
+    /// `DeviceLinkSpeed` in bytes per second.
+    pub link_speed: Option<i64>,
+    /// `true` if any stream channel is streaming.
+    pub is_streaming: bool,
+    /// Statistics of the last streaming of each stream channel which has been started.
+    pub streams: Vec<StreamDiagnostics>,
+    /// Statistics of the cache of the `GenApi` context.
+    pub cache_stats: CacheStats,
+}
+
+/// Statistics of a stream channel in [`Diagnostics`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct StreamDiagnostics {
+    /// Index of the stream channel.
+    pub channel: usize,
+    /// Statistics counted since the streaming of the channel is started last time.
+    pub statistics: StreamStatistics,
+}
+
+impl Diagnostics {
+    /// Returns the total number of errors of all stream channels, including dropped payloads.
+    pub fn num_stream_errors(&self) -> u64 {
+        self.streams
+            .iter()
+            .map(|stream| stream.statistics.num_errors + stream.statistics.num_dropped)
+            .sum()
+    }
+}
+
+impl<Ctrl, Strm, Ctxt> Camera<Ctrl, Strm, Ctxt> {
+    /// Collects a report of the health of the camera.
+    ///
+    /// Failing to read a device feature doesn't fail the whole report, the feature is reported
+    /// as `None` and the error is logged instead. So this method only fails if the `GenApi`
+    /// context is not loaded.
+    pub fn diagnostics(&mut self) -> CameleonResult<Diagnostics>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        let mut ctxt = self.params_ctxt()?;
+        let firmware_version = read_optional(&mut ctxt, sfnc::DEVICE_FIRMWARE_VERSION);
+        let device_temperature = read_optional(&mut ctxt, sfnc::DEVICE_TEMPERATURE);
+        let link_speed = read_optional(&mut ctxt, sfnc::DEVICE_LINK_SPEED);
+        let cache_stats = ctxt.ctxt.cache_stats();
+
+        let CameraInfo {
+            vendor_name,
+            model_name,
+            serial_number,
+        } = self.info().clone();
+        let streams = self
+            .stream_counters
+            .iter()
+            .map(|(channel, counters)| StreamDiagnostics {
+                channel: *channel,
+                statistics: counters.statistics(),
+            })
+            .collect();
+
+        Ok(Diagnostics {
+            vendor_name,
+            model_name,
+            serial_number,
+            firmware_version,
+            device_temperature,
+            link_speed,
+            is_streaming: self.is_streaming(),
+            streams,
+            cache_stats,
+        })
+    }
+}
+
+fn read_optional<Ctrl, Ctxt, T>(ctxt: &mut ParamsCtxt<Ctrl, Ctxt>, feature: Feature<T>) -> Option<T>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+    T: FeatureType,
+{
+    let res = feature.is_readable(ctxt).and_then(|is_readable| {
+        if is_readable {
+            feature.value(ctxt).map(Some)
+        } else {
+            Ok(None)
+        }
+    });
+    res.unwrap_or_else(|e| {
+        warn!("failed to read `{}`: {}", feature.name(), e);
+        None
+    })
+}
@@ -0,0 +1,462 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains [`FeatureConfig`], a snapshot of the configuration of the device which
+//! can be applied to the device later.
+//!
+//! With `serde` feature, `FeatureConfig` can be serialized to a human-editable format, e.g. JSON
+//! or TOML, so that the configuration is kept in a version control system and applied on startup.
+//!
+//! # Examples
+//! ```rust
+//! # use cameleon::u3v;
+//! # let mut cameras = u3v::enumerate_cameras().unwrap();
+//! # if cameras.is_empty() {
+//! #     return;
+//! # }
+//! # let mut camera = cameras.pop().unwrap();
+//! # camera.open().unwrap();
+//! use cameleon::genapi::FeatureConfig;
+//!
+//! camera.load_context().unwrap();
+//! let mut params_ctxt = camera.params_ctxt().unwrap();
+//!
+//! let config = FeatureConfig::export(&mut params_ctxt).unwrap();
+//! for setting in &config.features {
+//!     println!("{} = {:?}", setting.name, setting.value);
+//! }
+//! // Save the config with e.g. `serde_json::to_writer_pretty` or `toml::to_string`, then load it
+//! // and apply it to the camera.
+//! config.apply(&mut params_ctxt).unwrap();
+//!
+//! # camera.close().unwrap();
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use cameleon_genapi::{GenApiError, GenApiResult};
+use tracing::warn;
+
+use super::{node_kind::FeatureValue, DeviceControl, GenApiCtxt, Node, ParamsCtxt, SelectorValue};
+use crate::cancel::CancellationToken;
+
+/// A snapshot of values of features which are readable and writable.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct FeatureConfig {
+    /// Settings of features in the order they are applied.
+    pub features: Vec<FeatureSetting>,
+}
+
+/// A value of a feature in [`FeatureConfig`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct FeatureSetting {
+    /// Name of the feature node.
+    pub name: String,
+    /// Value of the feature.
+    pub value: FeatureValue,
+    /// Values of selectors under which the feature takes the value, e.g. `GainSelector` for
+    /// `Gain`. Empty if the feature is not selected by any selector.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub selectors: Vec<SelectorSetting>,
+}
+
+/// A value of a selector of [`FeatureSetting`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct SelectorSetting {
+    /// Name of the selector node.
+    pub name: String,
+    /// Value of the selector.
+    pub value: SelectorValue,
+}
+
+impl FeatureConfig {
+    /// Reads all features under the `Root` category which are readable and writable.
+    ///
+    /// A feature selected by selectors is read for each combination of the selector values.
+    /// Selectors themselves are placed after the other features, so that [`Self::apply`]
+    /// leaves the selectors as they are now. Values of selectors are restored after reading.
+    pub fn export<Ctrl, Ctxt>(ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<Self>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let tree = ctxt
+            .category_tree()?
+            .ok_or_else(|| GenApiError::InvalidNode("missing `Root` category".into()))?;
+        let mut nodes = vec![];
+        tree.visit(|entry, _| {
+            if !entry.is_category() {
+                nodes.push(Node::from(entry.id()));
+            }
+        });
+        // A feature may belong to multiple categories.
+        let mut visited = HashSet::new();
+        nodes.retain(|node| visited.insert(*node));
+
+        let mut selectors_of: HashMap<Node, Vec<Node>> = HashMap::new();
+        for node in &nodes {
+            for feature in node.selected_features(ctxt) {
+                selectors_of.entry(feature).or_default().push(*node);
+            }
+        }
+        let (selectors, features): (Vec<_>, Vec<_>) =
+            nodes.into_iter().partition(|node| node.is_selector(ctxt));
+
+        let mut settings = vec![];
+        for node in features.into_iter().chain(selectors) {
+            let selectors = selectors_of.remove(&node).unwrap_or_default();
+            export_feature(ctxt, node, &selectors, &mut vec![], &mut settings)?;
+        }
+
+        Ok(Self { features: settings })
+    }
+
+    /// Writes the values of the features to the device.
+    ///
+    /// Writing a feature may depend on other features, e.g. `OffsetX` can't exceed
+    /// `WidthMax - Width`. So settings which failed to be written are retried as long as any
+    /// other setting succeeds, then the last error is returned if some settings still fail.
+    pub fn apply<Ctrl, Ctxt>(&self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        self.apply_with_cancel(ctxt, &CancellationToken::new())
+    }
+
+    /// Same as [`Self::apply`], but stops writing the remaining settings when `token` is
+    /// cancelled.
+    ///
+    /// Returns [`ControlError::Cancelled`](crate::ControlError::Cancelled) wrapped in
+    /// [`GenApiError::Device`] on cancellation. Settings written before the cancellation are
+    /// kept, and selectors are restored as well as when a setting fails.
+    pub fn apply_with_cancel<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        token: &CancellationToken,
+    ) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let mut pending: Vec<_> = self.features.iter().collect();
+        loop {
+            let mut failed = vec![];
+            let mut last_err = None;
+            for setting in &pending {
+                token.check_genapi()?;
+                if let Err(e) = apply_setting(ctxt, setting) {
+                    failed.push(*setting);
+                    last_err = Some(e);
+                }
+            }
+
+            match last_err {
+                None => return Ok(()),
+                Some(e) if failed.len() == pending.len() => return Err(e),
+                Some(_) => pending = failed,
+            }
+        }
+    }
+}
+
+fn export_feature<Ctrl, Ctxt>(
+    ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+    feature: Node,
+    selectors: &[Node],
+    selector_settings: &mut Vec<SelectorSetting>,
+    settings: &mut Vec<FeatureSetting>,
+) -> GenApiResult<()>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    let (selector, rest) = match selectors.split_first() {
+        Some(split) => split,
+        None => {
+            if feature.is_readable(ctxt)? && feature.is_writable(ctxt)? {
+                settings.push(FeatureSetting {
+                    name: feature.name(ctxt).to_string(),
+                    value: feature.feature_value(ctxt)?,
+                    selectors: selector_settings.clone(),
+                });
+            }
+            return Ok(());
+        }
+    };
+
+    let original = selector.selector_value(ctxt)?;
+    let res = selector.selector_values(ctxt).and_then(|values| {
+        for value in values {
+            selector.set_selector_value(ctxt, value.clone())?;
+            selector_settings.push(SelectorSetting {
+                name: selector.name(ctxt).to_string(),
+                value,
+            });
+            let res = export_feature(ctxt, feature, rest, selector_settings, settings);
+            selector_settings.pop();
+            res?;
+        }
+        Ok(())
+    });
+    let restore_res = selector.set_selector_value(ctxt, original);
+    with_restore_result(ctxt, *selector, res, restore_res)
+}
+
+fn apply_setting<Ctrl, Ctxt>(
+    ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+    setting: &FeatureSetting,
+) -> GenApiResult<()>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    let node = |ctxt: &ParamsCtxt<Ctrl, Ctxt>, name: &str| {
+        ctxt.node(name)
+            .ok_or_else(|| GenApiError::InvalidNode(format!("missing `{}`", name).into()))
+    };
+
+    let mut originals = Vec::with_capacity(setting.selectors.len());
+    let mut res = Ok(());
+    for selector_setting in &setting.selectors {
+        res = node(ctxt, &selector_setting.name).and_then(|selector| {
+            originals.push((selector, selector.selector_value(ctxt)?));
+            selector.set_selector_value(ctxt, selector_setting.value.clone())
+        });
+        if res.is_err() {
+            break;
+        }
+    }
+    if res.is_ok() {
+        res = node(ctxt, &setting.name)
+            .and_then(|feature| feature.set_feature_value(ctxt, setting.value.clone()));
+    }
+
+    // Restore selectors in the reverse order, since an outer selector may change the range of
+    // an inner one.
+    for (selector, original) in originals.into_iter().rev() {
+        let restore_res = selector.set_selector_value(ctxt, original);
+        res = with_restore_result(ctxt, selector, res, restore_res);
+    }
+    res
+}
+
+/// Merges `res` with the result of restoring `selector` afterwards. An error of `res` takes
+/// precedence, and the restore error is logged in that case.
+fn with_restore_result<T, Ctrl, Ctxt>(
+    ctxt: &ParamsCtxt<Ctrl, Ctxt>,
+    selector: Node,
+    res: GenApiResult<T>,
+    restore_res: GenApiResult<()>,
+) -> GenApiResult<T>
+where
+    Ctxt: GenApiCtxt,
+{
+    match (res, restore_res) {
+        (Err(err), Err(restore_err)) => {
+            warn!(
+                "failed to restore `{}`: {}",
+                selector.name(ctxt),
+                restore_err
+            );
+            Err(err)
+        }
+        (res, restore_res) => {
+            restore_res?;
+            res
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cameleon_genapi::{
+        builder::GenApiBuilder,
+        elem_type::{AccessMode, CachingMode},
+        store::DefaultNodeStore,
+    };
+
+    use super::{
+        super::{tests::MemoryControl, DefaultGenApiCtxt},
+        *,
+    };
+
+    const WIDTH_ADDRESS: usize = 0x00;
+    const SELECTOR_ADDRESS: usize = 0x10;
+    const GAIN_ADDRESS: usize = 0x20;
+
+    /// Builds a context where `GainSelector` selects `Gain`, which is stored at
+    /// `GAIN_ADDRESS + GainSelector`. `Width` isn't selected by any selector.
+    fn params_ctxt(gains: [u8; 3], selector: u8) -> ParamsCtxt<MemoryControl, DefaultGenApiCtxt> {
+        let mut builder =
+            GenApiBuilder::<DefaultNodeStore>::default().programmatic("Model", "Vendor");
+        builder.port("Device").finish();
+        builder
+            .category("Root")
+            .feature("Width")
+            .feature("GainSelector")
+            .feature("Gain")
+            .finish();
+        builder
+            .int_reg("Width", "Device")
+            .address(WIDTH_ADDRESS as i64)
+            .length(2)
+            .access_mode(AccessMode::RW)
+            .cacheable(CachingMode::NoCache)
+            .finish();
+        builder
+            .int_reg("GainSelectorReg", "Device")
+            .address(SELECTOR_ADDRESS as i64)
+            .length(1)
+            .access_mode(AccessMode::RW)
+            .cacheable(CachingMode::NoCache)
+            .finish();
+        builder
+            .integer("GainSelector")
+            .p_value("GainSelectorReg")
+            .min(0)
+            .max(2)
+            .p_selected("Gain")
+            .finish();
+        builder
+            .int_reg("Gain", "Device")
+            .address(GAIN_ADDRESS as i64)
+            .p_address("GainSelector")
+            .length(1)
+            .access_mode(AccessMode::RW)
+            .cacheable(CachingMode::NoCache)
+            .finish();
+        let (reg_desc, node_store, value_ctxt) = builder.build();
+
+        let mut memory = vec![0; GAIN_ADDRESS + 3];
+        memory[WIDTH_ADDRESS..WIDTH_ADDRESS + 2].copy_from_slice(&640_u16.to_le_bytes());
+        memory[SELECTOR_ADDRESS] = selector;
+        memory[GAIN_ADDRESS..].copy_from_slice(&gains);
+        ParamsCtxt {
+            ctrl: MemoryControl::new(memory),
+            ctxt: DefaultGenApiCtxt {
+                node_store,
+                value_ctxt,
+                reg_desc,
+            },
+        }
+    }
+
+    fn gain_setting(selector: i64, gain: i64) -> FeatureSetting {
+        FeatureSetting {
+            name: "Gain".into(),
+            value: FeatureValue::Integer(gain),
+            selectors: vec![SelectorSetting {
+                name: "GainSelector".into(),
+                value: SelectorValue::Integer(selector),
+            }],
+        }
+    }
+
+    fn setting(name: &str, value: i64) -> FeatureSetting {
+        FeatureSetting {
+            name: name.into(),
+            value: FeatureValue::Integer(value),
+            selectors: vec![],
+        }
+    }
+
+    #[test]
+    fn test_export() {
+        let mut ctxt = params_ctxt([10, 20, 30], 1);
+
+        let config = FeatureConfig::export(&mut ctxt).unwrap();
+        assert_eq!(
+            config.features,
+            [
+                setting("Width", 640),
+                gain_setting(0, 10),
+                gain_setting(1, 20),
+                gain_setting(2, 30),
+                setting("GainSelector", 1),
+            ]
+        );
+        assert_eq!(ctxt.ctrl.memory[SELECTOR_ADDRESS], 1);
+    }
+
+    #[test]
+    fn test_export_apply() {
+        let mut src = params_ctxt([10, 20, 30], 2);
+        let config = FeatureConfig::export(&mut src).unwrap();
+
+        let mut dst = params_ctxt([0, 0, 0], 0);
+        config.apply(&mut dst).unwrap();
+        assert_eq!(dst.ctrl.memory, src.ctrl.memory);
+    }
+
+    #[test]
+    fn test_apply_restores_selector_on_error() {
+        let mut ctxt = params_ctxt([0, 0, 0], 1);
+        let config = FeatureConfig {
+            features: vec![gain_setting(2, 30), setting("Missing", 0)],
+        };
+
+        let err = config.apply(&mut ctxt).unwrap_err();
+        assert!(err.to_string().contains("Missing"));
+        assert_eq!(ctxt.ctrl.memory[GAIN_ADDRESS + 2], 30);
+        assert_eq!(ctxt.ctrl.memory[SELECTOR_ADDRESS], 1);
+    }
+
+    #[test]
+    fn test_restore_error_precedence() {
+        // Looking up the feature fails, then restoring `GainSelector` fails.
+        let mut ctxt = params_ctxt([0, 0, 0], 1);
+        ctxt.ctrl.remaining_writes = Some(1);
+        let mut missing = gain_setting(2, 30);
+        missing.name = "Missing".into();
+        let config = FeatureConfig {
+            features: vec![missing],
+        };
+        let err = config.apply(&mut ctxt).unwrap_err();
+        assert!(err.to_string().contains("Missing"));
+        assert_eq!(ctxt.ctrl.memory[SELECTOR_ADDRESS], 2);
+
+        // Only restoring `GainSelector` fails.
+        let mut ctxt = params_ctxt([0, 0, 0], 1);
+        ctxt.ctrl.remaining_writes = Some(2);
+        let config = FeatureConfig {
+            features: vec![gain_setting(2, 30)],
+        };
+        let err = config.apply(&mut ctxt).unwrap_err();
+        assert!(err.to_string().contains("write failed"));
+        assert_eq!(ctxt.ctrl.memory[GAIN_ADDRESS + 2], 30);
+        assert_eq!(ctxt.ctrl.memory[SELECTOR_ADDRESS], 2);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let mut ctxt = params_ctxt([10, 20, 30], 1);
+        let config = FeatureConfig::export(&mut ctxt).unwrap();
+
+        let json = serde_json::to_string(&config).unwrap();
+        // Selectors are omitted for features which aren't selected.
+        assert!(json.contains(r#"{"name":"Width","value":{"Integer":640}}"#));
+        let config2: FeatureConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(config, config2);
+    }
+}
@@ -0,0 +1,855 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains a helper to transfer files from/to the device via `FileAccessControl`
+//! features defined in `SFNC`.
+//!
+//! # Examples
+//! ```rust
+//! # use cameleon::u3v;
+//! # let mut cameras = u3v::enumerate_cameras().unwrap();
+//! # if cameras.is_empty() {
+//! #     return;
+//! # }
+//! # let mut camera = cameras.pop().unwrap();
+//! # camera.open().unwrap();
+//! use cameleon::genapi::FileAccess;
+//!
+//! camera.load_context().unwrap();
+//! let mut params_ctxt = camera.params_ctxt().unwrap();
+//!
+//! // Returns error if the device doesn't support `FileAccessControl`.
+//! let file_access = FileAccess::new(&params_ctxt).unwrap();
+//! for file in file_access.files(&mut params_ctxt).unwrap() {
+//!     println!("{}", file);
+//! }
+//!
+//! // Downloads `UserSet1` and shows the progress.
+//! let data = file_access
+//!     .download(&mut params_ctxt, "UserSet1", |progress| {
+//!         println!("{}/{:?}", progress.transferred, progress.total)
+//!     })
+//!     .unwrap();
+//!
+//! // Uploads the file back to the device, the written data is verified by reading it back.
+//! file_access
+//!     .upload(&mut params_ctxt, "UserSet1", &data, |_| {})
+//!     .unwrap();
+//!
+//! # camera.close().unwrap();
+//! ```
+
+use std::{convert::TryInto, time::Duration};
+
+use cameleon_genapi::{GenApiError, GenApiResult};
+
+use super::{
+    node_kind::{CommandNode, EnumerationNode, IntegerNode, RegisterNode},
+    DeviceControl, GenApiCtxt, ParamsCtxt,
+};
+use crate::cancel::CancellationToken;
+
+/// Maximum duration to wait for a file operation to complete.
+const OPERATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Progress of a file transfer, notified after each chunk is transferred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileTransferProgress {
+    /// Number of bytes transferred so far.
+    pub transferred: usize,
+    /// Total number of bytes to transfer, `None` if the device doesn't provide `FileSize`.
+    pub total: Option<usize>,
+}
+
+/// Open mode of a file, corresponds to entries of `FileOpenMode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OpenMode {
+    Read,
+    Write,
+}
+
+impl OpenMode {
+    fn symbolic(self) -> &'static str {
+        match self {
+            Self::Read => "Read",
+            Self::Write => "Write",
+        }
+    }
+}
+
+/// A helper to transfer files from/to the device, which drives the `FileAccessControl` state
+/// machine of `SFNC`.
+///
+/// Each transfer opens the file selected by `FileSelector`, reads or writes the file chunk by
+/// chunk through `FileAccessBuffer`, then closes the file. The file is closed even if the
+/// transfer fails halfway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileAccess {
+    file_selector: EnumerationNode,
+    operation_selector: EnumerationNode,
+    operation_execute: CommandNode,
+    open_mode: EnumerationNode,
+    access_buffer: RegisterNode,
+    access_offset: IntegerNode,
+    access_length: IntegerNode,
+    operation_status: EnumerationNode,
+    operation_result: IntegerNode,
+    file_size: Option<IntegerNode>,
+    verify_upload: bool,
+}
+
+impl FileAccess {
+    /// Constructs `FileAccess`.
+    ///
+    /// Returns [`GenApiError::InvalidNode`] if the context lacks a node required for
+    /// `FileAccessControl`.
+    pub fn new<Ctrl, Ctxt>(ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<Self>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        macro_rules! node {
+            ($name:expr, $downcast:ident) => {
+                ctxt.node($name)
+                    .and_then(|node| node.$downcast(ctxt))
+                    .ok_or_else(|| {
+                        GenApiError::InvalidNode(
+                            format!("`{}` is required for file access", $name).into(),
+                        )
+                    })?
+            };
+        }
+
+        Ok(Self {
+            file_selector: node!("FileSelector", as_enumeration),
+            operation_selector: node!("FileOperationSelector", as_enumeration),
+            operation_execute: node!("FileOperationExecute", as_command),
+            open_mode: node!("FileOpenMode", as_enumeration),
+            access_buffer: node!("FileAccessBuffer", as_register),
+            access_offset: node!("FileAccessOffset", as_integer),
+            access_length: node!("FileAccessLength", as_integer),
+            operation_status: node!("FileOperationStatus", as_enumeration),
+            operation_result: node!("FileOperationResult", as_integer),
+            file_size: ctxt.node("FileSize").and_then(|node| node.as_integer(ctxt)),
+            verify_upload: true,
+        })
+    }
+
+    /// Sets whether [`Self::upload`] reads the uploaded file back to verify its content.
+    ///
+    /// Verification is enabled by default, disable it for write-only files, e.g. firmware
+    /// images that can't be read back.
+    #[must_use]
+    pub fn verify_upload(mut self, verify: bool) -> Self {
+        self.verify_upload = verify;
+        self
+    }
+
+    /// Returns names of files that are available on the device.
+    pub fn files<Ctrl, Ctxt>(&self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<Vec<String>>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let mut files = vec![];
+        for entry in self.file_selector.entries(ctxt) {
+            if entry.is_implemented(ctxt)? && entry.is_available(ctxt)? {
+                files.push(entry.symbolic(ctxt).to_string());
+            }
+        }
+        Ok(files)
+    }
+
+    /// Returns the size of the file, `None` if the device doesn't provide `FileSize`.
+    pub fn file_size<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        file: &str,
+    ) -> GenApiResult<Option<usize>>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        self.file_selector.set_entry_by_symbolic(ctxt, file)?;
+        self.current_file_size(ctxt)
+    }
+
+    /// Downloads the file from the device.
+    ///
+    /// `progress` is called every time a chunk is read. If the device provides `FileSize`, the
+    /// length of the downloaded data is checked against it.
+    pub fn download<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        file: &str,
+        progress: impl FnMut(FileTransferProgress),
+    ) -> GenApiResult<Vec<u8>>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        self.download_with_cancel(ctxt, file, progress, &CancellationToken::new())
+    }
+
+    /// Same as [`Self::download`], but aborts the transfer when `token` is cancelled.
+    ///
+    /// Returns [`ControlError::Cancelled`](crate::ControlError::Cancelled) wrapped in
+    /// [`GenApiError::Device`] on cancellation, the file is closed before returning.
+    pub fn download_with_cancel<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        file: &str,
+        mut progress: impl FnMut(FileTransferProgress),
+        token: &CancellationToken,
+    ) -> GenApiResult<Vec<u8>>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        token.check_genapi()?;
+        self.file_selector.set_entry_by_symbolic(ctxt, file)?;
+        let total = self.current_file_size(ctxt)?;
+
+        self.open(ctxt, OpenMode::Read, token)?;
+        let res = self.read_file(ctxt, total, &mut progress, token);
+        let close_res = self.close(ctxt);
+        let data = res?;
+        close_res?;
+
+        match total {
+            Some(total) if total != data.len() => Err(GenApiError::InvalidData(
+                format!(
+                    "`{}` has {} bytes, but {} bytes are downloaded",
+                    file,
+                    total,
+                    data.len()
+                )
+                .into(),
+            )),
+            _ => Ok(data),
+        }
+    }
+
+    /// Uploads `data` to the file on the device.
+    ///
+    /// `progress` is called every time a chunk is written. Unless disabled by
+    /// [`Self::verify_upload`], the file is downloaded again after the upload and compared with
+    /// `data`.
+    pub fn upload<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        file: &str,
+        data: &[u8],
+        progress: impl FnMut(FileTransferProgress),
+    ) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        self.upload_with_cancel(ctxt, file, data, progress, &CancellationToken::new())
+    }
+
+    /// Same as [`Self::upload`], but aborts the transfer, including the verification, when
+    /// `token` is cancelled.
+    ///
+    /// Returns [`ControlError::Cancelled`](crate::ControlError::Cancelled) wrapped in
+    /// [`GenApiError::Device`] on cancellation, the file is closed before returning. Note that
+    /// the file may be partially written.
+    pub fn upload_with_cancel<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        file: &str,
+        data: &[u8],
+        mut progress: impl FnMut(FileTransferProgress),
+        token: &CancellationToken,
+    ) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        token.check_genapi()?;
+        self.file_selector.set_entry_by_symbolic(ctxt, file)?;
+
+        self.open(ctxt, OpenMode::Write, token)?;
+        let res = self.write_file(ctxt, data, &mut progress, token);
+        let close_res = self.close(ctxt);
+        res?;
+        close_res?;
+
+        if self.verify_upload {
+            let written = self.download_with_cancel(ctxt, file, |_| {}, token)?;
+            if written.get(..data.len()) != Some(data) {
+                return Err(GenApiError::InvalidData(
+                    format!("content of `{}` differs from the uploaded data", file).into(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_file<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        total: Option<usize>,
+        progress: &mut impl FnMut(FileTransferProgress),
+        token: &CancellationToken,
+    ) -> GenApiResult<Vec<u8>>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let mut chunk = vec![0; self.buffer_length(ctxt)?];
+        let mut data = Vec::with_capacity(total.unwrap_or_default());
+
+        loop {
+            let request_len = match total {
+                Some(total) => (total - data.len()).min(chunk.len()),
+                None => chunk.len(),
+            };
+            if request_len == 0 {
+                break;
+            }
+
+            token.check_genapi()?;
+            self.access_offset.set_value(ctxt, to_i64(data.len())?)?;
+            self.access_length.set_value(ctxt, to_i64(request_len)?)?;
+            let read_len = self.execute(ctxt, "Read", token)?;
+            if read_len > request_len {
+                return Err(GenApiError::InvalidData(
+                    "the device read more bytes than requested".into(),
+                ));
+            }
+
+            self.access_buffer.read(ctxt, &mut chunk)?;
+            data.extend_from_slice(&chunk[..read_len]);
+            progress(FileTransferProgress {
+                transferred: data.len(),
+                total,
+            });
+
+            // Reached the end of the file.
+            if read_len < request_len {
+                break;
+            }
+        }
+
+        Ok(data)
+    }
+
+    fn write_file<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        data: &[u8],
+        progress: &mut impl FnMut(FileTransferProgress),
+        token: &CancellationToken,
+    ) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let mut buf = vec![0; self.buffer_length(ctxt)?];
+        let mut transferred = 0;
+
+        for chunk in data.chunks(buf.len()) {
+            token.check_genapi()?;
+            // The register must be written as a whole, so pad the last chunk.
+            buf[..chunk.len()].copy_from_slice(chunk);
+            buf[chunk.len()..].iter_mut().for_each(|b| *b = 0);
+            self.access_buffer.write(ctxt, &buf)?;
+
+            self.access_offset.set_value(ctxt, to_i64(transferred)?)?;
+            self.access_length.set_value(ctxt, to_i64(chunk.len())?)?;
+            let written_len = self.execute(ctxt, "Write", token)?;
+            if written_len != chunk.len() {
+                return Err(GenApiError::InvalidData(
+                    format!(
+                        "the device wrote {} bytes, but {} bytes are requested",
+                        written_len,
+                        chunk.len()
+                    )
+                    .into(),
+                ));
+            }
+
+            transferred += chunk.len();
+            progress(FileTransferProgress {
+                transferred,
+                total: Some(data.len()),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn open<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        mode: OpenMode,
+        token: &CancellationToken,
+    ) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        self.open_mode
+            .set_entry_by_symbolic(ctxt, mode.symbolic())?;
+        self.execute(ctxt, "Open", token).map(|_| ())
+    }
+
+    fn close<Ctrl, Ctxt>(&self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        // Closing is not cancellable so that the file is not left opened on cancellation.
+        self.execute(ctxt, "Close", &CancellationToken::new())
+            .map(|_| ())
+    }
+
+    /// Executes the operation, then returns `FileOperationResult` if the operation succeeded.
+    ///
+    /// Returns [`ControlError::Timeout`](crate::ControlError::Timeout) wrapped in
+    /// [`GenApiError::Device`] if the operation doesn't complete within [`OPERATION_TIMEOUT`].
+    fn execute<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        operation: &str,
+        token: &CancellationToken,
+    ) -> GenApiResult<usize>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        self.operation_selector
+            .set_entry_by_symbolic(ctxt, operation)?;
+        self.operation_execute
+            .execute_and_wait_with_cancel(ctxt, OPERATION_TIMEOUT, token)?;
+
+        let status = self.operation_status.current_entry(ctxt)?;
+        if status.symbolic(ctxt) != "Success" {
+            return Err(GenApiError::InvalidData(
+                format!(
+                    "file operation `{}` failed with status `{}`",
+                    operation,
+                    status.symbolic(ctxt)
+                )
+                .into(),
+            ));
+        }
+
+        let result = self.operation_result.value(ctxt)?;
+        result.try_into().map_err(|_| {
+            GenApiError::InvalidData(format!("invalid file operation result: {}", result).into())
+        })
+    }
+
+    fn current_file_size<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+    ) -> GenApiResult<Option<usize>>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        match self.file_size {
+            Some(node) if node.is_readable(ctxt)? => {
+                let size = node.value(ctxt)?;
+                size.try_into().map(Some).map_err(|_| {
+                    GenApiError::InvalidData(format!("invalid file size: {}", size).into())
+                })
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn buffer_length<Ctrl, Ctxt>(&self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<usize>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let len = self.access_buffer.length(ctxt)?;
+        match len.try_into() {
+            Ok(len) if len > 0 => Ok(len),
+            _ => Err(GenApiError::InvalidNode(
+                format!("invalid length of `FileAccessBuffer`: {}", len).into(),
+            )),
+        }
+    }
+}
+
+fn to_i64(value: usize) -> GenApiResult<i64> {
+    value
+        .try_into()
+        .map_err(|_| GenApiError::InvalidData(format!("{} is too large", value).into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{
+            tests::{MemoryControl, IMMEDIATE_VALUE_XML},
+            DefaultGenApiCtxt, FromXml,
+        },
+        *,
+    };
+    use crate::ControlResult;
+
+    const FILE_SELECTOR: usize = 0x00;
+    const OPERATION_SELECTOR: usize = 0x04;
+    const OPERATION_EXECUTE: usize = 0x08;
+    const OPEN_MODE: usize = 0x0c;
+    const ACCESS_OFFSET: usize = 0x10;
+    const ACCESS_LENGTH: usize = 0x14;
+    const OPERATION_STATUS: usize = 0x18;
+    const OPERATION_RESULT: usize = 0x1c;
+    const FILE_SIZE: usize = 0x20;
+    const ACCESS_BUFFER: usize = 0x40;
+    const BUFFER_LEN: usize = 4;
+
+    const OPERATIONS: [&str; 4] = ["Open", "Close", "Read", "Write"];
+
+    fn xml(has_file_size: bool) -> String {
+        let int_reg = |name: &str, address: usize, access_mode: &str| {
+            format!(
+                r#"
+                <IntReg Name="{}">
+                    <Address>{}</Address>
+                    <Length>4</Length>
+                    <AccessMode>{}</AccessMode>
+                    <pPort>Device</pPort>
+                    <Cachable>NoCache</Cachable>
+                </IntReg>"#,
+                name, address, access_mode
+            )
+        };
+        let enumeration = |name: &str, entries: &[&str]| {
+            let entries: String = entries
+                .iter()
+                .enumerate()
+                .map(|(i, entry)| {
+                    format!(
+                        r#"<EnumEntry Name="{}"><Value>{}</Value></EnumEntry>"#,
+                        entry, i
+                    )
+                })
+                .collect();
+            format!(
+                r#"
+                <Enumeration Name="{0}">
+                    {1}
+                    <pValue>{0}Reg</pValue>
+                </Enumeration>"#,
+                name, entries
+            )
+        };
+
+        let mut nodes = vec![
+            enumeration("FileSelector", &["UserSet1", "UserSet2"]),
+            int_reg("FileSelectorReg", FILE_SELECTOR, "RW"),
+            enumeration("FileOperationSelector", &OPERATIONS),
+            int_reg("FileOperationSelectorReg", OPERATION_SELECTOR, "RW"),
+            r#"
+                <Command Name="FileOperationExecute">
+                    <pValue>FileOperationExecuteReg</pValue>
+                    <CommandValue>1</CommandValue>
+                </Command>"#
+                .to_string(),
+            int_reg("FileOperationExecuteReg", OPERATION_EXECUTE, "RW"),
+            enumeration("FileOpenMode", &["Read", "Write"]),
+            int_reg("FileOpenModeReg", OPEN_MODE, "RW"),
+            int_reg("FileAccessOffset", ACCESS_OFFSET, "RW"),
+            int_reg("FileAccessLength", ACCESS_LENGTH, "RW"),
+            enumeration("FileOperationStatus", &["Success", "Failure"]),
+            int_reg("FileOperationStatusReg", OPERATION_STATUS, "RO"),
+            int_reg("FileOperationResult", OPERATION_RESULT, "RO"),
+            format!(
+                r#"
+                <Register Name="FileAccessBuffer">
+                    <Address>{}</Address>
+                    <Length>{}</Length>
+                    <AccessMode>RW</AccessMode>
+                    <pPort>Device</pPort>
+                    <Cachable>NoCache</Cachable>
+                </Register>"#,
+                ACCESS_BUFFER, BUFFER_LEN
+            ),
+            r#"<Port Name="Device"/>"#.to_string(),
+        ];
+        if has_file_size {
+            nodes.push(int_reg("FileSize", FILE_SIZE, "RO"));
+        }
+
+        format!(
+            r#"
+            <RegisterDescription
+              ModelName="CameleonModel"
+              VendorName="CameleonVendor"
+              StandardNameSpace="None"
+              SchemaMajorVersion="1"
+              SchemaMinorVersion="1"
+              SchemaSubMinorVersion="0"
+              MajorVersion="1"
+              MinorVersion="0"
+              SubMinorVersion="0"
+              ProductGuid="01234567-0123-0123-0123-0123456789ab"
+              VersionGuid="76543210-3210-3210-3210-ba9876543210">
+                {}
+            </RegisterDescription>"#,
+            nodes.concat()
+        )
+    }
+
+    /// A device that emulates `FileAccessControl` on top of [`MemoryControl`].
+    #[derive(Debug, Default)]
+    struct FileControl {
+        memory: MemoryControl,
+        files: [Vec<u8>; 2],
+        /// `(operation, offset, length)` of executed operations.
+        operations: Vec<(&'static str, usize, usize)>,
+        /// The operation that finishes with `Failure` status.
+        failing_operation: Option<&'static str>,
+        /// Flips the first written byte of each chunk if `true`.
+        corrupt_writes: bool,
+    }
+
+    impl FileControl {
+        fn new(files: [Vec<u8>; 2]) -> Self {
+            Self {
+                memory: MemoryControl::new(vec![0; ACCESS_BUFFER + BUFFER_LEN]),
+                files,
+                ..Self::default()
+            }
+        }
+
+        fn reg(&self, address: usize) -> usize {
+            let mut bytes = [0; 4];
+            bytes.copy_from_slice(&self.memory.memory[address..address + 4]);
+            u32::from_le_bytes(bytes) as usize
+        }
+
+        fn set_reg(&mut self, address: usize, value: usize) {
+            self.memory.memory[address..address + 4].copy_from_slice(&(value as u32).to_le_bytes());
+        }
+
+        fn execute_operation(&mut self) {
+            let operation = OPERATIONS[self.reg(OPERATION_SELECTOR)];
+            let offset = self.reg(ACCESS_OFFSET);
+            let length = self.reg(ACCESS_LENGTH);
+            self.operations.push((operation, offset, length));
+
+            let file = &mut self.files[self.reg(FILE_SELECTOR)];
+            let result = match operation {
+                "Read" => {
+                    let read = &file[offset.min(file.len())..(offset + length).min(file.len())];
+                    let buffer = &mut self.memory.memory[ACCESS_BUFFER..];
+                    buffer[..read.len()].copy_from_slice(read);
+                    read.len()
+                }
+                "Write" => {
+                    let mut chunk =
+                        self.memory.memory[ACCESS_BUFFER..ACCESS_BUFFER + length].to_vec();
+                    if self.corrupt_writes {
+                        chunk[0] = !chunk[0];
+                    }
+                    file.resize(file.len().max(offset + length), 0);
+                    file[offset..offset + length].copy_from_slice(&chunk);
+                    length
+                }
+                _ => 0,
+            };
+            let file_size = file.len();
+
+            let status = if self.failing_operation == Some(operation) {
+                1
+            } else {
+                0
+            };
+            self.set_reg(OPERATION_STATUS, status);
+            self.set_reg(OPERATION_RESULT, result);
+            self.set_reg(FILE_SIZE, file_size);
+            self.set_reg(OPERATION_EXECUTE, 0);
+        }
+    }
+
+    impl DeviceControl for FileControl {
+        fn open(&mut self) -> ControlResult<()> {
+            Ok(())
+        }
+
+        fn close(&mut self) -> ControlResult<()> {
+            Ok(())
+        }
+
+        fn is_opened(&self) -> bool {
+            true
+        }
+
+        fn read(&mut self, address: u64, buf: &mut [u8]) -> ControlResult<()> {
+            if address as usize == FILE_SIZE {
+                let file_size = self.files[self.reg(FILE_SELECTOR)].len();
+                self.set_reg(FILE_SIZE, file_size);
+            }
+            self.memory.read(address, buf)
+        }
+
+        fn write(&mut self, address: u64, data: &[u8]) -> ControlResult<()> {
+            self.memory.write(address, data)?;
+            if address as usize == OPERATION_EXECUTE && self.reg(OPERATION_EXECUTE) == 1 {
+                self.execute_operation();
+            }
+            Ok(())
+        }
+
+        fn genapi(&mut self) -> ControlResult<String> {
+            self.memory.genapi()
+        }
+
+        fn enable_streaming(&mut self) -> ControlResult<()> {
+            Ok(())
+        }
+
+        fn disable_streaming(&mut self) -> ControlResult<()> {
+            Ok(())
+        }
+    }
+
+    fn params_ctxt(
+        files: [Vec<u8>; 2],
+        has_file_size: bool,
+    ) -> ParamsCtxt<FileControl, DefaultGenApiCtxt> {
+        ParamsCtxt {
+            ctrl: FileControl::new(files),
+            ctxt: DefaultGenApiCtxt::from_xml(&xml(has_file_size)).unwrap(),
+        }
+    }
+
+    fn download(
+        ctxt: &mut ParamsCtxt<FileControl, DefaultGenApiCtxt>,
+        file: &str,
+    ) -> (GenApiResult<Vec<u8>>, Vec<FileTransferProgress>) {
+        let file_access = FileAccess::new(ctxt).unwrap();
+        let mut progress = vec![];
+        let res = file_access.download(ctxt, file, |p| progress.push(p));
+        (res, progress)
+    }
+
+    #[test]
+    fn test_files() {
+        let mut ctxt = params_ctxt(Default::default(), true);
+        let file_access = FileAccess::new(&ctxt).unwrap();
+        assert_eq!(
+            file_access.files(&mut ctxt).unwrap(),
+            vec!["UserSet1", "UserSet2"]
+        );
+        assert!(FileAccess::new(&ParamsCtxt {
+            ctrl: FileControl::default(),
+            ctxt: DefaultGenApiCtxt::from_xml(&IMMEDIATE_VALUE_XML).unwrap(),
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_download_in_chunks() {
+        let data: Vec<u8> = (0..10).collect();
+        let mut ctxt = params_ctxt([vec![], data.clone()], true);
+
+        let (res, progress) = download(&mut ctxt, "UserSet2");
+        assert_eq!(res.unwrap(), data);
+        let transferred: Vec<_> = progress.iter().map(|p| p.transferred).collect();
+        assert_eq!(transferred, vec![4, 8, 10]);
+        assert!(progress.iter().all(|p| p.total == Some(10)));
+        assert_eq!(
+            ctxt.ctrl.operations,
+            vec![
+                ("Open", 0, 0),
+                ("Read", 0, 4),
+                ("Read", 4, 4),
+                ("Read", 8, 2),
+                ("Close", 8, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_download_detects_eof_without_file_size() {
+        // A file whose size is a multiple of the buffer length needs an empty read to detect EOF.
+        let data: Vec<u8> = (0..8).collect();
+        let mut ctxt = params_ctxt([data.clone(), vec![]], false);
+        let (res, progress) = download(&mut ctxt, "UserSet1");
+        assert_eq!(res.unwrap(), data);
+        let transferred: Vec<_> = progress.iter().map(|p| p.transferred).collect();
+        assert_eq!(transferred, vec![4, 8, 8]);
+        assert!(progress.iter().all(|p| p.total.is_none()));
+
+        let data: Vec<u8> = (0..6).collect();
+        let mut ctxt = params_ctxt([data.clone(), vec![]], false);
+        let (res, progress) = download(&mut ctxt, "UserSet1");
+        assert_eq!(res.unwrap(), data);
+        assert_eq!(progress.len(), 2);
+    }
+
+    #[test]
+    fn test_upload_pads_last_chunk() {
+        let data: Vec<u8> = (1..=6).collect();
+        let mut ctxt = params_ctxt(Default::default(), true);
+        // Skip verification so that reading the file back doesn't overwrite the buffer.
+        let file_access = FileAccess::new(&ctxt).unwrap().verify_upload(false);
+        let mut transferred = vec![];
+        file_access
+            .upload(&mut ctxt, "UserSet1", &data, |p| {
+                transferred.push(p.transferred)
+            })
+            .unwrap();
+
+        assert_eq!(transferred, vec![4, 6]);
+        assert_eq!(ctxt.ctrl.files[0], data);
+        // The stale bytes of the first chunk are cleared.
+        assert_eq!(&ctxt.ctrl.memory.memory[ACCESS_BUFFER..], &[5, 6, 0, 0][..]);
+        let writes: Vec<_> = ctxt
+            .ctrl
+            .operations
+            .iter()
+            .filter(|(op, ..)| *op == "Write")
+            .collect();
+        assert_eq!(writes, vec![&("Write", 0, 4), &("Write", 4, 2)]);
+    }
+
+    #[test]
+    fn test_verify_upload() {
+        let data: Vec<u8> = (1..=6).collect();
+        let mut ctxt = params_ctxt(Default::default(), true);
+        ctxt.ctrl.corrupt_writes = true;
+        let file_access = FileAccess::new(&ctxt).unwrap();
+
+        assert!(matches!(
+            file_access.upload(&mut ctxt, "UserSet1", &data, |_| {}),
+            Err(GenApiError::InvalidData(..))
+        ));
+        assert!(ctxt.ctrl.operations.iter().any(|(op, ..)| *op == "Read"));
+
+        ctxt.ctrl.operations.clear();
+        file_access
+            .verify_upload(false)
+            .upload(&mut ctxt, "UserSet1", &data, |_| {})
+            .unwrap();
+        assert!(ctxt.ctrl.operations.iter().all(|(op, ..)| *op != "Read"));
+    }
+
+    #[test]
+    fn test_operation_status() {
+        let mut ctxt = params_ctxt([vec![0; 10], vec![]], true);
+        ctxt.ctrl.failing_operation = Some("Read");
+
+        let (res, progress) = download(&mut ctxt, "UserSet1");
+        match res {
+            Err(GenApiError::InvalidData(msg)) => {
+                assert!(msg.contains("`Read` failed with status `Failure`"))
+            }
+            res => panic!("unexpected result: {:?}", res),
+        }
+        assert!(progress.is_empty());
+        // The file is closed even if the transfer fails.
+        assert_eq!(ctxt.ctrl.operations.last().unwrap().0, "Close");
+    }
+}
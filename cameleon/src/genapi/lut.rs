@@ -0,0 +1,432 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains a helper to upload a user lookup table to the device via `LUTControl`
+//! features defined in `SFNC`.
+//!
+//! # Examples
+//! ```rust
+//! # use cameleon::u3v;
+//! # let mut cameras = u3v::enumerate_cameras().unwrap();
+//! # if cameras.is_empty() {
+//! #     return;
+//! # }
+//! # let mut camera = cameras.pop().unwrap();
+//! # camera.open().unwrap();
+//! use cameleon::genapi::LutAccess;
+//!
+//! camera.load_context().unwrap();
+//! let mut params_ctxt = camera.params_ctxt().unwrap();
+//!
+//! // Returns error if the device doesn't support `LUTControl`.
+//! let lut = LutAccess::new(&params_ctxt).unwrap();
+//! lut.select(&mut params_ctxt, "Luminance").unwrap();
+//!
+//! // Uploads a table which inverts pixel values.
+//! let len = lut.len(&mut params_ctxt).unwrap();
+//! let (min, max) = lut.value_range(&mut params_ctxt).unwrap();
+//! let values: Vec<_> = (0..len as i64)
+//!     .map(|i| max - (max - min) * i / (len as i64 - 1))
+//!     .collect();
+//! lut.upload(&mut params_ctxt, &values).unwrap();
+//!
+//! // Or uploads a gamma curve, then enables the table.
+//! lut.upload_gamma(&mut params_ctxt, 0.45).unwrap();
+//! lut.set_enabled(&mut params_ctxt, true).unwrap();
+//!
+//! # camera.close().unwrap();
+//! ```
+
+use cameleon_genapi::{elem_type::Endianness, GenApiError, GenApiResult};
+
+use super::{
+    node_kind::{BooleanNode, EnumerationNode, IntegerNode, RegisterNode},
+    DeviceControl, GenApiCtxt, ParamsCtxt,
+};
+
+/// Default size in bytes of a chunk written to `LUTValueAll` at once.
+const DEFAULT_CHUNK_SIZE: usize = 512;
+
+/// A helper to upload a user lookup table of the device.
+///
+/// If the device has `LUTValueAll`, the whole table is written to the register in chunks,
+/// which are batched by [`DeviceControl::write_stacked`]. Otherwise, each entry is written by
+/// selecting it with `LUTIndex` and writing `LUTValue`, which takes a few transactions per entry.
+///
+/// `LUTEnable` is turned off while the table is written so that no image is processed by a
+/// partially written table, then it's restored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LutAccess {
+    selector: Option<EnumerationNode>,
+    enable: Option<BooleanNode>,
+    index: Option<IntegerNode>,
+    value: Option<IntegerNode>,
+    value_all: Option<RegisterNode>,
+    chunk_size: usize,
+    endianness: Endianness,
+    verify_upload: bool,
+}
+
+impl LutAccess {
+    /// Constructs `LutAccess`.
+    ///
+    /// Returns [`GenApiError::InvalidNode`] if the context has neither `LUTIndex` and
+    /// `LUTValue` nor `LUTValueAll`.
+    pub fn new<Ctrl, Ctxt>(ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<Self>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        macro_rules! node {
+            ($name:expr, $downcast:ident) => {
+                ctxt.node($name).and_then(|node| node.$downcast(ctxt))
+            };
+        }
+
+        let lut = Self {
+            selector: node!("LUTSelector", as_enumeration),
+            enable: node!("LUTEnable", as_boolean),
+            index: node!("LUTIndex", as_integer),
+            value: node!("LUTValue", as_integer),
+            value_all: node!("LUTValueAll", as_register),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            endianness: Endianness::LE,
+            verify_upload: true,
+        };
+        if lut.value_all.is_none() && (lut.index.is_none() || lut.value.is_none()) {
+            return Err(GenApiError::InvalidNode(
+                "`LUTIndex` and `LUTValue`, or `LUTValueAll` is required for LUT".into(),
+            ));
+        }
+        Ok(lut)
+    }
+
+    /// Sets the size in bytes of a chunk written to `LUTValueAll` at once.
+    ///
+    /// # Panics
+    /// Panics if `chunk_size` is zero.
+    #[must_use]
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be larger than zero");
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Sets the byte order of entries in `LUTValueAll`.
+    ///
+    /// The layout of `LUTValueAll` is device specific. Entries are assumed to be packed
+    /// integers in little endian by default, set [`Endianness::BE`] for devices which store
+    /// entries in big endian, e.g. some `GigE Vision` devices.
+    #[must_use]
+    pub fn endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    /// Sets whether [`Self::upload`] reads the uploaded table back to verify its content.
+    ///
+    /// Verification is enabled by default.
+    #[must_use]
+    pub fn verify_upload(mut self, verify: bool) -> Self {
+        self.verify_upload = verify;
+        self
+    }
+
+    /// Selects the table by the symbolic name of the entry of `LUTSelector`, e.g. `Luminance`
+    /// or `Red`.
+    pub fn select<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        name: &str,
+    ) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let selector = self
+            .selector
+            .ok_or_else(|| GenApiError::InvalidNode("missing `LUTSelector`".into()))?;
+        selector.set_entry_by_symbolic(ctxt, name)
+    }
+
+    /// Returns the number of entries of the selected table, which is the range of `LUTIndex`.
+    pub fn len<Ctrl, Ctxt>(&self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<usize>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let index = self.index.ok_or_else(|| {
+            GenApiError::InvalidNode("`LUTIndex` is required to get the size of LUT".into())
+        })?;
+        let len = index.max(ctxt)? - index.min(ctxt)? + 1;
+        Ok(len.max(0) as usize)
+    }
+
+    /// Returns the minimum and maximum of values of entries, which is the range of `LUTValue`.
+    pub fn value_range<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+    ) -> GenApiResult<(i64, i64)>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let value = self.value.ok_or_else(|| {
+            GenApiError::InvalidNode("`LUTValue` is required to get the range of LUT".into())
+        })?;
+        Ok((value.min(ctxt)?, value.max(ctxt)?))
+    }
+
+    /// Turns `LUTEnable` of the selected table on or off.
+    pub fn set_enabled<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        enabled: bool,
+    ) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let enable = self
+            .enable
+            .ok_or_else(|| GenApiError::InvalidNode("missing `LUTEnable`".into()))?;
+        enable.set_value(ctxt, enabled)
+    }
+
+    /// Uploads `values` to the selected table, `values[i]` is the output for the input `i`.
+    ///
+    /// Returns [`GenApiError::InvalidData`] if the number of `values` differs from
+    /// [`Self::len`], a value is out of [`Self::value_range`], or the table read back differs
+    /// from `values`.
+    pub fn upload<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        values: &[i64],
+    ) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        self.validate(ctxt, values)?;
+
+        let was_enabled = match self.enable {
+            Some(enable) if enable.is_readable(ctxt)? && enable.value(ctxt)? => {
+                enable.set_value(ctxt, false)?;
+                true
+            }
+            _ => false,
+        };
+        let res = match self.value_all_entry_size(ctxt, values.len())? {
+            Some((value_all, entry_size)) => self.write_all(ctxt, value_all, values, entry_size),
+            None => self.write_each(ctxt, values),
+        };
+        let restore_res = if was_enabled {
+            self.set_enabled(ctxt, true)
+        } else {
+            Ok(())
+        };
+        res?;
+        restore_res
+    }
+
+    /// Uploads a gamma curve to the selected table.
+    ///
+    /// The output for the input `i` is `min + (max - min) * (i / (len - 1)) ^ gamma`, where
+    /// `len` is [`Self::len`] and `min` and `max` are [`Self::value_range`]. i.e. `gamma` less
+    /// than `1.0` brightens dark pixels.
+    pub fn upload_gamma<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        gamma: f64,
+    ) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let len = self.len(ctxt)?;
+        let (min, max) = self.value_range(ctxt)?;
+        self.upload(ctxt, &gamma_values(len, min, max, gamma))
+    }
+
+    fn validate<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        values: &[i64],
+    ) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        if self.index.is_some() {
+            let len = self.len(ctxt)?;
+            if values.len() != len {
+                return Err(GenApiError::InvalidData(
+                    format!("LUT has {} entries, but {} values given", len, values.len()).into(),
+                ));
+            }
+        }
+        if self.value.is_some() {
+            let (min, max) = self.value_range(ctxt)?;
+            if let Some(i) = values.iter().position(|v| *v < min || *v > max) {
+                return Err(GenApiError::InvalidData(
+                    format!(
+                        "value {} of entry {} is out of {}..={}",
+                        values[i], i, min, max
+                    )
+                    .into(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `LUTValueAll` and the size of its entries if the table can be written through it.
+    fn value_all_entry_size<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        len: usize,
+    ) -> GenApiResult<Option<(RegisterNode, usize)>>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let value_all = match self.value_all {
+            Some(value_all) if len > 0 => value_all,
+            _ => return Ok(None),
+        };
+        let length = value_all.length(ctxt)? as usize;
+        let entry_size = length / len;
+        if entry_size * len == length && matches!(entry_size, 1 | 2 | 4 | 8) {
+            Ok(Some((value_all, entry_size)))
+        } else if self.index.is_some() && self.value.is_some() {
+            Ok(None)
+        } else {
+            Err(GenApiError::InvalidData(
+                format!("{} values don't fit `LUTValueAll` of {} bytes", len, length).into(),
+            ))
+        }
+    }
+
+    fn write_all<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        value_all: RegisterNode,
+        values: &[i64],
+        entry_size: usize,
+    ) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let address = value_all.address(ctxt)? as u64;
+        let data = encode_entries(values, entry_size, self.endianness);
+        let chunk_address = |i: usize| address + (i * self.chunk_size) as u64;
+
+        let chunks: Vec<_> = data
+            .chunks(self.chunk_size)
+            .enumerate()
+            .map(|(i, chunk)| (chunk_address(i), chunk))
+            .collect();
+        let res = ctxt.ctrl.write_stacked(&chunks);
+        // The register is written bypassing the context, so cached values may be stale.
+        ctxt.ctxt.clear_cache();
+        res.map_err(|e| GenApiError::Device(Box::new(e)))?;
+
+        if self.verify_upload {
+            let mut written = vec![0; data.len()];
+            let mut chunks: Vec<_> = written
+                .chunks_mut(self.chunk_size)
+                .enumerate()
+                .map(|(i, chunk)| (chunk_address(i), chunk))
+                .collect();
+            ctxt.ctrl
+                .read_stacked(&mut chunks)
+                .map_err(|e| GenApiError::Device(Box::new(e)))?;
+            if let Some(pos) = written.iter().zip(&data).position(|(a, b)| a != b) {
+                return Err(mismatch(pos / entry_size));
+            }
+        }
+        Ok(())
+    }
+
+    fn write_each<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        values: &[i64],
+    ) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        // `value_all_entry_size` falls back to this method only if both nodes exist.
+        let (index, value) = (self.index.unwrap(), self.value.unwrap());
+        let min = index.min(ctxt)?;
+
+        for (i, v) in values.iter().enumerate() {
+            index.set_value(ctxt, min + i as i64)?;
+            value.set_value(ctxt, *v)?;
+        }
+
+        if self.verify_upload {
+            for (i, v) in values.iter().enumerate() {
+                index.set_value(ctxt, min + i as i64)?;
+                // `LUTValue` may not be invalidated by `LUTIndex`, so read it from the device.
+                ctxt.ctxt.clear_cache();
+                if value.value(ctxt)? != *v {
+                    return Err(mismatch(i));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn mismatch(index: usize) -> GenApiError {
+    GenApiError::InvalidData(format!("LUT entry {} differs from the uploaded value", index).into())
+}
+
+/// Packs `values` into integers of `entry_size` bytes.
+fn encode_entries(values: &[i64], entry_size: usize, endianness: Endianness) -> Vec<u8> {
+    let mut data = Vec::with_capacity(values.len() * entry_size);
+    for value in values {
+        match endianness {
+            Endianness::LE => data.extend_from_slice(&value.to_le_bytes()[..entry_size]),
+            Endianness::BE => data.extend_from_slice(&value.to_be_bytes()[8 - entry_size..]),
+        }
+    }
+    data
+}
+
+fn gamma_values(len: usize, min: i64, max: i64, gamma: f64) -> Vec<i64> {
+    let last = len.saturating_sub(1).max(1) as f64;
+    let range = (max - min) as f64;
+    (0..len)
+        .map(|i| min + (range * (i as f64 / last).powf(gamma)).round() as i64)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_entries() {
+        let values = [0x1234, 0xABCD];
+        assert_eq!(
+            encode_entries(&values, 2, Endianness::LE),
+            [0x34, 0x12, 0xCD, 0xAB]
+        );
+        assert_eq!(
+            encode_entries(&values, 4, Endianness::BE),
+            [0, 0, 0x12, 0x34, 0, 0, 0xAB, 0xCD]
+        );
+    }
+
+    #[test]
+    fn test_gamma_values() {
+        assert_eq!(gamma_values(5, 0, 100, 1.0), [0, 25, 50, 75, 100]);
+        assert_eq!(gamma_values(3, 0, 100, 2.0), [0, 25, 100]);
+        assert_eq!(gamma_values(3, 0, 100, 0.5), [0, 71, 100]);
+        assert_eq!(gamma_values(1, 0, 100, 0.5), [0]);
+    }
+}
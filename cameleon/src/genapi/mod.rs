@@ -38,12 +38,24 @@
 //! # camera.close().unwrap();
 //! ```
 
+mod config;
+mod file_access;
+mod lut;
 mod node_kind;
+mod selector;
+mod sequencer;
+mod store_cache;
 
+pub use config::{FeatureConfig, FeatureSetting, SelectorSetting};
+pub use file_access::{FileAccess, FileTransferProgress};
+pub use lut::LutAccess;
 pub use node_kind::{
-    BooleanNode, CategoryNode, CommandNode, EnumEntryNode, EnumerationNode, FloatNode, IntegerNode,
-    Node, PortNode, RegisterNode, StringNode,
+    BooleanNode, CategoryNode, CommandNode, EnumEntryNode, EnumerationNode, FeatureValue,
+    FloatNode, IntegerNode, Node, PortNode, RegisterNode, StringNode,
 };
+pub use selector::SelectorValue;
+pub use sequencer::{SequencerBuilder, SequencerPath, SequencerSet};
+pub use store_cache::{FromNodeStoreCache, NodeStoreCache};
 
 use std::{
     convert::TryInto,
@@ -51,17 +63,21 @@ use std::{
 };
 
 use auto_impl::auto_impl;
-use cameleon_genapi::{builder::GenApiBuilder, store};
+use cameleon_genapi::{builder::GenApiBuilder, store, GenApiResult};
 
-use super::{ControlError, ControlResult, DeviceControl};
+use super::{
+    cancel::CancellationToken,
+    payload::{ChunkMap, Payload, PayloadType},
+    ControlError, ControlResult, DeviceControl,
+};
 
 pub use cameleon_genapi::{
-    elem_type::{AccessMode, NameSpace, Visibility},
+    elem_type::{AccessMode, Endianness, NameSpace, Visibility},
     store::{
         CacheSink, CacheStore, DefaultCacheStore, DefaultNodeStore, DefaultValueStore, NodeId,
         NodeStore, ValueStore,
     },
-    GenApiError, RegisterDescription, ValueCtxt,
+    CacheStats, CategoryTree, CategoryTreeEntry, GenApiError, RegisterDescription, ValueCtxt,
 };
 
 /// Manages context of parameters of the device.
@@ -121,6 +137,28 @@ where
     pub fn node_store(&self) -> &Ctxt::NS {
         self.ctxt.node_store()
     }
+
+    /// Loads chunk data of `payload` into the context so that values of chunk features, e.g.
+    /// `ChunkExposureTime`, are decoded from the payload.
+    ///
+    /// Chunk data loaded previously are discarded. For a payload of
+    /// [`PayloadType::ImageExtendedChunk`], the first chunk, which is the image, is not loaded to
+    /// avoid copying it.
+    ///
+    /// See [`Payload::chunks`] for an example.
+    pub fn load_chunks(&mut self, payload: &Payload) {
+        let skip = match payload.payload_type() {
+            PayloadType::ImageExtendedChunk => 1,
+            _ => 0,
+        };
+        self.ctxt.enter(|_, value_ctxt| {
+            value_ctxt.clear_chunk_data();
+            let chunks = payload.chunks().into_iter().flat_map(ChunkMap::iter);
+            for (id, range) in chunks.skip(skip) {
+                value_ctxt.set_chunk_data(id.into(), payload.payload()[range].to_vec());
+            }
+        });
+    }
 }
 
 impl<Ctrl, Ctxt> ParamsCtxt<Ctrl, Ctxt>
@@ -145,6 +183,68 @@ where
             ctxt.enter(|node_store, value_ctxt| f(ctrl, node_store, value_ctxt))
         })
     }
+
+    /// Returns the category hierarchy starting from `Root` category.
+    ///
+    /// Access modes of the entries are evaluated with the current state of the device.
+    /// Returns `None` if there is no `Root` category in the context.
+    pub fn category_tree(&mut self) -> GenApiResult<Option<CategoryTree>> {
+        self.enter2(|ctrl, ns, vc| CategoryTree::new(&mut GenApiDevice::new(ctrl), ns, vc))
+    }
+}
+
+impl<Ctrl, Ctxt> ParamsCtxt<Ctrl, Ctxt>
+where
+    Ctrl: Clone,
+    Ctxt: Clone,
+{
+    /// Returns a read-only view of the context.
+    ///
+    /// The view shares the device and the `GenApi` context with `self` if both of `Ctrl` and
+    /// `Ctxt` are sharable, e.g. [`SharedControlHandle`](crate::u3v::SharedControlHandle) and
+    /// [`SharedDefaultGenApiCtxt`]. So the view can be moved to another thread to display values of
+    /// nodes while `self` is used to configure the device.
+    ///
+    /// The view only reads values of nodes, see [`ReadOnlyParamsCtxt`] for details.
+    ///
+    /// Note that the view is not a lock-free snapshot. Reads through the view and accesses through
+    /// `self` are serialized by the locks of `Ctrl` and `Ctxt` as usual.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use cameleon::u3v;
+    /// use cameleon::{
+    ///     genapi::{ParamsCtxt, SharedDefaultGenApiCtxt},
+    ///     Camera,
+    /// };
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// // Use sharable handle and context.
+    /// let mut camera: Camera<u3v::SharedControlHandle, u3v::StreamHandle, SharedDefaultGenApiCtxt> =
+    ///     camera.convert_into();
+    /// let params_ctxt = ParamsCtxt {
+    ///     ctrl: camera.ctrl.clone(),
+    ///     ctxt: camera.ctxt.clone().unwrap(),
+    /// };
+    /// let mut inspector = params_ctxt.read_only();
+    ///
+    /// let handle = std::thread::spawn(move || {
+    ///     let gain_node = inspector.node("Gain").unwrap();
+    ///     println!("{:?}", inspector.feature_value(gain_node));
+    /// });
+    /// handle.join().unwrap();
+    ///
+    /// # camera.close().unwrap();
+    /// ```
+    pub fn read_only(&self) -> ReadOnlyParamsCtxt<Ctrl, Ctxt> {
+        ReadOnlyParamsCtxt::new(self.ctrl.clone(), self.ctxt.clone())
+    }
 }
 
 impl<Ctrl, Ctxt> ParamsCtxt<Ctrl, Ctxt> {
@@ -175,6 +275,190 @@ impl<Ctrl, Ctxt> ParamsCtxt<Ctrl, Ctxt> {
     }
 }
 
+/// A read-only view of [`ParamsCtxt`], see [`ParamsCtxt::read_only`].
+///
+/// The view never gives out `&mut ParamsCtxt`, so methods of nodes that may write, e.g.
+/// [`IntegerNode::set_value`] or [`CommandNode::execute`], can't be called with it.
+///
+/// ```rust,compile_fail
+/// use cameleon::{
+///     genapi::{GenApiCtxt, IntegerNode, ReadOnlyParamsCtxt},
+///     DeviceControl,
+/// };
+///
+/// fn write<Ctrl, Ctxt>(view: &mut ReadOnlyParamsCtxt<Ctrl, Ctxt>, node: IntegerNode)
+/// where
+///     Ctrl: DeviceControl,
+///     Ctxt: GenApiCtxt,
+/// {
+///     node.set_value(view, 0).unwrap();
+/// }
+/// ```
+///
+/// Values are read with [`Self::feature_value`], and [`Self::params_ctxt`] gives access to
+/// information of nodes which doesn't need the device, e.g. names and descriptions.
+#[derive(Debug)]
+pub struct ReadOnlyParamsCtxt<Ctrl, Ctxt> {
+    inner: ParamsCtxt<ReadOnlyControl<Ctrl>, ReadOnlyGenApiCtxt<Ctxt>>,
+}
+
+impl<Ctrl, Ctxt> ReadOnlyParamsCtxt<Ctrl, Ctxt> {
+    fn new(ctrl: Ctrl, ctxt: Ctxt) -> Self {
+        Self {
+            inner: ParamsCtxt {
+                ctrl: ReadOnlyControl(ctrl),
+                ctxt: ReadOnlyGenApiCtxt(ctxt),
+            },
+        }
+    }
+
+    /// Returns the context the view reads through.
+    ///
+    /// The context is only accessible by shared reference, which is enough to get information
+    /// of nodes, e.g. [`Node::name`] or [`EnumerationNode::entries`].
+    pub fn params_ctxt(&self) -> &ParamsCtxt<ReadOnlyControl<Ctrl>, ReadOnlyGenApiCtxt<Ctxt>> {
+        &self.inner
+    }
+}
+
+impl<Ctrl, Ctxt> ReadOnlyParamsCtxt<Ctrl, Ctxt>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    /// Returns `None` if there is node node with the given name in the context.
+    pub fn node(&self, name: &str) -> Option<Node> {
+        self.inner.node(name)
+    }
+
+    /// Returns `true` if the node has a value interface and is readable, see
+    /// [`Node::is_readable`].
+    pub fn is_readable(&mut self, node: Node) -> GenApiResult<bool> {
+        node.is_readable(&mut self.inner)
+    }
+
+    /// Reads the value of the node, see [`Node::feature_value`].
+    pub fn feature_value(&mut self, node: Node) -> GenApiResult<FeatureValue> {
+        node.feature_value(&mut self.inner)
+    }
+}
+
+impl<Ctrl, Ctxt> Clone for ReadOnlyParamsCtxt<Ctrl, Ctxt>
+where
+    Ctrl: Clone,
+    Ctxt: Clone,
+{
+    fn clone(&self) -> Self {
+        Self::new(self.inner.ctrl.0.clone(), self.inner.ctxt.0.clone())
+    }
+}
+
+/// A control handle that forbids any operation that may change the device state.
+///
+/// This is a guard for [`ReadOnlyParamsCtxt`] in addition to the restriction on its methods.
+/// The inner handle is not accessible, and the wrapper is neither constructible nor clonable
+/// outside of this crate. [`DeviceControl::read`], [`DeviceControl::read_stacked`] and
+/// [`DeviceControl::genapi`] are forwarded to the inner handle, and other operations except for
+/// [`DeviceControl::is_opened`] return an error.
+#[derive(Debug)]
+pub struct ReadOnlyControl<Ctrl>(Ctrl);
+
+impl<Ctrl> ReadOnlyControl<Ctrl> {
+    fn error() -> ControlError {
+        ControlError::InvalidData("the control handle is read-only".into())
+    }
+}
+
+impl<Ctrl> DeviceControl for ReadOnlyControl<Ctrl>
+where
+    Ctrl: DeviceControl,
+{
+    fn open(&mut self) -> ControlResult<()> {
+        Err(Self::error())
+    }
+
+    fn close(&mut self) -> ControlResult<()> {
+        Err(Self::error())
+    }
+
+    fn is_opened(&self) -> bool {
+        self.0.is_opened()
+    }
+
+    fn read(&mut self, address: u64, buf: &mut [u8]) -> ControlResult<()> {
+        self.0.read(address, buf)
+    }
+
+    fn write(&mut self, _: u64, _: &[u8]) -> ControlResult<()> {
+        Err(Self::error())
+    }
+
+    fn read_stacked(&mut self, entries: &mut [(u64, &mut [u8])]) -> ControlResult<()> {
+        self.0.read_stacked(entries)
+    }
+
+    fn write_stacked(&mut self, _: &[(u64, &[u8])]) -> ControlResult<()> {
+        Err(Self::error())
+    }
+
+    fn genapi(&mut self) -> ControlResult<String> {
+        self.0.genapi()
+    }
+
+    fn genapi_with_cancel(&mut self, token: &CancellationToken) -> ControlResult<String> {
+        self.0.genapi_with_cancel(token)
+    }
+
+    fn enable_streaming(&mut self) -> ControlResult<()> {
+        Err(Self::error())
+    }
+
+    fn disable_streaming(&mut self) -> ControlResult<()> {
+        Err(Self::error())
+    }
+
+    fn enable_streaming_channel(&mut self, _: usize) -> ControlResult<()> {
+        Err(Self::error())
+    }
+
+    fn disable_streaming_channel(&mut self, _: usize) -> ControlResult<()> {
+        Err(Self::error())
+    }
+}
+
+/// A `GenApi` context that rejects writes to values held in the context.
+///
+/// Like [`ReadOnlyControl`], this is a guard for [`ReadOnlyParamsCtxt`]. Values are marked as
+/// read-only with [`ValueCtxt::set_read_only`] only while the context is entered through the
+/// wrapper, so the inner context is still writable if it's shared with other users.
+#[derive(Debug)]
+pub struct ReadOnlyGenApiCtxt<Ctxt>(Ctxt);
+
+impl<Ctxt> GenApiCtxt for ReadOnlyGenApiCtxt<Ctxt>
+where
+    Ctxt: GenApiCtxt,
+{
+    type NS = Ctxt::NS;
+    type VS = Ctxt::VS;
+    type CS = Ctxt::CS;
+
+    fn enter<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&Self::NS, &mut ValueCtxt<Self::VS, Self::CS>) -> R,
+    {
+        self.0.enter(|node_store, value_ctxt| {
+            let was_read_only = value_ctxt.set_read_only(true);
+            let res = f(node_store, value_ctxt);
+            value_ctxt.set_read_only(was_read_only);
+            res
+        })
+    }
+
+    fn node_store(&self) -> &Self::NS {
+        self.0.node_store()
+    }
+}
+
 /// A trait that provides accesss to `GenApi` context.
 #[auto_impl(&mut, Box)]
 pub trait GenApiCtxt {
@@ -197,6 +481,11 @@ pub trait GenApiCtxt {
     fn clear_cache(&mut self) {
         self.enter(|_, value_ctxt| value_ctxt.clear_cache())
     }
+
+    /// Returns statistics of accesses to the cache of the context.
+    fn cache_stats(&mut self) -> CacheStats {
+        self.enter(|_, value_ctxt| value_ctxt.cache_stats())
+    }
 }
 
 /// A trait that provides directly conversion from `GenApi` string to a `GenApi` context.
@@ -205,13 +494,70 @@ pub trait FromXml {
     fn from_xml(xml: &impl AsRef<str>) -> ControlResult<Self>
     where
         Self: Sized + GenApiCtxt;
+
+    /// Parse multiple `GenApi` xmls, e.g. a standard xml and its vendor extension, and build a
+    /// context from merged nodes of them.
+    ///
+    /// When nodes with the same name are defined in multiple xmls, the node with the higher
+    /// `MergePriority` is kept, and the node defined later is kept if the priorities are the
+    /// same.
+    ///
+    /// The default implementation doesn't merge xmls, it builds the context from a single xml
+    /// with [`Self::from_xml`] and returns an error if the number of xmls is not one.
+    fn from_xmls<X: AsRef<str>>(xmls: &[X]) -> ControlResult<Self>
+    where
+        Self: Sized + GenApiCtxt,
+    {
+        match xmls {
+            [xml] => Self::from_xml(xml),
+            _ => Err(ControlError::InvalidData(
+                format!(
+                    "the context can't be built from {} xmls, exactly one xml is required",
+                    xmls.len()
+                )
+                .into(),
+            )),
+        }
+    }
 }
 
 /// Default `GenApi` context.  
 /// This context caches values of `GenApi` nodes if possible to reduce transaction.
 ///
 /// If you need no cache context, use [`NoCacheGenApiCtxt`].
+///
+/// With `serde` feature, the context can be serialized to skip parsing `GenApi` xml on
+/// subsequent startups. Cached values of nodes are not serialized.
+///
+/// # Examples
+/// ```rust
+/// # use cameleon::u3v;
+/// # let mut cameras = u3v::enumerate_cameras().unwrap();
+/// # if cameras.is_empty() {
+/// #     return;
+/// # }
+/// # let mut camera = cameras.pop().unwrap();
+/// use cameleon::genapi::{xml_hash, DefaultGenApiCtxt, FromXml};
+/// use cameleon::DeviceControl;
+///
+/// camera.open().unwrap();
+///
+/// let xml = camera.ctrl.genapi().unwrap();
+/// // Use the hash as a key of the persisted context, e.g. a file name.
+/// let key = xml_hash(&xml);
+/// // Load the persisted context keyed by `key` if exists, otherwise, parse the xml and persist
+/// // the context with e.g. `serde_json::to_writer`.
+/// let ctxt = DefaultGenApiCtxt::from_xml(&xml).unwrap();
+/// let mut camera = camera.set_context(ctxt);
+///
+/// camera.close().unwrap();
+/// ```
 #[derive(Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct DefaultGenApiCtxt {
     /// Node store.
     pub node_store: store::DefaultNodeStore,
@@ -253,6 +599,20 @@ impl FromXml for DefaultGenApiCtxt {
             reg_desc,
         })
     }
+
+    fn from_xmls<X: AsRef<str>>(xmls: &[X]) -> ControlResult<Self>
+    where
+        Self: Sized + GenApiCtxt,
+    {
+        let (reg_desc, node_store, value_ctxt) = GenApiBuilder::<DefaultNodeStore>::default()
+            .build_merged(xmls)
+            .map_err(|e| ControlError::InvalidData(e.into()))?;
+        Ok(Self {
+            node_store,
+            value_ctxt,
+            reg_desc,
+        })
+    }
 }
 
 /// A sharable version of [`DefaultGenApiCtxt`].
@@ -291,6 +651,13 @@ impl FromXml for SharedDefaultGenApiCtxt {
     {
         Ok(DefaultGenApiCtxt::from_xml(xml)?.into())
     }
+
+    fn from_xmls<X: AsRef<str>>(xmls: &[X]) -> ControlResult<Self>
+    where
+        Self: Sized + GenApiCtxt,
+    {
+        Ok(DefaultGenApiCtxt::from_xmls(xmls)?.into())
+    }
 }
 
 impl From<DefaultGenApiCtxt> for SharedDefaultGenApiCtxt {
@@ -306,6 +673,11 @@ impl From<DefaultGenApiCtxt> for SharedDefaultGenApiCtxt {
 /// `GenApi` context.  
 /// This context doesn't cache any value of `GenApi` nodes.
 #[derive(Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct NoCacheGenApiCtxt {
     /// Node store.
     pub node_store: store::DefaultNodeStore,
@@ -348,6 +720,21 @@ impl FromXml for NoCacheGenApiCtxt {
             reg_desc,
         })
     }
+
+    fn from_xmls<X: AsRef<str>>(xmls: &[X]) -> ControlResult<Self>
+    where
+        Self: Sized + GenApiCtxt,
+    {
+        let (reg_desc, node_store, value_ctxt) = GenApiBuilder::<DefaultNodeStore>::default()
+            .no_cache()
+            .build_merged(xmls)
+            .map_err(|e| ControlError::InvalidData(e.into()))?;
+        Ok(Self {
+            node_store,
+            value_ctxt,
+            reg_desc,
+        })
+    }
 }
 
 impl From<DefaultGenApiCtxt> for NoCacheGenApiCtxt {
@@ -395,6 +782,13 @@ impl FromXml for SharedNoCacheGenApiCtxt {
     {
         Ok(NoCacheGenApiCtxt::from_xml(xml)?.into())
     }
+
+    fn from_xmls<X: AsRef<str>>(xmls: &[X]) -> ControlResult<Self>
+    where
+        Self: Sized + GenApiCtxt,
+    {
+        Ok(NoCacheGenApiCtxt::from_xmls(xmls)?.into())
+    }
 }
 
 impl From<NoCacheGenApiCtxt> for SharedNoCacheGenApiCtxt {
@@ -414,8 +808,18 @@ impl From<DefaultGenApiCtxt> for SharedNoCacheGenApiCtxt {
     }
 }
 
+/// Returns `SHA-1` hash of `GenApi` xml.
+///
+/// The hash can be used as a key to identify contexts built from the same xml, e.g. when
+/// persisting a serialized context.
+pub fn xml_hash(xml: &impl AsRef<str>) -> [u8; 20] {
+    use sha1::Digest;
+
+    sha1::Sha1::digest(xml.as_ref().as_bytes()).into()
+}
+
 /// Represents `CompressionType` of `GenICam` XML file on the device's memory.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CompressionType {
     /// Uncompressed `GenICam` XML file.
     Uncompressed,
@@ -423,6 +827,70 @@ pub enum CompressionType {
     Zip,
 }
 
+impl CompressionType {
+    /// Signature of a local file header, which every non-empty ZIP archive starts with.
+    const ZIP_SIGNATURE: &'static [u8] = b"PK\x03\x04";
+
+    /// Detects `CompressionType` from the content of the file.
+    #[must_use]
+    pub fn detect(file: &[u8]) -> Self {
+        if file.starts_with(Self::ZIP_SIGNATURE) {
+            Self::Zip
+        } else {
+            Self::Uncompressed
+        }
+    }
+}
+
+/// Decodes `GenICam` XML file retrieved from the device's memory into XML string.
+///
+/// `comp_type` is the type reported by the device, but the actual type is detected from the
+/// content because some devices report a wrong type.
+/// If a ZIP archive contains files other than the XML, e.g. a readme, the single file with `.xml`
+/// extension is used.
+pub fn decode_xml(file: Vec<u8>, comp_type: CompressionType) -> ControlResult<String> {
+    use std::io::Read;
+
+    fn zip_err(err: impl std::fmt::Debug) -> ControlError {
+        ControlError::InvalidDevice(format!("zipped xml file is broken: {:?}", err).into())
+    }
+
+    let detected = CompressionType::detect(&file);
+    if detected != comp_type {
+        tracing::warn!(
+            "device reports {:?} for GenApi xml, but the content is {:?}",
+            comp_type,
+            detected
+        );
+    }
+
+    match detected {
+        CompressionType::Zip => {
+            let mut zip = zip::ZipArchive::new(std::io::Cursor::new(file)).map_err(zip_err)?;
+            let mut file = if zip.len() == 1 {
+                zip.by_index(0)
+            } else {
+                let xml_files: Vec<_> = zip
+                    .file_names()
+                    .filter(|name| name.to_ascii_lowercase().ends_with(".xml"))
+                    .map(ToString::to_string)
+                    .collect();
+                if xml_files.len() != 1 {
+                    return Err(zip_err("zipped GenApi XML must contain a single XML file"));
+                }
+                zip.by_name(&xml_files[0])
+            }
+            .map_err(zip_err)?;
+            let file_size: usize = file.size().try_into()?;
+            let mut xml = Vec::with_capacity(file_size);
+            file.read_to_end(&mut xml).map_err(zip_err)?;
+            Ok(String::from_utf8_lossy(&xml).into())
+        }
+
+        CompressionType::Uncompressed => Ok(String::from_utf8_lossy(&file).into()),
+    }
+}
+
 struct GenApiDevice<'a, T> {
     inner: &'a mut T,
 }
@@ -463,3 +931,220 @@ where
         Ok(self.inner.write(address, data)?)
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    const XML: &str = "<RegisterDescription/>";
+
+    fn zip(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(vec![]));
+        for (name, content) in files {
+            writer
+                .start_file(*name, zip::write::FileOptions::default())
+                .unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_decode_xml() {
+        let zipped = zip(&[("device.xml", XML)]);
+        assert_eq!(CompressionType::detect(&zipped), CompressionType::Zip);
+        assert_eq!(decode_xml(zipped, CompressionType::Zip).unwrap(), XML);
+
+        let raw = XML.as_bytes().to_vec();
+        assert_eq!(CompressionType::detect(&raw), CompressionType::Uncompressed);
+        assert_eq!(decode_xml(raw, CompressionType::Uncompressed).unwrap(), XML);
+    }
+
+    #[test]
+    fn test_decode_xml_wrong_compression_type() {
+        let zipped = zip(&[("device.xml", XML)]);
+        assert_eq!(
+            decode_xml(zipped, CompressionType::Uncompressed).unwrap(),
+            XML
+        );
+
+        let raw = XML.as_bytes().to_vec();
+        assert_eq!(decode_xml(raw, CompressionType::Zip).unwrap(), XML);
+    }
+
+    #[test]
+    fn test_decode_xml_multiple_files() {
+        let zipped = zip(&[("readme.txt", "readme"), ("device.XML", XML)]);
+        assert_eq!(decode_xml(zipped, CompressionType::Zip).unwrap(), XML);
+
+        let zipped = zip(&[("device.xml", XML), ("another.xml", XML)]);
+        assert!(decode_xml(zipped, CompressionType::Zip).is_err());
+
+        let broken = b"PK\x03\x04broken".to_vec();
+        assert!(decode_xml(broken, CompressionType::Zip).is_err());
+    }
+
+    /// A control handle of a device whose registers are backed by `memory`.
+    #[derive(Debug, Clone, Default)]
+    pub(crate) struct MemoryControl {
+        pub(crate) memory: Vec<u8>,
+        /// The number of writes that succeed before all subsequent writes fail, writes never
+        /// fail if `None`.
+        pub(crate) remaining_writes: Option<usize>,
+    }
+
+    impl MemoryControl {
+        pub(crate) fn new(memory: Vec<u8>) -> Self {
+            Self {
+                memory,
+                remaining_writes: None,
+            }
+        }
+
+        fn range(&self, address: u64, len: usize) -> ControlResult<std::ops::Range<usize>> {
+            let start = address as usize;
+            if start + len <= self.memory.len() {
+                Ok(start..start + len)
+            } else {
+                Err(ControlError::InvalidData("address is out of range".into()))
+            }
+        }
+    }
+
+    impl DeviceControl for MemoryControl {
+        fn open(&mut self) -> ControlResult<()> {
+            Ok(())
+        }
+
+        fn close(&mut self) -> ControlResult<()> {
+            Ok(())
+        }
+
+        fn is_opened(&self) -> bool {
+            true
+        }
+
+        fn read(&mut self, address: u64, buf: &mut [u8]) -> ControlResult<()> {
+            let range = self.range(address, buf.len())?;
+            buf.copy_from_slice(&self.memory[range]);
+            Ok(())
+        }
+
+        fn write(&mut self, address: u64, data: &[u8]) -> ControlResult<()> {
+            match &mut self.remaining_writes {
+                Some(0) => return Err(ControlError::Io(anyhow::Error::msg("write failed"))),
+                Some(remaining) => *remaining -= 1,
+                None => {}
+            }
+            let range = self.range(address, data.len())?;
+            self.memory[range].copy_from_slice(data);
+            Ok(())
+        }
+
+        fn genapi(&mut self) -> ControlResult<String> {
+            Err(ControlError::InvalidData("no xml".into()))
+        }
+
+        fn enable_streaming(&mut self) -> ControlResult<()> {
+            Ok(())
+        }
+
+        fn disable_streaming(&mut self) -> ControlResult<()> {
+            Ok(())
+        }
+    }
+
+    pub(super) const IMMEDIATE_VALUE_XML: &str = r#"
+        <RegisterDescription
+          ModelName="CameleonModel"
+          VendorName="CameleonVendor"
+          StandardNameSpace="None"
+          SchemaMajorVersion="1"
+          SchemaMinorVersion="1"
+          SchemaSubMinorVersion="0"
+          MajorVersion="1"
+          MinorVersion="2"
+          SubMinorVersion="3"
+          ProductGuid="01234567-0123-0123-0123-0123456789ab"
+          VersionGuid="76543210-3210-3210-3210-ba9876543210"
+          xmlns="http://www.genicam.org/GenApi/Version_1_0"
+          xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+          xsi:schemaLocation="http://www.genicam.org/GenApi/Version_1_0 GenApiSchema.xsd">
+
+            <Integer Name="Width" NameSpace="Standard">
+                <Value>640</Value>
+            </Integer>
+        </RegisterDescription>
+        "#;
+
+    #[test]
+    fn test_read_only_rejects_immediate_value_writes() {
+        let ctxt = SharedDefaultGenApiCtxt::from_xml(&IMMEDIATE_VALUE_XML).unwrap();
+        let mut params_ctxt = ParamsCtxt {
+            ctrl: MemoryControl::default(),
+            ctxt,
+        };
+        let mut view = params_ctxt.read_only();
+        let node = view.node("Width").unwrap();
+        let width = node.as_integer(view.params_ctxt()).unwrap();
+        assert_eq!(node.name(view.params_ctxt()), "Width");
+
+        assert!(view.is_readable(node).unwrap());
+        assert_eq!(
+            view.feature_value(node).unwrap(),
+            FeatureValue::Integer(640)
+        );
+
+        // The guards reject writes even if the inner context were reachable.
+        let inner = &mut view.inner;
+        assert!(!width.is_writable(inner).unwrap());
+        assert!(width.set_value(inner, 320).is_err());
+        assert!(inner.ctrl.write(0, &[0]).is_err());
+        assert_eq!(width.value(&mut params_ctxt).unwrap(), 640);
+
+        // The shared context is still writable through the original context.
+        assert!(width.is_writable(&mut params_ctxt).unwrap());
+        width.set_value(&mut params_ctxt, 320).unwrap();
+        assert_eq!(
+            view.feature_value(node).unwrap(),
+            FeatureValue::Integer(320)
+        );
+    }
+
+    /// A context which only implements [`FromXml::from_xml`].
+    struct SingleXmlCtxt(DefaultGenApiCtxt);
+
+    impl GenApiCtxt for SingleXmlCtxt {
+        type NS = store::DefaultNodeStore;
+        type VS = store::DefaultValueStore;
+        type CS = store::DefaultCacheStore;
+
+        fn enter<F, R>(&mut self, f: F) -> R
+        where
+            F: FnOnce(&Self::NS, &mut ValueCtxt<Self::VS, Self::CS>) -> R,
+        {
+            self.0.enter(f)
+        }
+
+        fn node_store(&self) -> &Self::NS {
+            self.0.node_store()
+        }
+    }
+
+    impl FromXml for SingleXmlCtxt {
+        fn from_xml(xml: &impl AsRef<str>) -> ControlResult<Self> {
+            DefaultGenApiCtxt::from_xml(xml).map(Self)
+        }
+    }
+
+    #[test]
+    fn test_default_from_xmls() {
+        let ctxt = SingleXmlCtxt::from_xmls(&[IMMEDIATE_VALUE_XML]).unwrap();
+        assert!(ctxt.node_store().id_by_name("Width").is_some());
+
+        assert!(SingleXmlCtxt::from_xmls(&[IMMEDIATE_VALUE_XML, IMMEDIATE_VALUE_XML]).is_err());
+        assert!(SingleXmlCtxt::from_xmls::<&str>(&[]).is_err());
+    }
+}
@@ -5,14 +5,18 @@
 //! This module contains types which implement `IInterface` defined in `GenICam
 //! Starndard`.
 
+use std::time::{Duration, Instant};
+
 use cameleon_genapi::{
     elem_type::{DisplayNotation, FloatRepresentation, IntegerRepresentation},
     interface::IncrementMode,
     prelude::*,
+    store::NodeStore,
     GenApiError, GenApiResult, NodeId,
 };
 
 use super::{DeviceControl, GenApiCtxt, GenApiDevice, ParamsCtxt};
+use crate::{cancel::CancellationToken, ControlError};
 
 /// A node that has `IInteger` interface
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -58,6 +62,26 @@ pub struct EnumEntryNode(NodeId);
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Node(pub(super) NodeId);
 
+/// A value of a feature, tagged with the interface the feature node implements.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub enum FeatureValue {
+    /// A value of a node that has `IInteger` interface.
+    Integer(i64),
+    /// A value of a node that has `IFloat` interface.
+    Float(f64),
+    /// A value of a node that has `IString` interface.
+    String(String),
+    /// A value of a node that has `IBoolean` interface.
+    Boolean(bool),
+    /// A symbolic name of the current entry of a node that has `IEnumeration` interface.
+    Enumeration(String),
+}
+
 impl From<NodeId> for Node {
     fn from(nid: NodeId) -> Self {
         Node(nid)
@@ -346,6 +370,61 @@ impl CommandNode {
         pub fn is_writable<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<bool>,
     }
 
+    /// Executes the command, then polls [`Self::is_done`] until the device completes it.
+    ///
+    /// Returns [`ControlError::Timeout`] wrapped in [`GenApiError::Device`] if the command
+    /// doesn't complete within `timeout`.
+    pub fn execute_and_wait<Ctrl, Ctxt>(
+        self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        timeout: Duration,
+    ) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        self.execute_and_wait_with_cancel(ctxt, timeout, &CancellationToken::new())
+    }
+
+    /// Same as [`Self::execute_and_wait`], but stops waiting when `token` is cancelled.
+    ///
+    /// Returns [`ControlError::Cancelled`] wrapped in [`GenApiError::Device`] on cancellation.
+    pub fn execute_and_wait_with_cancel<Ctrl, Ctxt>(
+        self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        timeout: Duration,
+        token: &CancellationToken,
+    ) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        self.execute(ctxt)?;
+
+        let start = Instant::now();
+        while !self.is_done(ctxt)? {
+            token.check_genapi()?;
+            if start.elapsed() > timeout {
+                return Err(GenApiError::Device(Box::new(ControlError::Timeout)));
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the command has been executed and its completion hasn't been observed
+    /// by [`Self::is_done`] yet.
+    ///
+    /// While the command is busy, writing to the selectors of the command fails with
+    /// [`GenApiError::Busy`].
+    pub fn is_busy<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> bool
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        ctxt.enter2(|_, _, vc| vc.is_command_busy(self.0))
+    }
+
     /// Upcast to [`Node`].
     pub fn as_node(self) -> Node {
         Node(self.0)
@@ -517,6 +596,151 @@ impl Node {
         }
     }
 
+    /// Returns the node which provides the same feature through another interface if exists, e.g.
+    /// `ExposureTime` of `IFloat` for `ExposureTimeRaw` of `IInteger`.
+    pub fn alias<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> Option<Node>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        ctxt.node_store().alias(self.0).map(Node)
+    }
+
+    /// Same as [`Self::alias`], but returns the node referred by `pCastAlias`, whose value may
+    /// be converted with a type cast.
+    pub fn cast_alias<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> Option<Node>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        ctxt.node_store().cast_alias(self.0).map(Node)
+    }
+
+    /// Returns `true` if the node has a value interface and is readable.
+    ///
+    /// Returns `false` if the node doesn't have any value interface, e.g. `ICommand` or
+    /// `ICategory`.
+    pub fn is_readable<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<bool>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        if let Some(node) = self.as_integer(ctxt) {
+            node.is_readable(ctxt)
+        } else if let Some(node) = self.as_float(ctxt) {
+            node.is_readable(ctxt)
+        } else if let Some(node) = self.as_string(ctxt) {
+            node.is_readable(ctxt)
+        } else if let Some(node) = self.as_boolean(ctxt) {
+            node.is_readable(ctxt)
+        } else if let Some(node) = self.as_enumeration(ctxt) {
+            node.is_readable(ctxt)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Returns `true` if the node has a value interface and is writable.
+    ///
+    /// Returns `false` if the node doesn't have any value interface, e.g. `ICommand` or
+    /// `ICategory`.
+    pub fn is_writable<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<bool>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        if let Some(node) = self.as_integer(ctxt) {
+            node.is_writable(ctxt)
+        } else if let Some(node) = self.as_float(ctxt) {
+            node.is_writable(ctxt)
+        } else if let Some(node) = self.as_string(ctxt) {
+            node.is_writable(ctxt)
+        } else if let Some(node) = self.as_boolean(ctxt) {
+            node.is_writable(ctxt)
+        } else if let Some(node) = self.as_enumeration(ctxt) {
+            node.is_writable(ctxt)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Reads the value of the node through the value interface the node implements.
+    ///
+    /// Returns [`GenApiError::InvalidNode`] if the node doesn't have any value interface.
+    pub fn feature_value<Ctrl, Ctxt>(
+        self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+    ) -> GenApiResult<FeatureValue>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        if let Some(node) = self.as_integer(ctxt) {
+            node.value(ctxt).map(FeatureValue::Integer)
+        } else if let Some(node) = self.as_float(ctxt) {
+            node.value(ctxt).map(FeatureValue::Float)
+        } else if let Some(node) = self.as_string(ctxt) {
+            node.value(ctxt).map(FeatureValue::String)
+        } else if let Some(node) = self.as_boolean(ctxt) {
+            node.value(ctxt).map(FeatureValue::Boolean)
+        } else if let Some(node) = self.as_enumeration(ctxt) {
+            let entry = node.current_entry(ctxt)?;
+            Ok(FeatureValue::Enumeration(entry.symbolic(ctxt).to_string()))
+        } else {
+            Err(GenApiError::InvalidNode(
+                format!("`{}` doesn't have any value interface", self.name(ctxt)).into(),
+            ))
+        }
+    }
+
+    /// Writes the value to the node through the value interface the node implements.
+    ///
+    /// Returns [`GenApiError::InvalidNode`] if the interface of the node doesn't match the
+    /// value.
+    pub fn set_feature_value<Ctrl, Ctxt>(
+        self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        value: FeatureValue,
+    ) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        match value {
+            FeatureValue::Integer(v) => {
+                if let Some(node) = self.as_integer(ctxt) {
+                    return node.set_value(ctxt, v);
+                }
+            }
+            FeatureValue::Float(v) => {
+                if let Some(node) = self.as_float(ctxt) {
+                    return node.set_value(ctxt, v);
+                }
+            }
+            FeatureValue::String(v) => {
+                if let Some(node) = self.as_string(ctxt) {
+                    return node.set_value(ctxt, v);
+                }
+            }
+            FeatureValue::Boolean(v) => {
+                if let Some(node) = self.as_boolean(ctxt) {
+                    return node.set_value(ctxt, v);
+                }
+            }
+            FeatureValue::Enumeration(v) => {
+                if let Some(node) = self.as_enumeration(ctxt) {
+                    return node.set_entry_by_symbolic(ctxt, &v);
+                }
+            }
+        }
+
+        Err(GenApiError::InvalidNode(
+            format!(
+                "`{}` doesn't have the interface of the value",
+                self.name(ctxt)
+            )
+            .into(),
+        ))
+    }
+
     delegate_node_base! {
         /// Returns name space of the node.
         pub fn name_space<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> super::NameSpace,
@@ -532,5 +756,80 @@ impl Node {
         pub fn event_id<Ctlr, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> Option<u64>,
         /// Returns tooltip of the node. This method is mainly for GUI.
         pub fn tooltip<Ctlr, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> Option<&str>,
+        /// Returns URL of the documentation of the node if exists. This method is mainly for GUI.
+        pub fn docu_url<Ctlr, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> Option<&str>,
+        /// Returns access mode imposed on the node by the `ImposedAccessMode` element.
+        pub fn imposed_access_mode<Ctlr, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> super::AccessMode,
     }
 }
+
+macro_rules! impl_node_metadata {
+    ($($ty:ident,)*) => {
+        $(
+            impl $ty {
+                /// Returns name of the node.
+                pub fn name<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> &str
+                where
+                    Ctxt: GenApiCtxt,
+                {
+                    self.as_node().name(ctxt)
+                }
+
+                /// Returns display name of the node, or the node name if the node has no display
+                /// name. This method is mainly for GUI.
+                pub fn display_name<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> &str
+                where
+                    Ctxt: GenApiCtxt,
+                {
+                    self.as_node().display_name(ctxt)
+                }
+
+                /// Returns tooltip of the node. This method is mainly for GUI.
+                pub fn tooltip<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> Option<&str>
+                where
+                    Ctxt: GenApiCtxt,
+                {
+                    self.as_node().tooltip(ctxt)
+                }
+
+                /// Returns description of the node if exists. This method is mainly for GUI.
+                pub fn description<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> Option<&str>
+                where
+                    Ctxt: GenApiCtxt,
+                {
+                    self.as_node().description(ctxt)
+                }
+
+                /// Returns visibility of the node. This method is mainly for GUI.
+                pub fn visibility<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> super::Visibility
+                where
+                    Ctxt: GenApiCtxt,
+                {
+                    self.as_node().visibility(ctxt)
+                }
+
+                /// Returns URL of the documentation of the node if exists. This method is mainly
+                /// for GUI.
+                pub fn docu_url<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> Option<&str>
+                where
+                    Ctxt: GenApiCtxt,
+                {
+                    self.as_node().docu_url(ctxt)
+                }
+            }
+        )*
+    };
+}
+
+impl_node_metadata! {
+    IntegerNode,
+    FloatNode,
+    StringNode,
+    EnumerationNode,
+    EnumEntryNode,
+    CommandNode,
+    BooleanNode,
+    RegisterNode,
+    CategoryNode,
+    PortNode,
+}
@@ -0,0 +1,432 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains helpers to access features that are selected by a selector node.
+//!
+//! # Examples
+//! ```rust
+//! # use cameleon::u3v;
+//! # let mut cameras = u3v::enumerate_cameras().unwrap();
+//! # if cameras.is_empty() {
+//! #     return;
+//! # }
+//! # let mut camera = cameras.pop().unwrap();
+//! # camera.open().unwrap();
+//! camera.load_context().unwrap();
+//!
+//! let mut params_ctxt = camera.params_ctxt().unwrap();
+//! let selector = params_ctxt.node("GainSelector").unwrap();
+//! let gain = params_ctxt.node("Gain").unwrap();
+//!
+//! // Reads `Gain` for each entry of `GainSelector`, e.g. `All`, `Red`, `Green` and `Blue`.
+//! for (selector_value, gain_value) in selector.values_by_selector(&mut params_ctxt, gain).unwrap() {
+//!     println!("{:?}: {:?}", selector_value, gain_value);
+//! }
+//!
+//! # camera.close().unwrap();
+//! ```
+
+use cameleon_genapi::{prelude::*, GenApiError, GenApiResult};
+use tracing::warn;
+
+use super::{node_kind::FeatureValue, DeviceControl, GenApiCtxt, Node, ParamsCtxt};
+
+/// The maximum number of values iterated for a selector that has `IInteger` interface.
+const MAX_INTEGER_SELECTOR_VALUES: i64 = 4096;
+
+/// A value which a selector node can take.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub enum SelectorValue {
+    /// An entry of a selector that has `IEnumeration` interface, represented by its symbolic
+    /// name.
+    Symbolic(String),
+    /// A value of a selector that has `IInteger` interface.
+    Integer(i64),
+    /// A value of a selector that has `IBoolean` interface.
+    Boolean(bool),
+}
+
+impl Node {
+    /// Returns `true` if the node selects at least one feature, i.e. the node has `pSelected`
+    /// elements.
+    pub fn is_selector<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> bool
+    where
+        Ctxt: GenApiCtxt,
+    {
+        !self.selected_features(ctxt).is_empty()
+    }
+
+    /// Returns features selected by the node.
+    ///
+    /// Returns an empty vector if the node is not a selector.
+    pub fn selected_features<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> Vec<Node>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        let ns = ctxt.node_store();
+        self.0
+            .as_iselector_kind(ns)
+            .and_then(|kind| {
+                kind.selecting_nodes(ns)
+                    .ok()
+                    .map(|nodes| nodes.iter().map(|nid| Node(*nid)).collect())
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the current value of the selector.
+    pub fn selector_value<Ctrl, Ctxt>(
+        self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+    ) -> GenApiResult<SelectorValue>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        match self.feature_value(ctxt)? {
+            FeatureValue::Enumeration(symbolic) => Ok(SelectorValue::Symbolic(symbolic)),
+            FeatureValue::Integer(value) => Ok(SelectorValue::Integer(value)),
+            FeatureValue::Boolean(value) => Ok(SelectorValue::Boolean(value)),
+            _ => Err(self.not_selector(ctxt)),
+        }
+    }
+
+    /// Sets the value to the selector.
+    pub fn set_selector_value<Ctrl, Ctxt>(
+        self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        value: SelectorValue,
+    ) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let value = match value {
+            SelectorValue::Symbolic(symbolic) => FeatureValue::Enumeration(symbolic),
+            SelectorValue::Integer(value) => FeatureValue::Integer(value),
+            SelectorValue::Boolean(value) => FeatureValue::Boolean(value),
+        };
+        self.set_feature_value(ctxt, value)
+    }
+
+    /// Returns all values that the selector can take.
+    ///
+    /// For a selector that has `IEnumeration` interface, entries that are not implemented or
+    /// not available are omitted.
+    pub fn selector_values<Ctrl, Ctxt>(
+        self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+    ) -> GenApiResult<Vec<SelectorValue>>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        if !self.is_selector(ctxt) {
+            return Err(self.not_selector(ctxt));
+        }
+
+        if let Some(node) = self.as_enumeration(ctxt) {
+            let mut values = vec![];
+            for entry in node.entries(ctxt) {
+                if entry.is_implemented(ctxt)? && entry.is_available(ctxt)? {
+                    values.push(SelectorValue::Symbolic(entry.symbolic(ctxt).to_string()));
+                }
+            }
+            Ok(values)
+        } else if let Some(node) = self.as_integer(ctxt) {
+            let min = node.min(ctxt)?;
+            let max = node.max(ctxt)?;
+            let inc = node.inc(ctxt)?.unwrap_or(1).max(1);
+            if max.saturating_sub(min) / inc >= MAX_INTEGER_SELECTOR_VALUES {
+                return Err(GenApiError::InvalidNode(
+                    format!("`{}` has too many values to iterate over", self.name(ctxt)).into(),
+                ));
+            }
+            Ok(std::iter::successors(Some(min), |v| v.checked_add(inc))
+                .take_while(|v| *v <= max)
+                .map(SelectorValue::Integer)
+                .collect())
+        } else if self.as_boolean(ctxt).is_some() {
+            Ok(vec![
+                SelectorValue::Boolean(false),
+                SelectorValue::Boolean(true),
+            ])
+        } else {
+            Err(self.not_selector(ctxt))
+        }
+    }
+
+    /// Iterates over all values of the selector and reads the `feature` for each of them.
+    ///
+    /// Returns pairs of the selector value and the feature value in the order of
+    /// [`Self::selector_values`]. Selector values for which the `feature` is not readable are
+    /// omitted.
+    ///
+    /// The selector value is restored after the iteration, even if reading the `feature` fails.
+    /// If both of reading and restoring fail, the error of reading is returned and the error of
+    /// restoring is logged.
+    ///
+    /// Returns [`GenApiError::InvalidNode`] if the `feature` is not selected by the node.
+    pub fn values_by_selector<Ctrl, Ctxt>(
+        self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        feature: Node,
+    ) -> GenApiResult<Vec<(SelectorValue, FeatureValue)>>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        if !self.selected_features(ctxt).contains(&feature) {
+            return Err(GenApiError::InvalidNode(
+                format!(
+                    "`{}` is not selected by `{}`",
+                    feature.name(ctxt),
+                    self.name(ctxt)
+                )
+                .into(),
+            ));
+        }
+
+        let selector_values = self.selector_values(ctxt)?;
+        let original = self.selector_value(ctxt)?;
+        let res = self.collect_values_by_selector(ctxt, feature, selector_values);
+        let restore_res = self.set_selector_value(ctxt, original);
+        match (res, restore_res) {
+            (Err(err), Err(restore_err)) => {
+                warn!(
+                    "failed to restore `{}` after reading `{}`: {}",
+                    self.name(ctxt),
+                    feature.name(ctxt),
+                    restore_err
+                );
+                Err(err)
+            }
+            (res, restore_res) => {
+                restore_res?;
+                res
+            }
+        }
+    }
+
+    fn collect_values_by_selector<Ctrl, Ctxt>(
+        self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        feature: Node,
+        selector_values: Vec<SelectorValue>,
+    ) -> GenApiResult<Vec<(SelectorValue, FeatureValue)>>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let mut values = Vec::with_capacity(selector_values.len());
+        for selector_value in selector_values {
+            self.set_selector_value(ctxt, selector_value.clone())?;
+            if feature.is_readable(ctxt)? {
+                values.push((selector_value, feature.feature_value(ctxt)?));
+            }
+        }
+        Ok(values)
+    }
+
+    fn not_selector<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> GenApiError
+    where
+        Ctxt: GenApiCtxt,
+    {
+        GenApiError::InvalidNode(format!("`{}` is not a selector", self.name(ctxt)).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cameleon_genapi::{
+        builder::GenApiBuilder,
+        elem_type::{AccessMode, CachingMode},
+        store::DefaultNodeStore,
+    };
+
+    use super::{
+        super::{tests::MemoryControl, DefaultGenApiCtxt},
+        *,
+    };
+
+    const SELECTOR_ADDRESS: usize = 0x10;
+    const GAIN_ADDRESS: usize = 0x20;
+
+    /// Builds a context where `GainSelector` selects `Gain`, which is stored at
+    /// `GAIN_ADDRESS + GainSelector`. The memory holds gains of `GainSelector` from `0` to `2`.
+    fn params_ctxt(
+        selector_max: i64,
+        selector: u8,
+    ) -> ParamsCtxt<MemoryControl, DefaultGenApiCtxt> {
+        let mut builder =
+            GenApiBuilder::<DefaultNodeStore>::default().programmatic("Model", "Vendor");
+        builder.port("Device").finish();
+        builder
+            .int_reg("GainSelectorReg", "Device")
+            .address(SELECTOR_ADDRESS as i64)
+            .length(1)
+            .access_mode(AccessMode::RW)
+            .cacheable(CachingMode::NoCache)
+            .finish();
+        builder
+            .integer("GainSelector")
+            .p_value("GainSelectorReg")
+            .min(0)
+            .max(selector_max)
+            .p_selected("Gain")
+            .finish();
+        builder
+            .int_reg("Gain", "Device")
+            .address(GAIN_ADDRESS as i64)
+            .p_address("GainSelector")
+            .length(1)
+            .cacheable(CachingMode::NoCache)
+            .finish();
+        builder.integer("Width").value(640).finish();
+        let (reg_desc, node_store, value_ctxt) = builder.build();
+
+        let mut memory = vec![0; GAIN_ADDRESS + 3];
+        memory[SELECTOR_ADDRESS] = selector;
+        memory[GAIN_ADDRESS..].copy_from_slice(&[10, 20, 30]);
+        ParamsCtxt {
+            ctrl: MemoryControl::new(memory),
+            ctxt: DefaultGenApiCtxt {
+                node_store,
+                value_ctxt,
+                reg_desc,
+            },
+        }
+    }
+
+    #[test]
+    fn test_selected_features() {
+        let ctxt = params_ctxt(2, 0);
+        let selector = ctxt.node("GainSelector").unwrap();
+        let gain = ctxt.node("Gain").unwrap();
+
+        assert!(selector.is_selector(&ctxt));
+        assert_eq!(selector.selected_features(&ctxt), [gain]);
+        assert!(!gain.is_selector(&ctxt));
+        assert!(gain.selected_features(&ctxt).is_empty());
+    }
+
+    #[test]
+    fn test_selector_values() {
+        let mut ctxt = params_ctxt(2, 1);
+        let selector = ctxt.node("GainSelector").unwrap();
+
+        assert_eq!(
+            selector.selector_values(&mut ctxt).unwrap(),
+            [
+                SelectorValue::Integer(0),
+                SelectorValue::Integer(1),
+                SelectorValue::Integer(2)
+            ]
+        );
+        assert_eq!(
+            selector.selector_value(&mut ctxt).unwrap(),
+            SelectorValue::Integer(1)
+        );
+        selector
+            .set_selector_value(&mut ctxt, SelectorValue::Integer(2))
+            .unwrap();
+        assert_eq!(ctxt.ctrl.memory[SELECTOR_ADDRESS], 2);
+
+        let gain = ctxt.node("Gain").unwrap();
+        assert!(gain.selector_values(&mut ctxt).is_err());
+    }
+
+    #[test]
+    fn test_selector_values_near_max() {
+        let mut builder =
+            GenApiBuilder::<DefaultNodeStore>::default().programmatic("Model", "Vendor");
+        builder
+            .integer("Selector")
+            .value(i64::MAX)
+            .min(i64::MAX - 2)
+            .max(i64::MAX)
+            .inc(2)
+            .p_selected("Width")
+            .finish();
+        builder.integer("Width").value(640).finish();
+        let (reg_desc, node_store, value_ctxt) = builder.build();
+        let mut ctxt = ParamsCtxt {
+            ctrl: MemoryControl::default(),
+            ctxt: DefaultGenApiCtxt {
+                node_store,
+                value_ctxt,
+                reg_desc,
+            },
+        };
+
+        let selector = ctxt.node("Selector").unwrap();
+        assert_eq!(
+            selector.selector_values(&mut ctxt).unwrap(),
+            [
+                SelectorValue::Integer(i64::MAX - 2),
+                SelectorValue::Integer(i64::MAX)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_values_by_selector() {
+        let mut ctxt = params_ctxt(2, 1);
+        let selector = ctxt.node("GainSelector").unwrap();
+        let gain = ctxt.node("Gain").unwrap();
+
+        assert_eq!(
+            selector.values_by_selector(&mut ctxt, gain).unwrap(),
+            [
+                (SelectorValue::Integer(0), FeatureValue::Integer(10)),
+                (SelectorValue::Integer(1), FeatureValue::Integer(20)),
+                (SelectorValue::Integer(2), FeatureValue::Integer(30))
+            ]
+        );
+        assert_eq!(ctxt.ctrl.memory[SELECTOR_ADDRESS], 1);
+
+        let width = ctxt.node("Width").unwrap();
+        assert!(matches!(
+            selector.values_by_selector(&mut ctxt, width),
+            Err(GenApiError::InvalidNode(_))
+        ));
+    }
+
+    #[test]
+    fn test_values_by_selector_restores_on_error() {
+        // Reading `Gain` fails when `GainSelector` is `3`.
+        let mut ctxt = params_ctxt(3, 1);
+        let selector = ctxt.node("GainSelector").unwrap();
+        let gain = ctxt.node("Gain").unwrap();
+
+        let err = selector.values_by_selector(&mut ctxt, gain).unwrap_err();
+        assert!(err.to_string().contains("out of range"));
+        assert_eq!(ctxt.ctrl.memory[SELECTOR_ADDRESS], 1);
+    }
+
+    #[test]
+    fn test_values_by_selector_error_precedence() {
+        // Reading `Gain` fails, then restoring `GainSelector` fails.
+        let mut ctxt = params_ctxt(3, 1);
+        ctxt.ctrl.remaining_writes = Some(4);
+        let selector = ctxt.node("GainSelector").unwrap();
+        let gain = ctxt.node("Gain").unwrap();
+
+        let err = selector.values_by_selector(&mut ctxt, gain).unwrap_err();
+        assert!(err.to_string().contains("out of range"));
+        assert_eq!(ctxt.ctrl.memory[SELECTOR_ADDRESS], 3);
+
+        // Only restoring `GainSelector` fails.
+        let mut ctxt = params_ctxt(2, 1);
+        ctxt.ctrl.remaining_writes = Some(3);
+        let err = selector.values_by_selector(&mut ctxt, gain).unwrap_err();
+        assert!(err.to_string().contains("write failed"));
+        assert_eq!(ctxt.ctrl.memory[SELECTOR_ADDRESS], 2);
+    }
+}
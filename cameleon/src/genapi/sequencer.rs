@@ -0,0 +1,368 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains a builder to program sequencer sets via `SequencerControl` features
+//! defined in `SFNC`.
+//!
+//! # Examples
+//! ```rust
+//! # use cameleon::u3v;
+//! # let mut cameras = u3v::enumerate_cameras().unwrap();
+//! # if cameras.is_empty() {
+//! #     return;
+//! # }
+//! # let mut camera = cameras.pop().unwrap();
+//! # camera.open().unwrap();
+//! use cameleon::genapi::{FeatureValue, SequencerBuilder, SequencerPath, SequencerSet};
+//!
+//! camera.load_context().unwrap();
+//! let mut params_ctxt = camera.params_ctxt().unwrap();
+//!
+//! // Alternates short and long exposures for HDR, switching the set at the end of each frame.
+//! let set = |index, exposure_time, next_set| {
+//!     SequencerSet::new(index)
+//!         .value("ExposureTime", FeatureValue::Float(exposure_time))
+//!         .path(SequencerPath::new(0, next_set).trigger_source("FrameEnd"))
+//! };
+//! SequencerBuilder::new()
+//!     .set(set(0, 1000.0, 1))
+//!     .set(set(1, 8000.0, 0))
+//!     .program(&mut params_ctxt)
+//!     .unwrap();
+//!
+//! # camera.close().unwrap();
+//! ```
+
+use std::{collections::HashSet, time::Duration};
+
+use cameleon_genapi::{GenApiError, GenApiResult};
+
+use super::{
+    node_kind::{BooleanNode, CommandNode, EnumerationNode, FeatureValue, IntegerNode},
+    DeviceControl, GenApiCtxt, ParamsCtxt,
+};
+
+/// Maximum duration to wait for `SequencerSetSave` to complete.
+const SAVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A builder to program sequencer sets of the device.
+///
+/// [`Self::program`] configures the device in the order `SFNC` requires:
+/// 1. Turns `SequencerMode` off and `SequencerConfigurationMode` on.
+/// 2. Enables features used in the sets by `SequencerFeatureEnable`.
+/// 3. For each set, selects the set, writes the feature values and the paths, then saves the set
+///    by `SequencerSetSave`.
+/// 4. Sets `SequencerSetStart`, then turns `SequencerConfigurationMode` off.
+/// 5. Turns `SequencerMode` on if the sequencer is to be activated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequencerBuilder {
+    sets: Vec<SequencerSet>,
+    start_set: i64,
+    activate: bool,
+}
+
+/// A sequencer set, i.e. feature values which are applied while the set is active, and paths to
+/// the next sets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequencerSet {
+    index: i64,
+    values: Vec<(String, FeatureValue)>,
+    paths: Vec<SequencerPath>,
+}
+
+/// A path of a sequencer set, which transits to the next set when the trigger of the path is
+/// activated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequencerPath {
+    index: i64,
+    next_set: i64,
+    trigger_source: Option<String>,
+    trigger_activation: Option<String>,
+}
+
+impl SequencerBuilder {
+    /// Constructs a builder without sets.
+    ///
+    /// The sequencer starts from set `0`, and is activated after programming by default.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            sets: vec![],
+            start_set: 0,
+            activate: true,
+        }
+    }
+
+    /// Adds a sequencer set.
+    #[must_use]
+    pub fn set(mut self, set: SequencerSet) -> Self {
+        self.sets.push(set);
+        self
+    }
+
+    /// Sets the index of the set which the sequencer starts from.
+    #[must_use]
+    pub fn start_set(mut self, index: i64) -> Self {
+        self.start_set = index;
+        self
+    }
+
+    /// Sets whether [`Self::program`] turns `SequencerMode` on after programming the sets.
+    #[must_use]
+    pub fn activate(mut self, activate: bool) -> Self {
+        self.activate = activate;
+        self
+    }
+
+    /// Programs the sets to the device.
+    ///
+    /// Returns [`GenApiError::InvalidData`] if the sets are inconsistent, e.g. a path refers to
+    /// a set which is not added, and [`GenApiError::InvalidNode`] if the context lacks a node
+    /// required for the sequencer. Returns [`ControlError::Timeout`](crate::ControlError::Timeout)
+    /// wrapped in [`GenApiError::Device`] if saving a set doesn't complete.
+    /// `SequencerConfigurationMode` is turned off even if programming fails halfway.
+    pub fn program<Ctrl, Ctxt>(&self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        self.validate()?;
+        let nodes = SequencerNodes::new(ctxt)?;
+
+        nodes.mode.set_entry_by_symbolic(ctxt, "Off")?;
+        if let Some(configuration_mode) = nodes.configuration_mode {
+            configuration_mode.set_entry_by_symbolic(ctxt, "On")?;
+        }
+        let res = self.program_sets(ctxt, &nodes);
+        let close_res = match nodes.configuration_mode {
+            Some(configuration_mode) => configuration_mode.set_entry_by_symbolic(ctxt, "Off"),
+            None => Ok(()),
+        };
+        res?;
+        close_res?;
+
+        if self.activate {
+            nodes.mode.set_entry_by_symbolic(ctxt, "On")?;
+        }
+        Ok(())
+    }
+
+    fn program_sets<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        nodes: &SequencerNodes,
+    ) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        if let Some((feature_selector, feature_enable)) = nodes.feature_enable {
+            let mut enabled = HashSet::new();
+            for (name, _) in self.sets.iter().flat_map(|set| &set.values) {
+                if enabled.insert(name) {
+                    feature_selector.set_entry_by_symbolic(ctxt, name)?;
+                    feature_enable.set_value(ctxt, true)?;
+                }
+            }
+        }
+
+        for set in &self.sets {
+            nodes.set_selector.set_value(ctxt, set.index)?;
+
+            for (name, value) in &set.values {
+                let node = ctxt.node(name).ok_or_else(|| {
+                    GenApiError::InvalidNode(format!("missing `{}`", name).into())
+                })?;
+                node.set_feature_value(ctxt, value.clone())?;
+            }
+
+            for path in &set.paths {
+                match nodes.path_selector {
+                    Some(path_selector) => path_selector.set_value(ctxt, path.index)?,
+                    None if path.index == 0 => {}
+                    None => {
+                        return Err(GenApiError::InvalidNode(
+                            "`SequencerPathSelector` is required for multiple paths".into(),
+                        ))
+                    }
+                }
+                nodes.set_next.set_value(ctxt, path.next_set)?;
+                if let Some(source) = &path.trigger_source {
+                    nodes.trigger_source.set_entry_by_symbolic(ctxt, source)?;
+                }
+                if let Some(activation) = &path.trigger_activation {
+                    let trigger_activation = nodes.trigger_activation.ok_or_else(|| {
+                        GenApiError::InvalidNode("missing `SequencerTriggerActivation`".into())
+                    })?;
+                    trigger_activation.set_entry_by_symbolic(ctxt, activation)?;
+                }
+            }
+
+            nodes.set_save.execute_and_wait(ctxt, SAVE_TIMEOUT)?;
+        }
+
+        nodes.set_start.set_value(ctxt, self.start_set)
+    }
+
+    fn validate(&self) -> GenApiResult<()> {
+        let invalid = |msg: String| Err(GenApiError::InvalidData(msg.into()));
+
+        let mut indices = HashSet::new();
+        for set in &self.sets {
+            if !indices.insert(set.index) {
+                return invalid(format!("sequencer set {} is added twice", set.index));
+            }
+        }
+        if !indices.contains(&self.start_set) {
+            return invalid(format!("start set {} is not added", self.start_set));
+        }
+        for set in &self.sets {
+            for path in &set.paths {
+                if !indices.contains(&path.next_set) {
+                    return invalid(format!(
+                        "path {} of set {} refers to set {} which is not added",
+                        path.index, set.index, path.next_set
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for SequencerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SequencerSet {
+    /// Constructs a set of `index` without feature values and paths.
+    #[must_use]
+    pub fn new(index: i64) -> Self {
+        Self {
+            index,
+            values: vec![],
+            paths: vec![],
+        }
+    }
+
+    /// Adds a value of a feature which is applied while the set is active.
+    ///
+    /// The feature must be an entry of `SequencerFeatureSelector` if the device has it.
+    #[must_use]
+    pub fn value(mut self, feature: impl Into<String>, value: FeatureValue) -> Self {
+        self.values.push((feature.into(), value));
+        self
+    }
+
+    /// Adds a path to the next set.
+    #[must_use]
+    pub fn path(mut self, path: SequencerPath) -> Self {
+        self.paths.push(path);
+        self
+    }
+}
+
+impl SequencerPath {
+    /// Constructs a path of `index` which transits to `next_set`.
+    ///
+    /// `SequencerTriggerSource` and `SequencerTriggerActivation` are left as they are unless
+    /// specified.
+    #[must_use]
+    pub fn new(index: i64, next_set: i64) -> Self {
+        Self {
+            index,
+            next_set,
+            trigger_source: None,
+            trigger_activation: None,
+        }
+    }
+
+    /// Sets the symbolic name of `SequencerTriggerSource` of the path, e.g. `FrameEnd` or
+    /// `Line0`.
+    #[must_use]
+    pub fn trigger_source(mut self, source: impl Into<String>) -> Self {
+        self.trigger_source = Some(source.into());
+        self
+    }
+
+    /// Sets the symbolic name of `SequencerTriggerActivation` of the path, e.g. `RisingEdge`.
+    #[must_use]
+    pub fn trigger_activation(mut self, activation: impl Into<String>) -> Self {
+        self.trigger_activation = Some(activation.into());
+        self
+    }
+}
+
+/// Nodes of `SequencerControl`.
+struct SequencerNodes {
+    mode: EnumerationNode,
+    configuration_mode: Option<EnumerationNode>,
+    feature_enable: Option<(EnumerationNode, BooleanNode)>,
+    set_selector: IntegerNode,
+    set_save: CommandNode,
+    set_start: IntegerNode,
+    path_selector: Option<IntegerNode>,
+    set_next: IntegerNode,
+    trigger_source: EnumerationNode,
+    trigger_activation: Option<EnumerationNode>,
+}
+
+impl SequencerNodes {
+    fn new<Ctrl, Ctxt>(ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<Self>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        macro_rules! node {
+            ($name:expr, $downcast:ident) => {
+                ctxt.node($name).and_then(|node| node.$downcast(ctxt))
+            };
+        }
+        macro_rules! required_node {
+            ($name:expr, $downcast:ident) => {
+                node!($name, $downcast).ok_or_else(|| {
+                    GenApiError::InvalidNode(
+                        format!("`{}` is required for sequencer", $name).into(),
+                    )
+                })?
+            };
+        }
+
+        let feature_enable = node!("SequencerFeatureSelector", as_enumeration)
+            .zip(node!("SequencerFeatureEnable", as_boolean));
+        Ok(Self {
+            mode: required_node!("SequencerMode", as_enumeration),
+            configuration_mode: node!("SequencerConfigurationMode", as_enumeration),
+            feature_enable,
+            set_selector: required_node!("SequencerSetSelector", as_integer),
+            set_save: required_node!("SequencerSetSave", as_command),
+            set_start: required_node!("SequencerSetStart", as_integer),
+            path_selector: node!("SequencerPathSelector", as_integer),
+            set_next: required_node!("SequencerSetNext", as_integer),
+            trigger_source: required_node!("SequencerTriggerSource", as_enumeration),
+            trigger_activation: node!("SequencerTriggerActivation", as_enumeration),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let set = |index, next_set| SequencerSet::new(index).path(SequencerPath::new(0, next_set));
+
+        let builder = SequencerBuilder::new().set(set(0, 1)).set(set(1, 0));
+        assert!(builder.validate().is_ok());
+        assert!(builder.clone().start_set(1).validate().is_ok());
+        assert!(builder.clone().start_set(2).validate().is_err());
+        assert!(builder.clone().set(set(1, 1)).validate().is_err());
+
+        let builder = SequencerBuilder::new().set(set(0, 1));
+        assert!(builder.validate().is_err());
+        assert!(SequencerBuilder::new().validate().is_err());
+    }
+}
@@ -0,0 +1,227 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains [`NodeStoreCache`] to share a parsed [`NodeStore`](super::NodeStore) among cameras of the
+//! same model.
+//!
+//! # Examples
+//! ```rust
+//! # use cameleon::u3v;
+//! use cameleon::{
+//!     genapi::{NodeStoreCache, SharedDefaultGenApiCtxt},
+//!     Camera,
+//! };
+//!
+//! let cache = NodeStoreCache::new();
+//! let cameras = u3v::enumerate_cameras().unwrap();
+//!
+//! for camera in cameras {
+//!     let mut camera: Camera<u3v::ControlHandle, u3v::StreamHandle, SharedDefaultGenApiCtxt> =
+//!         camera.convert_into();
+//!     camera.open().unwrap();
+//!     // The xml is parsed only once for each camera model.
+//!     camera.load_context_with_cache(&cache).unwrap();
+//!     camera.close().unwrap();
+//! }
+//! ```
+
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use cameleon_genapi::builder::GenApiBuilder;
+
+use super::{
+    xml_hash, CacheSink, ControlError, ControlResult, DefaultCacheStore, DefaultNodeStore,
+    DefaultValueStore, GenApiCtxt, RegisterDescription, SharedDefaultGenApiCtxt,
+    SharedNoCacheGenApiCtxt, ValueCtxt,
+};
+
+/// A cache of parsed `GenApi` contexts keyed by the hash of `GenApi` xml.
+///
+/// [`NodeStore`](super::NodeStore) and [`RegisterDescription`] are immutable once they are built,
+/// so contexts loaded from the same xml share them, while each context has its own value and
+/// cache stores.
+///
+/// Only contexts which hold the node store and the register description in [`Arc`], i.e.
+/// [`SharedDefaultGenApiCtxt`] and [`SharedNoCacheGenApiCtxt`], can be built from the cache. See
+/// [`FromNodeStoreCache`].
+///
+/// Cloned caches share the entries.
+#[derive(Debug, Clone, Default)]
+pub struct NodeStoreCache {
+    entries: Arc<Mutex<HashMap<[u8; 20], CacheEntry>>>,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    node_store: Arc<DefaultNodeStore>,
+    reg_desc: Arc<RegisterDescription>,
+    /// The value context right after the xml is parsed, cloned for each context.
+    value_ctxt: ValueCtxt<DefaultValueStore, DefaultCacheStore>,
+}
+
+impl NodeStoreCache {
+    /// Creates an empty cache.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of cached xmls.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Returns `true` if no xml is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all entries from the cache.
+    ///
+    /// Contexts already built from the cache are not affected.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// Builds a context from `xml`, parses `xml` only if it's not cached yet.
+    pub fn load<Ctxt>(&self, xml: &impl AsRef<str>) -> ControlResult<Ctxt>
+    where
+        Ctxt: FromNodeStoreCache + GenApiCtxt,
+    {
+        let key = xml_hash(xml);
+
+        let mut entries = self.entries.lock().unwrap();
+        let entry = match entries.entry(key) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let (reg_desc, node_store, value_ctxt) =
+                    GenApiBuilder::<DefaultNodeStore>::default()
+                        .build(xml)
+                        .map_err(|e| ControlError::InvalidData(e.into()))?;
+                entry.insert(CacheEntry {
+                    node_store: Arc::new(node_store),
+                    reg_desc: Arc::new(reg_desc),
+                    value_ctxt,
+                })
+            }
+        };
+
+        Ok(Ctxt::from_parts(
+            entry.node_store.clone(),
+            entry.reg_desc.clone(),
+            entry.value_ctxt.clone(),
+        ))
+    }
+}
+
+/// A trait to build a `GenApi` context from parts shared via [`NodeStoreCache`].
+pub trait FromNodeStoreCache {
+    /// Builds a context from the shared node store and register description, and the value
+    /// context owned by the built context.
+    fn from_parts(
+        node_store: Arc<DefaultNodeStore>,
+        reg_desc: Arc<RegisterDescription>,
+        value_ctxt: ValueCtxt<DefaultValueStore, DefaultCacheStore>,
+    ) -> Self;
+}
+
+impl FromNodeStoreCache for SharedDefaultGenApiCtxt {
+    fn from_parts(
+        node_store: Arc<DefaultNodeStore>,
+        reg_desc: Arc<RegisterDescription>,
+        value_ctxt: ValueCtxt<DefaultValueStore, DefaultCacheStore>,
+    ) -> Self {
+        Self {
+            node_store,
+            value_ctxt: Arc::new(Mutex::new(value_ctxt)),
+            reg_desc,
+        }
+    }
+}
+
+impl FromNodeStoreCache for SharedNoCacheGenApiCtxt {
+    fn from_parts(
+        node_store: Arc<DefaultNodeStore>,
+        reg_desc: Arc<RegisterDescription>,
+        value_ctxt: ValueCtxt<DefaultValueStore, DefaultCacheStore>,
+    ) -> Self {
+        Self {
+            node_store,
+            value_ctxt: Arc::new(Mutex::new(ValueCtxt::new(
+                value_ctxt.value_store,
+                CacheSink::default(),
+            ))),
+            reg_desc,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{
+            tests::{MemoryControl, IMMEDIATE_VALUE_XML},
+            ParamsCtxt,
+        },
+        *,
+    };
+
+    #[test]
+    fn test_reuse_parsed_xml() {
+        let cache = NodeStoreCache::new();
+        let ctxt0: SharedDefaultGenApiCtxt = cache.load(&IMMEDIATE_VALUE_XML).unwrap();
+        let ctxt1: SharedNoCacheGenApiCtxt = cache.load(&IMMEDIATE_VALUE_XML).unwrap();
+
+        assert_eq!(cache.len(), 1);
+        assert!(Arc::ptr_eq(&ctxt0.node_store, &ctxt1.node_store));
+        assert!(Arc::ptr_eq(&ctxt0.reg_desc, &ctxt1.reg_desc));
+
+        // A different xml is parsed and cached separately.
+        let xml = IMMEDIATE_VALUE_XML.replace("640", "1280");
+        let ctxt2: SharedDefaultGenApiCtxt = cache.load(&xml).unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(!Arc::ptr_eq(&ctxt0.node_store, &ctxt2.node_store));
+
+        cache.clear();
+        assert!(cache.is_empty());
+        let ctxt3: SharedDefaultGenApiCtxt = cache.load(&IMMEDIATE_VALUE_XML).unwrap();
+        assert!(!Arc::ptr_eq(&ctxt0.node_store, &ctxt3.node_store));
+    }
+
+    #[test]
+    fn test_value_ctxt_is_not_shared() {
+        let cache = NodeStoreCache::new();
+        let mut ctxt0 = ParamsCtxt {
+            ctrl: MemoryControl::default(),
+            ctxt: cache
+                .load::<SharedDefaultGenApiCtxt>(&IMMEDIATE_VALUE_XML)
+                .unwrap(),
+        };
+        let width = ctxt0.node("Width").unwrap().as_integer(&ctxt0).unwrap();
+        width.set_value(&mut ctxt0, 320).unwrap();
+
+        // A context loaded later starts with the values right after parsing.
+        let mut ctxt1 = ParamsCtxt {
+            ctrl: MemoryControl::default(),
+            ctxt: cache
+                .load::<SharedDefaultGenApiCtxt>(&IMMEDIATE_VALUE_XML)
+                .unwrap(),
+        };
+        assert_eq!(width.value(&mut ctxt1).unwrap(), 640);
+        assert_eq!(width.value(&mut ctxt0).unwrap(), 320);
+    }
+
+    #[test]
+    fn test_invalid_xml_is_not_cached() {
+        let cache = NodeStoreCache::new();
+        assert!(matches!(
+            cache.load::<SharedDefaultGenApiCtxt>(&"<RegisterDescription>"),
+            Err(ControlError::InvalidData(_))
+        ));
+        assert!(cache.is_empty());
+    }
+}
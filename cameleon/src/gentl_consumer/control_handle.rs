@@ -0,0 +1,266 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains device control implementation over the remote device port of a
+//! `GenTL` producer.
+
+use std::sync::Arc;
+
+use tracing::error;
+
+use crate::{
+    camera::DeviceControl,
+    genapi::{self, CompressionType},
+    ControlError, ControlResult,
+};
+
+use super::{device::DeviceModule, ffi};
+
+/// Controls a device through the remote device port of a `GenTL` producer.
+pub struct ControlHandle {
+    device: Arc<DeviceModule>,
+}
+
+impl ControlHandle {
+    pub(super) fn new(device: Arc<DeviceModule>) -> Self {
+        Self { device }
+    }
+
+    /// Returns the URL of the `GenApi` xml which the producer reports first.
+    pub fn xml_url(&self) -> ControlResult<String> {
+        let port = self.device.port()?;
+        let (producer, api) = (&self.device.producer, &self.device.producer.api);
+
+        unsafe {
+            let mut num_urls = 0;
+            producer.check(
+                "GCGetNumPortURLs",
+                (api.GCGetNumPortURLs)(port, &mut num_urls),
+            )?;
+            if num_urls == 0 {
+                return Err(ControlError::InvalidDevice(
+                    "device doesn't provide any `GenApi` xml URL".into(),
+                ));
+            }
+
+            let url = producer.string_info("GCGetPortURLInfo", |buf, size| {
+                let mut ty = 0;
+                (api.GCGetPortURLInfo)(port, 0, ffi::URL_INFO_URL, &mut ty, buf, size)
+            })?;
+            Ok(url.to_string_lossy().into_owned())
+        }
+    }
+}
+
+impl DeviceControl for ControlHandle {
+    fn open(&mut self) -> ControlResult<()> {
+        self.device.open()
+    }
+
+    fn close(&mut self) -> ControlResult<()> {
+        self.device.close()
+    }
+
+    fn is_opened(&self) -> bool {
+        self.device.is_opened()
+    }
+
+    fn read(&mut self, address: u64, buf: &mut [u8]) -> ControlResult<()> {
+        let port = self.device.port()?;
+        let (producer, api) = (&self.device.producer, &self.device.producer.api);
+
+        let mut size = buf.len();
+        producer.check("GCReadPort", unsafe {
+            (api.GCReadPort)(port, address, buf.as_mut_ptr().cast(), &mut size)
+        })?;
+        if size != buf.len() {
+            return Err(ControlError::Io(anyhow::Error::msg(format!(
+                "`GCReadPort` read {} bytes, but {} bytes are requested",
+                size,
+                buf.len()
+            ))));
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, address: u64, data: &[u8]) -> ControlResult<()> {
+        let port = self.device.port()?;
+        let (producer, api) = (&self.device.producer, &self.device.producer.api);
+
+        let mut size = data.len();
+        producer.check("GCWritePort", unsafe {
+            (api.GCWritePort)(port, address, data.as_ptr().cast(), &mut size)
+        })?;
+        if size != data.len() {
+            return Err(ControlError::Io(anyhow::Error::msg(format!(
+                "`GCWritePort` wrote {} bytes, but {} bytes are requested",
+                size,
+                data.len()
+            ))));
+        }
+        Ok(())
+    }
+
+    fn genapi(&mut self) -> ControlResult<String> {
+        let url = self.xml_url()?;
+        let (file, file_name) = match XmlUrl::parse(&url)? {
+            XmlUrl::Local {
+                file_name,
+                address,
+                size,
+            } => {
+                let mut buf = vec![0; size];
+                self.read(address, &mut buf)?;
+                (buf, file_name)
+            }
+            XmlUrl::File { path } => {
+                let file = std::fs::read(&path).map_err(|e| ControlError::Io(e.into()))?;
+                (file, path)
+            }
+        };
+
+        let comp_type = if file_name.to_ascii_lowercase().ends_with(".zip") {
+            CompressionType::Zip
+        } else {
+            CompressionType::Uncompressed
+        };
+        genapi::decode_xml(file, comp_type)
+    }
+
+    /// Streaming is controlled by the data stream module, so this is a no-op.
+    fn enable_streaming(&mut self) -> ControlResult<()> {
+        Ok(())
+    }
+
+    /// Streaming is controlled by the data stream module, so this is a no-op.
+    fn disable_streaming(&mut self) -> ControlResult<()> {
+        Ok(())
+    }
+}
+
+impl Drop for ControlHandle {
+    fn drop(&mut self) {
+        if let Err(e) = self.close() {
+            error!(?e)
+        }
+    }
+}
+
+impl From<ControlHandle> for Box<dyn DeviceControl> {
+    fn from(ctrl: ControlHandle) -> Self {
+        Box::new(ctrl)
+    }
+}
+
+/// Location of a `GenApi` xml reported by `GCGetPortURLInfo`.
+#[derive(Debug, PartialEq, Eq)]
+enum XmlUrl {
+    /// `local:[///]filename.extension;address;length[?SchemaVersion=x.x.x]`, where `address`
+    /// and `length` are hexadecimal.
+    Local {
+        file_name: String,
+        address: u64,
+        size: usize,
+    },
+    /// `file:///path[?SchemaVersion=x.x.x]`.
+    File { path: String },
+}
+
+impl XmlUrl {
+    fn parse(url: &str) -> ControlResult<Self> {
+        let invalid = || ControlError::InvalidDevice(format!("invalid xml URL: {}", url).into());
+        let url = url.split('?').next().unwrap();
+        let (scheme, rest) = url.split_once(':').ok_or_else(invalid)?;
+
+        match scheme.to_ascii_lowercase().as_str() {
+            "local" => {
+                let mut fields = rest.trim_start_matches('/').split(';');
+                let (file_name, address, size) =
+                    match (fields.next(), fields.next(), fields.next(), fields.next()) {
+                        (Some(file_name), Some(address), Some(size), None) => {
+                            (file_name, address, size)
+                        }
+                        _ => return Err(invalid()),
+                    };
+                let hex = |s: &str| {
+                    let s = s.trim_start_matches("0x").trim_start_matches("0X");
+                    u64::from_str_radix(s, 16).map_err(|_| invalid())
+                };
+                Ok(Self::Local {
+                    file_name: file_name.to_string(),
+                    address: hex(address)?,
+                    size: hex(size)? as usize,
+                })
+            }
+            "file" => {
+                let path = rest.strip_prefix("//").ok_or_else(invalid)?;
+                // Strip the leading slash of an absolute path with a drive letter, e.g.
+                // `file:///C:/xml/device.xml`.
+                let path = match path.strip_prefix('/') {
+                    Some(stripped) if stripped.get(1..2) == Some(":") => stripped,
+                    _ => path,
+                };
+                Ok(Self::File {
+                    path: path.to_string(),
+                })
+            }
+            _ => Err(ControlError::InvalidDevice(
+                format!("unsupported xml URL scheme: {}", url).into(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_local_url() {
+        let url = "local:///device.zip;1000;2a0?SchemaVersion=1.1.0";
+        assert_eq!(
+            XmlUrl::parse(url).unwrap(),
+            XmlUrl::Local {
+                file_name: "device.zip".into(),
+                address: 0x1000,
+                size: 0x2a0
+            }
+        );
+
+        let url = "Local:device.xml;0x8000;0X100";
+        assert_eq!(
+            XmlUrl::parse(url).unwrap(),
+            XmlUrl::Local {
+                file_name: "device.xml".into(),
+                address: 0x8000,
+                size: 0x100
+            }
+        );
+
+        assert!(XmlUrl::parse("local:///device.xml;1000").is_err());
+        assert!(XmlUrl::parse("local:///device.xml;1000;zz").is_err());
+    }
+
+    #[test]
+    fn test_parse_file_url() {
+        assert_eq!(
+            XmlUrl::parse("file:///home/user/device.xml?SchemaVersion=1.0.0").unwrap(),
+            XmlUrl::File {
+                path: "/home/user/device.xml".into()
+            }
+        );
+        assert_eq!(
+            XmlUrl::parse("file:///C:/xml/device.zip").unwrap(),
+            XmlUrl::File {
+                path: "C:/xml/device.zip".into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_unsupported_url() {
+        assert!(XmlUrl::parse("http://www.example.com/device.xml").is_err());
+        assert!(XmlUrl::parse("device.xml").is_err());
+    }
+}
@@ -0,0 +1,194 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{
+    ffi::CString,
+    ptr,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use tracing::error;
+
+use crate::{ControlError, ControlResult, StreamError, StreamResult};
+
+use super::{
+    ffi::{self, DEV_HANDLE, DS_HANDLE, IF_HANDLE, PORT_HANDLE},
+    producer::ProducerInner,
+    stream_handle::Acquisition,
+};
+
+/// A device module shared by [`super::ControlHandle`] and [`super::StreamHandle`].
+///
+/// The data stream is owned by the device so that closing the device also closes its data
+/// stream, regardless of the order the handles are closed or dropped.
+pub(super) struct DeviceModule {
+    pub(super) producer: Arc<ProducerInner>,
+    iface: IF_HANDLE,
+    id: CString,
+    state: Mutex<Option<OpenedDevice>>,
+}
+
+pub(super) struct OpenedDevice {
+    pub(super) dev: DEV_HANDLE,
+    pub(super) port: PORT_HANDLE,
+    pub(super) stream: Option<OpenedStream>,
+}
+
+pub(super) struct OpenedStream {
+    pub(super) ds: DS_HANDLE,
+    pub(super) acquisition: Option<Acquisition>,
+}
+
+// SAFETY: See `ProducerInner`.
+unsafe impl Send for DeviceModule {}
+unsafe impl Sync for DeviceModule {}
+
+impl DeviceModule {
+    pub(super) fn new(producer: Arc<ProducerInner>, iface: IF_HANDLE, id: CString) -> Self {
+        Self {
+            producer,
+            iface,
+            id,
+            state: Mutex::new(None),
+        }
+    }
+
+    pub(super) fn state(&self) -> MutexGuard<'_, Option<OpenedDevice>> {
+        // The state is always kept consistent, so it's safe to ignore poisoning.
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    pub(super) fn is_opened(&self) -> bool {
+        self.state().is_some()
+    }
+
+    pub(super) fn port(&self) -> ControlResult<PORT_HANDLE> {
+        self.state()
+            .as_ref()
+            .map(|opened| opened.port)
+            .ok_or(ControlError::NotOpened)
+    }
+
+    pub(super) fn open(&self) -> ControlResult<()> {
+        let mut state = self.state();
+        if state.is_some() {
+            return Ok(());
+        }
+
+        let (producer, api) = (&self.producer, &self.producer.api);
+        unsafe {
+            let mut dev = ptr::null_mut();
+            producer.check(
+                "IFOpenDevice",
+                (api.IFOpenDevice)(
+                    self.iface,
+                    self.id.as_ptr(),
+                    ffi::DEVICE_ACCESS_CONTROL,
+                    &mut dev,
+                ),
+            )?;
+            let mut port = ptr::null_mut();
+            if let Err(e) = producer.check("DevGetPort", (api.DevGetPort)(dev, &mut port)) {
+                (api.DevClose)(dev);
+                return Err(e.into());
+            }
+
+            *state = Some(OpenedDevice {
+                dev,
+                port,
+                stream: None,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn close(&self) -> ControlResult<()> {
+        let mut state = self.state();
+        let mut opened = match state.take() {
+            Some(opened) => opened,
+            None => return Ok(()),
+        };
+
+        if let Err(e) = self.close_stream_of(&mut opened) {
+            error!(?e);
+        }
+        let api = &self.producer.api;
+        self.producer
+            .check("DevClose", unsafe { (api.DevClose)(opened.dev) })?;
+        Ok(())
+    }
+
+    pub(super) fn open_stream(&self) -> StreamResult<()> {
+        let mut state = self.state();
+        let opened = state
+            .as_mut()
+            .ok_or_else(|| StreamError::Io(anyhow::Error::msg("device is not opened")))?;
+        if opened.stream.is_some() {
+            return Ok(());
+        }
+
+        let (producer, api) = (&self.producer, &self.producer.api);
+        unsafe {
+            let mut num_streams = 0;
+            producer.check(
+                "DevGetNumDataStreams",
+                (api.DevGetNumDataStreams)(opened.dev, &mut num_streams),
+            )?;
+            if num_streams == 0 {
+                return Err(StreamError::Io(anyhow::Error::msg(
+                    "device doesn't have any data stream",
+                )));
+            }
+
+            let ds_id = producer.string_info("DevGetDataStreamID", |buf, size| {
+                (api.DevGetDataStreamID)(opened.dev, 0, buf.cast(), size)
+            })?;
+            let mut ds = ptr::null_mut();
+            producer.check(
+                "DevOpenDataStream",
+                (api.DevOpenDataStream)(opened.dev, ds_id.as_ptr(), &mut ds),
+            )?;
+
+            opened.stream = Some(OpenedStream {
+                ds,
+                acquisition: None,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn close_stream(&self) -> StreamResult<()> {
+        match self.state().as_mut() {
+            Some(opened) => self.close_stream_of(opened),
+            None => Ok(()),
+        }
+    }
+
+    fn close_stream_of(&self, opened: &mut OpenedDevice) -> StreamResult<()> {
+        let mut stream = match opened.stream.take() {
+            Some(stream) => stream,
+            None => return Ok(()),
+        };
+
+        if let Some(acquisition) = stream.acquisition.take() {
+            acquisition.stop(&self.producer, stream.ds)?;
+        }
+        let api = &self.producer.api;
+        self.producer
+            .check("DSClose", unsafe { (api.DSClose)(stream.ds) })?;
+        Ok(())
+    }
+}
+
+impl Drop for DeviceModule {
+    fn drop(&mut self) {
+        if let Err(e) = self.close() {
+            error!(?e);
+        }
+    }
+}
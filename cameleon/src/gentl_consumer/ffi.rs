@@ -0,0 +1,199 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Raw definitions of the `GenTL` C API which are needed to consume a producer.
+#![allow(non_camel_case_types, non_snake_case, clippy::upper_case_acronyms)]
+
+use std::os::raw::{c_char, c_void};
+
+use libloading::Library;
+
+pub(super) type GC_ERROR = i32;
+pub(super) type INFO_DATATYPE = i32;
+pub(super) type bool8_t = u8;
+
+pub(super) type TL_HANDLE = *mut c_void;
+pub(super) type IF_HANDLE = *mut c_void;
+pub(super) type DEV_HANDLE = *mut c_void;
+pub(super) type DS_HANDLE = *mut c_void;
+pub(super) type PORT_HANDLE = *mut c_void;
+pub(super) type BUFFER_HANDLE = *mut c_void;
+pub(super) type EVENTSRC_HANDLE = *mut c_void;
+pub(super) type EVENT_HANDLE = *mut c_void;
+
+pub(super) const GC_ERR_SUCCESS: GC_ERROR = 0;
+pub(super) const GC_ERR_NOT_INITIALIZED: GC_ERROR = -1002;
+pub(super) const GC_ERR_NOT_IMPLEMENTED: GC_ERROR = -1003;
+pub(super) const GC_ERR_RESOURCE_IN_USE: GC_ERROR = -1004;
+pub(super) const GC_ERR_ACCESS_DENIED: GC_ERROR = -1005;
+pub(super) const GC_ERR_INVALID_HANDLE: GC_ERROR = -1006;
+pub(super) const GC_ERR_TIMEOUT: GC_ERROR = -1011;
+pub(super) const GC_ERR_ABORT: GC_ERROR = -1012;
+pub(super) const GC_ERR_NOT_AVAILABLE: GC_ERROR = -1014;
+pub(super) const GC_ERR_BUFFER_TOO_SMALL: GC_ERROR = -1016;
+pub(super) const GC_ERR_BUSY: GC_ERROR = -1022;
+
+pub(super) const GENTL_INFINITE: u64 = 0xFFFF_FFFF_FFFF_FFFF;
+
+pub(super) const DEVICE_INFO_VENDOR: i32 = 1;
+pub(super) const DEVICE_INFO_MODEL: i32 = 2;
+pub(super) const DEVICE_INFO_SERIAL_NUMBER: i32 = 7;
+
+pub(super) const DEVICE_ACCESS_CONTROL: i32 = 3;
+
+pub(super) const URL_INFO_URL: i32 = 0;
+
+pub(super) const STREAM_INFO_PAYLOAD_SIZE: i32 = 7;
+pub(super) const STREAM_INFO_DEFINES_PAYLOADSIZE: i32 = 9;
+pub(super) const STREAM_INFO_BUF_ANNOUNCE_MIN: i32 = 12;
+
+pub(super) const BUFFER_INFO_BASE: i32 = 0;
+pub(super) const BUFFER_INFO_TIMESTAMP: i32 = 3;
+pub(super) const BUFFER_INFO_IS_INCOMPLETE: i32 = 7;
+pub(super) const BUFFER_INFO_SIZE_FILLED: i32 = 9;
+pub(super) const BUFFER_INFO_WIDTH: i32 = 10;
+pub(super) const BUFFER_INFO_HEIGHT: i32 = 11;
+pub(super) const BUFFER_INFO_XOFFSET: i32 = 12;
+pub(super) const BUFFER_INFO_YOFFSET: i32 = 13;
+pub(super) const BUFFER_INFO_FRAMEID: i32 = 16;
+pub(super) const BUFFER_INFO_IMAGEPRESENT: i32 = 17;
+pub(super) const BUFFER_INFO_PAYLOADTYPE: i32 = 19;
+pub(super) const BUFFER_INFO_PIXELFORMAT: i32 = 20;
+pub(super) const BUFFER_INFO_PIXELFORMAT_NAMESPACE: i32 = 21;
+pub(super) const BUFFER_INFO_DELIVERED_IMAGEHEIGHT: i32 = 22;
+pub(super) const BUFFER_INFO_TIMESTAMP_NS: i32 = 28;
+
+pub(super) const PAYLOAD_TYPE_IMAGE: usize = 1;
+pub(super) const PAYLOAD_TYPE_EXTENDED_CHUNK: usize = 4;
+pub(super) const PAYLOAD_TYPE_CHUNK_ONLY: usize = 8;
+pub(super) const PAYLOAD_TYPE_GENDC: usize = 11;
+
+pub(super) const PIXELFORMAT_NAMESPACE_PFNC_32BIT: u64 = 4;
+
+pub(super) const EVENT_NEW_BUFFER: i32 = 1;
+
+pub(super) const ACQ_START_FLAGS_DEFAULT: i32 = 0;
+pub(super) const ACQ_STOP_FLAGS_KILL: i32 = 1;
+pub(super) const ACQ_QUEUE_ALL_DISCARD: i32 = 4;
+
+#[repr(C)]
+pub(super) struct EVENT_NEW_BUFFER_DATA {
+    pub(super) BufferHandle: BUFFER_HANDLE,
+    #[allow(dead_code)]
+    pub(super) pUserPointer: *mut c_void,
+}
+
+macro_rules! define_api {
+    ($($name:ident($($arg:ident: $ty:ty),*);)*) => {
+        /// Function table of a producer.
+        ///
+        /// `GC_CALLTYPE` is `__stdcall` on Windows, which `extern "system"` corresponds to.
+        pub(super) struct Api {
+            $(pub(super) $name: unsafe extern "system" fn($($arg: $ty),*) -> GC_ERROR,)*
+        }
+
+        impl Api {
+            /// Loads all functions from `lib`.
+            ///
+            /// # Safety
+            /// `lib` must be a `GenTL` producer, and must outlive the returned table.
+            pub(super) unsafe fn load(lib: &Library) -> Result<Self, libloading::Error> {
+                Ok(Self {
+                    $($name: *lib.get(concat!(stringify!($name), "\0").as_bytes())?,)*
+                })
+            }
+        }
+    };
+}
+
+define_api! {
+    GCGetLastError(piErrorCode: *mut GC_ERROR, sErrorText: *mut c_char, piSize: *mut usize);
+    GCInitLib();
+    GCCloseLib();
+    GCReadPort(hPort: PORT_HANDLE, iAddress: u64, pBuffer: *mut c_void, piSize: *mut usize);
+    GCWritePort(hPort: PORT_HANDLE, iAddress: u64, pBuffer: *const c_void, piSize: *mut usize);
+    GCGetNumPortURLs(hPort: PORT_HANDLE, piNumURLs: *mut u32);
+    GCGetPortURLInfo(
+        hPort: PORT_HANDLE,
+        iURLIndex: u32,
+        iInfoCmd: i32,
+        piType: *mut INFO_DATATYPE,
+        pBuffer: *mut c_void,
+        piSize: *mut usize
+    );
+    GCRegisterEvent(hEventSrc: EVENTSRC_HANDLE, iEventID: i32, phEvent: *mut EVENT_HANDLE);
+    GCUnregisterEvent(hEventSrc: EVENTSRC_HANDLE, iEventID: i32);
+    EventGetData(hEvent: EVENT_HANDLE, pBuffer: *mut c_void, piSize: *mut usize, iTimeout: u64);
+    EventKill(hEvent: EVENT_HANDLE);
+    TLOpen(phSystem: *mut TL_HANDLE);
+    TLClose(hSystem: TL_HANDLE);
+    TLUpdateInterfaceList(hSystem: TL_HANDLE, pbChanged: *mut bool8_t, iTimeout: u64);
+    TLGetNumInterfaces(hSystem: TL_HANDLE, piNumIfaces: *mut u32);
+    TLGetInterfaceID(hSystem: TL_HANDLE, iIndex: u32, sID: *mut c_char, piSize: *mut usize);
+    TLOpenInterface(hSystem: TL_HANDLE, sIfaceID: *const c_char, phIface: *mut IF_HANDLE);
+    IFClose(hIface: IF_HANDLE);
+    IFUpdateDeviceList(hIface: IF_HANDLE, pbChanged: *mut bool8_t, iTimeout: u64);
+    IFGetNumDevices(hIface: IF_HANDLE, piNumDevices: *mut u32);
+    IFGetDeviceID(hIface: IF_HANDLE, iIndex: u32, sIDeviceID: *mut c_char, piSize: *mut usize);
+    IFGetDeviceInfo(
+        hIface: IF_HANDLE,
+        sDeviceID: *const c_char,
+        iInfoCmd: i32,
+        piType: *mut INFO_DATATYPE,
+        pBuffer: *mut c_void,
+        piSize: *mut usize
+    );
+    IFOpenDevice(
+        hIface: IF_HANDLE,
+        sDeviceID: *const c_char,
+        iOpenFlags: i32,
+        phDevice: *mut DEV_HANDLE
+    );
+    DevGetPort(hDevice: DEV_HANDLE, phRemoteDevice: *mut PORT_HANDLE);
+    DevGetNumDataStreams(hDevice: DEV_HANDLE, piNumDataStreams: *mut u32);
+    DevGetDataStreamID(
+        hDevice: DEV_HANDLE,
+        iIndex: u32,
+        sDataStreamID: *mut c_char,
+        piSize: *mut usize
+    );
+    DevOpenDataStream(
+        hDevice: DEV_HANDLE,
+        sDataStreamID: *const c_char,
+        phDataStream: *mut DS_HANDLE
+    );
+    DevClose(hDevice: DEV_HANDLE);
+    DSAllocAndAnnounceBuffer(
+        hDataStream: DS_HANDLE,
+        iBufferSize: usize,
+        pPrivate: *mut c_void,
+        phBuffer: *mut BUFFER_HANDLE
+    );
+    DSFlushQueue(hDataStream: DS_HANDLE, iOperation: i32);
+    DSStartAcquisition(hDataStream: DS_HANDLE, iStartFlags: i32, iNumToAcquire: u64);
+    DSStopAcquisition(hDataStream: DS_HANDLE, iStopFlags: i32);
+    DSGetInfo(
+        hDataStream: DS_HANDLE,
+        iInfoCmd: i32,
+        piType: *mut INFO_DATATYPE,
+        pBuffer: *mut c_void,
+        piSize: *mut usize
+    );
+    DSGetBufferInfo(
+        hDataStream: DS_HANDLE,
+        hBuffer: BUFFER_HANDLE,
+        iInfoCmd: i32,
+        piType: *mut INFO_DATATYPE,
+        pBuffer: *mut c_void,
+        piSize: *mut usize
+    );
+    DSQueueBuffer(hDataStream: DS_HANDLE, hBuffer: BUFFER_HANDLE);
+    DSRevokeBuffer(
+        hDataStream: DS_HANDLE,
+        hBuffer: BUFFER_HANDLE,
+        pBuffer: *mut *mut c_void,
+        pPrivate: *mut *mut c_void
+    );
+    DSClose(hDataStream: DS_HANDLE);
+}
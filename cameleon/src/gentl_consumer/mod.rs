@@ -0,0 +1,76 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module provides cameras through third-party `GenTL` producers (`.cti` files).
+//!
+//! A producer is loaded dynamically, and its system, interface, device and data stream modules
+//! are walked to construct [`Camera`]s. The remote device port is used as [`DeviceControl`],
+//! and the first data stream of the device is used as [`PayloadStream`].
+//!
+//! [`DeviceControl`]: crate::DeviceControl
+//! [`PayloadStream`]: crate::PayloadStream
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use cameleon::gentl_consumer;
+//!
+//! // Enumerates cameras which the producer can find.
+//! let mut cameras = gentl_consumer::enumerate_cameras("/opt/vendor/lib/producer.cti").unwrap();
+//!
+//! // If no camera is found, return.
+//! if cameras.is_empty() {
+//!     return;
+//! }
+//!
+//! let mut camera = cameras.pop().unwrap();
+//! camera.open().unwrap();
+//! camera.load_context().unwrap();
+//!
+//! // Start streaming. Channel capacity is set to 3.
+//! let payload_rx = camera.start_streaming(3).unwrap();
+//!
+//! let mut payload_count = 0;
+//! while payload_count < 10 {
+//!     if let Ok(payload) = payload_rx.try_recv() {
+//!         println!("{:?}", payload.image_info());
+//!         payload_rx.send_back(payload);
+//!         payload_count += 1;
+//!     }
+//! }
+//!
+//! camera.close().unwrap();
+//! ```
+
+pub mod control_handle;
+pub mod stream_handle;
+
+mod device;
+mod ffi;
+mod producer;
+
+pub use control_handle::ControlHandle;
+pub use producer::Producer;
+pub use stream_handle::{StreamHandle, StreamParams};
+
+use std::path::Path;
+
+use super::{CameleonResult, Camera};
+
+/// Loads the `GenTL` producer at `path` and enumerates all cameras which the producer can find.
+///
+/// Use [`Producer`] directly to enumerate cameras more than once with the same producer.
+///
+/// # Examples
+///
+/// ```no_run
+/// use cameleon::gentl_consumer;
+///
+/// let mut cameras = gentl_consumer::enumerate_cameras("/opt/vendor/lib/producer.cti").unwrap();
+/// ```
+pub fn enumerate_cameras(
+    path: impl AsRef<Path>,
+) -> CameleonResult<Vec<Camera<ControlHandle, StreamHandle>>> {
+    Producer::load(path)?.enumerate_cameras()
+}
@@ -0,0 +1,329 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{
+    collections::HashMap,
+    ffi::{CStr, CString},
+    mem,
+    os::raw::c_void,
+    path::Path,
+    ptr,
+    sync::{Arc, Mutex},
+};
+
+use libloading::Library;
+use tracing::{error, warn};
+
+use crate::{CameleonResult, Camera, CameraInfo, ControlError, StreamError};
+
+use super::{
+    control_handle::ControlHandle,
+    device::DeviceModule,
+    ffi::{self, Api, GC_ERROR, IF_HANDLE, TL_HANDLE},
+    stream_handle::StreamHandle,
+};
+
+/// Timeout of updating the interface and device lists in milliseconds.
+const UPDATE_LIST_TIMEOUT_MS: u64 = 1000;
+
+/// A `GenTL` producer loaded from a `.cti` file.
+///
+/// Cameras enumerated from the producer keep the producer loaded, so it's fine to drop the
+/// `Producer` while the cameras are in use.
+pub struct Producer {
+    inner: Arc<ProducerInner>,
+}
+
+impl Producer {
+    /// Loads the producer at `path` and opens its system module.
+    ///
+    /// # Errors
+    /// Returns [`ControlError::Io`] if `path` isn't a `GenTL` producer, and
+    /// [`ControlError::Busy`] if the producer is already initialized by another consumer in the
+    /// process.
+    pub fn load(path: impl AsRef<Path>) -> CameleonResult<Self> {
+        let path = path.as_ref();
+        let io_err = |e: libloading::Error| {
+            ControlError::Io(anyhow::Error::msg(format!(
+                "failed to load `GenTL` producer {}: {}",
+                path.display(),
+                e
+            )))
+        };
+
+        // SAFETY: A producer doesn't run any code on load, all initialization is done in
+        // `GCInitLib`.
+        let lib = unsafe { Library::new(path) }.map_err(io_err)?;
+        // SAFETY: `lib` is moved into `ProducerInner` together with the table.
+        let api = unsafe { Api::load(&lib) }.map_err(io_err)?;
+
+        unsafe {
+            check(&api, "GCInitLib", (api.GCInitLib)())?;
+            let mut system = ptr::null_mut();
+            if let Err(e) = check(&api, "TLOpen", (api.TLOpen)(&mut system)) {
+                (api.GCCloseLib)();
+                return Err(e.into());
+            }
+
+            Ok(Self {
+                inner: Arc::new(ProducerInner {
+                    api,
+                    system,
+                    interfaces: Mutex::new(HashMap::new()),
+                    _lib: lib,
+                }),
+            })
+        }
+    }
+
+    /// Enumerates all cameras which the producer can find.
+    ///
+    /// Interfaces are opened during enumeration and kept open while the producer or any of
+    /// the returned cameras is alive.
+    ///
+    /// Only the first data stream of each device is exposed as a stream channel.
+    pub fn enumerate_cameras(&self) -> CameleonResult<Vec<Camera<ControlHandle, StreamHandle>>> {
+        let inner = &self.inner;
+        let api = &inner.api;
+        let mut cameras = vec![];
+
+        unsafe {
+            let mut changed = 0;
+            inner.check(
+                "TLUpdateInterfaceList",
+                (api.TLUpdateInterfaceList)(inner.system, &mut changed, UPDATE_LIST_TIMEOUT_MS),
+            )?;
+            let mut num_ifaces = 0;
+            inner.check(
+                "TLGetNumInterfaces",
+                (api.TLGetNumInterfaces)(inner.system, &mut num_ifaces),
+            )?;
+
+            for if_index in 0..num_ifaces {
+                let if_id = inner.string_info("TLGetInterfaceID", |buf, size| {
+                    (api.TLGetInterfaceID)(inner.system, if_index, buf.cast(), size)
+                })?;
+                let iface = inner.open_interface(&if_id)?;
+
+                inner.check(
+                    "IFUpdateDeviceList",
+                    (api.IFUpdateDeviceList)(iface, &mut changed, UPDATE_LIST_TIMEOUT_MS),
+                )?;
+                let mut num_devices = 0;
+                inner.check(
+                    "IFGetNumDevices",
+                    (api.IFGetNumDevices)(iface, &mut num_devices),
+                )?;
+
+                for dev_index in 0..num_devices {
+                    let dev_id = inner.string_info("IFGetDeviceID", |buf, size| {
+                        (api.IFGetDeviceID)(iface, dev_index, buf.cast(), size)
+                    })?;
+                    cameras.push(camera_from_device(inner, iface, dev_id));
+                }
+            }
+        }
+
+        Ok(cameras)
+    }
+}
+
+fn camera_from_device(
+    producer: &Arc<ProducerInner>,
+    iface: IF_HANDLE,
+    id: CString,
+) -> Camera<ControlHandle, StreamHandle> {
+    let info = |cmd| {
+        let res = unsafe {
+            producer.string_info("IFGetDeviceInfo", |buf, size| {
+                let mut ty = 0;
+                (producer.api.IFGetDeviceInfo)(iface, id.as_ptr(), cmd, &mut ty, buf, size)
+            })
+        };
+        match res {
+            Ok(info) => info.to_string_lossy().into_owned(),
+            Err(e) => {
+                warn!(?e);
+                String::new()
+            }
+        }
+    };
+    let camera_info = CameraInfo {
+        vendor_name: info(ffi::DEVICE_INFO_VENDOR),
+        model_name: info(ffi::DEVICE_INFO_MODEL),
+        serial_number: info(ffi::DEVICE_INFO_SERIAL_NUMBER),
+    };
+
+    let device = Arc::new(DeviceModule::new(producer.clone(), iface, id));
+    let ctrl = ControlHandle::new(device.clone());
+    let strm = StreamHandle::new(device);
+    Camera::new(ctrl, strm, None, camera_info)
+}
+
+pub(super) struct ProducerInner {
+    pub(super) api: Api,
+    system: TL_HANDLE,
+    interfaces: Mutex<HashMap<CString, IF_HANDLE>>,
+    /// Must be dropped after all the handles are closed.
+    _lib: Library,
+}
+
+// SAFETY: `GenTL` requires all functions of a producer to be thread safe, and handles are just
+// opaque identifiers of modules.
+unsafe impl Send for ProducerInner {}
+unsafe impl Sync for ProducerInner {}
+
+impl ProducerInner {
+    /// Converts the return code of `func` into [`GcError`].
+    pub(super) fn check(&self, func: &'static str, code: GC_ERROR) -> Result<(), GcError> {
+        check(&self.api, func, code)
+    }
+
+    /// Retrieves a string from a producer with two calls, the first one queries the size.
+    ///
+    /// # Safety
+    /// `f` must call a producer function that follows the `GenTL` buffer convention.
+    pub(super) unsafe fn string_info(
+        &self,
+        func: &'static str,
+        mut f: impl FnMut(*mut c_void, *mut usize) -> GC_ERROR,
+    ) -> Result<CString, GcError> {
+        let mut size = 0;
+        self.check(func, f(ptr::null_mut(), &mut size))?;
+        let mut buf = vec![0_u8; size];
+        self.check(func, f(buf.as_mut_ptr().cast(), &mut size))?;
+
+        let nul = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+        buf.truncate(nul);
+        Ok(CString::new(buf).unwrap())
+    }
+
+    /// Retrieves a fixed size value from a producer.
+    ///
+    /// # Safety
+    /// `f` must call a producer function that follows the `GenTL` buffer convention, and the
+    /// value must be of type `T`.
+    pub(super) unsafe fn value_info<T: Copy + Default>(
+        &self,
+        func: &'static str,
+        f: impl FnOnce(*mut c_void, *mut usize) -> GC_ERROR,
+    ) -> Result<T, GcError> {
+        let mut value = T::default();
+        let mut size = mem::size_of::<T>();
+        self.check(func, f((&mut value as *mut T).cast(), &mut size))?;
+        Ok(value)
+    }
+
+    unsafe fn open_interface(&self, id: &CStr) -> Result<IF_HANDLE, GcError> {
+        let mut interfaces = self.interfaces.lock().unwrap();
+        if let Some(iface) = interfaces.get(id) {
+            return Ok(*iface);
+        }
+
+        let mut iface = ptr::null_mut();
+        self.check(
+            "TLOpenInterface",
+            (self.api.TLOpenInterface)(self.system, id.as_ptr(), &mut iface),
+        )?;
+        interfaces.insert(id.to_owned(), iface);
+        Ok(iface)
+    }
+}
+
+impl Drop for ProducerInner {
+    fn drop(&mut self) {
+        let api = &self.api;
+        unsafe {
+            for (_, iface) in self.interfaces.get_mut().unwrap().drain() {
+                if let Err(e) = check(api, "IFClose", (api.IFClose)(iface)) {
+                    error!(?e);
+                }
+            }
+            if let Err(e) = check(api, "TLClose", (api.TLClose)(self.system)) {
+                error!(?e);
+            }
+            if let Err(e) = check(api, "GCCloseLib", (api.GCCloseLib)()) {
+                error!(?e);
+            }
+        }
+    }
+}
+
+fn check(api: &Api, func: &'static str, code: GC_ERROR) -> Result<(), GcError> {
+    if code == ffi::GC_ERR_SUCCESS {
+        return Ok(());
+    }
+
+    // `GCGetLastError` returns the description of the last error in the calling thread.
+    let mut last_code = 0;
+    let mut text = [0_u8; 1024];
+    let mut size = text.len();
+    let description = unsafe {
+        if (api.GCGetLastError)(&mut last_code, text.as_mut_ptr().cast(), &mut size)
+            == ffi::GC_ERR_SUCCESS
+            && last_code == code
+        {
+            let nul = text.iter().position(|&b| b == 0).unwrap_or(text.len());
+            String::from_utf8_lossy(&text[..nul]).into_owned()
+        } else {
+            String::new()
+        }
+    };
+
+    Err(GcError {
+        func,
+        code,
+        description,
+    })
+}
+
+/// An error returned from a producer function.
+#[derive(Debug, thiserror::Error)]
+#[error("`{func}` failed with error code {code}: {description}")]
+pub(super) struct GcError {
+    func: &'static str,
+    pub(super) code: GC_ERROR,
+    description: String,
+}
+
+impl GcError {
+    /// Returns `true` if the producer doesn't provide the requested information.
+    pub(super) fn is_not_available(&self) -> bool {
+        matches!(
+            self.code,
+            ffi::GC_ERR_NOT_AVAILABLE | ffi::GC_ERR_NOT_IMPLEMENTED
+        )
+    }
+}
+
+impl From<GcError> for ControlError {
+    fn from(err: GcError) -> Self {
+        match err.code {
+            ffi::GC_ERR_RESOURCE_IN_USE | ffi::GC_ERR_ACCESS_DENIED | ffi::GC_ERR_BUSY => {
+                ControlError::Busy
+            }
+            ffi::GC_ERR_NOT_INITIALIZED | ffi::GC_ERR_INVALID_HANDLE => ControlError::NotOpened,
+            ffi::GC_ERR_TIMEOUT => ControlError::Timeout,
+            ffi::GC_ERR_BUFFER_TOO_SMALL => ControlError::BufferTooSmall,
+            _ => ControlError::Io(err.into()),
+        }
+    }
+}
+
+impl From<GcError> for StreamError {
+    fn from(err: GcError) -> Self {
+        match err.code {
+            ffi::GC_ERR_TIMEOUT => StreamError::Timeout,
+            ffi::GC_ERR_ABORT => StreamError::Cancelled,
+            ffi::GC_ERR_BUFFER_TOO_SMALL => StreamError::BufferTooSmall,
+            _ => StreamError::Io(err.into()),
+        }
+    }
+}
+
+impl From<GcError> for crate::CameleonError {
+    fn from(err: GcError) -> Self {
+        ControlError::from(err).into()
+    }
+}
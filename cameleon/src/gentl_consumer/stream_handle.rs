@@ -0,0 +1,528 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains streaming implementation over the data stream module of a `GenTL`
+//! producer.
+
+use std::{
+    convert::TryFrom,
+    mem,
+    os::raw::c_void,
+    ptr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_std::task;
+use futures::channel::oneshot;
+use tracing::{error, info, warn};
+
+use crate::{
+    camera::PayloadStream,
+    payload::{
+        first_chunk_size, parse_chunks, ImageInfo, Payload, PayloadSender, PayloadType, PixelFormat,
+    },
+    DeviceControl, StreamError, StreamResult,
+};
+
+use super::{
+    device::DeviceModule,
+    ffi::{self, BUFFER_HANDLE, DS_HANDLE, EVENT_HANDLE},
+    producer::{GcError, ProducerInner},
+};
+
+/// Timeout of waiting for a new buffer event in milliseconds.
+/// The streaming loop checks the cancellation signal at least once in this period.
+const EVENT_TIMEOUT_MS: u64 = 100;
+
+/// Receives payloads through the first data stream of a `GenTL` device.
+pub struct StreamHandle {
+    device: Arc<DeviceModule>,
+    params: StreamParams,
+}
+
+/// Parameters of the buffers announced to a producer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamParams {
+    /// The number of buffers announced to the producer.
+    ///
+    /// The minimum number the producer requires is used if this is smaller.
+    pub num_buffers: usize,
+
+    /// Size of each buffer in bytes.
+    ///
+    /// This is only used when the producer doesn't define the payload size, e.g. when the size
+    /// must be read from `PayloadSize` node of the device.
+    pub payload_size: Option<usize>,
+}
+
+impl Default for StreamParams {
+    fn default() -> Self {
+        Self {
+            num_buffers: 4,
+            payload_size: None,
+        }
+    }
+}
+
+impl StreamHandle {
+    pub(super) fn new(device: Arc<DeviceModule>) -> Self {
+        Self {
+            device,
+            params: StreamParams::default(),
+        }
+    }
+
+    /// Returns the parameters used when streaming is started.
+    #[must_use]
+    pub fn params(&self) -> &StreamParams {
+        &self.params
+    }
+
+    /// Returns the mutable parameters used when streaming is started.
+    pub fn params_mut(&mut self) -> &mut StreamParams {
+        &mut self.params
+    }
+}
+
+impl PayloadStream for StreamHandle {
+    fn open(&mut self) -> StreamResult<()> {
+        self.device.open_stream()
+    }
+
+    fn close(&mut self) -> StreamResult<()> {
+        self.device.close_stream()
+    }
+
+    fn start_streaming_loop(
+        &mut self,
+        sender: PayloadSender,
+        _ctrl: &mut dyn DeviceControl,
+    ) -> StreamResult<()> {
+        let mut state = self.device.state();
+        let stream = state
+            .as_mut()
+            .and_then(|opened| opened.stream.as_mut())
+            .ok_or_else(|| StreamError::Io(anyhow::Error::msg("stream is not opened")))?;
+        if stream.acquisition.is_some() {
+            return Err(StreamError::InStreaming);
+        }
+
+        let producer = &self.device.producer;
+        let mut acquisition = Acquisition::start(producer, stream.ds, &self.params)?;
+
+        let (cancellation_tx, cancellation_rx) = oneshot::channel();
+        let (completion_tx, completion_rx) = oneshot::channel();
+        let strm_loop = StreamingLoop {
+            producer: producer.clone(),
+            ds: stream.ds,
+            event: acquisition.event,
+            sender,
+            completion_tx,
+            cancellation_rx,
+        };
+        std::thread::spawn(|| {
+            strm_loop.run();
+        });
+
+        acquisition.cancellation_tx = Some(cancellation_tx);
+        acquisition.completion_rx = Some(completion_rx);
+        stream.acquisition = Some(acquisition);
+
+        info!("start streaming loop successfully");
+        Ok(())
+    }
+
+    fn stop_streaming_loop(&mut self) -> StreamResult<()> {
+        let mut state = self.device.state();
+        let stream = state.as_mut().and_then(|opened| opened.stream.as_mut());
+        if let Some(stream) = stream {
+            if let Some(acquisition) = stream.acquisition.take() {
+                acquisition.stop(&self.device.producer, stream.ds)?;
+            }
+        }
+
+        info!("stop streaming loop successfully");
+        Ok(())
+    }
+
+    fn is_loop_running(&self) -> bool {
+        self.device
+            .state()
+            .as_ref()
+            .and_then(|opened| opened.stream.as_ref())
+            .map_or(false, |stream| stream.acquisition.is_some())
+    }
+}
+
+impl Drop for StreamHandle {
+    fn drop(&mut self) {
+        if let Err(e) = self.close() {
+            error!(?e)
+        }
+    }
+}
+
+impl From<StreamHandle> for Box<dyn PayloadStream> {
+    fn from(strm: StreamHandle) -> Self {
+        Box::new(strm)
+    }
+}
+
+/// Buffers and the event announced to a data stream while acquisition is running.
+pub(super) struct Acquisition {
+    event: EVENT_HANDLE,
+    buffers: Vec<BUFFER_HANDLE>,
+    cancellation_tx: Option<oneshot::Sender<()>>,
+    completion_rx: Option<oneshot::Receiver<()>>,
+}
+
+impl Acquisition {
+    fn start(producer: &ProducerInner, ds: DS_HANDLE, params: &StreamParams) -> StreamResult<Self> {
+        let mut acquisition = Self {
+            event: ptr::null_mut(),
+            buffers: vec![],
+            cancellation_tx: None,
+            completion_rx: None,
+        };
+
+        match unsafe { acquisition.setup(producer, ds, params) } {
+            Ok(()) => Ok(acquisition),
+            Err(e) => {
+                acquisition.release(producer, ds);
+                Err(e)
+            }
+        }
+    }
+
+    unsafe fn setup(
+        &mut self,
+        producer: &ProducerInner,
+        ds: DS_HANDLE,
+        params: &StreamParams,
+    ) -> StreamResult<()> {
+        let api = &producer.api;
+        let (payload_size, num_buffers) = buffer_layout(producer, ds, params)?;
+
+        producer.check(
+            "GCRegisterEvent",
+            (api.GCRegisterEvent)(ds, ffi::EVENT_NEW_BUFFER, &mut self.event),
+        )?;
+        for _ in 0..num_buffers {
+            let mut buffer = ptr::null_mut();
+            producer.check(
+                "DSAllocAndAnnounceBuffer",
+                (api.DSAllocAndAnnounceBuffer)(ds, payload_size, ptr::null_mut(), &mut buffer),
+            )?;
+            self.buffers.push(buffer);
+            producer.check("DSQueueBuffer", (api.DSQueueBuffer)(ds, buffer))?;
+        }
+        producer.check(
+            "DSStartAcquisition",
+            (api.DSStartAcquisition)(ds, ffi::ACQ_START_FLAGS_DEFAULT, ffi::GENTL_INFINITE),
+        )?;
+
+        Ok(())
+    }
+
+    /// Stops the streaming loop and the acquisition, then revokes all the buffers.
+    pub(super) fn stop(mut self, producer: &ProducerInner, ds: DS_HANDLE) -> StreamResult<()> {
+        let api = &producer.api;
+        if let (Some(cancellation_tx), Some(completion_rx)) =
+            (self.cancellation_tx.take(), self.completion_rx.take())
+        {
+            // The loop may have already exited on a fatal error.
+            cancellation_tx.send(()).ok();
+            // Wake up the loop waiting for a new buffer.
+            if let Err(e) = producer.check("EventKill", unsafe { (api.EventKill)(self.event) }) {
+                warn!(?e);
+            }
+            task::block_on(completion_rx)
+                .map_err(|e| StreamError::Poisoned(e.to_string().into()))?;
+        }
+
+        let res = producer.check("DSStopAcquisition", unsafe {
+            (api.DSStopAcquisition)(ds, ffi::ACQ_STOP_FLAGS_KILL)
+        });
+        self.release(producer, ds);
+        res.map_err(Into::into)
+    }
+
+    fn release(&mut self, producer: &ProducerInner, ds: DS_HANDLE) {
+        let api = &producer.api;
+        unsafe {
+            if let Err(e) = producer.check(
+                "DSFlushQueue",
+                (api.DSFlushQueue)(ds, ffi::ACQ_QUEUE_ALL_DISCARD),
+            ) {
+                warn!(?e);
+            }
+            for buffer in self.buffers.drain(..) {
+                if let Err(e) = producer.check(
+                    "DSRevokeBuffer",
+                    (api.DSRevokeBuffer)(ds, buffer, ptr::null_mut(), ptr::null_mut()),
+                ) {
+                    warn!(?e);
+                }
+            }
+            if !self.event.is_null() {
+                if let Err(e) = producer.check(
+                    "GCUnregisterEvent",
+                    (api.GCUnregisterEvent)(ds, ffi::EVENT_NEW_BUFFER),
+                ) {
+                    warn!(?e);
+                }
+                self.event = ptr::null_mut();
+            }
+        }
+    }
+}
+
+/// Returns the size and the number of buffers to announce.
+unsafe fn buffer_layout(
+    producer: &ProducerInner,
+    ds: DS_HANDLE,
+    params: &StreamParams,
+) -> StreamResult<(usize, usize)> {
+    let api = &producer.api;
+    let ds_info = |cmd: i32| {
+        move |buf: *mut c_void, size: *mut usize| {
+            let mut ty = 0;
+            (api.DSGetInfo)(ds, cmd, &mut ty, buf, size)
+        }
+    };
+
+    let defines_payload_size: ffi::bool8_t = producer
+        .value_info("DSGetInfo", ds_info(ffi::STREAM_INFO_DEFINES_PAYLOADSIZE))
+        .or_else(|e| if e.is_not_available() { Ok(0) } else { Err(e) })?;
+    let payload_size = if defines_payload_size != 0 {
+        producer.value_info("DSGetInfo", ds_info(ffi::STREAM_INFO_PAYLOAD_SIZE))?
+    } else {
+        params.payload_size.ok_or_else(|| {
+            StreamError::Io(anyhow::Error::msg(
+                "producer doesn't define the payload size, `StreamParams::payload_size` must be set",
+            ))
+        })?
+    };
+
+    let announce_min: usize = producer
+        .value_info("DSGetInfo", ds_info(ffi::STREAM_INFO_BUF_ANNOUNCE_MIN))
+        .or_else(|e| if e.is_not_available() { Ok(1) } else { Err(e) })?;
+
+    Ok((payload_size, params.num_buffers.max(announce_min)))
+}
+
+struct StreamingLoop {
+    producer: Arc<ProducerInner>,
+    ds: DS_HANDLE,
+    event: EVENT_HANDLE,
+    sender: PayloadSender,
+    completion_tx: oneshot::Sender<()>,
+    cancellation_rx: oneshot::Receiver<()>,
+}
+
+// SAFETY: See `ProducerInner`.
+unsafe impl Send for StreamingLoop {}
+
+impl StreamingLoop {
+    fn run(mut self) {
+        let api = &self.producer.api;
+
+        loop {
+            // Stop the loop when
+            // 1. `cancellation_tx` sends signal.
+            // 2. `cancellation_tx` is dropped.
+            if self.cancellation_rx.try_recv().transpose().is_some() {
+                break;
+            }
+
+            let mut data = ffi::EVENT_NEW_BUFFER_DATA {
+                BufferHandle: ptr::null_mut(),
+                pUserPointer: ptr::null_mut(),
+            };
+            let mut size = mem::size_of::<ffi::EVENT_NEW_BUFFER_DATA>();
+            let code = unsafe {
+                (api.EventGetData)(
+                    self.event,
+                    (&mut data as *mut ffi::EVENT_NEW_BUFFER_DATA).cast(),
+                    &mut size,
+                    EVENT_TIMEOUT_MS,
+                )
+            };
+            match self.producer.check("EventGetData", code) {
+                Ok(()) => {}
+                Err(e) if matches!(e.code, ffi::GC_ERR_TIMEOUT | ffi::GC_ERR_ABORT) => continue,
+                Err(e) => {
+                    error!(?e);
+                    self.sender.try_send(Err(e.into())).ok();
+                    break;
+                }
+            }
+
+            // The new buffer event is signaled after the producer completed the buffer.
+            let host_timestamp = Instant::now();
+            let payload = self.build_payload(data.BufferHandle, host_timestamp);
+            // Give the buffer back to the producer as soon as its content is copied.
+            if let Err(e) = self.producer.check("DSQueueBuffer", unsafe {
+                (api.DSQueueBuffer)(self.ds, data.BufferHandle)
+            }) {
+                warn!(?e);
+            }
+
+            match payload {
+                Ok(payload) => {
+                    if let Err(err) = self.sender.try_send(Ok(payload)) {
+                        warn!(?err);
+                    }
+                }
+                Err(e) => {
+                    warn!(?e);
+                    self.sender.try_send(Err(e)).ok();
+                }
+            }
+        }
+
+        if let Err(e) = self.completion_tx.send(()) {
+            error!(?e);
+        }
+    }
+
+    fn build_payload(
+        &self,
+        buffer: BUFFER_HANDLE,
+        host_timestamp: Instant,
+    ) -> StreamResult<Payload> {
+        let is_incomplete: ffi::bool8_t = self.info(buffer, ffi::BUFFER_INFO_IS_INCOMPLETE)?;
+        if is_incomplete != 0 {
+            return Err(StreamError::InvalidPayload(
+                "producer delivered an incomplete buffer".into(),
+            ));
+        }
+
+        let base: usize = self.info(buffer, ffi::BUFFER_INFO_BASE)?;
+        let valid_payload_size: usize = self.info(buffer, ffi::BUFFER_INFO_SIZE_FILLED)?;
+        let id: u64 = self
+            .optional_info(buffer, ffi::BUFFER_INFO_FRAMEID)?
+            .unwrap_or(0);
+        let timestamp = match self.optional_info(buffer, ffi::BUFFER_INFO_TIMESTAMP_NS)? {
+            Some(ns) => ns,
+            None => self
+                .optional_info(buffer, ffi::BUFFER_INFO_TIMESTAMP)?
+                .unwrap_or(0),
+        };
+
+        let image_present: ffi::bool8_t = self
+            .optional_info(buffer, ffi::BUFFER_INFO_IMAGEPRESENT)?
+            .unwrap_or(0);
+        let payload_type = match self.optional_info(buffer, ffi::BUFFER_INFO_PAYLOADTYPE)? {
+            Some(ffi::PAYLOAD_TYPE_IMAGE) => PayloadType::Image,
+            Some(ffi::PAYLOAD_TYPE_EXTENDED_CHUNK) => PayloadType::ImageExtendedChunk,
+            Some(ffi::PAYLOAD_TYPE_CHUNK_ONLY) => PayloadType::Chunk,
+            Some(ffi::PAYLOAD_TYPE_GENDC) => PayloadType::GenDC,
+            _ if image_present != 0 => PayloadType::Image,
+            _ => PayloadType::Chunk,
+        };
+
+        // Reuse a buffer sent back from the receiver if any.
+        let mut payload = match self.sender.try_recv() {
+            Ok(payload) => payload.payload,
+            Err(_) => Vec::with_capacity(valid_payload_size),
+        };
+        payload.clear();
+        // SAFETY: The buffer isn't touched by the producer until it's queued again.
+        payload.extend_from_slice(unsafe {
+            std::slice::from_raw_parts(base as *const u8, valid_payload_size)
+        });
+
+        let (image_info, chunks) = match payload_type {
+            PayloadType::Image => (Some(self.image_info(buffer, valid_payload_size)?), None),
+            PayloadType::ImageExtendedChunk => {
+                let chunks = parse_chunks(&payload)?;
+                let image_size = first_chunk_size(&chunks)?;
+                (Some(self.image_info(buffer, image_size)?), Some(chunks))
+            }
+            PayloadType::Chunk => (None, Some(parse_chunks(&payload)?)),
+            PayloadType::GenDC => (None, None),
+        };
+
+        Ok(Payload {
+            id,
+            payload_type,
+            image_info,
+            payload,
+            valid_payload_size,
+            timestamp: Duration::from_nanos(timestamp),
+            host_timestamp,
+            chunks,
+        })
+    }
+
+    fn image_info(&self, buffer: BUFFER_HANDLE, image_size: usize) -> StreamResult<ImageInfo> {
+        let namespace: u64 = self
+            .optional_info(buffer, ffi::BUFFER_INFO_PIXELFORMAT_NAMESPACE)?
+            .unwrap_or(ffi::PIXELFORMAT_NAMESPACE_PFNC_32BIT);
+        if namespace != ffi::PIXELFORMAT_NAMESPACE_PFNC_32BIT {
+            return Err(StreamError::InvalidPayload(
+                format!("unsupported pixel format namespace: {}", namespace).into(),
+            ));
+        }
+        let pixel_format: u64 = self.info(buffer, ffi::BUFFER_INFO_PIXELFORMAT)?;
+        let pixel_format = u32::try_from(pixel_format)
+            .ok()
+            .and_then(|pf| PixelFormat::try_from(pf).ok())
+            .ok_or_else(|| {
+                StreamError::InvalidPayload(
+                    format!("invalid pixel format: {:#x}", pixel_format).into(),
+                )
+            })?;
+
+        let height = match self.optional_info(buffer, ffi::BUFFER_INFO_DELIVERED_IMAGEHEIGHT)? {
+            Some(height) if height != 0 => height,
+            _ => self.info(buffer, ffi::BUFFER_INFO_HEIGHT)?,
+        };
+
+        Ok(ImageInfo {
+            width: self.info(buffer, ffi::BUFFER_INFO_WIDTH)?,
+            height,
+            x_offset: self
+                .optional_info(buffer, ffi::BUFFER_INFO_XOFFSET)?
+                .unwrap_or(0),
+            y_offset: self
+                .optional_info(buffer, ffi::BUFFER_INFO_YOFFSET)?
+                .unwrap_or(0),
+            pixel_format,
+            image_size,
+        })
+    }
+
+    fn info<T: Copy + Default>(&self, buffer: BUFFER_HANDLE, cmd: i32) -> StreamResult<T> {
+        self.buffer_info(buffer, cmd).map_err(Into::into)
+    }
+
+    fn optional_info<T: Copy + Default>(
+        &self,
+        buffer: BUFFER_HANDLE,
+        cmd: i32,
+    ) -> StreamResult<Option<T>> {
+        match self.buffer_info(buffer, cmd) {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if e.is_not_available() => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn buffer_info<T: Copy + Default>(
+        &self,
+        buffer: BUFFER_HANDLE,
+        cmd: i32,
+    ) -> Result<T, GcError> {
+        let api = &self.producer.api;
+        unsafe {
+            self.producer.value_info("DSGetBufferInfo", |buf, size| {
+                let mut ty = 0;
+                (api.DSGetBufferInfo)(self.ds, buffer, cmd, &mut ty, buf, size)
+            })
+        }
+    }
+}
@@ -0,0 +1,406 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains [`CameraGroup`] which captures frames of multiple cameras
+//! synchronously, e.g. for stereo or array camera rigs.
+//!
+//! Each camera streams independently, so frames of the same moment arrive in different orders
+//! and some of them may be dropped. [`CameraGroup::next_synchronized_set`] matches frames by
+//! their timestamps and discards frames which have no counterpart.
+//!
+//! # Examples
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use cameleon::{
+//!     group::{CameraGroup, ClockSync},
+//!     u3v,
+//! };
+//!
+//! let cameras = u3v::enumerate_cameras().unwrap();
+//! if cameras.len() < 2 {
+//!     return;
+//! }
+//! let mut group = CameraGroup::new(cameras);
+//! group.open().unwrap();
+//!
+//! // Correlate device clocks with the host clock to compare timestamps of different cameras.
+//! // Use `ClockSync::Device` instead if the cameras are synchronized by e.g. PTP.
+//! let correlations = group
+//!     .cameras
+//!     .iter_mut()
+//!     .map(|camera| camera.ctrl.correlate_clock(16).unwrap())
+//!     .collect();
+//! group
+//!     .start_streaming(3, ClockSync::Correlated(correlations))
+//!     .unwrap();
+//!
+//! for _ in 0..10 {
+//!     let payloads = group
+//!         .next_synchronized_set(Duration::from_millis(5), Duration::from_secs(1))
+//!         .unwrap();
+//!     // `payloads[i]` is the frame of `group.cameras[i]`.
+//!     group.send_back(payloads);
+//! }
+//!
+//! group.close().unwrap();
+//! ```
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use tracing::warn;
+
+use super::{
+    camera::{recv_timeout, Camera, DeviceControl, PayloadStream},
+    clock::ClockCorrelation,
+    genapi::{DefaultGenApiCtxt, FromXml, GenApiCtxt},
+    payload::{Payload, PayloadReceiver},
+    CameleonResult, StreamError,
+};
+
+/// How timestamps of frames of different cameras are compared.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClockSync {
+    /// Device clocks are synchronized with each other, e.g. by `PTP`, so device timestamps are
+    /// compared directly.
+    Device,
+    /// Device timestamps are converted to the host clock by the correlation of each camera.
+    ///
+    /// Correlations must be in the same order as [`CameraGroup::cameras`]. See
+    /// [`ClockCorrelation`] for how to obtain them.
+    Correlated(Vec<ClockCorrelation>),
+}
+
+/// A group of cameras which captures frames synchronously.
+pub struct CameraGroup<Ctrl, Strm, Ctxt = DefaultGenApiCtxt> {
+    /// Cameras in the group.
+    ///
+    /// Cameras can be configured individually, but must not be started or stopped streaming
+    /// directly while the group is streaming.
+    pub cameras: Vec<Camera<Ctrl, Strm, Ctxt>>,
+    streams: Vec<MemberStream>,
+    /// The origin of timestamps converted to the host clock.
+    host_base: Instant,
+}
+
+/// A stream of a camera in [`CameraGroup`].
+struct MemberStream {
+    payload_rx: PayloadReceiver,
+    correlation: Option<ClockCorrelation>,
+    /// Payloads received but not matched yet.
+    pending: VecDeque<Payload>,
+}
+
+impl MemberStream {
+    /// Returns the timestamp of `payload` in nanoseconds in the time domain shared by the group.
+    fn sync_time(&self, payload: &Payload, host_base: Instant) -> Option<i128> {
+        match &self.correlation {
+            Some(correlation) => {
+                let host_time = correlation.to_host(payload.timestamp())?;
+                Some(if host_time >= host_base {
+                    (host_time - host_base).as_nanos() as i128
+                } else {
+                    -((host_base - host_time).as_nanos() as i128)
+                })
+            }
+            None => Some(payload.timestamp().as_nanos() as i128),
+        }
+    }
+}
+
+impl<Ctrl, Strm, Ctxt> CameraGroup<Ctrl, Strm, Ctxt> {
+    /// Constructs a group of `cameras`.
+    pub fn new(cameras: Vec<Camera<Ctrl, Strm, Ctxt>>) -> Self {
+        Self {
+            cameras,
+            streams: vec![],
+            host_base: Instant::now(),
+        }
+    }
+
+    /// Opens all cameras and loads their `GenApi` contexts.
+    pub fn open(&mut self) -> CameleonResult<()>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt + FromXml,
+    {
+        for camera in &mut self.cameras {
+            camera.open()?;
+            camera.load_context()?;
+        }
+        Ok(())
+    }
+
+    /// Closes all cameras, streaming is stopped if it's running.
+    ///
+    /// All cameras are tried to be closed even if some of them fail, then the first error is
+    /// returned.
+    pub fn close(&mut self) -> CameleonResult<()>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        self.streams.clear();
+        let mut res = Ok(());
+        for camera in &mut self.cameras {
+            let close_res = camera.close();
+            if res.is_ok() {
+                res = close_res;
+            }
+        }
+        res
+    }
+
+    /// Starts streaming of all cameras.
+    ///
+    /// See [`Camera::start_streaming`] for the meaning of `cap`.
+    ///
+    /// # Panics
+    /// If `cap` is zero, or the number of correlations of [`ClockSync::Correlated`] differs from
+    /// the number of cameras, this method will panic.
+    pub fn start_streaming(&mut self, cap: usize, clock_sync: ClockSync) -> CameleonResult<()>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        if !self.streams.is_empty() {
+            return Err(StreamError::InStreaming.into());
+        }
+        let correlations = match clock_sync {
+            ClockSync::Device => vec![None; self.cameras.len()],
+            ClockSync::Correlated(correlations) => {
+                assert_eq!(
+                    correlations.len(),
+                    self.cameras.len(),
+                    "a correlation is required for each camera"
+                );
+                correlations.into_iter().map(Some).collect()
+            }
+        };
+
+        let mut streams = Vec::with_capacity(self.cameras.len());
+        for (index, correlation) in correlations.into_iter().enumerate() {
+            match self.cameras[index].start_streaming(cap) {
+                Ok(payload_rx) => streams.push(MemberStream {
+                    payload_rx,
+                    correlation,
+                    pending: VecDeque::new(),
+                }),
+                Err(e) => {
+                    // Leave no camera streaming partially.
+                    for camera in &mut self.cameras[..index] {
+                        camera.stop_streaming().ok();
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        self.streams = streams;
+        Ok(())
+    }
+
+    /// Stops streaming of all cameras.
+    pub fn stop_streaming(&mut self) -> CameleonResult<()>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        self.streams.clear();
+        for camera in &mut self.cameras {
+            camera.stop_streaming()?;
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the group is streaming.
+    pub fn is_streaming(&self) -> bool {
+        !self.streams.is_empty()
+    }
+
+    /// Waits for a set of frames, one frame per camera, whose timestamps are within `tolerance`
+    /// of each other.
+    ///
+    /// The returned frames are in the same order as [`Self::cameras`]. A frame is discarded if
+    /// it's older than the latest frame of the other cameras by more than `tolerance`, i.e. its
+    /// counterparts have been dropped or the camera lags behind. Frames whose timestamps can't
+    /// be converted to the host clock are also discarded.
+    ///
+    /// An error of a single frame of a camera, e.g. a broken payload, is treated as a dropped
+    /// frame. Other errors, e.g. disconnection of a camera, are returned immediately.
+    ///
+    /// Returns [`StreamError::Timeout`] if no set is complete within `timeout`, frames received
+    /// until then are kept for the next call.
+    pub fn next_synchronized_set(
+        &mut self,
+        tolerance: Duration,
+        timeout: Duration,
+    ) -> CameleonResult<Vec<Payload>> {
+        if self.streams.is_empty() {
+            return Err(StreamError::ReceiveError("streaming is not started".into()).into());
+        }
+        let deadline = Instant::now() + timeout;
+        let tolerance = tolerance.as_nanos() as i128;
+        let host_base = self.host_base;
+
+        loop {
+            let mut heads = Vec::with_capacity(self.streams.len());
+            for stream in &mut self.streams {
+                let time = loop {
+                    if stream.pending.is_empty() {
+                        let remaining = deadline.saturating_duration_since(Instant::now());
+                        match recv_timeout(&stream.payload_rx, remaining) {
+                            Ok(payload) => stream.pending.push_back(payload),
+                            Err(err) if is_frame_error(&err) => {
+                                if Instant::now() >= deadline {
+                                    return Err(StreamError::Timeout.into());
+                                }
+                                warn!(?err, "frame is dropped");
+                                continue;
+                            }
+                            Err(err) => return Err(err.into()),
+                        }
+                    }
+                    let payload = &stream.pending[0];
+                    match stream.sync_time(payload, host_base) {
+                        Some(time) => break time,
+                        None => {
+                            let payload = stream.pending.pop_front().unwrap();
+                            stream.payload_rx.send_back(payload);
+                        }
+                    }
+                };
+                heads.push(time);
+            }
+
+            let latest = heads.iter().copied().max().unwrap();
+            if heads.iter().all(|time| latest - time <= tolerance) {
+                return Ok(self
+                    .streams
+                    .iter_mut()
+                    .map(|stream| stream.pending.pop_front().unwrap())
+                    .collect());
+            }
+
+            // Discard stragglers, then wait for their next frames.
+            for (stream, time) in self.streams.iter_mut().zip(heads) {
+                if latest - time > tolerance {
+                    let payload = stream.pending.pop_front().unwrap();
+                    stream.payload_rx.send_back(payload);
+                }
+            }
+        }
+    }
+
+    /// Sends back payloads returned from [`Self::next_synchronized_set`] to reuse their buffers.
+    pub fn send_back(&self, payloads: Vec<Payload>) {
+        for (stream, payload) in self.streams.iter().zip(payloads) {
+            stream.payload_rx.send_back(payload);
+        }
+    }
+}
+
+/// Returns `true` if `err` is an error of a single frame, which doesn't stop the stream.
+fn is_frame_error(err: &StreamError) -> bool {
+    matches!(
+        err,
+        StreamError::InvalidPayload(..) | StreamError::BufferTooSmall | StreamError::Timeout
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        payload::{channel, PayloadSender, PayloadType},
+        CameleonError,
+    };
+
+    fn payload(id: u64, timestamp_ms: u64) -> Payload {
+        Payload {
+            id,
+            payload_type: PayloadType::Chunk,
+            image_info: None,
+            payload: vec![],
+            valid_payload_size: 0,
+            timestamp: Duration::from_millis(timestamp_ms),
+            host_timestamp: Instant::now(),
+            chunks: None,
+        }
+    }
+
+    fn group(len: usize) -> (CameraGroup<(), (), ()>, Vec<PayloadSender>) {
+        let mut group = CameraGroup::new(vec![]);
+        let mut senders = vec![];
+        for _ in 0..len {
+            let (sender, payload_rx) = channel(10, 10);
+            senders.push(sender);
+            group.streams.push(MemberStream {
+                payload_rx,
+                correlation: None,
+                pending: VecDeque::new(),
+            });
+        }
+        (group, senders)
+    }
+
+    #[test]
+    fn test_next_synchronized_set() {
+        let (mut group, senders) = group(2);
+        for (id, timestamp) in [(0, 0), (1, 10), (2, 20)].iter() {
+            senders[0].try_send(Ok(payload(*id, *timestamp))).unwrap();
+        }
+        // The first frame of the second camera is dropped, and the others lag by 1ms.
+        for (id, timestamp) in [(0, 11), (1, 21)].iter() {
+            senders[1].try_send(Ok(payload(*id, *timestamp))).unwrap();
+        }
+
+        let tolerance = Duration::from_millis(2);
+        let timeout = Duration::from_millis(100);
+        let ids = |payloads: Vec<Payload>| payloads.iter().map(Payload::id).collect::<Vec<_>>();
+        let set = group.next_synchronized_set(tolerance, timeout).unwrap();
+        assert_eq!(ids(set), vec![1, 0]);
+        let set = group.next_synchronized_set(tolerance, timeout).unwrap();
+        assert_eq!(ids(set), vec![2, 1]);
+
+        senders[0].try_send(Ok(payload(3, 30))).unwrap();
+        assert!(matches!(
+            group.next_synchronized_set(tolerance, timeout),
+            Err(CameleonError::StreamError(StreamError::Timeout))
+        ));
+    }
+
+    #[test]
+    fn test_next_synchronized_set_with_frame_error() {
+        let (mut group, senders) = group(2);
+        for (id, timestamp) in [(0, 0), (1, 10)].iter() {
+            senders[0].try_send(Ok(payload(*id, *timestamp))).unwrap();
+        }
+        // The first frame of the second camera is broken.
+        senders[1]
+            .try_send(Err(StreamError::InvalidPayload("broken".into())))
+            .unwrap();
+        senders[1].try_send(Ok(payload(1, 10))).unwrap();
+
+        let tolerance = Duration::from_millis(2);
+        let timeout = Duration::from_millis(100);
+        let set = group.next_synchronized_set(tolerance, timeout).unwrap();
+        assert_eq!(set.iter().map(Payload::id).collect::<Vec<_>>(), vec![1, 1]);
+
+        // Errors that stop the stream are returned.
+        senders[0].try_send(Ok(payload(2, 20))).unwrap();
+        senders[1].try_send(Err(StreamError::Disconnected)).unwrap();
+        assert!(matches!(
+            group.next_synchronized_set(tolerance, timeout),
+            Err(CameleonError::StreamError(StreamError::Disconnected))
+        ));
+    }
+}
@@ -0,0 +1,295 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module provides saving of images in payloads to files without losing their bit depth.
+//!
+//! [`save_png`] is available when `png` feature is enabled, and [`save_tiff`] is available when
+//! `tiff` feature is enabled.
+//!
+//! Images deeper than 8 bits, e.g. `Mono12` or `RGB16`, are saved as 16 bits images.
+//! * PNG: Pixels are scaled to the full 16 bits range as required by the PNG specification, and
+//!   the original bit depth is recorded in `sBIT` chunk. The frame ID and the timestamp of the
+//!   payload are recorded in `FrameID` and `Timestamp` text chunks.
+//! * TIFF: Pixels are saved as is, e.g. pixels of `Mono12` are in `0..4096`. The frame ID, the
+//!   timestamp and the bit depth are recorded in `ImageDescription` tag as
+//!   `frame_id=<id>`, `timestamp_ns=<timestamp>` and `significant_bits=<bits>` lines.
+//!
+//! Bayer images are saved as raw monochrome images to keep the original data, use
+//! [`crate::pixel::convert`] to demosaic them. `4:2:2` images are converted into `RGB8`.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use cameleon::{io, u3v};
+//!
+//! let mut camera = u3v::enumerate_cameras().unwrap().pop().unwrap();
+//! camera.open().unwrap();
+//! camera.load_context().unwrap();
+//!
+//! let payload_rx = camera.start_streaming(3).unwrap();
+//! let payload = async_std::task::block_on(payload_rx.recv()).unwrap();
+//! io::save_tiff(&payload, "frame.tiff").unwrap();
+//! payload_rx.send_back(payload);
+//! ```
+
+use std::{borrow::Cow, convert::TryFrom, fs::File, io::BufWriter, path::Path};
+
+use super::{
+    payload::Payload,
+    pixel::{ConvertError, Samples},
+};
+
+/// A `Result` type of saving images.
+pub type SaveResult<T> = std::result::Result<T, SaveError>;
+
+/// An error of saving images.
+#[derive(Debug, thiserror::Error)]
+pub enum SaveError {
+    /// IO error.
+    #[error("input/output error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Failed to decode the image in the payload.
+    #[error("failed to decode the image: {0}")]
+    Convert(#[from] ConvertError),
+
+    /// Failed to encode the image.
+    #[error("failed to encode the image: {0}")]
+    Encode(Cow<'static, str>),
+}
+
+#[cfg(feature = "png")]
+impl From<png::EncodingError> for SaveError {
+    fn from(err: png::EncodingError) -> Self {
+        match err {
+            png::EncodingError::IoError(err) => Self::Io(err),
+            err => Self::Encode(err.to_string().into()),
+        }
+    }
+}
+
+#[cfg(feature = "tiff")]
+impl From<tiff::TiffError> for SaveError {
+    fn from(err: tiff::TiffError) -> Self {
+        match err {
+            tiff::TiffError::IoError(err) => Self::Io(err),
+            err => Self::Encode(err.to_string().into()),
+        }
+    }
+}
+
+/// Saves the image in `payload` to `path` in PNG format.
+///
+/// See the [module level documentation](self) for details.
+#[cfg(feature = "png")]
+pub fn save_png(payload: &Payload, path: impl AsRef<Path>) -> SaveResult<()> {
+    write_png(payload, BufWriter::new(File::create(path)?))
+}
+
+/// Writes the image in `payload` to `writer` in PNG format.
+///
+/// See the [module level documentation](self) for details.
+#[cfg(feature = "png")]
+pub fn write_png(payload: &Payload, writer: impl std::io::Write) -> SaveResult<()> {
+    use png::{BitDepth, ColorType};
+
+    let (width, height) = dimensions(payload)?;
+    let to_be_bytes = |data: Vec<u16>, shift: u32| -> Vec<u8> {
+        data.into_iter()
+            .flat_map(|v| (v << shift).to_be_bytes())
+            .collect()
+    };
+    let (color_type, bit_depth, data, significant_bits) = match Samples::decode(payload)? {
+        Samples::Gray8(data) => (ColorType::Grayscale, BitDepth::Eight, data, None),
+        Samples::Gray16 { data, bits } => (
+            ColorType::Grayscale,
+            BitDepth::Sixteen,
+            to_be_bytes(data, 16 - bits),
+            Some(bits).filter(|bits| *bits < 16),
+        ),
+        Samples::Rgb8(data) => (ColorType::Rgb, BitDepth::Eight, data, None),
+        Samples::Rgba8(data) => (ColorType::Rgba, BitDepth::Eight, data, None),
+        Samples::Rgb16(data) => (
+            ColorType::Rgb,
+            BitDepth::Sixteen,
+            to_be_bytes(data, 0),
+            None,
+        ),
+    };
+
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(color_type);
+    encoder.set_depth(bit_depth);
+    encoder.add_text_chunk("FrameID".into(), payload.id().to_string())?;
+    encoder.add_text_chunk(
+        "Timestamp".into(),
+        payload.timestamp().as_nanos().to_string(),
+    )?;
+
+    let mut writer = encoder.write_header()?;
+    if let Some(bits) = significant_bits {
+        writer.write_chunk(png::chunk::ChunkType(*b"sBIT"), &[bits as u8])?;
+    }
+    writer.write_image_data(&data)?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Saves the image in `payload` to `path` in TIFF format.
+///
+/// See the [module level documentation](self) for details.
+#[cfg(feature = "tiff")]
+pub fn save_tiff(payload: &Payload, path: impl AsRef<Path>) -> SaveResult<()> {
+    write_tiff(payload, BufWriter::new(File::create(path)?))
+}
+
+/// Writes the image in `payload` to `writer` in TIFF format.
+///
+/// See the [module level documentation](self) for details.
+#[cfg(feature = "tiff")]
+pub fn write_tiff(
+    payload: &Payload,
+    writer: impl std::io::Write + std::io::Seek,
+) -> SaveResult<()> {
+    use tiff::encoder::{colortype, TiffEncoder};
+
+    let (width, height) = dimensions(payload)?;
+    let mut encoder = TiffEncoder::new(writer)?;
+    let mut description = format!(
+        "frame_id={}\ntimestamp_ns={}\n",
+        payload.id(),
+        payload.timestamp().as_nanos()
+    );
+
+    match Samples::decode(payload)? {
+        Samples::Gray8(data) => {
+            write_tiff_image::<_, colortype::Gray8>(&mut encoder, width, height, &data, description)
+        }
+        Samples::Gray16 { data, bits } => {
+            description.push_str(&format!("significant_bits={}\n", bits));
+            write_tiff_image::<_, colortype::Gray16>(
+                &mut encoder,
+                width,
+                height,
+                &data,
+                description,
+            )
+        }
+        Samples::Rgb8(data) => {
+            write_tiff_image::<_, colortype::RGB8>(&mut encoder, width, height, &data, description)
+        }
+        Samples::Rgba8(data) => {
+            write_tiff_image::<_, colortype::RGBA8>(&mut encoder, width, height, &data, description)
+        }
+        Samples::Rgb16(data) => {
+            write_tiff_image::<_, colortype::RGB16>(&mut encoder, width, height, &data, description)
+        }
+    }
+}
+
+#[cfg(feature = "tiff")]
+fn write_tiff_image<W, C>(
+    encoder: &mut tiff::encoder::TiffEncoder<W>,
+    width: u32,
+    height: u32,
+    data: &[C::Inner],
+    description: String,
+) -> SaveResult<()>
+where
+    W: std::io::Write + std::io::Seek,
+    C: tiff::encoder::colortype::ColorType,
+    [C::Inner]: tiff::encoder::TiffValue,
+{
+    use tiff::tags::Tag;
+
+    let mut image = encoder.new_image::<C>(width, height)?;
+    image
+        .encoder()
+        .write_tag(Tag::ImageDescription, description.as_str())?;
+    image.encoder().write_tag(Tag::Software, "cameleon")?;
+    image.write_data(data)?;
+    Ok(())
+}
+
+fn dimensions(payload: &Payload) -> SaveResult<(u32, u32)> {
+    let info = payload.image_info().ok_or(ConvertError::NoImage)?;
+    let size = |v: usize| {
+        u32::try_from(v).map_err(|_| ConvertError::InvalidSize("image is too large".into()))
+    };
+    Ok((size(info.width)?, size(info.height)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::payload::{ImageInfo, PayloadType, PixelFormat};
+
+    fn payload(pixel_format: PixelFormat, width: usize, height: usize, data: Vec<u8>) -> Payload {
+        Payload {
+            id: 42,
+            payload_type: PayloadType::Image,
+            image_info: Some(ImageInfo {
+                width,
+                height,
+                x_offset: 0,
+                y_offset: 0,
+                pixel_format,
+                image_size: data.len(),
+            }),
+            valid_payload_size: data.len(),
+            payload: data,
+            timestamp: Duration::from_micros(1500),
+            host_timestamp: Instant::now(),
+            chunks: None,
+        }
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn test_write_png() {
+        // 0xABC and 0x123 in `Mono12p`.
+        let payload = payload(PixelFormat::Mono12p, 2, 1, vec![0xBC, 0x3A, 0x12]);
+        let mut buf = vec![];
+        write_png(&payload, &mut buf).unwrap();
+
+        let mut reader = png::Decoder::new(buf.as_slice()).read_info().unwrap();
+        let info = reader.info();
+        assert_eq!(info.bit_depth, png::BitDepth::Sixteen);
+        let texts: Vec<_> = info
+            .uncompressed_latin1_text
+            .iter()
+            .map(|chunk| (chunk.keyword.as_str(), chunk.text.as_str()))
+            .collect();
+        assert_eq!(texts, [("FrameID", "42"), ("Timestamp", "1500000")]);
+
+        let mut data = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut data).unwrap();
+        assert_eq!(data, [0xAB, 0xC0, 0x12, 0x30]);
+        assert!(buf.windows(4).any(|chunk| chunk == b"sBIT"));
+    }
+
+    #[cfg(feature = "tiff")]
+    #[test]
+    fn test_write_tiff() {
+        use tiff::{decoder::DecodingResult, tags::Tag};
+
+        let payload = payload(PixelFormat::Mono12, 2, 1, vec![0xBC, 0x0A, 0x23, 0x01]);
+        let mut buf = std::io::Cursor::new(vec![]);
+        write_tiff(&payload, &mut buf).unwrap();
+
+        buf.set_position(0);
+        let mut decoder = tiff::decoder::Decoder::new(buf).unwrap();
+        let description = decoder.get_tag_ascii_string(Tag::ImageDescription).unwrap();
+        assert_eq!(
+            description,
+            "frame_id=42\ntimestamp_ns=1500000\nsignificant_bits=12\n"
+        );
+        match decoder.read_image().unwrap() {
+            DecodingResult::U16(data) => assert_eq!(data, [0xABC, 0x123]),
+            _ => panic!("unexpected sample type"),
+        }
+    }
+}
@@ -144,12 +144,28 @@
 )]
 
 pub mod camera;
+pub mod cancel;
+pub mod capability;
+pub mod clock;
+pub mod diagnostics;
 pub mod genapi;
+#[cfg(feature = "gentl-consumer")]
+pub mod gentl_consumer;
+pub mod group;
+#[cfg(any(feature = "png", feature = "tiff"))]
+pub mod io;
 pub mod payload;
-#[cfg(feature = "libusb")]
+pub mod pixel;
+pub mod replay;
+pub mod retry;
+pub mod sequence;
+pub mod sfnc;
+#[cfg(any(feature = "libusb", feature = "nusb"))]
 pub mod u3v;
 
-pub use camera::{Camera, CameraInfo, DeviceControl, PayloadStream};
+mod binary;
+
+pub use camera::{Camera, CameraInfo, DeviceControl, PayloadStream, StreamChannel};
 
 use std::{borrow::Cow, num::TryFromIntError};
 
@@ -180,13 +196,31 @@ pub enum CameleonError {
     GenApiError(#[from] cameleon_genapi::GenApiError),
 }
 
+impl CameleonError {
+    /// Returns `true` if the error is caused by cancellation through [`cancel::CancellationToken`].
+    ///
+    /// Cancellation of `GenApi` operations, which is reported as [`ControlError::Cancelled`]
+    /// wrapped in [`cameleon_genapi::GenApiError::Device`], is also detected.
+    pub fn is_cancelled(&self) -> bool {
+        match self {
+            Self::ControlError(ControlError::Cancelled)
+            | Self::StreamError(StreamError::Cancelled) => true,
+            Self::GenApiError(cameleon_genapi::GenApiError::Device(e)) => {
+                matches!(e.downcast_ref(), Some(ControlError::Cancelled))
+            }
+            _ => false,
+        }
+    }
+}
+
 /// A specialized `Result` type for device control.
 pub type ControlResult<T> = std::result::Result<T, ControlError>;
 
 /// An error type for device control.
 #[derive(Debug, thiserror::Error)]
 pub enum ControlError {
-    /// The device is busy, may be opened by another application.
+    /// The device is busy, may be opened by another application or still processing a previous
+    /// command.
     #[error("device is busy")]
     Busy,
 
@@ -218,6 +252,91 @@ pub enum ControlError {
     /// e.g. try to write too large data that will overrun register.
     #[error("try to write invalid data to the device: {0}")]
     InvalidData(Box<dyn std::error::Error + Send + Sync>),
+
+    /// The operation is cancelled through [`cancel::CancellationToken`].
+    #[error("operation is cancelled")]
+    Cancelled,
+
+    /// The device rejected a command with the status code.
+    #[error("device returned error status: {0}")]
+    DeviceStatus(StatusCode),
+}
+
+/// A status code returned from the device when it fails to process a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StatusCode {
+    code: u16,
+    kind: StatusKind,
+}
+
+/// Decoded meaning of [`StatusCode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum StatusKind {
+    /// Command not implemented in the device.
+    NotImplemented,
+    /// Command parameter is invalid.
+    InvalidParameter,
+    /// Attempt to access an address that doesn't exist.
+    InvalidAddress,
+    /// Attempt to write to a read only address.
+    WriteProtect,
+    /// Attempt to access an address with bad alignment.
+    BadAlignment,
+    /// Attempt to read unreadable address or write to unwritable address.
+    AccessDenied,
+    /// The device timed out while processing the command.
+    Timeout,
+    /// Header is inconsistent with data.
+    InvalidHeader,
+    /// The device configuration does not allow the execution of the command.
+    WrongConfig,
+    /// Generic error.
+    GenericError,
+    /// Resend command is not supported by the device.
+    ResendNotSupported,
+    /// Stream endpoint is halted.
+    StreamEndpointHalted,
+    /// Payload size is not aligned.
+    PayloadSizeNotAligned,
+    /// Event endpoint is halted.
+    EventEndpointHalted,
+    /// Streaming interface is in invalid state.
+    InvalidSiState,
+    /// A status defined by the device vendor.
+    DeviceSpecific,
+}
+
+impl StatusCode {
+    /// Constructs `StatusCode`.
+    #[must_use]
+    pub fn new(code: u16, kind: StatusKind) -> Self {
+        Self { code, kind }
+    }
+
+    /// Returns the raw status code sent from the device.
+    #[must_use]
+    pub fn code(self) -> u16 {
+        self.code
+    }
+
+    /// Returns decoded meaning of the status code.
+    #[must_use]
+    pub fn kind(self) -> StatusKind {
+        self.kind
+    }
+
+    /// Returns `true` if the status is fatal, i.e. the most significant bit of the code is set.
+    #[must_use]
+    pub fn is_fatal(self) -> bool {
+        self.code & 0x8000 != 0
+    }
+}
+
+impl std::fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} ({:#06X})", self.kind, self.code)
+    }
 }
 
 /// A specialized `Result` type for streaming.
@@ -263,6 +382,10 @@ pub enum StreamError {
         "streaming is already started. can't use the handle from the outside of streaming loop"
     )]
     InStreaming,
+
+    /// The operation is cancelled through [`cancel::CancellationToken`].
+    #[error("operation is cancelled")]
+    Cancelled,
 }
 
 impl From<TryFromIntError> for ControlError {
@@ -286,4 +409,19 @@ mod tests {
             assert_sync::<CameleonError>();
         }
     };
+
+    #[test]
+    fn test_is_cancelled() {
+        let token = cancel::CancellationToken::new();
+        assert!(token.check_genapi().is_ok());
+        token.cancel();
+
+        assert!(CameleonError::from(token.check_control().unwrap_err()).is_cancelled());
+        assert!(CameleonError::from(token.check_stream().unwrap_err()).is_cancelled());
+        assert!(CameleonError::from(token.check_genapi().unwrap_err()).is_cancelled());
+
+        let timeout = cameleon_genapi::GenApiError::Device(Box::new(ControlError::Timeout));
+        assert!(!CameleonError::from(timeout).is_cancelled());
+        assert!(!CameleonError::from(ControlError::Busy).is_cancelled());
+    }
 }
@@ -9,11 +9,21 @@
 
 pub use cameleon_device::PixelFormat;
 
-use std::time;
+use std::{
+    convert::TryInto,
+    fmt,
+    ops::Range,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time,
+};
 
-use async_std::channel::{Receiver, Sender};
+use async_std::channel::{Receiver, Sender, TrySendError};
+use futures::future::{self, Either};
 
-use super::{StreamError, StreamResult};
+use super::{cancel::CancellationToken, clock::ClockCorrelation, StreamError, StreamResult};
 
 /// Represents Payload type of the image.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -24,6 +34,8 @@ pub enum PayloadType {
     ImageExtendedChunk,
     /// Payload contains multiple data chunks, no gurantee about its first chunk.
     Chunk,
+    /// Payload contains a GenDC container, its descriptor describes the layout of the data.
+    GenDC,
 }
 
 /// Image meta information.
@@ -54,6 +66,8 @@ pub struct Payload {
     pub(crate) payload: Vec<u8>,
     pub(crate) valid_payload_size: usize,
     pub(crate) timestamp: time::Duration,
+    pub(crate) host_timestamp: time::Instant,
+    pub(crate) chunks: Option<ChunkMap>,
 }
 
 impl Payload {
@@ -92,6 +106,84 @@ impl Payload {
         self.timestamp
     }
 
+    /// Time of the host monotonic clock when the payload was completely received, i.e. when its
+    /// trailer was read.
+    ///
+    /// Host timestamps are not recorded in files, so this is the time when the payload was read or
+    /// played back for payloads of recorded files.
+    pub fn host_timestamp(&self) -> time::Instant {
+        self.host_timestamp
+    }
+
+    /// Returns the time from when the payload was generated on the device to when it was received
+    /// by the host.
+    ///
+    /// The generation time is estimated from [`Self::timestamp`] by `correlation`, so the result
+    /// has the error of [`ClockCorrelation::uncertainty`] and is clamped to zero.
+    /// Returns `None` if the generation time can't be estimated.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use cameleon::u3v;
+    ///
+    /// let mut camera = u3v::enumerate_cameras().unwrap().pop().unwrap();
+    /// camera.open().unwrap();
+    /// let correlation = camera.ctrl.correlate_clock(16).unwrap();
+    ///
+    /// let payload_rx = camera.start_streaming(3).unwrap();
+    /// let payload = async_std::task::block_on(payload_rx.recv()).unwrap();
+    /// println!("latency: {:?}", payload.latency(&correlation));
+    /// payload_rx.send_back(payload);
+    /// ```
+    pub fn latency(&self, correlation: &ClockCorrelation) -> Option<time::Duration> {
+        let generated = correlation.to_host(self.timestamp)?;
+        Some(self.host_timestamp.saturating_duration_since(generated))
+    }
+
+    /// Returns the layout of chunk data if `payload_type` is [`PayloadType::Chunk`] or
+    /// [`PayloadType::ImageExtendedChunk`].
+    ///
+    /// Values of chunk features, e.g. `ChunkExposureTime`, are decoded through the `GenApi`
+    /// context after loading the chunk data by [`crate::genapi::ParamsCtxt::load_chunks`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use cameleon::u3v;
+    ///
+    /// let mut camera = u3v::enumerate_cameras().unwrap().pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// let payload_rx = camera.start_streaming(3).unwrap();
+    /// let payload = async_std::task::block_on(payload_rx.recv()).unwrap();
+    /// if let Some(chunks) = payload.chunks() {
+    ///     for (id, range) in chunks.iter() {
+    ///         println!("chunk {:#x}: {} bytes", id, range.len());
+    ///     }
+    ///
+    ///     let mut params_ctxt = camera.params_ctxt().unwrap();
+    ///     params_ctxt.load_chunks(&payload);
+    ///     let exposure_time = params_ctxt
+    ///         .node("ChunkExposureTime")
+    ///         .unwrap()
+    ///         .as_float(&params_ctxt)
+    ///         .unwrap();
+    ///     println!("{}", exposure_time.value(&mut params_ctxt).unwrap());
+    /// }
+    /// payload_rx.send_back(payload);
+    /// ```
+    pub fn chunks(&self) -> Option<&ChunkMap> {
+        self.chunks.as_ref()
+    }
+
+    /// Returns the data of the chunk with `id` if exists.
+    pub fn chunk_data(&self, id: u32) -> Option<&[u8]> {
+        let range = self.chunks()?.get(id)?;
+        Some(&self.payload[range])
+    }
+
     /// Returns the payload as `Vec<u8>`.
     pub fn into_vec(mut self) -> Vec<u8> {
         self.payload.resize(self.valid_payload_size, 0);
@@ -99,6 +191,170 @@ impl Payload {
     }
 }
 
+/// Layout of chunk data in a payload, which maps chunk IDs to byte ranges in the payload.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChunkMap {
+    /// Chunks in the order of the payload.
+    entries: Vec<(u32, Range<usize>)>,
+}
+
+impl ChunkMap {
+    /// Returns the byte range of the chunk with `id` in the payload.
+    ///
+    /// If the payload contains multiple chunks with the same ID, the first one is returned.
+    #[must_use]
+    pub fn get(&self, id: u32) -> Option<Range<usize>> {
+        self.entries
+            .iter()
+            .find(|(chunk_id, _)| *chunk_id == id)
+            .map(|(_, range)| range.clone())
+    }
+
+    /// Returns an iterator over chunk IDs and byte ranges of chunks in the order of the payload.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, Range<usize>)> + '_ {
+        self.entries.iter().cloned()
+    }
+
+    /// Returns the number of chunks.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the payload contains no chunks.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Occupancy of the payload channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChannelOccupancy {
+    /// The number of payloads in the channel.
+    pub len: usize,
+    /// Capacity of the channel.
+    pub capacity: usize,
+}
+
+impl ChannelOccupancy {
+    /// Returns the ratio of `len` to `capacity`, which is in the range of `0.0..=1.0`.
+    pub fn ratio(self) -> f64 {
+        if self.capacity == 0 {
+            0.0
+        } else {
+            self.len as f64 / self.capacity as f64
+        }
+    }
+}
+
+/// An event notified to the callback registered by [`PayloadReceiver::set_watermark_callback`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatermarkEvent {
+    /// Occupancy of the channel reached the high watermark, i.e. the host falls behind the
+    /// device.
+    High(ChannelOccupancy),
+    /// Occupancy of the channel fell to the low watermark after reaching the high watermark,
+    /// i.e. the host caught up with the device.
+    Low(ChannelOccupancy),
+}
+
+type WatermarkCallback = Box<dyn FnMut(WatermarkEvent) + Send>;
+
+struct Watermark {
+    high: f64,
+    low: f64,
+    is_above_high: bool,
+    callback: WatermarkCallback,
+}
+
+impl Watermark {
+    fn update(&mut self, occupancy: ChannelOccupancy) {
+        let ratio = occupancy.ratio();
+        if !self.is_above_high && ratio >= self.high {
+            self.is_above_high = true;
+            (self.callback)(WatermarkEvent::High(occupancy));
+        } else if self.is_above_high && ratio <= self.low {
+            self.is_above_high = false;
+            (self.callback)(WatermarkEvent::Low(occupancy));
+        }
+    }
+}
+
+impl fmt::Debug for Watermark {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Watermark")
+            .field("high", &self.high)
+            .field("low", &self.low)
+            .field("is_above_high", &self.is_above_high)
+            .finish()
+    }
+}
+
+type SharedWatermark = Arc<Mutex<Option<Watermark>>>;
+
+fn update_watermark(watermark: &SharedWatermark, len: usize, capacity: Option<usize>) {
+    if let Some(watermark) = watermark.lock().unwrap().as_mut() {
+        let occupancy = ChannelOccupancy {
+            len,
+            capacity: capacity.unwrap_or_default(),
+        };
+        watermark.update(occupancy);
+    }
+}
+
+/// Statistics of payloads sent through the payload channel.
+///
+/// The statistics are counted since the channel is created, i.e. since streaming is started.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct StreamStatistics {
+    /// The number of payloads delivered to the channel.
+    pub num_payloads: u64,
+    /// The number of payloads dropped because the channel was full, i.e. the host fell behind
+    /// the device.
+    pub num_dropped: u64,
+    /// The number of errors sent instead of payloads, e.g. for broken or lost payloads.
+    pub num_errors: u64,
+}
+
+/// Counters of [`StreamStatistics`] shared by [`PayloadSender`] and [`PayloadReceiver`].
+#[derive(Debug, Default)]
+pub(crate) struct StreamCounters {
+    num_payloads: AtomicU64,
+    num_dropped: AtomicU64,
+    num_errors: AtomicU64,
+}
+
+impl StreamCounters {
+    /// Counts a payload or an error sent to the channel. `is_dropped` is `true` if it was
+    /// dropped because the channel was full.
+    fn record(&self, is_ok: bool, is_dropped: bool) {
+        let counter = if !is_ok {
+            &self.num_errors
+        } else if is_dropped {
+            &self.num_dropped
+        } else {
+            &self.num_payloads
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn statistics(&self) -> StreamStatistics {
+        StreamStatistics {
+            num_payloads: self.num_payloads.load(Ordering::Relaxed),
+            num_dropped: self.num_dropped.load(Ordering::Relaxed),
+            num_errors: self.num_errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+pub(crate) type SharedStreamCounters = Arc<StreamCounters>;
+
 /// An Receiver of the `Payload` which is sent from a device.
 #[derive(Debug, Clone)]
 pub struct PayloadReceiver {
@@ -107,19 +363,148 @@ pub struct PayloadReceiver {
 
     /// Receives `payload` from the device.
     rx: Receiver<StreamResult<Payload>>,
+
+    /// Watermarks of the occupancy of `rx`, shared with [`PayloadSender`].
+    watermark: SharedWatermark,
+
+    /// Statistics of payloads sent to `rx`, shared with [`PayloadSender`].
+    counters: SharedStreamCounters,
 }
 
 impl PayloadReceiver {
     /// Receives [`Payload`] sent from the device.
     pub async fn recv(&self) -> StreamResult<Payload> {
-        self.rx.recv().await?
+        let payload = self.rx.recv().await?;
+        update_watermark(&self.watermark, self.rx.len(), self.rx.capacity());
+        payload
+    }
+
+    /// Receives [`Payload`] sent from the device, or returns [`StreamError::Cancelled`] as soon
+    /// as `token` is cancelled.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// # camera.open().unwrap();
+    /// # camera.load_context().unwrap();
+    /// use cameleon::cancel::CancellationToken;
+    ///
+    /// let payload_rx = camera.start_streaming(3).unwrap();
+    /// let token = CancellationToken::new();
+    /// // Call `token.cancel()` from e.g. a cancel button to stop waiting.
+    /// match async_std::task::block_on(payload_rx.recv_with_cancel(&token)) {
+    ///     Ok(payload) => payload_rx.send_back(payload),
+    ///     Err(e) => println!("{}", e),
+    /// }
+    /// # camera.close().unwrap();
+    /// ```
+    pub async fn recv_with_cancel(&self, token: &CancellationToken) -> StreamResult<Payload> {
+        token.check_stream()?;
+        let recv = self.recv();
+        let cancelled = token.cancelled();
+        futures::pin_mut!(recv, cancelled);
+        match future::select(recv, cancelled).await {
+            Either::Left((payload, _)) => payload,
+            Either::Right(_) => Err(StreamError::Cancelled),
+        }
     }
 
     /// Tries to receive [`Payload`].
     /// This method doesn't wait arrival of `payload` and immediately returns `StreamError` if
     /// the channel is empty.
     pub fn try_recv(&self) -> StreamResult<Payload> {
-        self.rx.try_recv()?
+        let payload = self.rx.try_recv()?;
+        update_watermark(&self.watermark, self.rx.len(), self.rx.capacity());
+        payload
+    }
+
+    /// Returns the current occupancy of the channel.
+    pub fn occupancy(&self) -> ChannelOccupancy {
+        ChannelOccupancy {
+            len: self.rx.len(),
+            capacity: self.rx.capacity().unwrap_or_default(),
+        }
+    }
+
+    /// Registers a callback which is called when occupancy of the channel crosses watermarks.
+    ///
+    /// `high` and `low` are ratios of the occupancy to the capacity of the channel.
+    /// [`WatermarkEvent::High`] is notified when the occupancy reaches `high`, then
+    /// [`WatermarkEvent::Low`] is notified when the occupancy falls to `low`. This hysteresis
+    /// prevents the callback from being called repeatedly while the occupancy fluctuates around a
+    /// watermark.
+    ///
+    /// Applications can use the callback to lower frame rate or resolution of the device when
+    /// the host falls behind, and to restore them when the host catches up.
+    ///
+    /// The callback is called from the streaming loop or the thread receiving payloads, so it
+    /// should return quickly. Registering a new callback replaces the previous one.
+    /// Calling [`Self::set_watermark_callback`] or [`Self::clear_watermark_callback`] inside the
+    /// callback leads to a deadlock.
+    ///
+    /// # Panics
+    /// Panics if `0.0 <= low < high <= 1.0` is not satisfied.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// # camera.open().unwrap();
+    /// # camera.load_context().unwrap();
+    /// use cameleon::payload::WatermarkEvent;
+    ///
+    /// let payload_rx = camera.start_streaming(10).unwrap();
+    /// let (event_tx, event_rx) = std::sync::mpsc::channel();
+    /// // Notify when the channel gets 80% full, and when it's drained below 20% again.
+    /// payload_rx.set_watermark_callback(0.8, 0.2, move |event| {
+    ///     event_tx.send(event).ok();
+    /// });
+    ///
+    /// // Adjust `AcquisitionFrameRate` through `GenApi` on receiving events.
+    /// if let Ok(WatermarkEvent::High(_)) = event_rx.try_recv() {
+    ///     let mut params_ctxt = camera.params_ctxt().unwrap();
+    ///     // ...
+    /// }
+    /// # camera.close().unwrap();
+    /// ```
+    pub fn set_watermark_callback<F>(&self, high: f64, low: f64, callback: F)
+    where
+        F: FnMut(WatermarkEvent) + Send + 'static,
+    {
+        assert!(
+            (0.0..=1.0).contains(&low) && (0.0..=1.0).contains(&high) && low < high,
+            "watermarks must satisfy `0.0 <= low < high <= 1.0`"
+        );
+
+        *self.watermark.lock().unwrap() = Some(Watermark {
+            high,
+            low,
+            is_above_high: false,
+            callback: Box::new(callback),
+        });
+    }
+
+    /// Unregisters the callback registered by [`Self::set_watermark_callback`].
+    pub fn clear_watermark_callback(&self) {
+        *self.watermark.lock().unwrap() = None;
+    }
+
+    /// Returns statistics of payloads sent to the channel.
+    pub fn statistics(&self) -> StreamStatistics {
+        self.counters.statistics()
+    }
+
+    pub(crate) fn shared_counters(&self) -> SharedStreamCounters {
+        self.counters.clone()
     }
 
     /// Sends back [`Payload`] to the device to reuse already allocated `payload`.
@@ -138,18 +523,34 @@ pub struct PayloadSender {
     tx: Sender<StreamResult<Payload>>,
     /// Sends back payload to reuse it.
     rx: Receiver<Payload>,
+    /// Watermarks of the occupancy of `tx`, shared with [`PayloadReceiver`].
+    watermark: SharedWatermark,
+    /// Statistics of payloads sent to `tx`, shared with [`PayloadReceiver`].
+    counters: SharedStreamCounters,
 }
 
 impl PayloadSender {
     /// Sends [`Payload`] to the host.
     pub async fn send(&self, payload: StreamResult<Payload>) -> StreamResult<()> {
-        Ok(self.tx.send(payload).await?)
+        let is_ok = payload.is_ok();
+        self.tx.send(payload).await?;
+        self.counters.record(is_ok, false);
+        update_watermark(&self.watermark, self.tx.len(), self.tx.capacity());
+        Ok(())
     }
 
     /// Tries to send [`Payload`] to the host.
     /// Returns `StreamError` if the channel is full or empty.
     pub fn try_send(&self, payload: StreamResult<Payload>) -> StreamResult<()> {
-        Ok(self.tx.try_send(payload)?)
+        let is_ok = payload.is_ok();
+        let res = self.tx.try_send(payload);
+        // Nobody cares about statistics if the receiver has gone.
+        if !matches!(res, Err(TrySendError::Closed(_))) {
+            self.counters.record(is_ok, res.is_err());
+        }
+        // Update watermarks even if the channel is full so that the callback is notified.
+        update_watermark(&self.watermark, self.tx.len(), self.tx.capacity());
+        Ok(res?)
     }
 
     /// Tries to receive [`Payload`].
@@ -160,18 +561,69 @@ impl PayloadSender {
     }
 }
 
+/// Returns the size of the first chunk, which is an image, in a payload of
+/// [`PayloadType::ImageExtendedChunk`].
+pub(crate) fn first_chunk_size(chunks: &ChunkMap) -> StreamResult<usize> {
+    chunks
+        .entries
+        .first()
+        .map(|(_, range)| range.len())
+        .ok_or_else(|| StreamError::InvalidPayload("failed to parse chunk data: no chunk".into()))
+}
+
+/// Parses the layout of chunk data.
+///
+/// Each chunk consists of its data followed by its ID and its data size as big endian `u32`, so
+/// chunk data is designed to be decoded from the last byte to the first byte.
+pub(crate) fn parse_chunks(chunk_data: &[u8]) -> StreamResult<ChunkMap> {
+    const CHUNK_ID_LEN: usize = 4;
+    const CHUNK_SIZE_LEN: usize = 4;
+
+    let read_u32 =
+        |offset: usize| u32::from_be_bytes(chunk_data[offset..offset + 4].try_into().unwrap());
+
+    let mut entries = vec![];
+    let mut current_offset = chunk_data.len();
+    while current_offset > 0 {
+        current_offset = current_offset.checked_sub(CHUNK_SIZE_LEN).ok_or_else(|| {
+            StreamError::InvalidPayload("failed to parse chunk data: size field missing".into())
+        })?;
+        let data_size = read_u32(current_offset) as usize;
+        current_offset = current_offset.checked_sub(CHUNK_ID_LEN).ok_or_else(|| {
+            StreamError::InvalidPayload("failed to parse chunk data: ID field missing".into())
+        })?;
+        let id = read_u32(current_offset);
+        current_offset = current_offset.checked_sub(data_size).ok_or_else(|| {
+            StreamError::InvalidPayload(
+                "failed to parse chunk data: chunk data size is smaller than specified size".into(),
+            )
+        })?;
+
+        entries.push((id, current_offset..current_offset + data_size));
+    }
+
+    entries.reverse();
+    Ok(ChunkMap { entries })
+}
+
 /// Creates [`PayloadReceiver`] and [`PayloadSender`].
 pub fn channel(payload_cap: usize, buffer_cap: usize) -> (PayloadSender, PayloadReceiver) {
     let (device_tx, host_rx) = async_std::channel::bounded(payload_cap);
     let (host_tx, device_rx) = async_std::channel::bounded(buffer_cap);
+    let watermark = SharedWatermark::default();
+    let counters = SharedStreamCounters::default();
     (
         PayloadSender {
             tx: device_tx,
             rx: device_rx,
+            watermark: watermark.clone(),
+            counters: counters.clone(),
         },
         PayloadReceiver {
             tx: host_tx,
             rx: host_rx,
+            watermark,
+            counters,
         },
     )
 }
@@ -199,3 +651,96 @@ impl<T> From<async_std::channel::TrySendError<T>> for StreamError {
         StreamError::ReceiveError(err.to_string().into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: u32, data: &[u8]) -> Vec<u8> {
+        let mut chunk = data.to_vec();
+        chunk.extend_from_slice(&id.to_be_bytes());
+        chunk.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        chunk
+    }
+
+    #[test]
+    fn test_parse_chunks() {
+        let data = [
+            chunk(1, &[0; 6]),
+            chunk(0x1234, &[1, 2, 3, 4]),
+            chunk(7, &[]),
+        ]
+        .concat();
+        let chunks = parse_chunks(&data).unwrap();
+        assert_eq!(
+            chunks.iter().collect::<Vec<_>>(),
+            [(1, 0..6), (0x1234, 14..18), (7, 26..26)]
+        );
+        assert_eq!(chunks.get(0x1234), Some(14..18));
+        assert_eq!(chunks.get(2), None);
+        assert_eq!(first_chunk_size(&chunks).unwrap(), 6);
+
+        assert!(parse_chunks(&data[1..]).is_err());
+        assert!(parse_chunks(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_latency() {
+        let device_time = time::Duration::from_secs(10);
+        let correlation = ClockCorrelation::sample(1, || Ok(device_time)).unwrap();
+        let generated = correlation.to_host(device_time).unwrap();
+
+        let mut payload = Payload {
+            id: 0,
+            payload_type: PayloadType::Chunk,
+            image_info: None,
+            payload: vec![],
+            valid_payload_size: 0,
+            timestamp: device_time,
+            host_timestamp: generated + time::Duration::from_millis(5),
+            chunks: None,
+        };
+        assert_eq!(
+            payload.latency(&correlation),
+            Some(time::Duration::from_millis(5))
+        );
+
+        // Latency within the error of the correlation is clamped.
+        payload.host_timestamp = generated;
+        payload.timestamp += time::Duration::from_millis(1);
+        assert_eq!(
+            payload.latency(&correlation),
+            Some(time::Duration::default())
+        );
+    }
+
+    #[test]
+    fn test_statistics() {
+        let payload = Payload {
+            id: 0,
+            payload_type: PayloadType::Chunk,
+            image_info: None,
+            payload: vec![],
+            valid_payload_size: 0,
+            timestamp: time::Duration::default(),
+            host_timestamp: time::Instant::now(),
+            chunks: None,
+        };
+        let (sender, receiver) = channel(1, 1);
+        sender.try_send(Ok(payload.clone())).unwrap();
+        assert!(sender.try_send(Ok(payload)).is_err());
+        assert!(sender.try_send(Err(StreamError::Timeout)).is_err());
+        assert_eq!(
+            receiver.statistics(),
+            StreamStatistics {
+                num_payloads: 1,
+                num_dropped: 1,
+                num_errors: 1,
+            }
+        );
+
+        drop(receiver);
+        assert!(sender.try_send(Err(StreamError::Timeout)).is_err());
+        assert_eq!(sender.counters.statistics().num_errors, 1);
+    }
+}
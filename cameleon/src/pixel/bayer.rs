@@ -0,0 +1,153 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use super::PixelFormat;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Color {
+    Red,
+    Green,
+    Blue,
+}
+
+/// Color filter array pattern, named after the colors of the first two pixels of the first row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum CfaPattern {
+    Rg,
+    Gr,
+    Gb,
+    Bg,
+}
+
+impl CfaPattern {
+    /// Returns the pattern and the bit depth of `format`, or `None` if `format` is not a bayer
+    /// format.
+    pub(super) fn of(format: PixelFormat) -> Option<(Self, u32)> {
+        use PixelFormat::{
+            BayerBG10, BayerBG12, BayerBG16, BayerBG8, BayerGB10, BayerGB12, BayerGB16, BayerGB8,
+            BayerGR10, BayerGR12, BayerGR16, BayerGR8, BayerRG10, BayerRG12, BayerRG16, BayerRG8,
+        };
+
+        let pattern = match format {
+            BayerRG8 | BayerRG10 | BayerRG12 | BayerRG16 => Self::Rg,
+            BayerGR8 | BayerGR10 | BayerGR12 | BayerGR16 => Self::Gr,
+            BayerGB8 | BayerGB10 | BayerGB12 | BayerGB16 => Self::Gb,
+            BayerBG8 | BayerBG10 | BayerBG12 | BayerBG16 => Self::Bg,
+            _ => return None,
+        };
+        let bits = match format {
+            BayerRG8 | BayerGR8 | BayerGB8 | BayerBG8 => 8,
+            BayerRG10 | BayerGR10 | BayerGB10 | BayerBG10 => 10,
+            BayerRG12 | BayerGR12 | BayerGB12 | BayerBG12 => 12,
+            _ => 16,
+        };
+        Some((pattern, bits))
+    }
+
+    fn color_at(self, x: usize, y: usize) -> Color {
+        let cell = match self {
+            Self::Rg => [[Color::Red, Color::Green], [Color::Green, Color::Blue]],
+            Self::Gr => [[Color::Green, Color::Red], [Color::Blue, Color::Green]],
+            Self::Gb => [[Color::Green, Color::Blue], [Color::Red, Color::Green]],
+            Self::Bg => [[Color::Blue, Color::Green], [Color::Green, Color::Red]],
+        };
+        cell[y % 2][x % 2]
+    }
+}
+
+/// Demosaics `src` into `RGB8` pixels in `dst` by bilinear interpolation.
+///
+/// Missing colors of each pixel are the average of the nearest pixels of the color. Pixels outside
+/// of the image are mirrored at the borders so that the pattern is kept. `width` and `height` must
+/// be at least 2.
+pub(super) fn demosaic(
+    src: &[u8],
+    width: usize,
+    height: usize,
+    pattern: CfaPattern,
+    dst: &mut [u8],
+) {
+    debug_assert!(width >= 2 && height >= 2);
+
+    // Mirror the coordinate so that `-1` becomes `1` and `len` becomes `len - 2`.
+    let mirror = |v: isize, len: usize| -> usize {
+        if v < 0 {
+            1
+        } else if v as usize >= len {
+            len - 2
+        } else {
+            v as usize
+        }
+    };
+
+    for y in 0..height {
+        for x in 0..width {
+            let at = |dx: isize, dy: isize| {
+                let x = mirror(x as isize + dx, width);
+                let y = mirror(y as isize + dy, height);
+                u16::from(src[y * width + x])
+            };
+            let cross = || ((at(-1, 0) + at(1, 0) + at(0, -1) + at(0, 1) + 2) / 4) as u8;
+            let diagonal = || ((at(-1, -1) + at(1, -1) + at(-1, 1) + at(1, 1) + 2) / 4) as u8;
+            let horizontal = || (at(-1, 0) + at(1, 0)).div_ceil(2) as u8;
+            let vertical = || (at(0, -1) + at(0, 1)).div_ceil(2) as u8;
+
+            let center = at(0, 0) as u8;
+            let rgb = match pattern.color_at(x, y) {
+                Color::Red => [center, cross(), diagonal()],
+                Color::Blue => [diagonal(), cross(), center],
+                Color::Green => {
+                    if pattern.color_at(x + 1, y) == Color::Red {
+                        [horizontal(), center, vertical()]
+                    } else {
+                        [vertical(), center, horizontal()]
+                    }
+                }
+            };
+
+            let offset = (y * width + x) * 3;
+            dst[offset..offset + 3].copy_from_slice(&rgb);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_demosaic_uniform() {
+        // A uniform gray scene must be restored as is regardless of the pattern.
+        for pattern in &[
+            CfaPattern::Rg,
+            CfaPattern::Gr,
+            CfaPattern::Gb,
+            CfaPattern::Bg,
+        ] {
+            let src = [100; 16];
+            let mut dst = [0; 48];
+            demosaic(&src, 4, 4, *pattern, &mut dst);
+            assert!(dst.iter().all(|v| *v == 100));
+        }
+    }
+
+    #[test]
+    fn test_demosaic_color() {
+        // A red scene captured with `RG` pattern.
+        let src = [200, 0, 200, 0, 0, 0, 0, 0];
+        let mut dst = [0; 24];
+        demosaic(&src, 4, 2, CfaPattern::Rg, &mut dst);
+        for rgb in dst.chunks_exact(3) {
+            assert_eq!(rgb, [200, 0, 0]);
+        }
+
+        // A blue scene captured with `GR` pattern.
+        let src = [0, 0, 0, 0, 50, 0, 50, 0];
+        let mut dst = [0; 24];
+        demosaic(&src, 4, 2, CfaPattern::Gr, &mut dst);
+        for rgb in dst.chunks_exact(3) {
+            assert_eq!(rgb, [0, 0, 50]);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::convert::TryFrom;
+
+use image::{DynamicImage, ImageBuffer, Pixel};
+
+use super::{convert, le_u16s, strip_padding, ConvertError, ConvertResult, Layout};
+use crate::payload::{ImageInfo, Payload, PixelFormat};
+
+impl Payload {
+    /// Converts the image in the payload into [`DynamicImage`].
+    ///
+    /// `Mono8`, `RGB8`, `RGBa8` and `RGB16` images are mapped into the corresponding variants
+    /// as is, and `BGRa8` images into [`DynamicImage::ImageRgba8`]. Other monochrome formats are
+    /// converted into [`DynamicImage::ImageLuma16`] and other color formats into
+    /// [`DynamicImage::ImageRgb8`] by [`convert`].
+    ///
+    /// If the image is larger than its pixels, each line is assumed to be padded to
+    /// `image_size / height` bytes and the padding is removed.
+    ///
+    /// This method is available only when `image` feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use cameleon::u3v;
+    ///
+    /// let mut camera = u3v::enumerate_cameras().unwrap().pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// let payload_rx = camera.start_streaming(3).unwrap();
+    /// let payload = async_std::task::block_on(payload_rx.recv()).unwrap();
+    /// let image = payload.to_image().unwrap().into_rgb8();
+    /// println!("{:?}", image.dimensions());
+    /// payload_rx.send_back(payload);
+    /// ```
+    pub fn to_image(&self) -> ConvertResult<DynamicImage> {
+        let info = self.image_info().ok_or(ConvertError::NoImage)?;
+        let data = strip_padding(self.image().ok_or(ConvertError::NoImage)?, info)?;
+        let (width, height) = (info.width, info.height);
+
+        let image = match info.pixel_format {
+            PixelFormat::Mono8 => DynamicImage::ImageLuma8(buffer(info, data.into_owned())?),
+            PixelFormat::RGB8 => DynamicImage::ImageRgb8(buffer(info, data.into_owned())?),
+            PixelFormat::RGBa8 => DynamicImage::ImageRgba8(buffer(info, data.into_owned())?),
+            PixelFormat::BGRa8 => {
+                let mut data = data.into_owned();
+                for pixel in data.chunks_exact_mut(4) {
+                    pixel.swap(0, 2);
+                }
+                DynamicImage::ImageRgba8(buffer(info, data)?)
+            }
+            PixelFormat::RGB16 => DynamicImage::ImageRgb16(buffer(info, le_u16s(&data).collect())?),
+            format => match Layout::of(format) {
+                Some(Layout::Mono16 { .. } | Layout::MonoPacked { .. }) => {
+                    let image = convert(&data, width, height, format, PixelFormat::Mono16)?;
+                    DynamicImage::ImageLuma16(buffer(info, le_u16s(&image.data).collect())?)
+                }
+                _ => {
+                    let image = convert(&data, width, height, format, PixelFormat::RGB8)?;
+                    DynamicImage::ImageRgb8(buffer(info, image.data)?)
+                }
+            },
+        };
+        Ok(image)
+    }
+}
+
+fn buffer<P: Pixel>(
+    info: &ImageInfo,
+    mut data: Vec<P::Subpixel>,
+) -> ConvertResult<ImageBuffer<P, Vec<P::Subpixel>>> {
+    let size = |v: usize| {
+        u32::try_from(v).map_err(|_| ConvertError::InvalidSize("image is too large".into()))
+    };
+    let (width, height) = (size(info.width)?, size(info.height)?);
+
+    let len = info.width * info.height * usize::from(P::CHANNEL_COUNT);
+    if data.len() < len {
+        return Err(ConvertError::too_short(len, data.len()));
+    }
+    data.truncate(len);
+    Ok(ImageBuffer::from_raw(width, height, data).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::payload::PayloadType;
+
+    fn payload(pixel_format: PixelFormat, width: usize, height: usize, data: Vec<u8>) -> Payload {
+        Payload {
+            id: 0,
+            payload_type: PayloadType::Image,
+            image_info: Some(ImageInfo {
+                width,
+                height,
+                x_offset: 0,
+                y_offset: 0,
+                pixel_format,
+                image_size: data.len(),
+            }),
+            valid_payload_size: data.len(),
+            payload: data,
+            timestamp: Duration::default(),
+            host_timestamp: Instant::now(),
+            chunks: None,
+        }
+    }
+
+    #[test]
+    fn test_to_image() {
+        let image = payload(PixelFormat::Mono8, 2, 1, vec![1, 2])
+            .to_image()
+            .unwrap();
+        assert_eq!(image.as_luma8().unwrap().as_raw(), &[1, 2]);
+
+        let image = payload(PixelFormat::Mono12p, 2, 1, vec![0xBC, 0x3A, 0x12])
+            .to_image()
+            .unwrap();
+        assert_eq!(image.as_luma16().unwrap().as_raw(), &[0xABC0, 0x1230]);
+
+        let image = payload(PixelFormat::BGR8, 1, 1, vec![1, 2, 3])
+            .to_image()
+            .unwrap();
+        assert_eq!(image.as_rgb8().unwrap().as_raw(), &[3, 2, 1]);
+    }
+
+    #[test]
+    fn test_to_image_padded() {
+        // Each line is padded to 4 bytes.
+        let image = payload(PixelFormat::Mono8, 3, 2, vec![1, 2, 3, 0, 4, 5, 6, 0])
+            .to_image()
+            .unwrap();
+        assert_eq!(image.as_luma8().unwrap().as_raw(), &[1, 2, 3, 4, 5, 6]);
+    }
+}
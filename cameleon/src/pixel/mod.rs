@@ -0,0 +1,561 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module provides conversion of image data between pixel formats, and statistics of pixel
+//! values.
+//!
+//! Most devices send images in formats which can't be displayed or processed directly, e.g.
+//! bayer patterns or packed pixels. [`convert`] converts such images into one of the following
+//! formats.
+//!
+//! | Source                                               | Destination                       |
+//! |------------------------------------------------------|-----------------------------------|
+//! | `Mono8`, `Mono10`, `Mono12`, `Mono14`, `Mono16`      | `Mono8`, `Mono16`, `RGB8`, `BGR8` |
+//! | `Mono10Packed`, `Mono12Packed`, `Mono10p`, `Mono12p` | `Mono8`, `Mono16`, `RGB8`, `BGR8` |
+//! | `Bayer{RG,GR,GB,BG}{8,10,12,16}`                     | `RGB8`, `BGR8`                    |
+//! | `YUV422_8`, `YCbCr422_8`, `YCbCr422_8_CbYCrY`        | `Mono8`, `RGB8`, `BGR8`           |
+//! | `RGB8`, `BGR8`, `RGBa8`, `BGRa8`, `RGB16`            | `Mono8`, `RGB8`, `BGR8`           |
+//!
+//! Pixels are converted to the full range of the destination format, e.g. a `Mono12` pixel is
+//! shifted left by 4 bits when converted to `Mono16`. Bayer patterns are demosaiced by bilinear
+//! interpolation.
+//!
+//! [`image_stats`] computes a histogram, the mean and the saturation of pixel values directly from
+//! image data, which is cheap enough to be used in auto exposure or focus loops.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use cameleon::{payload::PixelFormat, u3v};
+//!
+//! let mut camera = u3v::enumerate_cameras().unwrap().pop().unwrap();
+//! camera.open().unwrap();
+//! camera.load_context().unwrap();
+//!
+//! let payload_rx = camera.start_streaming(3).unwrap();
+//! let payload = async_std::task::block_on(payload_rx.recv()).unwrap();
+//! let image = payload.convert_image(PixelFormat::RGB8).unwrap();
+//! println!("{}x{}, {} bytes", image.width, image.height, image.data.len());
+//! payload_rx.send_back(payload);
+//! ```
+
+mod bayer;
+#[cfg(feature = "image")]
+mod image_interop;
+#[cfg(feature = "ndarray")]
+mod ndarray_interop;
+mod stats;
+mod unpack;
+mod yuv;
+
+pub use stats::{image_stats, Histogram, ImageStats};
+pub use unpack::unpack;
+
+use std::borrow::Cow;
+
+#[cfg(any(
+    feature = "image",
+    feature = "ndarray",
+    feature = "png",
+    feature = "tiff"
+))]
+use super::payload::ImageInfo;
+use super::payload::{Payload, PixelFormat};
+
+use bayer::CfaPattern;
+use unpack::Packing;
+use yuv::YuvOrder;
+
+/// A `Result` type of pixel format conversion.
+pub type ConvertResult<T> = std::result::Result<T, ConvertError>;
+
+/// An error of pixel format conversion.
+#[derive(Debug, thiserror::Error)]
+pub enum ConvertError {
+    /// The conversion between the formats is not supported.
+    #[error("conversion from {src:?} to {dst:?} is not supported")]
+    UnsupportedConversion {
+        /// Format of the source image.
+        src: PixelFormat,
+        /// Requested format.
+        dst: PixelFormat,
+    },
+
+    /// The format is not supported by the operation.
+    #[error("{0:?} is not supported")]
+    UnsupportedFormat(PixelFormat),
+
+    /// The size of the image doesn't match its format.
+    #[error("invalid image size: {0}")]
+    InvalidSize(Cow<'static, str>),
+
+    /// The payload doesn't contain an image.
+    #[error("payload doesn't contain an image")]
+    NoImage,
+}
+
+impl ConvertError {
+    fn too_short(expected: usize, actual: usize) -> Self {
+        Self::InvalidSize(
+            format!(
+                "image data is too short: expected {} bytes, but got {} bytes",
+                expected, actual
+            )
+            .into(),
+        )
+    }
+}
+
+/// An image converted by [`convert`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    /// Width of the image.
+    pub width: usize,
+    /// Height of the image.
+    pub height: usize,
+    /// [`PixelFormat`] of the image.
+    pub pixel_format: PixelFormat,
+    /// Pixel data without padding. Multi-byte pixels are little endian.
+    pub data: Vec<u8>,
+}
+
+/// Layout of source images.
+#[derive(Clone, Copy, Debug)]
+enum Layout {
+    Mono8,
+    /// Unpacked monochrome pixels in little endian 16 bits containers.
+    Mono16 {
+        bits: u32,
+    },
+    MonoPacked {
+        packing: Packing,
+        bits: u32,
+    },
+    Bayer {
+        pattern: CfaPattern,
+        bits: u32,
+    },
+    Yuv422(YuvOrder),
+    Rgb {
+        bgr: bool,
+        alpha: bool,
+    },
+    Rgb16,
+}
+
+impl Layout {
+    fn of(format: PixelFormat) -> Option<Self> {
+        if let Some((packing, bits)) = Packing::of(format) {
+            return Some(Self::MonoPacked { packing, bits });
+        }
+        if let Some((pattern, bits)) = CfaPattern::of(format) {
+            return Some(Self::Bayer { pattern, bits });
+        }
+        if let Some(order) = YuvOrder::of(format) {
+            return Some(Self::Yuv422(order));
+        }
+
+        let layout = match format {
+            PixelFormat::Mono8 => Self::Mono8,
+            PixelFormat::Mono10 => Self::Mono16 { bits: 10 },
+            PixelFormat::Mono12 => Self::Mono16 { bits: 12 },
+            PixelFormat::Mono14 => Self::Mono16 { bits: 14 },
+            PixelFormat::Mono16 => Self::Mono16 { bits: 16 },
+            PixelFormat::RGB8 => Self::Rgb {
+                bgr: false,
+                alpha: false,
+            },
+            PixelFormat::BGR8 => Self::Rgb {
+                bgr: true,
+                alpha: false,
+            },
+            PixelFormat::RGBa8 => Self::Rgb {
+                bgr: false,
+                alpha: true,
+            },
+            PixelFormat::BGRa8 => Self::Rgb {
+                bgr: true,
+                alpha: true,
+            },
+            PixelFormat::RGB16 => Self::Rgb16,
+            _ => return None,
+        };
+        Some(layout)
+    }
+
+    fn image_size(self, num_pixels: usize) -> usize {
+        match self {
+            Self::Mono8 => num_pixels,
+            Self::Mono16 { .. } | Self::Yuv422(..) => num_pixels * 2,
+            Self::MonoPacked { packing, bits } => packing.packed_len(bits, num_pixels),
+            Self::Bayer { bits, .. } => num_pixels * if bits == 8 { 1 } else { 2 },
+            Self::Rgb { alpha, .. } => num_pixels * if alpha { 4 } else { 3 },
+            Self::Rgb16 => num_pixels * 6,
+        }
+    }
+}
+
+/// Converts `src`, an image of `width` x `height` pixels in `src_format`, into `dst_format`.
+///
+/// Lines of `src` must not be padded. Bytes after the image are ignored.
+/// See the [module level documentation](self) for supported conversions.
+///
+/// # Examples
+///
+/// ```rust
+/// use cameleon::{payload::PixelFormat, pixel};
+///
+/// let src = [0x00, 0x00, 0xFF, 0x0F];
+/// let image = pixel::convert(&src, 2, 1, PixelFormat::Mono12, PixelFormat::Mono8).unwrap();
+/// assert_eq!(image.data, [0x00, 0xFF]);
+/// ```
+pub fn convert(
+    src: &[u8],
+    width: usize,
+    height: usize,
+    src_format: PixelFormat,
+    dst_format: PixelFormat,
+) -> ConvertResult<Image> {
+    let unsupported = || ConvertError::UnsupportedConversion {
+        src: src_format,
+        dst: dst_format,
+    };
+    let layout = Layout::of(src_format).ok_or_else(unsupported)?;
+
+    let num_pixels = width * height;
+    let expected = layout.image_size(num_pixels);
+    if src.len() < expected {
+        return Err(ConvertError::too_short(expected, src.len()));
+    }
+    let src = &src[..expected];
+
+    let data = match (layout, dst_format) {
+        (Layout::Mono8, PixelFormat::Mono8) => src.to_vec(),
+        (Layout::Mono8, _) => {
+            mono_to(src.iter().map(|v| u16::from(*v)), 8, dst_format).ok_or_else(unsupported)?
+        }
+
+        (Layout::Mono16 { bits }, _) => {
+            mono_to(le_u16s(src), bits, dst_format).ok_or_else(unsupported)?
+        }
+
+        (Layout::MonoPacked { bits, .. }, _) => {
+            let mut unpacked = vec![0; num_pixels];
+            unpack(src, src_format, &mut unpacked)?;
+            mono_to(unpacked.into_iter(), bits, dst_format).ok_or_else(unsupported)?
+        }
+
+        (Layout::Bayer { pattern, bits }, PixelFormat::RGB8 | PixelFormat::BGR8) => {
+            if width < 2 || height < 2 {
+                return Err(ConvertError::InvalidSize(
+                    "bayer image must be at least 2x2 pixels".into(),
+                ));
+            }
+            let src = if bits == 8 {
+                Cow::Borrowed(src)
+            } else {
+                Cow::Owned(le_u16s(src).map(|v| (v >> (bits - 8)) as u8).collect())
+            };
+            let mut rgb = vec![0; num_pixels * 3];
+            bayer::demosaic(&src, width, height, pattern, &mut rgb);
+            if dst_format == PixelFormat::BGR8 {
+                swap_red_blue(&mut rgb);
+            }
+            rgb
+        }
+
+        (Layout::Yuv422(order), PixelFormat::Mono8 | PixelFormat::RGB8 | PixelFormat::BGR8) => {
+            if !width.is_multiple_of(2) {
+                return Err(ConvertError::InvalidSize(
+                    "width of 4:2:2 image must be even".into(),
+                ));
+            }
+            if dst_format == PixelFormat::Mono8 {
+                let mut luma = vec![0; num_pixels];
+                yuv::to_luma(src, order, &mut luma);
+                luma
+            } else {
+                let mut rgb = vec![0; num_pixels * 3];
+                yuv::to_rgb(src, order, &mut rgb);
+                if dst_format == PixelFormat::BGR8 {
+                    swap_red_blue(&mut rgb);
+                }
+                rgb
+            }
+        }
+
+        (Layout::Rgb16, PixelFormat::Mono8 | PixelFormat::RGB8 | PixelFormat::BGR8) => {
+            let rgb: Vec<u8> = le_u16s(src).map(|v| (v >> 8) as u8).collect();
+            return convert(&rgb, width, height, PixelFormat::RGB8, dst_format);
+        }
+
+        (Layout::Rgb { bgr, alpha }, PixelFormat::RGB8 | PixelFormat::BGR8) => {
+            let channels = if alpha { 4 } else { 3 };
+            let mut rgb: Vec<u8> = src
+                .chunks_exact(channels)
+                .flat_map(|pixel| pixel[..3].iter().copied())
+                .collect();
+            if bgr != (dst_format == PixelFormat::BGR8) {
+                swap_red_blue(&mut rgb);
+            }
+            rgb
+        }
+
+        (Layout::Rgb { bgr, alpha }, PixelFormat::Mono8) => {
+            let channels = if alpha { 4 } else { 3 };
+            src.chunks_exact(channels)
+                .map(|pixel| {
+                    let (r, g, b) = if bgr {
+                        (pixel[2], pixel[1], pixel[0])
+                    } else {
+                        (pixel[0], pixel[1], pixel[2])
+                    };
+                    luma(r, g, b)
+                })
+                .collect()
+        }
+
+        _ => return Err(unsupported()),
+    };
+
+    Ok(Image {
+        width,
+        height,
+        pixel_format: dst_format,
+        data,
+    })
+}
+
+impl Payload {
+    /// Converts the image in the payload into `format`.
+    ///
+    /// See [`convert`] for details.
+    pub fn convert_image(&self, format: PixelFormat) -> ConvertResult<Image> {
+        let info = self.image_info().ok_or(ConvertError::NoImage)?;
+        let image = self.image().ok_or(ConvertError::NoImage)?;
+        convert(image, info.width, info.height, info.pixel_format, format)
+    }
+}
+
+/// Converts monochrome pixels of `bits` bits into `dst_format`, returns `None` if `dst_format` is
+/// not supported.
+fn mono_to(
+    pixels: impl Iterator<Item = u16>,
+    bits: u32,
+    dst_format: PixelFormat,
+) -> Option<Vec<u8>> {
+    let to_u8 = move |v: u16| (v >> (bits - 8)) as u8;
+    let data = match dst_format {
+        PixelFormat::Mono8 => pixels.map(to_u8).collect(),
+        PixelFormat::Mono16 => pixels
+            .flat_map(|v| (v << (16 - bits)).to_le_bytes())
+            .collect(),
+        PixelFormat::RGB8 | PixelFormat::BGR8 => pixels.flat_map(|v| [to_u8(v); 3]).collect(),
+        _ => return None,
+    };
+    Some(data)
+}
+
+/// Returns the number of bytes from the start of a line to the start of the next line, or `None`
+/// if lines are not padded.
+///
+/// If the image is larger than its pixels, each line is assumed to be padded to
+/// `image_size / height` bytes.
+#[cfg(any(
+    feature = "image",
+    feature = "ndarray",
+    feature = "png",
+    feature = "tiff"
+))]
+fn padded_stride(layout: Layout, info: &ImageInfo, len: usize) -> Option<usize> {
+    let unpadded_len = layout.image_size(info.width * info.height);
+    if info.height == 0 || len <= unpadded_len || !len.is_multiple_of(info.height) {
+        None
+    } else {
+        Some(len / info.height)
+    }
+}
+
+/// Removes padding at the end of each line, see [`padded_stride`].
+#[cfg(any(
+    feature = "image",
+    feature = "ndarray",
+    feature = "png",
+    feature = "tiff"
+))]
+fn strip_padding<'a>(data: &'a [u8], info: &ImageInfo) -> ConvertResult<Cow<'a, [u8]>> {
+    let layout =
+        Layout::of(info.pixel_format).ok_or(ConvertError::UnsupportedFormat(info.pixel_format))?;
+    match padded_stride(layout, info, data.len()) {
+        Some(stride) => {
+            let row_len = layout.image_size(info.width);
+            Ok(Cow::Owned(
+                data.chunks_exact(stride)
+                    .flat_map(|row| &row[..row_len])
+                    .copied()
+                    .collect(),
+            ))
+        }
+        None => Ok(Cow::Borrowed(data)),
+    }
+}
+
+/// Pixels of an image decoded without losing the bit depth.
+#[cfg(any(feature = "png", feature = "tiff"))]
+pub(crate) enum Samples {
+    Gray8(Vec<u8>),
+    /// Monochrome pixels of `bits` bits.
+    Gray16 {
+        data: Vec<u16>,
+        bits: u32,
+    },
+    Rgb8(Vec<u8>),
+    Rgba8(Vec<u8>),
+    Rgb16(Vec<u16>),
+}
+
+#[cfg(any(feature = "png", feature = "tiff"))]
+impl Samples {
+    /// Decodes the image in `payload`.
+    ///
+    /// Bayer images are decoded as raw monochrome images, since demosaicing loses the bit depth
+    /// and the original data. `4:2:2` images are converted into `RGB8`.
+    pub(crate) fn decode(payload: &Payload) -> ConvertResult<Self> {
+        let info = payload.image_info().ok_or(ConvertError::NoImage)?;
+        let data = strip_padding(payload.image().ok_or(ConvertError::NoImage)?, info)?;
+        let layout = Layout::of(info.pixel_format)
+            .ok_or(ConvertError::UnsupportedFormat(info.pixel_format))?;
+        let num_pixels = info.width * info.height;
+        let expected = layout.image_size(num_pixels);
+        if data.len() < expected {
+            return Err(ConvertError::too_short(expected, data.len()));
+        }
+        let data = &data[..expected];
+
+        let samples = match layout {
+            Layout::Mono8 | Layout::Bayer { bits: 8, .. } => Self::Gray8(data.to_vec()),
+            Layout::Mono16 { bits } | Layout::Bayer { bits, .. } => Self::Gray16 {
+                data: le_u16s(data).collect(),
+                bits,
+            },
+            Layout::MonoPacked { bits, .. } => {
+                let mut unpacked = vec![0; num_pixels];
+                unpack(data, info.pixel_format, &mut unpacked)?;
+                Self::Gray16 {
+                    data: unpacked,
+                    bits,
+                }
+            }
+            Layout::Rgb { bgr, alpha } => {
+                let mut data = data.to_vec();
+                if bgr {
+                    for pixel in data.chunks_exact_mut(if alpha { 4 } else { 3 }) {
+                        pixel.swap(0, 2);
+                    }
+                }
+                if alpha {
+                    Self::Rgba8(data)
+                } else {
+                    Self::Rgb8(data)
+                }
+            }
+            Layout::Rgb16 => Self::Rgb16(le_u16s(data).collect()),
+            Layout::Yuv422(..) => Self::Rgb8(
+                convert(
+                    data,
+                    info.width,
+                    info.height,
+                    info.pixel_format,
+                    PixelFormat::RGB8,
+                )?
+                .data,
+            ),
+        };
+        Ok(samples)
+    }
+}
+
+fn le_u16s(src: &[u8]) -> impl Iterator<Item = u16> + '_ {
+    src.chunks_exact(2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn swap_red_blue(pixels: &mut [u8]) {
+    for pixel in pixels.chunks_exact_mut(3) {
+        pixel.swap(0, 2);
+    }
+}
+
+/// Returns `BT.601` luma of the pixel.
+fn luma(r: u8, g: u8, b: u8) -> u8 {
+    ((77 * u32::from(r) + 150 * u32::from(g) + 29 * u32::from(b) + 128) >> 8) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_mono() {
+        let src = [0x00, 0x80, 0xFF];
+        let image = convert(&src, 3, 1, PixelFormat::Mono8, PixelFormat::Mono16).unwrap();
+        assert_eq!(image.data, [0x00, 0x00, 0x00, 0x80, 0x00, 0xFF]);
+
+        let image = convert(&src, 3, 1, PixelFormat::Mono8, PixelFormat::BGR8).unwrap();
+        assert_eq!(image.data, [0, 0, 0, 0x80, 0x80, 0x80, 0xFF, 0xFF, 0xFF]);
+
+        // 0xABC and 0x123 in `Mono12p`.
+        let src = [0xBC, 0x3A, 0x12];
+        let image = convert(&src, 2, 1, PixelFormat::Mono12p, PixelFormat::Mono16).unwrap();
+        assert_eq!(image.pixel_format, PixelFormat::Mono16);
+        assert_eq!(image.data, [0xC0, 0xAB, 0x30, 0x12]);
+    }
+
+    #[test]
+    fn test_convert_bayer() {
+        let src = [0x00, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        let image = convert(&src, 2, 2, PixelFormat::BayerRG16, PixelFormat::BGR8).unwrap();
+        for bgr in image.data.chunks_exact(3) {
+            assert_eq!(bgr, [0, 0, 0x40]);
+        }
+
+        assert!(matches!(
+            convert(&[0; 2], 2, 1, PixelFormat::BayerRG8, PixelFormat::RGB8),
+            Err(ConvertError::InvalidSize(..))
+        ));
+    }
+
+    #[test]
+    fn test_convert_rgb() {
+        let src = [1, 2, 3, 255, 4, 5, 6, 255];
+        let image = convert(&src, 2, 1, PixelFormat::RGBa8, PixelFormat::BGR8).unwrap();
+        assert_eq!(image.data, [3, 2, 1, 6, 5, 4]);
+
+        let image = convert(
+            &[255, 255, 255],
+            1,
+            1,
+            PixelFormat::BGR8,
+            PixelFormat::Mono8,
+        )
+        .unwrap();
+        assert_eq!(image.data, [255]);
+
+        let src = [0x00, 0x01, 0x00, 0x02, 0x00, 0x03];
+        let image = convert(&src, 1, 1, PixelFormat::RGB16, PixelFormat::BGR8).unwrap();
+        assert_eq!(image.data, [3, 2, 1]);
+    }
+
+    #[test]
+    fn test_convert_error() {
+        assert!(matches!(
+            convert(&[0; 4], 2, 2, PixelFormat::RGB8, PixelFormat::Mono8),
+            Err(ConvertError::InvalidSize(..))
+        ));
+        assert!(matches!(
+            convert(&[0; 4], 2, 2, PixelFormat::Mono8, PixelFormat::YUV422_8),
+            Err(ConvertError::UnsupportedConversion { .. })
+        ));
+    }
+}
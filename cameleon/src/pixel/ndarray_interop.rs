@@ -0,0 +1,168 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use ndarray::{Array2, ArrayView2, ArrayView3, Axis, ShapeBuilder};
+
+use super::{le_u16s, padded_stride, strip_padding, unpack, ConvertError, ConvertResult, Layout};
+use crate::payload::{ImageInfo, Payload};
+
+impl Payload {
+    /// Returns a view of the monochrome image in the payload with shape `(height, width)`.
+    ///
+    /// Supported formats are `Mono8` and 8 bits bayer formats. The view borrows the payload
+    /// without copying, and padding at the end of each line is skipped by the stride of the view.
+    /// Use [`Self::to_array2_u16`] for monochrome formats of higher bit depth.
+    ///
+    /// This method is available only when `ndarray` feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use cameleon::u3v;
+    ///
+    /// let mut camera = u3v::enumerate_cameras().unwrap().pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// let payload_rx = camera.start_streaming(3).unwrap();
+    /// let payload = async_std::task::block_on(payload_rx.recv()).unwrap();
+    /// let array = payload.as_array2().unwrap();
+    /// let mean = array.iter().map(|v| f64::from(*v)).sum::<f64>() / array.len() as f64;
+    /// println!("mean: {}", mean);
+    /// payload_rx.send_back(payload);
+    /// ```
+    pub fn as_array2(&self) -> ConvertResult<ArrayView2<'_, u8>> {
+        let (info, data) = self.image_and_info()?;
+        match Layout::of(info.pixel_format) {
+            Some(Layout::Mono8 | Layout::Bayer { bits: 8, .. }) => {
+                Ok(view(info, data, 1)?.index_axis_move(Axis(2), 0))
+            }
+            _ => Err(ConvertError::UnsupportedFormat(info.pixel_format)),
+        }
+    }
+
+    /// Returns a view of the color image in the payload with shape `(height, width, channel)`.
+    ///
+    /// Supported formats are `RGB8`, `BGR8`, `RGBa8` and `BGRa8`, channels are in the order of
+    /// the format. The view borrows the payload without copying, and padding at the end of each
+    /// line is skipped by the stride of the view. Use [`Self::convert_image`] to convert other
+    /// color formats first.
+    ///
+    /// This method is available only when `ndarray` feature is enabled.
+    pub fn as_array3(&self) -> ConvertResult<ArrayView3<'_, u8>> {
+        let (info, data) = self.image_and_info()?;
+        match Layout::of(info.pixel_format) {
+            Some(Layout::Rgb { alpha, .. }) => view(info, data, if alpha { 4 } else { 3 }),
+            _ => Err(ConvertError::UnsupportedFormat(info.pixel_format)),
+        }
+    }
+
+    /// Returns the monochrome image in the payload with shape `(height, width)`.
+    ///
+    /// Supported formats are `Mono8`, `Mono10`, `Mono12`, `Mono14`, `Mono16` and packed variants
+    /// of them. Unlike [`Self::convert_image`], pixels keep their values, e.g. pixels of `Mono12`
+    /// are in `0..4096`.
+    ///
+    /// This method is available only when `ndarray` feature is enabled.
+    pub fn to_array2_u16(&self) -> ConvertResult<Array2<u16>> {
+        let (info, data) = self.image_and_info()?;
+        let data = strip_padding(data, info)?;
+        let num_pixels = info.width * info.height;
+
+        let mut pixels: Vec<u16> = match Layout::of(info.pixel_format) {
+            Some(Layout::Mono8) => data.iter().map(|v| u16::from(*v)).collect(),
+            Some(Layout::Mono16 { .. }) => le_u16s(&data).collect(),
+            Some(Layout::MonoPacked { .. }) => {
+                let mut pixels = vec![0; num_pixels];
+                unpack(&data, info.pixel_format, &mut pixels)?;
+                pixels
+            }
+            _ => return Err(ConvertError::UnsupportedFormat(info.pixel_format)),
+        };
+        if pixels.len() < num_pixels {
+            return Err(ConvertError::too_short(num_pixels, pixels.len()));
+        }
+        pixels.truncate(num_pixels);
+
+        Ok(Array2::from_shape_vec((info.height, info.width), pixels).unwrap())
+    }
+
+    fn image_and_info(&self) -> ConvertResult<(&ImageInfo, &[u8])> {
+        Ok((
+            self.image_info().ok_or(ConvertError::NoImage)?,
+            self.image().ok_or(ConvertError::NoImage)?,
+        ))
+    }
+}
+
+/// Returns a view of 8 bits pixels with shape `(height, width, channels)`.
+fn view<'a>(
+    info: &ImageInfo,
+    data: &'a [u8],
+    channels: usize,
+) -> ConvertResult<ArrayView3<'a, u8>> {
+    let layout = Layout::of(info.pixel_format).unwrap();
+    let stride = padded_stride(layout, info, data.len()).unwrap_or(info.width * channels);
+    let shape = (info.height, info.width, channels).strides((stride, channels, 1));
+    ArrayView3::from_shape(shape, data).map_err(|e| ConvertError::InvalidSize(e.to_string().into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::payload::{PayloadType, PixelFormat};
+
+    fn payload(pixel_format: PixelFormat, width: usize, height: usize, data: Vec<u8>) -> Payload {
+        Payload {
+            id: 0,
+            payload_type: PayloadType::Image,
+            image_info: Some(ImageInfo {
+                width,
+                height,
+                x_offset: 0,
+                y_offset: 0,
+                pixel_format,
+                image_size: data.len(),
+            }),
+            valid_payload_size: data.len(),
+            payload: data,
+            timestamp: Duration::default(),
+            host_timestamp: Instant::now(),
+            chunks: None,
+        }
+    }
+
+    #[test]
+    fn test_as_array() {
+        // Each line is padded to 4 bytes.
+        let payload2 = payload(PixelFormat::Mono8, 3, 2, vec![1, 2, 3, 0, 4, 5, 6, 0]);
+        let array = payload2.as_array2().unwrap();
+        assert_eq!(array, ndarray::arr2(&[[1, 2, 3], [4, 5, 6]]));
+        assert_eq!(array.as_ptr(), payload2.payload().as_ptr());
+
+        let payload3 = payload(PixelFormat::BGRa8, 2, 1, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        let array = payload3.as_array3().unwrap();
+        assert_eq!(array, ndarray::arr3(&[[[1, 2, 3, 4], [5, 6, 7, 8]]]));
+
+        assert!(matches!(
+            payload3.as_array2(),
+            Err(ConvertError::UnsupportedFormat(PixelFormat::BGRa8))
+        ));
+    }
+
+    #[test]
+    fn test_to_array2_u16() {
+        let array = payload(PixelFormat::Mono12p, 2, 1, vec![0xBC, 0x3A, 0x12])
+            .to_array2_u16()
+            .unwrap();
+        assert_eq!(array, ndarray::arr2(&[[0xABC, 0x123]]));
+
+        let array = payload(PixelFormat::Mono10, 1, 2, vec![0xFF, 0x03, 0x01, 0x00])
+            .to_array2_u16()
+            .unwrap();
+        assert_eq!(array, ndarray::arr2(&[[0x3FF], [0x001]]));
+    }
+}
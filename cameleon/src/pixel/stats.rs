@@ -0,0 +1,263 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use super::{convert, le_u16s, unpack, ConvertError, ConvertResult, Layout, Payload, PixelFormat};
+
+/// Statistics of pixel values of an image computed by [`image_stats`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageStats {
+    /// Minimum pixel value.
+    pub min: u16,
+    /// Maximum pixel value.
+    pub max: u16,
+    /// Mean of pixel values.
+    pub mean: f64,
+    /// Standard deviation of pixel values.
+    pub stddev: f64,
+    /// Percentage of saturated pixels, i.e. pixels of the maximum value of the bit depth.
+    pub saturation: f64,
+    /// Histogram of pixel values.
+    pub histogram: Histogram,
+}
+
+impl ImageStats {
+    fn from_histogram(histogram: Histogram) -> Self {
+        let num_pixels = histogram.num_pixels();
+        if num_pixels == 0 {
+            return Self {
+                min: 0,
+                max: 0,
+                mean: 0.0,
+                stddev: 0.0,
+                saturation: 0.0,
+                histogram,
+            };
+        }
+
+        let bins = histogram.counts();
+        let min = bins.iter().position(|count| *count > 0).unwrap_or(0) as u16;
+        let max = bins.iter().rposition(|count| *count > 0).unwrap_or(0) as u16;
+
+        let num_pixels = num_pixels as f64;
+        let sum: f64 = bins
+            .iter()
+            .enumerate()
+            .map(|(value, count)| value as f64 * *count as f64)
+            .sum();
+        let mean = sum / num_pixels;
+        let variance: f64 = bins
+            .iter()
+            .enumerate()
+            .map(|(value, count)| (value as f64 - mean).powi(2) * *count as f64)
+            .sum::<f64>()
+            / num_pixels;
+        let saturation = *bins.last().unwrap() as f64 / num_pixels * 100.0;
+
+        Self {
+            min,
+            max,
+            mean,
+            stddev: variance.sqrt(),
+            saturation,
+            histogram,
+        }
+    }
+}
+
+/// Histogram of pixel values with a bin for each value of the bit depth.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Histogram {
+    bins: Vec<u64>,
+    bits: u32,
+}
+
+impl Histogram {
+    fn new(bits: u32) -> Self {
+        Self {
+            bins: vec![0; 1 << bits],
+            bits,
+        }
+    }
+
+    /// Returns the bit depth of pixel values.
+    #[must_use]
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    /// Returns the number of pixels of each value, i.e. `counts()[v]` is the number of pixels of
+    /// value `v`.
+    #[must_use]
+    pub fn counts(&self) -> &[u64] {
+        &self.bins
+    }
+
+    /// Returns the number of pixels in the histogram.
+    #[must_use]
+    pub fn num_pixels(&self) -> u64 {
+        self.bins.iter().sum()
+    }
+
+    /// Returns the smallest value which is larger than or equal to `percent` % of pixels, or
+    /// `None` if the histogram is empty.
+    ///
+    /// e.g. `percentile(50.0)` is the median, and `percentile(99.0)` is a robust alternative to
+    /// the maximum for auto exposure.
+    #[must_use]
+    pub fn percentile(&self, percent: f64) -> Option<u16> {
+        let num_pixels = self.num_pixels();
+        if num_pixels == 0 {
+            return None;
+        }
+
+        let threshold = ((percent.clamp(0.0, 100.0) / 100.0) * num_pixels as f64).ceil() as u64;
+        let mut cumulative = 0;
+        for (value, count) in self.bins.iter().enumerate() {
+            cumulative += count;
+            if cumulative >= threshold.max(1) {
+                return Some(value as u16);
+            }
+        }
+        None
+    }
+
+    fn add_u8(&mut self, values: &[u8]) {
+        for value in values {
+            self.bins[usize::from(*value)] += 1;
+        }
+    }
+
+    fn add_u16(&mut self, values: impl Iterator<Item = u16>) {
+        let max = self.bins.len() - 1;
+        for value in values {
+            // Values beyond the bit depth are counted as saturated.
+            self.bins[usize::from(value).min(max)] += 1;
+        }
+    }
+}
+
+/// Computes statistics of pixel values of `src`, an image of `width` x `height` pixels in
+/// `format`.
+///
+/// Values are taken without conversion for monochrome and bayer formats, so the statistics keep
+/// the bit depth of the format, e.g. values of `Mono12` are in `0..4096`. Bayer patterns are not
+/// demosaiced, i.e. all color channels are mixed. For color formats, the statistics of `BT.601`
+/// luma in 8 bits are computed.
+///
+/// Lines of `src` must not be padded. Bytes after the image are ignored.
+///
+/// # Examples
+///
+/// ```rust
+/// use cameleon::{payload::PixelFormat, pixel};
+///
+/// let src = [0x00, 0x00, 0xFF, 0x0F];
+/// let stats = pixel::image_stats(&src, 2, 1, PixelFormat::Mono12).unwrap();
+/// assert_eq!((stats.min, stats.max), (0, 0xFFF));
+/// assert_eq!(stats.saturation, 50.0);
+/// ```
+pub fn image_stats(
+    src: &[u8],
+    width: usize,
+    height: usize,
+    format: PixelFormat,
+) -> ConvertResult<ImageStats> {
+    let layout = Layout::of(format).ok_or(ConvertError::UnsupportedFormat(format))?;
+    let num_pixels = width * height;
+    let expected = layout.image_size(num_pixels);
+    if src.len() < expected {
+        return Err(ConvertError::too_short(expected, src.len()));
+    }
+    let src = &src[..expected];
+
+    let histogram = match layout {
+        Layout::Mono8 | Layout::Bayer { bits: 8, .. } => {
+            let mut histogram = Histogram::new(8);
+            histogram.add_u8(src);
+            histogram
+        }
+        Layout::Mono16 { bits } | Layout::Bayer { bits, .. } => {
+            let mut histogram = Histogram::new(bits);
+            histogram.add_u16(le_u16s(src));
+            histogram
+        }
+        Layout::MonoPacked { bits, .. } => {
+            let mut unpacked = vec![0; num_pixels];
+            unpack(src, format, &mut unpacked)?;
+            let mut histogram = Histogram::new(bits);
+            histogram.add_u16(unpacked.into_iter());
+            histogram
+        }
+        Layout::Yuv422(..) | Layout::Rgb { .. } | Layout::Rgb16 => {
+            let luma = convert(src, width, height, format, PixelFormat::Mono8)?;
+            let mut histogram = Histogram::new(8);
+            histogram.add_u8(&luma.data);
+            histogram
+        }
+    };
+
+    Ok(ImageStats::from_histogram(histogram))
+}
+
+impl Payload {
+    /// Computes statistics of pixel values of the image in the payload.
+    ///
+    /// See [`image_stats`] for details.
+    pub fn image_stats(&self) -> ConvertResult<ImageStats> {
+        let info = self.image_info().ok_or(ConvertError::NoImage)?;
+        let image = self.image().ok_or(ConvertError::NoImage)?;
+        image_stats(image, info.width, info.height, info.pixel_format)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mono_stats() {
+        let stats = image_stats(&[10, 20, 30, 255], 2, 2, PixelFormat::Mono8).unwrap();
+        assert_eq!((stats.min, stats.max), (10, 255));
+        assert!((stats.mean - 78.75).abs() < 1e-9);
+        assert!((stats.stddev - 102.0).abs() < 0.01);
+        assert!((stats.saturation - 25.0).abs() < 1e-9);
+        assert_eq!(stats.histogram.bits(), 8);
+        assert_eq!(stats.histogram.counts()[20], 1);
+        assert_eq!(stats.histogram.num_pixels(), 4);
+
+        // 0xABC and 0xFFF in `Mono12p`.
+        let stats = image_stats(&[0xBC, 0xFA, 0xFF], 2, 1, PixelFormat::Mono12p).unwrap();
+        assert_eq!(stats.histogram.bits(), 12);
+        assert_eq!((stats.min, stats.max), (0xABC, 0xFFF));
+        assert!((stats.saturation - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_color_stats() {
+        let stats = image_stats(&[255, 255, 255, 0, 0, 0], 2, 1, PixelFormat::BGR8).unwrap();
+        assert_eq!((stats.min, stats.max), (0, 255));
+        assert!((stats.mean - 127.5).abs() < 1e-9);
+
+        assert!(matches!(
+            image_stats(&[0; 5], 2, 1, PixelFormat::RGB8),
+            Err(ConvertError::InvalidSize(..))
+        ));
+    }
+
+    #[test]
+    fn test_percentile() {
+        let src: Vec<u8> = (0..100).collect();
+        let histogram = image_stats(&src, 10, 10, PixelFormat::Mono8)
+            .unwrap()
+            .histogram;
+        assert_eq!(histogram.percentile(0.0), Some(0));
+        assert_eq!(histogram.percentile(50.0), Some(49));
+        assert_eq!(histogram.percentile(99.0), Some(98));
+        assert_eq!(histogram.percentile(100.0), Some(99));
+
+        let empty = image_stats(&[], 0, 0, PixelFormat::Mono8).unwrap();
+        assert_eq!(empty.histogram.percentile(50.0), None);
+        assert_eq!(empty.mean, 0.0);
+    }
+}
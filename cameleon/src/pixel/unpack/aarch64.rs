@@ -0,0 +1,52 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! `NEON` unpacking kernels.
+//!
+//! `vld3` deinterleaves 24 bytes into the first, second and third bytes of 8 groups, and `vst2`
+//! interleaves the even and odd pixels computed from them, so each iteration unpacks 16 pixels.
+//!
+//! All kernels unpack as many leading pixels as they can without reading out of `src`, and return
+//! the number of unpacked pixels, which is always even.
+
+use std::arch::aarch64::*;
+
+/// Number of bytes unpacked by an iteration.
+const CHUNK_BYTES: usize = 24;
+
+/// Number of pixels unpacked by an iteration.
+const CHUNK_PIXELS: usize = 16;
+
+#[target_feature(enable = "neon")]
+pub(super) unsafe fn unpack_gev_neon(src: &[u8], dst: &mut [u16], low_bits: u32) -> usize {
+    let shift = vdupq_n_s16(low_bits as i16);
+    let mask = vdupq_n_u16((1 << low_bits) - 1);
+
+    let mut done = 0;
+    while done + CHUNK_PIXELS <= dst.len() && done / 2 * 3 + CHUNK_BYTES <= src.len() {
+        let bytes = vld3_u8(src.as_ptr().add(done / 2 * 3));
+        let (b0, b1, b2) = (vmovl_u8(bytes.0), vmovl_u8(bytes.1), vmovl_u8(bytes.2));
+        let even = vorrq_u16(vshlq_u16(b0, shift), vandq_u16(b1, mask));
+        let odd = vorrq_u16(vshlq_u16(b2, shift), vandq_u16(vshrq_n_u16::<4>(b1), mask));
+        vst2q_u16(dst.as_mut_ptr().add(done), uint16x8x2_t(even, odd));
+        done += CHUNK_PIXELS;
+    }
+    done
+}
+
+#[target_feature(enable = "neon")]
+pub(super) unsafe fn unpack_lsb12_neon(src: &[u8], dst: &mut [u16]) -> usize {
+    let low_nibble = vdupq_n_u16(0xf);
+
+    let mut done = 0;
+    while done + CHUNK_PIXELS <= dst.len() && done / 2 * 3 + CHUNK_BYTES <= src.len() {
+        let bytes = vld3_u8(src.as_ptr().add(done / 2 * 3));
+        let (b0, b1, b2) = (vmovl_u8(bytes.0), vmovl_u8(bytes.1), vmovl_u8(bytes.2));
+        let even = vorrq_u16(b0, vshlq_n_u16::<8>(vandq_u16(b1, low_nibble)));
+        let odd = vorrq_u16(vshrq_n_u16::<4>(b1), vshlq_n_u16::<4>(b2));
+        vst2q_u16(dst.as_mut_ptr().add(done), uint16x8x2_t(even, odd));
+        done += CHUNK_PIXELS;
+    }
+    done
+}
@@ -0,0 +1,289 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Unpacking of packed monochrome pixels.
+//!
+//! `Mono10Packed`, `Mono12Packed` and `Mono12p` are unpacked with `SSSE3` or `AVX2` on x86 and
+//! `NEON` on aarch64 if the CPU supports them, the instruction set is detected at runtime. Pixels
+//! which don't fill a whole SIMD register and other formats are unpacked by scalar code.
+
+#[cfg(target_arch = "aarch64")]
+mod aarch64;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod x86;
+
+use super::{ConvertError, ConvertResult, PixelFormat};
+
+/// Bit layout of packed monochrome pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Packing {
+    /// `GigE Vision` style packing, two pixels in three bytes. The most significant bits of each
+    /// pixel are stored in the first and third bytes, and the remaining bits are stored in the
+    /// middle byte.
+    Gev,
+
+    /// `PFNC` style packing, pixels are stored as a continuous little endian bit stream.
+    Lsb,
+}
+
+impl Packing {
+    /// Returns the packing and the bit depth of `format`, or `None` if `format` is not packed.
+    pub(super) fn of(format: PixelFormat) -> Option<(Self, u32)> {
+        match format {
+            PixelFormat::Mono10Packed => Some((Self::Gev, 10)),
+            PixelFormat::Mono12Packed => Some((Self::Gev, 12)),
+            PixelFormat::Mono10p => Some((Self::Lsb, 10)),
+            PixelFormat::Mono12p => Some((Self::Lsb, 12)),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of bytes required to store `num_pixels` pixels.
+    pub(super) fn packed_len(self, bits: u32, num_pixels: usize) -> usize {
+        match self {
+            // Two pixels always occupy three bytes regardless of the bit depth.
+            Self::Gev => (num_pixels * 3).div_ceil(2),
+            Self::Lsb => (num_pixels * bits as usize).div_ceil(8),
+        }
+    }
+}
+
+/// Unpacks packed monochrome pixels into `dst`, one pixel per element.
+///
+/// Supported formats are [`PixelFormat::Mono10Packed`], [`PixelFormat::Mono12Packed`],
+/// [`PixelFormat::Mono10p`] and [`PixelFormat::Mono12p`]. Unpacked values keep their bit depth,
+/// e.g. values unpacked from [`PixelFormat::Mono12p`] are in `0..4096`.
+///
+/// The number of unpacked pixels is `dst.len()`. `Mono10Packed`, `Mono12Packed` and `Mono12p` are
+/// unpacked with SIMD instructions if the CPU supports `SSSE3`, `AVX2` or `NEON`.
+///
+/// # Examples
+///
+/// ```rust
+/// use cameleon::{payload::PixelFormat, pixel};
+///
+/// // Two pixels, 0x123 and 0x456, packed in `Mono12p`.
+/// let src = [0x23, 0x61, 0x45];
+/// let mut dst = [0; 2];
+/// pixel::unpack(&src, PixelFormat::Mono12p, &mut dst).unwrap();
+/// assert_eq!(dst, [0x123, 0x456]);
+/// ```
+pub fn unpack(src: &[u8], format: PixelFormat, dst: &mut [u16]) -> ConvertResult<()> {
+    let (packing, bits) = Packing::of(format).ok_or(ConvertError::UnsupportedFormat(format))?;
+    let expected = packing.packed_len(bits, dst.len());
+    if src.len() < expected {
+        return Err(ConvertError::too_short(expected, src.len()));
+    }
+
+    match (packing, bits) {
+        (Packing::Gev, 10) => unpack_gev(src, dst, 2),
+        (Packing::Gev, 12) => unpack_gev(src, dst, 4),
+        (Packing::Lsb, 12) => unpack_lsb12(src, dst),
+        (Packing::Lsb, bits) => unpack_lsb(src, bits, dst),
+        _ => unreachable!(),
+    }
+    Ok(())
+}
+
+/// Unpacks `GigE Vision` style packing, `low_bits` is the number of bits of each pixel stored in
+/// the middle byte.
+fn unpack_gev(src: &[u8], dst: &mut [u16], low_bits: u32) {
+    // SIMD kernels always unpack an even number of pixels, i.e. whole groups of three bytes.
+    let done = unpack_gev_simd(src, dst, low_bits);
+    unpack_gev_scalar(&src[done / 2 * 3..], &mut dst[done..], low_bits);
+}
+
+/// Unpacks `Mono12p`, which has the fixed layout of two pixels in three bytes.
+fn unpack_lsb12(src: &[u8], dst: &mut [u16]) {
+    let done = unpack_lsb12_simd(src, dst);
+    unpack_lsb12_scalar(&src[done / 2 * 3..], &mut dst[done..]);
+}
+
+/// Unpacks leading pixels with the fastest SIMD kernel available, returns the number of unpacked
+/// pixels.
+#[cfg_attr(
+    not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")),
+    allow(unused_variables)
+)]
+fn unpack_gev_simd(src: &[u8], dst: &mut [u16], low_bits: u32) -> usize {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: The CPU supports `AVX2`.
+            return unsafe { x86::unpack_gev_avx2(src, dst, low_bits) };
+        }
+        if is_x86_feature_detected!("ssse3") {
+            // SAFETY: The CPU supports `SSSE3`.
+            return unsafe { x86::unpack_gev_ssse3(src, dst, low_bits) };
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            // SAFETY: The CPU supports `NEON`.
+            return unsafe { aarch64::unpack_gev_neon(src, dst, low_bits) };
+        }
+    }
+    0
+}
+
+/// Unpacks leading pixels with the fastest SIMD kernel available, returns the number of unpacked
+/// pixels.
+#[cfg_attr(
+    not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")),
+    allow(unused_variables)
+)]
+fn unpack_lsb12_simd(src: &[u8], dst: &mut [u16]) -> usize {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: The CPU supports `AVX2`.
+            return unsafe { x86::unpack_lsb12_avx2(src, dst) };
+        }
+        if is_x86_feature_detected!("ssse3") {
+            // SAFETY: The CPU supports `SSSE3`.
+            return unsafe { x86::unpack_lsb12_ssse3(src, dst) };
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            // SAFETY: The CPU supports `NEON`.
+            return unsafe { aarch64::unpack_lsb12_neon(src, dst) };
+        }
+    }
+    0
+}
+
+fn unpack_gev_scalar(src: &[u8], dst: &mut [u16], low_bits: u32) {
+    let mask = (1 << low_bits) - 1;
+    let len = dst.len();
+    let mut pairs = dst.chunks_exact_mut(2);
+    for (bytes, pair) in src.chunks_exact(3).zip(&mut pairs) {
+        pair[0] = u16::from(bytes[0]) << low_bits | u16::from(bytes[1]) & mask;
+        pair[1] = u16::from(bytes[2]) << low_bits | u16::from(bytes[1]) >> 4 & mask;
+    }
+    // The last odd pixel is in the first two bytes of the last group.
+    if let [.., last] = pairs.into_remainder() {
+        let bytes = &src[len / 2 * 3..];
+        *last = u16::from(bytes[0]) << low_bits | u16::from(bytes[1]) & mask;
+    }
+}
+
+fn unpack_lsb12_scalar(src: &[u8], dst: &mut [u16]) {
+    let len = dst.len();
+    let mut pairs = dst.chunks_exact_mut(2);
+    for (bytes, pair) in src.chunks_exact(3).zip(&mut pairs) {
+        pair[0] = u16::from(bytes[0]) | (u16::from(bytes[1]) & 0xf) << 8;
+        pair[1] = u16::from(bytes[1]) >> 4 | u16::from(bytes[2]) << 4;
+    }
+    if let [.., last] = pairs.into_remainder() {
+        let bytes = &src[len / 2 * 3..];
+        *last = u16::from(bytes[0]) | (u16::from(bytes[1]) & 0xf) << 8;
+    }
+}
+
+/// Unpacks an arbitrary little endian bit stream of `bits` bits pixels.
+fn unpack_lsb(src: &[u8], bits: u32, dst: &mut [u16]) {
+    let mask = (1_u32 << bits) - 1;
+    for (i, pixel) in dst.iter_mut().enumerate() {
+        let bit_offset = i * bits as usize;
+        let byte_offset = bit_offset / 8;
+        // A pixel of at most 16 bits spans at most three bytes.
+        let window = src[byte_offset..]
+            .iter()
+            .take(3)
+            .enumerate()
+            .fold(0_u32, |acc, (i, b)| acc | u32::from(*b) << (i * 8));
+        *pixel = ((window >> (bit_offset % 8)) & mask) as u16;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unpack_gev() {
+        // 0x3FF and 0x155.
+        let src = [0xFF, 0x13, 0x55];
+        let mut dst = [0; 2];
+        unpack(&src, PixelFormat::Mono10Packed, &mut dst).unwrap();
+        assert_eq!(dst, [0x3FF, 0x155]);
+
+        // 0xABC, 0x123 and 0xFED.
+        let src = [0xAB, 0x3C, 0x12, 0xFE, 0x0D];
+        let mut dst = [0; 3];
+        unpack(&src, PixelFormat::Mono12Packed, &mut dst).unwrap();
+        assert_eq!(dst, [0xABC, 0x123, 0xFED]);
+    }
+
+    #[test]
+    fn test_unpack_lsb() {
+        // 0x123, 0x3FF, 0x000, 0x2AA and 0x001.
+        let values = [0x123, 0x3FF, 0x000, 0x2AA, 0x001];
+        let mut bitstream = 0_u64;
+        for (i, v) in values.iter().enumerate() {
+            bitstream |= (*v as u64) << (i * 10);
+        }
+        let src = bitstream.to_le_bytes();
+        let mut dst = [0; 5];
+        unpack(&src[..7], PixelFormat::Mono10p, &mut dst).unwrap();
+        assert_eq!(dst, values);
+
+        // 0xABC, 0x123 and 0xFED.
+        let src = [0xBC, 0x3A, 0x12, 0xED, 0x0F];
+        let mut dst = [0; 3];
+        unpack(&src, PixelFormat::Mono12p, &mut dst).unwrap();
+        assert_eq!(dst, [0xABC, 0x123, 0xFED]);
+    }
+
+    /// Returns pseudo random bytes.
+    pub(super) fn random_bytes(len: usize) -> Vec<u8> {
+        let mut state = 0x2545_f491_u32;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_simd_matches_scalar() {
+        // Lengths around the register widths to cover the scalar tails.
+        for num_pixels in (0..40).chain(1000..1003) {
+            let src = random_bytes(num_pixels * 3 / 2 + 1);
+
+            for low_bits in &[2, 4] {
+                let mut expected = vec![0; num_pixels];
+                unpack_gev_scalar(&src, &mut expected, *low_bits);
+                let mut dst = vec![0; num_pixels];
+                unpack_gev(&src, &mut dst, *low_bits);
+                assert_eq!(dst, expected);
+            }
+
+            let mut expected = vec![0; num_pixels];
+            unpack_lsb12_scalar(&src, &mut expected);
+            let mut dst = vec![0; num_pixels];
+            unpack_lsb12(&src, &mut dst);
+            assert_eq!(dst, expected);
+        }
+    }
+
+    #[test]
+    fn test_unpack_error() {
+        let mut dst = [0; 3];
+        assert!(matches!(
+            unpack(&[0; 4], PixelFormat::Mono12p, &mut dst),
+            Err(ConvertError::InvalidSize(..))
+        ));
+        assert!(matches!(
+            unpack(&[0; 6], PixelFormat::Mono8, &mut dst),
+            Err(ConvertError::UnsupportedFormat(PixelFormat::Mono8))
+        ));
+    }
+}
@@ -0,0 +1,193 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! `SSSE3` and `AVX2` unpacking kernels.
+//!
+//! Each 128 bits lane unpacks 12 bytes into 8 pixels. Bytes of each pixel are gathered into a 16
+//! bits lane by `pshufb`, then the bits are moved into place by shifts and masks.
+//!
+//! All kernels unpack as many leading pixels as they can without reading out of `src`, and return
+//! the number of unpacked pixels, which is always even.
+
+#[cfg(target_arch = "x86")]
+use std::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+/// Shuffle which gathers the most significant byte of `GigE Vision` style packed pixels.
+/// `-1` clears the byte.
+const GEV_HIGH: [i8; 16] = [0, -1, 2, -1, 3, -1, 5, -1, 6, -1, 8, -1, 9, -1, 11, -1];
+
+/// Shuffle which gathers the byte containing the least significant bits of `GigE Vision` style
+/// packed pixels.
+const GEV_LOW: [i8; 16] = [1, -1, 1, -1, 4, -1, 4, -1, 7, -1, 7, -1, 10, -1, 10, -1];
+
+/// Shuffle which gathers the two bytes containing each `Mono12p` pixel.
+const LSB12: [i8; 16] = [0, 1, 1, 2, 3, 4, 4, 5, 6, 7, 7, 8, 9, 10, 10, 11];
+
+/// Number of bytes unpacked by a 128 bits lane.
+const LANE_BYTES: usize = 12;
+
+/// Number of pixels unpacked by a 128 bits lane.
+const LANE_PIXELS: usize = 8;
+
+#[target_feature(enable = "ssse3")]
+pub(super) unsafe fn unpack_gev_ssse3(src: &[u8], dst: &mut [u16], low_bits: u32) -> usize {
+    let high_shuffle = _mm_loadu_si128(GEV_HIGH.as_ptr().cast());
+    let low_shuffle = _mm_loadu_si128(GEV_LOW.as_ptr().cast());
+    let shift = _mm_cvtsi32_si128(low_bits as i32);
+    let mask = (1 << low_bits) - 1;
+    let even_mask = _mm_set1_epi32(mask);
+    let odd_mask = _mm_set1_epi32(mask << 16);
+
+    let mut done = 0;
+    // A load reads 16 bytes though only 12 bytes of them are unpacked.
+    while done + LANE_PIXELS <= dst.len() && done / 2 * 3 + 16 <= src.len() {
+        let v = _mm_loadu_si128(src.as_ptr().add(done / 2 * 3).cast());
+        let high = _mm_sll_epi16(_mm_shuffle_epi8(v, high_shuffle), shift);
+        let low = _mm_shuffle_epi8(v, low_shuffle);
+        let low = _mm_or_si128(
+            _mm_and_si128(low, even_mask),
+            _mm_and_si128(_mm_srli_epi16(low, 4), odd_mask),
+        );
+        _mm_storeu_si128(dst.as_mut_ptr().add(done).cast(), _mm_or_si128(high, low));
+        done += LANE_PIXELS;
+    }
+    done
+}
+
+#[target_feature(enable = "ssse3")]
+pub(super) unsafe fn unpack_lsb12_ssse3(src: &[u8], dst: &mut [u16]) -> usize {
+    let shuffle = _mm_loadu_si128(LSB12.as_ptr().cast());
+    let even_mask = _mm_set1_epi32(0x0fff);
+    let odd_mask = _mm_set1_epi32(0x0fff_0000);
+
+    let mut done = 0;
+    while done + LANE_PIXELS <= dst.len() && done / 2 * 3 + 16 <= src.len() {
+        let v = _mm_loadu_si128(src.as_ptr().add(done / 2 * 3).cast());
+        let v = _mm_shuffle_epi8(v, shuffle);
+        // Even pixels are in the lower 12 bits and odd pixels are in the upper 12 bits of their
+        // lanes.
+        let even = _mm_and_si128(v, even_mask);
+        let odd = _mm_and_si128(_mm_srli_epi16(v, 4), odd_mask);
+        _mm_storeu_si128(dst.as_mut_ptr().add(done).cast(), _mm_or_si128(even, odd));
+        done += LANE_PIXELS;
+    }
+    done
+}
+
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn unpack_gev_avx2(src: &[u8], dst: &mut [u16], low_bits: u32) -> usize {
+    let high_shuffle = _mm256_broadcastsi128_si256(_mm_loadu_si128(GEV_HIGH.as_ptr().cast()));
+    let low_shuffle = _mm256_broadcastsi128_si256(_mm_loadu_si128(GEV_LOW.as_ptr().cast()));
+    let shift = _mm_cvtsi32_si128(low_bits as i32);
+    let mask = (1 << low_bits) - 1;
+    let even_mask = _mm256_set1_epi32(mask);
+    let odd_mask = _mm256_set1_epi32(mask << 16);
+
+    let mut done = 0;
+    while done + LANE_PIXELS * 2 <= dst.len() && done / 2 * 3 + LANE_BYTES + 16 <= src.len() {
+        let v = load_lanes(src.as_ptr().add(done / 2 * 3));
+        let high = _mm256_sll_epi16(_mm256_shuffle_epi8(v, high_shuffle), shift);
+        let low = _mm256_shuffle_epi8(v, low_shuffle);
+        let low = _mm256_or_si256(
+            _mm256_and_si256(low, even_mask),
+            _mm256_and_si256(_mm256_srli_epi16(low, 4), odd_mask),
+        );
+        _mm256_storeu_si256(
+            dst.as_mut_ptr().add(done).cast(),
+            _mm256_or_si256(high, low),
+        );
+        done += LANE_PIXELS * 2;
+    }
+    done
+}
+
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn unpack_lsb12_avx2(src: &[u8], dst: &mut [u16]) -> usize {
+    let shuffle = _mm256_broadcastsi128_si256(_mm_loadu_si128(LSB12.as_ptr().cast()));
+    let even_mask = _mm256_set1_epi32(0x0fff);
+    let odd_mask = _mm256_set1_epi32(0x0fff_0000);
+
+    let mut done = 0;
+    while done + LANE_PIXELS * 2 <= dst.len() && done / 2 * 3 + LANE_BYTES + 16 <= src.len() {
+        let v = _mm256_shuffle_epi8(load_lanes(src.as_ptr().add(done / 2 * 3)), shuffle);
+        let even = _mm256_and_si256(v, even_mask);
+        let odd = _mm256_and_si256(_mm256_srli_epi16(v, 4), odd_mask);
+        _mm256_storeu_si256(
+            dst.as_mut_ptr().add(done).cast(),
+            _mm256_or_si256(even, odd),
+        );
+        done += LANE_PIXELS * 2;
+    }
+    done
+}
+
+/// Loads 12 bytes from `ptr` into the lower lane and the next 12 bytes into the upper lane, since
+/// `vpshufb` can't move bytes across lanes. Reads 28 bytes from `ptr`.
+#[target_feature(enable = "avx2")]
+unsafe fn load_lanes(ptr: *const u8) -> __m256i {
+    let low = _mm_loadu_si128(ptr.cast());
+    let high = _mm_loadu_si128(ptr.add(LANE_BYTES).cast());
+    _mm256_inserti128_si256(_mm256_castsi128_si256(low), high, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{tests::random_bytes, unpack_gev_scalar, unpack_lsb12_scalar};
+    use super::*;
+
+    fn check(kernel: fn(&[u8], &mut [u16]) -> usize, scalar: fn(&[u8], &mut [u16])) {
+        for num_pixels in 0..100 {
+            let src = random_bytes(num_pixels * 3 / 2 + 4);
+            let mut expected = vec![0; num_pixels];
+            scalar(&src, &mut expected);
+
+            let mut dst = vec![0; num_pixels];
+            let done = kernel(&src, &mut dst);
+            assert_eq!(done % 2, 0);
+            // The tail left to the scalar code must be shorter than two loads.
+            assert!(num_pixels - done < LANE_PIXELS * 4);
+            assert_eq!(dst[..done], expected[..done]);
+        }
+    }
+
+    #[test]
+    fn test_ssse3() {
+        if !is_x86_feature_detected!("ssse3") {
+            return;
+        }
+        check(
+            |src, dst| unsafe { unpack_gev_ssse3(src, dst, 2) },
+            |src, dst| unpack_gev_scalar(src, dst, 2),
+        );
+        check(
+            |src, dst| unsafe { unpack_gev_ssse3(src, dst, 4) },
+            |src, dst| unpack_gev_scalar(src, dst, 4),
+        );
+        check(
+            |src, dst| unsafe { unpack_lsb12_ssse3(src, dst) },
+            unpack_lsb12_scalar,
+        );
+    }
+
+    #[test]
+    fn test_avx2() {
+        if !is_x86_feature_detected!("avx2") {
+            return;
+        }
+        check(
+            |src, dst| unsafe { unpack_gev_avx2(src, dst, 2) },
+            |src, dst| unpack_gev_scalar(src, dst, 2),
+        );
+        check(
+            |src, dst| unsafe { unpack_gev_avx2(src, dst, 4) },
+            |src, dst| unpack_gev_scalar(src, dst, 4),
+        );
+        check(
+            |src, dst| unsafe { unpack_lsb12_avx2(src, dst) },
+            unpack_lsb12_scalar,
+        );
+    }
+}
@@ -0,0 +1,92 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use super::PixelFormat;
+
+/// Component order of `4:2:2` subsampled pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum YuvOrder {
+    /// `Y0 U Y1 V`.
+    Yuyv,
+    /// `U Y0 V Y1`.
+    Uyvy,
+}
+
+impl YuvOrder {
+    /// Returns the component order of `format`, or `None` if `format` is not a supported `4:2:2`
+    /// format.
+    pub(super) fn of(format: PixelFormat) -> Option<Self> {
+        match format {
+            PixelFormat::YUV422_8 | PixelFormat::YCbCr422_8 => Some(Self::Yuyv),
+            PixelFormat::YCbCr422_8_CbYCrY => Some(Self::Uyvy),
+            _ => None,
+        }
+    }
+
+    /// Returns `(y0, u, y1, v)` of a pair of pixels.
+    fn split(self, pair: &[u8]) -> (u8, u8, u8, u8) {
+        match self {
+            Self::Yuyv => (pair[0], pair[1], pair[2], pair[3]),
+            Self::Uyvy => (pair[1], pair[0], pair[3], pair[2]),
+        }
+    }
+}
+
+/// Converts `4:2:2` subsampled pixels in `src` into `RGB8` pixels in `dst`.
+///
+/// Full range `BT.601` coefficients are used, as specified by `PFNC` for these formats.
+pub(super) fn to_rgb(src: &[u8], order: YuvOrder, dst: &mut [u8]) {
+    for (pair, rgb) in src.chunks_exact(4).zip(dst.chunks_exact_mut(6)) {
+        let (y0, u, y1, v) = order.split(pair);
+        rgb[..3].copy_from_slice(&ycbcr_to_rgb(y0, u, v));
+        rgb[3..].copy_from_slice(&ycbcr_to_rgb(y1, u, v));
+    }
+}
+
+/// Extracts the luma of `4:2:2` subsampled pixels in `src` into `dst`.
+pub(super) fn to_luma(src: &[u8], order: YuvOrder, dst: &mut [u8]) {
+    for (pair, luma) in src.chunks_exact(4).zip(dst.chunks_exact_mut(2)) {
+        let (y0, _, y1, _) = order.split(pair);
+        luma[0] = y0;
+        luma[1] = y1;
+    }
+}
+
+fn ycbcr_to_rgb(y: u8, cb: u8, cr: u8) -> [u8; 3] {
+    // Coefficients are scaled by 2^16.
+    let y = i32::from(y) << 16;
+    let cb = i32::from(cb) - 128;
+    let cr = i32::from(cr) - 128;
+    let clamp = |v: i32| ((v + (1 << 15)) >> 16).clamp(0, 255) as u8;
+
+    [
+        clamp(y + 91_881 * cr),
+        clamp(y - 22_554 * cb - 46_802 * cr),
+        clamp(y + 116_130 * cb),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_rgb() {
+        // Gray and pure red pixels.
+        let mut dst = [0; 6];
+        to_rgb(&[128, 128, 200, 128], YuvOrder::Yuyv, &mut dst);
+        assert_eq!(dst, [128, 128, 128, 200, 200, 200]);
+
+        let mut dst = [0; 6];
+        to_rgb(&[85, 76, 255, 85], YuvOrder::Uyvy, &mut dst);
+        assert_eq!(dst[..3], [254, 0, 0]);
+    }
+
+    #[test]
+    fn test_to_luma() {
+        let mut dst = [0; 4];
+        to_luma(&[1, 2, 3, 4, 5, 6, 7, 8], YuvOrder::Uyvy, &mut dst);
+        assert_eq!(dst, [2, 4, 6, 8]);
+    }
+}
@@ -0,0 +1,205 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module provides recording of camera sessions and a virtual camera which plays them back.
+//!
+//! [`Recorder`] wraps [`DeviceControl`] and [`PayloadStream`] of a real camera and writes their
+//! control transactions and payloads to a trace file. [`load_camera`] constructs a [`Camera`]
+//! from the trace, whose [`ReplayControl`] answers reads with the recorded data and whose
+//! [`ReplayStream`] sends the recorded payloads at the recorded intervals.
+//!
+//! This is useful to reproduce issues of a specific device offline, and to write regression
+//! tests against traces of real devices.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use cameleon::replay;
+//!
+//! let mut camera = replay::load_camera("camera.trace").unwrap();
+//! camera.open().unwrap();
+//! camera.load_context().unwrap();
+//!
+//! let payload_rx = camera.start_streaming(3).unwrap();
+//! while let Ok(payload) = async_std::task::block_on(payload_rx.recv()) {
+//!     println!("{:?}", payload.image_info());
+//!     payload_rx.send_back(payload);
+//! }
+//!
+//! camera.close().unwrap();
+//! ```
+//!
+//! [`DeviceControl`]: crate::DeviceControl
+//! [`PayloadStream`]: crate::PayloadStream
+
+mod player;
+mod recorder;
+mod trace;
+
+pub use player::{ReplayControl, ReplayStream};
+pub use recorder::{Recorder, RecordingControl, RecordingStream};
+
+use std::{fs::File, io, io::BufReader, path::Path};
+
+use super::Camera;
+
+use trace::{Record, TraceReader};
+
+/// Loads the trace at `path` recorded by [`Recorder`] and constructs a camera which plays it
+/// back.
+///
+/// All records are loaded into memory.
+pub fn load_camera(path: impl AsRef<Path>) -> io::Result<Camera<ReplayControl, ReplayStream>> {
+    let (mut reader, info) = TraceReader::new(BufReader::new(File::open(path)?))?;
+
+    let mut ctrl = ReplayControl::default();
+    let mut payloads = vec![];
+    while let Some(record) = reader.next_record()? {
+        match record {
+            Record::Payload { elapsed, payload } => payloads.push((elapsed, payload)),
+            record => ctrl.push_record(record),
+        }
+    }
+
+    Ok(Camera::new(ctrl, ReplayStream::new(payloads), None, info))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::{
+        payload::{self, Payload, PayloadType},
+        CameraInfo, ControlError, DeviceControl, PayloadStream, StreamError,
+    };
+
+    fn camera_info() -> CameraInfo {
+        CameraInfo {
+            vendor_name: "Vendor".into(),
+            model_name: "Model".into(),
+            serial_number: "0123".into(),
+        }
+    }
+
+    fn payload(id: u64) -> Payload {
+        Payload {
+            id,
+            payload_type: PayloadType::Chunk,
+            image_info: None,
+            payload: vec![id as u8; 4],
+            valid_payload_size: 4,
+            timestamp: Duration::from_millis(id),
+            host_timestamp: Instant::now(),
+            chunks: None,
+        }
+    }
+
+    #[test]
+    fn test_replay_control() {
+        let mut ctrl = ReplayControl::default();
+        ctrl.push_record(Record::Read {
+            address: 0x10,
+            data: vec![1],
+        });
+        ctrl.push_record(Record::Read {
+            address: 0x10,
+            data: vec![2],
+        });
+        ctrl.push_record(Record::GenApi("<xml/>".into()));
+
+        let mut buf = [0; 1];
+        assert!(matches!(
+            ctrl.read(0x10, &mut buf),
+            Err(ControlError::NotOpened)
+        ));
+        ctrl.open().unwrap();
+        assert_eq!(ctrl.genapi().unwrap(), "<xml/>");
+
+        // Recorded data are returned in order, then the last one is repeated.
+        for expected in &[1, 2, 2] {
+            ctrl.read(0x10, &mut buf).unwrap();
+            assert_eq!(buf[0], *expected);
+        }
+
+        // Reads with a different length are not answered by recorded data.
+        assert!(ctrl.read(0x10, &mut [0; 2]).is_err());
+        ctrl.write(0x20, &[3, 4]).unwrap();
+        let mut buf = [0; 2];
+        ctrl.read(0x20, &mut buf).unwrap();
+        assert_eq!(buf, [3, 4]);
+    }
+
+    #[test]
+    fn test_replay_stream() {
+        let mut strm = ReplayStream::new(vec![
+            (Duration::from_millis(10), payload(0)),
+            (Duration::from_millis(20), payload(1)),
+        ]);
+        let (sender, receiver) = payload::channel(2, 2);
+        strm.open().unwrap();
+        strm.start_streaming_loop(sender, &mut ReplayControl::default())
+            .unwrap();
+        assert!(matches!(
+            strm.start_streaming_loop(payload::channel(1, 1).0, &mut ReplayControl::default()),
+            Err(StreamError::InStreaming)
+        ));
+
+        for id in 0..2 {
+            let payload = async_std::task::block_on(receiver.recv()).unwrap();
+            assert_eq!(payload.id(), id);
+        }
+        // The loop finishes after sending all payloads.
+        assert!(async_std::task::block_on(receiver.recv()).is_err());
+        strm.close().unwrap();
+        assert!(!strm.is_loop_running());
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let mut source = ReplayControl::default();
+        source.push_record(Record::Read {
+            address: 0x10,
+            data: vec![1, 2, 3, 4],
+        });
+        source.push_record(Record::GenApi("<xml/>".into()));
+        let source_strm = ReplayStream::new(vec![
+            (Duration::default(), payload(0)),
+            (Duration::default(), payload(1)),
+        ]);
+
+        let path = std::env::temp_dir().join(format!(
+            "cameleon-test-record-and-replay-{}.trace",
+            std::process::id()
+        ));
+        let recorder = Recorder::create(&path, &camera_info()).unwrap();
+        let mut ctrl = recorder.record_control(source);
+        let mut strm = recorder.record_stream(source_strm);
+
+        ctrl.open().unwrap();
+        ctrl.genapi().unwrap();
+        ctrl.read(0x10, &mut [0; 4]).unwrap();
+        ctrl.write(0x20, &[5]).unwrap();
+
+        let (sender, receiver) = payload::channel(2, 2);
+        strm.start_streaming_loop(sender, &mut ctrl).unwrap();
+        for id in 0..2 {
+            let payload = async_std::task::block_on(receiver.recv()).unwrap();
+            assert_eq!(payload.id(), id);
+        }
+        strm.stop_streaming_loop().unwrap();
+        recorder.flush().unwrap();
+
+        let mut camera = load_camera(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(camera.info(), &camera_info());
+        assert_eq!(camera.strm.len(), 2);
+
+        camera.ctrl.open().unwrap();
+        assert_eq!(camera.ctrl.genapi().unwrap(), "<xml/>");
+        let mut buf = [0; 4];
+        camera.ctrl.read(0x10, &mut buf).unwrap();
+        assert_eq!(buf, [1, 2, 3, 4]);
+    }
+}
@@ -0,0 +1,262 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_std::task;
+use futures::{
+    channel::oneshot,
+    future::{self, Either},
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    camera::{DeviceControl, PayloadStream},
+    payload::{Payload, PayloadSender},
+    ControlError, ControlResult, StreamError, StreamResult,
+};
+
+use super::trace::Record;
+
+/// [`DeviceControl`] which plays back control transactions of a trace.
+///
+/// A read returns the data recorded for the same address and length. When the same region was
+/// read several times, the recorded data are returned in the recorded order, then the last one is
+/// repeated. Writes are accepted without any effect except that a region which was never read in
+/// the trace returns the data written last.
+#[derive(Debug, Default)]
+pub struct ReplayControl {
+    reads: HashMap<(u64, usize), VecDeque<Vec<u8>>>,
+    writes: HashMap<u64, Vec<u8>>,
+    xml: Option<String>,
+    is_opened: bool,
+}
+
+impl ReplayControl {
+    pub(super) fn push_record(&mut self, record: Record) {
+        match record {
+            Record::Read { address, data } => self
+                .reads
+                .entry((address, data.len()))
+                .or_default()
+                .push_back(data),
+            Record::GenApi(xml) => self.xml = Some(xml),
+            // Recorded writes are not needed to answer reads.
+            Record::Write { .. } | Record::Payload { .. } => {}
+        }
+    }
+
+    fn assert_open(&self) -> ControlResult<()> {
+        if self.is_opened {
+            Ok(())
+        } else {
+            Err(ControlError::NotOpened)
+        }
+    }
+}
+
+impl DeviceControl for ReplayControl {
+    fn open(&mut self) -> ControlResult<()> {
+        self.is_opened = true;
+        Ok(())
+    }
+
+    fn close(&mut self) -> ControlResult<()> {
+        self.is_opened = false;
+        Ok(())
+    }
+
+    fn is_opened(&self) -> bool {
+        self.is_opened
+    }
+
+    fn read(&mut self, address: u64, buf: &mut [u8]) -> ControlResult<()> {
+        self.assert_open()?;
+
+        if let Some(queue) = self.reads.get_mut(&(address, buf.len())) {
+            if queue.len() > 1 {
+                buf.copy_from_slice(&queue.pop_front().unwrap());
+            } else {
+                buf.copy_from_slice(&queue[0]);
+            }
+            return Ok(());
+        }
+
+        match self.writes.get(&address) {
+            Some(data) if data.len() >= buf.len() => {
+                buf.copy_from_slice(&data[..buf.len()]);
+                Ok(())
+            }
+            _ => Err(ControlError::InvalidData(
+                format!(
+                    "no data recorded for address {:#x} with length {}",
+                    address,
+                    buf.len()
+                )
+                .into(),
+            )),
+        }
+    }
+
+    fn write(&mut self, address: u64, data: &[u8]) -> ControlResult<()> {
+        self.assert_open()?;
+
+        self.writes.insert(address, data.to_vec());
+        Ok(())
+    }
+
+    fn genapi(&mut self) -> ControlResult<String> {
+        self.assert_open()?;
+
+        self.xml
+            .clone()
+            .ok_or_else(|| ControlError::InvalidDevice("no `GenApi` xml in the trace".into()))
+    }
+
+    fn enable_streaming(&mut self) -> ControlResult<()> {
+        self.assert_open()
+    }
+
+    fn disable_streaming(&mut self) -> ControlResult<()> {
+        self.assert_open()
+    }
+}
+
+/// [`PayloadStream`] which plays back payloads of a trace.
+///
+/// Payloads are sent at the same intervals as they were recorded. The streaming loop finishes
+/// after sending the last payload.
+#[derive(Debug, Default)]
+pub struct ReplayStream {
+    payloads: Arc<Vec<(Duration, Payload)>>,
+    cancellation_tx: Option<oneshot::Sender<()>>,
+    completion_rx: Option<oneshot::Receiver<()>>,
+}
+
+impl ReplayStream {
+    pub(super) fn new(payloads: Vec<(Duration, Payload)>) -> Self {
+        Self {
+            payloads: Arc::new(payloads),
+            ..Self::default()
+        }
+    }
+
+    /// Returns the number of payloads in the trace.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.payloads.len()
+    }
+
+    /// Returns `true` if the trace contains no payloads.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.payloads.is_empty()
+    }
+}
+
+impl PayloadStream for ReplayStream {
+    fn open(&mut self) -> StreamResult<()> {
+        Ok(())
+    }
+
+    fn close(&mut self) -> StreamResult<()> {
+        if self.is_loop_running() {
+            self.stop_streaming_loop()?;
+        }
+        Ok(())
+    }
+
+    fn start_streaming_loop(
+        &mut self,
+        sender: PayloadSender,
+        _ctrl: &mut dyn DeviceControl,
+    ) -> StreamResult<()> {
+        if self.is_loop_running() {
+            return Err(StreamError::InStreaming);
+        }
+
+        let (cancellation_tx, cancellation_rx) = oneshot::channel();
+        let (completion_tx, completion_rx) = oneshot::channel();
+        self.cancellation_tx = Some(cancellation_tx);
+        self.completion_rx = Some(completion_rx);
+
+        let strm_loop = StreamingLoop {
+            payloads: self.payloads.clone(),
+            sender,
+            completion_tx,
+            cancellation_rx,
+        };
+        std::thread::spawn(|| {
+            strm_loop.run();
+        });
+
+        info!("start replay loop successfully");
+        Ok(())
+    }
+
+    fn stop_streaming_loop(&mut self) -> StreamResult<()> {
+        if self.is_loop_running() {
+            let (cancellation_tx, completion_rx) = (
+                self.cancellation_tx.take().unwrap(),
+                self.completion_rx.take().unwrap(),
+            );
+            // The loop may have already finished after sending all payloads.
+            cancellation_tx.send(()).ok();
+            task::block_on(completion_rx)
+                .map_err(|e| StreamError::Poisoned(e.to_string().into()))?;
+        }
+
+        info!("stop replay loop successfully");
+        Ok(())
+    }
+
+    fn is_loop_running(&self) -> bool {
+        debug_assert_eq!(self.completion_rx.is_some(), self.cancellation_tx.is_some());
+        self.completion_rx.is_some()
+    }
+}
+
+struct StreamingLoop {
+    payloads: Arc<Vec<(Duration, Payload)>>,
+    sender: PayloadSender,
+    completion_tx: oneshot::Sender<()>,
+    cancellation_rx: oneshot::Receiver<()>,
+}
+
+impl StreamingLoop {
+    fn run(mut self) {
+        let start = Instant::now();
+        let first_elapsed = self
+            .payloads
+            .first()
+            .map_or_else(Duration::default, |(elapsed, _)| *elapsed);
+
+        for (elapsed, payload) in self.payloads.iter() {
+            let wait = elapsed
+                .saturating_sub(first_elapsed)
+                .saturating_sub(start.elapsed());
+            let sleep = Box::pin(task::sleep(wait));
+            // Stop the loop when `cancellation_tx` sends signal or is dropped.
+            if let Either::Right(_) =
+                task::block_on(future::select(sleep, &mut self.cancellation_rx))
+            {
+                break;
+            }
+
+            let mut payload = payload.clone();
+            payload.host_timestamp = Instant::now();
+            if let Err(err) = self.sender.try_send(Ok(payload)) {
+                warn!(?err);
+            }
+        }
+
+        if let Err(e) = self.completion_tx.send(()) {
+            error!(?e);
+        }
+    }
+}
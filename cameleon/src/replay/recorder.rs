@@ -0,0 +1,292 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{
+    fs::File,
+    io::{self, BufWriter},
+    path::Path,
+    sync::{Arc, Mutex},
+    thread::JoinHandle,
+};
+
+use async_std::task;
+use tracing::{error, info, warn};
+
+use crate::{
+    camera::{CameraInfo, DeviceControl, PayloadStream},
+    cancel::CancellationToken,
+    payload::{self, PayloadReceiver, PayloadSender},
+    ControlError, ControlResult, StreamError, StreamResult,
+};
+
+use super::trace::TraceWriter;
+
+/// Capacity of the channel between the wrapped streaming loop and the recorder.
+const RECORDING_CHANNEL_CAP: usize = 5;
+
+type SharedWriter = Arc<Mutex<TraceWriter<BufWriter<File>>>>;
+
+/// Records control transactions and payloads of a camera to a trace file.
+///
+/// The recorder wraps [`DeviceControl`] and [`PayloadStream`] of a camera. Recorded traces are
+/// played back by [`super::load_camera`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use cameleon::{replay::Recorder, u3v, Camera};
+///
+/// let camera = u3v::enumerate_cameras().unwrap().pop().unwrap();
+/// let recorder = Recorder::create("camera.trace", camera.info()).unwrap();
+///
+/// // Wrap the camera before loading the context so that the `GenApi` xml is recorded.
+/// let info = camera.info().clone();
+/// let mut camera: Camera<_, _> = Camera::new(
+///     recorder.record_control(camera.ctrl),
+///     recorder.record_stream(camera.strm),
+///     None,
+///     info,
+/// );
+/// camera.open().unwrap();
+/// camera.load_context().unwrap();
+/// let payload_rx = camera.start_streaming(3).unwrap();
+/// // ...
+/// camera.close().unwrap();
+/// recorder.flush().unwrap();
+/// ```
+#[derive(Clone)]
+pub struct Recorder {
+    writer: SharedWriter,
+}
+
+impl Recorder {
+    /// Creates a trace file at `path` for the camera described by `info`.
+    pub fn create(path: impl AsRef<Path>, info: &CameraInfo) -> io::Result<Self> {
+        let file = BufWriter::new(File::create(path)?);
+        Ok(Self {
+            writer: Arc::new(Mutex::new(TraceWriter::new(file, info)?)),
+        })
+    }
+
+    /// Wraps `ctrl` so that its reads, writes and `GenApi` xml are recorded.
+    pub fn record_control<Ctrl>(&self, ctrl: Ctrl) -> RecordingControl<Ctrl> {
+        RecordingControl {
+            inner: ctrl,
+            writer: self.writer.clone(),
+        }
+    }
+
+    /// Wraps `strm` so that its payloads are recorded.
+    pub fn record_stream<Strm>(&self, strm: Strm) -> RecordingStream<Strm> {
+        RecordingStream {
+            inner: strm,
+            writer: self.writer.clone(),
+            recording_loop: None,
+        }
+    }
+
+    /// Flushes buffered records to the file.
+    pub fn flush(&self) -> io::Result<()> {
+        self.writer.lock().unwrap().flush()
+    }
+}
+
+/// [`DeviceControl`] which records transactions of the inner control handle.
+///
+/// Only successful transactions are recorded.
+pub struct RecordingControl<Ctrl> {
+    inner: Ctrl,
+    writer: SharedWriter,
+}
+
+impl<Ctrl> RecordingControl<Ctrl> {
+    /// Returns the inner control handle.
+    pub fn into_inner(self) -> Ctrl {
+        self.inner
+    }
+
+    fn record(
+        &self,
+        f: impl FnOnce(&mut TraceWriter<BufWriter<File>>) -> io::Result<()>,
+    ) -> ControlResult<()> {
+        f(&mut self.writer.lock().unwrap()).map_err(|e| ControlError::Io(e.into()))
+    }
+}
+
+impl<Ctrl: DeviceControl> DeviceControl for RecordingControl<Ctrl> {
+    fn open(&mut self) -> ControlResult<()> {
+        self.inner.open()
+    }
+
+    fn close(&mut self) -> ControlResult<()> {
+        self.inner.close()
+    }
+
+    fn is_opened(&self) -> bool {
+        self.inner.is_opened()
+    }
+
+    fn read(&mut self, address: u64, buf: &mut [u8]) -> ControlResult<()> {
+        self.inner.read(address, buf)?;
+        self.record(|w| w.write_read(address, buf))
+    }
+
+    fn write(&mut self, address: u64, data: &[u8]) -> ControlResult<()> {
+        self.inner.write(address, data)?;
+        self.record(|w| w.write_write(address, data))
+    }
+
+    fn read_stacked(&mut self, entries: &mut [(u64, &mut [u8])]) -> ControlResult<()> {
+        self.inner.read_stacked(entries)?;
+        self.record(|w| {
+            entries
+                .iter()
+                .try_for_each(|(address, buf)| w.write_read(*address, buf))
+        })
+    }
+
+    fn write_stacked(&mut self, entries: &[(u64, &[u8])]) -> ControlResult<()> {
+        self.inner.write_stacked(entries)?;
+        self.record(|w| {
+            entries
+                .iter()
+                .try_for_each(|(address, data)| w.write_write(*address, data))
+        })
+    }
+
+    fn genapi(&mut self) -> ControlResult<String> {
+        let xml = self.inner.genapi()?;
+        self.record(|w| w.write_genapi(&xml))?;
+        Ok(xml)
+    }
+
+    fn genapi_with_cancel(&mut self, token: &CancellationToken) -> ControlResult<String> {
+        let xml = self.inner.genapi_with_cancel(token)?;
+        self.record(|w| w.write_genapi(&xml))?;
+        Ok(xml)
+    }
+
+    fn enable_streaming(&mut self) -> ControlResult<()> {
+        self.inner.enable_streaming()
+    }
+
+    fn disable_streaming(&mut self) -> ControlResult<()> {
+        self.inner.disable_streaming()
+    }
+
+    fn enable_streaming_channel(&mut self, index: usize) -> ControlResult<()> {
+        self.inner.enable_streaming_channel(index)
+    }
+
+    fn disable_streaming_channel(&mut self, index: usize) -> ControlResult<()> {
+        self.inner.disable_streaming_channel(index)
+    }
+}
+
+/// [`PayloadStream`] which records payloads sent from the inner stream handle.
+///
+/// Payloads pass through a recording loop between the streaming loop of the inner handle and the
+/// host. Errors sent from the streaming loop are passed to the host but not recorded.
+pub struct RecordingStream<Strm> {
+    inner: Strm,
+    writer: SharedWriter,
+    recording_loop: Option<JoinHandle<()>>,
+}
+
+impl<Strm> RecordingStream<Strm> {
+    /// Returns the inner stream handle.
+    ///
+    /// The recording loop is detached if it's running.
+    pub fn into_inner(self) -> Strm {
+        self.inner
+    }
+}
+
+impl<Strm: PayloadStream> PayloadStream for RecordingStream<Strm> {
+    fn open(&mut self) -> StreamResult<()> {
+        self.inner.open()
+    }
+
+    fn close(&mut self) -> StreamResult<()> {
+        if self.is_loop_running() {
+            self.stop_streaming_loop()?;
+        }
+        self.inner.close()
+    }
+
+    fn start_streaming_loop(
+        &mut self,
+        sender: PayloadSender,
+        ctrl: &mut dyn DeviceControl,
+    ) -> StreamResult<()> {
+        if self.recording_loop.is_some() {
+            return Err(StreamError::InStreaming);
+        }
+
+        let (inner_sender, receiver) =
+            payload::channel(RECORDING_CHANNEL_CAP, RECORDING_CHANNEL_CAP);
+        self.inner.start_streaming_loop(inner_sender, ctrl)?;
+
+        let recording_loop = RecordingLoop {
+            receiver,
+            sender,
+            writer: self.writer.clone(),
+        };
+        self.recording_loop = Some(std::thread::spawn(|| recording_loop.run()));
+
+        info!("start recording loop successfully");
+        Ok(())
+    }
+
+    fn stop_streaming_loop(&mut self) -> StreamResult<()> {
+        self.inner.stop_streaming_loop()?;
+        // The recording loop finishes once the inner streaming loop drops its sender.
+        if let Some(recording_loop) = self.recording_loop.take() {
+            recording_loop
+                .join()
+                .map_err(|_| StreamError::Poisoned("recording loop panicked".into()))?;
+        }
+
+        info!("stop recording loop successfully");
+        Ok(())
+    }
+
+    fn is_loop_running(&self) -> bool {
+        self.recording_loop.is_some()
+    }
+}
+
+struct RecordingLoop {
+    receiver: PayloadReceiver,
+    sender: PayloadSender,
+    writer: SharedWriter,
+}
+
+impl RecordingLoop {
+    fn run(self) {
+        loop {
+            let payload = match task::block_on(self.receiver.recv()) {
+                Ok(payload) => payload,
+                // The inner streaming loop is finished.
+                Err(StreamError::ReceiveError(..)) => break,
+                Err(err) => {
+                    self.sender.try_send(Err(err)).ok();
+                    continue;
+                }
+            };
+
+            if let Err(e) = self.writer.lock().unwrap().write_payload(&payload) {
+                error!(?e, "failed to record payload");
+            }
+            if let Err(err) = self.sender.try_send(Ok(payload)) {
+                warn!(?err);
+            }
+
+            // Pass buffers sent back from the host to the inner streaming loop.
+            while let Ok(payload) = self.sender.try_recv() {
+                self.receiver.send_back(payload);
+            }
+        }
+    }
+}
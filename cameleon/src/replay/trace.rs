@@ -0,0 +1,274 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Binary format of trace files.
+//!
+//! A trace file starts with a header, followed by records until the end of the file. All integers
+//! are little endian, and byte sequences and strings are prefixed with their length as `u32`.
+//!
+//! | Field                  | Type    |
+//! |------------------------|---------|
+//! | Magic `b"CMLTRACE"`    | 8 bytes |
+//! | Version                | `u32`   |
+//! | Vendor name            | string  |
+//! | Model name             | string  |
+//! | Serial number          | string  |
+//!
+//! Each record starts with a `u8` tag, see [`Tag`].
+
+use std::{
+    convert::TryFrom,
+    io::{self, Read, Write},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    binary::{
+        self, invalid_data, read_bytes, read_string, read_u32, read_u64, write_bytes, write_u32,
+        write_u64,
+    },
+    payload::Payload,
+    CameraInfo,
+};
+
+const MAGIC: &[u8; 8] = b"CMLTRACE";
+const VERSION: u32 = 1;
+
+/// A record read from a trace file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Record {
+    /// `data` was read from `address`.
+    Read { address: u64, data: Vec<u8> },
+
+    /// `data` was written to `address`.
+    Write { address: u64, data: Vec<u8> },
+
+    /// `GenApi` xml retrieved from the device.
+    GenApi(String),
+
+    /// A payload received `elapsed` after the recording started.
+    Payload { elapsed: Duration, payload: Payload },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum Tag {
+    Read = 0,
+    Write = 1,
+    GenApi = 2,
+    Payload = 3,
+}
+
+impl TryFrom<u8> for Tag {
+    type Error = io::Error;
+
+    fn try_from(value: u8) -> io::Result<Self> {
+        match value {
+            0 => Ok(Self::Read),
+            1 => Ok(Self::Write),
+            2 => Ok(Self::GenApi),
+            3 => Ok(Self::Payload),
+            _ => Err(invalid_data(format!("unknown record tag: {}", value))),
+        }
+    }
+}
+
+pub(super) struct TraceWriter<W> {
+    writer: W,
+    start: Instant,
+}
+
+impl<W: Write> TraceWriter<W> {
+    pub(super) fn new(mut writer: W, info: &CameraInfo) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        write_u32(&mut writer, VERSION)?;
+        write_bytes(&mut writer, info.vendor_name.as_bytes())?;
+        write_bytes(&mut writer, info.model_name.as_bytes())?;
+        write_bytes(&mut writer, info.serial_number.as_bytes())?;
+
+        Ok(Self {
+            writer,
+            start: Instant::now(),
+        })
+    }
+
+    pub(super) fn write_read(&mut self, address: u64, data: &[u8]) -> io::Result<()> {
+        self.writer.write_all(&[Tag::Read as u8])?;
+        write_u64(&mut self.writer, address)?;
+        write_bytes(&mut self.writer, data)
+    }
+
+    pub(super) fn write_write(&mut self, address: u64, data: &[u8]) -> io::Result<()> {
+        self.writer.write_all(&[Tag::Write as u8])?;
+        write_u64(&mut self.writer, address)?;
+        write_bytes(&mut self.writer, data)
+    }
+
+    pub(super) fn write_genapi(&mut self, xml: &str) -> io::Result<()> {
+        self.writer.write_all(&[Tag::GenApi as u8])?;
+        write_bytes(&mut self.writer, xml.as_bytes())
+    }
+
+    pub(super) fn write_payload(&mut self, payload: &Payload) -> io::Result<()> {
+        let w = &mut self.writer;
+        w.write_all(&[Tag::Payload as u8])?;
+        write_u64(w, self.start.elapsed().as_nanos() as u64)?;
+        binary::write_payload(w, payload)
+    }
+
+    pub(super) fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+pub(super) struct TraceReader<R> {
+    reader: R,
+}
+
+impl<R: Read> TraceReader<R> {
+    /// Reads the header of the trace, then returns the reader and the information of the
+    /// recorded camera.
+    pub(super) fn new(mut reader: R) -> io::Result<(Self, CameraInfo)> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a trace file".into()));
+        }
+        let version = read_u32(&mut reader)?;
+        if version != VERSION {
+            return Err(invalid_data(format!(
+                "unsupported trace version: {}",
+                version
+            )));
+        }
+
+        let info = CameraInfo {
+            vendor_name: read_string(&mut reader)?,
+            model_name: read_string(&mut reader)?,
+            serial_number: read_string(&mut reader)?,
+        };
+        Ok((Self { reader }, info))
+    }
+
+    /// Reads the next record, returns `None` at the end of the trace.
+    pub(super) fn next_record(&mut self) -> io::Result<Option<Record>> {
+        let mut tag = [0];
+        if self.reader.read(&mut tag)? == 0 {
+            return Ok(None);
+        }
+
+        let r = &mut self.reader;
+        let record = match Tag::try_from(tag[0])? {
+            Tag::Read => Record::Read {
+                address: read_u64(r)?,
+                data: read_bytes(r)?,
+            },
+            Tag::Write => Record::Write {
+                address: read_u64(r)?,
+                data: read_bytes(r)?,
+            },
+            Tag::GenApi => Record::GenApi(read_string(r)?),
+            Tag::Payload => Record::Payload {
+                elapsed: Duration::from_nanos(read_u64(r)?),
+                payload: binary::read_payload(r)?,
+            },
+        };
+
+        Ok(Some(record))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload::{ImageInfo, PayloadType, PixelFormat};
+
+    fn camera_info() -> CameraInfo {
+        CameraInfo {
+            vendor_name: "Vendor".into(),
+            model_name: "Model".into(),
+            serial_number: "0123".into(),
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let payload = Payload {
+            id: 7,
+            payload_type: PayloadType::Image,
+            image_info: Some(ImageInfo {
+                width: 2,
+                height: 2,
+                x_offset: 0,
+                y_offset: 1,
+                pixel_format: PixelFormat::Mono8,
+                image_size: 4,
+            }),
+            // Bytes after `valid_payload_size` are not recorded.
+            payload: vec![1, 2, 3, 4, 0, 0],
+            valid_payload_size: 4,
+            timestamp: Duration::from_micros(100),
+            host_timestamp: Instant::now(),
+            chunks: None,
+        };
+
+        let mut buf = vec![];
+        let mut writer = TraceWriter::new(&mut buf, &camera_info()).unwrap();
+        writer.write_genapi("<xml/>").unwrap();
+        writer.write_read(0x10, &[1, 2]).unwrap();
+        writer.write_write(0x20, &[3]).unwrap();
+        writer.write_payload(&payload).unwrap();
+
+        let (mut reader, info) = TraceReader::new(buf.as_slice()).unwrap();
+        assert_eq!(info.serial_number, "0123");
+        assert_eq!(
+            reader.next_record().unwrap(),
+            Some(Record::GenApi("<xml/>".into()))
+        );
+        assert_eq!(
+            reader.next_record().unwrap(),
+            Some(Record::Read {
+                address: 0x10,
+                data: vec![1, 2]
+            })
+        );
+        assert_eq!(
+            reader.next_record().unwrap(),
+            Some(Record::Write {
+                address: 0x20,
+                data: vec![3]
+            })
+        );
+        match reader.next_record().unwrap() {
+            Some(Record::Payload { payload: read, .. }) => {
+                assert_eq!(read.image_info, payload.image_info);
+                assert_eq!(read.payload(), payload.payload());
+                assert_eq!(read.id(), 7);
+                assert_eq!(read.timestamp(), payload.timestamp());
+            }
+            record => panic!("unexpected record: {:?}", record),
+        }
+        assert_eq!(reader.next_record().unwrap(), None);
+    }
+
+    #[test]
+    fn test_truncated_trace() {
+        let mut buf = vec![];
+        let mut writer = TraceWriter::new(&mut buf, &camera_info()).unwrap();
+        writer.write_read(0x10, &[1, 2]).unwrap();
+        buf.pop();
+
+        let (mut reader, _) = TraceReader::new(buf.as_slice()).unwrap();
+        let err = reader.next_record().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_invalid_magic() {
+        let err = TraceReader::new(&b"NOTTRACE\x01\x00\x00\x00"[..])
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
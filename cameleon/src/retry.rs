@@ -0,0 +1,171 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains [`RetryPolicy`] which governs resending of control commands.
+//!
+//! A control handle resends a command when the transaction times out or the device reports that
+//! it's busy. The policy can be configured per handle, and overridden for a specific operation.
+//!
+//! # Examples
+//! ```rust
+//! # use cameleon::u3v;
+//! use std::time::Duration;
+//!
+//! use cameleon::{retry::RetryPolicy, DeviceControl};
+//!
+//! # let mut cameras = u3v::enumerate_cameras().unwrap();
+//! # if cameras.is_empty() {
+//! #     return;
+//! # }
+//! # let mut camera = cameras.pop().unwrap();
+//! camera.open().unwrap();
+//!
+//! // Make the handle robust against a flaky USB hub.
+//! let policy = RetryPolicy::new(5, Duration::from_millis(50), Duration::from_millis(20));
+//! camera.ctrl.set_retry_policy(policy);
+//!
+//! // Fail fast only for this read.
+//! let mut buf = [0; 4];
+//! camera
+//!     .ctrl
+//!     .with_retry_policy(RetryPolicy::no_retry(), |ctrl| ctrl.read(0x0000, &mut buf))
+//!     .unwrap();
+//!
+//! camera.close().unwrap();
+//! ```
+
+use std::{
+    collections::hash_map::RandomState,
+    convert::TryFrom,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use super::ControlError;
+
+/// Default upper bound of the wait before a resend.
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Policy of resending a command when a control transaction times out or the device is busy.
+///
+/// The wait before the `n`th resend is `backoff * 2^(n - 1)` capped at `max_backoff`, plus a
+/// random duration up to `jitter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts including the first one. `0` is treated as `1`.
+    pub attempts: u16,
+    /// Wait before the first resend, doubled on each subsequent resend.
+    pub backoff: Duration,
+    /// Upper bound of the doubled `backoff`.
+    pub max_backoff: Duration,
+    /// Upper bound of a random duration added to each wait, so that hosts sharing a link don't
+    /// resend in lockstep.
+    pub jitter: Duration,
+}
+
+impl RetryPolicy {
+    /// Constructs `RetryPolicy`, `max_backoff` is set to [`DEFAULT_MAX_BACKOFF`].
+    #[must_use]
+    pub const fn new(attempts: u16, backoff: Duration, jitter: Duration) -> Self {
+        Self {
+            attempts,
+            backoff,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            jitter,
+        }
+    }
+
+    /// Returns the policy that never resends a command.
+    #[must_use]
+    pub const fn no_retry() -> Self {
+        Self::new(1, Duration::from_secs(0), Duration::from_secs(0))
+    }
+
+    /// Returns the wait before the `retry`th resend, `retry` starts from 1.
+    #[must_use]
+    pub fn delay(&self, retry: u16) -> Duration {
+        let exp = u32::from(retry.saturating_sub(1));
+        let backoff = 2_u32
+            .checked_pow(exp)
+            .and_then(|factor| self.backoff.checked_mul(factor))
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff));
+
+        let jitter_max = u64::try_from(self.jitter.as_nanos()).unwrap_or(u64::MAX);
+        if jitter_max == 0 {
+            backoff
+        } else {
+            let jitter = random_u64() % jitter_max.saturating_add(1);
+            backoff.saturating_add(Duration::from_nanos(jitter))
+        }
+    }
+
+    /// Returns `true` if the command should be resent after `attempt`th attempt failed with
+    /// `err`.
+    pub(crate) fn should_retry(&self, attempt: u16, err: &ControlError) -> bool {
+        attempt < self.attempts && matches!(err, ControlError::Timeout | ControlError::Busy)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(3, Duration::from_millis(20), Duration::from_millis(10))
+    }
+}
+
+/// Returns a random number without depending on a random number generator crate.
+///
+/// Each `RandomState` is seeded with different keys, which is enough for jitter.
+fn random_u64() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay() {
+        let policy = RetryPolicy::new(4, Duration::from_millis(10), Duration::from_secs(0));
+        assert_eq!(policy.delay(1), Duration::from_millis(10));
+        assert_eq!(policy.delay(2), Duration::from_millis(20));
+        assert_eq!(policy.delay(3), Duration::from_millis(40));
+        assert_eq!(policy.delay(u16::MAX), DEFAULT_MAX_BACKOFF);
+
+        let policy = RetryPolicy {
+            max_backoff: Duration::from_millis(30),
+            ..policy
+        };
+        assert_eq!(policy.delay(2), Duration::from_millis(20));
+        assert_eq!(policy.delay(3), Duration::from_millis(30));
+        assert_eq!(policy.delay(u16::MAX), Duration::from_millis(30));
+    }
+
+    #[test]
+    fn test_delay_with_jitter() {
+        let policy = RetryPolicy::new(4, Duration::from_millis(10), Duration::from_millis(5));
+        for retry in 1..4 {
+            let delay = policy.delay(retry);
+            let backoff = Duration::from_millis(10) * 2_u32.pow(u32::from(retry - 1));
+            assert!(delay >= backoff);
+            assert!(delay <= backoff + Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_should_retry() {
+        let policy = RetryPolicy::new(2, Duration::from_millis(10), Duration::from_secs(0));
+        assert!(policy.should_retry(1, &ControlError::Timeout));
+        assert!(policy.should_retry(1, &ControlError::Busy));
+        assert!(!policy.should_retry(2, &ControlError::Timeout));
+        assert!(!policy.should_retry(1, &ControlError::NotOpened));
+
+        let policy = RetryPolicy::no_retry();
+        assert!(!policy.should_retry(1, &ControlError::Timeout));
+    }
+}
@@ -0,0 +1,104 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module provides recording of raw payloads to sequence files and reading them back.
+//!
+//! [`SequenceRecorder`] writes payloads to a file on a background thread through a large buffer,
+//! so that the streaming loop is never blocked by the disk for long captures. [`SequenceReader`]
+//! reads the recorded frames in any order, and [`SequenceStream`] plays them back as a
+//! [`PayloadStream`] so that code written against cameras runs against recorded data as is.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use cameleon::{sequence::SequenceRecorder, u3v};
+//!
+//! let mut camera = u3v::enumerate_cameras().unwrap().pop().unwrap();
+//! camera.open().unwrap();
+//! camera.load_context().unwrap();
+//!
+//! let recorder = SequenceRecorder::create("capture.seq").unwrap();
+//! let payload_rx = camera.start_streaming(10).unwrap();
+//! async_std::task::block_on(async {
+//!     for _ in 0..1000 {
+//!         let payload = payload_rx.recv().await.unwrap();
+//!         recorder.record(&payload).await.unwrap();
+//!         payload_rx.send_back(payload);
+//!     }
+//! });
+//!
+//! camera.stop_streaming().unwrap();
+//! recorder.finish().unwrap();
+//! ```
+//!
+//! # File format
+//!
+//! All integers are little endian. A sequence file starts with a header.
+//!
+//! | Field                  | Type                                        |
+//! |------------------------|---------------------------------------------|
+//! | Magic `b"CMLSEQNC"`    | 8 bytes                                     |
+//! | Version                | `u32`                                       |
+//! | Image info flag        | `u8`, `1` if the fields below are valid     |
+//! | Pixel format           | `u32`                                       |
+//! | Width                  | `u64`                                       |
+//! | Height                 | `u64`                                       |
+//!
+//! The image info in the header is taken from the first recorded frame. The header is followed by
+//! frame records, each of which is a `u8` tag `0` and the payload.
+//!
+//! When the recording is finished, an index is appended. It's a `u8` tag `1`, the number of
+//! frames as `u64`, and the file offset, the block ID and the timestamp in nanoseconds of each
+//! frame as `u64`. The file ends with the offset of the index as `u64` and `b"CMLSEQIX"`.
+//!
+//! If the recording was not finished, e.g. the process was killed, [`SequenceReader`] rebuilds the
+//! index by scanning frame records, and ignores the truncated record at the end.
+//!
+//! [`PayloadStream`]: crate::PayloadStream
+
+mod player;
+mod reader;
+mod recorder;
+
+pub use player::SequenceStream;
+pub use reader::SequenceReader;
+pub use recorder::SequenceRecorder;
+
+use std::time::Duration;
+
+use crate::payload::PixelFormat;
+
+const MAGIC: &[u8; 8] = b"CMLSEQNC";
+const INDEX_MAGIC: &[u8; 8] = b"CMLSEQIX";
+const VERSION: u32 = 1;
+
+/// Size of the header in bytes.
+const HEADER_SIZE: u64 = 8 + 4 + 1 + 4 + 8 + 8;
+
+/// Size of the trailer in bytes.
+const TRAILER_SIZE: u64 = 8 + 8;
+
+const FRAME_TAG: u8 = 0;
+const INDEX_TAG: u8 = 1;
+
+/// Image information of a sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequenceInfo {
+    /// [`PixelFormat`] of the first frame.
+    pub pixel_format: PixelFormat,
+    /// Width of the first frame.
+    pub width: usize,
+    /// Height of the first frame.
+    pub height: usize,
+}
+
+/// An entry of the frame index of a sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameEntry {
+    /// Block ID of the frame, i.e. [`crate::payload::Payload::id`].
+    pub block_id: u64,
+    /// Timestamp of the frame, i.e. [`crate::payload::Payload::timestamp`].
+    pub timestamp: Duration,
+    offset: u64,
+}
@@ -0,0 +1,361 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{
+    fs::File,
+    io::{self, BufReader, Read, Seek},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_std::task;
+use futures::{
+    channel::oneshot,
+    future::{self, Either},
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    camera::{DeviceControl, PayloadStream},
+    payload::PayloadSender,
+    StreamError, StreamResult,
+};
+
+use super::SequenceReader;
+
+/// [`PayloadStream`] which plays back frames of a sequence file.
+///
+/// By default, frames are sent at the intervals of their timestamps, i.e. at the frame rate of the
+/// recording. [`Self::set_frame_rate`] overrides it with a fixed frame rate. The streaming loop
+/// finishes after sending the last frame unless looping is enabled by [`Self::set_looping`].
+///
+/// The stream doesn't touch the device control handle, so it can be combined with any
+/// [`DeviceControl`], e.g. [`crate::replay::ReplayControl`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use cameleon::{
+///     payload, replay::ReplayControl, sequence::SequenceStream, PayloadStream,
+/// };
+///
+/// let mut strm = SequenceStream::from_file("capture.seq").unwrap();
+/// strm.set_frame_rate(Some(30.0));
+///
+/// let (sender, receiver) = payload::channel(10, 10);
+/// strm.open().unwrap();
+/// strm.start_streaming_loop(sender, &mut ReplayControl::default())
+///     .unwrap();
+/// while let Ok(payload) = async_std::task::block_on(receiver.recv()) {
+///     println!("{:?}", payload.image_info());
+///     receiver.send_back(payload);
+/// }
+/// strm.close().unwrap();
+/// ```
+pub struct SequenceStream<R = BufReader<File>> {
+    reader: Arc<Mutex<SequenceReader<R>>>,
+    frame_rate: Option<f64>,
+    looping: bool,
+    cancellation_tx: Option<oneshot::Sender<()>>,
+    completion_rx: Option<oneshot::Receiver<()>>,
+}
+
+impl SequenceStream {
+    /// Opens the sequence file at `path` for playback.
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(SequenceReader::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> SequenceStream<R> {
+    /// Constructs a stream which plays back frames read by `reader`.
+    #[must_use]
+    pub fn new(reader: SequenceReader<R>) -> Self {
+        Self {
+            reader: Arc::new(Mutex::new(reader)),
+            frame_rate: None,
+            looping: false,
+            cancellation_tx: None,
+            completion_rx: None,
+        }
+    }
+
+    /// Sets the frame rate of playback in frames per second. `None` plays back frames at the
+    /// intervals of their timestamps.
+    ///
+    /// The change takes effect from the next call of [`PayloadStream::start_streaming_loop`].
+    ///
+    /// # Panics
+    /// Panics if `frame_rate` is not positive.
+    pub fn set_frame_rate(&mut self, frame_rate: Option<f64>) {
+        if let Some(frame_rate) = frame_rate {
+            assert!(frame_rate > 0.0, "frame rate must be positive");
+        }
+        self.frame_rate = frame_rate;
+    }
+
+    /// Returns the frame rate set by [`Self::set_frame_rate`].
+    #[must_use]
+    pub fn frame_rate(&self) -> Option<f64> {
+        self.frame_rate
+    }
+
+    /// Sets whether playback restarts from the first frame after the last frame.
+    ///
+    /// The change takes effect from the next call of [`PayloadStream::start_streaming_loop`].
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    /// Returns the number of frames in the sequence.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.reader.lock().unwrap().len()
+    }
+
+    /// Returns `true` if the sequence contains no frames.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the times to send each frame relative to the start of playback.
+    fn schedule(&self) -> Vec<Duration> {
+        let reader = self.reader.lock().unwrap();
+        let entries = reader.entries();
+        match self.frame_rate {
+            Some(frame_rate) => (0..entries.len())
+                .map(|i| Duration::from_secs_f64(i as f64 / frame_rate))
+                .collect(),
+            None => {
+                let first = entries
+                    .first()
+                    .map_or_else(Duration::default, |e| e.timestamp);
+                // Timestamps going backward, e.g. by a reset of the device clock, are clamped so
+                // that frames are sent immediately.
+                let mut last = Duration::default();
+                entries
+                    .iter()
+                    .map(|e| {
+                        last = last.max(e.timestamp.saturating_sub(first));
+                        last
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+impl<R: Read + Seek + Send + 'static> PayloadStream for SequenceStream<R> {
+    fn open(&mut self) -> StreamResult<()> {
+        Ok(())
+    }
+
+    fn close(&mut self) -> StreamResult<()> {
+        if self.is_loop_running() {
+            self.stop_streaming_loop()?;
+        }
+        Ok(())
+    }
+
+    fn start_streaming_loop(
+        &mut self,
+        sender: PayloadSender,
+        _ctrl: &mut dyn DeviceControl,
+    ) -> StreamResult<()> {
+        if self.is_loop_running() {
+            return Err(StreamError::InStreaming);
+        }
+
+        let (cancellation_tx, cancellation_rx) = oneshot::channel();
+        let (completion_tx, completion_rx) = oneshot::channel();
+        self.cancellation_tx = Some(cancellation_tx);
+        self.completion_rx = Some(completion_rx);
+
+        let strm_loop = StreamingLoop {
+            reader: self.reader.clone(),
+            schedule: self.schedule(),
+            period: self
+                .frame_rate
+                .map(|rate| Duration::from_secs_f64(1.0 / rate)),
+            looping: self.looping,
+            sender,
+            completion_tx,
+            cancellation_rx,
+        };
+        std::thread::spawn(|| {
+            strm_loop.run();
+        });
+
+        info!("start sequence playback loop successfully");
+        Ok(())
+    }
+
+    fn stop_streaming_loop(&mut self) -> StreamResult<()> {
+        if self.is_loop_running() {
+            let (cancellation_tx, completion_rx) = (
+                self.cancellation_tx.take().unwrap(),
+                self.completion_rx.take().unwrap(),
+            );
+            // The loop may have already finished after sending all frames.
+            cancellation_tx.send(()).ok();
+            task::block_on(completion_rx)
+                .map_err(|e| StreamError::Poisoned(e.to_string().into()))?;
+        }
+
+        info!("stop sequence playback loop successfully");
+        Ok(())
+    }
+
+    fn is_loop_running(&self) -> bool {
+        debug_assert_eq!(self.completion_rx.is_some(), self.cancellation_tx.is_some());
+        self.completion_rx.is_some()
+    }
+}
+
+struct StreamingLoop<R> {
+    reader: Arc<Mutex<SequenceReader<R>>>,
+    schedule: Vec<Duration>,
+    /// Interval between the last frame and the first frame of the next round when looping. `None`
+    /// means the original frame rate.
+    period: Option<Duration>,
+    looping: bool,
+    sender: PayloadSender,
+    completion_tx: oneshot::Sender<()>,
+    cancellation_rx: oneshot::Receiver<()>,
+}
+
+impl<R: Read + Seek> StreamingLoop<R> {
+    fn run(mut self) {
+        if !self.schedule.is_empty() {
+            self.play();
+        }
+
+        if let Err(e) = self.completion_tx.send(()) {
+            error!(?e);
+        }
+    }
+
+    fn play(&mut self) {
+        let round_length = self.schedule.last().copied().unwrap_or_default()
+            + self.period.unwrap_or_else(|| self.average_interval());
+        let mut round_start = Instant::now();
+
+        loop {
+            for (i, at) in self.schedule.iter().enumerate() {
+                let wait = at.saturating_sub(round_start.elapsed());
+                let sleep = Box::pin(task::sleep(wait));
+                // Stop the loop when `cancellation_tx` sends signal or is dropped.
+                if let Either::Right(_) =
+                    task::block_on(future::select(sleep, &mut self.cancellation_rx))
+                {
+                    return;
+                }
+
+                let payload = self.reader.lock().unwrap().read_frame(i);
+                let payload = payload.map_err(|e| StreamError::Io(e.into()));
+                if let Err(err) = self.sender.try_send(payload) {
+                    warn!(?err);
+                }
+                // Buffers sent back from the host are not reused.
+                while self.sender.try_recv().is_ok() {}
+            }
+
+            if !self.looping {
+                return;
+            }
+            round_start += round_length;
+        }
+    }
+
+    /// Returns the average interval of frames, which is used as the interval between rounds at
+    /// the original frame rate.
+    fn average_interval(&self) -> Duration {
+        match self.schedule.len() {
+            0 | 1 => Duration::default(),
+            len => *self.schedule.last().unwrap() / (len - 1) as u32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        payload::{self, Payload, PayloadType},
+        replay::ReplayControl,
+        sequence::recorder::SequenceWriter,
+    };
+
+    fn stream(timestamps_ms: &[u64]) -> SequenceStream<Cursor<Vec<u8>>> {
+        let mut buf = vec![];
+        let mut writer = SequenceWriter::new(&mut buf);
+        for (id, timestamp) in timestamps_ms.iter().enumerate() {
+            let payload = Payload {
+                id: id as u64,
+                payload_type: PayloadType::Chunk,
+                image_info: None,
+                payload: vec![id as u8; 4],
+                valid_payload_size: 4,
+                timestamp: Duration::from_millis(*timestamp),
+                host_timestamp: Instant::now(),
+                chunks: None,
+            };
+            writer.write_frame(&payload).unwrap();
+        }
+        writer.finish().unwrap();
+        let reader = SequenceReader::new(Cursor::new(buf)).unwrap();
+        SequenceStream::new(reader)
+    }
+
+    #[test]
+    fn test_schedule() {
+        let mut strm = stream(&[100, 110, 105, 130]);
+        assert_eq!(strm.schedule(), [0, 10, 10, 30].map(Duration::from_millis));
+
+        strm.set_frame_rate(Some(50.0));
+        assert_eq!(strm.schedule(), [0, 20, 40, 60].map(Duration::from_millis));
+    }
+
+    #[test]
+    fn test_sequence_stream() {
+        let mut strm = stream(&[0, 1, 2]);
+        let (sender, receiver) = payload::channel(3, 3);
+        strm.open().unwrap();
+        strm.start_streaming_loop(sender, &mut ReplayControl::default())
+            .unwrap();
+        assert!(matches!(
+            strm.start_streaming_loop(payload::channel(1, 1).0, &mut ReplayControl::default()),
+            Err(StreamError::InStreaming)
+        ));
+
+        for id in 0..3 {
+            let payload = task::block_on(receiver.recv()).unwrap();
+            assert_eq!(payload.id(), id);
+            assert_eq!(payload.payload(), &[id as u8; 4]);
+        }
+        // The loop finishes after sending all frames.
+        assert!(task::block_on(receiver.recv()).is_err());
+        strm.close().unwrap();
+        assert!(!strm.is_loop_running());
+    }
+
+    #[test]
+    fn test_looping() {
+        let mut strm = stream(&[0, 1]);
+        strm.set_looping(true);
+        let (sender, receiver) = payload::channel(4, 4);
+        strm.start_streaming_loop(sender, &mut ReplayControl::default())
+            .unwrap();
+
+        let ids: Vec<_> = (0..4)
+            .map(|_| task::block_on(receiver.recv()).unwrap().id())
+            .collect();
+        assert_eq!(ids, [0, 1, 0, 1]);
+        strm.stop_streaming_loop().unwrap();
+    }
+}
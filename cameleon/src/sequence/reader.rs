@@ -0,0 +1,284 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{
+    convert::TryFrom,
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::Path,
+    time::Duration,
+};
+
+use crate::{
+    binary::{self, invalid_data, read_u32, read_u64, read_u8},
+    payload::{Payload, PixelFormat},
+};
+
+use super::{
+    FrameEntry, SequenceInfo, FRAME_TAG, HEADER_SIZE, INDEX_MAGIC, INDEX_TAG, MAGIC, TRAILER_SIZE,
+    VERSION,
+};
+
+/// Reads frames from a sequence file recorded by [`super::SequenceRecorder`].
+///
+/// Only the header and the frame index are loaded when the file is opened, frames are read on
+/// demand.
+///
+/// # Examples
+///
+/// ```no_run
+/// use cameleon::sequence::SequenceReader;
+///
+/// let mut reader = SequenceReader::open("capture.seq").unwrap();
+/// println!("{:?}", reader.info());
+/// for i in 0..reader.len() {
+///     let payload = reader.read_frame(i).unwrap();
+///     println!("{}: {:?}", payload.id(), payload.timestamp());
+/// }
+/// ```
+pub struct SequenceReader<R = BufReader<File>> {
+    reader: R,
+    info: Option<SequenceInfo>,
+    index: Vec<FrameEntry>,
+}
+
+impl SequenceReader {
+    /// Opens the sequence file at `path`.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> SequenceReader<R> {
+    /// Reads the header and the frame index of the sequence from `reader`.
+    ///
+    /// If the sequence has no index, the index is rebuilt by scanning frames.
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        reader.seek(SeekFrom::Start(0))?;
+        let info = read_header(&mut reader)?;
+        let index = match read_index(&mut reader)? {
+            Some(index) => index,
+            None => scan_frames(&mut reader)?,
+        };
+
+        Ok(Self {
+            reader,
+            info,
+            index,
+        })
+    }
+
+    /// Returns the image information of the first frame, or `None` if the first frame has no
+    /// image or the sequence is empty.
+    pub fn info(&self) -> Option<&SequenceInfo> {
+        self.info.as_ref()
+    }
+
+    /// Returns the number of frames in the sequence.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Returns `true` if the sequence has no frames.
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Returns the frame index of the sequence.
+    pub fn entries(&self) -> &[FrameEntry] {
+        &self.index
+    }
+
+    /// Reads the `i`th frame of the sequence.
+    ///
+    /// # Panics
+    /// Panics if `i` is out of range.
+    pub fn read_frame(&mut self, i: usize) -> io::Result<Payload> {
+        let entry = self.index[i];
+        self.reader.seek(SeekFrom::Start(entry.offset))?;
+        if read_u8(&mut self.reader)? != FRAME_TAG {
+            return Err(invalid_data(format!("frame {} is broken", i)));
+        }
+        binary::read_payload(&mut self.reader)
+    }
+}
+
+fn read_header(r: &mut impl Read) -> io::Result<Option<SequenceInfo>> {
+    let mut magic = [0; 8];
+    r.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("not a sequence file".into()));
+    }
+    let version = read_u32(r)?;
+    if version != VERSION {
+        return Err(invalid_data(format!(
+            "unsupported sequence version: {}",
+            version
+        )));
+    }
+
+    let has_info = read_u8(r)? != 0;
+    let pixel_format = read_u32(r)?;
+    let width = read_u64(r)? as usize;
+    let height = read_u64(r)? as usize;
+    if !has_info {
+        return Ok(None);
+    }
+    Ok(Some(SequenceInfo {
+        pixel_format: PixelFormat::try_from(pixel_format).map_err(invalid_data)?,
+        width,
+        height,
+    }))
+}
+
+/// Reads the index at the end of the sequence, returns `None` if the sequence has no index.
+fn read_index(r: &mut (impl Read + Seek)) -> io::Result<Option<Vec<FrameEntry>>> {
+    let len = r.seek(SeekFrom::End(0))?;
+    if len < HEADER_SIZE + TRAILER_SIZE {
+        return Ok(None);
+    }
+
+    r.seek(SeekFrom::End(-(TRAILER_SIZE as i64)))?;
+    let index_offset = read_u64(r)?;
+    let mut magic = [0; 8];
+    r.read_exact(&mut magic)?;
+    if &magic != INDEX_MAGIC || index_offset < HEADER_SIZE || index_offset >= len {
+        return Ok(None);
+    }
+
+    r.seek(SeekFrom::Start(index_offset))?;
+    if read_u8(r)? != INDEX_TAG {
+        return Err(invalid_data("index of the sequence is broken".into()));
+    }
+    let num_frames = read_u64(r)?;
+    let mut index = Vec::with_capacity(num_frames.min(len / 24) as usize);
+    for _ in 0..num_frames {
+        index.push(FrameEntry {
+            offset: read_u64(r)?,
+            block_id: read_u64(r)?,
+            timestamp: Duration::from_nanos(read_u64(r)?),
+        });
+    }
+    Ok(Some(index))
+}
+
+/// Rebuilds the index by reading all frames, a truncated frame at the end is ignored.
+fn scan_frames(r: &mut (impl Read + Seek)) -> io::Result<Vec<FrameEntry>> {
+    let mut index = vec![];
+    let mut offset = r.seek(SeekFrom::Start(HEADER_SIZE))?;
+    loop {
+        let payload = match read_u8(r) {
+            Ok(FRAME_TAG) => binary::read_payload(r),
+            Ok(_) => break,
+            Err(e) => Err(e),
+        };
+        match payload {
+            Ok(payload) => index.push(FrameEntry {
+                block_id: payload.id,
+                timestamp: payload.timestamp,
+                offset,
+            }),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+        offset = r.stream_position()?;
+    }
+    Ok(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use async_std::task;
+
+    use super::*;
+    use crate::{
+        payload::{ImageInfo, PayloadType},
+        sequence::{recorder::SequenceWriter, SequenceRecorder},
+    };
+
+    fn payload(id: u64) -> Payload {
+        Payload {
+            id,
+            payload_type: PayloadType::Image,
+            image_info: Some(ImageInfo {
+                width: 2,
+                height: 2,
+                x_offset: 0,
+                y_offset: 0,
+                pixel_format: PixelFormat::Mono8,
+                image_size: 4,
+            }),
+            // Bytes after `valid_payload_size` are not recorded.
+            payload: vec![id as u8; 6],
+            valid_payload_size: 4,
+            timestamp: Duration::from_millis(id * 10),
+            host_timestamp: Instant::now(),
+            chunks: None,
+        }
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("cameleon-{}-{}.seq", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let path = temp_path("roundtrip");
+        let recorder = SequenceRecorder::with_capacity(&path, 2).unwrap();
+        task::block_on(async {
+            for id in 0..5 {
+                recorder.record(&payload(id)).await.unwrap();
+            }
+        });
+        recorder.finish().unwrap();
+
+        let mut reader = SequenceReader::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            reader.info(),
+            Some(&SequenceInfo {
+                pixel_format: PixelFormat::Mono8,
+                width: 2,
+                height: 2,
+            })
+        );
+        assert_eq!(reader.len(), 5);
+        assert_eq!(reader.entries()[3].block_id, 3);
+        assert_eq!(reader.entries()[3].timestamp, Duration::from_millis(30));
+
+        let frame = reader.read_frame(4).unwrap();
+        assert_eq!(frame.id(), 4);
+        assert_eq!(frame.payload(), &[4; 4]);
+        assert_eq!(frame.image_info(), payload(4).image_info());
+    }
+
+    #[test]
+    fn test_recover_without_index() {
+        let mut buf = vec![];
+        let mut writer = SequenceWriter::new(&mut buf);
+        for id in 0..3 {
+            writer.write_frame(&payload(id)).unwrap();
+        }
+        // Simulate a recording killed while writing the last frame.
+        drop(writer);
+        buf.truncate(buf.len() - 1);
+
+        let mut reader = SequenceReader::new(io::Cursor::new(buf)).unwrap();
+        assert_eq!(reader.len(), 2);
+        assert_eq!(reader.read_frame(1).unwrap().id(), 1);
+    }
+
+    #[test]
+    fn test_empty_sequence() {
+        let path = temp_path("empty");
+        SequenceRecorder::create(&path).unwrap().finish().unwrap();
+
+        let reader = SequenceReader::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(reader.is_empty());
+        assert_eq!(reader.info(), None);
+    }
+}
@@ -0,0 +1,270 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+    thread::JoinHandle,
+};
+
+use async_std::{
+    channel::{self, Receiver, Sender, TrySendError},
+    task,
+};
+use tracing::error;
+
+use crate::{
+    binary::{self, write_u32, write_u64},
+    payload::Payload,
+};
+
+use super::{FrameEntry, FRAME_TAG, INDEX_MAGIC, INDEX_TAG, MAGIC, VERSION};
+
+/// Default number of frames queued to the writer thread.
+const DEFAULT_QUEUE_CAP: usize = 64;
+
+/// Size of the buffer of the writer thread.
+const WRITE_BUFFER_SIZE: usize = 8 * 1024 * 1024;
+
+/// Records payloads to a sequence file.
+///
+/// Payloads are copied into a queue and written by a background thread, so recording doesn't
+/// block the caller unless the queue is full. Buffers of written frames are reused for
+/// subsequent frames to avoid allocations during long captures.
+///
+/// [`Self::finish`] must be called to write the frame index, though files without the index are
+/// still readable by [`super::SequenceReader`]. Dropping the recorder finishes it and logs an error
+/// on failure.
+///
+/// See the [module level documentation](super) for an example and the file format.
+pub struct SequenceRecorder {
+    sender: Option<Sender<Payload>>,
+    recycled: Receiver<Vec<u8>>,
+    writer: Option<JoinHandle<io::Result<()>>>,
+    num_dropped: AtomicU64,
+}
+
+impl SequenceRecorder {
+    /// Creates a sequence file at `path`.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::with_capacity(path, DEFAULT_QUEUE_CAP)
+    }
+
+    /// Creates a sequence file at `path` with a queue which holds up to `cap` frames.
+    ///
+    /// A larger queue absorbs longer stalls of the disk at the cost of memory.
+    ///
+    /// # Panics
+    /// Panics if `cap` is 0.
+    pub fn with_capacity(path: impl AsRef<Path>, cap: usize) -> io::Result<Self> {
+        let file = File::create(path)?;
+        let (sender, receiver) = channel::bounded(cap);
+        let (recycle_tx, recycled) = channel::bounded(cap);
+
+        let writer = SequenceWriter::new(BufWriter::with_capacity(WRITE_BUFFER_SIZE, file));
+        let writer = std::thread::spawn(move || writer.run(receiver, recycle_tx));
+
+        Ok(Self {
+            sender: Some(sender),
+            recycled,
+            writer: Some(writer),
+            num_dropped: AtomicU64::new(0),
+        })
+    }
+
+    /// Queues `payload` to be recorded, waits while the queue is full.
+    ///
+    /// Returns an error if the writer thread stopped due to an IO error, the error itself is
+    /// returned by [`Self::finish`].
+    pub async fn record(&self, payload: &Payload) -> io::Result<()> {
+        let frame = self.copy(payload);
+        self.sender()
+            .send(frame)
+            .await
+            .map_err(|_| writer_stopped())
+    }
+
+    /// Queues `payload` to be recorded without waiting.
+    ///
+    /// If the queue is full, the payload is dropped and an error of [`io::ErrorKind::WouldBlock`]
+    /// is returned. The number of dropped payloads is returned by [`Self::num_dropped`].
+    pub fn try_record(&self, payload: &Payload) -> io::Result<()> {
+        match self.sender().try_send(self.copy(payload)) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => {
+                self.num_dropped.fetch_add(1, Ordering::Relaxed);
+                Err(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    "sequence recorder queue is full",
+                ))
+            }
+            Err(TrySendError::Closed(_)) => Err(writer_stopped()),
+        }
+    }
+
+    /// Returns the number of payloads dropped by [`Self::try_record`].
+    pub fn num_dropped(&self) -> u64 {
+        self.num_dropped.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of payloads waiting to be written.
+    pub fn queue_len(&self) -> usize {
+        self.sender().len()
+    }
+
+    /// Writes all queued payloads and the frame index, then closes the file.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.finish_impl()
+    }
+
+    fn finish_impl(&mut self) -> io::Result<()> {
+        // Closing the channel makes the writer thread write the index and exit.
+        self.sender.take();
+        match self.writer.take() {
+            Some(writer) => writer
+                .join()
+                .map_err(|_| io::Error::other("sequence writer thread panicked"))?,
+            None => Ok(()),
+        }
+    }
+
+    fn sender(&self) -> &Sender<Payload> {
+        // `sender` is taken only when the recorder is consumed.
+        self.sender.as_ref().unwrap()
+    }
+
+    /// Copies the valid bytes of `payload` into a recycled buffer if any.
+    fn copy(&self, payload: &Payload) -> Payload {
+        let mut buf = self.recycled.try_recv().unwrap_or_default();
+        buf.clear();
+        buf.extend_from_slice(payload.payload());
+        Payload {
+            id: payload.id,
+            payload_type: payload.payload_type,
+            image_info: payload.image_info.clone(),
+            valid_payload_size: buf.len(),
+            payload: buf,
+            timestamp: payload.timestamp,
+            host_timestamp: payload.host_timestamp,
+            chunks: payload.chunks.clone(),
+        }
+    }
+}
+
+impl Drop for SequenceRecorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish_impl() {
+            error!(?e, "failed to finish sequence recording");
+        }
+    }
+}
+
+fn writer_stopped() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::BrokenPipe,
+        "sequence writer thread stopped due to an error",
+    )
+}
+
+pub(super) struct SequenceWriter<W> {
+    writer: CountingWriter<W>,
+    index: Vec<FrameEntry>,
+    has_header: bool,
+}
+
+impl<W: Write> SequenceWriter<W> {
+    pub(super) fn new(writer: W) -> Self {
+        Self {
+            writer: CountingWriter {
+                inner: writer,
+                position: 0,
+            },
+            index: vec![],
+            has_header: false,
+        }
+    }
+
+    fn run(mut self, receiver: Receiver<Payload>, recycle: Sender<Vec<u8>>) -> io::Result<()> {
+        while let Ok(payload) = task::block_on(receiver.recv()) {
+            self.write_frame(&payload)?;
+            recycle.try_send(payload.payload).ok();
+        }
+        self.finish()
+    }
+
+    pub(super) fn write_frame(&mut self, payload: &Payload) -> io::Result<()> {
+        if !self.has_header {
+            self.write_header(Some(payload))?;
+        }
+
+        self.index.push(FrameEntry {
+            block_id: payload.id,
+            timestamp: payload.timestamp,
+            offset: self.writer.position,
+        });
+        self.writer.write_all(&[FRAME_TAG])?;
+        binary::write_payload(&mut self.writer, payload)
+    }
+
+    fn write_header(&mut self, first_frame: Option<&Payload>) -> io::Result<()> {
+        let w = &mut self.writer;
+        w.write_all(MAGIC)?;
+        write_u32(w, VERSION)?;
+        match first_frame.and_then(|payload| payload.image_info.as_ref()) {
+            Some(info) => {
+                w.write_all(&[1])?;
+                write_u32(w, info.pixel_format.into())?;
+                write_u64(w, info.width as u64)?;
+                write_u64(w, info.height as u64)?;
+            }
+            None => {
+                w.write_all(&[0])?;
+                write_u32(w, 0)?;
+                write_u64(w, 0)?;
+                write_u64(w, 0)?;
+            }
+        }
+        self.has_header = true;
+        Ok(())
+    }
+
+    pub(super) fn finish(mut self) -> io::Result<()> {
+        if !self.has_header {
+            self.write_header(None)?;
+        }
+
+        let index_offset = self.writer.position;
+        let w = &mut self.writer;
+        w.write_all(&[INDEX_TAG])?;
+        write_u64(w, self.index.len() as u64)?;
+        for entry in &self.index {
+            write_u64(w, entry.offset)?;
+            write_u64(w, entry.block_id)?;
+            write_u64(w, entry.timestamp.as_nanos() as u64)?;
+        }
+        write_u64(w, index_offset)?;
+        w.write_all(INDEX_MAGIC)?;
+        w.flush()
+    }
+}
+
+/// A writer which tracks the number of bytes written so far.
+struct CountingWriter<W> {
+    inner: W,
+    position: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.inner.write(buf)?;
+        self.position += len as u64;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,926 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module provides typed accessors to common features defined in `GenICam SFNC`.
+//!
+//! Each feature is a [`Feature`] constant which knows the name and the value type of the feature,
+//! so that features are accessed without looking up nodes by name and downcasting them to the
+//! interface. Enumeration features are represented by Rust enums, e.g. [`AcquisitionMode`], and
+//! `PixelFormat` is represented by [`PixelFormat`].
+//!
+//! Accessing a feature that the device doesn't implement returns
+//! [`GenApiError::InvalidNode`], use [`Feature::is_available`] to check it beforehand.
+//!
+//! # Examples
+//! ```rust
+//! # use cameleon::u3v;
+//! # let mut cameras = u3v::enumerate_cameras().unwrap();
+//! # if cameras.is_empty() {
+//! #     return;
+//! # }
+//! # let mut camera = cameras.pop().unwrap();
+//! # camera.open().unwrap();
+//! use cameleon::{
+//!     payload::PixelFormat,
+//!     sfnc::{self, AcquisitionMode, TriggerMode},
+//! };
+//!
+//! camera.load_context().unwrap();
+//! let mut params_ctxt = camera.params_ctxt().unwrap();
+//!
+//! let exposure_time = sfnc::EXPOSURE_TIME.value(&mut params_ctxt).unwrap();
+//! let max = sfnc::EXPOSURE_TIME.max(&mut params_ctxt).unwrap();
+//! sfnc::EXPOSURE_TIME
+//!     .set_value(&mut params_ctxt, (exposure_time * 2.0).min(max))
+//!     .unwrap();
+//!
+//! sfnc::PIXEL_FORMAT
+//!     .set_value(&mut params_ctxt, PixelFormat::Mono8)
+//!     .unwrap();
+//! sfnc::ACQUISITION_MODE
+//!     .set_value(&mut params_ctxt, AcquisitionMode::Continuous)
+//!     .unwrap();
+//!
+//! if sfnc::TRIGGER_MODE.is_available(&params_ctxt) {
+//!     sfnc::TRIGGER_MODE
+//!         .set_value(&mut params_ctxt, TriggerMode::Off)
+//!         .unwrap();
+//! }
+//!
+//! # camera.close().unwrap();
+//! ```
+
+use std::{convert::TryFrom, fmt, marker::PhantomData, time::Duration};
+
+use cameleon_genapi::{GenApiError, GenApiResult};
+
+use super::{
+    genapi::{GenApiCtxt, Node, ParamsCtxt},
+    payload::PixelFormat,
+    DeviceControl,
+};
+
+/// A feature whose value is represented by `T`.
+///
+/// Features defined in `SFNC` are provided as constants of this module, and [`Feature::new`]
+/// constructs a feature which is not covered by them, e.g. a vendor specific feature.
+pub struct Feature<T> {
+    name: &'static str,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<T> Feature<T> {
+    /// Constructs a feature with the name of its node.
+    #[must_use]
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            _value: PhantomData,
+        }
+    }
+
+    /// Returns the name of the feature node.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        self.name
+    }
+}
+
+impl<T: FeatureType> Feature<T> {
+    /// Returns the node of the feature, or `None` if the device doesn't implement the feature or
+    /// the node doesn't have the interface for `T`.
+    pub fn node<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> Option<Node>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        ctxt.node(self.name)
+            .filter(|node| T::is_compatible(*node, ctxt))
+    }
+
+    /// Returns `true` if the device implements the feature with the interface for `T`.
+    pub fn is_available<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> bool
+    where
+        Ctxt: GenApiCtxt,
+    {
+        self.node(ctxt).is_some()
+    }
+
+    /// Returns `true` if the feature is available and readable.
+    pub fn is_readable<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<bool>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        match self.node(ctxt) {
+            Some(node) => node.is_readable(ctxt),
+            None => Ok(false),
+        }
+    }
+
+    /// Reads the value of the feature.
+    pub fn value<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<T>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let node = self.expect_node(ctxt)?;
+        T::read(node, ctxt)
+    }
+
+    /// Writes the value to the feature.
+    pub fn set_value<Ctrl, Ctxt>(
+        self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        value: T,
+    ) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let node = self.expect_node(ctxt)?;
+        value.write(node, ctxt)
+    }
+
+    fn expect_node<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<Node>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        match ctxt.node(self.name) {
+            Some(node) if T::is_compatible(node, ctxt) => Ok(node),
+            Some(_) => Err(GenApiError::InvalidNode(
+                format!("`{}` doesn't have the expected interface", self.name).into(),
+            )),
+            None => Err(not_implemented(self.name)),
+        }
+    }
+}
+
+impl Feature<i64> {
+    /// Returns the minimum value of the feature.
+    pub fn min<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<i64>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let node = self.expect_node(ctxt)?;
+        node.as_integer(ctxt).unwrap().min(ctxt)
+    }
+
+    /// Returns the maximum value of the feature.
+    pub fn max<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<i64>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let node = self.expect_node(ctxt)?;
+        node.as_integer(ctxt).unwrap().max(ctxt)
+    }
+
+    /// Returns the increment of the feature, `None` if the feature has no fixed increment.
+    pub fn inc<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<Option<i64>>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let node = self.expect_node(ctxt)?;
+        node.as_integer(ctxt).unwrap().inc(ctxt)
+    }
+}
+
+impl Feature<f64> {
+    /// Returns the minimum value of the feature.
+    pub fn min<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<f64>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let node = self.expect_node(ctxt)?;
+        match node.as_float(ctxt) {
+            Some(node) => node.min(ctxt),
+            None => Ok(node.as_integer(ctxt).unwrap().min(ctxt)? as f64),
+        }
+    }
+
+    /// Returns the maximum value of the feature.
+    pub fn max<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<f64>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let node = self.expect_node(ctxt)?;
+        match node.as_float(ctxt) {
+            Some(node) => node.max(ctxt),
+            None => Ok(node.as_integer(ctxt).unwrap().max(ctxt)? as f64),
+        }
+    }
+}
+
+impl<T> Clone for Feature<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Feature<T> {}
+
+impl<T> fmt::Debug for Feature<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Feature").field(&self.name).finish()
+    }
+}
+
+/// A feature that has `ICommand` interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandFeature {
+    name: &'static str,
+}
+
+impl CommandFeature {
+    /// Constructs a command feature with the name of its node.
+    #[must_use]
+    pub const fn new(name: &'static str) -> Self {
+        Self { name }
+    }
+
+    /// Returns the name of the feature node.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        self.name
+    }
+
+    /// Returns `true` if the device implements the command.
+    pub fn is_available<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> bool
+    where
+        Ctxt: GenApiCtxt,
+    {
+        ctxt.node(self.name)
+            .and_then(|node| node.as_command(ctxt))
+            .is_some()
+    }
+
+    /// Executes the command.
+    pub fn execute<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        ctxt.node(self.name)
+            .and_then(|node| node.as_command(ctxt))
+            .ok_or_else(|| not_implemented(self.name))?
+            .execute(ctxt)
+    }
+
+    /// Executes the command, then waits for the device to complete it.
+    ///
+    /// See [`CommandNode::execute_and_wait`](crate::genapi::CommandNode::execute_and_wait) for details.
+    pub fn execute_and_wait<Ctrl, Ctxt>(
+        self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        timeout: Duration,
+    ) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        ctxt.node(self.name)
+            .and_then(|node| node.as_command(ctxt))
+            .ok_or_else(|| not_implemented(self.name))?
+            .execute_and_wait(ctxt, timeout)
+    }
+}
+
+/// A type which represents values of a [`Feature`].
+pub trait FeatureType: Sized {
+    /// Returns `true` if `node` has the interface to access values of the type.
+    fn is_compatible<Ctrl, Ctxt>(node: Node, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> bool
+    where
+        Ctxt: GenApiCtxt;
+
+    /// Reads the value from `node`.
+    fn read<Ctrl, Ctxt>(node: Node, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<Self>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt;
+
+    /// Writes the value to `node`.
+    fn write<Ctrl, Ctxt>(self, node: Node, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt;
+}
+
+macro_rules! impl_feature_type {
+    ($($ty:ty => $downcast:ident,)*) => {
+        $(
+            impl FeatureType for $ty {
+                fn is_compatible<Ctrl, Ctxt>(node: Node, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> bool
+                where
+                    Ctxt: GenApiCtxt,
+                {
+                    node.$downcast(ctxt).is_some()
+                }
+
+                fn read<Ctrl, Ctxt>(node: Node, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<Self>
+                where
+                    Ctrl: DeviceControl,
+                    Ctxt: GenApiCtxt,
+                {
+                    node.$downcast(ctxt).unwrap().value(ctxt)
+                }
+
+                fn write<Ctrl, Ctxt>(self, node: Node, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<()>
+                where
+                    Ctrl: DeviceControl,
+                    Ctxt: GenApiCtxt,
+                {
+                    node.$downcast(ctxt).unwrap().set_value(ctxt, self)
+                }
+            }
+        )*
+    };
+}
+
+impl_feature_type! {
+    i64 => as_integer,
+    bool => as_boolean,
+    String => as_string,
+}
+
+/// Some devices define features that are `IFloat` in `SFNC` as `IInteger`, e.g. `Gain`, so values
+/// of `IInteger` nodes are also accessed as `f64`. Written values are rounded in that case.
+impl FeatureType for f64 {
+    fn is_compatible<Ctrl, Ctxt>(node: Node, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> bool
+    where
+        Ctxt: GenApiCtxt,
+    {
+        node.as_float(ctxt).is_some() || node.as_integer(ctxt).is_some()
+    }
+
+    fn read<Ctrl, Ctxt>(node: Node, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<Self>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        match node.as_float(ctxt) {
+            Some(node) => node.value(ctxt),
+            None => Ok(node.as_integer(ctxt).unwrap().value(ctxt)? as f64),
+        }
+    }
+
+    fn write<Ctrl, Ctxt>(self, node: Node, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        match node.as_float(ctxt) {
+            Some(node) => node.set_value(ctxt, self),
+            None => node
+                .as_integer(ctxt)
+                .unwrap()
+                .set_value(ctxt, self.round() as i64),
+        }
+    }
+}
+
+/// Entries of `PixelFormat` are mapped by their values, which are `PFNC` codes as specified by
+/// `SFNC`.
+impl FeatureType for PixelFormat {
+    fn is_compatible<Ctrl, Ctxt>(node: Node, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> bool
+    where
+        Ctxt: GenApiCtxt,
+    {
+        node.as_enumeration(ctxt).is_some()
+    }
+
+    fn read<Ctrl, Ctxt>(node: Node, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<Self>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let entry = node.as_enumeration(ctxt).unwrap().current_entry(ctxt)?;
+        let value = entry.value(ctxt);
+        u32::try_from(value)
+            .ok()
+            .and_then(|value| PixelFormat::try_from(value).ok())
+            .ok_or_else(|| {
+                GenApiError::InvalidData(
+                    format!(
+                        "`{}` is not a known pixel format: {:#x}",
+                        entry.symbolic(ctxt),
+                        value
+                    )
+                    .into(),
+                )
+            })
+    }
+
+    fn write<Ctrl, Ctxt>(self, node: Node, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        node.as_enumeration(ctxt)
+            .unwrap()
+            .set_entry_by_value(ctxt, i64::from(u32::from(self)))
+    }
+}
+
+macro_rules! sfnc_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $(
+                $(#[$variant_meta:meta])*
+                $variant:ident,
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        ///
+        /// Variants are named after symbolic names of entries.
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $(
+                $(#[$variant_meta])*
+                $variant,
+            )*
+            /// An entry which is not listed above, e.g. a vendor specific entry, represented by
+            /// its symbolic name.
+            Other(String),
+        }
+
+        impl $name {
+            /// Returns the symbolic name of the entry.
+            #[must_use]
+            pub fn symbolic(&self) -> &str {
+                match self {
+                    $(Self::$variant => stringify!($variant),)*
+                    Self::Other(symbolic) => symbolic.as_str(),
+                }
+            }
+
+            /// Returns the variant corresponding to the symbolic name.
+            #[must_use]
+            pub fn from_symbolic(symbolic: &str) -> Self {
+                match symbolic {
+                    $(stringify!($variant) => Self::$variant,)*
+                    _ => Self::Other(symbolic.to_string()),
+                }
+            }
+        }
+
+        impl FeatureType for $name {
+            fn is_compatible<Ctrl, Ctxt>(node: Node, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> bool
+            where
+                Ctxt: GenApiCtxt,
+            {
+                node.as_enumeration(ctxt).is_some()
+            }
+
+            fn read<Ctrl, Ctxt>(node: Node, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<Self>
+            where
+                Ctrl: DeviceControl,
+                Ctxt: GenApiCtxt,
+            {
+                let entry = node.as_enumeration(ctxt).unwrap().current_entry(ctxt)?;
+                Ok(Self::from_symbolic(entry.symbolic(ctxt)))
+            }
+
+            fn write<Ctrl, Ctxt>(self, node: Node, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<()>
+            where
+                Ctrl: DeviceControl,
+                Ctxt: GenApiCtxt,
+            {
+                node.as_enumeration(ctxt)
+                    .unwrap()
+                    .set_entry_by_symbolic(ctxt, self.symbolic())
+            }
+        }
+    };
+}
+
+sfnc_enum! {
+    /// Entries of `AcquisitionMode`.
+    pub enum AcquisitionMode {
+        /// Acquires one frame.
+        SingleFrame,
+        /// Acquires the number of frames specified by `AcquisitionFrameCount`.
+        MultiFrame,
+        /// Acquires frames continuously until `AcquisitionStop` is executed.
+        Continuous,
+    }
+}
+
+sfnc_enum! {
+    /// Entries of `TriggerSelector`.
+    pub enum TriggerSelector {
+        /// Trigger that starts the acquisition.
+        AcquisitionStart,
+        /// Trigger that ends the acquisition.
+        AcquisitionEnd,
+        /// Trigger that controls the duration of the acquisition.
+        AcquisitionActive,
+        /// Trigger that starts the capture of a frame.
+        FrameStart,
+        /// Trigger that ends the capture of a frame.
+        FrameEnd,
+        /// Trigger that controls the duration of the capture of a frame.
+        FrameActive,
+        /// Trigger that starts the capture of a burst of frames.
+        FrameBurstStart,
+        /// Trigger that ends the capture of a burst of frames.
+        FrameBurstEnd,
+        /// Trigger that controls the duration of the capture of a burst of frames.
+        FrameBurstActive,
+        /// Trigger that starts the capture of a line.
+        LineStart,
+        /// Trigger that starts the exposure.
+        ExposureStart,
+        /// Trigger that ends the exposure.
+        ExposureEnd,
+        /// Trigger that controls the duration of the exposure.
+        ExposureActive,
+    }
+}
+
+sfnc_enum! {
+    /// Entries of `TriggerMode`.
+    pub enum TriggerMode {
+        /// The trigger selected by `TriggerSelector` is disabled.
+        Off,
+        /// The trigger selected by `TriggerSelector` is enabled.
+        On,
+    }
+}
+
+sfnc_enum! {
+    /// Entries of `TriggerSource`.
+    pub enum TriggerSource {
+        /// The trigger is generated by `TriggerSoftware`.
+        Software,
+        /// The trigger is generated by the physical line 0.
+        Line0,
+        /// The trigger is generated by the physical line 1.
+        Line1,
+        /// The trigger is generated by the physical line 2.
+        Line2,
+        /// The trigger is generated by the physical line 3.
+        Line3,
+        /// The trigger is generated by the action command 0.
+        Action0,
+    }
+}
+
+sfnc_enum! {
+    /// Entries of `TriggerActivation`.
+    pub enum TriggerActivation {
+        /// The trigger is activated on the rising edge of the source signal.
+        RisingEdge,
+        /// The trigger is activated on the falling edge of the source signal.
+        FallingEdge,
+        /// The trigger is activated on both edges of the source signal.
+        AnyEdge,
+        /// The trigger is active while the source signal is high.
+        LevelHigh,
+        /// The trigger is active while the source signal is low.
+        LevelLow,
+    }
+}
+
+sfnc_enum! {
+    /// Entries of automatic features, e.g. `ExposureAuto` and `GainAuto`.
+    pub enum AutoMode {
+        /// The feature is controlled manually.
+        Off,
+        /// The device adjusts the feature once, then returns to `Off`.
+        Once,
+        /// The device adjusts the feature continuously.
+        Continuous,
+    }
+}
+
+sfnc_enum! {
+    /// Entries of `UserSetSelector`.
+    ///
+    /// Devices may have more user sets than listed here, use [`UserSetSelector::from_index`] to
+    /// select them.
+    pub enum UserSetSelector {
+        /// The factory default settings, which can be loaded but not saved.
+        Default,
+        /// User set 0.
+        UserSet0,
+        /// User set 1.
+        UserSet1,
+        /// User set 2.
+        UserSet2,
+        /// User set 3.
+        UserSet3,
+    }
+}
+
+impl UserSetSelector {
+    /// Returns the user set named `UserSet{index}`.
+    #[must_use]
+    pub fn from_index(index: u32) -> Self {
+        Self::from_symbolic(&format!("UserSet{}", index))
+    }
+}
+
+/// `DeviceVendorName`.
+pub const DEVICE_VENDOR_NAME: Feature<String> = Feature::new("DeviceVendorName");
+/// `DeviceModelName`.
+pub const DEVICE_MODEL_NAME: Feature<String> = Feature::new("DeviceModelName");
+/// `DeviceSerialNumber`.
+pub const DEVICE_SERIAL_NUMBER: Feature<String> = Feature::new("DeviceSerialNumber");
+/// `DeviceUserID`.
+pub const DEVICE_USER_ID: Feature<String> = Feature::new("DeviceUserID");
+/// `DeviceFirmwareVersion`.
+pub const DEVICE_FIRMWARE_VERSION: Feature<String> = Feature::new("DeviceFirmwareVersion");
+
+/// `Width`, the width of the image in pixels.
+pub const WIDTH: Feature<i64> = Feature::new("Width");
+/// `Height`, the height of the image in pixels.
+pub const HEIGHT: Feature<i64> = Feature::new("Height");
+/// `OffsetX`, the horizontal offset of the region of interest in pixels.
+pub const OFFSET_X: Feature<i64> = Feature::new("OffsetX");
+/// `OffsetY`, the vertical offset of the region of interest in pixels.
+pub const OFFSET_Y: Feature<i64> = Feature::new("OffsetY");
+/// `WidthMax`, the maximum width of the image in pixels.
+pub const WIDTH_MAX: Feature<i64> = Feature::new("WidthMax");
+/// `HeightMax`, the maximum height of the image in pixels.
+pub const HEIGHT_MAX: Feature<i64> = Feature::new("HeightMax");
+/// `PixelFormat`, the format of pixels sent by the device.
+pub const PIXEL_FORMAT: Feature<PixelFormat> = Feature::new("PixelFormat");
+
+/// `AcquisitionMode`.
+pub const ACQUISITION_MODE: Feature<AcquisitionMode> = Feature::new("AcquisitionMode");
+/// `AcquisitionFrameCount`, the number of frames acquired in [`AcquisitionMode::MultiFrame`].
+pub const ACQUISITION_FRAME_COUNT: Feature<i64> = Feature::new("AcquisitionFrameCount");
+/// `AcquisitionFrameRate` in Hz.
+pub const ACQUISITION_FRAME_RATE: Feature<f64> = Feature::new("AcquisitionFrameRate");
+/// `AcquisitionFrameRateEnable`, which enables [`ACQUISITION_FRAME_RATE`].
+pub const ACQUISITION_FRAME_RATE_ENABLE: Feature<bool> = Feature::new("AcquisitionFrameRateEnable");
+/// `AcquisitionStart`.
+pub const ACQUISITION_START: CommandFeature = CommandFeature::new("AcquisitionStart");
+/// `AcquisitionStop`.
+pub const ACQUISITION_STOP: CommandFeature = CommandFeature::new("AcquisitionStop");
+
+/// `ExposureTime` in microseconds.
+pub const EXPOSURE_TIME: Feature<f64> = Feature::new("ExposureTime");
+/// `ExposureAuto`.
+pub const EXPOSURE_AUTO: Feature<AutoMode> = Feature::new("ExposureAuto");
+/// `Gain` in dB.
+pub const GAIN: Feature<f64> = Feature::new("Gain");
+/// `GainAuto`.
+pub const GAIN_AUTO: Feature<AutoMode> = Feature::new("GainAuto");
+/// `BlackLevel`.
+pub const BLACK_LEVEL: Feature<f64> = Feature::new("BlackLevel");
+/// `Gamma`.
+pub const GAMMA: Feature<f64> = Feature::new("Gamma");
+
+/// `TriggerSelector`, which selects the trigger configured by other trigger features.
+pub const TRIGGER_SELECTOR: Feature<TriggerSelector> = Feature::new("TriggerSelector");
+/// `TriggerMode`.
+pub const TRIGGER_MODE: Feature<TriggerMode> = Feature::new("TriggerMode");
+/// `TriggerSource`.
+pub const TRIGGER_SOURCE: Feature<TriggerSource> = Feature::new("TriggerSource");
+/// `TriggerActivation`.
+pub const TRIGGER_ACTIVATION: Feature<TriggerActivation> = Feature::new("TriggerActivation");
+/// `TriggerDelay` in microseconds.
+pub const TRIGGER_DELAY: Feature<f64> = Feature::new("TriggerDelay");
+/// `TriggerSoftware`, which generates a trigger when [`TRIGGER_SOURCE`] is
+/// [`TriggerSource::Software`].
+pub const TRIGGER_SOFTWARE: CommandFeature = CommandFeature::new("TriggerSoftware");
+
+/// `UserSetSelector`, which selects the user set loaded or saved by [`USER_SET_LOAD`] and
+/// [`USER_SET_SAVE`].
+pub const USER_SET_SELECTOR: Feature<UserSetSelector> = Feature::new("UserSetSelector");
+/// `UserSetLoad`, which loads the selected user set to the current settings of the device.
+pub const USER_SET_LOAD: CommandFeature = CommandFeature::new("UserSetLoad");
+/// `UserSetSave`, which saves the current settings of the device to the selected user set.
+pub const USER_SET_SAVE: CommandFeature = CommandFeature::new("UserSetSave");
+/// `UserSetDefault`, the user set loaded when the device is powered up or reset.
+pub const USER_SET_DEFAULT: Feature<UserSetSelector> = Feature::new("UserSetDefault");
+/// `UserSetDefaultSelector`, the deprecated predecessor of [`USER_SET_DEFAULT`].
+pub const USER_SET_DEFAULT_SELECTOR: Feature<UserSetSelector> =
+    Feature::new("UserSetDefaultSelector");
+
+/// `DeviceTemperature` in degrees Celsius.
+pub const DEVICE_TEMPERATURE: Feature<f64> = Feature::new("DeviceTemperature");
+/// `DeviceLinkSpeed`, the speed of the link between the device and the host in bytes per
+/// second.
+pub const DEVICE_LINK_SPEED: Feature<i64> = Feature::new("DeviceLinkSpeed");
+
+/// `TimestampLatch`, which latches the current timestamp of the device to
+/// [`TIMESTAMP_LATCH_VALUE`].
+pub const TIMESTAMP_LATCH: CommandFeature = CommandFeature::new("TimestampLatch");
+/// `TimestampLatchValue` in nanoseconds.
+pub const TIMESTAMP_LATCH_VALUE: Feature<i64> = Feature::new("TimestampLatchValue");
+
+fn not_implemented(name: &str) -> GenApiError {
+    GenApiError::InvalidNode(format!("`{}` is not implemented by the device", name).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::genapi::{tests::MemoryControl, DefaultGenApiCtxt, FromXml},
+        *,
+    };
+
+    const XML: &str = r#"
+        <RegisterDescription
+          ModelName="CameleonModel"
+          VendorName="CameleonVendor"
+          StandardNameSpace="None"
+          SchemaMajorVersion="1"
+          SchemaMinorVersion="1"
+          SchemaSubMinorVersion="0"
+          MajorVersion="1"
+          MinorVersion="0"
+          SubMinorVersion="0"
+          ProductGuid="01234567-0123-0123-0123-0123456789ab"
+          VersionGuid="76543210-3210-3210-3210-ba9876543210">
+
+            <Integer Name="Width">
+                <Value>640</Value>
+                <Min>16</Min>
+                <Max>1024</Max>
+                <Inc>16</Inc>
+            </Integer>
+
+            <Integer Name="Gain">
+                <Value>3</Value>
+                <Min>0</Min>
+                <Max>24</Max>
+            </Integer>
+
+            <Float Name="ExposureTime">
+                <Value>1000.0</Value>
+                <Min>10.0</Min>
+                <Max>100000.0</Max>
+            </Float>
+
+            <Enumeration Name="AcquisitionMode">
+                <EnumEntry Name="SingleFrame">
+                    <Value>0</Value>
+                </EnumEntry>
+                <EnumEntry Name="Continuous">
+                    <Value>2</Value>
+                </EnumEntry>
+                <EnumEntry Name="VendorMode">
+                    <Value>3</Value>
+                </EnumEntry>
+                <Value>2</Value>
+            </Enumeration>
+
+            <Enumeration Name="PixelFormat">
+                <EnumEntry Name="Mono8">
+                    <Value>0x01080001</Value>
+                </EnumEntry>
+                <EnumEntry Name="Mono16">
+                    <Value>0x01100007</Value>
+                </EnumEntry>
+                <EnumEntry Name="VendorFormat">
+                    <Value>0x80000001</Value>
+                </EnumEntry>
+                <Value>0x01080001</Value>
+            </Enumeration>
+
+            <Integer Name="TriggerMode">
+                <Value>0</Value>
+            </Integer>
+
+        </RegisterDescription>
+        "#;
+
+    fn params_ctxt() -> ParamsCtxt<MemoryControl, DefaultGenApiCtxt> {
+        ParamsCtxt {
+            ctrl: MemoryControl::default(),
+            ctxt: DefaultGenApiCtxt::from_xml(&XML).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_symbolic() {
+        assert_eq!(
+            AcquisitionMode::from_symbolic("Continuous"),
+            AcquisitionMode::Continuous
+        );
+        assert_eq!(AcquisitionMode::Continuous.symbolic(), "Continuous");
+
+        let other = AcquisitionMode::from_symbolic("VendorMode");
+        assert_eq!(other, AcquisitionMode::Other("VendorMode".into()));
+        assert_eq!(other.symbolic(), "VendorMode");
+
+        assert_eq!(UserSetSelector::from_index(1), UserSetSelector::UserSet1);
+        assert_eq!(
+            UserSetSelector::from_index(8),
+            UserSetSelector::Other("UserSet8".into())
+        );
+    }
+
+    #[test]
+    fn test_integer_feature() {
+        let mut ctxt = params_ctxt();
+        assert_eq!(WIDTH.value(&mut ctxt).unwrap(), 640);
+        assert_eq!(WIDTH.min(&mut ctxt).unwrap(), 16);
+        assert_eq!(WIDTH.max(&mut ctxt).unwrap(), 1024);
+        assert_eq!(WIDTH.inc(&mut ctxt).unwrap(), Some(16));
+
+        WIDTH.set_value(&mut ctxt, 320).unwrap();
+        assert_eq!(WIDTH.value(&mut ctxt).unwrap(), 320);
+    }
+
+    #[test]
+    fn test_float_feature() {
+        let mut ctxt = params_ctxt();
+        EXPOSURE_TIME.set_value(&mut ctxt, 2000.0).unwrap();
+        assert!((EXPOSURE_TIME.value(&mut ctxt).unwrap() - 2000.0).abs() < f64::EPSILON);
+
+        // `Gain` is defined as `IInteger`, written values are rounded.
+        assert!((GAIN.max(&mut ctxt).unwrap() - 24.0).abs() < f64::EPSILON);
+        GAIN.set_value(&mut ctxt, 5.6).unwrap();
+        assert!((GAIN.value(&mut ctxt).unwrap() - 6.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_enumeration_feature() {
+        let mut ctxt = params_ctxt();
+        assert_eq!(
+            ACQUISITION_MODE.value(&mut ctxt).unwrap(),
+            AcquisitionMode::Continuous
+        );
+
+        ACQUISITION_MODE
+            .set_value(&mut ctxt, AcquisitionMode::Other("VendorMode".into()))
+            .unwrap();
+        assert_eq!(
+            ACQUISITION_MODE.value(&mut ctxt).unwrap(),
+            AcquisitionMode::Other("VendorMode".into())
+        );
+
+        // `MultiFrame` is not an entry of the node.
+        assert!(ACQUISITION_MODE
+            .set_value(&mut ctxt, AcquisitionMode::MultiFrame)
+            .is_err());
+    }
+
+    #[test]
+    fn test_pixel_format_feature() {
+        let mut ctxt = params_ctxt();
+        assert_eq!(PIXEL_FORMAT.value(&mut ctxt).unwrap(), PixelFormat::Mono8);
+
+        PIXEL_FORMAT
+            .set_value(&mut ctxt, PixelFormat::Mono16)
+            .unwrap();
+        assert_eq!(PIXEL_FORMAT.value(&mut ctxt).unwrap(), PixelFormat::Mono16);
+
+        // The entry value is not a `PFNC` code.
+        let node = PIXEL_FORMAT.node(&ctxt).unwrap();
+        node.as_enumeration(&ctxt)
+            .unwrap()
+            .set_entry_by_symbolic(&mut ctxt, "VendorFormat")
+            .unwrap();
+        assert!(matches!(
+            PIXEL_FORMAT.value(&mut ctxt),
+            Err(GenApiError::InvalidData(..))
+        ));
+
+        // `BayerRG8` is not an entry of the node.
+        assert!(PIXEL_FORMAT
+            .set_value(&mut ctxt, PixelFormat::BayerRG8)
+            .is_err());
+    }
+
+    #[test]
+    fn test_unavailable_feature() {
+        let mut ctxt = params_ctxt();
+
+        // Not implemented.
+        assert!(!HEIGHT.is_available(&ctxt));
+        assert!(!HEIGHT.is_readable(&mut ctxt).unwrap());
+        assert!(matches!(
+            HEIGHT.value(&mut ctxt),
+            Err(GenApiError::InvalidNode(..))
+        ));
+        assert!(!ACQUISITION_START.is_available(&ctxt));
+        assert!(matches!(
+            ACQUISITION_START.execute(&mut ctxt),
+            Err(GenApiError::InvalidNode(..))
+        ));
+
+        // Implemented with an unexpected interface.
+        assert!(!TRIGGER_MODE.is_available(&ctxt));
+        assert!(!TRIGGER_MODE.is_readable(&mut ctxt).unwrap());
+        assert!(matches!(
+            TRIGGER_MODE.set_value(&mut ctxt, TriggerMode::On),
+            Err(GenApiError::InvalidNode(..))
+        ));
+        assert!(matches!(
+            Feature::<bool>::new("Width").value(&mut ctxt),
+            Err(GenApiError::InvalidNode(..))
+        ));
+    }
+}
@@ -5,21 +5,30 @@
 //! This module contains low level device control implementation for `U3V` device.
 
 use std::{
-    convert::TryInto,
-    io::Read,
+    collections::HashMap,
+    convert::{TryFrom, TryInto},
     sync::{Arc, Mutex},
     time::Duration,
 };
 
 use cameleon_device::{
     u3v,
-    u3v::protocol::{ack, cmd},
+    u3v::{
+        protocol::{ack, cmd},
+        register_map::{abrm, sbrm, sirm},
+    },
 };
-use tracing::error;
+use tracing::{error, warn};
 
-use super::register_map::{self, Abrm, ManifestTable, Sbrm, Sirm};
+use super::{
+    event_handle::EventHandle,
+    register_map::{self, Abrm, Eirm, ManifestTable, Sbrm, Sirm},
+};
 
-use crate::{camera::DeviceControl, genapi::CompressionType, ControlError, ControlResult};
+use crate::{
+    camera::DeviceControl, cancel::CancellationToken, clock::ClockCorrelation, genapi,
+    retry::RetryPolicy, ControlError, ControlResult, StatusCode, StatusKind,
+};
 
 /// Initial timeout duration for transaction between device and host.
 /// This value is temporarily used until the device's bootstrap register value is read.
@@ -35,6 +44,12 @@ const INITIAL_MAXIMUM_ACK_LENGTH: u32 = 128;
 
 const PAYLOAD_TRANSFER_SIZE: u32 = 1024 * 64;
 
+/// Length of the prefix and CCD of a command packet.
+const CMD_HEADER_LENGTH: usize = 4 + 8;
+
+/// Length of the address, reserved and length fields of each entry in a stacked command.
+const STACKED_ENTRY_HEADER_LENGTH: usize = 8 + 2 + 2;
+
 /// This handle provides low level API to read and write data from the device.  
 /// See [`ControlHandle::abrm`] and [`register_map`](super::register_map) which provide more
 /// convenient way to communicate with `u3v` specific registers.
@@ -64,7 +79,7 @@ const PAYLOAD_TRANSFER_SIZE: u32 = 1024 * 64;
 /// camera.ctrl.read(address, &mut buffer).unwrap();
 /// ```
 pub struct ControlHandle {
-    inner: u3v::ControlChannel,
+    inner: InnerChannel,
     config: ConnectionConfig,
     /// Request id of the next packet.
     next_req_id: u16,
@@ -73,6 +88,8 @@ pub struct ControlHandle {
 
     /// Device information.
     info: u3v::DeviceInfo,
+    /// Channel to receive event packets, taken by [`ControlHandle::event_handle`].
+    event_channel: Option<u3v::ReceiveChannel>,
 
     /// Cache for `Abrm`.
     abrm: Option<Abrm>,
@@ -80,8 +97,31 @@ pub struct ControlHandle {
     sbrm: Option<Sbrm>,
     /// Cache for `Sirm`.
     sirm: Option<Sirm>,
+    /// Cache for `Eirm`.
+    eirm: Option<Eirm>,
     /// Cache for `ManifestTable`.
     manifest_table: Option<ManifestTable>,
+    /// Cache for bootstrap registers whose values never change.
+    bootstrap_cache: BootstrapCache,
+}
+
+/// Channel to send commands to and receive acks from the device.
+#[cfg(not(test))]
+type InnerChannel = u3v::ControlChannel;
+/// The channel is replaced by a mock in tests.
+#[cfg(test)]
+type InnerChannel = Box<dyn tests::Channel>;
+
+macro_rules! unwrap_or_log {
+    ($expr:expr) => {{
+        match $expr {
+            Ok(v) => v,
+            Err(error) => {
+                error!(?error);
+                return Err(error.into());
+            }
+        }
+    }};
 }
 
 impl ControlHandle {
@@ -104,6 +144,9 @@ impl ControlHandle {
     ///
     /// NOTE: [`ControlHandle::read`] and [`ControlHandle::write`] may send multiple
     /// requests in a single call. In that case, Timeout is reflected to each request.
+    ///
+    /// When the device returns a pending acknowledge, the timeout advertised by it is used to
+    /// wait for the final acknowledge instead.
     #[must_use]
     pub fn timeout_duration(&self) -> Duration {
         self.config.timeout_duration
@@ -132,6 +175,55 @@ impl ControlHandle {
         self.config.retry_count = count;
     }
 
+    /// Policy of resending a command when a transaction times out or the device is busy.
+    #[must_use]
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.config.retry_policy
+    }
+
+    /// Set policy of resending a command when a transaction times out or the device is busy.
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.config.retry_policy = policy;
+    }
+
+    /// Calls `f` with `policy` overriding the retry policy of the handle, the original policy is
+    /// restored after the call.
+    pub fn with_retry_policy<T>(
+        &mut self,
+        policy: RetryPolicy,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        let original = std::mem::replace(&mut self.config.retry_policy, policy);
+        let res = f(self);
+        self.config.retry_policy = original;
+        res
+    }
+
+    /// Sends a vendor specific command with raw `scd` and returns raw SCD of the acknowledge.
+    ///
+    /// `command_id` must be in the range of `0x8000..=0xFFFF`, which `GenCP` reserves for vendor
+    /// specific commands. Request id and pending acknowledge are handled in the same way as
+    /// standard commands.
+    pub fn send_custom_cmd(&mut self, command_id: u16, scd: &[u8]) -> ControlResult<Vec<u8>> {
+        unwrap_or_log!(self.assert_open());
+
+        let maximum_ack_scd_len =
+            cmd::ReadMem::maximum_read_length(self.config.maximum_ack_length as usize);
+        let cmd = unwrap_or_log!(cmd::CustomCommand::new(
+            command_id,
+            scd,
+            maximum_ack_scd_len
+        ));
+        if CMD_HEADER_LENGTH + scd.len() > self.config.maximum_cmd_length as usize {
+            let err_msg = "custom command exceeds maximum command length of the device";
+            error!(err_msg);
+            return Err(ControlError::InvalidData(err_msg.into()));
+        }
+
+        let ack: ack::CustomAck = unwrap_or_log!(self.send_cmd(cmd));
+        Ok(ack.data.to_vec())
+    }
+
     /// Returns the device info of the handle.
     pub fn device_info(&self) -> &u3v::DeviceInfo {
         &self.info
@@ -174,6 +266,64 @@ impl ControlHandle {
         Ok(sirm)
     }
 
+    /// Returns [`Sirm`] of the stream channel of `index`.
+    ///
+    /// [`Self::sirm`] is same as `sirm_of(0)`.
+    pub fn sirm_of(&mut self, index: usize) -> ControlResult<Sirm> {
+        if index == 0 {
+            return self.sirm();
+        }
+
+        self.sbrm()?.sirm_of(self, index)?.ok_or_else(|| {
+            ControlError::InvalidDevice(
+                format!("the u3v device doesn't have stream channel {}", index).into(),
+            )
+        })
+    }
+
+    /// Returns [`Eirm`] if the device has an event interface.
+    pub fn eirm(&mut self) -> ControlResult<Option<Eirm>> {
+        if let Some(eirm) = self.eirm {
+            return Ok(Some(eirm));
+        }
+
+        let eirm = self.sbrm()?.eirm(self)?;
+        self.eirm = eirm;
+        Ok(eirm)
+    }
+
+    /// Returns [`EventHandle`] to receive events from the device.
+    ///
+    /// Returns `None` if the device doesn't have an event interface, or the handle has already
+    /// been taken.
+    pub fn event_handle(&mut self) -> ControlResult<Option<EventHandle>> {
+        if self.event_channel.is_none() {
+            return Ok(None);
+        }
+
+        match self.eirm()? {
+            Some(eirm) => Ok(self
+                .event_channel
+                .take()
+                .map(|channel| EventHandle::new(channel, eirm))),
+            None => Ok(None),
+        }
+    }
+
+    /// Estimates correlation between the device clock and the host clock by latching the device
+    /// timestamp `samples` times.
+    ///
+    /// See [`ClockCorrelation`] for more details.
+    ///
+    /// # Panics
+    /// If `samples` is zero.
+    pub fn correlate_clock(&mut self, samples: usize) -> ControlResult<ClockCorrelation> {
+        let abrm = self.abrm()?;
+        ClockCorrelation::sample(samples, || {
+            abrm.latch_timestamp(self).map(Duration::from_nanos)
+        })
+    }
+
     /// Returns [`ManifestTable`].
     pub fn manifest_table(&mut self) -> ControlResult<ManifestTable> {
         if let Some(manifest_table) = self.manifest_table {
@@ -186,20 +336,54 @@ impl ControlHandle {
         Ok(manifest_table)
     }
 
+    /// Invalidates cached values of bootstrap registers, they are read from the device again on
+    /// the next access.
+    ///
+    /// Read-only bootstrap registers, e.g. device capability or maximum transfer lengths, are
+    /// cached on the first read because their values never change while the device is opened.
+    /// The cache is also invalidated when the handle is closed.
+    pub fn invalidate_bootstrap_cache(&mut self) {
+        self.bootstrap_cache.clear();
+        self.abrm = None;
+        self.sbrm = None;
+        self.sirm = None;
+        self.eirm = None;
+        self.manifest_table = None;
+    }
+
     pub(super) fn new(device: &u3v::Device) -> ControlResult<Self> {
         let inner = device.control_channel()?;
+        let event_channel = device.event_channel()?;
+
+        #[cfg(test)]
+        let inner = Box::new(inner);
 
-        Ok(Self {
+        Ok(Self::with_channel(
+            inner,
+            device.device_info.clone(),
+            event_channel,
+        ))
+    }
+
+    fn with_channel(
+        inner: InnerChannel,
+        info: u3v::DeviceInfo,
+        event_channel: Option<u3v::ReceiveChannel>,
+    ) -> Self {
+        Self {
             inner,
             config: ConnectionConfig::default(),
             next_req_id: 0,
             buffer: Vec::new(),
-            info: device.device_info.clone(),
+            info,
+            event_channel,
             abrm: None,
             sbrm: None,
             sirm: None,
+            eirm: None,
             manifest_table: None,
-        })
+            bootstrap_cache: BootstrapCache::default(),
+        }
     }
 
     fn assert_open(&self) -> ControlResult<()> {
@@ -237,55 +421,130 @@ impl ControlHandle {
             self.buffer.resize(std::cmp::max(cmd_len, ack_len), 0);
         }
 
-        // Serialize and send command.
-        cmd.serialize(self.buffer.as_mut_slice())?;
+        // Resend the command according to the retry policy. The same request id is used for
+        // all attempts.
+        let policy = self.config.retry_policy;
+        let mut attempt = 1;
+        let recv_len = loop {
+            // Serialize the command in each attempt because the buffer is reused for ack.
+            cmd.serialize(self.buffer.as_mut_slice())?;
+            match self.transact(cmd_len) {
+                Ok(recv_len) => break recv_len,
+                Err(err) if policy.should_retry(attempt, &err) => {
+                    let delay = policy.delay(attempt);
+                    warn!(?err, attempt, ?delay, "resend command");
+                    std::thread::sleep(delay);
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        };
+        self.next_req_id = self.next_req_id.wrapping_add(1);
+
+        // This codes seems weird due to a lifetime problem.
+        // `ack::AckPacket::parse` is a fast operation, so it's ok to call it repeatedly.
+        Ok(ack::AckPacket::parse(&self.buffer[0..recv_len])
+            .unwrap()
+            .scd_as()?)
+    }
+
+    /// Returns `true` if the device supports `ReadMemStacked` and `WriteMemStacked` and the
+    /// maximum command length is large enough to contain at least one stacked entry.
+    fn use_stacked_commands(&mut self) -> ControlResult<bool> {
+        // `Abrm::new` only reads `DEVICE_CAPABILITY`, which is served from the bootstrap cache
+        // after the first read.
+        let capability = Abrm::new(self)?.device_capability()?;
+        Ok(capability.is_stacked_commands_supported()
+            && self.config.maximum_cmd_length as usize
+                > CMD_HEADER_LENGTH + STACKED_ENTRY_HEADER_LENGTH)
+    }
+
+    fn send_read_mem_stacked(&mut self, batch: &mut [(u64, &mut [u8])]) -> ControlResult<()> {
+        let entries = batch
+            .iter()
+            .map(|(address, buf)| cmd::ReadMem::new(*address, buf.len().try_into().unwrap()))
+            .collect();
+        let cmd = cmd::ReadMemStacked::new(entries)?;
+        let ack: ack::ReadMemStacked = self.send_cmd(cmd)?;
+
+        let read_len: usize = batch.iter().map(|(_, buf)| buf.len()).sum();
+        if ack.data.len() != read_len {
+            let err_msg = "read mem stacked failed: read length mismatch";
+            return Err(ControlError::Io(anyhow::Error::msg(err_msg)));
+        }
+
+        let mut data = ack.data;
+        for (_, buf) in batch {
+            let (head, tail) = data.split_at(buf.len());
+            buf.copy_from_slice(head);
+            data = tail;
+        }
+
+        Ok(())
+    }
+
+    fn send_write_mem_stacked(&mut self, batch: &[(u64, &[u8])]) -> ControlResult<()> {
+        let entries = batch
+            .iter()
+            .map(|(address, data)| cmd::WriteMem::new(*address, data))
+            .collect::<Result<Vec<_>, _>>()?;
+        let cmd = cmd::WriteMemStacked::new(entries)?;
+        let ack: ack::WriteMemStacked = self.send_cmd(cmd)?;
+
+        if ack.lengths.len() != batch.len()
+            || ack
+                .lengths
+                .iter()
+                .zip(batch)
+                .any(|(len, (_, data))| *len as usize != data.len())
+        {
+            let err_msg = "write mem stacked failed: written length mismatch";
+            return Err(ControlError::Io(anyhow::Error::msg(err_msg)));
+        }
+
+        Ok(())
+    }
+
+    /// Sends the command serialized in the buffer and receives its ack into the buffer, returns
+    /// the length of the ack.
+    fn transact(&mut self, cmd_len: usize) -> ControlResult<usize> {
         self.inner
             .send(&self.buffer[..cmd_len], self.config.timeout_duration)?;
 
         // Receive ack and interpret the packet.
-        let mut retry_count = self.config.retry_count;
-        let mut ok = None;
-        while retry_count > 0 {
-            let recv_len = self
-                .inner
-                .recv(&mut self.buffer, self.config.timeout_duration)?;
+        let mut pending_count = 0;
+        let mut timeout = self.config.timeout_duration;
+        loop {
+            let recv_len = self.inner.recv(&mut self.buffer, timeout)?;
 
             let ack = ack::AckPacket::parse(&self.buffer[0..recv_len])?;
             self.verify_ack(&ack)?;
 
-            // Retry up to retry count.
+            // The device needs more time to complete the command, wait for the final ack up to
+            // the timeout advertised by the pending ack. Retry up to retry count.
             if ack.scd_kind() == ack::ScdKind::Pending {
+                if pending_count == self.config.retry_count {
+                    return Err(ControlError::Io(anyhow::Error::msg(
+                        "the number of times pending was returned exceeds the retry_count.",
+                    )));
+                }
                 let pending_ack: ack::Pending = ack.scd_as()?;
-                std::thread::sleep(pending_ack.timeout);
-                retry_count -= 1;
+                timeout = pending_ack.timeout;
+                pending_count += 1;
                 continue;
             }
 
-            self.next_req_id = self.next_req_id.wrapping_add(1);
-            ok = Some(recv_len);
-            break;
-        }
-
-        // This codes seems weird due to a lifetime problem.
-        // `ack::AckPacket::parse` is a fast operation, so it's ok to call it repeatedly.
-        if let Some(recv_len) = ok {
-            Ok(ack::AckPacket::parse(&self.buffer[0..recv_len])
-                .unwrap()
-                .scd_as()?)
-        } else {
-            Err(ControlError::Io(anyhow::Error::msg(
-                "the number of times pending was returned exceeds the retry_count.",
-            )))
+            return Ok(recv_len);
         }
     }
 
     fn verify_ack(&self, ack: &ack::AckPacket) -> ControlResult<()> {
-        let status = ack.status().kind();
-        if status != ack::StatusKind::GenCp(ack::GenCpStatus::Success) {
-            return Err(ControlError::Io(anyhow::Error::msg(format!(
-                "invalid status: {:?}",
-                ack.status().kind()
-            ))));
+        let status = ack.status();
+        if status.kind() == ack::StatusKind::GenCp(ack::GenCpStatus::Busy) {
+            return Err(ControlError::Busy);
+        }
+        if !status.is_success() {
+            return Err(ControlError::DeviceStatus(status_code(*status)));
         }
 
         if ack.request_id() != self.next_req_id {
@@ -295,6 +554,68 @@ impl ControlHandle {
         Ok(())
     }
 
+    fn retrieve_genapi(&mut self, token: Option<&CancellationToken>) -> ControlResult<String> {
+        let table = unwrap_or_log!(self.manifest_table());
+        // Use newest version if there are more than one entries.
+        let mut newest_ent = None;
+        for ent in unwrap_or_log!(table.entries(self)) {
+            let file_info = unwrap_or_log!(ent.file_info(self));
+            if unwrap_or_log!(file_info.file_type()) == register_map::GenICamFileType::DeviceXml {
+                let version = unwrap_or_log!(ent.genicam_file_version(self));
+                match &newest_ent {
+                    Some((_, cur_version, _)) if &version <= cur_version => {
+                        // Current entry is newest.
+                    }
+                    _ => newest_ent = Some((ent, version, file_info)),
+                }
+            }
+        }
+
+        let (ent, _, file_info) = unwrap_or_log!(newest_ent.ok_or_else(|| {
+            ControlError::InvalidDevice("device doesn't have valid `ManifestEntry`".into())
+        }));
+
+        let file_address: u64 = unwrap_or_log!(ent.file_address(self));
+        let file_size: usize = unwrap_or_log!(unwrap_or_log!(ent.file_size(self)).try_into());
+        let comp_type = unwrap_or_log!(file_info.compression_type());
+
+        // Store current capacity so that we can set back it after XML retrieval because this needs exceptional large size of internal buffer.
+        let current_capacity = self.buffer_capacity();
+        let mut buf = vec![0; file_size];
+        let res = self.read_with_cancel(file_address, &mut buf, token);
+        self.resize_buffer(current_capacity);
+        unwrap_or_log!(res);
+
+        // Verify retrieved xml has correct hash.
+        unwrap_or_log!(self.verify_xml(&buf, ent));
+
+        genapi::decode_xml(buf, comp_type)
+    }
+
+    /// Reads data in chunks so that `token` is checked between transactions.
+    fn read_with_cancel(
+        &mut self,
+        mut address: u64,
+        buf: &mut [u8],
+        token: Option<&CancellationToken>,
+    ) -> ControlResult<()> {
+        /// Size of data read between cancellation checks.
+        const CHUNK_SIZE: usize = 64 * 1024;
+
+        let token = match token {
+            Some(token) => token,
+            None => return self.read(address, buf),
+        };
+
+        for chunk in buf.chunks_mut(CHUNK_SIZE) {
+            token.check_control()?;
+            self.read(address, chunk)?;
+            address += chunk.len() as u64;
+        }
+
+        Ok(())
+    }
+
     fn verify_xml(&mut self, xml: &[u8], ent: register_map::ManifestEntry) -> ControlResult<()> {
         use sha1::Digest;
 
@@ -313,18 +634,6 @@ impl ControlHandle {
     }
 }
 
-macro_rules! unwrap_or_log {
-    ($expr:expr) => {{
-        match $expr {
-            Ok(v) => v,
-            Err(error) => {
-                error!(?error);
-                return Err(error.into());
-            }
-        }
-    }};
-}
-
 impl DeviceControl for ControlHandle {
     fn open(&mut self) -> ControlResult<()> {
         if self.is_opened() {
@@ -348,105 +657,192 @@ impl DeviceControl for ControlHandle {
         if self.is_opened() {
             unwrap_or_log!(self.inner.close());
         }
+        self.invalidate_bootstrap_cache();
         Ok(())
     }
 
-    fn write(&mut self, address: u64, data: &[u8]) -> ControlResult<()> {
+    fn write(&mut self, mut address: u64, data: &[u8]) -> ControlResult<()> {
         unwrap_or_log!(self.assert_open());
-
-        let cmd = unwrap_or_log!(cmd::WriteMem::new(address, data));
-        let maximum_cmd_length = self.config.maximum_cmd_length;
-
-        for chunk in cmd.chunks(maximum_cmd_length as usize).unwrap() {
-            let chunk_data_len = chunk.data_len();
-            let ack: ack::WriteMem = unwrap_or_log!(self.send_cmd(chunk));
-
-            if ack.length as usize != chunk_data_len {
+        self.bootstrap_cache.invalidate(address, data.len());
+
+        // Chunks data if data length is larger than maximum data length calculated from maximum
+        // command length.
+        let maximum_data_length = unwrap_or_log!(cmd::WriteMem::maximum_data_length(
+            self.config.maximum_cmd_length as usize
+        ));
+        for data_chunk in data.chunks(maximum_data_length as usize) {
+            let cmd = unwrap_or_log!(cmd::WriteMem::new(address, data_chunk));
+            let ack: ack::WriteMem = unwrap_or_log!(self.send_cmd(cmd));
+
+            if ack.length as usize != data_chunk.len() {
                 let err_msg = "write mem failed: written length mismatch";
                 return Err(ControlError::Io(anyhow::Error::msg(err_msg)));
             }
+            address += data_chunk.len() as u64;
         }
 
         Ok(())
     }
 
-    fn read(&mut self, mut address: u64, buf: &mut [u8]) -> ControlResult<()> {
+    fn read(&mut self, address: u64, buf: &mut [u8]) -> ControlResult<()> {
         unwrap_or_log!(self.assert_open());
 
+        if let Some(value) = self.bootstrap_cache.get(address, buf.len()) {
+            buf.copy_from_slice(value);
+            return Ok(());
+        }
+
         // Chunks buffer if buffer length is larger than maximum read length calculated from
         // maximum ack length.
+        let mut chunk_address = address;
         for buf_chunk in buf.chunks_mut(cmd::ReadMem::maximum_read_length(
             self.config.maximum_ack_length as usize,
         ) as usize)
         {
             let read_len: u16 = buf_chunk.len().try_into().unwrap();
 
-            let cmd = cmd::ReadMem::new(address, read_len);
+            let cmd = cmd::ReadMem::new(chunk_address, read_len);
             let ack: ack::ReadMem = unwrap_or_log!(self.send_cmd(cmd));
             buf_chunk.copy_from_slice(ack.data);
-            address += read_len as u64;
+            chunk_address += read_len as u64;
         }
 
+        self.bootstrap_cache.store(address, buf);
         Ok(())
     }
 
-    fn genapi(&mut self) -> ControlResult<String> {
-        fn zip_err(err: impl std::fmt::Debug) -> ControlError {
-            ControlError::InvalidDevice(format!("zipped xml file is broken: {:?}", err).into())
-        }
+    /// Reads multiple regions with `ReadMemStacked` if the device supports it, entries are
+    /// packed into as few commands as the maximum command and acknowledge lengths allow.
+    ///
+    /// Falls back to [`ControlHandle::read`] for each entry otherwise.
+    fn read_stacked(&mut self, entries: &mut [(u64, &mut [u8])]) -> ControlResult<()> {
+        unwrap_or_log!(self.assert_open());
 
-        let table = unwrap_or_log!(self.manifest_table());
-        // Use newest version if there are more than one entries.
-        let mut newest_ent = None;
-        for ent in unwrap_or_log!(table.entries(self)) {
-            let file_info = unwrap_or_log!(ent.file_info(self));
-            if unwrap_or_log!(file_info.file_type()) == register_map::GenICamFileType::DeviceXml {
-                let version = unwrap_or_log!(ent.genicam_file_version(self));
-                match &newest_ent {
-                    Some((_, cur_version, _)) if &version <= cur_version => {
-                        // Current entry is newest.
-                    }
-                    _ => newest_ent = Some((ent, version, file_info)),
+        // Only read entries which are not cached.
+        let mut entries: Vec<_> = entries
+            .iter_mut()
+            .filter_map(|(address, buf)| {
+                if let Some(value) = self.bootstrap_cache.get(*address, buf.len()) {
+                    buf.copy_from_slice(value);
+                    None
+                } else {
+                    Some((*address, &mut **buf))
                 }
+            })
+            .collect();
+
+        if !unwrap_or_log!(self.use_stacked_commands()) {
+            for (address, buf) in entries {
+                unwrap_or_log!(self.read(address, buf));
             }
+            return Ok(());
         }
 
-        let (ent, _, file_info) = unwrap_or_log!(newest_ent.ok_or_else(|| {
-            ControlError::InvalidDevice("device doesn't have valid `ManifestEntry`".into())
-        }));
-
-        let file_address: u64 = unwrap_or_log!(ent.file_address(self));
-        let file_size: usize = unwrap_or_log!(unwrap_or_log!(ent.file_size(self)).try_into());
-        let comp_type = unwrap_or_log!(file_info.compression_type());
+        // All data read by a command must fit into a single ack.
+        let maximum_read_length =
+            cmd::ReadMem::maximum_read_length(self.config.maximum_ack_length as usize) as usize;
+        let maximum_entry_num = (self.config.maximum_cmd_length as usize - CMD_HEADER_LENGTH)
+            / STACKED_ENTRY_HEADER_LENGTH;
+
+        // Split entries which don't fit into a single ack.
+        let chunks = entries.iter_mut().flat_map(|(address, buf)| {
+            let address = *address;
+            buf.chunks_mut(maximum_read_length)
+                .enumerate()
+                .map(move |(i, chunk)| (address + (i * maximum_read_length) as u64, chunk))
+        });
+
+        let mut batch = vec![];
+        let mut batch_read_len = 0;
+        for (address, chunk) in chunks {
+            if batch.len() == maximum_entry_num
+                || batch_read_len + chunk.len() > maximum_read_length
+            {
+                unwrap_or_log!(self.send_read_mem_stacked(&mut batch));
+                batch.clear();
+                batch_read_len = 0;
+            }
+            batch_read_len += chunk.len();
+            batch.push((address, chunk));
+        }
+        if !batch.is_empty() {
+            unwrap_or_log!(self.send_read_mem_stacked(&mut batch));
+        }
 
-        // Store current capacity so that we can set back it after XML retrieval because this needs exceptional large size of internal buffer.
-        let current_capacity = self.buffer_capacity();
-        let mut buf = vec![0; file_size];
-        unwrap_or_log!(self.read(file_address, &mut buf));
-        self.resize_buffer(current_capacity);
+        for (address, buf) in &entries {
+            self.bootstrap_cache.store(*address, buf);
+        }
+        Ok(())
+    }
 
-        // Verify retrieved xml has correct hash.
-        unwrap_or_log!(self.verify_xml(&buf, ent));
+    /// Writes multiple regions with `WriteMemStacked` if the device supports it, entries are
+    /// packed into as few commands as the maximum command and acknowledge lengths allow.
+    ///
+    /// Falls back to [`ControlHandle::write`] for each entry otherwise.
+    fn write_stacked(&mut self, entries: &[(u64, &[u8])]) -> ControlResult<()> {
+        unwrap_or_log!(self.assert_open());
+        for (address, data) in entries {
+            self.bootstrap_cache.invalidate(*address, data.len());
+        }
 
-        match comp_type {
-            CompressionType::Zip => {
-                let mut zip = zip::ZipArchive::new(std::io::Cursor::new(buf)).unwrap();
-                if zip.len() != 1 {
-                    return Err(zip_err("more than one files in zipped GenApi XML"));
-                }
-                let mut file = unwrap_or_log!(zip.by_index(0).map_err(zip_err));
-                let file_size: usize = unwrap_or_log!(file.size().try_into());
-                let mut xml = Vec::with_capacity(file_size);
-                unwrap_or_log!(file.read_to_end(&mut xml).map_err(zip_err));
-                Ok(String::from_utf8_lossy(&xml).into())
+        if !unwrap_or_log!(self.use_stacked_commands()) {
+            for (address, data) in entries {
+                unwrap_or_log!(self.write(*address, data));
             }
+            return Ok(());
+        }
 
-            CompressionType::Uncompressed => Ok(String::from_utf8_lossy(&buf).into()),
+        let maximum_cmd_len = self.config.maximum_cmd_length as usize - CMD_HEADER_LENGTH;
+        let maximum_data_len = maximum_cmd_len - STACKED_ENTRY_HEADER_LENGTH;
+        // Each entry of the ack consists of reserved(2bytes) + length written(2bytes).
+        let maximum_entry_num =
+            cmd::ReadMem::maximum_read_length(self.config.maximum_ack_length as usize) as usize / 4;
+
+        // Split entries which don't fit into a single command.
+        let chunks = entries.iter().flat_map(|(address, data)| {
+            let address = *address;
+            data.chunks(maximum_data_len)
+                .enumerate()
+                .map(move |(i, chunk)| (address + (i * maximum_data_len) as u64, chunk))
+        });
+
+        let mut batch = vec![];
+        let mut batch_cmd_len = 0;
+        for (address, chunk) in chunks {
+            let entry_len = STACKED_ENTRY_HEADER_LENGTH + chunk.len();
+            if batch.len() == maximum_entry_num || batch_cmd_len + entry_len > maximum_cmd_len {
+                unwrap_or_log!(self.send_write_mem_stacked(&batch));
+                batch.clear();
+                batch_cmd_len = 0;
+            }
+            batch_cmd_len += entry_len;
+            batch.push((address, chunk));
         }
+        if !batch.is_empty() {
+            unwrap_or_log!(self.send_write_mem_stacked(&batch));
+        }
+
+        Ok(())
+    }
+
+    fn genapi(&mut self) -> ControlResult<String> {
+        self.retrieve_genapi(None)
+    }
+
+    fn genapi_with_cancel(&mut self, token: &CancellationToken) -> ControlResult<String> {
+        self.retrieve_genapi(Some(token))
     }
 
     fn enable_streaming(&mut self) -> ControlResult<()> {
-        let sirm = unwrap_or_log!(self.sirm());
+        self.enable_streaming_channel(0)
+    }
+
+    fn disable_streaming(&mut self) -> ControlResult<()> {
+        self.disable_streaming_channel(0)
+    }
+
+    fn enable_streaming_channel(&mut self, index: usize) -> ControlResult<()> {
+        let sirm = unwrap_or_log!(self.sirm_of(index));
 
         // It's forbidden to set SIRM registers while stream is enabled.
         // This is necessary in case we lost control of a device before properly closing it, which
@@ -495,8 +891,8 @@ impl DeviceControl for ControlHandle {
         Ok(())
     }
 
-    fn disable_streaming(&mut self) -> ControlResult<()> {
-        let sirm = unwrap_or_log!(self.sirm());
+    fn disable_streaming_channel(&mut self, index: usize) -> ControlResult<()> {
+        let sirm = unwrap_or_log!(self.sirm_of(index));
         sirm.disable_stream(self)
     }
 }
@@ -559,9 +955,40 @@ impl SharedControlHandle {
         #[must_use]
         pub fn retry_count(&self) -> u16,
         /// Thread safe version of [`ControlHandle::set_retry_count`].
-        pub fn set_retry_count(&self, count: u16) -> ()
+        pub fn set_retry_count(&self, count: u16) -> (),
+        /// Thread safe version of [`ControlHandle::retry_policy`].
+        #[must_use]
+        pub fn retry_policy(&self) -> RetryPolicy,
+        /// Thread safe version of [`ControlHandle::set_retry_policy`].
+        pub fn set_retry_policy(&self, policy: RetryPolicy) -> (),
+        /// Thread safe version of [`ControlHandle::invalidate_bootstrap_cache`].
+        pub fn invalidate_bootstrap_cache(&self) -> (),
+        /// Thread safe version of [`ControlHandle::eirm`].
+        pub fn eirm(&self) -> ControlResult<Option<Eirm>>,
+        /// Thread safe version of [`ControlHandle::event_handle`].
+        pub fn event_handle(&self) -> ControlResult<Option<EventHandle>>,
+        /// Thread safe version of [`ControlHandle::correlate_clock`].
+        ///
+        /// The handle is locked while sampling, so other threads don't delay latching.
+        pub fn correlate_clock(&self, samples: usize) -> ControlResult<ClockCorrelation>
     );
 
+    /// Thread safe version of [`ControlHandle::with_retry_policy`].
+    ///
+    /// The handle is locked during the call of `f`.
+    pub fn with_retry_policy<T>(
+        &self,
+        policy: RetryPolicy,
+        f: impl FnOnce(&mut ControlHandle) -> T,
+    ) -> T {
+        self.0.lock().unwrap().with_retry_policy(policy, f)
+    }
+
+    /// Thread safe version of [`ControlHandle::send_custom_cmd`].
+    pub fn send_custom_cmd(&self, command_id: u16, scd: &[u8]) -> ControlResult<Vec<u8>> {
+        self.0.lock().unwrap().send_custom_cmd(command_id, scd)
+    }
+
     /// Returns the device info of the handle.
     pub fn device_info(&self) -> u3v::DeviceInfo {
         self.0.lock().unwrap().device_info().clone()
@@ -578,9 +1005,14 @@ impl DeviceControl for SharedControlHandle {
         fn close(&mut self) -> ControlResult<()>,
         fn read(&mut self, address: u64, buf: &mut [u8]) -> ControlResult<()>,
         fn write(&mut self, address: u64, data: &[u8]) -> ControlResult<()>,
+        fn read_stacked(&mut self, entries: &mut [(u64, &mut [u8])]) -> ControlResult<()>,
+        fn write_stacked(&mut self, entries: &[(u64, &[u8])]) -> ControlResult<()>,
         fn genapi(&mut self) -> ControlResult<String>,
+        fn genapi_with_cancel(&mut self, token: &CancellationToken) -> ControlResult<String>,
         fn enable_streaming(&mut self) -> ControlResult<()>,
-        fn disable_streaming(&mut self) -> ControlResult<()>
+        fn disable_streaming(&mut self) -> ControlResult<()>,
+        fn enable_streaming_channel(&mut self, index: usize) -> ControlResult<()>,
+        fn disable_streaming_channel(&mut self, index: usize) -> ControlResult<()>
     }
 }
 
@@ -597,6 +1029,9 @@ struct ConnectionConfig {
 
     /// Maximum length of a acknowledge sent to host from device. Unit is byte.
     maximum_ack_length: u32,
+
+    /// Policy of resending a command on timeout or busy.
+    retry_policy: RetryPolicy,
 }
 
 impl Default for ConnectionConfig {
@@ -606,10 +1041,135 @@ impl Default for ConnectionConfig {
             retry_count: 3,
             maximum_cmd_length: INITIAL_MAXIMUM_CMD_LENGTH,
             maximum_ack_length: INITIAL_MAXIMUM_ACK_LENGTH,
+            retry_policy: RetryPolicy::default(),
         }
     }
 }
 
+/// Cache of bootstrap registers whose values never change while the device is opened.
+///
+/// Base addresses of `SBRM` and `SIRM` are taken from the cached values of `abrm::SBRM_ADDRESS`
+/// and `sbrm::SIRM_ADDRESS`, so registers in them are cached only after the addresses are read.
+#[derive(Default)]
+struct BootstrapCache {
+    /// Map from (address, length) of a register to its value.
+    entries: HashMap<(u64, usize), Vec<u8>>,
+}
+
+/// Read-only registers in `ABRM`.
+const STATIC_ABRM_REGISTERS: &[(u64, u16)] = &[
+    abrm::GENCP_VERSION,
+    abrm::MANUFACTURER_NAME,
+    abrm::MODEL_NAME,
+    abrm::FAMILY_NAME,
+    abrm::DEVICE_VERSION,
+    abrm::MANUFACTURER_INFO,
+    abrm::SERIAL_NUMBER,
+    abrm::DEVICE_CAPABILITY,
+    abrm::MAXIMUM_DEVICE_RESPONSE_TIME,
+    abrm::MANIFEST_TABLE_ADDRESS,
+    abrm::SBRM_ADDRESS,
+    abrm::TIMESTAMP_INCREMENT,
+    abrm::IMPLEMENTATION_ENDIANNESS,
+    abrm::DEVICE_SOFTWARE_INTERFACE_VERSION,
+];
+
+/// Read-only registers in `SBRM`.
+const STATIC_SBRM_REGISTERS: &[(u64, u16)] = &[
+    sbrm::U3V_VERSION,
+    sbrm::U3VCP_CAPABILITY_REGISTER,
+    sbrm::MAXIMUM_COMMAND_TRANSFER_LENGTH,
+    sbrm::MAXIMUM_ACKNOWLEDGE_TRANSFER_LENGTH,
+    sbrm::NUMBER_OF_STREAM_CHANNELS,
+    sbrm::SIRM_ADDRESS,
+    sbrm::SIRM_LENGTH,
+    sbrm::EIRM_ADDRESS,
+    sbrm::EIRM_LENGTH,
+    sbrm::IIDC2_ADDRESS,
+    sbrm::CURRENT_SPEED,
+];
+
+/// Read-only registers in `SIRM`.
+const STATIC_SIRM_REGISTERS: &[(u64, u16)] = &[sirm::SI_INFO];
+
+impl BootstrapCache {
+    fn get(&self, address: u64, len: usize) -> Option<&[u8]> {
+        self.entries.get(&(address, len)).map(Vec::as_slice)
+    }
+
+    /// Stores the value if the register at `address` is read-only.
+    fn store(&mut self, address: u64, value: &[u8]) {
+        if self.is_static(address, value.len()) {
+            self.entries.insert((address, value.len()), value.to_vec());
+        }
+    }
+
+    /// Invalidates entries overlapping with the region.
+    fn invalidate(&mut self, address: u64, len: usize) {
+        let end = address.saturating_add(len as u64);
+        self.entries.retain(|(entry_address, entry_len), _| {
+            entry_address.saturating_add(*entry_len as u64) <= address || end <= *entry_address
+        });
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn is_static(&self, address: u64, len: usize) -> bool {
+        let contains = |base: u64, registers: &[(u64, u16)]| {
+            registers.iter().any(|(offset, reg_len)| {
+                base.wrapping_add(*offset) == address && *reg_len as usize == len
+            })
+        };
+
+        let sbrm_base = self.cached_address(0, abrm::SBRM_ADDRESS);
+        let sirm_base = sbrm_base.and_then(|base| self.cached_address(base, sbrm::SIRM_ADDRESS));
+
+        contains(0, STATIC_ABRM_REGISTERS)
+            || sbrm_base.map_or(false, |base| contains(base, STATIC_SBRM_REGISTERS))
+            || sirm_base.map_or(false, |base| contains(base, STATIC_SIRM_REGISTERS))
+    }
+
+    /// Returns the cached address stored in `register` of the register map at `base`.
+    fn cached_address(&self, base: u64, register: (u64, u16)) -> Option<u64> {
+        let (offset, len) = register;
+        let value = self.get(base.wrapping_add(offset), len as usize)?;
+        Some(u64::from_le_bytes(<[u8; 8]>::try_from(value).ok()?))
+    }
+}
+
+/// Converts the status of an ack to [`StatusCode`].
+fn status_code(status: ack::Status) -> StatusCode {
+    use ack::{GenCpStatus, UsbSpecificStatus};
+
+    let kind = match status.kind() {
+        ack::StatusKind::GenCp(status) => match status {
+            GenCpStatus::NotImplemented => StatusKind::NotImplemented,
+            GenCpStatus::InvalidParameter => StatusKind::InvalidParameter,
+            GenCpStatus::InvalidAddress => StatusKind::InvalidAddress,
+            GenCpStatus::WriteProtect => StatusKind::WriteProtect,
+            GenCpStatus::BadAlignment => StatusKind::BadAlignment,
+            GenCpStatus::AccessDenied => StatusKind::AccessDenied,
+            GenCpStatus::Timeout => StatusKind::Timeout,
+            GenCpStatus::InvalidHeader => StatusKind::InvalidHeader,
+            GenCpStatus::WrongConfig => StatusKind::WrongConfig,
+            GenCpStatus::Success | GenCpStatus::Busy | GenCpStatus::GenericError => {
+                StatusKind::GenericError
+            }
+        },
+        ack::StatusKind::UsbSpecific(status) => match status {
+            UsbSpecificStatus::ResendNotSupported => StatusKind::ResendNotSupported,
+            UsbSpecificStatus::StreamEndpointHalted => StatusKind::StreamEndpointHalted,
+            UsbSpecificStatus::PayloadSizeNotAligned => StatusKind::PayloadSizeNotAligned,
+            UsbSpecificStatus::EventEndpointHalted => StatusKind::EventEndpointHalted,
+            UsbSpecificStatus::InvalidSiState => StatusKind::InvalidSiState,
+        },
+        ack::StatusKind::DeviceSpecific => StatusKind::DeviceSpecific,
+    };
+    StatusCode::new(status.code(), kind)
+}
+
 impl From<SharedControlHandle> for Box<dyn DeviceControl> {
     fn from(ctrl: SharedControlHandle) -> Self {
         Box::new(ctrl)
@@ -621,3 +1181,487 @@ impl From<ControlHandle> for Box<dyn DeviceControl> {
         Box::new(ctrl)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SBRM_BASE: u64 = 0x1000;
+    const SIRM_BASE: u64 = 0x2000;
+    const MEMORY_SIZE: usize = 0x4000;
+
+    /// Channel to send commands to and receive acks from the device.
+    ///
+    /// The channel of [`ControlHandle`] is replaced by [`MockChannel`] in tests.
+    pub(super) trait Channel: Send {
+        fn open(&mut self) -> u3v::Result<()>;
+
+        fn close(&mut self) -> u3v::Result<()>;
+
+        fn is_opened(&self) -> bool;
+
+        fn send(&self, buf: &[u8], timeout: Duration) -> u3v::Result<usize>;
+
+        fn recv(&self, buf: &mut [u8], timeout: Duration) -> u3v::Result<usize>;
+
+        fn set_halt(&self, timeout: Duration) -> u3v::Result<()>;
+
+        fn clear_halt(&mut self) -> u3v::Result<()>;
+    }
+
+    impl Channel for u3v::ControlChannel {
+        fn open(&mut self) -> u3v::Result<()> {
+            u3v::ControlChannel::open(self)
+        }
+
+        fn close(&mut self) -> u3v::Result<()> {
+            u3v::ControlChannel::close(self)
+        }
+
+        fn is_opened(&self) -> bool {
+            u3v::ControlChannel::is_opened(self)
+        }
+
+        fn send(&self, buf: &[u8], timeout: Duration) -> u3v::Result<usize> {
+            u3v::ControlChannel::send(self, buf, timeout)
+        }
+
+        fn recv(&self, buf: &mut [u8], timeout: Duration) -> u3v::Result<usize> {
+            u3v::ControlChannel::recv(self, buf, timeout)
+        }
+
+        fn set_halt(&self, timeout: Duration) -> u3v::Result<()> {
+            u3v::ControlChannel::set_halt(self, timeout)
+        }
+
+        fn clear_halt(&mut self) -> u3v::Result<()> {
+            u3v::ControlChannel::clear_halt(self)
+        }
+    }
+
+    /// Command received by [`MockDevice`], each entry consists of an address and a length.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Command {
+        ReadMem(u64, usize),
+        WriteMem(u64, usize),
+        ReadMemStacked(Vec<(u64, usize)>),
+        WriteMemStacked(Vec<(u64, usize)>),
+    }
+
+    /// Emulates a device whose registers are backed by `memory`.
+    #[derive(Default)]
+    struct MockDevice {
+        memory: Vec<u8>,
+        /// Commands received since the last [`MockDevice::take_commands`].
+        commands: Vec<Command>,
+        /// Ack of the last command.
+        ack: Vec<u8>,
+        is_opened: bool,
+    }
+
+    impl MockDevice {
+        fn new(stacked_commands: bool, maximum_cmd_length: u32, maximum_ack_length: u32) -> Self {
+            let mut device = Self {
+                memory: vec![0; MEMORY_SIZE],
+                ..Self::default()
+            };
+
+            let capability: u64 = if stacked_commands { 1 << 13 } else { 0 };
+            device.set(abrm::DEVICE_CAPABILITY.0, &capability.to_le_bytes());
+            device.set(abrm::MAXIMUM_DEVICE_RESPONSE_TIME.0, &100_u32.to_le_bytes());
+            device.set(abrm::SBRM_ADDRESS.0, &SBRM_BASE.to_le_bytes());
+            device.set(
+                SBRM_BASE + sbrm::MAXIMUM_COMMAND_TRANSFER_LENGTH.0,
+                &maximum_cmd_length.to_le_bytes(),
+            );
+            device.set(
+                SBRM_BASE + sbrm::MAXIMUM_ACKNOWLEDGE_TRANSFER_LENGTH.0,
+                &maximum_ack_length.to_le_bytes(),
+            );
+            device.set(SBRM_BASE + sbrm::SIRM_ADDRESS.0, &SIRM_BASE.to_le_bytes());
+            device
+        }
+
+        fn set(&mut self, address: u64, data: &[u8]) {
+            let address = address as usize;
+            self.memory[address..address + data.len()].copy_from_slice(data);
+        }
+
+        fn get(&self, address: u64, len: usize) -> &[u8] {
+            let address = address as usize;
+            &self.memory[address..address + len]
+        }
+
+        /// Executes the command packet and prepares its ack.
+        fn process(&mut self, cmd: &[u8]) {
+            let u16_at = |pos: usize| u16::from_le_bytes([cmd[pos], cmd[pos + 1]]);
+            let u64_at = |pos: usize| u64::from_le_bytes(cmd[pos..pos + 8].try_into().unwrap());
+            // Magic(4bytes) + flag(2bytes) + command id(2bytes) + scd len(2bytes) + request
+            // id(2bytes).
+            let command_id = u16_at(6);
+            let request_id = u16_at(10);
+
+            let mut ack_scd = vec![];
+            let mut pos = CMD_HEADER_LENGTH;
+            match command_id {
+                0x0800 => {
+                    let (address, len) = (u64_at(pos), u16_at(pos + 10) as usize);
+                    ack_scd.extend_from_slice(self.get(address, len));
+                    self.commands.push(Command::ReadMem(address, len));
+                }
+                0x0802 => {
+                    let (address, data) = (u64_at(pos), &cmd[pos + 8..]);
+                    self.set(address, data);
+                    ack_scd.extend_from_slice(&[0, 0]);
+                    ack_scd.extend_from_slice(&(data.len() as u16).to_le_bytes());
+                    self.commands.push(Command::WriteMem(address, data.len()));
+                }
+                0x0806 => {
+                    let mut entries = vec![];
+                    while pos < cmd.len() {
+                        let (address, len) = (u64_at(pos), u16_at(pos + 10) as usize);
+                        ack_scd.extend_from_slice(self.get(address, len));
+                        entries.push((address, len));
+                        pos += STACKED_ENTRY_HEADER_LENGTH;
+                    }
+                    self.commands.push(Command::ReadMemStacked(entries));
+                }
+                0x0808 => {
+                    let mut entries = vec![];
+                    while pos < cmd.len() {
+                        let (address, len) = (u64_at(pos), u16_at(pos + 10) as usize);
+                        pos += STACKED_ENTRY_HEADER_LENGTH;
+                        self.set(address, &cmd[pos..pos + len]);
+                        ack_scd.extend_from_slice(&[0, 0]);
+                        ack_scd.extend_from_slice(&(len as u16).to_le_bytes());
+                        entries.push((address, len));
+                        pos += len;
+                    }
+                    self.commands.push(Command::WriteMemStacked(entries));
+                }
+                _ => panic!("unexpected command id: {:#x}", command_id),
+            }
+
+            let mut ack = vec![];
+            ack.extend_from_slice(&0x4356_3355_u32.to_le_bytes());
+            ack.extend_from_slice(&0_u16.to_le_bytes());
+            ack.extend_from_slice(&(command_id + 1).to_le_bytes());
+            ack.extend_from_slice(&(ack_scd.len() as u16).to_le_bytes());
+            ack.extend_from_slice(&request_id.to_le_bytes());
+            ack.extend(ack_scd);
+            self.ack = ack;
+        }
+
+        fn take_commands(&mut self) -> Vec<Command> {
+            std::mem::take(&mut self.commands)
+        }
+    }
+
+    struct MockChannel(Arc<Mutex<MockDevice>>);
+
+    impl Channel for MockChannel {
+        fn open(&mut self) -> u3v::Result<()> {
+            self.0.lock().unwrap().is_opened = true;
+            Ok(())
+        }
+
+        fn close(&mut self) -> u3v::Result<()> {
+            self.0.lock().unwrap().is_opened = false;
+            Ok(())
+        }
+
+        fn is_opened(&self) -> bool {
+            self.0.lock().unwrap().is_opened
+        }
+
+        fn send(&self, buf: &[u8], _timeout: Duration) -> u3v::Result<usize> {
+            self.0.lock().unwrap().process(buf);
+            Ok(buf.len())
+        }
+
+        fn recv(&self, buf: &mut [u8], _timeout: Duration) -> u3v::Result<usize> {
+            let ack = std::mem::take(&mut self.0.lock().unwrap().ack);
+            buf[..ack.len()].copy_from_slice(&ack);
+            Ok(ack.len())
+        }
+
+        fn set_halt(&self, _timeout: Duration) -> u3v::Result<()> {
+            Ok(())
+        }
+
+        fn clear_halt(&mut self) -> u3v::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Returns an opened handle of `device`. Commands sent while opening are discarded.
+    fn open_handle(device: MockDevice) -> (ControlHandle, Arc<Mutex<MockDevice>>) {
+        let device = Arc::new(Mutex::new(device));
+        let info = u3v::DeviceInfo {
+            gencp_version: semver::Version::new(1, 0, 0),
+            u3v_version: semver::Version::new(1, 0, 0),
+            guid: "000000000000".into(),
+            vendor_name: "Vendor".into(),
+            model_name: "Model".into(),
+            family_name: None,
+            device_version: "1.0.0".into(),
+            manufacturer_info: String::new(),
+            serial_number: "0".into(),
+            user_defined_name: None,
+            supported_speed: u3v::BusSpeed::SuperSpeed,
+            topology: u3v::UsbTopology::default(),
+        };
+        let mut handle =
+            ControlHandle::with_channel(Box::new(MockChannel(device.clone())), info, None);
+        handle.open().unwrap();
+        device.lock().unwrap().take_commands();
+        (handle, device)
+    }
+
+    #[test]
+    fn test_bootstrap_cache_hit() {
+        let (mut handle, device) = open_handle(MockDevice::new(true, 128, 128));
+        let mut buf = [0; 8];
+
+        // Registers read while opening are cached.
+        handle.read(abrm::DEVICE_CAPABILITY.0, &mut buf).unwrap();
+        assert_eq!(u64::from_le_bytes(buf), 1 << 13);
+        let address = SBRM_BASE + sbrm::MAXIMUM_COMMAND_TRANSFER_LENGTH.0;
+        handle.read(address, &mut buf[..4]).unwrap();
+        assert_eq!(buf[..4], 128_u32.to_le_bytes());
+        assert!(device.lock().unwrap().take_commands().is_empty());
+
+        // Other read-only registers are cached on the first read.
+        let (address, len) = abrm::MANIFEST_TABLE_ADDRESS;
+        let mut buf = vec![0; len as usize];
+        handle.read(address, &mut buf).unwrap();
+        handle.read(address, &mut buf).unwrap();
+        handle.read_stacked(&mut [(address, &mut buf)]).unwrap();
+        assert_eq!(
+            device.lock().unwrap().take_commands(),
+            [Command::ReadMem(address, len as usize)]
+        );
+
+        // Writable registers and partial reads of read-only registers are not cached.
+        let (address, len) = abrm::DEVICE_CONFIGURATION;
+        let mut buf = vec![0; len as usize];
+        handle.read(address, &mut buf).unwrap();
+        handle.read(address, &mut buf).unwrap();
+        let capability_address = abrm::DEVICE_CAPABILITY.0;
+        handle.read(capability_address, &mut buf[..4]).unwrap();
+        handle.read(capability_address, &mut buf[..4]).unwrap();
+        assert_eq!(
+            device.lock().unwrap().take_commands(),
+            [
+                Command::ReadMem(address, len as usize),
+                Command::ReadMem(address, len as usize),
+                Command::ReadMem(capability_address, 4),
+                Command::ReadMem(capability_address, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bootstrap_cache_invalidation() {
+        let (mut handle, device) = open_handle(MockDevice::new(true, 128, 128));
+        let (address, len) = abrm::MANIFEST_TABLE_ADDRESS;
+        let len = len as usize;
+        let mut buf = vec![0; len];
+        handle.read(address, &mut buf).unwrap();
+        device.lock().unwrap().take_commands();
+
+        // A write adjacent to the register keeps the cache.
+        let (response_time_address, _) = abrm::MAXIMUM_DEVICE_RESPONSE_TIME;
+        handle
+            .write(response_time_address, &100_u32.to_le_bytes())
+            .unwrap();
+        handle.read(address, &mut buf).unwrap();
+        assert_eq!(
+            device.lock().unwrap().take_commands(),
+            [Command::WriteMem(response_time_address, 4)]
+        );
+
+        // `write` invalidates the overlapping register.
+        handle.write(address + 7, &[1]).unwrap();
+        handle.read(address, &mut buf).unwrap();
+        assert_eq!(buf[7], 1);
+        assert_eq!(
+            device.lock().unwrap().take_commands(),
+            [
+                Command::WriteMem(address + 7, 1),
+                Command::ReadMem(address, len)
+            ]
+        );
+
+        // `write_stacked` invalidates the overlapping register.
+        handle.write_stacked(&[(address, &[2; 4])]).unwrap();
+        handle.read(address, &mut buf).unwrap();
+        assert_eq!(buf[..4], [2; 4]);
+        assert_eq!(
+            device.lock().unwrap().take_commands(),
+            [
+                Command::WriteMemStacked(vec![(address, 4)]),
+                Command::ReadMem(address, len)
+            ]
+        );
+
+        // `close` invalidates all registers.
+        handle.close().unwrap();
+        handle.open().unwrap();
+        device.lock().unwrap().take_commands();
+        handle.read(address, &mut buf).unwrap();
+        assert_eq!(
+            device.lock().unwrap().take_commands(),
+            [Command::ReadMem(address, len)]
+        );
+    }
+
+    #[test]
+    fn test_bootstrap_cache_base_address() {
+        let (mut handle, device) = open_handle(MockDevice::new(true, 128, 128));
+        handle.invalidate_bootstrap_cache();
+        let mut buf = [0; 8];
+
+        // Registers in `SBRM` are cached only after the `SBRM` address is cached.
+        let address = SBRM_BASE + sbrm::MAXIMUM_COMMAND_TRANSFER_LENGTH.0;
+        handle.read(address, &mut buf[..4]).unwrap();
+        handle.read(address, &mut buf[..4]).unwrap();
+        handle.read(abrm::SBRM_ADDRESS.0, &mut buf).unwrap();
+        handle.read(address, &mut buf[..4]).unwrap();
+        handle.read(address, &mut buf[..4]).unwrap();
+        assert_eq!(
+            device.lock().unwrap().take_commands(),
+            [
+                Command::ReadMem(address, 4),
+                Command::ReadMem(address, 4),
+                Command::ReadMem(abrm::SBRM_ADDRESS.0, 8),
+                Command::ReadMem(address, 4),
+            ]
+        );
+
+        // Registers in `SIRM` are cached only after the `SIRM` address is cached.
+        let address = SIRM_BASE + sirm::SI_INFO.0;
+        let sirm_address = SBRM_BASE + sbrm::SIRM_ADDRESS.0;
+        handle.read(address, &mut buf[..4]).unwrap();
+        handle.read(address, &mut buf[..4]).unwrap();
+        handle.read(sirm_address, &mut buf).unwrap();
+        handle.read(address, &mut buf[..4]).unwrap();
+        handle.read(address, &mut buf[..4]).unwrap();
+        assert_eq!(
+            device.lock().unwrap().take_commands(),
+            [
+                Command::ReadMem(address, 4),
+                Command::ReadMem(address, 4),
+                Command::ReadMem(sirm_address, 8),
+                Command::ReadMem(address, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_stacked_batching() {
+        // At most 3 entries fit into a command, and at most 16 bytes fit into an ack.
+        let maximum_cmd_length = (CMD_HEADER_LENGTH + STACKED_ENTRY_HEADER_LENGTH * 3) as u32;
+        let (mut handle, device) = open_handle(MockDevice::new(true, maximum_cmd_length, 28));
+        let base = 0x3000;
+        let data: Vec<u8> = (0..0x80).collect();
+        device.lock().unwrap().set(base, &data);
+
+        let mut buf0 = [0; 4];
+        let mut buf1 = [0; 20];
+        let mut buf2 = [0; 4];
+        let mut buf3 = [0; 4];
+        let mut buf4 = [0; 4];
+        handle
+            .read_stacked(&mut [
+                (base, &mut buf0),
+                (base + 0x10, &mut buf1),
+                (base + 0x40, &mut buf2),
+                (base + 0x50, &mut buf3),
+                (base + 0x60, &mut buf4),
+            ])
+            .unwrap();
+
+        assert_eq!(buf0, data[..4]);
+        assert_eq!(buf1, data[0x10..0x24]);
+        assert_eq!(buf2, data[0x40..0x44]);
+        assert_eq!(buf3, data[0x50..0x54]);
+        assert_eq!(buf4, data[0x60..0x64]);
+        assert_eq!(
+            device.lock().unwrap().take_commands(),
+            [
+                Command::ReadMemStacked(vec![(base, 4)]),
+                // An entry larger than an ack is split.
+                Command::ReadMemStacked(vec![(base + 0x10, 16)]),
+                Command::ReadMemStacked(vec![(base + 0x20, 4), (base + 0x40, 4), (base + 0x50, 4)]),
+                Command::ReadMemStacked(vec![(base + 0x60, 4)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_stacked_batching() {
+        // At most 104 bytes of data fit into a command, and at most 4 entries fit into an ack.
+        let (mut handle, device) = open_handle(MockDevice::new(true, 128, 28));
+        let base = 0x3000;
+        let data: Vec<u8> = (0..110).collect();
+
+        handle
+            .write_stacked(&[
+                (base, &data),
+                (base + 0x100, &[1]),
+                (base + 0x110, &[2]),
+                (base + 0x120, &[3]),
+                (base + 0x130, &[4]),
+                (base + 0x140, &[5]),
+            ])
+            .unwrap();
+
+        let device = device.lock().unwrap();
+        assert_eq!(device.get(base, data.len()), data.as_slice());
+        for i in 0..5 {
+            assert_eq!(device.get(base + 0x100 + i * 0x10, 1), [i as u8 + 1]);
+        }
+        assert_eq!(
+            device.commands,
+            [
+                // An entry larger than a command is split.
+                Command::WriteMemStacked(vec![(base, 104)]),
+                Command::WriteMemStacked(vec![
+                    (base + 104, 6),
+                    (base + 0x100, 1),
+                    (base + 0x110, 1),
+                    (base + 0x120, 1)
+                ]),
+                Command::WriteMemStacked(vec![(base + 0x130, 1), (base + 0x140, 1)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stacked_commands_fallback() {
+        let (mut handle, device) = open_handle(MockDevice::new(false, 128, 128));
+        let base = 0x3000;
+
+        handle
+            .write_stacked(&[(base, &[1; 4]), (base + 4, &[2; 4])])
+            .unwrap();
+        let mut buf0 = [0; 4];
+        let mut buf1 = [0; 4];
+        handle
+            .read_stacked(&mut [(base, &mut buf0), (base + 4, &mut buf1)])
+            .unwrap();
+
+        assert_eq!(buf0, [1; 4]);
+        assert_eq!(buf1, [2; 4]);
+        assert_eq!(
+            device.lock().unwrap().take_commands(),
+            [
+                Command::WriteMem(base, 4),
+                Command::WriteMem(base + 4, 4),
+                Command::ReadMem(base, 4),
+                Command::ReadMem(base + 4, 4),
+            ]
+        );
+    }
+}
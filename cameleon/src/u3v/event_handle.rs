@@ -0,0 +1,168 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains low level event channel implementation for `U3V` device.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use cameleon::u3v;
+//!
+//! let mut cameras = u3v::enumerate_cameras().unwrap();
+//! if cameras.is_empty() {
+//!     return;
+//! }
+//! let mut camera = cameras.pop().unwrap();
+//! camera.open().unwrap();
+//!
+//! // `None` is returned if the device doesn't have an event interface.
+//! let mut event_handle = camera.ctrl.event_handle().unwrap().unwrap();
+//! event_handle.open().unwrap();
+//! event_handle.enable(&mut camera.ctrl).unwrap();
+//!
+//! for event in event_handle.recv().unwrap() {
+//!     println!("event id: {:#x}, timestamp: {}", event.id, event.timestamp);
+//! }
+//!
+//! event_handle.disable(&mut camera.ctrl).unwrap();
+//! event_handle.close().unwrap();
+//! ```
+
+use std::time::Duration;
+
+use cameleon_device::u3v::{self, protocol::event};
+use tracing::error;
+
+use crate::{ControlError, ControlResult, DeviceControl};
+
+use super::register_map::Eirm;
+
+/// Initial buffer size, the buffer is resized according to
+/// [`Eirm::maximum_event_transfer_length`] when the event is enabled.
+const DEFAULT_BUFFER_SIZE: usize = 1024;
+
+/// Default timeout duration of receiving an event packet.
+const DEFAULT_TIMEOUT_DURATION: Duration = Duration::from_millis(500);
+
+/// An event sent from the device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// `EventID` of the event, the value is defined by the device vendor or SFNC.
+    pub id: u16,
+    /// Timestamp of the event occurrence.
+    pub timestamp: u64,
+    /// Request id of the event packet that contains the event.
+    pub request_id: u16,
+    /// Event specific data.
+    pub data: Vec<u8>,
+}
+
+/// This type is used to receive event packets from the device.
+///
+/// To obtain `EventHandle`, use [`super::ControlHandle::event_handle`].
+pub struct EventHandle {
+    /// Inner channel to receive event packets.
+    pub inner: u3v::ReceiveChannel,
+    eirm: Eirm,
+    buffer: Vec<u8>,
+    timeout: Duration,
+}
+
+macro_rules! unwrap_or_log {
+    ($expr:expr) => {{
+        match $expr {
+            Ok(v) => v,
+            Err(error) => {
+                error!(?error);
+                return Err(error.into());
+            }
+        }
+    }};
+}
+
+impl EventHandle {
+    /// Opens the handle.
+    pub fn open(&mut self) -> ControlResult<()> {
+        unwrap_or_log!(self.inner.open());
+        Ok(())
+    }
+
+    /// Closes the handle.
+    pub fn close(&mut self) -> ControlResult<()> {
+        unwrap_or_log!(self.inner.close());
+        Ok(())
+    }
+
+    /// Returns `true` if the handle is opened.
+    #[must_use]
+    pub fn is_opened(&self) -> bool {
+        self.inner.is_opened()
+    }
+
+    /// Enables the event on the device.
+    ///
+    /// The buffer of the handle is resized to the maximum event transfer length of the device.
+    pub fn enable<Ctrl: DeviceControl + ?Sized>(&mut self, ctrl: &mut Ctrl) -> ControlResult<()> {
+        let maximum_length = unwrap_or_log!(self.eirm.maximum_event_transfer_length(ctrl));
+        self.buffer.resize(maximum_length as usize, 0);
+        unwrap_or_log!(self.eirm.enable_event(ctrl));
+        Ok(())
+    }
+
+    /// Disables the event on the device.
+    pub fn disable<Ctrl: DeviceControl + ?Sized>(&mut self, ctrl: &mut Ctrl) -> ControlResult<()> {
+        unwrap_or_log!(self.eirm.disable_event(ctrl));
+        Ok(())
+    }
+
+    /// Receives an event packet and returns events contained in it.
+    ///
+    /// More than one event is returned only when multi event is enabled on the device.
+    pub fn recv(&mut self) -> ControlResult<Vec<Event>> {
+        if !self.is_opened() {
+            return Err(ControlError::NotOpened);
+        }
+
+        let len = unwrap_or_log!(self.inner.recv(&mut self.buffer, self.timeout));
+        let packet = unwrap_or_log!(event::EventPacket::parse(&self.buffer[..len]));
+        let request_id = packet.request_id();
+
+        Ok(packet
+            .scd
+            .into_iter()
+            .map(|scd| Event {
+                id: scd.event_id,
+                timestamp: scd.timestamp,
+                request_id,
+                data: scd.data.to_vec(),
+            })
+            .collect())
+    }
+
+    /// Returns [`Eirm`] of the device.
+    #[must_use]
+    pub fn eirm(&self) -> Eirm {
+        self.eirm
+    }
+
+    /// Timeout duration of receiving an event packet.
+    #[must_use]
+    pub fn timeout_duration(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Sets timeout duration of receiving an event packet.
+    pub fn set_timeout_duration(&mut self, duration: Duration) {
+        self.timeout = duration;
+    }
+
+    pub(super) fn new(inner: u3v::ReceiveChannel, eirm: Eirm) -> Self {
+        Self {
+            inner,
+            eirm,
+            buffer: vec![0; DEFAULT_BUFFER_SIZE],
+            timeout: DEFAULT_TIMEOUT_DURATION,
+        }
+    }
+}
@@ -46,10 +46,12 @@
 #![allow(clippy::missing_panics_doc)]
 
 pub mod control_handle;
+pub mod event_handle;
 pub mod register_map;
 pub mod stream_handle;
 
 pub use control_handle::{ControlHandle, SharedControlHandle};
+pub use event_handle::{Event, EventHandle};
 pub use stream_handle::{StreamHandle, StreamParams};
 
 pub use cameleon_device::u3v::DeviceInfo;
@@ -57,7 +59,8 @@ pub use cameleon_device::u3v::DeviceInfo;
 use cameleon_device::u3v;
 
 use super::{
-    genapi::DefaultGenApiCtxt, CameleonResult, Camera, CameraInfo, ControlError, StreamError,
+    cancel::CancellationToken, genapi::DefaultGenApiCtxt, CameleonResult, Camera, CameraInfo,
+    ControlError, StreamError,
 };
 
 /// Enumerate all U3V compatible cameras connected to the host.
@@ -74,36 +77,152 @@ use super::{
 /// ```
 pub fn enumerate_cameras() -> CameleonResult<Vec<Camera<ControlHandle, StreamHandle>>> {
     let devices = u3v::enumerate_devices().map_err(ControlError::from)?;
+    cameras_from_devices(devices, None)
+}
 
-    let mut cameras: Vec<Camera<ControlHandle, StreamHandle>> = Vec::with_capacity(devices.len());
+/// Same as [`enumerate_cameras`], but aborts enumeration with
+/// [`ControlError::Cancelled`] when `token` is cancelled.
+///
+/// Constructing a camera requires transactions with the device, so enumeration may take a while
+/// when many cameras are connected to the host. `token` is checked before each camera is
+/// constructed.
+///
+/// # Examples
+///
+/// ```no_run
+/// use cameleon::u3v;
+/// use cameleon::cancel::CancellationToken;
+///
+/// let token = CancellationToken::new();
+/// // Call `token.cancel()` from another thread to abort enumeration.
+/// let mut cameras = u3v::enumerate_cameras_with_cancel(&token).unwrap();
+/// ```
+pub fn enumerate_cameras_with_cancel(
+    token: &CancellationToken,
+) -> CameleonResult<Vec<Camera<ControlHandle, StreamHandle>>> {
+    token.check_control()?;
+    let devices = u3v::enumerate_devices().map_err(ControlError::from)?;
+    cameras_from_devices(devices, Some(token))
+}
 
-    for dev in devices {
-        let ctrl = ControlHandle::new(&dev)?;
-        let strm = if let Some(strm) = StreamHandle::new(&dev)? {
-            strm
-        } else {
-            continue;
-        };
-        let ctxt = None;
+/// Enumerate all U3V compatible cameras connected to the host with the `libusb` context given
+/// by the caller.
+///
+/// # Examples
+///
+/// ```no_run
+/// use cameleon::u3v;
+/// use cameleon_device::u3v::rusb;
+///
+/// let ctx = rusb::Context::new().unwrap();
+/// let mut cameras = u3v::enumerate_cameras_with_context(&ctx).unwrap();
+/// ```
+#[cfg(feature = "libusb")]
+pub fn enumerate_cameras_with_context(
+    ctx: &u3v::rusb::Context,
+) -> CameleonResult<Vec<Camera<ControlHandle, StreamHandle>>> {
+    let devices = u3v::enumerate_devices_with_context(ctx).map_err(ControlError::from)?;
+    cameras_from_devices(devices, None)
+}
 
-        let dev_info = dev.device_info;
-        let camera_info = CameraInfo {
-            vendor_name: dev_info.vendor_name,
-            model_name: dev_info.model_name,
-            serial_number: dev_info.serial_number,
-        };
+/// Constructs a camera from a device handle which is already opened by the caller.
+///
+/// This is useful in environments where the host can't enumerate or open USB devices by
+/// itself, e.g. Android, where a device handle is wrapped from a file descriptor obtained from
+/// `UsbManager`. See [`cameleon_device::u3v::adopt_device_handle`] for details.
+///
+/// Returns `Ok(None)` if the device is not a U3V compatible camera.
+#[cfg(feature = "libusb")]
+pub fn camera_from_device_handle(
+    handle: u3v::rusb::DeviceHandle<u3v::rusb::Context>,
+) -> CameleonResult<Option<Camera<ControlHandle, StreamHandle>>> {
+    match u3v::adopt_device_handle(handle).map_err(ControlError::from)? {
+        Some(dev) => camera_from_device(dev),
+        None => Ok(None),
+    }
+}
+
+/// Constructs a camera from a file descriptor of a USB device node which is already opened by
+/// the caller.
+///
+/// On Android, an app can't open USB devices by itself, so the file descriptor must be obtained
+/// from `UsbDeviceConnection::getFileDescriptor` after the user grants the permission through
+/// `UsbManager`. The `libusb` context should be created with device discovery disabled there.
+///
+/// Returns `Ok(None)` if the device is not a U3V compatible camera.
+///
+/// # Safety
+/// `fd` must be a valid file descriptor of a USB device node, and must be kept open while the
+/// camera is alive.
+///
+/// # Examples
+///
+/// ```no_run
+/// use cameleon::u3v;
+/// use cameleon_device::u3v::rusb;
+///
+/// # let fd = 0;
+/// let ctx = rusb::Context::new().unwrap();
+/// // `fd` is handed over from Java through JNI.
+/// let camera = unsafe { u3v::camera_from_fd(&ctx, fd) }.unwrap();
+/// ```
+#[cfg(all(unix, feature = "libusb"))]
+pub unsafe fn camera_from_fd(
+    ctx: &u3v::rusb::Context,
+    fd: std::os::unix::io::RawFd,
+) -> CameleonResult<Option<Camera<ControlHandle, StreamHandle>>> {
+    match u3v::open_device_with_fd(ctx, fd).map_err(ControlError::from)? {
+        Some(dev) => camera_from_device(dev),
+        None => Ok(None),
+    }
+}
 
-        let camera: Camera<ControlHandle, StreamHandle, DefaultGenApiCtxt> =
-            Camera::new(ctrl, strm, ctxt, camera_info);
-        cameras.push(camera)
+fn cameras_from_devices(
+    devices: Vec<u3v::Device>,
+    token: Option<&CancellationToken>,
+) -> CameleonResult<Vec<Camera<ControlHandle, StreamHandle>>> {
+    let mut cameras: Vec<Camera<ControlHandle, StreamHandle>> = Vec::with_capacity(devices.len());
+
+    for dev in devices {
+        if let Some(token) = token {
+            token.check_control()?;
+        }
+        if let Some(camera) = camera_from_device(dev)? {
+            cameras.push(camera)
+        }
     }
 
     Ok(cameras)
 }
 
+fn camera_from_device(
+    dev: u3v::Device,
+) -> CameleonResult<Option<Camera<ControlHandle, StreamHandle>>> {
+    let ctrl = ControlHandle::new(&dev)?;
+    let mut strms = StreamHandle::new_all(&dev)?.into_iter();
+    let strm = if let Some(strm) = strms.next() {
+        strm
+    } else {
+        return Ok(None);
+    };
+    let ctxt = None;
+
+    let dev_info = dev.device_info;
+    let camera_info = CameraInfo {
+        vendor_name: dev_info.vendor_name,
+        model_name: dev_info.model_name,
+        serial_number: dev_info.serial_number,
+    };
+
+    let mut camera: Camera<ControlHandle, StreamHandle, DefaultGenApiCtxt> =
+        Camera::new(ctrl, strm, ctxt, camera_info);
+    camera.extra_strms = strms.collect();
+    Ok(Some(camera))
+}
+
 impl From<u3v::Error> for ControlError {
     fn from(err: u3v::Error) -> ControlError {
-        use u3v::Error::{BufferIo, InvalidDevice, InvalidPacket, LibUsb};
+        use u3v::Error::{BufferIo, InvalidDevice, InvalidPacket, KernelDriverActive, LibUsb};
         use u3v::LibUsbError::{
             Access, BadDescriptor, Busy, Interrupted, InvalidParam, Io, NoDevice, NoMem, NotFound,
             NotSupported, Other, Overflow, Pipe, Timeout,
@@ -118,7 +237,7 @@ impl From<u3v::Error> for ControlError {
                 Timeout => ControlError::Timeout,
             },
 
-            BufferIo(_) | InvalidPacket(_) => ControlError::Io(err.into()),
+            BufferIo(_) | InvalidPacket(_) | KernelDriverActive(_) => ControlError::Io(err.into()),
 
             InvalidDevice => ControlError::InvalidDevice("invalid device".into()),
         }
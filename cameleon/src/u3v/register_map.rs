@@ -50,7 +50,7 @@ use std::{convert::TryInto, time::Duration};
 
 use cameleon_device::u3v::{
     self,
-    register_map::{abrm, manifest_entry, sbrm, sirm},
+    register_map::{abrm, eirm, manifest_entry, sbrm, sirm},
 };
 
 use crate::{genapi::CompressionType, ControlError, ControlResult, DeviceControl};
@@ -272,6 +272,18 @@ impl Abrm {
         self.write_register(device, abrm::TIMESTAMP_LATCH, 1_u32)
     }
 
+    /// Latches the device internal clock and returns the latched timestamp in ns.
+    ///
+    /// This is a shorthand for [`Self::set_timestamp_latch_bit`] followed by
+    /// [`Self::timestamp`].
+    pub fn latch_timestamp<Ctrl: DeviceControl + ?Sized>(
+        &self,
+        device: &mut Ctrl,
+    ) -> ControlResult<u64> {
+        self.set_timestamp_latch_bit(device)?;
+        self.timestamp(device)
+    }
+
     /// Time stamp increment that indicates the ns/tick of the device internal clock.
     ///
     /// For example a value of 1000 indicates the device clock runs at 1MHz.
@@ -436,6 +448,28 @@ impl Sbrm {
         Ok(self.sirm_address(device)?.map(Sirm::new))
     }
 
+    /// Return [`Sirm`] of the stream channel of `index` if it's available.
+    ///
+    /// `Sirm`s of multiple stream channels are placed contiguously from [`Self::sirm_address`]
+    /// with the stride of [`Self::sirm_length`].
+    pub fn sirm_of<Ctrl: DeviceControl + ?Sized>(
+        &self,
+        device: &mut Ctrl,
+        index: usize,
+    ) -> ControlResult<Option<Sirm>> {
+        if index == 0 {
+            return self.sirm(device);
+        }
+
+        if index >= self.number_of_stream_channel(device)? as usize {
+            return Ok(None);
+        }
+        match (self.sirm_address(device)?, self.sirm_length(device)?) {
+            (Some(addr), Some(len)) => Ok(Some(Sirm::new(addr + index as u64 * u64::from(len)))),
+            _ => Ok(None),
+        }
+    }
+
     /// The initial address of `Sirm`.
     ///
     /// NOTE: Some device doesn't support this feature.
@@ -466,6 +500,14 @@ impl Sbrm {
         }
     }
 
+    /// Return [`Eirm`] if it's available.
+    pub fn eirm<Ctrl: DeviceControl + ?Sized>(
+        &self,
+        device: &mut Ctrl,
+    ) -> ControlResult<Option<Eirm>> {
+        Ok(self.eirm_address(device)?.map(Eirm::new))
+    }
+
     /// The initial address of `Eirm`.
     ///
     ///
@@ -747,6 +789,27 @@ impl Sirm {
         self.write_register(device, sirm::PAYLOAD_FINAL_TRANSFER2_SIZE, size)
     }
 
+    /// Reads `u32` registers in a single call of [`DeviceControl::read_stacked`], so that they
+    /// are read in one transaction if the device supports stacked commands.
+    pub(crate) fn read_u32_registers<Ctrl: DeviceControl + ?Sized>(
+        &self,
+        device: &mut Ctrl,
+        registers: &[(u64, u16)],
+    ) -> ControlResult<Vec<u32>> {
+        let mut bufs: Vec<_> = registers
+            .iter()
+            .map(|(_, len)| vec![0; *len as usize])
+            .collect();
+        let mut entries: Vec<_> = registers
+            .iter()
+            .zip(bufs.iter_mut())
+            .map(|((offset, _), buf)| (self.sirm_addr + offset, buf.as_mut_slice()))
+            .collect();
+        device.read_stacked(&mut entries)?;
+
+        bufs.iter().map(|buf| u32::parse_bytes(buf)).collect()
+    }
+
     fn read_register<T, Ctrl>(&self, device: &mut Ctrl, register: (u64, u16)) -> ControlResult<T>
     where
         T: ParseBytes,
@@ -771,6 +834,108 @@ impl Sirm {
     }
 }
 
+/// Represent Event Interface Register Map (EIRM).
+///
+/// To maintain consistency with the device data, `Eirm` doesn't cache any data. It means
+/// that all methods of this struct cause communication with the device every time, thus the device
+/// is expected to be opened when methods are called.
+#[derive(Clone, Copy, Debug)]
+pub struct Eirm {
+    eirm_addr: u64,
+}
+
+impl Eirm {
+    /// Constructs new `Eirm`, consider using [`super::ControlHandle::eirm`] instead.
+    ///
+    /// To construct `Eirm`, Use [`Sbrm::eirm`] also can be used.
+    #[must_use]
+    pub fn new(eirm_addr: u64) -> Self {
+        Self { eirm_addr }
+    }
+
+    /// Enables event.
+    ///
+    /// It's forbidden to write to `MAXIMUM_EVENT_TRANSFER_LENGTH` while event is enabled.
+    pub fn enable_event<Ctrl: DeviceControl + ?Sized>(
+        &self,
+        device: &mut Ctrl,
+    ) -> ControlResult<()> {
+        let value = 1_u32;
+        self.write_register(device, eirm::EI_CONTROL, value)
+    }
+
+    /// Disables event.
+    pub fn disable_event<Ctrl: DeviceControl + ?Sized>(
+        &self,
+        device: &mut Ctrl,
+    ) -> ControlResult<()> {
+        let value = 0_u32;
+        self.write_register(device, eirm::EI_CONTROL, value)
+    }
+
+    /// Returns `true` if event is enabled.
+    pub fn is_event_enable<Ctrl: DeviceControl + ?Sized>(
+        &self,
+        device: &mut Ctrl,
+    ) -> ControlResult<bool> {
+        let ei_ctrl: u32 = self.read_register(device, eirm::EI_CONTROL)?;
+        Ok((ei_ctrl & 1) == 1)
+    }
+
+    /// Maximum length of an event command packet.
+    pub fn maximum_event_transfer_length<Ctrl: DeviceControl + ?Sized>(
+        &self,
+        device: &mut Ctrl,
+    ) -> ControlResult<u32> {
+        self.read_register(device, eirm::MAXIMUM_EVENT_TRANSFER_LENGTH)
+    }
+
+    /// Sets maximum length of an event command packet.
+    ///
+    /// The device never sends an event command packet larger than this value.
+    pub fn set_maximum_event_transfer_length<Ctrl: DeviceControl + ?Sized>(
+        &self,
+        device: &mut Ctrl,
+        length: u32,
+    ) -> ControlResult<()> {
+        self.write_register(device, eirm::MAXIMUM_EVENT_TRANSFER_LENGTH, length)
+    }
+
+    /// Requests the device to send a test event.
+    ///
+    /// The device sends an event with `EventID` 0x4FFF if event is enabled.
+    pub fn fire_test_event<Ctrl: DeviceControl + ?Sized>(
+        &self,
+        device: &mut Ctrl,
+    ) -> ControlResult<()> {
+        let value = 1_u32;
+        self.write_register(device, eirm::EVENT_TEST_CONTROL, value)
+    }
+
+    fn read_register<T, Ctrl>(&self, device: &mut Ctrl, register: (u64, u16)) -> ControlResult<T>
+    where
+        T: ParseBytes,
+        Ctrl: DeviceControl + ?Sized,
+    {
+        let (offset, len) = register;
+        let addr = offset + self.eirm_addr;
+        read_register(device, addr, len)
+    }
+
+    fn write_register<Ctrl: DeviceControl + ?Sized>(
+        &self,
+        device: &mut Ctrl,
+        register: (u64, u16),
+        data: impl DumpBytes,
+    ) -> ControlResult<()> {
+        let (offset, len) = register;
+        let addr = self.eirm_addr + offset;
+        let mut buf = vec![0; len as usize];
+        data.dump_bytes(&mut buf)?;
+        device.write(addr, &buf)
+    }
+}
+
 /// `ManifestTable` provides iterator of [`ManifestEntry`].
 #[derive(Clone, Copy, Debug)]
 pub struct ManifestTable {
@@ -5,19 +5,20 @@
 //! This module contains low level streaming implementation for `U3V` device.
 
 use std::{
-    convert::TryInto,
     sync::{Arc, Mutex, MutexGuard},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use async_std::task;
-use cameleon_device::u3v::{self, async_read::AsyncPool, protocol::stream as u3v_stream};
+use cameleon_device::u3v::{
+    self, async_read::AsyncPool, protocol::stream as u3v_stream, register_map::sirm,
+};
 use futures::channel::oneshot;
 use tracing::{error, info, warn};
 
 use crate::{
     camera::PayloadStream,
-    payload::{ImageInfo, Payload, PayloadSender, PayloadType},
+    payload::{first_chunk_size, parse_chunks, ImageInfo, Payload, PayloadSender, PayloadType},
     ControlError, ControlResult, DeviceControl, StreamError, StreamResult,
 };
 
@@ -29,6 +30,8 @@ pub struct StreamHandle {
     pub inner: Arc<Mutex<u3v::ReceiveChannel>>,
     /// Parameters for streaming.
     params: StreamParams,
+    /// Index of the stream channel of the device.
+    channel_index: usize,
     cancellation_tx: Option<oneshot::Sender<()>>,
     completion_rx: Option<oneshot::Receiver<()>>,
 }
@@ -98,14 +101,27 @@ impl StreamHandle {
         &mut self.params
     }
 
-    pub(super) fn new(device: &u3v::Device) -> ControlResult<Option<Self>> {
-        let inner = device.stream_channel()?;
-        Ok(inner.map(|inner| Self {
-            inner: Arc::new(Mutex::new(inner)),
-            params: StreamParams::default(),
-            cancellation_tx: None,
-            completion_rx: None,
-        }))
+    /// Returns the index of the stream channel of the device which the handle receives.
+    #[must_use]
+    pub fn channel_index(&self) -> usize {
+        self.channel_index
+    }
+
+    /// Returns handles of all stream channels of the device, the `n`th handle receives the `n`th
+    /// stream channel.
+    pub(super) fn new_all(device: &u3v::Device) -> ControlResult<Vec<Self>> {
+        let channels = device.stream_channels()?;
+        Ok(channels
+            .into_iter()
+            .enumerate()
+            .map(|(channel_index, inner)| Self {
+                inner: Arc::new(Mutex::new(inner)),
+                params: StreamParams::default(),
+                channel_index,
+                cancellation_tx: None,
+                completion_rx: None,
+            })
+            .collect())
     }
 }
 
@@ -134,12 +150,15 @@ impl PayloadStream for StreamHandle {
         sender: PayloadSender,
         ctrl: &mut dyn DeviceControl,
     ) -> StreamResult<()> {
-        self.params = StreamParams::from_control(ctrl).map_err(|e| {
-            StreamError::Io(anyhow::Error::msg(format!(
-                "failed to setup streaming parameters: {}",
-                e
-            )))
-        })?;
+        let max_in_flight_transfers = self.params.max_in_flight_transfers;
+        self.params =
+            StreamParams::from_control_with_channel(ctrl, self.channel_index).map_err(|e| {
+                StreamError::Io(anyhow::Error::msg(format!(
+                    "failed to setup streaming parameters: {}",
+                    e
+                )))
+            })?;
+        self.params.max_in_flight_transfers = max_in_flight_transfers;
 
         if self.is_loop_running() {
             return Err(StreamError::InStreaming);
@@ -276,13 +295,16 @@ impl StreamingLoop {
                 read_trailer(&mut inner, &self.params, &mut leader_buf),
                 Some(payload_buf)
             );
+            // The payload is regarded as received when its trailer is read.
+            let host_timestamp = Instant::now();
 
             let payload = unwrap_or_continue!(
                 PayloadBuilder {
                     leader,
                     payload_buf,
                     read_payload_size,
-                    trailer
+                    trailer,
+                    host_timestamp,
                 }
                 .build(),
                 None
@@ -303,6 +325,7 @@ struct PayloadBuilder<'a> {
     payload_buf: Vec<u8>,
     read_payload_size: usize,
     trailer: u3v_stream::Trailer<'a>,
+    host_timestamp: Instant,
 }
 
 impl<'a> PayloadBuilder<'a> {
@@ -325,6 +348,7 @@ impl<'a> PayloadBuilder<'a> {
             u3v_stream::PayloadType::Image => self.build_image_payload(),
             u3v_stream::PayloadType::ImageExtendedChunk => self.build_image_extended_payload(),
             u3v_stream::PayloadType::Chunk => self.build_chunk_payload(),
+            u3v_stream::PayloadType::GenDC => self.build_gendc_payload(),
         }
     }
 
@@ -351,13 +375,12 @@ impl<'a> PayloadBuilder<'a> {
             payload: self.payload_buf,
             valid_payload_size,
             timestamp: leader.timestamp(),
+            host_timestamp: self.host_timestamp,
+            chunks: None,
         })
     }
 
     fn build_image_extended_payload(self) -> StreamResult<Payload> {
-        const CHUNK_ID_LEN: usize = 4;
-        const CHUNK_SIZE_LEN: usize = 4;
-
         let leader: u3v_stream::ImageExtendedChunkLeader = self.specific_leader_as()?;
         let trailer: u3v_stream::ImageExtendedChunkTrailer = self.specific_trailer_as()?;
 
@@ -365,28 +388,8 @@ impl<'a> PayloadBuilder<'a> {
         let valid_payload_size = self.trailer.valid_payload_size() as usize;
 
         // Extract image size from the first chunk of the paload data.
-        // Chunk data is designed to be decoded from the last byte to the first byte.
-        // Use chunk parser of `cameleon_genapi` once it gets implemented.
-        let mut current_offset = valid_payload_size;
-        let image_size = loop {
-            current_offset = current_offset.checked_sub(CHUNK_SIZE_LEN).ok_or_else(|| {
-                StreamError::InvalidPayload("failed to parse chunk data: size field missing".into())
-            })?;
-            let data_size = u32::from_be_bytes(
-                self.payload_buf[current_offset..current_offset + CHUNK_SIZE_LEN]
-                    .try_into()
-                    .unwrap(),
-            ) as usize;
-            current_offset = current_offset.checked_sub(data_size + CHUNK_ID_LEN).ok_or_else(|| {
-                StreamError::InvalidPayload(
-                    "failed to parse chunk data: chunk data size is smaller than specified size".into()
-                )
-            })?;
-
-            if current_offset == 0 {
-                break data_size;
-            }
-        };
+        let chunks = parse_chunks(&self.payload_buf[..valid_payload_size])?;
+        let image_size = first_chunk_size(&chunks)?;
 
         let image_info = Some(ImageInfo {
             width: leader.width() as usize,
@@ -404,6 +407,8 @@ impl<'a> PayloadBuilder<'a> {
             payload: self.payload_buf,
             valid_payload_size,
             timestamp: leader.timestamp(),
+            host_timestamp: self.host_timestamp,
+            chunks: Some(chunks),
         })
     }
 
@@ -413,6 +418,7 @@ impl<'a> PayloadBuilder<'a> {
 
         let id = self.leader.block_id();
         let valid_payload_size = self.trailer.valid_payload_size() as usize;
+        let chunks = parse_chunks(&self.payload_buf[..valid_payload_size])?;
 
         Ok(Payload {
             id,
@@ -421,6 +427,26 @@ impl<'a> PayloadBuilder<'a> {
             payload: self.payload_buf,
             valid_payload_size,
             timestamp: leader.timestamp(),
+            host_timestamp: self.host_timestamp,
+            chunks: Some(chunks),
+        })
+    }
+
+    fn build_gendc_payload(self) -> StreamResult<Payload> {
+        let leader: u3v_stream::GenDCLeader = self.specific_leader_as()?;
+
+        let id = self.leader.block_id();
+        let valid_payload_size = self.trailer.valid_payload_size() as usize;
+
+        Ok(Payload {
+            id,
+            payload_type: PayloadType::GenDC,
+            image_info: None,
+            payload: self.payload_buf,
+            valid_payload_size,
+            timestamp: leader.timestamp(),
+            host_timestamp: self.host_timestamp,
+            chunks: None,
         })
     }
 
@@ -462,6 +488,14 @@ pub struct StreamParams {
 
     /// Timeout duration of each transaction between device.
     pub timeout: Duration,
+
+    /// Maximum number of transfers kept in flight while reading payload. `0` means all transfers
+    /// of a payload are submitted at once.
+    ///
+    /// A smaller value reduces memory pinned by the host controller at the cost of throughput,
+    /// a larger value absorbs scheduling jitter of the host on fast cameras.
+    /// This value is not read from the device, so it's preserved when streaming is started.
+    pub max_in_flight_transfers: usize,
 }
 
 impl StreamParams {
@@ -493,24 +527,47 @@ impl StreamParams {
             payload_final1_size,
             payload_final2_size,
             timeout,
+            max_in_flight_transfers: 0,
         }
     }
 
     /// Build `StreamParams` from [`DeviceControl`].
     pub fn from_control<Ctrl: DeviceControl + ?Sized>(ctrl: &mut Ctrl) -> ControlResult<Self> {
+        Self::from_control_with_channel(ctrl, 0)
+    }
+
+    /// Build `StreamParams` of the stream channel of `index` from [`DeviceControl`].
+    pub fn from_control_with_channel<Ctrl: DeviceControl + ?Sized>(
+        ctrl: &mut Ctrl,
+        index: usize,
+    ) -> ControlResult<Self> {
         let abrm = Abrm::new(ctrl)?;
-        let sirm = abrm.sbrm(ctrl)?.sirm(ctrl)?.ok_or_else(|| {
-            let msg = "the U3V device doesn't have `SIRM`";
-            error!(msg);
+        let sirm = abrm.sbrm(ctrl)?.sirm_of(ctrl, index)?.ok_or_else(|| {
+            let msg = format!(
+                "the U3V device doesn't have `SIRM` of stream channel {}",
+                index
+            );
+            error!(%msg);
             ControlError::InvalidDevice(msg.into())
         })?;
-        let leader_size = sirm.maximum_leader_size(ctrl)? as usize;
-        let trailer_size = sirm.maximum_trailer_size(ctrl)? as usize;
-
-        let payload_size = sirm.payload_transfer_size(ctrl)? as usize;
-        let payload_count = sirm.payload_transfer_count(ctrl)? as usize;
-        let payload_final1_size = sirm.payload_final_transfer1_size(ctrl)? as usize;
-        let payload_final2_size = sirm.payload_final_transfer2_size(ctrl)? as usize;
+        let sizes = sirm.read_u32_registers(
+            ctrl,
+            &[
+                sirm::MAXIMUM_LEADER_SIZE,
+                sirm::MAXIMUM_TRAILER_SIZE,
+                sirm::PAYLOAD_TRANSFER_SIZE,
+                sirm::PAYLOAD_TRANSFER_COUNT,
+                sirm::PAYLOAD_FINAL_TRANSFER1_SIZE,
+                sirm::PAYLOAD_FINAL_TRANSFER2_SIZE,
+            ],
+        )?;
+        let leader_size = sizes[0] as usize;
+        let trailer_size = sizes[1] as usize;
+
+        let payload_size = sizes[2] as usize;
+        let payload_count = sizes[3] as usize;
+        let payload_final1_size = sizes[4] as usize;
+        let payload_final2_size = sizes[5] as usize;
         let timeout = abrm.maximum_device_response_time(ctrl)?;
 
         Ok(Self::new(
@@ -541,25 +598,34 @@ fn read_payload(
     params: &StreamParams,
     buf: &mut [u8],
 ) -> StreamResult<usize> {
-    let payload_size = params.payload_size;
-    let mut async_pool = AsyncPool::new(inner);
-    let mut cursor = 0;
-    for _ in 0..params.payload_count {
-        async_pool.submit(&mut buf[cursor..cursor + payload_size])?;
-        cursor += payload_size;
+    let final_lens = [params.payload_final1_size, params.payload_final2_size];
+    let segment_lens = std::iter::repeat(params.payload_size)
+        .take(params.payload_count)
+        .chain(final_lens.iter().copied().filter(|len| *len != 0));
+    let mut segments = Vec::with_capacity(params.payload_count + 2);
+    let mut rest = buf;
+    for len in segment_lens {
+        let (segment, tail) = std::mem::take(&mut rest).split_at_mut(len);
+        segments.push(segment);
+        rest = tail;
     }
 
-    if params.payload_final1_size != 0 {
-        async_pool.submit(&mut buf[cursor..cursor + params.payload_final1_size])?;
-        cursor += params.payload_final1_size;
-    }
-    if params.payload_final2_size != 0 {
-        async_pool.submit(&mut buf[cursor..cursor + params.payload_final2_size])?;
+    let depth = match params.max_in_flight_transfers {
+        0 => segments.len(),
+        depth => depth,
+    };
+    let mut segments = segments.into_iter();
+    let mut async_pool = AsyncPool::new(inner);
+    for segment in segments.by_ref().take(depth) {
+        async_pool.submit(segment)?;
     }
 
     let mut read_len = 0;
     while !async_pool.is_empty() {
         read_len += async_pool.poll(params.timeout)?;
+        if let Some(segment) = segments.next() {
+            async_pool.submit(segment)?;
+        }
     }
 
     Ok(read_len)
@@ -0,0 +1,126 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! A command to capture frames and save them into files.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use cameleon::{io, payload::Payload};
+
+use super::{Args, Result, U3vCamera};
+
+/// Format of files of captured frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Png,
+    Tiff,
+    /// Image data as sent from the camera, without any header.
+    Raw,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Tiff => "tiff",
+            Self::Raw => "raw",
+        }
+    }
+
+    fn save(self, payload: &Payload, path: &Path) -> Result<()> {
+        match self {
+            Self::Png => io::save_png(payload, path)?,
+            Self::Tiff => io::save_tiff(payload, path)?,
+            Self::Raw => fs::write(path, payload.image().unwrap_or_else(|| payload.payload()))?,
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "png" => Ok(Self::Png),
+            "tiff" => Ok(Self::Tiff),
+            "raw" => Ok(Self::Raw),
+            _ => Err(format!("unknown format `{}`", s)),
+        }
+    }
+}
+
+/// Options of `capture` command.
+pub(super) struct Options {
+    count: usize,
+    out_dir: PathBuf,
+    format: Format,
+    timeout: Duration,
+}
+
+impl Options {
+    pub(super) fn parse(args: &mut Args) -> Result<Self> {
+        let count = match args.option(&["-n", "--count"])? {
+            Some(count) => count
+                .parse()
+                .map_err(|_| format!("`{}` is not a valid count", count))?,
+            None => 1,
+        };
+        if count == 0 {
+            return Err("count must be larger than zero".into());
+        }
+        let out_dir = args
+            .option(&["-o", "--out"])?
+            .map_or_else(|| PathBuf::from("."), PathBuf::from);
+        let format = match args.option(&["--format"])? {
+            Some(format) => format.parse()?,
+            None => Format::Png,
+        };
+        let timeout = match args.option(&["--timeout"])? {
+            Some(timeout) => Duration::from_millis(
+                timeout
+                    .parse()
+                    .map_err(|_| format!("`{}` is not a valid timeout", timeout))?,
+            ),
+            None => Duration::from_secs(5),
+        };
+
+        Ok(Self {
+            count,
+            out_dir,
+            format,
+            timeout,
+        })
+    }
+}
+
+pub(super) fn capture(camera: &mut U3vCamera, options: &Options) -> Result<()> {
+    fs::create_dir_all(&options.out_dir)?;
+    let payloads = camera.grab_n(options.count, options.timeout)?;
+
+    for payload in &payloads {
+        let path = options.out_dir.join(format!(
+            "frame_{:06}.{}",
+            payload.id(),
+            options.format.extension()
+        ));
+        options.format.save(payload, &path)?;
+        match payload.image_info() {
+            Some(info) => println!(
+                "{}: {}x{} {:?}",
+                path.display(),
+                info.width,
+                info.height,
+                info.pixel_format
+            ),
+            None => println!("{}", path.display()),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,156 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Commands to inspect and configure features of the camera.
+
+use cameleon::{
+    genapi::{FeatureValue, GenApiCtxt, Node, ParamsCtxt, Visibility},
+    DeviceControl,
+};
+
+use super::{Result, U3vCamera};
+
+pub(super) fn tree(camera: &mut U3vCamera, all: bool) -> Result<()> {
+    let mut ctxt = camera.params_ctxt()?;
+    let mut tree = ctxt
+        .category_tree()?
+        .ok_or("the camera has no `Root` category")?;
+    if !all {
+        tree = tree.filter_by_visibility(Visibility::Guru);
+    }
+    let mut entries = vec![];
+    tree.visit(|entry, depth| {
+        let indent = "  ".repeat(depth);
+        entries.push((
+            Node::from(entry.id()),
+            entry.is_category(),
+            indent + entry.name(),
+        ));
+    });
+
+    for (node, is_category, line) in entries {
+        if is_category {
+            println!("{}", line);
+        } else {
+            println!("{} = {}", line, describe(&mut ctxt, node));
+        }
+    }
+    Ok(())
+}
+
+pub(super) fn get(camera: &mut U3vCamera, feature: &str) -> Result<()> {
+    let mut ctxt = camera.params_ctxt()?;
+    let node = expect_node(&ctxt, feature)?;
+    println!("{}", format_value(&node.feature_value(&mut ctxt)?));
+    Ok(())
+}
+
+pub(super) fn set(camera: &mut U3vCamera, feature: &str, value: &str) -> Result<()> {
+    let mut ctxt = camera.params_ctxt()?;
+    let node = expect_node(&ctxt, feature)?;
+    let value = parse_value(&ctxt, node, value)?;
+    node.set_feature_value(&mut ctxt, value)?;
+
+    // The device may round the value, so show the value actually written.
+    if node.is_readable(&mut ctxt)? {
+        println!("{}", format_value(&node.feature_value(&mut ctxt)?));
+    }
+    Ok(())
+}
+
+pub(super) fn exec(camera: &mut U3vCamera, feature: &str) -> Result<()> {
+    let mut ctxt = camera.params_ctxt()?;
+    let node = expect_node(&ctxt, feature)?
+        .as_command(&ctxt)
+        .ok_or_else(|| format!("`{}` is not a command", feature))?;
+    node.execute(&mut ctxt)?;
+    Ok(())
+}
+
+fn expect_node<Ctrl, Ctxt>(ctxt: &ParamsCtxt<Ctrl, Ctxt>, feature: &str) -> Result<Node>
+where
+    Ctxt: GenApiCtxt,
+{
+    ctxt.node(feature)
+        .ok_or_else(|| format!("`{}` is not found", feature).into())
+}
+
+/// Returns the value of the node or why it has no value.
+fn describe<Ctrl, Ctxt>(ctxt: &mut ParamsCtxt<Ctrl, Ctxt>, node: Node) -> String
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    if node.as_command(ctxt).is_some() {
+        return "<command>".into();
+    }
+    match node.is_readable(ctxt) {
+        Ok(true) => match node.feature_value(ctxt) {
+            Ok(value) => format_value(&value),
+            Err(err) => format!("<{}>", err),
+        },
+        Ok(false) => "<not readable>".into(),
+        Err(err) => format!("<{}>", err),
+    }
+}
+
+fn format_value(value: &FeatureValue) -> String {
+    match value {
+        FeatureValue::Integer(v) => v.to_string(),
+        FeatureValue::Float(v) => v.to_string(),
+        FeatureValue::String(v) => format!("{:?}", v),
+        FeatureValue::Boolean(v) => v.to_string(),
+        FeatureValue::Enumeration(v) => v.clone(),
+    }
+}
+
+/// Parses `value` as the value of the interface the node implements.
+fn parse_value<Ctrl, Ctxt>(
+    ctxt: &ParamsCtxt<Ctrl, Ctxt>,
+    node: Node,
+    value: &str,
+) -> Result<FeatureValue>
+where
+    Ctxt: GenApiCtxt,
+{
+    let invalid = |kind: &str| format!("`{}` is not a valid {}", value, kind);
+
+    Ok(if node.as_integer(ctxt).is_some() {
+        let parsed = match value.strip_prefix("0x") {
+            Some(hex) => i64::from_str_radix(hex, 16),
+            None => value.parse(),
+        };
+        FeatureValue::Integer(parsed.map_err(|_| invalid("integer"))?)
+    } else if node.as_float(ctxt).is_some() {
+        FeatureValue::Float(value.parse().map_err(|_| invalid("float"))?)
+    } else if node.as_boolean(ctxt).is_some() {
+        FeatureValue::Boolean(match value {
+            "true" | "1" => true,
+            "false" | "0" => false,
+            _ => return Err(invalid("boolean").into()),
+        })
+    } else if node.as_enumeration(ctxt).is_some() {
+        FeatureValue::Enumeration(value.into())
+    } else if node.as_string(ctxt).is_some() {
+        FeatureValue::String(value.into())
+    } else {
+        return Err(format!("`{}` doesn't have any value interface", node.name(ctxt)).into());
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_value() {
+        assert_eq!(format_value(&FeatureValue::Integer(-3)), "-3");
+        assert_eq!(format_value(&FeatureValue::Float(1.5)), "1.5");
+        assert_eq!(format_value(&FeatureValue::String("a b".into())), "\"a b\"");
+        assert_eq!(
+            format_value(&FeatureValue::Enumeration("Continuous".into())),
+            "Continuous"
+        );
+    }
+}
@@ -0,0 +1,259 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Command line tool of cameleon.
+//!
+//! ```sh
+//! # Lists connected cameras.
+//! cameleon-cli list
+//! # Prints the feature tree of the camera whose serial number is `ABC123`.
+//! cameleon-cli tree --camera ABC123
+//! # Sets `ExposureTime`, then captures 10 frames into `frames` directory.
+//! cameleon-cli set ExposureTime 5000
+//! cameleon-cli capture -n 10 -o frames
+//! # Saves `GenApi` XML of the camera into `genapi.xml`, then lints it.
+//! cameleon-cli xml dump -o genapi.xml
+//! cameleon-cli xml validate genapi.xml
+//! ```
+
+mod capture;
+mod feature;
+mod xml;
+
+use std::{env, process};
+
+use cameleon::{
+    u3v::{self, ControlHandle, StreamHandle},
+    Camera,
+};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+type U3vCamera = Camera<ControlHandle, StreamHandle>;
+
+const USAGE: &str = "\
+USAGE:
+    cameleon-cli [OPTIONS] <COMMAND>
+
+COMMANDS:
+    list                     Lists connected cameras
+    info                     Prints information and diagnostics of the camera
+    tree [--all]             Prints the feature tree with current values, invisible features
+                             are included with `--all`
+    get <FEATURE>            Prints the value of the feature
+    set <FEATURE> <VALUE>    Writes the value to the feature
+    exec <FEATURE>           Executes the command feature
+    capture [-n <COUNT>] [-o <DIR>] [--format <png|tiff|raw>] [--timeout <MS>]
+                             Captures frames and saves them into files
+    xml entries              Lists the entries of the manifest table of `GenApi` XMLs
+    xml dump [--entry <INDEX>] [-o <FILE>] [--raw]
+                             Saves `GenApi` XML of the entry, the newest device XML by default,
+                             ZIP files are decompressed unless `--raw` is given
+    xml validate [FILE]      Lints `GenApi` XML in the file, or of the camera if no file is given
+
+OPTIONS:
+    -c, --camera <CAMERA>    Selects the camera by its serial number or index in `list`,
+                             the first camera is selected by default
+    -h, --help               Prints this message";
+
+/// A subcommand of the tool.
+enum Command {
+    List,
+    Info,
+    Tree { all: bool },
+    Get { feature: String },
+    Set { feature: String, value: String },
+    Exec { feature: String },
+    Capture(capture::Options),
+    Xml(xml::Command),
+}
+
+impl Command {
+    fn parse(args: &mut Args) -> Result<Self> {
+        let command = args.positional()?;
+        let command = match command.as_str() {
+            "list" => Self::List,
+            "info" => Self::Info,
+            "tree" => Self::Tree {
+                all: args.flag(&["--all"]),
+            },
+            "get" => Self::Get {
+                feature: args.positional()?,
+            },
+            "set" => Self::Set {
+                feature: args.positional()?,
+                value: args.positional()?,
+            },
+            "exec" => Self::Exec {
+                feature: args.positional()?,
+            },
+            "capture" => Self::Capture(capture::Options::parse(args)?),
+            "xml" => Self::Xml(xml::Command::parse(args)?),
+            _ => return Err(format!("unknown command `{}`\n\n{}", command, USAGE).into()),
+        };
+        args.finish()?;
+        Ok(command)
+    }
+
+    fn run(self, camera: &mut U3vCamera) -> Result<()> {
+        match self {
+            Self::List => unreachable!("`list` doesn't open a camera"),
+            Self::Xml(command) => command.run(Some(camera)),
+            Self::Info => info(camera),
+            Self::Tree { all } => feature::tree(camera, all),
+            Self::Get { feature } => feature::get(camera, &feature),
+            Self::Set { feature, value } => feature::set(camera, &feature, &value),
+            Self::Exec { feature } => feature::exec(camera, &feature),
+            Self::Capture(options) => capture::capture(camera, &options),
+        }
+    }
+}
+
+/// Command line arguments, options are taken out of them before positional arguments.
+struct Args(Vec<String>);
+
+impl Args {
+    /// Takes out the option which has a value, e.g. `--camera <CAMERA>`.
+    fn option(&mut self, names: &[&str]) -> Result<Option<String>> {
+        let pos = match self.0.iter().position(|arg| names.contains(&arg.as_str())) {
+            Some(pos) => pos,
+            None => return Ok(None),
+        };
+        if pos + 1 == self.0.len() {
+            return Err(format!("`{}` requires a value", self.0[pos]).into());
+        }
+        let value = self.0.remove(pos + 1);
+        self.0.remove(pos);
+        Ok(Some(value))
+    }
+
+    /// Takes out the flag, returns `true` if it's given.
+    fn flag(&mut self, names: &[&str]) -> bool {
+        let len = self.0.len();
+        self.0.retain(|arg| !names.contains(&arg.as_str()));
+        self.0.len() != len
+    }
+
+    /// Takes out the next positional argument.
+    fn positional(&mut self) -> Result<String> {
+        if self.0.is_empty() {
+            return Err(format!("missing argument\n\n{}", USAGE).into());
+        }
+        Ok(self.0.remove(0))
+    }
+
+    /// Returns error if any argument is left.
+    fn finish(&self) -> Result<()> {
+        match self.0.first() {
+            Some(arg) => Err(format!("unknown argument `{}`\n\n{}", arg, USAGE).into()),
+            None => Ok(()),
+        }
+    }
+}
+
+fn main() {
+    let mut args = Args(env::args().skip(1).collect());
+    if args.0.is_empty() || args.flag(&["-h", "--help"]) {
+        println!("{}", USAGE);
+        return;
+    }
+
+    if let Err(err) = run(&mut args) {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
+fn run(args: &mut Args) -> Result<()> {
+    let selector = args.option(&["-c", "--camera"])?;
+    let command = Command::parse(args)?;
+    // `GenApi` context isn't loaded for `xml` commands because they are used to investigate a
+    // broken XML.
+    let load_context = match command {
+        Command::List => return list(),
+        Command::Xml(command) if !command.needs_camera() => return command.run(None),
+        Command::Xml(_) => false,
+        _ => true,
+    };
+
+    let mut camera = open_camera(selector.as_deref(), load_context)?;
+    let res = command.run(&mut camera);
+    let close_res = camera.close();
+    res?;
+    Ok(close_res?)
+}
+
+fn list() -> Result<()> {
+    let cameras = u3v::enumerate_cameras()?;
+    if cameras.is_empty() {
+        println!("no camera found");
+        return Ok(());
+    }
+
+    println!("{:<5} {:<24} {:<24} SERIAL", "INDEX", "VENDOR", "MODEL");
+    for (index, camera) in cameras.iter().enumerate() {
+        let info = camera.info();
+        println!(
+            "{:<5} {:<24} {:<24} {}",
+            index, info.vendor_name, info.model_name, info.serial_number
+        );
+    }
+    Ok(())
+}
+
+/// Opens the camera selected by its serial number or index, and loads its context if
+/// `load_context` is `true`.
+fn open_camera(selector: Option<&str>, load_context: bool) -> Result<U3vCamera> {
+    let mut cameras = u3v::enumerate_cameras()?;
+    let index = match selector {
+        Some(selector) => cameras
+            .iter()
+            .position(|camera| camera.info().serial_number == selector)
+            .or_else(|| selector.parse().ok().filter(|i| *i < cameras.len()))
+            .ok_or_else(|| format!("camera `{}` is not found", selector))?,
+        None if cameras.is_empty() => return Err("no camera found".into()),
+        None => 0,
+    };
+
+    let mut camera = cameras.swap_remove(index);
+    camera.open()?;
+    if !load_context {
+        return Ok(camera);
+    }
+    if let Err(err) = camera.load_context() {
+        camera.close().ok();
+        return Err(err.into());
+    }
+    Ok(camera)
+}
+
+fn info(camera: &mut U3vCamera) -> Result<()> {
+    let diagnostics = camera.diagnostics()?;
+    let or_unknown = |value: Option<String>| value.unwrap_or_else(|| "-".into());
+
+    println!("Vendor:           {}", diagnostics.vendor_name);
+    println!("Model:            {}", diagnostics.model_name);
+    println!("Serial number:    {}", diagnostics.serial_number);
+    println!(
+        "Firmware version: {}",
+        or_unknown(diagnostics.firmware_version)
+    );
+    println!(
+        "Temperature:      {}",
+        or_unknown(
+            diagnostics
+                .device_temperature
+                .map(|t| format!("{:.1} C", t))
+        )
+    );
+    println!(
+        "Link speed:       {}",
+        or_unknown(
+            diagnostics
+                .link_speed
+                .map(|speed| format!("{:.1} MB/s", speed as f64 / 1e6))
+        )
+    );
+    Ok(())
+}
@@ -0,0 +1,223 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Commands to retrieve and validate `GenApi` XML of the camera.
+
+use std::{
+    convert::TryFrom,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use cameleon::{
+    genapi::{self, CompressionType},
+    u3v::{
+        register_map::{GenICamFileType, ManifestEntry},
+        ControlHandle,
+    },
+    DeviceControl,
+};
+use cameleon_genapi::lint::{self, Severity};
+use sha1::Digest;
+
+use super::{Args, Result, U3vCamera, USAGE};
+
+/// A subcommand of `xml` command.
+pub(super) enum Command {
+    /// Lists the entries of the manifest table.
+    Entries,
+    /// Writes the file of the manifest entry into a file.
+    Dump {
+        /// Index of the manifest entry, the newest device XML is used if `None`.
+        entry: Option<usize>,
+        out: Option<PathBuf>,
+        /// Writes the file as is without decompressing it.
+        raw: bool,
+    },
+    /// Lints the XML in the file, or the XML of the camera if `file` is `None`.
+    Validate { file: Option<PathBuf> },
+}
+
+impl Command {
+    pub(super) fn parse(args: &mut Args) -> Result<Self> {
+        let command = args.positional()?;
+        Ok(match command.as_str() {
+            "entries" => Self::Entries,
+            "dump" => {
+                let entry = match args.option(&["--entry"])? {
+                    Some(entry) => Some(
+                        entry
+                            .parse()
+                            .map_err(|_| format!("`{}` is not a valid entry index", entry))?,
+                    ),
+                    None => None,
+                };
+                Self::Dump {
+                    entry,
+                    out: args.option(&["-o", "--out"])?.map(PathBuf::from),
+                    raw: args.flag(&["--raw"]),
+                }
+            }
+            "validate" => Self::Validate {
+                file: args.positional().ok().map(PathBuf::from),
+            },
+            _ => {
+                return Err(format!("unknown xml command `{}`\n\n{}", command, USAGE).into());
+            }
+        })
+    }
+
+    /// Returns `true` if the command reads the camera.
+    pub(super) fn needs_camera(&self) -> bool {
+        !matches!(self, Self::Validate { file: Some(_) })
+    }
+
+    pub(super) fn run(self, camera: Option<&mut U3vCamera>) -> Result<()> {
+        match (self, camera) {
+            (Self::Validate { file: Some(file) }, _) => validate_file(&file),
+            (Self::Entries, Some(camera)) => entries(&mut camera.ctrl),
+            (Self::Dump { entry, out, raw }, Some(camera)) => {
+                let stem = camera.info().serial_number.clone();
+                dump(&mut camera.ctrl, entry, out, raw, &stem)
+            }
+            (Self::Validate { file: None }, Some(camera)) => validate(&camera.ctrl.genapi()?),
+            (_, None) => unreachable!("the command requires a camera"),
+        }
+    }
+}
+
+/// Properties of a manifest entry.
+struct Entry {
+    index: usize,
+    entry: ManifestEntry,
+    file_type: GenICamFileType,
+    /// `(major, minor, subminor)` version of the file.
+    version: (u64, u64, u64),
+    compression: CompressionType,
+    address: u64,
+    size: usize,
+}
+
+impl Entry {
+    fn read_all(ctrl: &mut ControlHandle) -> Result<Vec<Self>> {
+        let table = ctrl.manifest_table()?;
+        let mut entries = vec![];
+        for (index, entry) in table.entries(ctrl)?.enumerate() {
+            let file_info = entry.file_info(ctrl)?;
+            let version = entry.genicam_file_version(ctrl)?;
+            entries.push(Self {
+                index,
+                entry,
+                file_type: file_info.file_type()?,
+                version: (version.major, version.minor, version.patch),
+                compression: file_info.compression_type()?,
+                address: entry.file_address(ctrl)?,
+                size: usize::try_from(entry.file_size(ctrl)?)?,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Reads the file and verifies its hash if the entry has it.
+    fn read_file(&self, ctrl: &mut ControlHandle) -> Result<Vec<u8>> {
+        // Reading the whole file extends the internal buffer, so restore it after reading.
+        let capacity = ctrl.buffer_capacity();
+        let mut file = vec![0; self.size];
+        let res = ctrl.read(self.address, &mut file);
+        ctrl.resize_buffer(capacity);
+        res?;
+
+        if let Some(hash) = self.entry.sha1_hash(ctrl)? {
+            if sha1::Sha1::digest(&file).as_slice() != hash {
+                return Err(format!(
+                    "sha1 hash of the file doesn't match the hash of entry {}",
+                    self.index
+                )
+                .into());
+            }
+        }
+        Ok(file)
+    }
+}
+
+fn entries(ctrl: &mut ControlHandle) -> Result<()> {
+    println!(
+        "{:<5} {:<10} {:<8} {:<12} {:<10} ADDRESS",
+        "INDEX", "TYPE", "VERSION", "COMPRESSION", "SIZE"
+    );
+    for entry in Entry::read_all(ctrl)? {
+        // Debug output of enums ignores width, so format them beforehand.
+        let file_type = format!("{:?}", entry.file_type);
+        let (major, minor, subminor) = entry.version;
+        let version = format!("{}.{}.{}", major, minor, subminor);
+        let compression = format!("{:?}", entry.compression);
+        println!(
+            "{:<5} {:<10} {:<8} {:<12} {:<10} {:#x}",
+            entry.index, file_type, version, compression, entry.size, entry.address
+        );
+    }
+    Ok(())
+}
+
+/// Writes the file of the entry into `out`, or `<stem>.xml` (`<stem>.zip` if the raw file is
+/// compressed) if `out` is `None`.
+fn dump(
+    ctrl: &mut ControlHandle,
+    index: Option<usize>,
+    out: Option<PathBuf>,
+    raw: bool,
+    stem: &str,
+) -> Result<()> {
+    let entries = Entry::read_all(ctrl)?;
+    let entry = match index {
+        Some(index) => entries
+            .get(index)
+            .ok_or_else(|| format!("entry {} is not found", index))?,
+        // Use the newest one if there are more than one device XMLs, as `GenApi` context does.
+        None => entries
+            .iter()
+            .filter(|entry| entry.file_type == GenICamFileType::DeviceXml)
+            .max_by_key(|entry| entry.version)
+            .ok_or("the camera has no device XML")?,
+    };
+
+    let file = entry.read_file(ctrl)?;
+    let is_zip = raw && CompressionType::detect(&file) == CompressionType::Zip;
+    let out = out.unwrap_or_else(|| {
+        let ext = if is_zip { "zip" } else { "xml" };
+        PathBuf::from(format!("{}.{}", stem, ext))
+    });
+    if raw {
+        fs::write(&out, file)?;
+    } else {
+        fs::write(&out, genapi::decode_xml(file, entry.compression)?)?;
+    }
+    println!("{}", out.display());
+    Ok(())
+}
+
+/// Lints the XML in the file, the file may be a ZIP archive as stored in the camera.
+fn validate_file(path: &Path) -> Result<()> {
+    let file = fs::read(path)?;
+    let compression = CompressionType::detect(&file);
+    validate(&genapi::decode_xml(file, compression)?)
+}
+
+fn validate(xml: &str) -> Result<()> {
+    let lints = lint::lint_xml(&xml)?;
+    for lint in &lints {
+        println!("{}: {}", lint.severity(), lint);
+    }
+
+    let num_errors = lints
+        .iter()
+        .filter(|lint| lint.severity() == Severity::Error)
+        .count();
+    if num_errors == 0 {
+        println!("{} problems found, no errors", lints.len());
+        Ok(())
+    } else {
+        Err(format!("{} problems found, {} errors", lints.len(), num_errors).into())
+    }
+}
@@ -10,7 +10,7 @@
     clippy::cast_possible_truncation
 )]
 
-#[cfg(feature = "libusb")]
+#[cfg(any(feature = "libusb", feature = "nusb"))]
 pub mod u3v;
 
 //// TODO: finish implementation.
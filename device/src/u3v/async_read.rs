@@ -18,7 +18,9 @@ use std::{
 };
 
 use super::{
-    channel::ReceiveIfaceInfo, device::RusbDeviceHandle, LibUsbError, ReceiveChannel, Result,
+    descriptor::ReceiveIfaceInfo,
+    device::{LibUsbDeviceHandle, RusbDeviceHandle},
+    LibUsbError, ReceiveChannel, Result,
 };
 use rusb::UsbContext;
 
@@ -34,7 +36,7 @@ impl<'a> AsyncPool<'a> {
     #[doc(hidden)]
     pub fn new(channel: &'a ReceiveChannel) -> Self {
         let iface_info = channel.iface_info.clone();
-        let handle = get_handle(channel);
+        let handle = get_handle(&channel.device_handle);
         Self {
             handle,
             iface_info,
@@ -43,12 +45,16 @@ impl<'a> AsyncPool<'a> {
     }
 
     #[doc(hidden)]
-    pub fn submit(&mut self, buf: &mut [u8]) -> Result<()> {
+    pub fn submit(&mut self, buf: &'a mut [u8]) -> Result<()> {
         // Safety: If transfer is submitted, it is pushed onto `pending` where it will be
         // dropped before `device` is freed.
         unsafe {
-            let mut transfer =
-                AsyncTransfer::new_bulk(self.handle.as_raw(), self.iface_info.bulk_in_ep, buf);
+            let mut transfer = AsyncTransfer::new_bulk(
+                self.handle.as_raw(),
+                self.iface_info.bulk_in_ep,
+                buf.as_mut_ptr(),
+                buf.len(),
+            );
             transfer.submit()?;
             self.pending.push_back(transfer);
             Ok(())
@@ -102,16 +108,96 @@ impl<'a> Drop for AsyncPool<'a> {
     }
 }
 
+/// Reads from the bulk in endpoint through the async API.
+///
+/// See [`bulk_transfer`] for the behavior on timeout.
+pub(super) fn read_bulk(
+    device_handle: &LibUsbDeviceHandle,
+    endpoint: u8,
+    buf: &mut [u8],
+    timeout: Duration,
+) -> Result<usize> {
+    // Safety: `buf` is valid and exclusively borrowed until the transfer completes.
+    unsafe {
+        bulk_transfer(
+            device_handle,
+            endpoint,
+            buf.as_mut_ptr(),
+            buf.len(),
+            timeout,
+        )
+    }
+}
+
+/// Writes to the bulk out endpoint through the async API.
+///
+/// See [`bulk_transfer`] for the behavior on timeout.
+pub(super) fn write_bulk(
+    device_handle: &LibUsbDeviceHandle,
+    endpoint: u8,
+    buf: &[u8],
+    timeout: Duration,
+) -> Result<usize> {
+    // Safety: libusb never writes to the buffer of an out transfer, and `buf` is valid until the
+    // transfer completes.
+    unsafe {
+        bulk_transfer(
+            device_handle,
+            endpoint,
+            buf.as_ptr() as *mut u8,
+            buf.len(),
+            timeout,
+        )
+    }
+}
+
+/// Submits a single bulk transfer and waits for its completion.
+///
+/// If the transfer doesn't complete within `timeout`, the transfer is cancelled and
+/// `LibUsbError::Timeout` is returned. This function doesn't return until libusb acknowledges
+/// the cancellation, so the buffer is never accessed after return and a late response of the
+/// cancelled transfer is never mixed into the next transfer.
+///
+/// Invariant: Caller must ensure `buf` is valid for `len` bytes until this function returns.
+unsafe fn bulk_transfer(
+    device_handle: &LibUsbDeviceHandle,
+    endpoint: u8,
+    buf: *mut u8,
+    len: usize,
+    timeout: Duration,
+) -> Result<usize> {
+    let handle = get_handle(device_handle);
+    let mut transfer = AsyncTransfer::new_bulk(handle.as_raw(), endpoint, buf, len);
+    transfer.submit()?;
+
+    if !poll_completed(handle.context(), timeout, transfer.completed_flag()).unwrap_or(false) {
+        transfer.cancel();
+        // The transfer must not be freed while it's pending.
+        while !poll_completed(
+            handle.context(),
+            Duration::from_secs(1),
+            transfer.completed_flag(),
+        )
+        .unwrap_or(false)
+        {}
+    }
+
+    transfer.handle_completed()
+}
+
 struct AsyncTransfer {
     ptr: NonNull<libusb1_sys::libusb_transfer>,
 }
 
 impl AsyncTransfer {
     /// Invariant: Caller must ensure `device` outlives this transfer.
+    /// Invariant: Caller must ensure `buffer` is valid for `length` bytes while the transfer
+    /// is pending.
     unsafe fn new_bulk(
         device: *mut libusb1_sys::libusb_device_handle,
         endpoint: u8,
-        buffer: &mut [u8],
+        buffer: *mut u8,
+        length: usize,
     ) -> Self {
         // non-isochronous endpoints (e.g. control, bulk, interrupt) specify a value of 0
         // This is step 1 of async API
@@ -120,13 +206,13 @@ impl AsyncTransfer {
 
         let user_data = Box::into_raw(Box::new(AtomicBool::new(false))).cast::<libc::c_void>();
 
-        let length = buffer.len() as libc::c_int;
+        let length = length as libc::c_int;
 
         libusb1_sys::libusb_fill_bulk_transfer(
             ptr.as_ptr(),
             device,
             endpoint,
-            buffer.as_ptr() as *mut u8,
+            buffer,
             length,
             Self::transfer_cb,
             user_data,
@@ -291,14 +377,14 @@ cfg_if::cfg_if! {
             }
         }
 
-        fn get_handle(channel: &ReceiveChannel) -> AsyncHandle {
-            AsyncHandle(channel.device_handle.handle.lock().unwrap())
+        fn get_handle(device_handle: &LibUsbDeviceHandle) -> AsyncHandle {
+            AsyncHandle(device_handle.handle.lock().unwrap())
         }
     } else {
-        type AsyncHandle<'a> = &'a RusbDeviceHandle;
+        type AsyncHandle<'a> = std::sync::RwLockReadGuard<'a, RusbDeviceHandle>;
 
-        fn get_handle(channel: &ReceiveChannel) -> AsyncHandle {
-            &channel.device_handle
+        fn get_handle(device_handle: &LibUsbDeviceHandle) -> AsyncHandle<'_> {
+            device_handle.read()
         }
     }
 }
@@ -6,19 +6,30 @@ use std::time;
 
 use crate::u3v::Result;
 
-use super::device::LibUsbDeviceHandle;
+use super::{
+    async_read,
+    descriptor::{ControlIfaceInfo, ReceiveIfaceInfo},
+    device::LibUsbDeviceHandle,
+};
 
 pub struct ControlChannel {
     pub(super) device_handle: LibUsbDeviceHandle,
     pub iface_info: ControlIfaceInfo,
     pub is_opened: bool,
+    /// If `true`, a kernel driver bound to the interface is detached when the channel is
+    /// opened, and reattached when the channel is closed. Defaults to `true`.
+    pub detach_kernel_driver: bool,
+    kernel_driver_detached: bool,
 }
 
 impl ControlChannel {
     pub fn open(&mut self) -> Result<()> {
         if !self.is_opened() {
-            self.device_handle
-                .claim_interface(self.iface_info.iface_number)?;
+            self.kernel_driver_detached = claim_interface(
+                &mut self.device_handle,
+                self.iface_info.iface_number,
+                self.detach_kernel_driver,
+            )?;
             self.is_opened = true;
         }
 
@@ -30,6 +41,7 @@ impl ControlChannel {
             self.device_handle
                 .release_interface(self.iface_info.iface_number)?;
             self.is_opened = false;
+            self.reattach_kernel_driver()?;
         }
 
         Ok(())
@@ -40,16 +52,28 @@ impl ControlChannel {
         self.is_opened
     }
 
+    /// Sends `buf` to the device.
+    ///
+    /// The transfer is cancelled if it doesn't complete within `timeout`.
     pub fn send(&self, buf: &[u8], timeout: time::Duration) -> Result<usize> {
-        Ok(self
-            .device_handle
-            .write_bulk(self.iface_info.bulk_out_ep, buf, timeout)?)
+        async_read::write_bulk(
+            &self.device_handle,
+            self.iface_info.bulk_out_ep,
+            buf,
+            timeout,
+        )
     }
 
+    /// Receives data from the device into `buf`.
+    ///
+    /// The transfer is cancelled if it doesn't complete within `timeout`.
     pub fn recv(&self, buf: &mut [u8], timeout: time::Duration) -> Result<usize> {
-        Ok(self
-            .device_handle
-            .read_bulk(self.iface_info.bulk_in_ep, buf, timeout)?)
+        async_read::read_bulk(
+            &self.device_handle,
+            self.iface_info.bulk_in_ep,
+            buf,
+            timeout,
+        )
     }
 
     pub fn set_halt(&self, timeout: time::Duration) -> Result<()> {
@@ -65,11 +89,21 @@ impl ControlChannel {
         Ok(())
     }
 
+    fn reattach_kernel_driver(&mut self) -> Result<()> {
+        if std::mem::take(&mut self.kernel_driver_detached) {
+            attach_kernel_driver(&mut self.device_handle, self.iface_info.iface_number)?;
+        }
+
+        Ok(())
+    }
+
     pub(super) fn new(device_handle: LibUsbDeviceHandle, iface_info: ControlIfaceInfo) -> Self {
         Self {
             device_handle,
             iface_info,
             is_opened: false,
+            detach_kernel_driver: true,
+            kernel_driver_detached: false,
         }
     }
 }
@@ -78,13 +112,20 @@ pub struct ReceiveChannel {
     pub(super) device_handle: LibUsbDeviceHandle,
     pub iface_info: ReceiveIfaceInfo,
     pub is_opened: bool,
+    /// If `true`, a kernel driver bound to the interface is detached when the channel is
+    /// opened, and reattached when the channel is closed. Defaults to `true`.
+    pub detach_kernel_driver: bool,
+    kernel_driver_detached: bool,
 }
 
 impl ReceiveChannel {
     pub fn open(&mut self) -> Result<()> {
         if !self.is_opened() {
-            self.device_handle
-                .claim_interface(self.iface_info.iface_number)?;
+            self.kernel_driver_detached = claim_interface(
+                &mut self.device_handle,
+                self.iface_info.iface_number,
+                self.detach_kernel_driver,
+            )?;
             self.is_opened = true;
         }
 
@@ -98,6 +139,14 @@ impl ReceiveChannel {
         }
 
         self.is_opened = false;
+        self.reattach_kernel_driver()
+    }
+
+    fn reattach_kernel_driver(&mut self) -> Result<()> {
+        if std::mem::take(&mut self.kernel_driver_detached) {
+            attach_kernel_driver(&mut self.device_handle, self.iface_info.iface_number)?;
+        }
+
         Ok(())
     }
 
@@ -128,21 +177,59 @@ impl ReceiveChannel {
             device_handle,
             iface_info,
             is_opened: false,
+            detach_kernel_driver: true,
+            kernel_driver_detached: false,
         }
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct ControlIfaceInfo {
-    pub iface_number: u8,
-    pub bulk_in_ep: u8,
-    pub bulk_out_ep: u8,
+/// Claims the interface.
+///
+/// If a kernel driver is bound to the interface, the driver is detached when `detach` is `true`,
+/// otherwise [`Error::KernelDriverActive`] is returned. Returns `true` if the driver is detached.
+#[cfg(not(target_os = "windows"))]
+fn claim_interface(handle: &mut LibUsbDeviceHandle, iface: u8, detach: bool) -> Result<bool> {
+    use crate::u3v::Error;
+
+    let detached = match handle.kernel_driver_active(iface) {
+        Ok(true) if detach => {
+            handle.detach_kernel_driver(iface).map_err(|e| match e {
+                rusb::Error::Access => Error::KernelDriverActive(iface),
+                e => e.into(),
+            })?;
+            true
+        }
+        Ok(true) => return Err(Error::KernelDriverActive(iface)),
+        // Detection is not supported on some platforms, e.g. macOS with old libusb.
+        Ok(false) | Err(rusb::Error::NotSupported) => false,
+        Err(e) => return Err(e.into()),
+    };
+
+    if let Err(e) = handle.claim_interface(iface) {
+        if detached {
+            handle.attach_kernel_driver(iface).ok();
+        }
+        return Err(e.into());
+    }
+
+    Ok(detached)
+}
+
+/// Windows doesn't have kernel drivers which can be detached, so just claims the interface.
+#[cfg(target_os = "windows")]
+fn claim_interface(handle: &mut LibUsbDeviceHandle, iface: u8, _detach: bool) -> Result<bool> {
+    handle.claim_interface(iface)?;
+    Ok(false)
 }
 
-#[derive(Clone, Debug)]
-pub struct ReceiveIfaceInfo {
-    pub iface_number: u8,
-    pub bulk_in_ep: u8,
+#[cfg(not(target_os = "windows"))]
+fn attach_kernel_driver(handle: &mut LibUsbDeviceHandle, iface: u8) -> Result<()> {
+    Ok(handle.attach_kernel_driver(iface)?)
+}
+
+#[cfg(target_os = "windows")]
+fn attach_kernel_driver(_handle: &mut LibUsbDeviceHandle, _iface: u8) -> Result<()> {
+    Ok(())
 }
 
 fn set_halt(
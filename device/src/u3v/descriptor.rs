@@ -0,0 +1,281 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains U3V specific descriptors and interface information which are shared by
+//! all USB backends.
+
+use cameleon_impl::bytes_io::ReadBytes;
+use semver::Version;
+
+use crate::u3v::{BusSpeed, DeviceInfo, Error, Result, UsbTopology};
+
+pub(super) const MISCELLANEOUS_CLASS: u8 = 0xEF;
+
+pub(super) const DEVICE_SUBCLASS: u8 = 0x02;
+pub(super) const DEVICE_PROTOCOL: u8 = 0x01;
+
+const IAD_DESC_TYPE: u8 = 0x0B;
+const IAD_FUNCTION_PROTOCOL: u8 = 0x00;
+
+pub(super) const USB3V_SUBCLASS: u8 = 0x05;
+
+/// Interface Association Descriptor.
+#[allow(unused)]
+pub(super) struct Iad {
+    length: u8,
+    descriptor_type: u8,
+    pub(super) first_interface: u8,
+    interface_count: u8,
+    function_class: u8,
+    function_subclass: u8,
+    function_protocol: u8,
+    function: u8,
+}
+
+impl Iad {
+    pub(super) fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut read = 0;
+        let len = bytes.len();
+
+        while read < len {
+            let desc_length = bytes[read];
+            if desc_length == 0 {
+                break;
+            } else if desc_length == 1 {
+                read += desc_length as usize;
+                continue;
+            }
+
+            let descriptor_type = bytes[read + 1];
+            if descriptor_type != IAD_DESC_TYPE {
+                read += desc_length as usize;
+                continue;
+            }
+
+            let first_interface = bytes[read + 2];
+            let interface_count = bytes[read + 3];
+            let function_class = bytes[read + 4];
+            let function_subclass = bytes[read + 5];
+            let function_protocol = bytes[read + 6];
+            let function = bytes[read + 7];
+            return Some(Self {
+                length: desc_length,
+                descriptor_type,
+                first_interface,
+                interface_count,
+                function_class,
+                function_subclass,
+                function_protocol,
+                function,
+            });
+        }
+
+        None
+    }
+
+    /// Returns `Some` only if the bytes contain an IAD of U3V function.
+    pub(super) fn u3v_from_bytes(bytes: &[u8]) -> Option<Self> {
+        Self::from_bytes(bytes).filter(Self::is_u3v)
+    }
+
+    fn is_u3v(&self) -> bool {
+        self.function_class == MISCELLANEOUS_CLASS
+            && self.function_subclass == USB3V_SUBCLASS
+            && self.function_protocol == IAD_FUNCTION_PROTOCOL
+    }
+}
+
+pub(super) struct DeviceInfoDescriptor {
+    #[allow(unused)]
+    length: u8,
+    #[allow(unused)]
+    descriptor_type: u8,
+    #[allow(unused)]
+    descriptor_subtype: u8,
+    gencp_version_major: u16,
+    gencp_version_minor: u16,
+    u3v_version_major: u16,
+    u3v_version_minor: u16,
+    guid_idx: u8,
+    vendor_name_idx: u8,
+    model_name_idx: u8,
+    family_name_idx: u8,
+    device_version_idx: u8,
+    manufacturer_info_idx: u8,
+    serial_number_idx: u8,
+    user_defined_name_idx: u8,
+    supported_speed_mask: u8,
+}
+
+impl DeviceInfoDescriptor {
+    const MINIMUM_DESC_LENGTH: u8 = 20;
+    pub(super) const DESCRIPTOR_TYPE: u8 = 0x24;
+    const DESCRIPTOR_SUBTYPE: u8 = 0x1;
+
+    pub(super) fn from_bytes(mut bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < Self::MINIMUM_DESC_LENGTH as usize {
+            return Err(Error::InvalidDevice);
+        }
+
+        let length: u8 = bytes.read_bytes_le()?;
+        let descriptor_type = bytes.read_bytes_le()?;
+        let descriptor_subtype = bytes.read_bytes_le()?;
+
+        if length < Self::MINIMUM_DESC_LENGTH
+            || descriptor_type != Self::DESCRIPTOR_TYPE
+            || descriptor_subtype != Self::DESCRIPTOR_SUBTYPE
+        {
+            return Err(Error::InvalidDevice);
+        }
+
+        let gencp_version_minor = bytes.read_bytes_le()?;
+        let gencp_version_major = bytes.read_bytes_le()?;
+        let u3v_version_minor = bytes.read_bytes_le()?;
+        let u3v_version_major = bytes.read_bytes_le()?;
+        let guid_idx = bytes.read_bytes_le()?;
+        let vendor_name_idx = bytes.read_bytes_le()?;
+        let model_name_idx = bytes.read_bytes_le()?;
+        let family_name_idx = bytes.read_bytes_le()?;
+        let device_version_idx = bytes.read_bytes_le()?;
+        let manufacturer_info_idx = bytes.read_bytes_le()?;
+        let serial_number_idx = bytes.read_bytes_le()?;
+        let user_defined_name_idx = bytes.read_bytes_le()?;
+        let supported_speed_mask = bytes.read_bytes_le()?;
+
+        Ok(Self {
+            length,
+            descriptor_type,
+            descriptor_subtype,
+            gencp_version_major,
+            gencp_version_minor,
+            u3v_version_major,
+            u3v_version_minor,
+            guid_idx,
+            vendor_name_idx,
+            model_name_idx,
+            family_name_idx,
+            device_version_idx,
+            manufacturer_info_idx,
+            serial_number_idx,
+            user_defined_name_idx,
+            supported_speed_mask,
+        })
+    }
+
+    /// Builds [`DeviceInfo`] from the descriptor.
+    ///
+    /// `read_string` reads the string descriptor of the given index from the device.
+    pub(super) fn interpret(
+        &self,
+        mut read_string: impl FnMut(u8) -> Result<String>,
+        topology: UsbTopology,
+    ) -> Result<DeviceInfo> {
+        let gencp_version = Version::new(
+            self.gencp_version_major.into(),
+            self.gencp_version_minor.into(),
+            0,
+        );
+
+        let u3v_version = Version::new(
+            self.u3v_version_major.into(),
+            self.u3v_version_minor.into(),
+            0,
+        );
+
+        let guid = read_string(self.guid_idx)?;
+        let vendor_name = read_string(self.vendor_name_idx)?;
+        let model_name = read_string(self.model_name_idx)?;
+        let family_name = if self.family_name_idx == 0 {
+            None
+        } else {
+            Some(read_string(self.family_name_idx)?)
+        };
+
+        let device_version = read_string(self.device_version_idx)?;
+        let manufacturer_info = read_string(self.manufacturer_info_idx)?;
+        let serial_number = read_string(self.serial_number_idx)?;
+        let user_defined_name = if self.user_defined_name_idx == 0 {
+            None
+        } else {
+            Some(read_string(self.user_defined_name_idx)?)
+        };
+        let supported_speed = if self.supported_speed_mask >> 4_i32 & 0b1 == 1 {
+            BusSpeed::SuperSpeedPlus
+        } else if self.supported_speed_mask >> 3_i32 & 0b1 == 1 {
+            BusSpeed::SuperSpeed
+        } else if self.supported_speed_mask >> 2_i32 & 0b1 == 1 {
+            BusSpeed::HighSpeed
+        } else if self.supported_speed_mask >> 1_i32 & 0b1 == 1 {
+            BusSpeed::FullSpeed
+        } else if self.supported_speed_mask & 0b1 == 1 {
+            BusSpeed::LowSpeed
+        } else {
+            return Err(Error::InvalidDevice);
+        };
+
+        Ok(DeviceInfo {
+            gencp_version,
+            u3v_version,
+            guid,
+            vendor_name,
+            model_name,
+            family_name,
+            device_version,
+            manufacturer_info,
+            serial_number,
+            user_defined_name,
+            supported_speed,
+            topology,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ControlIfaceInfo {
+    pub iface_number: u8,
+    pub bulk_in_ep: u8,
+    pub bulk_out_ep: u8,
+}
+
+impl ControlIfaceInfo {
+    pub(super) const CONTROL_IFACE_PROTOCOL: u8 = 0x00;
+}
+
+#[derive(Clone, Debug)]
+pub struct ReceiveIfaceInfo {
+    pub iface_number: u8,
+    pub bulk_in_ep: u8,
+}
+
+impl ReceiveIfaceInfo {
+    pub(super) const EVENT_IFACE_PROTOCOL: u8 = 0x01;
+    pub(super) const STREAM_IFACE_PROTOCOL: u8 = 0x02;
+}
+
+#[derive(PartialEq)]
+pub(super) enum ReceiveIfaceKind {
+    Stream,
+    Event,
+}
+
+/// Splits receive interfaces into the event interface and stream interfaces.
+///
+/// A device may have multiple stream interfaces, they are returned in the order of interface
+/// numbers.
+pub(super) fn split_receive_ifaces(
+    receive_ifaces: Vec<(ReceiveIfaceInfo, ReceiveIfaceKind)>,
+) -> Result<(Option<ReceiveIfaceInfo>, Vec<ReceiveIfaceInfo>)> {
+    let mut event_iface = None;
+    let mut stream_ifaces = vec![];
+    for (iface, kind) in receive_ifaces {
+        match kind {
+            ReceiveIfaceKind::Event if event_iface.is_none() => event_iface = Some(iface),
+            ReceiveIfaceKind::Event => return Err(Error::InvalidDevice),
+            ReceiveIfaceKind::Stream => stream_ifaces.push(iface),
+        }
+    }
+    stream_ifaces.sort_by_key(|iface| iface.iface_number);
+
+    Ok((event_iface, stream_ifaces))
+}
@@ -4,7 +4,10 @@
 
 use crate::u3v::{DeviceInfo, Result};
 
-use super::channel::{ControlChannel, ControlIfaceInfo, ReceiveChannel, ReceiveIfaceInfo};
+use super::{
+    channel::{ControlChannel, ReceiveChannel},
+    descriptor::{ControlIfaceInfo, ReceiveIfaceInfo},
+};
 
 /// Entry point to the connected device.
 /// This device itself doesn't communicate with the connected device but provide basic device
@@ -15,7 +18,7 @@ pub struct Device {
 
     ctrl_iface_info: ControlIfaceInfo,
     event_iface_info: Option<ReceiveIfaceInfo>,
-    stream_iface_info: Option<ReceiveIfaceInfo>,
+    stream_iface_infos: Vec<ReceiveIfaceInfo>,
 
     pub device_info: DeviceInfo,
 }
@@ -40,8 +43,9 @@ impl Device {
         }
     }
 
+    /// Returns the channel of the first stream interface.
     pub fn stream_channel(&self) -> Result<Option<ReceiveChannel>> {
-        match &self.stream_iface_info {
+        match self.stream_iface_infos.first() {
             Some(iface_info) => {
                 let device_handle = self.device.open()?;
                 Ok(Some(ReceiveChannel::new(device_handle, iface_info.clone())))
@@ -50,6 +54,19 @@ impl Device {
         }
     }
 
+    /// Returns channels of all stream interfaces.
+    ///
+    /// The `n`th channel corresponds to the `n`th stream channel of the device.
+    pub fn stream_channels(&self) -> Result<Vec<ReceiveChannel>> {
+        self.stream_iface_infos
+            .iter()
+            .map(|iface_info| {
+                let device_handle = self.device.open()?;
+                Ok(ReceiveChannel::new(device_handle, iface_info.clone()))
+            })
+            .collect()
+    }
+
     #[must_use]
     pub fn device_info(&self) -> &DeviceInfo {
         &self.device_info
@@ -57,18 +74,19 @@ impl Device {
 
     pub(super) fn new(
         device: RusbDevice,
+        adopted_handle: Option<RusbDeviceHandle>,
         ctrl_iface_info: ControlIfaceInfo,
         event_iface_info: Option<ReceiveIfaceInfo>,
-        stream_iface_info: Option<ReceiveIfaceInfo>,
+        stream_iface_infos: Vec<ReceiveIfaceInfo>,
         device_info: DeviceInfo,
     ) -> Self {
-        let device = get_device(device);
+        let device = get_device(device, adopted_handle);
 
         let device = Self {
             device,
             ctrl_iface_info,
             event_iface_info,
-            stream_iface_info,
+            stream_iface_infos,
             device_info,
         };
 
@@ -87,8 +105,8 @@ impl Device {
     }
 }
 
-pub(super) type RusbDevice = rusb::Device<rusb::GlobalContext>;
-pub(super) type RusbDeviceHandle = rusb::DeviceHandle<rusb::GlobalContext>;
+pub(super) type RusbDevice = rusb::Device<rusb::Context>;
+pub(super) type RusbDeviceHandle = rusb::DeviceHandle<rusb::Context>;
 
 cfg_if::cfg_if! {
     if #[cfg(target_os = "windows")] {
@@ -105,10 +123,10 @@ cfg_if::cfg_if! {
                 Ok(self.handle.clone())
             }
 
-            fn new(device: RusbDevice) -> Self {
+            fn new(device: RusbDevice, adopted_handle: Option<RusbDeviceHandle>) -> Self {
                 let handle = LibUsbDeviceHandle {
                     device: Arc::new(Mutex::new(device)),
-                    handle: Arc::new(Mutex::new(None)),
+                    handle: Arc::new(Mutex::new(adopted_handle)),
                 };
 
                 Self { handle }
@@ -159,16 +177,6 @@ cfg_if::cfg_if! {
                 delegate!(handle, read_bulk(endpoint, buf, timeout))
             }
 
-            pub(super) fn write_bulk(
-                &self,
-                endpoint: u8,
-                buf: &[u8],
-                timeout: time::Duration,
-            ) -> Result<usize> {
-                let mut handle = self.handle.lock().unwrap();
-                delegate!(handle, write_bulk(endpoint, buf, timeout))
-            }
-
             pub(super) fn clear_halt(&mut self, endpoint: u8) -> Result<()> {
                 let mut handle = self.handle.lock().unwrap();
                 delegate!(handle, clear_halt(endpoint))
@@ -191,16 +199,97 @@ cfg_if::cfg_if! {
             }
         }
 
-        fn get_device(device:RusbDevice) -> LibUsbDevice {
-            LibUsbDevice::new(device)
+        fn get_device(device: RusbDevice, adopted_handle: Option<RusbDeviceHandle>) -> LibUsbDevice {
+            LibUsbDevice::new(device, adopted_handle)
         }
     } else {
-        pub(super) type LibUsbDevice = RusbDevice;
-        pub(super) type LibUsbDeviceHandle = RusbDeviceHandle;
+        use std::{
+            sync::{Arc, RwLock, RwLockReadGuard},
+            time,
+        };
+
+        pub(super) struct LibUsbDevice {
+            device: RusbDevice,
+            /// A handle opened by the caller, e.g. a handle wrapping a file descriptor on Android.
+            /// Such a device can't be opened again, so the handle is shared by all channels.
+            adopted_handle: Option<Arc<RwLock<RusbDeviceHandle>>>,
+        }
 
-        fn get_device(device: RusbDevice) -> LibUsbDevice {
-            device
+        impl LibUsbDevice {
+            pub(super) fn open(&self) -> Result<LibUsbDeviceHandle> {
+                let handle = match &self.adopted_handle {
+                    Some(handle) => handle.clone(),
+                    None => Arc::new(RwLock::new(self.device.open()?)),
+                };
+                Ok(LibUsbDeviceHandle { handle })
+            }
         }
 
+        /// A device handle of a channel, which may be shared with other channels.
+        ///
+        /// Transfers only need a shared lock, so channels sharing the handle can transfer
+        /// concurrently. Operations that change the state of the handle, e.g. claiming an
+        /// interface, need an exclusive lock.
+        pub(super) struct LibUsbDeviceHandle {
+            pub(super) handle: Arc<RwLock<RusbDeviceHandle>>,
+        }
+
+        impl LibUsbDeviceHandle {
+            pub(super) fn read(&self) -> RwLockReadGuard<'_, RusbDeviceHandle> {
+                self.handle.read().unwrap()
+            }
+
+            pub(super) fn claim_interface(&mut self, iface: u8) -> rusb::Result<()> {
+                self.handle.write().unwrap().claim_interface(iface)
+            }
+
+            pub(super) fn release_interface(&mut self, iface: u8) -> rusb::Result<()> {
+                self.handle.write().unwrap().release_interface(iface)
+            }
+
+            pub(super) fn kernel_driver_active(&self, iface: u8) -> rusb::Result<bool> {
+                self.read().kernel_driver_active(iface)
+            }
+
+            pub(super) fn detach_kernel_driver(&mut self, iface: u8) -> rusb::Result<()> {
+                self.handle.write().unwrap().detach_kernel_driver(iface)
+            }
+
+            pub(super) fn attach_kernel_driver(&mut self, iface: u8) -> rusb::Result<()> {
+                self.handle.write().unwrap().attach_kernel_driver(iface)
+            }
+
+            pub(super) fn read_bulk(
+                &self,
+                endpoint: u8,
+                buf: &mut [u8],
+                timeout: time::Duration,
+            ) -> rusb::Result<usize> {
+                self.read().read_bulk(endpoint, buf, timeout)
+            }
+
+            pub(super) fn clear_halt(&mut self, endpoint: u8) -> rusb::Result<()> {
+                self.handle.write().unwrap().clear_halt(endpoint)
+            }
+
+            pub(super) fn write_control(
+                &self,
+                request_type: u8,
+                request: u8,
+                value: u16,
+                index: u16,
+                buf: &[u8],
+                timeout: time::Duration,
+            ) -> rusb::Result<usize> {
+                self.read().write_control(request_type, request, value, index, buf, timeout)
+            }
+        }
+
+        fn get_device(device: RusbDevice, adopted_handle: Option<RusbDeviceHandle>) -> LibUsbDevice {
+            LibUsbDevice {
+                device,
+                adopted_handle: adopted_handle.map(|handle| Arc::new(RwLock::new(handle))),
+            }
+        }
     }
 }
@@ -45,6 +45,39 @@ pub struct DeviceInfo {
 
     /// Bus speed supported by the device.
     pub supported_speed: BusSpeed,
+
+    /// Physical location of the device in the USB tree.
+    pub topology: UsbTopology,
+}
+
+/// Physical location of a USB device, which identifies the device by the port it's plugged
+/// into rather than by its serial number.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct UsbTopology {
+    /// Number of the bus that the device is connected to.
+    pub bus_number: u8,
+
+    /// Address of the device on the bus, which is reassigned every time the device is
+    /// reconnected.
+    pub address: u8,
+
+    /// Port numbers from the root hub to the device.
+    /// Unlike `address`, the port chain is stable as long as the device is plugged into the same
+    /// physical port.
+    pub port_numbers: Vec<u8>,
+}
+
+impl fmt::Display for UsbTopology {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ports: Vec<String> = self.port_numbers.iter().map(ToString::to_string).collect();
+        write!(
+            f,
+            "bus {} address {} port {}",
+            self.bus_number,
+            self.address,
+            ports.join(".")
+        )
+    }
 }
 
 /// Bus speed supported by each USB device.
@@ -96,7 +129,9 @@ impl fmt::Display for DeviceInfo {
             writeln!(f, "User Defined Name: N/A")
         }?;
 
-        write!(f, "Supported Speed: {:?}", self.supported_speed)?;
+        writeln!(f, "Supported Speed: {:?}", self.supported_speed)?;
+
+        write!(f, "Topology: {}", self.topology)?;
 
         Ok(())
     }
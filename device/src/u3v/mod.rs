@@ -2,7 +2,6 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-pub mod async_read;
 pub mod protocol;
 pub mod register_map;
 pub mod prelude {
@@ -12,15 +11,37 @@ pub mod prelude {
     use super::protocol;
 }
 
-mod channel;
-mod device;
-mod device_builder;
+mod descriptor;
 mod device_info;
 
-pub use channel::{ControlChannel, ReceiveChannel};
-pub use device::Device;
-pub use device_builder::enumerate_devices;
-pub use device_info::{BusSpeed, DeviceInfo};
+pub use device_info::{BusSpeed, DeviceInfo, UsbTopology};
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "libusb")] {
+        pub mod async_read;
+
+        mod channel;
+        mod device;
+        mod device_builder;
+
+        pub use channel::{ControlChannel, ReceiveChannel};
+        pub use device::Device;
+        #[cfg(unix)]
+        pub use device_builder::open_device_with_fd;
+        pub use device_builder::{
+            adopt_device_handle, enumerate_devices, enumerate_devices_with_context,
+        };
+        /// Re-export of `rusb` to construct a `libusb` context or a device handle with the same
+        /// version of `rusb` as this crate.
+        pub use rusb;
+    } else {
+        mod nusb_backend;
+
+        pub use nusb_backend::{
+            async_read, enumerate_devices, ControlChannel, Device, ReceiveChannel,
+        };
+    }
+}
 
 use std::borrow::Cow;
 
@@ -39,9 +60,16 @@ pub enum Error {
 
     #[error("device doesn't follow the specification")]
     InvalidDevice,
+
+    /// A kernel driver is bound to the interface and it can't be detached, e.g. detaching is
+    /// disabled or the process doesn't have the permission to detach it.
+    #[error("interface {0} is used by a kernel driver which can't be detached")]
+    KernelDriverActive(u8),
 }
 
-/// Errors raised from libusb.
+/// Errors raised from the USB backend.
+///
+/// Errors from `nusb` backend are also mapped to the corresponding `libusb` error.
 #[derive(Debug, Error)]
 pub enum LibUsbError {
     #[error("input/output error")]
@@ -76,6 +104,7 @@ pub enum LibUsbError {
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+#[cfg(feature = "libusb")]
 impl From<rusb::Error> for Error {
     fn from(err: rusb::Error) -> Error {
         use LibUsbError::{
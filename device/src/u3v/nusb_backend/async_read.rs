@@ -0,0 +1,215 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+#![doc(hidden)]
+//! This module contains a pool of asynchronous bulk transfers built on `nusb` transfer queues.
+//!
+//! `nusb` transfers own their buffers, so the received data is copied to the buffers given by
+//! the caller when each transfer completes.
+
+use std::{collections::VecDeque, future::Future, time::Duration};
+
+use ::nusb::transfer::{Completion, Queue, RequestBuffer, ResponseBuffer};
+use async_std::{future, task};
+
+use crate::u3v::{LibUsbError, ReceiveChannel, Result};
+
+#[doc(hidden)]
+/// Represents a pool of asynchronous transfers, that can be polled to completion.
+pub struct AsyncPool<'a> {
+    queue: Option<Queue<RequestBuffer>>,
+    pending: VecDeque<&'a mut [u8]>,
+}
+
+impl<'a> AsyncPool<'a> {
+    #[doc(hidden)]
+    pub fn new(channel: &'a ReceiveChannel) -> Self {
+        let queue = channel
+            .interface
+            .as_ref()
+            .map(|iface| iface.bulk_in_queue(channel.iface_info.bulk_in_ep));
+        Self {
+            queue,
+            pending: VecDeque::new(),
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn submit(&mut self, buf: &'a mut [u8]) -> Result<()> {
+        let queue = self.queue.as_mut().ok_or(LibUsbError::Io)?;
+        queue.submit(RequestBuffer::new(buf.len()));
+        self.pending.push_back(buf);
+        Ok(())
+    }
+
+    #[doc(hidden)]
+    /// # Panics
+    ///
+    /// Panics if there is no pending transfer.
+    pub fn poll(&mut self, timeout: Duration) -> Result<usize> {
+        debug_assert!(!self.pending.is_empty());
+        let queue = self.queue.as_mut().unwrap();
+        let completion =
+            wait_completion(queue.next_complete(), timeout).ok_or(LibUsbError::Timeout)?;
+        let buf = self.pending.pop_front().unwrap();
+        completion.status?;
+
+        let len = completion.data.len();
+        buf[..len].copy_from_slice(&completion.data);
+        Ok(len)
+    }
+
+    #[doc(hidden)]
+    pub fn cancel_all(&mut self) {
+        if let Some(queue) = &mut self.queue {
+            queue.cancel_all();
+        }
+    }
+
+    /// Returns the number of async transfers pending.
+    #[doc(hidden)]
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns `true` if there is no pending transfer.
+    #[doc(hidden)]
+    pub fn is_empty(&self) -> bool {
+        self.pending() == 0
+    }
+}
+
+impl<'a> Drop for AsyncPool<'a> {
+    fn drop(&mut self) {
+        self.cancel_all();
+        while !self.is_empty() {
+            self.poll(Duration::from_secs(1)).ok();
+        }
+    }
+}
+
+/// Reads from the bulk in endpoint.
+///
+/// See [`complete_single`] for the behavior on timeout.
+pub(super) fn read_bulk(
+    iface: &::nusb::Interface,
+    endpoint: u8,
+    buf: &mut [u8],
+    timeout: Duration,
+) -> Result<usize> {
+    let mut queue = iface.bulk_in_queue(endpoint);
+    queue.submit(RequestBuffer::new(buf.len()));
+    let data: Vec<u8> = complete_single!(queue, timeout);
+
+    let len = data.len();
+    buf[..len].copy_from_slice(&data);
+    Ok(len)
+}
+
+/// Writes to the bulk out endpoint.
+///
+/// See [`complete_single`] for the behavior on timeout.
+pub(super) fn write_bulk(
+    iface: &::nusb::Interface,
+    endpoint: u8,
+    buf: &[u8],
+    timeout: Duration,
+) -> Result<usize> {
+    let mut queue = iface.bulk_out_queue(endpoint);
+    queue.submit(buf.to_vec());
+    let response: ResponseBuffer = complete_single!(queue, timeout);
+    Ok(response.actual_length())
+}
+
+/// Waits for the completion of the single transfer submitted to `queue`, and evaluates to its
+/// data. This is a macro because the `TransferRequest` trait of `nusb` is sealed, so the queues
+/// of in and out transfers can't be handled by a generic function.
+///
+/// If the transfer doesn't complete within `timeout`, the transfer is cancelled and
+/// `LibUsbError::Timeout` is returned. The cancellation is waited for, so a late response of the
+/// cancelled transfer is never mixed into the next transfer.
+macro_rules! complete_single {
+    ($queue:ident, $timeout:expr) => {{
+        let completion = match wait_completion($queue.next_complete(), $timeout) {
+            Some(completion) => completion,
+            None => {
+                $queue.cancel_all();
+                let completion = task::block_on($queue.next_complete());
+                if completion.status.is_err() {
+                    return Err(LibUsbError::Timeout.into());
+                }
+                completion
+            }
+        };
+
+        completion.status?;
+        completion.data
+    }};
+}
+use complete_single;
+
+/// Waits for `completion`, which is the completion of the oldest pending transfer of a queue.
+///
+/// Returns `None` if the transfer doesn't complete within `timeout`. The transfer is left
+/// pending in that case.
+fn wait_completion<T>(
+    completion: impl Future<Output = Completion<T>>,
+    timeout: Duration,
+) -> Option<Completion<T>> {
+    task::block_on(future::timeout(timeout, completion)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use ::nusb::transfer::TransferError;
+
+    use super::*;
+    use crate::u3v::Error;
+
+    #[test]
+    fn test_wait_completion() {
+        let completion = async {
+            Completion {
+                data: vec![1, 2, 3],
+                status: Ok(()),
+            }
+        };
+        let completion = wait_completion(completion, Duration::from_millis(100)).unwrap();
+        assert_eq!(completion.data, vec![1, 2, 3]);
+
+        let completion = async {
+            Completion {
+                data: Vec::<u8>::new(),
+                status: Err(TransferError::Stall),
+            }
+        };
+        let completion = wait_completion(completion, Duration::from_millis(100)).unwrap();
+        assert!(matches!(
+            Error::from(completion.status.unwrap_err()),
+            Error::LibUsb(LibUsbError::Pipe)
+        ));
+
+        let pending = std::future::pending::<Completion<Vec<u8>>>();
+        assert!(wait_completion(pending, Duration::from_millis(10)).is_none());
+    }
+
+    #[test]
+    fn test_pool_without_interface() {
+        // The pool of a closed channel has no queue.
+        let mut pool = AsyncPool {
+            queue: None,
+            pending: VecDeque::new(),
+        };
+        let mut buf = [0; 4];
+        assert!(matches!(
+            pool.submit(&mut buf),
+            Err(Error::LibUsb(LibUsbError::Io))
+        ));
+        assert!(pool.is_empty());
+
+        // Cancelling and dropping an empty pool doesn't wait for anything.
+        pool.cancel_all();
+        drop(pool);
+    }
+}
@@ -0,0 +1,257 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::time;
+
+use ::nusb::transfer::{Control, ControlType, Recipient};
+
+use crate::u3v::{
+    descriptor::{ControlIfaceInfo, ReceiveIfaceInfo},
+    LibUsbError, Result,
+};
+
+use super::{async_read, io_error};
+
+/// `errno` returned when no kernel driver is bound to the interface.
+#[cfg(target_os = "linux")]
+const ENODATA: i32 = 61;
+
+pub struct ControlChannel {
+    pub(super) device: ::nusb::Device,
+    pub(super) interface: Option<::nusb::Interface>,
+    pub iface_info: ControlIfaceInfo,
+    pub is_opened: bool,
+    /// If `true`, a kernel driver bound to the interface is detached when the channel is
+    /// opened, and reattached when the channel is closed. Defaults to `true`.
+    pub detach_kernel_driver: bool,
+    kernel_driver_detached: bool,
+}
+
+impl ControlChannel {
+    pub fn open(&mut self) -> Result<()> {
+        if !self.is_opened() {
+            let (interface, detached) = claim_interface(
+                &self.device,
+                self.iface_info.iface_number,
+                self.detach_kernel_driver,
+            )?;
+            self.interface = Some(interface);
+            self.kernel_driver_detached = detached;
+            self.is_opened = true;
+        }
+
+        Ok(())
+    }
+
+    pub fn close(&mut self) -> Result<()> {
+        if self.is_opened() {
+            // The interface is released when the last reference to it is dropped.
+            self.interface = None;
+            self.is_opened = false;
+            if std::mem::take(&mut self.kernel_driver_detached) {
+                attach_kernel_driver(&self.device, self.iface_info.iface_number)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn is_opened(&self) -> bool {
+        self.is_opened
+    }
+
+    /// Sends `buf` to the device.
+    ///
+    /// The transfer is cancelled if it doesn't complete within `timeout`.
+    pub fn send(&self, buf: &[u8], timeout: time::Duration) -> Result<usize> {
+        async_read::write_bulk(self.iface()?, self.iface_info.bulk_out_ep, buf, timeout)
+    }
+
+    /// Receives data from the device into `buf`.
+    ///
+    /// The transfer is cancelled if it doesn't complete within `timeout`.
+    pub fn recv(&self, buf: &mut [u8], timeout: time::Duration) -> Result<usize> {
+        async_read::read_bulk(self.iface()?, self.iface_info.bulk_in_ep, buf, timeout)
+    }
+
+    pub fn set_halt(&self, timeout: time::Duration) -> Result<()> {
+        set_halt(self.iface()?, self.iface_info.bulk_in_ep, timeout)?;
+        set_halt(self.iface()?, self.iface_info.bulk_out_ep, timeout)?;
+
+        Ok(())
+    }
+
+    pub fn clear_halt(&mut self) -> Result<()> {
+        let iface = self.iface()?;
+        iface
+            .clear_halt(self.iface_info.bulk_in_ep)
+            .map_err(|e| io_error(&e))?;
+        iface
+            .clear_halt(self.iface_info.bulk_out_ep)
+            .map_err(|e| io_error(&e))?;
+        Ok(())
+    }
+
+    pub(super) fn new(device: ::nusb::Device, iface_info: ControlIfaceInfo) -> Self {
+        Self {
+            device,
+            interface: None,
+            iface_info,
+            is_opened: false,
+            detach_kernel_driver: true,
+            kernel_driver_detached: false,
+        }
+    }
+
+    fn iface(&self) -> Result<&::nusb::Interface> {
+        Ok(self.interface.as_ref().ok_or(LibUsbError::Io)?)
+    }
+}
+
+pub struct ReceiveChannel {
+    pub(super) device: ::nusb::Device,
+    pub(super) interface: Option<::nusb::Interface>,
+    pub iface_info: ReceiveIfaceInfo,
+    pub is_opened: bool,
+    /// If `true`, a kernel driver bound to the interface is detached when the channel is
+    /// opened, and reattached when the channel is closed. Defaults to `true`.
+    pub detach_kernel_driver: bool,
+    kernel_driver_detached: bool,
+}
+
+impl ReceiveChannel {
+    pub fn open(&mut self) -> Result<()> {
+        if !self.is_opened() {
+            let (interface, detached) = claim_interface(
+                &self.device,
+                self.iface_info.iface_number,
+                self.detach_kernel_driver,
+            )?;
+            self.interface = Some(interface);
+            self.kernel_driver_detached = detached;
+            self.is_opened = true;
+        }
+
+        Ok(())
+    }
+
+    pub fn close(&mut self) -> Result<()> {
+        self.interface = None;
+        self.is_opened = false;
+        if std::mem::take(&mut self.kernel_driver_detached) {
+            attach_kernel_driver(&self.device, self.iface_info.iface_number)?;
+        }
+
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn is_opened(&self) -> bool {
+        self.is_opened
+    }
+
+    pub fn recv(&self, buf: &mut [u8], timeout: time::Duration) -> Result<usize> {
+        async_read::read_bulk(self.iface()?, self.iface_info.bulk_in_ep, buf, timeout)
+    }
+
+    pub fn set_halt(&self, timeout: time::Duration) -> Result<()> {
+        set_halt(self.iface()?, self.iface_info.bulk_in_ep, timeout)?;
+
+        Ok(())
+    }
+
+    pub fn clear_halt(&mut self) -> Result<()> {
+        self.iface()?
+            .clear_halt(self.iface_info.bulk_in_ep)
+            .map_err(|e| io_error(&e))?;
+        Ok(())
+    }
+
+    pub(super) fn new(device: ::nusb::Device, iface_info: ReceiveIfaceInfo) -> Self {
+        Self {
+            device,
+            interface: None,
+            iface_info,
+            is_opened: false,
+            detach_kernel_driver: true,
+            kernel_driver_detached: false,
+        }
+    }
+
+    fn iface(&self) -> Result<&::nusb::Interface> {
+        Ok(self.interface.as_ref().ok_or(LibUsbError::Io)?)
+    }
+}
+
+/// Claims the interface.
+///
+/// If a kernel driver is bound to the interface, the driver is detached when `detach` is `true`.
+/// Returns `true` along with the interface if the driver is detached.
+fn claim_interface(
+    device: &::nusb::Device,
+    iface_number: u8,
+    detach: bool,
+) -> Result<(::nusb::Interface, bool)> {
+    let detached = detach && detach_kernel_driver(device, iface_number)?;
+
+    match device.claim_interface(iface_number) {
+        Ok(iface) => Ok((iface, detached)),
+        Err(e) => {
+            if detached {
+                attach_kernel_driver(device, iface_number).ok();
+            }
+            Err(io_error(&e))
+        }
+    }
+}
+
+/// Detaches a kernel driver from the interface, returns `false` if no driver is bound.
+#[cfg(target_os = "linux")]
+fn detach_kernel_driver(device: &::nusb::Device, iface_number: u8) -> Result<bool> {
+    use crate::u3v::Error;
+    use std::io;
+
+    match device.detach_kernel_driver(iface_number) {
+        Ok(()) => Ok(true),
+        // `ENODATA` is returned if no driver is bound to the interface.
+        Err(e) if e.raw_os_error() == Some(ENODATA) => Ok(false),
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            Err(Error::KernelDriverActive(iface_number))
+        }
+        Err(e) => Err(io_error(&e)),
+    }
+}
+
+/// Kernel drivers can't be detached on the platform.
+#[cfg(not(target_os = "linux"))]
+fn detach_kernel_driver(_device: &::nusb::Device, _iface_number: u8) -> Result<bool> {
+    Ok(false)
+}
+
+#[cfg(target_os = "linux")]
+fn attach_kernel_driver(device: &::nusb::Device, iface_number: u8) -> Result<()> {
+    device
+        .attach_kernel_driver(iface_number)
+        .map_err(|e| io_error(&e))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn attach_kernel_driver(_device: &::nusb::Device, _iface_number: u8) -> Result<()> {
+    Ok(())
+}
+
+fn set_halt(iface: &::nusb::Interface, endpoint_number: u8, timeout: time::Duration) -> Result<()> {
+    let control = Control {
+        control_type: ControlType::Standard,
+        recipient: Recipient::Endpoint,
+        request: 0x03, // SET_FEATURE.
+        value: 0x00,   // ENDPOINT_HALT.
+        index: u16::from(endpoint_number),
+    };
+
+    iface.control_out_blocking(control, &[], timeout)?;
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::u3v::{
+    descriptor::{ControlIfaceInfo, ReceiveIfaceInfo},
+    DeviceInfo, Result,
+};
+
+use super::channel::{ControlChannel, ReceiveChannel};
+
+/// Entry point to the connected device.
+/// This device itself doesn't communicate with the connected device but provide basic device
+/// information and channels to communicate with the connected device. So it's valid to use
+/// provided channels even after dropping this instance.
+pub struct Device {
+    device: ::nusb::Device,
+
+    ctrl_iface_info: ControlIfaceInfo,
+    event_iface_info: Option<ReceiveIfaceInfo>,
+    stream_iface_infos: Vec<ReceiveIfaceInfo>,
+
+    pub device_info: DeviceInfo,
+}
+
+impl Device {
+    pub fn control_channel(&self) -> Result<ControlChannel> {
+        Ok(ControlChannel::new(
+            self.device.clone(),
+            self.ctrl_iface_info.clone(),
+        ))
+    }
+
+    pub fn event_channel(&self) -> Result<Option<ReceiveChannel>> {
+        Ok(self
+            .event_iface_info
+            .as_ref()
+            .map(|iface_info| ReceiveChannel::new(self.device.clone(), iface_info.clone())))
+    }
+
+    /// Returns the channel of the first stream interface.
+    pub fn stream_channel(&self) -> Result<Option<ReceiveChannel>> {
+        Ok(self
+            .stream_iface_infos
+            .first()
+            .map(|iface_info| ReceiveChannel::new(self.device.clone(), iface_info.clone())))
+    }
+
+    /// Returns channels of all stream interfaces.
+    ///
+    /// The `n`th channel corresponds to the `n`th stream channel of the device.
+    pub fn stream_channels(&self) -> Result<Vec<ReceiveChannel>> {
+        Ok(self
+            .stream_iface_infos
+            .iter()
+            .map(|iface_info| ReceiveChannel::new(self.device.clone(), iface_info.clone()))
+            .collect())
+    }
+
+    #[must_use]
+    pub fn device_info(&self) -> &DeviceInfo {
+        &self.device_info
+    }
+
+    pub(super) fn new(
+        device: ::nusb::Device,
+        ctrl_iface_info: ControlIfaceInfo,
+        event_iface_info: Option<ReceiveIfaceInfo>,
+        stream_iface_infos: Vec<ReceiveIfaceInfo>,
+        device_info: DeviceInfo,
+    ) -> Self {
+        let device = Self {
+            device,
+            ctrl_iface_info,
+            event_iface_info,
+            stream_iface_infos,
+            device_info,
+        };
+
+        log::info! {"{}: create device", device.log_name()};
+        device
+    }
+
+    fn log_name(&self) -> String {
+        format!(
+            "{}-{}-{}",
+            self.device_info.vendor_name,
+            self.device_info.model_name,
+            self.device_info.serial_number,
+        )
+    }
+}
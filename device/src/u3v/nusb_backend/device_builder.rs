@@ -0,0 +1,203 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::time::Duration;
+
+use ::nusb::{
+    descriptors::{Configuration, InterfaceAltSetting},
+    transfer::{Direction, EndpointType},
+};
+
+use crate::u3v::{
+    descriptor::{
+        split_receive_ifaces, ControlIfaceInfo, DeviceInfoDescriptor, Iad, ReceiveIfaceInfo,
+        ReceiveIfaceKind, DEVICE_PROTOCOL, DEVICE_SUBCLASS, MISCELLANEOUS_CLASS, USB3V_SUBCLASS,
+    },
+    Error, Result, UsbTopology,
+};
+
+use super::{device::Device, io_error};
+
+/// Language ID used to read string descriptors.
+const LANGUAGE_ID_US_ENGLISH: u16 = 0x0409;
+
+/// Timeout duration of reading a string descriptor.
+const STRING_DESCRIPTOR_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Enumerates all U3V devices connected to the host.
+pub fn enumerate_devices() -> Result<Vec<Device>> {
+    let device_infos = ::nusb::list_devices().map_err(|e| io_error(&e))?;
+
+    Ok(device_infos
+        .filter(|info| {
+            info.class() == MISCELLANEOUS_CLASS
+                && info.subclass() == DEVICE_SUBCLASS
+                && info.protocol() == DEVICE_PROTOCOL
+        })
+        // TODO: Log it when device is broken or invalid.
+        .filter_map(|info| build(&info).ok().flatten())
+        .collect())
+}
+
+/// Opens the device and builds [`Device`] from its descriptors.
+///
+/// Returns `Ok(None)` if the device doesn't have the U3V function.
+fn build(info: &::nusb::DeviceInfo) -> Result<Option<Device>> {
+    let device = info.open().map_err(|e| io_error(&e))?;
+
+    let (iad, config_value) = match device
+        .configurations()
+        .find_map(|config| find_u3v_iad(&config).map(|iad| (iad, config.configuration_value())))
+    {
+        Some(found) => found,
+        None => return Ok(None),
+    };
+
+    let is_configured = matches!(
+        device.active_configuration(),
+        Ok(config) if config.configuration_value() == config_value
+    );
+    if !is_configured {
+        device
+            .set_configuration(config_value)
+            .map_err(|e| io_error(&e))?;
+    }
+    let config = device
+        .active_configuration()
+        .map_err(|_| Error::InvalidDevice)?;
+
+    // Skip interfaces while control interface is appeared.
+    let mut interfaces = config
+        .interface_alt_settings()
+        .filter(|alt| alt.alternate_setting() == 0)
+        .skip_while(|alt| alt.interface_number() != iad.first_interface);
+
+    // Retrieve control interface information.
+    let ctrl_iface = interfaces.next().ok_or(Error::InvalidDevice)?;
+    let ctrl_iface_info = control_iface_info(&ctrl_iface)?;
+
+    // Retrieve device information.
+    // This information is embedded next to control interface descriptor.
+    let device_info_desc = ctrl_iface
+        .descriptors()
+        .find(|desc| desc.descriptor_type() == DeviceInfoDescriptor::DESCRIPTOR_TYPE)
+        .ok_or(Error::InvalidDevice)?;
+    let device_info_desc = DeviceInfoDescriptor::from_bytes(&device_info_desc)?;
+    let topology = UsbTopology {
+        bus_number: info.bus_number(),
+        address: info.device_address(),
+        port_numbers: port_numbers(info),
+    };
+    let device_info = device_info_desc.interpret(
+        |idx| {
+            device
+                .get_string_descriptor(idx, LANGUAGE_ID_US_ENGLISH, STRING_DESCRIPTOR_TIMEOUT)
+                .map_err(|e| io_error(&e))
+        },
+        topology,
+    )?;
+
+    // Retrieve event and stream interface information if exists.
+    let receive_ifaces = interfaces
+        .filter_map(|iface| receive_iface_info(&iface))
+        .collect();
+    let (event_iface, stream_ifaces) = split_receive_ifaces(receive_ifaces)?;
+
+    Ok(Some(Device::new(
+        device,
+        ctrl_iface_info,
+        event_iface,
+        stream_ifaces,
+        device_info,
+    )))
+}
+
+/// Finds U3V IAD from all descriptors in the configuration.
+/// Returns the port numbers from the root hub to the device.
+///
+/// `nusb` doesn't expose the port chain, so it's parsed from the sysfs name of the device, e.g.
+/// `1-2.3` for the device connected to port 3 of the hub on port 2 of bus 1.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn port_numbers(info: &::nusb::DeviceInfo) -> Vec<u8> {
+    let name = info.sysfs_path().file_name().and_then(|name| name.to_str());
+    name.and_then(|name| name.split('-').nth(1))
+        .map(|ports| {
+            ports
+                .split('.')
+                .filter_map(|port| port.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The port chain is not available on this platform.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn port_numbers(_info: &::nusb::DeviceInfo) -> Vec<u8> {
+    vec![]
+}
+
+fn find_u3v_iad(config: &Configuration) -> Option<Iad> {
+    config
+        .descriptors()
+        .find_map(|desc| Iad::u3v_from_bytes(&desc))
+}
+
+fn control_iface_info(iface: &InterfaceAltSetting) -> Result<ControlIfaceInfo> {
+    if iface.class() != MISCELLANEOUS_CLASS
+        || iface.subclass() != USB3V_SUBCLASS
+        || iface.protocol() != ControlIfaceInfo::CONTROL_IFACE_PROTOCOL
+    {
+        return Err(Error::InvalidDevice);
+    }
+
+    let eps: Vec<_> = iface.endpoints().collect();
+    if eps.len() != 2
+        || eps
+            .iter()
+            .any(|ep| ep.transfer_type() != EndpointType::Bulk)
+    {
+        return Err(Error::InvalidDevice);
+    }
+    let ep_in = eps
+        .iter()
+        .find(|ep| ep.direction() == Direction::In)
+        .ok_or(Error::InvalidDevice)?;
+    let ep_out = eps
+        .iter()
+        .find(|ep| ep.direction() == Direction::Out)
+        .ok_or(Error::InvalidDevice)?;
+
+    Ok(ControlIfaceInfo {
+        iface_number: iface.interface_number(),
+        bulk_in_ep: ep_in.address(),
+        bulk_out_ep: ep_out.address(),
+    })
+}
+
+fn receive_iface_info(iface: &InterfaceAltSetting) -> Option<(ReceiveIfaceInfo, ReceiveIfaceKind)> {
+    if iface.class() != MISCELLANEOUS_CLASS || iface.subclass() != USB3V_SUBCLASS {
+        return None;
+    }
+
+    let iface_kind = match iface.protocol() {
+        ReceiveIfaceInfo::EVENT_IFACE_PROTOCOL => ReceiveIfaceKind::Event,
+        ReceiveIfaceInfo::STREAM_IFACE_PROTOCOL => ReceiveIfaceKind::Stream,
+        _ => return None,
+    };
+
+    if iface.num_endpoints() != 1 {
+        return None;
+    }
+    let ep = iface.endpoints().next()?;
+    if ep.transfer_type() != EndpointType::Bulk || ep.direction() != Direction::In {
+        return None;
+    }
+
+    let iface_info = ReceiveIfaceInfo {
+        iface_number: iface.interface_number(),
+        bulk_in_ep: ep.address(),
+    };
+
+    Some((iface_info, iface_kind))
+}
@@ -0,0 +1,118 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains the U3V transport built on [`nusb`](::nusb), which is used instead of
+//! `libusb` when `nusb` feature is enabled and `libusb` feature is disabled.
+//!
+//! `nusb` talks to the OS directly, so no system library is required to build and run.
+//! Windows is not supported yet because `nusb` can't read arbitrary string descriptors there.
+
+pub mod async_read;
+
+mod channel;
+mod device;
+mod device_builder;
+
+pub use channel::{ControlChannel, ReceiveChannel};
+pub use device::Device;
+pub use device_builder::enumerate_devices;
+
+use std::io;
+
+use ::nusb::transfer::TransferError;
+
+use super::{Error, LibUsbError};
+
+/// Converts an error from `nusb`, which is reported as [`io::Error`].
+fn io_error(err: &io::Error) -> Error {
+    let kind = match err.kind() {
+        io::ErrorKind::PermissionDenied => LibUsbError::Access,
+        io::ErrorKind::NotFound => LibUsbError::NoDevice,
+        io::ErrorKind::TimedOut => LibUsbError::Timeout,
+        io::ErrorKind::Interrupted => LibUsbError::Interrupted,
+        io::ErrorKind::InvalidInput => LibUsbError::InvalidParam,
+        io::ErrorKind::OutOfMemory => LibUsbError::NoMem,
+        io::ErrorKind::Unsupported => LibUsbError::NotSupported,
+        _ => LibUsbError::Io,
+    };
+
+    Error::LibUsb(kind)
+}
+
+impl From<TransferError> for Error {
+    fn from(err: TransferError) -> Self {
+        let kind = match err {
+            TransferError::Cancelled => LibUsbError::Interrupted,
+            TransferError::Stall => LibUsbError::Pipe,
+            TransferError::Disconnected => LibUsbError::NoDevice,
+            TransferError::Fault => LibUsbError::Io,
+            TransferError::Unknown => LibUsbError::Other,
+        };
+
+        Error::LibUsb(kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_io_error() {
+        let kind = |kind: io::ErrorKind| match io_error(&io::Error::from(kind)) {
+            Error::LibUsb(kind) => kind,
+            err => panic!("unexpected error: {}", err),
+        };
+
+        assert!(matches!(
+            kind(io::ErrorKind::PermissionDenied),
+            LibUsbError::Access
+        ));
+        assert!(matches!(
+            kind(io::ErrorKind::NotFound),
+            LibUsbError::NoDevice
+        ));
+        assert!(matches!(
+            kind(io::ErrorKind::TimedOut),
+            LibUsbError::Timeout
+        ));
+        assert!(matches!(
+            kind(io::ErrorKind::Interrupted),
+            LibUsbError::Interrupted
+        ));
+        assert!(matches!(
+            kind(io::ErrorKind::InvalidInput),
+            LibUsbError::InvalidParam
+        ));
+        assert!(matches!(
+            kind(io::ErrorKind::OutOfMemory),
+            LibUsbError::NoMem
+        ));
+        assert!(matches!(
+            kind(io::ErrorKind::Unsupported),
+            LibUsbError::NotSupported
+        ));
+        assert!(matches!(kind(io::ErrorKind::BrokenPipe), LibUsbError::Io));
+    }
+
+    #[test]
+    fn test_transfer_error() {
+        let kind = |err: TransferError| match Error::from(err) {
+            Error::LibUsb(kind) => kind,
+            err => panic!("unexpected error: {}", err),
+        };
+
+        assert!(matches!(
+            kind(TransferError::Cancelled),
+            LibUsbError::Interrupted
+        ));
+        assert!(matches!(kind(TransferError::Stall), LibUsbError::Pipe));
+        assert!(matches!(
+            kind(TransferError::Disconnected),
+            LibUsbError::NoDevice
+        ));
+        assert!(matches!(kind(TransferError::Fault), LibUsbError::Io));
+        assert!(matches!(kind(TransferError::Unknown), LibUsbError::Other));
+    }
+}
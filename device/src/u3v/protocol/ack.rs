@@ -289,6 +289,8 @@ pub enum ScdKind {
     ReadMemStacked,
     WriteMemStacked,
     Pending,
+    /// Acknowledge of a vendor specific command with its acknowledge id.
+    Custom(u16),
 }
 
 impl ScdKind {
@@ -300,6 +302,8 @@ impl ScdKind {
             0x0805 => Ok(ScdKind::Pending),
             0x0807 => Ok(ScdKind::ReadMemStacked),
             0x0809 => Ok(ScdKind::WriteMemStacked),
+            // Ids with the most significant bit set are reserved for vendor specific commands.
+            id if id & 0x8000 != 0 => Ok(ScdKind::Custom(id)),
             _ => Err(Error::InvalidPacket(
                 format!("unknown ack command id {:#X}", id).into(),
             )),
@@ -348,6 +352,19 @@ impl<'a> ParseScd<'a> for ReadMem<'a> {
     }
 }
 
+impl<'a> ParseScd<'a> for CustomAck<'a> {
+    fn parse(buf: &'a [u8], ccd: &AckCcd) -> Result<Self> {
+        let scd_len = ccd.scd_len() as usize;
+        if buf.len() < scd_len {
+            return Err(Error::InvalidPacket(
+                "SCD length is smaller than specified length in CCD".into(),
+            ));
+        }
+        let data = &buf[..scd_len];
+        Ok(Self { data })
+    }
+}
+
 impl<'a> ParseScd<'a> for WriteMem {
     fn parse(buf: &'a [u8], _ccd: &AckCcd) -> Result<Self> {
         let mut cursor = Cursor::new(buf);
@@ -516,6 +533,21 @@ mod tests {
         assert_eq!(parsed_scd.timeout, Duration::from_millis(700));
     }
 
+    #[test]
+    fn test_custom_ack() {
+        let scd = &[0x01, 0x02, 0x03];
+        let mut raw_packet = serialize_header(0x0000, 0x8001, scd.len() as u16, 1);
+        raw_packet.extend(scd);
+
+        let ack = AckPacket::parse(&raw_packet).unwrap();
+        assert!(ack.status().is_success());
+        assert_eq!(ack.scd_kind(), ScdKind::Custom(0x8001));
+        assert_eq!(ack.request_id(), 1);
+
+        let parsed_scd = ack.scd_as::<CustomAck>().unwrap();
+        assert_eq!(parsed_scd.data, scd);
+    }
+
     #[test]
     fn test_gencp_error_status() {
         let mut code_buf = vec![0; 2];
@@ -202,6 +202,23 @@ impl<'a> WriteMem<'a> {
         self.data.len()
     }
 
+    /// Returns maximum data length that corresponding command length fit into `maximum_cmd_len`.
+    ///
+    /// Returns an error if no data fits into `maximum_cmd_len`.
+    pub fn maximum_data_length(maximum_cmd_len: usize) -> Result<u16> {
+        let cmd_header_len = CommandPacket::<WriteMem>::header_len() + 8;
+        if maximum_cmd_len <= cmd_header_len {
+            let msg = format!("cmd_len must be larger than {}", cmd_header_len);
+            return Err(Error::InvalidPacket(msg.into()));
+        }
+
+        // Address(8bytes) + data must fit into scd length field.
+        let len: u16 = (maximum_cmd_len - cmd_header_len)
+            .try_into()
+            .unwrap_or(u16::MAX);
+        Ok(std::cmp::min(len, u16::MAX - 8))
+    }
+
     /// Split into multiple [`WriteMem`] chunks so that all commands resulting from chunks fit into `cmd_len`.
     pub fn chunks(&self, cmd_len: usize) -> Result<WriteMemChunks<'a>> {
         let cmd_header_len = CommandPacket::<WriteMem>::header_len() + 8;
@@ -285,6 +302,35 @@ impl<'a> WriteMemStacked<'a> {
     }
 }
 
+/// Vendor specific command which sends raw SCD.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CustomCommand<'a> {
+    command_id: u16,
+    data: &'a [u8],
+    ack_scd_len: u16,
+}
+
+impl<'a> CustomCommand<'a> {
+    /// `command_id` must have the most significant bit set, ids in `0x0000..0x8000` are reserved
+    /// by `GenCP`.
+    ///
+    /// `maximum_ack_scd_len` is the maximum SCD length of the acknowledge the host can receive.
+    pub fn new(command_id: u16, data: &'a [u8], maximum_ack_scd_len: u16) -> Result<Self> {
+        if command_id & 0x8000 == 0 {
+            return Err(Error::InvalidPacket(
+                "custom command id must be in the range of 0x8000..=0xFFFF".into(),
+            ));
+        }
+        into_scd_len(data.len())?;
+
+        Ok(Self {
+            command_id,
+            data,
+            ack_scd_len: maximum_ack_scd_len,
+        })
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct CommandCcd {
     flag: CommandFlag,
@@ -367,6 +413,8 @@ pub enum ScdKind {
     WriteMem,
     ReadMemStacked,
     WriteMemStacked,
+    /// Vendor specific command with its command id.
+    Custom(u16),
 }
 
 impl ScdKind {
@@ -376,6 +424,7 @@ impl ScdKind {
             Self::WriteMem => 0x0802,
             Self::ReadMemStacked => 0x0806,
             Self::WriteMemStacked => 0x0808,
+            Self::Custom(id) => id,
         };
 
         buf.write_bytes_le(kind_id)?;
@@ -504,6 +553,29 @@ impl<'a> CommandScd for WriteMemStacked<'a> {
     }
 }
 
+impl<'a> CommandScd for CustomCommand<'a> {
+    fn flag(&self) -> CommandFlag {
+        CommandFlag::RequestAck
+    }
+
+    fn scd_kind(&self) -> ScdKind {
+        ScdKind::Custom(self.command_id)
+    }
+
+    fn scd_len(&self) -> u16 {
+        self.data.len() as u16
+    }
+
+    fn serialize(&self, mut buf: impl Write) -> Result<()> {
+        buf.write_all(self.data)?;
+        Ok(())
+    }
+
+    fn ack_scd_len(&self) -> u16 {
+        self.ack_scd_len
+    }
+}
+
 fn into_scd_len(len: usize) -> Result<u16> {
     len.try_into()
         .map_err(|_| Error::InvalidPacket("scd length must be less than u16::MAX".into()))
@@ -612,6 +684,26 @@ mod tests {
         assert_eq!(buf, expected);
     }
 
+    #[test]
+    fn test_custom_command() {
+        let command = CustomCommand::new(0x8000, &[0x01, 0x02, 0x03], 16)
+            .unwrap()
+            .finalize(1);
+        let scd_len = 3;
+
+        assert_eq!(command.cmd_len(), (HEADER_LEN + scd_len).into());
+        assert_eq!(command.maximum_ack_len(), (HEADER_LEN + 16).into());
+
+        let mut buf = vec![];
+        command.serialize(&mut buf).unwrap();
+        let mut expected = serialize_header([0x00, 0x80], [scd_len, 0x00], [0x01, 0x00]);
+        expected.extend(vec![0x01, 0x02, 0x03]); // Data.
+
+        assert_eq!(buf, expected);
+
+        assert!(CustomCommand::new(0x0800, &[], 16).is_err());
+    }
+
     #[test]
     fn test_read_mem_chunks() {
         let read_mem = ReadMem::new(0, 128);
@@ -653,4 +745,21 @@ mod tests {
         assert_eq!(last_chunk.address, expected_addr);
         assert_eq!(last_chunk.data_len, data.len() as u16 - sent_data_len);
     }
+
+    #[test]
+    fn test_write_mem_maximum_data_length() {
+        let len = WriteMem::maximum_data_length(128).unwrap();
+        let data = vec![0; len as usize];
+        let command = WriteMem::new(0, &data).unwrap().finalize(1);
+        assert_eq!(command.cmd_len(), 128);
+
+        let len = WriteMem::maximum_data_length(1024 * 1024).unwrap();
+        let data = vec![0; len as usize];
+        assert!(WriteMem::new(0, &data).is_ok());
+
+        let header_len = CommandPacket::<WriteMem>::header_len() + 8;
+        assert_eq!(WriteMem::maximum_data_length(header_len + 1).unwrap(), 1);
+        assert!(WriteMem::maximum_data_length(header_len).is_err());
+        assert!(WriteMem::maximum_data_length(0).is_err());
+    }
 }
@@ -21,7 +21,8 @@ use crate::{
 /// # Example
 /// ```no_run
 /// use cameleon_device::u3v::protocol::stream::{Leader, PayloadType, ImageLeader,
-///                                             ImageExtendedChunkLeader, ChunkLeader};
+///                                             ImageExtendedChunkLeader, ChunkLeader,
+///                                             GenDCLeader};
 ///
 /// // Buffer for leader bytes.
 /// let mut buf = Vec::new();
@@ -47,6 +48,10 @@ use crate::{
 ///         // Try parsing specific part as Image Extended Chunk Leader.
 ///         let image_leader: ChunkLeader = leader.specific_leader_as().unwrap();
 ///     }
+///     PayloadType::GenDC => {
+///         // Try parsing specific part as GenDC Leader.
+///         let gendc_leader: GenDCLeader = leader.specific_leader_as().unwrap();
+///     }
 /// }
 /// ```
 #[derive(Debug, Clone)]
@@ -88,7 +93,8 @@ impl<'a> Leader<'a> {
     /// # Example
     /// ```no_run
     /// # use cameleon_device::u3v::protocol::stream::{Leader, PayloadType, ImageLeader,
-    ///                                             ImageExtendedChunkLeader, ChunkLeader};
+    ///                                             ImageExtendedChunkLeader, ChunkLeader,
+    ///                                             GenDCLeader};
     /// # let mut buf = Vec::new();
     /// let leader = Leader::parse(&buf).unwrap();
     /// // Parse a specific part of the leader.
@@ -106,6 +112,10 @@ impl<'a> Leader<'a> {
     ///         // Try parsing specific part as Image Extended Chunk Leader.
     ///         let image_leader: ChunkLeader = leader.specific_leader_as().unwrap();
     ///     }
+    ///     PayloadType::GenDC => {
+    ///         // Try parsing specific part as GenDC Leader.
+    ///         let gendc_leader: GenDCLeader = leader.specific_leader_as().unwrap();
+    ///     }
     /// }
     /// ```
     pub fn specific_leader_as<T: SpecificLeader>(&self) -> Result<T> {
@@ -159,6 +169,9 @@ pub enum PayloadType {
 
     /// Type representing chunk data.
     Chunk,
+
+    /// Type representing a GenDC container.
+    GenDC,
 }
 
 /// Image leader is a specific leader part of stream leader.
@@ -343,6 +356,7 @@ impl TryFrom<u16> for PayloadType {
             0x0001 => Ok(PayloadType::Image),
             0x4001 => Ok(PayloadType::ImageExtendedChunk),
             0x4000 => Ok(PayloadType::Chunk),
+            0x000B => Ok(PayloadType::GenDC),
             val => Err(Error::InvalidPacket(
                 format!("invalid value for leader payload type: {}", val).into(),
             )),
@@ -376,6 +390,32 @@ impl SpecificLeader for ChunkLeader {
     }
 }
 
+/// GenDC leader is a specific leader part of stream leader.
+///
+/// When [`Leader::payload_type`] returns [`PayloadType::GenDC`], then the leader contains
+/// [`GenDCLeader`] in a specific leader part.
+pub struct GenDCLeader {
+    timestamp: u64,
+}
+
+impl GenDCLeader {
+    /// Timestamp when the GenDC container is created.
+    /// Timestamp represents duration since the device starts running.
+    #[must_use]
+    pub fn timestamp(&self) -> time::Duration {
+        time::Duration::from_nanos(self.timestamp)
+    }
+}
+
+impl SpecificLeader for GenDCLeader {
+    fn from_bytes(buf: &[u8]) -> Result<Self> {
+        let mut cursor = Cursor::new(buf);
+        let timestamp = cursor.read_bytes_le()?;
+
+        Ok(Self { timestamp })
+    }
+}
+
 /// Trailer part of stream containing auxiliary information of payload data, which is sent after
 /// the payload data.
 #[derive(Debug, Clone)]
@@ -592,6 +632,7 @@ mod tests {
             PayloadType::Image => (0x0001, 50),
             PayloadType::ImageExtendedChunk => (0x4001, 50),
             PayloadType::Chunk => (0x4000, 20),
+            PayloadType::GenDC => (0x000B, 20),
         };
         // Leader magic.
         buf.write_bytes_le(0x4C56_3355_u32).unwrap();
@@ -613,6 +654,7 @@ mod tests {
         let mut buf = vec![];
         let trailer_size: u16 = match payload_type {
             PayloadType::Image | PayloadType::Chunk => 32,
+            PayloadType::GenDC => 28,
             PayloadType::ImageExtendedChunk => 36,
         };
 
@@ -736,6 +778,18 @@ mod tests {
         assert_eq!(image_leader.timestamp(), time::Duration::from_nanos(100));
     }
 
+    #[test]
+    fn test_parse_gendc_leader() {
+        let mut buf = generic_leader_bytes(PayloadType::GenDC);
+        // Time stamp.
+        buf.write_bytes_le(100_u64).unwrap();
+
+        let leader = Leader::parse(&buf).unwrap();
+        assert_eq!(leader.payload_type(), PayloadType::GenDC);
+        let gendc_leader: GenDCLeader = leader.specific_leader_as().unwrap();
+        assert_eq!(gendc_leader.timestamp(), time::Duration::from_nanos(100));
+    }
+
     #[test]
     fn test_parse_generic_trailer() {
         let mut buf = vec![];
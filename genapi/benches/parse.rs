@@ -0,0 +1,107 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Compares the default DOM parsing with the streaming parsing.
+//!
+//! Set `CAMELEON_GENAPI_BENCH_XML` to the path of a vendor XML to measure it instead of the
+//! generated one.
+
+use cameleon_genapi::{
+    parser::{self, ParseOptions},
+    store::{DefaultCacheStore, DefaultNodeStore, DefaultValueStore},
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/// Generates a register description which has `n` features backed by registers.
+fn generate_xml(n: usize) -> String {
+    let mut nodes = String::new();
+    nodes.push_str("<Category Name=\"Root\">\n");
+    for i in 0..n {
+        nodes.push_str(&format!("  <pFeature>Feature{}</pFeature>\n", i));
+    }
+    nodes.push_str("</Category>\n");
+
+    for i in 0..n {
+        nodes.push_str(&format!(
+            r#"<Integer Name="Feature{i}" NameSpace="Custom">
+  <ToolTip>Tooltip of feature {i}</ToolTip>
+  <Description>Description of feature {i}</Description>
+  <DisplayName>Feature {i}</DisplayName>
+  <pValue>Feature{i}Reg</pValue>
+  <Min>0</Min>
+  <Max>65535</Max>
+</Integer>
+<IntReg Name="Feature{i}Reg">
+  <Address>0x{addr:x}</Address>
+  <Length>4</Length>
+  <AccessMode>RW</AccessMode>
+  <pPort>Device</pPort>
+  <Sign>Unsigned</Sign>
+  <Endianess>LittleEndian</Endianess>
+</IntReg>
+"#,
+            i = i,
+            addr = 0x1_0000 + i * 4
+        ));
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<RegisterDescription
+  ModelName="Bench"
+  VendorName="CameleonVendor"
+  StandardNameSpace="None"
+  SchemaMajorVersion="1"
+  SchemaMinorVersion="1"
+  SchemaSubMinorVersion="0"
+  MajorVersion="1"
+  MinorVersion="0"
+  SubMinorVersion="0"
+  ProductGuid="01234567-0123-0123-0123-0123456789ab"
+  VersionGuid="76543210-3210-3210-3210-ba9876543210">
+<Port Name="Device"/>
+{}
+</RegisterDescription>
+"#,
+        nodes
+    )
+}
+
+fn parse(xml: &str, options: ParseOptions) {
+    parser::parse_with_options(
+        &xml,
+        options,
+        &mut DefaultNodeStore::new(),
+        &mut DefaultValueStore::new(),
+        &mut DefaultCacheStore::new(),
+    )
+    .unwrap();
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let xmls = match std::env::var("CAMELEON_GENAPI_BENCH_XML") {
+        Ok(path) => vec![(path.clone(), std::fs::read_to_string(path).unwrap())],
+        // About 10 MB for the largest one.
+        Err(_) => [1_000, 10_000, 30_000]
+            .iter()
+            .map(|&n| (format!("{} features", n), generate_xml(n)))
+            .collect(),
+    };
+
+    let mut group = c.benchmark_group("parse");
+    group.sample_size(10);
+    for (name, xml) in &xmls {
+        group.throughput(Throughput::Bytes(xml.len() as u64));
+        group.bench_with_input(BenchmarkId::new("dom", name), xml, |b, xml| {
+            b.iter(|| parse(xml, ParseOptions::default()))
+        });
+        group.bench_with_input(BenchmarkId::new("streaming", name), xml, |b, xml| {
+            b.iter(|| parse(xml, ParseOptions::default().streaming(true)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
@@ -8,10 +8,11 @@ use super::{
     ivalue::IValue,
     node_base::{NodeAttributeBase, NodeBase, NodeElementBase},
     store::{CacheStore, IntegerId, NodeId, NodeStore, ValueStore},
-    Device, GenApiError, GenApiResult, ValueCtxt,
+    utils, Device, GenApiError, GenApiResult, ValueCtxt,
 };
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BooleanNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -87,7 +88,9 @@ impl IBoolean for BooleanNode {
         store: &impl NodeStore,
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<()> {
-        cx.invalidate_cache_by(self.node_base().id());
+        let nid = self.node_base().id();
+        utils::verify_no_busy_command(nid, &self.p_selected, device, store, cx)?;
+        cx.invalidate_cache_by(nid);
         let value = if value { self.on_value } else { self.off_value };
         self.value.set_value(value, device, store, cx)
     }
@@ -4,6 +4,7 @@
 
 use super::{
     parser,
+    programmatic::ProgrammaticBuilder,
     store::{
         CacheSink, DefaultCacheStore, DefaultNodeStore, DefaultValueStore, NodeData, NodeId,
         ValueData, ValueId,
@@ -16,6 +17,7 @@ pub struct GenApiBuilder<T = DefaultNodeStore, U = DefaultValueStore, S = Defaul
     node_store: T,
     value_store: U,
     cache_store: S,
+    options: parser::ParseOptions,
 }
 
 pub type BuildResult<T, U, S> = parser::ParseResult<(RegisterDescription, T, ValueCtxt<U, S>)>;
@@ -27,8 +29,9 @@ impl<T, U, S> GenApiBuilder<T, U, S> {
         U: ValueStoreBuilder,
         S: CacheStoreBuilder,
     {
-        let reg_desc = parser::parse(
+        let reg_desc = parser::parse_with_options(
             xml,
+            self.options,
             &mut self.node_store,
             &mut self.value_store,
             &mut self.cache_store,
@@ -41,11 +44,73 @@ impl<T, U, S> GenApiBuilder<T, U, S> {
         ))
     }
 
+    /// Builds stores from multiple XMLs, merging nodes defined in them.
+    ///
+    /// See [`parser::parse_merged`] for the rules of merging.
+    pub fn build_merged<X: AsRef<str>>(
+        mut self,
+        xmls: &[X],
+    ) -> BuildResult<T::Store, U::Store, S::Store>
+    where
+        T: NodeStoreBuilder,
+        U: ValueStoreBuilder,
+        S: CacheStoreBuilder,
+    {
+        let reg_desc = parser::parse_merged_with_options(
+            xmls,
+            self.options,
+            &mut self.node_store,
+            &mut self.value_store,
+            &mut self.cache_store,
+        )?;
+
+        Ok((
+            reg_desc,
+            self.node_store.build(),
+            ValueCtxt::new(self.value_store.build(), self.cache_store.build()),
+        ))
+    }
+
+    /// Converts into [`ProgrammaticBuilder`] to construct nodes in Rust code without XML.
+    pub fn programmatic(
+        self,
+        model_name: impl Into<String>,
+        vendor_name: impl Into<String>,
+    ) -> ProgrammaticBuilder<T, U, S> {
+        ProgrammaticBuilder::new(
+            self.node_store,
+            self.value_store,
+            self.cache_store,
+            model_name.into(),
+            vendor_name.into(),
+        )
+    }
+
+    /// Parses XMLs in tolerant mode, which keeps non-standard elements and attributes instead of
+    /// failing on them.
+    ///
+    /// See [`parser::ParseOptions::tolerant`] for more details.
+    #[must_use]
+    pub fn tolerant(mut self) -> Self {
+        self.options = self.options.tolerant(true);
+        self
+    }
+
+    /// Parses XMLs element by element instead of building the DOM of the whole XML at once.
+    ///
+    /// See [`parser::ParseOptions::streaming`] for more details.
+    #[must_use]
+    pub fn streaming(mut self) -> Self {
+        self.options = self.options.streaming(true);
+        self
+    }
+
     pub fn no_cache(self) -> GenApiBuilder<T, U, CacheSink> {
         GenApiBuilder {
             node_store: self.node_store,
             value_store: self.value_store,
             cache_store: CacheSink::default(),
+            options: self.options,
         }
     }
 
@@ -54,6 +119,7 @@ impl<T, U, S> GenApiBuilder<T, U, S> {
             node_store,
             value_store: self.value_store,
             cache_store: self.cache_store,
+            options: self.options,
         }
     }
 
@@ -62,6 +128,7 @@ impl<T, U, S> GenApiBuilder<T, U, S> {
             node_store: self.node_store,
             value_store,
             cache_store: self.cache_store,
+            options: self.options,
         }
     }
 
@@ -70,6 +137,7 @@ impl<T, U, S> GenApiBuilder<T, U, S> {
             node_store: self.node_store,
             value_store: self.value_store,
             cache_store,
+            options: self.options,
         }
     }
 }
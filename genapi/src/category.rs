@@ -3,12 +3,15 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use super::{
-    interface::{ICategory, INode},
+    elem_type::{AccessMode, Visibility},
+    interface::{IBoolean, ICategory, ICommand, IEnumeration, IFloat, IInteger, INode, IString},
     node_base::{NodeAttributeBase, NodeBase, NodeElementBase},
-    store::{NodeId, NodeStore},
+    store::{CacheStore, NodeId, NodeStore, ValueStore},
+    Device, GenApiResult, ValueCtxt,
 };
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CategoryNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -38,3 +41,444 @@ impl ICategory for CategoryNode {
         self.p_features()
     }
 }
+
+/// A hierarchy of categories and features which is used to build parameter trees of GUI.
+///
+/// The tree starts from `Root` category in usual, every category entry has features and
+/// sub-categories listed in its `pFeature` elements as children.
+///
+/// Access modes of entries are evaluated when the tree is built, so the tree needs to be built
+/// again to reflect changes of the device state, e.g. a feature becomes writable after another
+/// feature is modified.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CategoryTree {
+    root: CategoryTreeEntry,
+}
+
+impl CategoryTree {
+    /// Build the tree starting from `Root` category.
+    ///
+    /// Returns `None` if the store has no `Root` category.
+    pub fn new<T: ValueStore, U: CacheStore>(
+        device: &mut impl Device,
+        store: &impl NodeStore,
+        cx: &mut ValueCtxt<T, U>,
+    ) -> GenApiResult<Option<Self>> {
+        match store.id_by_name("Root") {
+            Some(root) => Self::with_root(root, device, store, cx),
+            None => Ok(None),
+        }
+    }
+
+    /// Build the tree starting from the given category.
+    ///
+    /// Returns `None` if the `root` isn't a category node.
+    pub fn with_root<T: ValueStore, U: CacheStore>(
+        root: NodeId,
+        device: &mut impl Device,
+        store: &impl NodeStore,
+        cx: &mut ValueCtxt<T, U>,
+    ) -> GenApiResult<Option<Self>> {
+        if root.as_icategory_kind(store).is_none() {
+            return Ok(None);
+        }
+        let mut ancestors = vec![];
+        let root = CategoryTreeEntry::new(root, device, store, cx, &mut ancestors)?;
+        Ok(root.map(|root| Self { root }))
+    }
+
+    #[must_use]
+    pub fn root(&self) -> &CategoryTreeEntry {
+        &self.root
+    }
+
+    /// Visit all entries in the tree in depth first order.
+    ///
+    /// `f` is called with an entry and its depth, the depth of the root is `0`.
+    pub fn visit<F>(&self, mut f: F)
+    where
+        F: FnMut(&CategoryTreeEntry, usize),
+    {
+        fn visit_impl<F>(entry: &CategoryTreeEntry, depth: usize, f: &mut F)
+        where
+            F: FnMut(&CategoryTreeEntry, usize),
+        {
+            f(entry, depth);
+            for child in entry.children() {
+                visit_impl(child, depth + 1, f);
+            }
+        }
+
+        visit_impl(&self.root, 0, &mut f);
+    }
+
+    /// Returns a tree which contains only entries visible at the given `visibility` level.
+    ///
+    /// e.g. `filter_by_visibility(Visibility::Expert)` removes entries whose visibility is
+    /// `Guru` or `Invisible`. Categories that become empty are also removed except for the root.
+    #[must_use]
+    pub fn filter_by_visibility(&self, visibility: Visibility) -> Self {
+        fn filter_impl(
+            entry: &CategoryTreeEntry,
+            visibility: Visibility,
+        ) -> Option<CategoryTreeEntry> {
+            if entry.visibility > visibility {
+                return None;
+            }
+
+            let children: Vec<_> = entry
+                .children
+                .iter()
+                .filter_map(|child| filter_impl(child, visibility))
+                .collect();
+            if entry.is_category && children.is_empty() {
+                return None;
+            }
+
+            Some(CategoryTreeEntry {
+                children,
+                ..entry.clone()
+            })
+        }
+
+        let children = self
+            .root
+            .children
+            .iter()
+            .filter_map(|child| filter_impl(child, visibility))
+            .collect();
+        Self {
+            root: CategoryTreeEntry {
+                children,
+                ..self.root.clone()
+            },
+        }
+    }
+}
+
+/// An entry of [`CategoryTree`], which is either a category or a feature.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CategoryTreeEntry {
+    id: NodeId,
+    name: String,
+    display_name: Option<String>,
+    visibility: Visibility,
+    imposed_access_mode: AccessMode,
+    access_mode: Option<AccessMode>,
+    is_category: bool,
+    children: Vec<CategoryTreeEntry>,
+}
+
+impl CategoryTreeEntry {
+    fn new<T: ValueStore, U: CacheStore>(
+        id: NodeId,
+        device: &mut impl Device,
+        store: &impl NodeStore,
+        cx: &mut ValueCtxt<T, U>,
+        ancestors: &mut Vec<NodeId>,
+    ) -> GenApiResult<Option<Self>> {
+        let (node_base, name) = match (id.as_inode_kind(store), store.name_by_id(id)) {
+            (Some(node), Some(name)) => (node.node_base_precise(), name.to_string()),
+            _ => return Ok(None),
+        };
+        let display_name = node_base.display_name().map(ToString::to_string);
+        let visibility = node_base.visibility();
+        let imposed_access_mode = node_base.imposed_access_mode();
+
+        let (is_category, children) = if let Some(category) = id.as_icategory_kind(store) {
+            // Break cyclic references between categories.
+            if ancestors.contains(&id) {
+                return Ok(None);
+            }
+            ancestors.push(id);
+            let mut children = vec![];
+            for child in category.nodes(store) {
+                if let Some(child) = Self::new(*child, device, store, cx, ancestors)? {
+                    children.push(child);
+                }
+            }
+            ancestors.pop();
+            (true, children)
+        } else {
+            (false, vec![])
+        };
+        let access_mode = access_mode(id, node_base, device, store, cx)?;
+
+        Ok(Some(Self {
+            id,
+            name,
+            display_name,
+            visibility,
+            imposed_access_mode,
+            access_mode,
+            is_category,
+            children,
+        }))
+    }
+
+    #[must_use]
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns `DisplayName` of the node, or the node name if the node has no `DisplayName`.
+    #[must_use]
+    pub fn display_name(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(&self.name)
+    }
+
+    #[must_use]
+    pub fn visibility(&self) -> Visibility {
+        self.visibility
+    }
+
+    #[must_use]
+    pub fn imposed_access_mode(&self) -> AccessMode {
+        self.imposed_access_mode
+    }
+
+    /// Returns the access mode of the node evaluated when the tree is built.
+    ///
+    /// Unlike [`Self::imposed_access_mode`], this takes into account whether the node is
+    /// implemented, available and locked, and the access mode of the underlying register. Returns
+    /// `None` if the node is neither readable nor writable.
+    #[must_use]
+    pub fn access_mode(&self) -> Option<AccessMode> {
+        self.access_mode
+    }
+
+    #[must_use]
+    pub fn is_category(&self) -> bool {
+        self.is_category
+    }
+
+    /// Returns sub-categories and features of the category. Returns an empty slice if the entry
+    /// is a feature.
+    #[must_use]
+    pub fn children(&self) -> &[CategoryTreeEntry] {
+        &self.children
+    }
+}
+
+/// Evaluates the access mode of the node with the interface of the node kind.
+fn access_mode<T: ValueStore, U: CacheStore>(
+    id: NodeId,
+    node_base: NodeBase,
+    device: &mut impl Device,
+    store: &impl NodeStore,
+    cx: &mut ValueCtxt<T, U>,
+) -> GenApiResult<Option<AccessMode>> {
+    macro_rules! readable_writable {
+        ($node:expr) => {
+            (
+                $node.is_readable(device, store, cx)?,
+                $node.is_writable(device, store, cx)?,
+            )
+        };
+    }
+
+    let (readable, writable) = if let Some(node) = id.as_iinteger_kind(store) {
+        readable_writable!(node)
+    } else if let Some(node) = id.as_ifloat_kind(store) {
+        readable_writable!(node)
+    } else if let Some(node) = id.as_istring_kind(store) {
+        readable_writable!(node)
+    } else if let Some(node) = id.as_ienumeration_kind(store) {
+        readable_writable!(node)
+    } else if let Some(node) = id.as_iboolean_kind(store) {
+        readable_writable!(node)
+    } else if let Some(node) = id.as_icommand_kind(store) {
+        (false, node.is_writable(device, store, cx)?)
+    } else if id.as_icategory_kind(store).is_some() {
+        // A category has no value to be written.
+        (node_base.elem.is_readable(device, store, cx)?, false)
+    } else {
+        readable_writable!(node_base.elem)
+    };
+
+    Ok(match (readable, writable) {
+        (true, true) => Some(AccessMode::RW),
+        (true, false) => Some(AccessMode::RO),
+        (false, true) => Some(AccessMode::WO),
+        (false, false) => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        builder::GenApiBuilder,
+        store::DefaultNodeStore,
+        utils::tests::{register_description, Memory},
+    };
+
+    use super::*;
+
+    fn xml() -> String {
+        register_description(
+            r#"
+                <Category Name="Root" NameSpace="Standard">
+                    <pFeature>ImageFormatControl</pFeature>
+                    <pFeature>DebugControl</pFeature>
+                </Category>
+
+                <Category Name="ImageFormatControl" NameSpace="Standard">
+                    <DisplayName>Image Format Control</DisplayName>
+                    <pFeature>Width</pFeature>
+                    <pFeature>SensorWidth</pFeature>
+                    <pFeature>Root</pFeature>
+                </Category>
+
+                <Category Name="DebugControl" NameSpace="Custom">
+                    <Visibility>Guru</Visibility>
+                    <pFeature>DebugLevel</pFeature>
+                </Category>
+
+                <Integer Name="Width" NameSpace="Standard">
+                    <Value>640</Value>
+                </Integer>
+
+                <Integer Name="SensorWidth" NameSpace="Standard">
+                    <Visibility>Expert</Visibility>
+                    <ImposedAccessMode>RO</ImposedAccessMode>
+                    <Value>1280</Value>
+                </Integer>
+
+                <Integer Name="DebugLevel" NameSpace="Custom">
+                    <Visibility>Guru</Visibility>
+                    <Value>0</Value>
+                </Integer>
+            "#,
+        )
+    }
+
+    #[test]
+    fn test_category_tree() {
+        let (_, store, mut cx) = GenApiBuilder::<DefaultNodeStore>::default()
+            .build(&xml())
+            .unwrap();
+        let tree = CategoryTree::new(&mut Memory(vec![]), &store, &mut cx)
+            .unwrap()
+            .unwrap();
+
+        let root = tree.root();
+        assert_eq!(root.name(), "Root");
+        assert!(root.is_category());
+        assert_eq!(root.children().len(), 2);
+
+        let image_format = &root.children()[0];
+        assert_eq!(image_format.display_name(), "Image Format Control");
+        // Cyclic reference to `Root` must be ignored.
+        assert_eq!(image_format.children().len(), 2);
+
+        let sensor_width = &image_format.children()[1];
+        assert_eq!(sensor_width.name(), "SensorWidth");
+        assert_eq!(sensor_width.display_name(), "SensorWidth");
+        assert_eq!(sensor_width.visibility(), Visibility::Expert);
+        assert_eq!(sensor_width.imposed_access_mode(), AccessMode::RO);
+        assert_eq!(sensor_width.access_mode(), Some(AccessMode::RO));
+        assert!(!sensor_width.is_category());
+
+        let mut depths = vec![];
+        tree.visit(|entry, depth| depths.push((entry.name().to_string(), depth)));
+        assert_eq!(depths.len(), 6);
+        assert_eq!(depths[5], ("DebugLevel".to_string(), 2));
+    }
+
+    #[test]
+    fn test_category_tree_filter_by_visibility() {
+        let (_, store, mut cx) = GenApiBuilder::<DefaultNodeStore>::default()
+            .build(&xml())
+            .unwrap();
+        let tree = CategoryTree::new(&mut Memory(vec![]), &store, &mut cx)
+            .unwrap()
+            .unwrap();
+
+        let beginner = tree.filter_by_visibility(Visibility::Beginner);
+        assert_eq!(beginner.root().children().len(), 1);
+        assert_eq!(beginner.root().children()[0].children().len(), 1);
+
+        let expert = tree.filter_by_visibility(Visibility::Expert);
+        assert_eq!(expert.root().children().len(), 1);
+        assert_eq!(expert.root().children()[0].children().len(), 2);
+
+        let guru = tree.filter_by_visibility(Visibility::Guru);
+        assert_eq!(guru.root().children().len(), 2);
+    }
+
+    #[test]
+    fn test_category_tree_access_mode() {
+        let mut builder =
+            GenApiBuilder::<DefaultNodeStore>::default().programmatic("Model", "Vendor");
+        builder.port("Device").finish();
+        builder
+            .category("Root")
+            .feature("Width")
+            .feature("SensorWidth")
+            .feature("Height")
+            .feature("OffsetX")
+            .feature("AcquisitionStart")
+            .finish();
+        builder.integer("Width").value(640).finish();
+        builder
+            .int_reg("SensorWidth", "Device")
+            .address(0)
+            .length(4)
+            .access_mode(AccessMode::RO)
+            .finish();
+        builder
+            .integer("Height")
+            .value(480)
+            .p_is_locked("StreamActive")
+            .finish();
+        builder.boolean("StreamActive").value(true).finish();
+        builder
+            .integer("OffsetX")
+            .p_is_available("OffsetXAvailable")
+            .finish();
+        builder.boolean("OffsetXAvailable").finish();
+        builder.integer("AcquisitionStartValue").finish();
+        builder
+            .command("AcquisitionStart", "AcquisitionStartValue", 1)
+            .finish();
+        let (_, store, mut cx) = builder.build();
+        let mut device = Memory(vec![0; 4]);
+
+        let access_modes = |device: &mut Memory, cx: &mut ValueCtxt<_, _>| {
+            let tree = CategoryTree::new(device, &store, cx).unwrap().unwrap();
+            let mut access_modes = vec![];
+            tree.visit(|entry, _| {
+                access_modes.push((entry.name().to_string(), entry.access_mode()))
+            });
+            access_modes
+        };
+        assert_eq!(
+            access_modes(&mut device, &mut cx),
+            [
+                ("Root".to_string(), Some(AccessMode::RO)),
+                ("Width".to_string(), Some(AccessMode::RW)),
+                ("SensorWidth".to_string(), Some(AccessMode::RO)),
+                ("Height".to_string(), Some(AccessMode::RO)),
+                ("OffsetX".to_string(), None),
+                ("AcquisitionStart".to_string(), Some(AccessMode::WO)),
+            ]
+        );
+
+        // Access modes reflect the current values of the context.
+        let offset_x_available = store.id_by_name("OffsetXAvailable").unwrap();
+        offset_x_available
+            .expect_iboolean_kind(&store)
+            .unwrap()
+            .set_value(true, &mut device, &store, &mut cx)
+            .unwrap();
+        let offset_x = access_modes(&mut device, &mut cx).remove(4);
+        assert_eq!(offset_x, ("OffsetX".to_string(), Some(AccessMode::RW)));
+    }
+}
@@ -8,10 +8,11 @@ use super::{
     ivalue::IValue,
     node_base::{NodeAttributeBase, NodeBase, NodeElementBase},
     store::{CacheStore, IntegerId, NodeStore, ValueStore},
-    Device, GenApiResult, ValueCtxt,
+    Device, GenApiError, GenApiResult, ValueCtxt,
 };
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CommandNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -57,10 +58,20 @@ impl ICommand for CommandNode {
         store: &impl NodeStore,
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<()> {
-        cx.invalidate_cache_by(self.node_base().id());
+        let nid = self.node_base().id();
+        if cx.is_command_busy(nid) && !self.is_done(device, store, cx)? {
+            return Err(GenApiError::busy(
+                format!("`{}` is still executing", nid.name(store)).into(),
+            ));
+        }
+        cx.invalidate_cache_by(nid);
 
         let value = self.command_value.value(device, store, cx)?;
-        self.value.set_value(value, device, store, cx)
+        self.value.set_value(value, device, store, cx)?;
+        if self.value.pnode().is_some() {
+            cx.mark_command_busy(nid);
+        }
+        Ok(())
     }
 
     #[tracing::instrument(skip(self, device, store, cx),
@@ -72,19 +83,27 @@ impl ICommand for CommandNode {
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<bool> {
         let nid = match self.value {
-            ImmOrPNode::Imm(..) => return Ok(true),
+            ImmOrPNode::Imm(..) => {
+                cx.mark_command_done(self.node_base().id());
+                return Ok(true);
+            }
             ImmOrPNode::PNode(nid) => nid,
         };
 
         cx.invalidate_cache_of(nid);
         let node = nid.expect_iinteger_kind(store)?;
-        if node.is_readable(device, store, cx)? {
+        let is_done = if node.is_readable(device, store, cx)? {
             let command_value = self.command_value.value(device, store, cx)?;
             let reg_value = node.value(device, store, cx)?;
-            Ok(command_value != reg_value)
+            command_value != reg_value
         } else {
-            Ok(true)
+            true
+        };
+
+        if is_done {
+            cx.mark_command_done(self.node_base().id());
         }
+        Ok(is_done)
     }
 
     #[tracing::instrument(skip(self, device, store, cx),
@@ -0,0 +1,532 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains [`diff`] that compares two versions of a device description file.
+//!
+//! Nodes are matched by their names. A matched node is compared element by element, so the
+//! result tells exactly which elements and attributes are changed, e.g. `Max` of `Width` or
+//! `Value` of an entry of `PixelFormat`.
+//!
+//! # Examples
+//! ```rust
+//! use cameleon_genapi::{builder::GenApiBuilder, diff, store::DefaultNodeStore};
+//!
+//! let mut builder = GenApiBuilder::<DefaultNodeStore>::default().programmatic("Model", "Vendor");
+//! builder.integer("Width").value(640).max(1280).finish();
+//! let (old_reg_desc, old_node_store, old_value_ctxt) = builder.build();
+//!
+//! let mut builder = GenApiBuilder::<DefaultNodeStore>::default().programmatic("Model", "Vendor");
+//! builder.integer("Width").value(640).max(2560).finish();
+//! builder.integer("Height").value(480).finish();
+//! let (new_reg_desc, new_node_store, new_value_ctxt) = builder.build();
+//!
+//! let diff = diff::diff(
+//!     &old_reg_desc,
+//!     &old_node_store,
+//!     &old_value_ctxt.value_store,
+//!     &new_reg_desc,
+//!     &new_node_store,
+//!     &new_value_ctxt.value_store,
+//! );
+//! assert_eq!(diff.added(), &["Height"]);
+//! assert!(diff.removed().is_empty());
+//! assert_eq!(diff.changed()[0].name(), "Width");
+//! assert_eq!(diff.changed()[0].changes()[0].to_string(), "Max: 1280 -> 2560");
+//! ```
+//!
+//! Use [`diff_xml`] to compare XMLs directly.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use string_interner::Symbol;
+
+use super::{
+    builder::GenApiBuilder,
+    parser::ParseResult,
+    store::{DefaultNodeStore, NodeStore, ValueStore},
+    writer, RegisterDescription,
+};
+
+/// Differences between two versions of a device description file, see [`diff`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diff {
+    pub(crate) attributes: Vec<Change>,
+    pub(crate) added: Vec<String>,
+    pub(crate) removed: Vec<String>,
+    pub(crate) changed: Vec<NodeDiff>,
+}
+
+impl Diff {
+    /// Changed attributes of `RegisterDescription`, e.g. `MajorVersion`.
+    #[must_use]
+    pub fn attributes(&self) -> &[Change] {
+        &self.attributes
+    }
+
+    /// Names of the nodes which exist only in the new version.
+    #[must_use]
+    pub fn added(&self) -> &[String] {
+        &self.added
+    }
+
+    /// Names of the nodes which exist only in the old version.
+    #[must_use]
+    pub fn removed(&self) -> &[String] {
+        &self.removed
+    }
+
+    /// Nodes which exist in both versions but differ.
+    #[must_use]
+    pub fn changed(&self) -> &[NodeDiff] {
+        &self.changed
+    }
+
+    /// Returns `true` if the two versions are the same.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for change in &self.attributes {
+            writeln!(f, "~ RegisterDescription {}", change)?;
+        }
+        for name in &self.removed {
+            writeln!(f, "- {}", name)?;
+        }
+        for name in &self.added {
+            writeln!(f, "+ {}", name)?;
+        }
+        for node in &self.changed {
+            if node.old_kind != node.new_kind {
+                writeln!(f, "~ {}: {} -> {}", node.name, node.old_kind, node.new_kind)?;
+            }
+            for change in &node.changes {
+                writeln!(f, "~ {} {}", node.name, change)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Differences of a node which exists in both versions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeDiff {
+    pub(crate) name: String,
+    pub(crate) old_kind: String,
+    pub(crate) new_kind: String,
+    pub(crate) changes: Vec<Change>,
+}
+
+impl NodeDiff {
+    /// Name of the node.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Element names of the node in the old and new versions, e.g. `Integer`. These differ if
+    /// the node kind is changed.
+    #[must_use]
+    pub fn kinds(&self) -> (&str, &str) {
+        (&self.old_kind, &self.new_kind)
+    }
+
+    /// Changed elements and attributes of the node.
+    #[must_use]
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+}
+
+/// A changed element or attribute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Change {
+    pub(crate) item: String,
+    pub(crate) old: Option<String>,
+    pub(crate) new: Option<String>,
+}
+
+impl Change {
+    /// Path of the changed item from the node, e.g. `Max`, `@NameSpace`,
+    /// `EnumEntry[Mono8]/Value` or `pIndex@Offset`.
+    ///
+    /// Elements are distinguished by their `Name` attribute if exists. Values of repeated
+    /// elements, e.g. `pFeature`, are joined with `, `.
+    #[must_use]
+    pub fn item(&self) -> &str {
+        &self.item
+    }
+
+    /// Value in the old version, or `None` if the item is added.
+    #[must_use]
+    pub fn old_value(&self) -> Option<&str> {
+        self.old.as_deref()
+    }
+
+    /// Value in the new version, or `None` if the item is removed.
+    #[must_use]
+    pub fn new_value(&self) -> Option<&str> {
+        self.new.as_deref()
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = |v: &Option<String>| v.clone().unwrap_or_else(|| "(none)".into());
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.item,
+            value(&self.old),
+            value(&self.new)
+        )
+    }
+}
+
+/// Compares two versions of a device description file.
+///
+/// Values of nodes are read from the value stores, so the current values are compared if they
+/// have been modified after the stores were built.
+pub fn diff(
+    old_reg_desc: &RegisterDescription,
+    old_node_store: &impl NodeStore,
+    old_value_store: &impl ValueStore,
+    new_reg_desc: &RegisterDescription,
+    new_node_store: &impl NodeStore,
+    new_value_store: &impl ValueStore,
+) -> Diff {
+    let attributes = diff_entries(
+        &attribute_entries(old_reg_desc),
+        &attribute_entries(new_reg_desc),
+    );
+
+    let old_nodes = node_entries(old_node_store, old_value_store);
+    let new_nodes = node_entries(new_node_store, new_value_store);
+    let old_names: HashSet<_> = old_nodes.iter().map(|node| node.name.as_str()).collect();
+    let new_index: HashMap<_, _> = new_nodes
+        .iter()
+        .map(|node| (node.name.as_str(), node))
+        .collect();
+
+    let mut diff = Diff {
+        attributes,
+        ..Diff::default()
+    };
+    for old in &old_nodes {
+        let new = match new_index.get(old.name.as_str()) {
+            Some(new) => new,
+            None => {
+                diff.removed.push(old.name.clone());
+                continue;
+            }
+        };
+        let changes = diff_entries(&old.entries, &new.entries);
+        if old.kind != new.kind || !changes.is_empty() {
+            diff.changed.push(NodeDiff {
+                name: old.name.clone(),
+                old_kind: old.kind.clone(),
+                new_kind: new.kind.clone(),
+                changes,
+            });
+        }
+    }
+    diff.added = new_nodes
+        .iter()
+        .filter(|node| !old_names.contains(node.name.as_str()))
+        .map(|node| node.name.clone())
+        .collect();
+
+    diff
+}
+
+/// Parses two XMLs and compares them, see [`diff`] for details.
+pub fn diff_xml(old: &impl AsRef<str>, new: &impl AsRef<str>) -> ParseResult<Diff> {
+    let (old_reg_desc, old_node_store, old_value_ctxt) =
+        GenApiBuilder::<DefaultNodeStore>::default()
+            .no_cache()
+            .build(old)?;
+    let (new_reg_desc, new_node_store, new_value_ctxt) =
+        GenApiBuilder::<DefaultNodeStore>::default()
+            .no_cache()
+            .build(new)?;
+    Ok(diff(
+        &old_reg_desc,
+        &old_node_store,
+        &old_value_ctxt.value_store,
+        &new_reg_desc,
+        &new_node_store,
+        &new_value_ctxt.value_store,
+    ))
+}
+
+/// Flattened items of an element, each item has the values of the elements sharing its path.
+#[derive(Default)]
+struct Entries {
+    items: Vec<(String, Vec<String>)>,
+    index: HashMap<String, usize>,
+}
+
+impl Entries {
+    fn push(&mut self, item: String, value: String) {
+        if let Some(&i) = self.index.get(&item) {
+            self.items[i].1.push(value);
+        } else {
+            self.index.insert(item.clone(), self.items.len());
+            self.items.push((item, vec![value]));
+        }
+    }
+
+    fn get(&self, item: &str) -> Option<&[String]> {
+        self.index.get(item).map(|&i| self.items[i].1.as_slice())
+    }
+}
+
+struct NodeEntries {
+    name: String,
+    kind: String,
+    entries: Entries,
+}
+
+fn attribute_entries(reg_desc: &RegisterDescription) -> Entries {
+    let mut entries = Entries::default();
+    for (name, value) in writer::register_description_attrs(reg_desc) {
+        entries.push(name.to_string(), value);
+    }
+    entries
+}
+
+/// Returns the flattened nodes in the order in which the nodes are interned.
+fn node_entries(node_store: &impl NodeStore, value_store: &impl ValueStore) -> Vec<NodeEntries> {
+    let mut ids = vec![];
+    node_store.visit_nodes(|data| ids.push(data.node_base().id()));
+    ids.sort_by_key(|nid| nid.to_usize());
+
+    ids.into_iter()
+        .filter_map(|nid| {
+            let xml = writer::write_node(node_store.node(nid), node_store, value_store);
+            if xml.is_empty() {
+                // `EnumEntry`s are compared as a part of their `Enumeration`.
+                return None;
+            }
+
+            let name = nid.name(node_store).to_string();
+            let mut entries = Entries::default();
+            let kind = match roxmltree::Document::parse(&xml) {
+                Ok(document) => {
+                    let root = document.root_element();
+                    flatten(root, "", &mut entries);
+                    root.tag_name().name().to_string()
+                }
+                // Extension elements may use namespace prefixes which are declared only in the
+                // root element, so compare the whole XML as it is.
+                Err(_) => {
+                    let kind = xml
+                        .trim_start()
+                        .trim_start_matches('<')
+                        .split(|c: char| c.is_whitespace() || c == '>')
+                        .next()
+                        .unwrap_or_default()
+                        .to_string();
+                    entries.push(String::new(), xml);
+                    kind
+                }
+            };
+            Some(NodeEntries {
+                name,
+                kind,
+                entries,
+            })
+        })
+        .collect()
+}
+
+fn flatten(elem: roxmltree::Node, path: &str, entries: &mut Entries) {
+    for attr in elem.attributes() {
+        // `Name` is a part of the path.
+        if attr.name() != "Name" {
+            entries.push(format!("{}@{}", path, attr.name()), attr.value().into());
+        }
+    }
+
+    for child in elem.children().filter(roxmltree::Node::is_element) {
+        let tag = child.tag_name().name();
+        let key = match child.attribute("Name") {
+            Some(name) => format!("{}[{}]", tag, name),
+            None => tag.to_string(),
+        };
+        let child_path = if path.is_empty() {
+            key
+        } else {
+            format!("{}/{}", path, key)
+        };
+
+        if child.children().any(|c| c.is_element()) {
+            flatten(child, &child_path, entries);
+        } else {
+            for attr in child.attributes() {
+                if attr.name() != "Name" {
+                    entries.push(
+                        format!("{}@{}", child_path, attr.name()),
+                        attr.value().into(),
+                    );
+                }
+            }
+            entries.push(child_path, child.text().unwrap_or_default().trim().into());
+        }
+    }
+}
+
+fn diff_entries(old: &Entries, new: &Entries) -> Vec<Change> {
+    let join = |values: Option<&[String]>| values.map(|values| values.join(", "));
+
+    let mut changes = vec![];
+    for (item, values) in &old.items {
+        let new_values = new.get(item);
+        if new_values != Some(values.as_slice()) {
+            changes.push(Change {
+                item: item.clone(),
+                old: join(Some(values)),
+                new: join(new_values),
+            });
+        }
+    }
+    for (item, values) in &new.items {
+        if old.get(item).is_none() {
+            changes.push(Change {
+                item: item.clone(),
+                old: None,
+                new: join(Some(values)),
+            });
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::tests::register_description as xml;
+
+    use super::*;
+
+    fn change(item: &str, old: Option<&str>, new: Option<&str>) -> Change {
+        Change {
+            item: item.into(),
+            old: old.map(Into::into),
+            new: new.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn test_same() {
+        let xml = xml(r#"
+            <Category Name="Root">
+                <pFeature>Width</pFeature>
+            </Category>
+            <Integer Name="Width">
+                <Value>640</Value>
+            </Integer>
+            "#);
+        assert!(diff_xml(&xml, &xml).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = xml(r#"
+            <Category Name="Root">
+                <pFeature>Width</pFeature>
+                <pFeature>Gain</pFeature>
+            </Category>
+            <Integer Name="Width" NameSpace="Standard">
+                <Value>640</Value>
+                <Max>1280</Max>
+            </Integer>
+            <Float Name="Gain">
+                <Value>1.0</Value>
+            </Float>
+            <Enumeration Name="PixelFormat">
+                <EnumEntry Name="Mono8">
+                    <Value>1</Value>
+                </EnumEntry>
+                <Value>1</Value>
+            </Enumeration>
+            "#);
+        let new = xml(r#"
+            <Category Name="Root">
+                <pFeature>Width</pFeature>
+                <pFeature>Height</pFeature>
+            </Category>
+            <Integer Name="Width">
+                <Value>640</Value>
+                <Min>16</Min>
+                <Max>1280</Max>
+            </Integer>
+            <Integer Name="Height">
+                <Value>480</Value>
+            </Integer>
+            <Enumeration Name="PixelFormat">
+                <EnumEntry Name="Mono8">
+                    <Value>2</Value>
+                </EnumEntry>
+                <Value>2</Value>
+            </Enumeration>
+            "#);
+
+        let diff = diff_xml(&old, &new).unwrap();
+        assert!(diff.attributes().is_empty());
+        assert_eq!(diff.added(), &["Height"]);
+        assert_eq!(diff.removed(), &["Gain"]);
+
+        let changed = diff.changed();
+        assert_eq!(changed.len(), 3);
+        assert_eq!(changed[0].name(), "Root");
+        assert_eq!(
+            changed[0].changes(),
+            &[change(
+                "pFeature",
+                Some("Width, Gain"),
+                Some("Width, Height")
+            )]
+        );
+        assert_eq!(changed[1].name(), "Width");
+        assert_eq!(
+            changed[1].changes(),
+            &[
+                change("@NameSpace", Some("Standard"), None),
+                // The parser fills missing `Min` with the deduced value.
+                change("Min", Some("-9223372036854775808"), Some("16")),
+            ]
+        );
+        assert_eq!(changed[2].name(), "PixelFormat");
+        assert_eq!(
+            changed[2].changes(),
+            &[
+                change("EnumEntry[Mono8]/Value", Some("1"), Some("2")),
+                change("Value", Some("1"), Some("2")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_kind_changed() {
+        let old = xml(r#"<Integer Name="Gain"><Value>1</Value></Integer>"#);
+        let new = xml(r#"<Float Name="Gain"><Value>1.0</Value></Float>"#);
+
+        let diff = diff_xml(&old, &new).unwrap();
+        assert_eq!(diff.changed().len(), 1);
+        assert_eq!(diff.changed()[0].kinds(), ("Integer", "Float"));
+        assert!(diff.to_string().starts_with("~ Gain: Integer -> Float\n"));
+    }
+}
@@ -13,12 +13,16 @@ use super::{
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NameSpace {
     Standard,
     Custom,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Visibility of the node. Variants are ordered from the most visible one, i.e.
+/// `Beginner < Expert < Guru < Invisible`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Visibility {
     Beginner,
     Expert,
@@ -27,6 +31,7 @@ pub enum Visibility {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MergePriority {
     High,
     Mid,
@@ -34,6 +39,7 @@ pub enum MergePriority {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AccessMode {
     RO,
     WO,
@@ -41,6 +47,7 @@ pub enum AccessMode {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ImmOrPNode<T> {
     Imm(T),
     PNode(NodeId),
@@ -63,6 +70,7 @@ impl<T> ImmOrPNode<T> {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum IntegerRepresentation {
     Linear,
     Logarithmic,
@@ -99,6 +107,7 @@ impl IntegerRepresentation {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FloatRepresentation {
     Linear,
     Logarithmic,
@@ -106,6 +115,7 @@ pub enum FloatRepresentation {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Slope {
     Increasing,
     Decreasing,
@@ -114,6 +124,7 @@ pub enum Slope {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DisplayNotation {
     Automatic,
     Fixed,
@@ -121,6 +132,7 @@ pub enum DisplayNotation {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StandardNameSpace {
     None,
     IIDC,
@@ -130,6 +142,7 @@ pub enum StandardNameSpace {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CachingMode {
     /// Allow to caching on read/write.
     WriteThrough,
@@ -140,6 +153,7 @@ pub enum CachingMode {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NamedValue<T> {
     pub(crate) name: String,
     pub(crate) value: T,
@@ -163,6 +177,7 @@ impl<T> NamedValue<T> {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ValueKind<T> {
     Value(T),
     PValue(PValue<T>),
@@ -199,6 +214,7 @@ impl<T> ValueKind<T> {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PValue<T> {
     pub(crate) p_value: NodeId,
     pub(crate) p_value_copies: Vec<NodeId>,
@@ -218,6 +234,7 @@ impl<T> PValue<T> {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PIndex<T> {
     pub(crate) p_index: NodeId,
     pub(crate) value_indexed: Vec<ValueIndexed<T>>,
@@ -245,6 +262,7 @@ impl<T> PIndex<T> {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ValueIndexed<T> {
     pub(crate) index: i64,
     pub(crate) indexed: ImmOrPNode<T>,
@@ -266,6 +284,7 @@ impl<T> ValueIndexed<T> {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AddressKind {
     Address(ImmOrPNode<i64>),
     IntSwissKnife(NodeId),
@@ -288,6 +307,7 @@ impl AddressKind {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RegPIndex {
     pub(crate) offset: Option<ImmOrPNode<i64>>,
     pub(crate) p_index: NodeId,
@@ -323,19 +343,117 @@ impl RegPIndex {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Endianness {
     LE,
     BE,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Sign {
     Signed,
     Unsigned,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BitMask {
     SingleBit(u64),
     Range { lsb: u64, msb: u64 },
 }
+
+/// Non-standard attributes and elements of a node, e.g. vendor specific extensions.
+///
+/// They are collected only when the XML is parsed in tolerant mode, see
+/// [`crate::parser::ParseOptions`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Extensions {
+    pub(crate) attributes: Vec<(String, String)>,
+    pub(crate) elements: Vec<ExtensionElement>,
+}
+
+impl Extensions {
+    /// Returns non-standard attributes as pairs of a qualified name and a value.
+    #[must_use]
+    pub fn attributes(&self) -> &[(String, String)] {
+        &self.attributes
+    }
+
+    #[must_use]
+    pub fn elements(&self) -> &[ExtensionElement] {
+        &self.elements
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty() && self.elements.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtensionElement {
+    pub(crate) name: String,
+    pub(crate) source: String,
+}
+
+impl ExtensionElement {
+    /// Returns the qualified name of the element, e.g. `vendor:Info`.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the XML source of the element as it is written in the original XML.
+    #[must_use]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+/// Version of the `GenApi` schema which a register description conforms to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SchemaVersion {
+    pub(crate) major: u64,
+    pub(crate) minor: u64,
+    pub(crate) subminor: u64,
+}
+
+impl SchemaVersion {
+    pub const V1_0: Self = Self::new(1, 0, 0);
+    pub const V1_1: Self = Self::new(1, 1, 0);
+
+    #[must_use]
+    pub const fn new(major: u64, minor: u64, subminor: u64) -> Self {
+        Self {
+            major,
+            minor,
+            subminor,
+        }
+    }
+
+    #[must_use]
+    pub fn major(self) -> u64 {
+        self.major
+    }
+
+    #[must_use]
+    pub fn minor(self) -> u64 {
+        self.minor
+    }
+
+    #[must_use]
+    pub fn subminor(self) -> u64 {
+        self.subminor
+    }
+
+    /// Returns `true` if the version is 1.0.x, which differs from later schemas in defaults and
+    /// allowed elements.
+    #[must_use]
+    pub fn is_1_0(self) -> bool {
+        self.major == 1 && self.minor == 0
+    }
+}
@@ -8,10 +8,11 @@ use super::{
     ivalue::IValue,
     node_base::{NodeAttributeBase, NodeBase, NodeElementBase},
     store::{CacheStore, IntegerId, NodeId, NodeStore, ValueStore},
-    Device, GenApiError, GenApiResult, ValueCtxt,
+    utils, Device, GenApiError, GenApiResult, ValueCtxt,
 };
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EnumerationNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -138,7 +139,9 @@ impl IEnumeration for EnumerationNode {
                 format!("not found entry with the value `{}`", value).into(),
             ));
         };
-        cx.invalidate_cache_by(self.node_base().id());
+        let nid = self.node_base().id();
+        utils::verify_no_busy_command(nid, &self.p_selected, device, store, cx)?;
+        cx.invalidate_cache_by(nid);
         self.value.set_value(value, device, store, cx)
     }
 
@@ -176,6 +179,7 @@ impl ISelector for EnumerationNode {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EnumEntryNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -11,6 +11,7 @@ use super::{
 };
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FloatRegNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) register_base: RegisterBase,
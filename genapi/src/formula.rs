@@ -15,8 +15,12 @@ use tracing::debug;
 use super::{GenApiError, GenApiResult};
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Formula {
     pub(crate) expr: Expr,
+    /// The compiled program of `expr`, which is set by the node owning the formula when all
+    /// identifiers in `expr` can be resolved.
+    pub(crate) compiled: Option<CompiledFormula>,
 }
 
 impl Formula {
@@ -25,6 +29,12 @@ impl Formula {
         &self.expr
     }
 
+    /// Returns the compiled program of the formula if exists.
+    #[must_use]
+    pub fn compiled(&self) -> Option<&CompiledFormula> {
+        self.compiled.as_ref()
+    }
+
     pub fn eval<K, V>(&self, var_env: &HashMap<K, V>) -> GenApiResult<EvaluationResult>
     where
         K: Borrow<str> + Eq + Hash + fmt::Debug,
@@ -32,9 +42,264 @@ impl Formula {
     {
         self.expr.eval(var_env)
     }
+
+    /// Compiles the formula into a flat stack program. See [`CompiledFormula`] for details.
+    pub fn compile<K, V>(
+        &self,
+        slots: &[&str],
+        bindings: &HashMap<K, V>,
+    ) -> GenApiResult<CompiledFormula>
+    where
+        K: Borrow<str> + Eq + Hash + fmt::Debug,
+        V: Borrow<Expr> + fmt::Debug,
+    {
+        CompiledFormula::compile(&self.expr, slots, bindings)
+    }
 }
 
+/// A formula lowered into a flat stack program.
+///
+/// Identifiers in the formula are resolved at compile time. An identifier bound in `bindings`,
+/// e.g. `Constant` or `Expression` elements of the node, is inlined into the program. An
+/// identifier listed in `slots`, e.g. `pVariable` elements of the node, is loaded from the
+/// corresponding index of the slot values passed to [`CompiledFormula::eval`]. `bindings` take
+/// precedence over `slots`.
+///
+/// Evaluating the program requires neither tree walking nor building a variable environment,
+/// which makes it cheaper than [`Formula::eval`] for formulas that are evaluated on every read.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompiledFormula {
+    code: Vec<Instr>,
+    slot_num: usize,
+    max_stack_depth: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Instr {
+    Push(EvaluationResult),
+    Load(usize),
+    BinOp(BinOpKind),
+    UnOp(UnOpKind),
+    /// Converts the top of the stack to `0` or `1`.
+    ToBool,
+    Jump(usize),
+    /// Pops the top of the stack, then jumps if it's zero.
+    JumpIfZero(usize),
+    /// Pops the top of the stack, then jumps if it's not zero.
+    JumpIfNonZero(usize),
+}
+
+impl CompiledFormula {
+    fn compile<K, V>(expr: &Expr, slots: &[&str], bindings: &HashMap<K, V>) -> GenApiResult<Self>
+    where
+        K: Borrow<str> + Eq + Hash + fmt::Debug,
+        V: Borrow<Expr> + fmt::Debug,
+    {
+        let mut compiler = Compiler {
+            slots,
+            bindings,
+            code: vec![],
+            inlining: vec![],
+            stack_depth: 0,
+            max_stack_depth: 0,
+        };
+        compiler.compile(expr)?;
+        Ok(Self {
+            code: compiler.code,
+            slot_num: slots.len(),
+            max_stack_depth: compiler.max_stack_depth,
+        })
+    }
+
+    /// Evaluates the program with `slots` values, the values must be in the same order as
+    /// `slots` passed to [`Formula::compile`].
+    pub fn eval(&self, slots: &[EvaluationResult]) -> GenApiResult<EvaluationResult> {
+        if slots.len() != self.slot_num {
+            return Err(GenApiError::invalid_node(
+                format!(
+                    "the number of slot values mismatches: expected {}, but got {}",
+                    self.slot_num,
+                    slots.len()
+                )
+                .into(),
+            ));
+        }
+
+        let mut stack = Vec::with_capacity(self.max_stack_depth);
+        let mut pc = 0;
+        while let Some(instr) = self.code.get(pc) {
+            pc += 1;
+            match *instr {
+                Instr::Push(value) => stack.push(value),
+                Instr::Load(slot) => stack.push(slots[slot]),
+                Instr::BinOp(op) => {
+                    let rhs = stack.pop().unwrap();
+                    let lhs = stack.pop().unwrap();
+                    stack.push(apply_binop(op, lhs, rhs));
+                }
+                Instr::UnOp(op) => {
+                    let value = stack.pop().unwrap();
+                    stack.push(apply_unop(op, value));
+                }
+                Instr::ToBool => {
+                    let value = stack.pop().unwrap();
+                    stack.push(value.as_bool().into());
+                }
+                Instr::Jump(target) => pc = target,
+                Instr::JumpIfZero(target) => {
+                    if !stack.pop().unwrap().as_bool() {
+                        pc = target;
+                    }
+                }
+                Instr::JumpIfNonZero(target) => {
+                    if stack.pop().unwrap().as_bool() {
+                        pc = target;
+                    }
+                }
+            }
+        }
+
+        debug_assert_eq!(stack.len(), 1);
+        Ok(stack.pop().unwrap())
+    }
+}
+
+struct Compiler<'a, K, V> {
+    slots: &'a [&'a str],
+    bindings: &'a HashMap<K, V>,
+    code: Vec<Instr>,
+    /// Names of bindings being inlined, used to detect cyclic references.
+    inlining: Vec<&'a str>,
+    stack_depth: usize,
+    max_stack_depth: usize,
+}
+
+impl<'a, K, V> Compiler<'a, K, V>
+where
+    K: Borrow<str> + Eq + Hash + fmt::Debug,
+    V: Borrow<Expr> + fmt::Debug,
+{
+    fn compile(&mut self, expr: &'a Expr) -> GenApiResult<()> {
+        match expr {
+            Expr::BinOp {
+                kind: BinOpKind::And,
+                lhs,
+                rhs,
+            } => self.compile_short_circuit(lhs, rhs, true),
+            Expr::BinOp {
+                kind: BinOpKind::Or,
+                lhs,
+                rhs,
+            } => self.compile_short_circuit(lhs, rhs, false),
+            Expr::BinOp { kind, lhs, rhs } => {
+                self.compile(lhs)?;
+                self.compile(rhs)?;
+                self.emit(Instr::BinOp(*kind));
+                Ok(())
+            }
+            Expr::UnOp { kind, expr } => {
+                self.compile(expr)?;
+                self.emit(Instr::UnOp(*kind));
+                Ok(())
+            }
+            Expr::If { cond, then, else_ } => {
+                self.compile(cond)?;
+                let jump_to_else = self.emit(Instr::JumpIfZero(0));
+                self.compile(then)?;
+                let jump_to_end = self.emit(Instr::Jump(0));
+                // Only one of the branches pushes its result.
+                self.stack_depth -= 1;
+                self.patch(jump_to_else);
+                self.compile(else_)?;
+                self.patch(jump_to_end);
+                Ok(())
+            }
+            &Expr::Integer(i) => {
+                self.emit(Instr::Push(i.into()));
+                Ok(())
+            }
+            &Expr::Float(f) => {
+                self.emit(Instr::Push(f.into()));
+                Ok(())
+            }
+            Expr::Ident(name) => self.compile_ident(name),
+        }
+    }
+
+    /// Compiles `lhs && rhs` if `is_and` is `true`, otherwise compiles `lhs || rhs`.
+    /// `rhs` is evaluated only when `lhs` doesn't determine the result as the tree evaluator
+    /// does.
+    fn compile_short_circuit(
+        &mut self,
+        lhs: &'a Expr,
+        rhs: &'a Expr,
+        is_and: bool,
+    ) -> GenApiResult<()> {
+        self.compile(lhs)?;
+        let jump_to_short_circuit = if is_and {
+            self.emit(Instr::JumpIfZero(0))
+        } else {
+            self.emit(Instr::JumpIfNonZero(0))
+        };
+        self.compile(rhs)?;
+        self.emit(Instr::ToBool);
+        let jump_to_end = self.emit(Instr::Jump(0));
+        self.stack_depth -= 1;
+        self.patch(jump_to_short_circuit);
+        self.emit(Instr::Push((!is_and).into()));
+        self.patch(jump_to_end);
+        Ok(())
+    }
+
+    fn compile_ident(&mut self, name: &'a str) -> GenApiResult<()> {
+        if let Some(expr) = self.bindings.get(name) {
+            if self.inlining.contains(&name) {
+                return Err(GenApiError::invalid_node(
+                    format!("cyclic reference found in formula: {}", name).into(),
+                ));
+            }
+            self.inlining.push(name);
+            self.compile(expr.borrow())?;
+            self.inlining.pop();
+            Ok(())
+        } else if let Some(slot) = self.slots.iter().position(|slot| *slot == name) {
+            self.emit(Instr::Load(slot));
+            Ok(())
+        } else {
+            Err(GenApiError::invalid_node(
+                format!("ident not found in formula: {} not found", name).into(),
+            ))
+        }
+    }
+
+    /// Emits the instruction and returns its address.
+    fn emit(&mut self, instr: Instr) -> usize {
+        match instr {
+            Instr::Push(..) | Instr::Load(..) => self.stack_depth += 1,
+            Instr::BinOp(..) | Instr::JumpIfZero(..) | Instr::JumpIfNonZero(..) => {
+                self.stack_depth -= 1;
+            }
+            Instr::UnOp(..) | Instr::ToBool | Instr::Jump(..) => {}
+        }
+        self.max_stack_depth = self.max_stack_depth.max(self.stack_depth);
+        self.code.push(instr);
+        self.code.len() - 1
+    }
+
+    /// Sets the target of the jump instruction at `addr` to the next instruction to be emitted.
+    fn patch(&mut self, addr: usize) {
+        let target = self.code.len();
+        match &mut self.code[addr] {
+            Instr::Jump(t) | Instr::JumpIfZero(t) | Instr::JumpIfNonZero(t) => *t = target,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expr {
     BinOp {
         kind: BinOpKind,
@@ -77,7 +342,94 @@ impl From<bool> for Expr {
     }
 }
 
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.expr, f)
+    }
+}
+
+/// Precedence of expressions, larger binds tighter.
+const IF_PREC: u8 = 0;
+const UNOP_PREC: u8 = 11;
+const POW_PREC: u8 = 12;
+const PRIMARY_PREC: u8 = 13;
+
+impl Expr {
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::BinOp { kind, .. } => kind.precedence(),
+            Self::UnOp { kind, .. } => {
+                if kind.is_prefix_op() {
+                    UNOP_PREC
+                } else {
+                    PRIMARY_PREC
+                }
+            }
+            Self::If { .. } => IF_PREC,
+            // Negative literals are printed with `-` prefix.
+            &Self::Integer(i) if i < 0 => UNOP_PREC,
+            &Self::Float(f) if f.is_sign_negative() => UNOP_PREC,
+            Self::Integer(..) | Self::Float(..) | Self::Ident(..) => PRIMARY_PREC,
+        }
+    }
+
+    /// Writes the expression, enclosing it with parentheses if its precedence is lower than
+    /// `min_prec`.
+    fn fmt_with_prec(&self, f: &mut fmt::Formatter<'_>, min_prec: u8) -> fmt::Result {
+        if self.precedence() < min_prec {
+            write!(f, "(")?;
+            self.fmt_with_prec(f, IF_PREC)?;
+            return write!(f, ")");
+        }
+
+        match self {
+            Self::BinOp { kind, lhs, rhs } => {
+                let prec = kind.precedence();
+                let (lhs_prec, rhs_prec) = if *kind == BinOpKind::Pow {
+                    // `**` is right associative and its lhs must be a primary expression.
+                    (PRIMARY_PREC, UNOP_PREC)
+                } else {
+                    (prec, prec + 1)
+                };
+                lhs.fmt_with_prec(f, lhs_prec)?;
+                write!(f, " {} ", kind.symbol())?;
+                rhs.fmt_with_prec(f, rhs_prec)
+            }
+            Self::UnOp { kind, expr } => {
+                if kind.is_prefix_op() {
+                    write!(f, "{}", kind.symbol())?;
+                    expr.fmt_with_prec(f, UNOP_PREC)
+                } else {
+                    write!(f, "{}(", kind.symbol())?;
+                    expr.fmt_with_prec(f, IF_PREC)?;
+                    write!(f, ")")
+                }
+            }
+            Self::If { cond, then, else_ } => {
+                cond.fmt_with_prec(f, IF_PREC + 1)?;
+                write!(f, " ? ")?;
+                then.fmt_with_prec(f, IF_PREC)?;
+                write!(f, " : ")?;
+                else_.fmt_with_prec(f, IF_PREC)
+            }
+            Self::Integer(i) => write!(f, "{}", i),
+            // `Debug` format always contains a decimal point or an exponent, so the literal is
+            // parsed as a float again.
+            Self::Float(v) => write!(f, "{:?}", v),
+            Self::Ident(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// Formats the expression in `GenApi` formula syntax, which can be parsed by [`parse`] again.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with_prec(f, IF_PREC)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EvaluationResult {
     Integer(i64),
     Float(f64),
@@ -175,8 +527,6 @@ impl Expr {
         K: Borrow<str> + Eq + Hash + fmt::Debug,
         V: Borrow<Expr> + fmt::Debug,
     {
-        use std::ops::{Add, Mul, Rem, Sub};
-
         Ok(match op {
             BinOpKind::And => {
                 (self.eval(var_env)?.as_bool() && rhs.eval(var_env)?.as_bool()).into()
@@ -186,67 +536,7 @@ impl Expr {
             _ => {
                 let lhs = self.eval(var_env)?;
                 let rhs = rhs.eval(var_env)?;
-
-                macro_rules! apply_arithmetic_op {
-                    ($fint:ident, $ffloat:ident) => {{
-                        if lhs.is_integer() && rhs.is_integer() {
-                            (lhs.as_integer().$fint(rhs.as_integer())).0.into()
-                        } else {
-                            (lhs.as_float().$ffloat(rhs.as_float())).into()
-                        }
-                    }};
-                }
-
-                macro_rules! apply_cmp_op {
-                    ($fint:ident, $ffloat:ident) => {{
-                        if lhs.is_integer() && rhs.is_integer() {
-                            (lhs.as_integer().$fint(&rhs.as_integer())).into()
-                        } else {
-                            (lhs.as_float().$ffloat(&rhs.as_float())).into()
-                        }
-                    }};
-                }
-                match op {
-                    BinOpKind::Add => apply_arithmetic_op!(overflowing_add, add),
-                    BinOpKind::Sub => apply_arithmetic_op!(overflowing_sub, sub),
-                    BinOpKind::Mul => apply_arithmetic_op!(overflowing_mul, mul),
-                    BinOpKind::Div => {
-                        // Division must be treated as floating points.
-                        // e.g. Converter node with `<FormulaFrom>TO/(1&lt;&lt;P1)</FormulaFrom>` where `P1` points to integer node are commonplace.
-                        (lhs.as_float() / rhs.as_float()).into()
-                    }
-                    BinOpKind::Rem => apply_arithmetic_op!(overflowing_rem, rem),
-                    BinOpKind::Pow => {
-                        if lhs.is_integer() && rhs.is_integer() && rhs.as_integer() >= 0 {
-                            lhs.as_integer()
-                                .overflowing_pow(rhs.as_integer() as u32)
-                                .0
-                                .into()
-                        } else {
-                            lhs.as_float().powf(rhs.as_float()).into()
-                        }
-                    }
-                    BinOpKind::Eq => apply_cmp_op!(eq, eq),
-                    BinOpKind::Ne => apply_cmp_op!(ne, ne),
-                    BinOpKind::Lt => apply_cmp_op!(lt, lt),
-                    BinOpKind::Le => apply_cmp_op!(le, le),
-                    BinOpKind::Gt => apply_cmp_op!(gt, gt),
-                    BinOpKind::Ge => apply_cmp_op!(ge, ge),
-                    BinOpKind::Shl => lhs
-                        .as_integer()
-                        .overflowing_shl(rhs.as_integer() as u32)
-                        .0
-                        .into(),
-                    BinOpKind::Shr => lhs
-                        .as_integer()
-                        .overflowing_shr(rhs.as_integer() as u32)
-                        .0
-                        .into(),
-                    BinOpKind::BitAnd => (lhs.as_integer() & rhs.as_integer()).into(),
-                    BinOpKind::BitOr => (lhs.as_integer() | rhs.as_integer()).into(),
-                    BinOpKind::Xor => (lhs.as_integer() ^ rhs.as_integer()).into(),
-                    _ => unreachable!(),
-                }
+                apply_binop(op, lhs, rhs)
             }
         })
     }
@@ -260,42 +550,114 @@ impl Expr {
         K: Borrow<str> + Eq + Hash + fmt::Debug,
         V: Borrow<Expr> + fmt::Debug,
     {
-        use std::ops::Neg;
-
         let res = self.eval(var_env)?;
-        macro_rules! apply_op {
-            ($f:ident) => {
-                match res {
-                    EvaluationResult::Integer(i) => EvaluationResult::from(i.$f()),
-                    EvaluationResult::Float(f) => EvaluationResult::from(f.$f()),
-                }
-            };
+        Ok(apply_unop(op, res))
+    }
+}
+
+fn apply_binop(op: BinOpKind, lhs: EvaluationResult, rhs: EvaluationResult) -> EvaluationResult {
+    use std::ops::{Add, Mul, Rem, Sub};
+
+    macro_rules! apply_arithmetic_op {
+        ($fint:ident, $ffloat:ident) => {{
+            if lhs.is_integer() && rhs.is_integer() {
+                (lhs.as_integer().$fint(rhs.as_integer())).0.into()
+            } else {
+                (lhs.as_float().$ffloat(rhs.as_float())).into()
+            }
+        }};
+    }
+
+    macro_rules! apply_cmp_op {
+        ($fint:ident, $ffloat:ident) => {{
+            if lhs.is_integer() && rhs.is_integer() {
+                (lhs.as_integer().$fint(&rhs.as_integer())).into()
+            } else {
+                (lhs.as_float().$ffloat(&rhs.as_float())).into()
+            }
+        }};
+    }
+
+    match op {
+        BinOpKind::Add => apply_arithmetic_op!(overflowing_add, add),
+        BinOpKind::Sub => apply_arithmetic_op!(overflowing_sub, sub),
+        BinOpKind::Mul => apply_arithmetic_op!(overflowing_mul, mul),
+        BinOpKind::Div => {
+            // Division must be treated as floating points.
+            // e.g. Converter node with `<FormulaFrom>TO/(1&lt;&lt;P1)</FormulaFrom>` where `P1` points to integer node are commonplace.
+            (lhs.as_float() / rhs.as_float()).into()
         }
+        BinOpKind::Rem => apply_arithmetic_op!(overflowing_rem, rem),
+        BinOpKind::Pow => {
+            if lhs.is_integer() && rhs.is_integer() && rhs.as_integer() >= 0 {
+                lhs.as_integer()
+                    .overflowing_pow(rhs.as_integer() as u32)
+                    .0
+                    .into()
+            } else {
+                lhs.as_float().powf(rhs.as_float()).into()
+            }
+        }
+        BinOpKind::Eq => apply_cmp_op!(eq, eq),
+        BinOpKind::Ne => apply_cmp_op!(ne, ne),
+        BinOpKind::Lt => apply_cmp_op!(lt, lt),
+        BinOpKind::Le => apply_cmp_op!(le, le),
+        BinOpKind::Gt => apply_cmp_op!(gt, gt),
+        BinOpKind::Ge => apply_cmp_op!(ge, ge),
+        BinOpKind::Shl => lhs
+            .as_integer()
+            .overflowing_shl(rhs.as_integer() as u32)
+            .0
+            .into(),
+        BinOpKind::Shr => lhs
+            .as_integer()
+            .overflowing_shr(rhs.as_integer() as u32)
+            .0
+            .into(),
+        BinOpKind::BitAnd => (lhs.as_integer() & rhs.as_integer()).into(),
+        BinOpKind::BitOr => (lhs.as_integer() | rhs.as_integer()).into(),
+        BinOpKind::Xor => (lhs.as_integer() ^ rhs.as_integer()).into(),
+        BinOpKind::And => (lhs.as_bool() && rhs.as_bool()).into(),
+        BinOpKind::Or => (lhs.as_bool() || rhs.as_bool()).into(),
+    }
+}
 
-        Ok(match op {
-            UnOpKind::Not => (!res.as_integer()).into(),
-            UnOpKind::Abs => apply_op!(abs),
-            UnOpKind::Sgn => apply_op!(signum),
-            UnOpKind::Neg => apply_op!(neg),
-            UnOpKind::Sin => res.as_float().sin().into(),
-            UnOpKind::Cos => res.as_float().cos().into(),
-            UnOpKind::Tan => res.as_float().tan().into(),
-            UnOpKind::Asin => res.as_float().asin().into(),
-            UnOpKind::Acos => res.as_float().acos().into(),
-            UnOpKind::Atan => res.as_float().atan().into(),
-            UnOpKind::Exp => res.as_float().exp().into(),
-            UnOpKind::Ln => res.as_float().ln().into(),
-            UnOpKind::Lg => res.as_float().log10().into(),
-            UnOpKind::Sqrt => res.as_float().sqrt().into(),
-            UnOpKind::Trunc => res.as_float().trunc().into(),
-            UnOpKind::Floor => res.as_float().floor().into(),
-            UnOpKind::Ceil => res.as_float().ceil().into(),
-            UnOpKind::Round => res.as_float().round().into(),
-        })
+fn apply_unop(op: UnOpKind, res: EvaluationResult) -> EvaluationResult {
+    use std::ops::Neg;
+
+    macro_rules! apply_op {
+        ($f:ident) => {
+            match res {
+                EvaluationResult::Integer(i) => EvaluationResult::from(i.$f()),
+                EvaluationResult::Float(f) => EvaluationResult::from(f.$f()),
+            }
+        };
+    }
+
+    match op {
+        UnOpKind::Not => (!res.as_integer()).into(),
+        UnOpKind::Abs => apply_op!(abs),
+        UnOpKind::Sgn => apply_op!(signum),
+        UnOpKind::Neg => apply_op!(neg),
+        UnOpKind::Sin => res.as_float().sin().into(),
+        UnOpKind::Cos => res.as_float().cos().into(),
+        UnOpKind::Tan => res.as_float().tan().into(),
+        UnOpKind::Asin => res.as_float().asin().into(),
+        UnOpKind::Acos => res.as_float().acos().into(),
+        UnOpKind::Atan => res.as_float().atan().into(),
+        UnOpKind::Exp => res.as_float().exp().into(),
+        UnOpKind::Ln => res.as_float().ln().into(),
+        UnOpKind::Lg => res.as_float().log10().into(),
+        UnOpKind::Sqrt => res.as_float().sqrt().into(),
+        UnOpKind::Trunc => res.as_float().trunc().into(),
+        UnOpKind::Floor => res.as_float().floor().into(),
+        UnOpKind::Ceil => res.as_float().ceil().into(),
+        UnOpKind::Round => res.as_float().round().into(),
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BinOpKind {
     Add,
     Sub,
@@ -318,7 +680,50 @@ pub enum BinOpKind {
     Xor,
 }
 
+impl BinOpKind {
+    fn precedence(self) -> u8 {
+        match self {
+            Self::Or => 1,
+            Self::And => 2,
+            Self::BitOr => 3,
+            Self::Xor => 4,
+            Self::BitAnd => 5,
+            Self::Eq | Self::Ne => 6,
+            Self::Lt | Self::Le | Self::Gt | Self::Ge => 7,
+            Self::Shl | Self::Shr => 8,
+            Self::Add | Self::Sub => 9,
+            Self::Mul | Self::Div | Self::Rem => 10,
+            Self::Pow => POW_PREC,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Rem => "%",
+            Self::Pow => "**",
+            Self::Shl => "<<",
+            Self::Shr => ">>",
+            Self::And => "&&",
+            Self::Or => "||",
+            Self::Eq => "=",
+            Self::Ne => "<>",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::BitAnd => "&",
+            Self::BitOr => "|",
+            Self::Xor => "^",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum UnOpKind {
     Not,
     Abs,
@@ -340,12 +745,64 @@ pub enum UnOpKind {
     Round,
 }
 
+impl UnOpKind {
+    /// Returns `true` if the operator is written as a prefix operator, otherwise the operator is
+    /// written as a function.
+    fn is_prefix_op(self) -> bool {
+        matches!(self, Self::Not | Self::Neg)
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Self::Not => "~",
+            Self::Neg => "-",
+            Self::Abs => "ABS",
+            Self::Sgn => "SGN",
+            Self::Sin => "SIN",
+            Self::Cos => "COS",
+            Self::Tan => "TAN",
+            Self::Asin => "ASIN",
+            Self::Acos => "ACOS",
+            Self::Atan => "ATAN",
+            Self::Exp => "EXP",
+            Self::Ln => "LN",
+            Self::Lg => "LG",
+            Self::Sqrt => "SQRT",
+            Self::Trunc => "TRUNC",
+            Self::Floor => "FLOOR",
+            Self::Ceil => "CEIL",
+            Self::Round => "ROUND",
+        }
+    }
+}
+
+/// Parses a formula.
+///
+/// # Panics
+/// Panics if `s` is not a valid formula, use [`try_parse`] for formulas from untrusted sources.
 #[must_use]
-#[tracing::instrument(level = "trace")]
 pub fn parse(s: &str) -> Expr {
+    match parse_formula(s) {
+        Ok(expr) => expr,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+/// Parses a formula, returns an error if `s` is not a valid formula.
+pub fn try_parse(s: &str) -> GenApiResult<Expr> {
+    parse_formula(s).map_err(|e| GenApiError::invalid_data(e.into()))
+}
+
+#[tracing::instrument(level = "trace")]
+pub(crate) fn parse_formula(s: &str) -> Result<Expr, String> {
     debug!("start parsing expression in `formula`");
     let lexer = Lexer::new(s);
-    Parser { lexer }.expr()
+    let mut parser = Parser { lexer };
+    let expr = parser.expr();
+    match parser.lexer.error.take() {
+        Some(e) => Err(e),
+        None => expr,
+    }
 }
 
 struct Parser<'a> {
@@ -355,12 +812,12 @@ struct Parser<'a> {
 macro_rules! parse_binop {
     ($self:ident.$f:ident, ($token:expr, $op:expr) $(,($token_rep:expr, $op_rep:expr))*) => {
         {
-        let mut expr = $self.$f();
+        let mut expr = $self.$f()?;
         loop {
             let (op_kind, rhs) = if $self.eat(&$token) {
-                ($op, $self.$f())
+                ($op, $self.$f()?)
             } $(else if $self.eat(&$token_rep) {
-                ($op_rep, $self.$f())
+                ($op_rep, $self.$f()?)
             })* else {
                 break;
             };
@@ -370,49 +827,49 @@ macro_rules! parse_binop {
                 rhs: rhs.into(),
             };
         }
-        expr
+        Ok(expr)
         }
     }
 }
 
 impl<'a> Parser<'a> {
-    fn expr(&mut self) -> Expr {
-        let expr = self.logical_or();
+    fn expr(&mut self) -> Result<Expr, String> {
+        let expr = self.logical_or()?;
         if self.eat(&Token::Question) {
-            let then = self.expr();
-            self.expect(&Token::Colon);
-            let else_ = self.expr();
-            Expr::If {
+            let then = self.expr()?;
+            self.expect(&Token::Colon)?;
+            let else_ = self.expr()?;
+            Ok(Expr::If {
                 cond: expr.into(),
                 then: then.into(),
                 else_: else_.into(),
-            }
+            })
         } else {
-            expr
+            Ok(expr)
         }
     }
 
-    fn logical_or(&mut self) -> Expr {
+    fn logical_or(&mut self) -> Result<Expr, String> {
         parse_binop!(self.logical_and, (Token::DoubleOr, BinOpKind::Or))
     }
 
-    fn logical_and(&mut self) -> Expr {
+    fn logical_and(&mut self) -> Result<Expr, String> {
         parse_binop!(self.bitwise_or, (Token::DoubleAnd, BinOpKind::And))
     }
 
-    fn bitwise_or(&mut self) -> Expr {
+    fn bitwise_or(&mut self) -> Result<Expr, String> {
         parse_binop!(self.bitwise_xor, (Token::Or, BinOpKind::BitOr))
     }
 
-    fn bitwise_xor(&mut self) -> Expr {
+    fn bitwise_xor(&mut self) -> Result<Expr, String> {
         parse_binop!(self.bitwise_and, (Token::Caret, BinOpKind::Xor))
     }
 
-    fn bitwise_and(&mut self) -> Expr {
+    fn bitwise_and(&mut self) -> Result<Expr, String> {
         parse_binop!(self.eq, (Token::And, BinOpKind::BitAnd))
     }
 
-    fn eq(&mut self) -> Expr {
+    fn eq(&mut self) -> Result<Expr, String> {
         parse_binop!(
             self.rel,
             (Token::Eq, BinOpKind::Eq),
@@ -420,7 +877,7 @@ impl<'a> Parser<'a> {
         )
     }
 
-    fn rel(&mut self) -> Expr {
+    fn rel(&mut self) -> Result<Expr, String> {
         parse_binop!(
             self.bit_shift,
             (Token::Lt, BinOpKind::Lt),
@@ -430,7 +887,7 @@ impl<'a> Parser<'a> {
         )
     }
 
-    fn bit_shift(&mut self) -> Expr {
+    fn bit_shift(&mut self) -> Result<Expr, String> {
         parse_binop!(
             self.term,
             (Token::Shl, BinOpKind::Shl),
@@ -438,7 +895,7 @@ impl<'a> Parser<'a> {
         )
     }
 
-    fn term(&mut self) -> Expr {
+    fn term(&mut self) -> Result<Expr, String> {
         parse_binop!(
             self.factor,
             (Token::Plus, BinOpKind::Add),
@@ -446,7 +903,7 @@ impl<'a> Parser<'a> {
         )
     }
 
-    fn factor(&mut self) -> Expr {
+    fn factor(&mut self) -> Result<Expr, String> {
         parse_binop!(
             self.unop,
             (Token::Star, BinOpKind::Mul),
@@ -455,19 +912,19 @@ impl<'a> Parser<'a> {
         )
     }
 
-    fn unop(&mut self) -> Expr {
+    fn unop(&mut self) -> Result<Expr, String> {
         if self.eat(&Token::Tilde) {
-            let expr = self.unop();
-            Expr::UnOp {
+            let expr = self.unop()?;
+            Ok(Expr::UnOp {
                 kind: UnOpKind::Not,
                 expr: expr.into(),
-            }
+            })
         } else if self.eat(&Token::Minus) {
-            let expr = self.unop();
-            Expr::UnOp {
+            let expr = self.unop()?;
+            Ok(Expr::UnOp {
                 kind: UnOpKind::Neg,
                 expr: expr.into(),
-            }
+            })
         } else {
             // Eat unary `+` if exists.
             self.eat(&Token::Plus);
@@ -475,31 +932,33 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn pow(&mut self) -> Expr {
-        let expr = self.primary();
+    fn pow(&mut self) -> Result<Expr, String> {
+        let expr = self.primary()?;
         if self.eat(&Token::DoubleStar) {
-            let rhs = self.unop();
-            Expr::BinOp {
+            let rhs = self.unop()?;
+            Ok(Expr::BinOp {
                 kind: BinOpKind::Pow,
                 lhs: expr.into(),
                 rhs: rhs.into(),
-            }
+            })
         } else {
-            expr
+            Ok(expr)
         }
     }
 
-    fn primary(&mut self) -> Expr {
+    fn primary(&mut self) -> Result<Expr, String> {
         if self.eat(&Token::LParen) {
-            let expr = self.expr();
-            self.expect(&Token::RParen);
-            expr
+            let expr = self.expr()?;
+            self.expect(&Token::RParen)?;
+            Ok(expr)
         } else if let Some(i) = self.next_integer() {
-            Expr::Integer(i)
+            Ok(Expr::Integer(i))
         } else if let Some(f) = self.next_float() {
-            Expr::Float(f)
+            Ok(Expr::Float(f))
         } else {
-            let s = self.next_ident().unwrap();
+            let s = self
+                .next_ident()
+                .ok_or_else(|| self.error("expected an operand"))?;
             if self.eat(&Token::LParen) {
                 let op = match s.as_str() {
                     "NEG" => UnOpKind::Neg,
@@ -510,6 +969,7 @@ impl<'a> Parser<'a> {
                     "ACOS" => UnOpKind::Acos,
                     "ATAN" => UnOpKind::Atan,
                     "ABS" => UnOpKind::Abs,
+                    "SGN" => UnOpKind::Sgn,
                     "EXP" => UnOpKind::Exp,
                     "LN" => UnOpKind::Ln,
                     "LG" => UnOpKind::Lg,
@@ -518,16 +978,16 @@ impl<'a> Parser<'a> {
                     "FLOOR" => UnOpKind::Floor,
                     "CEIL" => UnOpKind::Ceil,
                     "ROUND" => UnOpKind::Round,
-                    other => panic!("{} is not a keyword or function name", other),
+                    other => return Err(format!("{} is not a keyword or function name", other)),
                 };
-                let expr = self.expr();
-                self.expect(&Token::RParen);
-                Expr::UnOp {
+                let expr = self.expr()?;
+                self.expect(&Token::RParen)?;
+                Ok(Expr::UnOp {
                     kind: op,
                     expr: expr.into(),
-                }
+                })
             } else {
-                Expr::Ident(s)
+                Ok(Expr::Ident(s))
             }
         }
     }
@@ -578,8 +1038,20 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn expect(&mut self, tok: &Token) {
-        assert!(self.eat(tok))
+    fn expect(&mut self, tok: &Token) -> Result<(), String> {
+        if self.eat(tok) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{:?}`", tok)))
+        }
+    }
+
+    /// Returns the lexer error if exists since it hides the following tokens.
+    fn error(&mut self, message: &str) -> String {
+        self.lexer
+            .error
+            .take()
+            .unwrap_or_else(|| format!("{} in formula", message))
     }
 }
 
@@ -619,6 +1091,8 @@ struct Lexer<'a> {
     peek: Option<Token>,
     cur: usize,
     peek_char: Option<(char, usize)>,
+    /// Set when the lexer finds an invalid token, the lexer returns no token after that.
+    error: Option<String>,
 }
 
 impl<'a> Lexer<'a> {
@@ -629,6 +1103,7 @@ impl<'a> Lexer<'a> {
             peek: None,
             cur: 0,
             peek_char: None,
+            error: None,
         }
     }
 
@@ -641,6 +1116,9 @@ impl<'a> Lexer<'a> {
         if let Some(ref peek) = self.peek {
             return Some(peek);
         }
+        if self.error.is_some() {
+            return None;
+        }
 
         while self.eat_char(|c| c.is_whitespace() || c.is_ascii_control()) {}
 
@@ -701,8 +1179,11 @@ impl<'a> Lexer<'a> {
                 let start_pos = self.cur - 1;
                 while self.eat_char(char::is_numeric) {}
                 let end_pos = self.cur;
-                let f = f64::from_str(self.sub_string(start_pos, end_pos)).unwrap();
-                Token::Float(f)
+                let s = self.sub_string(start_pos, end_pos);
+                match f64::from_str(s) {
+                    Ok(f) => Token::Float(f),
+                    Err(_) => return self.set_error(format!("invalid float `{}`", s)),
+                }
             }
 
             c if c.is_alphabetic() => {
@@ -713,12 +1194,15 @@ impl<'a> Lexer<'a> {
             }
 
             c if c.is_numeric() => {
-                if c == '0' && self.eat_char(|c| c == 'x') {
+                if c == '0' && self.eat_char(|c| c == 'x' || c == 'X') {
                     let start_pos = self.cur;
                     while self.eat_char(|c| c.is_ascii_hexdigit()) {}
                     let end_pos = self.cur;
-                    let i = i64::from_str_radix(self.sub_string(start_pos, end_pos), 16).unwrap();
-                    Token::Integer(i)
+                    let s = self.sub_string(start_pos, end_pos);
+                    match i64::from_str_radix(s, 16) {
+                        Ok(i) => Token::Integer(i),
+                        Err(_) => return self.set_error(format!("invalid integer `0x{}`", s)),
+                    }
                 } else {
                     let start_pos = self.cur - 1;
                     let mut is_integer = true;
@@ -731,22 +1215,38 @@ impl<'a> Lexer<'a> {
                         }
                     };
                     while self.eat_char(&mut check_digit) {}
+                    // Eat exponent part, e.g. `1.5E-3`.
+                    if self.is_exponent_start() {
+                        is_integer = false;
+                        self.next_char();
+                        self.eat_char(|c| c == '+' || c == '-');
+                        while self.eat_char(char::is_numeric) {}
+                    }
                     let end_pos = self.cur;
                     let s = self.sub_string(start_pos, end_pos);
-                    if is_integer {
-                        Token::Integer(i64::from_str(s).unwrap())
+                    let token = if is_integer {
+                        i64::from_str(s).ok().map(Token::Integer)
                     } else {
-                        Token::Float(f64::from_str(s).unwrap())
+                        f64::from_str(s).ok().map(Token::Float)
+                    };
+                    match token {
+                        Some(token) => token,
+                        None => return self.set_error(format!("invalid number `{}`", s)),
                     }
                 }
             }
 
-            c => panic!("unexpected character `{}` in formula", c),
+            c => return self.set_error(format!("unexpected character `{}` in formula", c)),
         });
 
         self.peek.as_ref()
     }
 
+    fn set_error(&mut self, message: String) -> Option<&Token> {
+        self.error = Some(message);
+        None
+    }
+
     fn next_char(&mut self) -> Option<char> {
         self.peek_char();
         if let Some((peek, idx)) = self.peek_char.take() {
@@ -801,6 +1301,20 @@ impl<'a> Lexer<'a> {
         Some(peek)
     }
 
+    /// Returns `true` if the following characters are an exponent part of a float literal, i.e.
+    /// `e` or `E` followed by digits with an optional sign.
+    fn is_exponent_start(&self) -> bool {
+        let is_digit = |n: usize| {
+            self.src
+                .get(self.cur + n)
+                .map_or(false, |c| (*c as char).is_numeric())
+        };
+
+        (self.peek_char_raw('e', 0) || self.peek_char_raw('E', 0))
+            && (is_digit(1)
+                || ((self.peek_char_raw('+', 1) || self.peek_char_raw('-', 1)) && is_digit(2)))
+    }
+
     fn peek_char_raw(&self, c: char, n: usize) -> bool {
         self.src
             .get(self.cur + n)
@@ -845,6 +1359,20 @@ mod tests {
         let t = Lexer::new("  10 ").next().unwrap();
         assert_eq!(Token::Integer(10), t);
 
+        let t = Lexer::new("0XFF").next().unwrap();
+        assert_eq!(Token::Integer(0xff), t);
+
+        let t = Lexer::new("1.5E-3").next().unwrap();
+        assert_eq!(Token::Float(1.5E-3), t);
+
+        let t = Lexer::new("2e3").next().unwrap();
+        assert_eq!(Token::Float(2e3), t);
+
+        let mut lexer = Lexer::new("2*E");
+        assert_eq!(Token::Integer(2), lexer.next().unwrap());
+        assert_eq!(Token::Star, lexer.next().unwrap());
+        assert_eq!(Token::Ident("E".into()), lexer.next().unwrap());
+
         let mut lexer = Lexer::new("&&||<>**>><<");
         assert_eq!(Token::DoubleAnd, lexer.next().unwrap());
         assert_eq!(Token::DoubleOr, lexer.next().unwrap());
@@ -902,4 +1430,171 @@ mod tests {
         test_eval_impl("ABS(VAR1 + 1 / 4 - 1.25) < EPS", &env);
         test_eval_impl("( EXP = 1 ) ? 1 : 0", &env);
     }
+
+    #[test]
+    fn test_eval_math_functions() {
+        let env = vec![("EPS", Expr::Float(1e-9))].into_iter().collect();
+
+        test_eval_impl("(SGN(-5) = -1) && (SGN(0) = 0) && (SGN(3) = 1)", &env);
+        test_eval_impl("SGN(-0.5) = -1.", &env);
+        test_eval_impl("TRUNC(-2.7) = -2.", &env);
+        test_eval_impl("FLOOR(-2.3) = -3.", &env);
+        test_eval_impl("CEIL(2.3) = 3.", &env);
+        test_eval_impl("ROUND(2.5) = 3.", &env);
+        test_eval_impl("ABS(ASIN(1) - PI / 2) < EPS", &env);
+        test_eval_impl("ABS(ACOS(1)) < EPS", &env);
+        test_eval_impl("ABS(ATAN(1) - PI / 4) < EPS", &env);
+        test_eval_impl("ABS(EXP(1) - E) < EPS", &env);
+        test_eval_impl("ABS(LG(1000) - 3) < EPS", &env);
+        test_eval_impl("ABS(SQRT(16) - 4) < EPS", &env);
+        test_eval_impl("ABS(1.5E-3 * 1e3 - 1.5) < EPS", &env);
+    }
+
+    #[test]
+    fn test_display() {
+        let expr = parse("(1+2)*3");
+        assert_eq!(expr.to_string(), "(1 + 2) * 3");
+
+        let expr = parse("1 - (2 - 3)");
+        assert_eq!(expr.to_string(), "1 - (2 - 3)");
+
+        let expr = parse("(2 ** 3) ** 2");
+        assert_eq!(expr.to_string(), "(2 ** 3) ** 2");
+
+        let expr = parse("(A &lt; 1) ? SIN(-B) : ~C");
+        assert_eq!(expr.to_string(), "A < 1 ? SIN(-B) : ~C");
+
+        let expr = parse("(A ? 1 : 0) + 1.5");
+        assert_eq!(expr.to_string(), "(A ? 1 : 0) + 1.5");
+
+        let expr = Expr::BinOp {
+            kind: BinOpKind::Pow,
+            lhs: Expr::Integer(-2).into(),
+            rhs: Expr::Float(1.0).into(),
+        };
+        assert_eq!(expr.to_string(), "(-2) ** 1.0");
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let formulas = [
+            "(1 + 2 * 3 - 6) = 1",
+            "2 ** 3 ** 2",
+            "-1 ** 2",
+            "2 ** -1 ** 2",
+            "(1 << 2 + 2 >> 1) = 8",
+            "1 || 1 && 0",
+            "(0xff00 &amp; 0xf0f0) | 0x0ff0 ^ 1",
+            "~(A - 1) &amp; B",
+            "ABS(SGN(-5) + TRUNC(1.5E-3) + FLOOR(PI) + CEIL(E))",
+            "A &gt;= 1 ? B : C ? D : E.Max",
+            "(A ? B : C) ? D : F",
+            "TO / (1 &lt;&lt; P1) - 0.25",
+            "1 - (2 + 3) - (4 - 5)",
+        ];
+
+        for formula in &formulas {
+            let expr = parse(formula);
+            assert_eq!(parse(&expr.to_string()), expr, "{}", formula);
+        }
+    }
+
+    #[test]
+    fn test_eval_converter_formulas() {
+        // Formulas taken from `Converter` and `IntConverter` nodes of vendor XMLs.
+        let env = vec![
+            ("FROM", Expr::Integer(0x1234)),
+            ("TO", Expr::Float(100.0)),
+            ("P1", Expr::Integer(4)),
+            ("EPS", Expr::Float(1e-9)),
+        ]
+        .into_iter()
+        .collect();
+
+        test_eval_impl("((FROM &gt;&gt; 8) &amp; 0xFF) = 0x12", &env);
+        test_eval_impl("((FROM &lt;&lt; 4) | 0xF) = 0x1234F", &env);
+        test_eval_impl("((FROM ^ 0xFFFF) &amp; 0xFF) = 0xCB", &env);
+        test_eval_impl("ABS(TO/(1&lt;&lt;P1) - 6.25) < EPS", &env);
+        test_eval_impl("ROUND(TO * (1 &lt;&lt; P1)) = 1600", &env);
+        test_eval_impl("(FROM &gt;= 0x1000) ? 1 : 0", &env);
+    }
+
+    #[test]
+    fn test_compile() {
+        let slots = ["FROM", "TO"];
+        let slot_values = [
+            EvaluationResult::Integer(0x1234),
+            EvaluationResult::Float(100.0),
+        ];
+        let bindings: HashMap<&str, Expr> = vec![
+            ("P1", Expr::Integer(4)),
+            ("EPS", Expr::Float(1e-9)),
+            ("Shifted", parse("FROM &gt;&gt; P1")),
+        ]
+        .into_iter()
+        .collect();
+
+        let mut var_env = bindings.clone();
+        var_env.insert("FROM", Expr::Integer(0x1234));
+        var_env.insert("TO", Expr::Float(100.0));
+
+        let formulas = [
+            "((FROM &gt;&gt; 8) &amp; 0xFF) = 0x12",
+            "ABS(TO/(1&lt;&lt;P1) - 6.25) < EPS",
+            "Shifted + TO * 2",
+            "FROM &gt;= 0x1000 ? TO : -TO",
+            "FROM &lt; 0x1000 ? TO : Shifted ? 1 : 2",
+            "0 &amp;&amp; (1 / 0)",
+            "1 || (1 / 0)",
+            "(FROM &amp;&amp; 3) + (0 || TO)",
+            "2 ** 3 ** 2 - SGN(-FROM)",
+        ];
+
+        for formula in &formulas {
+            let expr = parse(formula);
+            let formula = Formula {
+                expr,
+                compiled: None,
+            };
+            let compiled = formula.compile(&slots, &bindings).unwrap();
+            assert_eq!(
+                compiled.eval(&slot_values).unwrap(),
+                formula.eval(&var_env).unwrap(),
+                "{}",
+                formula
+            );
+        }
+    }
+
+    #[test]
+    fn test_compile_error() {
+        let compile = |formula: &str, bindings: &HashMap<&str, Expr>| {
+            Formula {
+                expr: parse(formula),
+                compiled: None,
+            }
+            .compile(&["FROM"], bindings)
+        };
+
+        // Unknown identifier.
+        assert!(compile("FROM + TO", &HashMap::new()).is_err());
+
+        // Cyclic reference.
+        let bindings = vec![("A", parse("B + 1")), ("B", parse("A * 2"))]
+            .into_iter()
+            .collect();
+        assert!(compile("FROM + A", &bindings).is_err());
+
+        // Slot number mismatch.
+        let compiled = compile("FROM + 1", &HashMap::new()).unwrap();
+        assert!(compiled.eval(&[]).is_err());
+    }
+
+    #[test]
+    fn test_try_parse() {
+        assert!(try_parse("(1 + 2) * 3").is_ok());
+        assert!(try_parse("1 +").is_err());
+        assert!(try_parse("(1 + 2").is_err());
+        assert!(try_parse("1 $ 2").is_err());
+    }
 }
@@ -12,6 +12,7 @@ use super::{
 };
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IntConverterNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -98,9 +99,8 @@ impl IInteger for IntConverterNode {
         let mut collector =
             utils::FormulaEnvCollector::new(&self.p_variables, &self.constants, &self.expressions);
         collector.insert("TO", self.p_value(), device, store, cx)?;
-        let var_env = collector.collect(device, store, cx)?;
 
-        let eval_result = self.formula_from.eval(&var_env)?;
+        let eval_result = collector.eval(&self.formula_from, device, store, cx)?;
         Ok(eval_result.as_integer())
     }
 
@@ -119,9 +119,8 @@ impl IInteger for IntConverterNode {
         let mut collector =
             utils::FormulaEnvCollector::new(&self.p_variables, &self.constants, &self.expressions);
         collector.insert_imm("FROM", value);
-        let var_env = collector.collect(device, store, cx)?;
 
-        let eval_result = self.formula_to.eval(&var_env)?;
+        let eval_result = collector.eval(&self.formula_to, device, store, cx)?;
         utils::set_eval_result(self.p_value, eval_result, device, store, cx)?;
         Ok(())
     }
@@ -12,6 +12,7 @@ use super::{
 };
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IntSwissKnifeNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -77,10 +78,9 @@ impl IInteger for IntSwissKnifeNode {
         store: &impl NodeStore,
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<i64> {
-        let var_env =
+        let eval_result =
             utils::FormulaEnvCollector::new(&self.p_variables, &self.constants, &self.expressions)
-                .collect(device, store, cx)?;
-        let eval_result = self.formula.eval(&var_env)?;
+                .eval(&self.formula, device, store, cx)?;
         Ok(eval_result.as_integer())
     }
 
@@ -8,10 +8,11 @@ use super::{
     ivalue::IValue,
     node_base::{NodeAttributeBase, NodeBase, NodeElementBase},
     store::{CacheStore, IntegerId, NodeId, NodeStore, ValueStore},
-    Device, GenApiResult, ValueCtxt,
+    utils, Device, GenApiResult, ValueCtxt,
 };
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IntegerNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -96,7 +97,9 @@ impl IInteger for IntegerNode {
         store: &impl NodeStore,
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<()> {
-        cx.invalidate_cache_by(self.node_base().id());
+        let nid = self.node_base().id();
+        utils::verify_no_busy_command(nid, &self.p_selected, device, store, cx)?;
+        cx.invalidate_cache_by(nid);
         self.value_kind().set_value(value, device, store, cx)
     }
 
@@ -12,6 +12,7 @@ use super::{
 };
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum IncrementMode {
     FixedIncrement,
     /// NOTE: `ListIncrement` is not supported in `GenApiSchema Version 1.1` yet.
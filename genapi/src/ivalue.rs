@@ -107,6 +107,9 @@ macro_rules! impl_ivalue_for_vid {
                 _: &impl NodeStore,
                 cx: &mut ValueCtxt<U, S>,
             ) -> GenApiResult<()> {
+                if cx.is_read_only() {
+                    return Err(GenApiError::not_writable());
+                }
                 cx.value_store_mut().update(*self, value);
                 Ok(())
             }
@@ -124,9 +127,9 @@ macro_rules! impl_ivalue_for_vid {
                 &self,
                 _: &mut impl Device,
                 _: &impl NodeStore,
-                _: &mut ValueCtxt<U, S>,
+                cx: &mut ValueCtxt<U, S>,
             ) -> GenApiResult<bool> {
-                Ok(true)
+                Ok(!cx.is_read_only())
             }
         }
     };
@@ -13,11 +13,16 @@
 )]
 
 pub mod builder;
+pub mod diff;
 pub mod elem_type;
 pub mod formula;
 pub mod interface;
+pub mod lint;
 pub mod parser;
+pub mod programmatic;
 pub mod store;
+pub mod validation;
+pub mod writer;
 
 mod boolean;
 mod category;
@@ -44,7 +49,7 @@ mod swiss_knife;
 mod utils;
 
 pub use boolean::BooleanNode;
-pub use category::CategoryNode;
+pub use category::{CategoryNode, CategoryTree, CategoryTreeEntry};
 pub use command::CommandNode;
 pub use converter::ConverterNode;
 pub use enumeration::{EnumEntryNode, EnumerationNode};
@@ -66,7 +71,10 @@ pub use string::StringNode;
 pub use string_reg::StringRegNode;
 pub use swiss_knife::SwissKnifeNode;
 
-use std::borrow::Cow;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
 
 use auto_impl::auto_impl;
 use tracing::error;
@@ -119,6 +127,12 @@ pub enum GenApiError {
     /// Invalid buffer.
     #[error("invalid buffer: {0}")]
     InvalidBuffer(Cow<'static, str>),
+
+    /// The operation conflicts with a command which is still executing on the device.
+    ///
+    /// e.g. try to change a selector while the command selected by it is not done yet.
+    #[error("busy: {0}")]
+    Busy(Cow<'static, str>),
 }
 
 impl GenApiError {
@@ -157,14 +171,56 @@ impl GenApiError {
         error!("{}", err);
         err
     }
+
+    fn busy(inner: Cow<'static, str>) -> Self {
+        let err = GenApiError::Busy(inner);
+        error!("{}", err);
+        err
+    }
 }
 
 pub type GenApiResult<T> = std::result::Result<T, GenApiError>;
 
-#[derive(Clone, Debug)]
+/// Statistics of accesses to the cache of register values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CacheStats {
+    /// The number of register reads served from the cache.
+    pub hits: u64,
+    /// The number of register reads which missed the cache and accessed the device.
+    pub misses: u64,
+}
+
+impl CacheStats {
+    /// Returns the ratio of hits to all reads, or `0.0` if nothing has been read.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn hit_ratio(self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ValueCtxt<T, U> {
     pub value_store: T,
     pub cache_store: U,
+    /// Command nodes whose execution has been requested but not confirmed as done yet.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    busy_commands: HashSet<store::NodeId>,
+    /// Chunk data of the current payload keyed by chunk ID, which are read through ports with
+    /// `ChunkID`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    chunk_data: HashMap<u64, Vec<u8>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    cache_stats: CacheStats,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    read_only: bool,
 }
 
 impl<T, U> ValueCtxt<T, U> {
@@ -172,6 +228,10 @@ impl<T, U> ValueCtxt<T, U> {
         Self {
             value_store,
             cache_store,
+            busy_commands: HashSet::new(),
+            chunk_data: HashMap::new(),
+            cache_stats: CacheStats::default(),
+            read_only: false,
         }
     }
 
@@ -229,4 +289,65 @@ impl<T, U> ValueCtxt<T, U> {
     {
         self.cache_store.clear()
     }
+
+    /// Returns `true` if writes to values held in the context are rejected.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Sets whether writes to values held in the context, e.g. immediate `<Value>` of nodes, are
+    /// rejected with [`GenApiError::NotWritable`]. Returns the previous setting.
+    ///
+    /// Writes to the device are not affected.
+    pub fn set_read_only(&mut self, read_only: bool) -> bool {
+        std::mem::replace(&mut self.read_only, read_only)
+    }
+
+    /// Returns statistics of cache accesses since the context is created.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache_stats
+    }
+
+    pub(crate) fn record_cache_access(&mut self, is_hit: bool) {
+        if is_hit {
+            self.cache_stats.hits += 1;
+        } else {
+            self.cache_stats.misses += 1;
+        }
+    }
+
+    /// Returns `true` if the command node is executed and its completion has not been observed
+    /// through `ICommand::is_done` yet.
+    pub fn is_command_busy(&self, nid: store::NodeId) -> bool {
+        self.busy_commands.contains(&nid)
+    }
+
+    /// Returns command nodes which are executed and whose completion has not been observed yet.
+    pub fn busy_commands(&self) -> impl Iterator<Item = store::NodeId> + '_ {
+        self.busy_commands.iter().copied()
+    }
+
+    pub(crate) fn mark_command_busy(&mut self, nid: store::NodeId) {
+        self.busy_commands.insert(nid);
+    }
+
+    pub(crate) fn mark_command_done(&mut self, nid: store::NodeId) {
+        self.busy_commands.remove(&nid);
+    }
+
+    /// Sets the data of the chunk with `id`, which is read by nodes through ports whose
+    /// `ChunkID` is `id`.
+    pub fn set_chunk_data(&mut self, id: u64, data: Vec<u8>) {
+        self.chunk_data.insert(id, data);
+    }
+
+    /// Returns the data of the chunk with `id` if set.
+    pub fn chunk_data(&self, id: u64) -> Option<&[u8]> {
+        self.chunk_data.get(&id).map(Vec::as_slice)
+    }
+
+    /// Removes all chunk data set by [`Self::set_chunk_data`].
+    pub fn clear_chunk_data(&mut self) {
+        self.chunk_data.clear();
+    }
 }
@@ -0,0 +1,426 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains [`lint`] that checks a device description file for spec violations and
+//! suspicious constructs.
+//!
+//! In addition to the problems reported by [`validation::validate`], which always break
+//! evaluation, the linter reports constructs which work but are likely to be mistakes of the
+//! author. Each [`Lint`] has a [`Severity`] so that tools can decide which ones to fail on.
+//!
+//! # Examples
+//! ```rust
+//! use cameleon_genapi::{
+//!     builder::GenApiBuilder,
+//!     lint::{self, Severity},
+//!     store::DefaultNodeStore,
+//! };
+//!
+//! let mut builder = GenApiBuilder::<DefaultNodeStore>::default().programmatic("Model", "Vendor");
+//! builder.category("Root").feature("Gain").finish();
+//! builder.float("Gain").value(1.0).min(0.0).finish();
+//! let (_, node_store, value_ctxt) = builder.build();
+//!
+//! let lints = lint::lint(&node_store, &value_ctxt.value_store);
+//! assert_eq!(lints.len(), 1);
+//! assert_eq!(lints[0].severity(), Severity::Warning);
+//! assert_eq!(lints[0].to_string(), "`Gain` doesn't specify `Max`");
+//! ```
+
+use std::{collections::BTreeMap, fmt};
+
+use string_interner::Symbol;
+
+use super::{
+    builder::GenApiBuilder,
+    elem_type::{CachingMode, ImmOrPNode, IntegerRepresentation, Visibility},
+    parser::ParseResult,
+    store::{DefaultNodeStore, NodeData, NodeStore, ValueStore},
+    validation::{self, Diagnostic},
+    RegisterBase,
+};
+
+/// Severity of a [`Lint`]. Variants are ordered from the least severe one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Severity {
+    /// The construct is allowed, but worth reviewing.
+    Info,
+    /// The construct is likely to be a mistake.
+    Warning,
+    /// The construct violates the spec.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Info => write!(f, "info"),
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found by [`lint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Lint {
+    /// A problem found by [`validation::validate`].
+    Invalid(Diagnostic),
+
+    /// `Integer` or `Float` node doesn't specify its bound, so any value is accepted.
+    MissingBound {
+        /// Name of the node.
+        node: String,
+        /// Element name of the missing bound, either `Min` or `Max`.
+        element: &'static str,
+    },
+
+    /// A register is polled though caching is disabled, which makes polling meaningless.
+    PolledUncachedRegister {
+        /// Name of the register node.
+        node: String,
+    },
+
+    /// Entries of an `Enumeration` have the same value, so the current entry is ambiguous.
+    DuplicateEnumValue {
+        /// Name of the `Enumeration` node.
+        node: String,
+        /// Symbolic names of the entries.
+        entries: Vec<String>,
+        /// The duplicated value.
+        value: i64,
+    },
+
+    /// A category refers to an invisible node, which is never shown to users.
+    InvisibleFeature {
+        /// Name of the category node.
+        category: String,
+        /// Name of the invisible node.
+        feature: String,
+    },
+}
+
+impl Lint {
+    /// Returns the severity of the lint.
+    #[must_use]
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::Invalid(..) | Self::DuplicateEnumValue { .. } => Severity::Error,
+            Self::MissingBound { .. } | Self::PolledUncachedRegister { .. } => Severity::Warning,
+            Self::InvisibleFeature { .. } => Severity::Info,
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Invalid(diagnostic) => write!(f, "{}", diagnostic),
+            Self::MissingBound { node, element } => {
+                write!(f, "`{}` doesn't specify `{}`", node, element)
+            }
+            Self::PolledUncachedRegister { node } => write!(
+                f,
+                "`{}` has `PollingTime`, but its `Cachable` is `NoCache`",
+                node
+            ),
+            Self::DuplicateEnumValue {
+                node,
+                entries,
+                value,
+            } => {
+                write!(f, "entries of `{}` have the same value {}: ", node, value)?;
+                for (i, entry) in entries.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "`{}`", entry)?;
+                }
+                Ok(())
+            }
+            Self::InvisibleFeature { category, feature } => write!(
+                f,
+                "`{}` refers to invisible feature `{}`",
+                category, feature
+            ),
+        }
+    }
+}
+
+/// Checks the nodes in the stores and returns the found problems.
+///
+/// Problems reported by [`validation::validate`] come first, then the others follow in the
+/// order of the nodes in the original XML.
+/// An empty vector is returned if no problem is found.
+pub fn lint(node_store: &impl NodeStore, value_store: &impl ValueStore) -> Vec<Lint> {
+    let mut lints: Vec<_> = validation::validate(node_store)
+        .into_iter()
+        .map(Lint::Invalid)
+        .collect();
+
+    let mut ids = vec![];
+    node_store.visit_nodes(|data| ids.push(data.node_base().id()));
+    ids.sort_by_key(|nid| nid.to_usize());
+
+    for nid in ids {
+        let name = || nid.name(node_store).to_string();
+        match node_store.node(nid) {
+            NodeData::Integer(node) => {
+                let is_number = !matches!(
+                    node.representation,
+                    IntegerRepresentation::Boolean
+                        | IntegerRepresentation::IpV4Address
+                        | IntegerRepresentation::MacAddress
+                );
+                if is_number {
+                    let is_default = |bound: ImmOrPNode<_>, default| match bound {
+                        ImmOrPNode::Imm(id) => value_store.integer_value(id) == Some(default),
+                        ImmOrPNode::PNode(_) => false,
+                    };
+                    if is_default(node.min, node.representation.deduce_min()) {
+                        lints.push(missing_bound(name(), "Min"));
+                    }
+                    if is_default(node.max, node.representation.deduce_max()) {
+                        lints.push(missing_bound(name(), "Max"));
+                    }
+                }
+            }
+            NodeData::Float(node) => {
+                // Parser fills missing bounds with the extreme values.
+                let bound = |bound: ImmOrPNode<_>| match bound {
+                    ImmOrPNode::Imm(id) => value_store.float_value(id),
+                    ImmOrPNode::PNode(_) => None,
+                };
+                if bound(node.min).map_or(false, |min| min <= f64::MIN) {
+                    lints.push(missing_bound(name(), "Min"));
+                }
+                if bound(node.max).map_or(false, |max| max >= f64::MAX) {
+                    lints.push(missing_bound(name(), "Max"));
+                }
+            }
+            NodeData::Enumeration(node) => {
+                let mut values: BTreeMap<i64, Vec<String>> = BTreeMap::new();
+                for entry in &node.entries {
+                    if let Some(NodeData::EnumEntry(entry)) = node_store.node_opt(*entry) {
+                        values
+                            .entry(entry.value)
+                            .or_default()
+                            .push(entry.symbolic.clone());
+                    }
+                }
+                for (value, entries) in values {
+                    if entries.len() > 1 {
+                        lints.push(Lint::DuplicateEnumValue {
+                            node: name(),
+                            entries,
+                            value,
+                        });
+                    }
+                }
+            }
+            NodeData::Category(node) => {
+                for feature in &node.p_features {
+                    let is_invisible = node_store.node_opt(*feature).map_or(false, |data| {
+                        data.node_base().visibility() == Visibility::Invisible
+                    });
+                    if is_invisible {
+                        lints.push(Lint::InvisibleFeature {
+                            category: name(),
+                            feature: feature.name(node_store).into(),
+                        });
+                    }
+                }
+            }
+            NodeData::IntReg(node) => polled_uncached(&node.register_base, name, &mut lints),
+            NodeData::MaskedIntReg(node) => {
+                polled_uncached(&node.register_base, name, &mut lints);
+            }
+            NodeData::FloatReg(node) => polled_uncached(&node.register_base, name, &mut lints),
+            NodeData::StringReg(node) => polled_uncached(&node.register_base, name, &mut lints),
+            NodeData::Register(node) => polled_uncached(&node.register_base, name, &mut lints),
+            _ => {}
+        }
+    }
+
+    lints
+}
+
+/// Parses `xml` and checks the nodes in it, see [`lint`] for details.
+pub fn lint_xml(xml: &impl AsRef<str>) -> ParseResult<Vec<Lint>> {
+    let (_, node_store, value_ctxt) = GenApiBuilder::<DefaultNodeStore>::default()
+        .no_cache()
+        .build(xml)?;
+    Ok(lint(&node_store, &value_ctxt.value_store))
+}
+
+fn missing_bound(node: String, element: &'static str) -> Lint {
+    Lint::MissingBound { node, element }
+}
+
+fn polled_uncached(register_base: &RegisterBase, name: impl Fn() -> String, lints: &mut Vec<Lint>) {
+    if register_base.polling_time.is_some() && register_base.cacheable == CachingMode::NoCache {
+        lints.push(Lint::PolledUncachedRegister { node: name() });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::tests::register_description;
+
+    use super::*;
+
+    fn lint_nodes(nodes: &str) -> Vec<Lint> {
+        let nodes = format!(
+            r#"
+            <Category Name="Root">
+            </Category>
+            <Port Name="Device">
+            </Port>
+            {}
+            "#,
+            nodes
+        );
+        lint_xml(&register_description(&nodes)).unwrap()
+    }
+
+    #[test]
+    fn test_clean() {
+        let lints = lint_nodes(
+            r#"
+            <Integer Name="Width">
+                <Value>640</Value>
+                <Min>16</Min>
+                <Max>1280</Max>
+            </Integer>
+            <Integer Name="IpAddress">
+                <Value>0</Value>
+                <Representation>IPV4Address</Representation>
+            </Integer>
+            "#,
+        );
+        assert!(lints.is_empty());
+    }
+
+    #[test]
+    fn test_missing_bound() {
+        let lints = lint_nodes(
+            r#"
+            <Integer Name="Width">
+                <Value>640</Value>
+                <Max>1280</Max>
+            </Integer>
+            <Float Name="Gain">
+                <Value>1.0</Value>
+            </Float>
+            "#,
+        );
+        assert_eq!(
+            lints,
+            vec![
+                missing_bound("Width".into(), "Min"),
+                missing_bound("Gain".into(), "Min"),
+                missing_bound("Gain".into(), "Max"),
+            ]
+        );
+        assert!(lints.iter().all(|l| l.severity() == Severity::Warning));
+    }
+
+    #[test]
+    fn test_polled_uncached_register() {
+        let lints = lint_nodes(
+            r#"
+            <IntReg Name="Temperature">
+                <Address>0x1000</Address>
+                <Length>4</Length>
+                <AccessMode>RO</AccessMode>
+                <pPort>Device</pPort>
+                <Cachable>NoCache</Cachable>
+                <PollingTime>1000</PollingTime>
+            </IntReg>
+            "#,
+        );
+        assert_eq!(
+            lints,
+            vec![Lint::PolledUncachedRegister {
+                node: "Temperature".into()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_enum_value() {
+        let lints = lint_nodes(
+            r#"
+            <Enumeration Name="PixelFormat">
+                <EnumEntry Name="Mono8">
+                    <Value>1</Value>
+                </EnumEntry>
+                <EnumEntry Name="Mono8Alias">
+                    <Value>1</Value>
+                </EnumEntry>
+                <EnumEntry Name="Mono16">
+                    <Value>2</Value>
+                </EnumEntry>
+                <Value>1</Value>
+            </Enumeration>
+            "#,
+        );
+        assert_eq!(
+            lints,
+            vec![Lint::DuplicateEnumValue {
+                node: "PixelFormat".into(),
+                entries: vec!["Mono8".into(), "Mono8Alias".into()],
+                value: 1,
+            }]
+        );
+        assert_eq!(lints[0].severity(), Severity::Error);
+        assert_eq!(
+            lints[0].to_string(),
+            "entries of `PixelFormat` have the same value 1: `Mono8`, `Mono8Alias`"
+        );
+    }
+
+    #[test]
+    fn test_invisible_feature() {
+        let lints = lint_nodes(
+            r#"
+            <Category Name="Control">
+                <pFeature>Debug</pFeature>
+            </Category>
+            <Integer Name="Debug">
+                <Visibility>Invisible</Visibility>
+                <Value>0</Value>
+                <Min>0</Min>
+                <Max>1</Max>
+            </Integer>
+            "#,
+        );
+        assert_eq!(
+            lints,
+            vec![Lint::InvisibleFeature {
+                category: "Control".into(),
+                feature: "Debug".into(),
+            }]
+        );
+        assert_eq!(lints[0].severity(), Severity::Info);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let lints = lint_nodes(
+            r#"
+            <Integer Name="Width">
+                <pValue>WidthReg</pValue>
+                <Min>16</Min>
+                <Max>1280</Max>
+            </Integer>
+            "#,
+        );
+        assert_eq!(lints.len(), 1);
+        assert!(matches!(lints[0], Lint::Invalid(..)));
+        assert_eq!(lints[0].severity(), Severity::Error);
+    }
+}
@@ -12,6 +12,7 @@ use super::{
 };
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MaskedIntRegNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) register_base: RegisterBase,
@@ -110,6 +111,7 @@ impl IInteger for MaskedIntRegNode {
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<()> {
         let nid = self.node_base().id();
+        utils::verify_no_busy_command(nid, &self.p_selected, device, store, cx)?;
         cx.invalidate_cache_by(nid);
 
         let reg = self.register_base();
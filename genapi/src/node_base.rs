@@ -3,7 +3,7 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use super::{
-    elem_type::{AccessMode, MergePriority, NameSpace, Visibility},
+    elem_type::{AccessMode, Extensions, MergePriority, NameSpace, Visibility},
     store::{CacheStore, NodeId, NodeStore, ValueStore},
     utils::bool_from_id,
     Device, GenApiResult, ValueCtxt,
@@ -63,6 +63,14 @@ impl<'a> NodeBase<'a> {
         self.attr.expose_static
     }
 
+    /// Returns non-standard attributes and elements of the node.
+    ///
+    /// Always empty unless the XML is parsed in tolerant mode.
+    #[must_use]
+    pub fn extensions(&self) -> &'a Extensions {
+        &self.attr.extensions
+    }
+
     #[must_use]
     pub fn display_name(&self) -> Option<&'a str> {
         self.elem.display_name.as_deref()
@@ -105,14 +113,17 @@ impl<'a> NodeBase<'a> {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct NodeAttributeBase {
     pub(crate) id: NodeId,
     pub(crate) name_space: NameSpace,
     pub(crate) merge_priority: MergePriority,
     pub(crate) expose_static: Option<bool>,
+    pub(crate) extensions: Extensions,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct NodeElementBase {
     pub(crate) tooltip: Option<String>,
     pub(crate) description: Option<String>,
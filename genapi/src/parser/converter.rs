@@ -6,7 +6,7 @@ use tracing::debug;
 
 use crate::{
     builder::{CacheStoreBuilder, NodeStoreBuilder, ValueStoreBuilder},
-    ConverterNode,
+    utils, ConverterNode,
 };
 
 use super::{
@@ -14,7 +14,7 @@ use super::{
         CONSTANT, CONVERTER, DISPLAY_NOTATION, DISPLAY_PRECISION, EXPRESSION, IS_LINEAR,
         P_VARIABLE, REPRESENTATION, SLOPE, STREAMABLE, UNIT,
     },
-    xml, Parse,
+    xml, Parse, ParseResult,
 };
 
 impl Parse for ConverterNode {
@@ -24,28 +24,44 @@ impl Parse for ConverterNode {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         debug!("start parsing `ConverterNode`");
         debug_assert_eq!(node.tag_name(), CONVERTER);
 
-        let attr_base = node.parse(node_builder, value_builder, cache_builder);
-        let elem_base = node.parse(node_builder, value_builder, cache_builder);
+        let attr_base = node.parse(node_builder, value_builder, cache_builder)?;
+        let elem_base = node.parse(node_builder, value_builder, cache_builder)?;
 
         let streamable = node
-            .parse_if(STREAMABLE, node_builder, value_builder, cache_builder)
+            .parse_if(STREAMABLE, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
-        let p_variables = node.parse_while(P_VARIABLE, node_builder, value_builder, cache_builder);
-        let constants = node.parse_while(CONSTANT, node_builder, value_builder, cache_builder);
-        let expressions = node.parse_while(EXPRESSION, node_builder, value_builder, cache_builder);
-        let formula_to = node.parse(node_builder, value_builder, cache_builder);
-        let formula_from = node.parse(node_builder, value_builder, cache_builder);
-        let p_value = node.parse(node_builder, value_builder, cache_builder);
-        let unit = node.parse_if(UNIT, node_builder, value_builder, cache_builder);
+        let p_variables =
+            node.parse_while(P_VARIABLE, node_builder, value_builder, cache_builder)?;
+        let constants = node.parse_while(CONSTANT, node_builder, value_builder, cache_builder)?;
+        let expressions =
+            node.parse_while(EXPRESSION, node_builder, value_builder, cache_builder)?;
+        let mut formula_to = node.parse(node_builder, value_builder, cache_builder)?;
+        utils::compile_formula(
+            &mut formula_to,
+            &p_variables,
+            &constants,
+            &expressions,
+            &["FROM"],
+        );
+        let mut formula_from = node.parse(node_builder, value_builder, cache_builder)?;
+        utils::compile_formula(
+            &mut formula_from,
+            &p_variables,
+            &constants,
+            &expressions,
+            &["TO"],
+        );
+        let p_value = node.parse(node_builder, value_builder, cache_builder)?;
+        let unit = node.parse_if(UNIT, node_builder, value_builder, cache_builder)?;
         let representation = node
-            .parse_if(REPRESENTATION, node_builder, value_builder, cache_builder)
+            .parse_if(REPRESENTATION, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
         let display_notation = node
-            .parse_if(DISPLAY_NOTATION, node_builder, value_builder, cache_builder)
+            .parse_if(DISPLAY_NOTATION, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
         let display_precision = node
             .parse_if(
@@ -53,16 +69,16 @@ impl Parse for ConverterNode {
                 node_builder,
                 value_builder,
                 cache_builder,
-            )
+            )?
             .unwrap_or(6);
         let slope = node
-            .parse_if(SLOPE, node_builder, value_builder, cache_builder)
+            .parse_if(SLOPE, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
         let is_linear = node
-            .parse_if(IS_LINEAR, node_builder, value_builder, cache_builder)
+            .parse_if(IS_LINEAR, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
 
-        Self {
+        Ok(Self {
             attr_base,
             elem_base,
             streamable,
@@ -78,7 +94,7 @@ impl Parse for ConverterNode {
             display_precision,
             slope,
             is_linear,
-        }
+        })
     }
 }
 
@@ -22,7 +22,7 @@ use super::{
         ADDRESS, BIT, INDEX, INT_SWISS_KNIFE, NAME, OFFSET, P_ADDRESS, P_INDEX, P_OFFSET, P_VALUE,
         P_VALUE_COPY, P_VALUE_INDEXED, VALUE, VALUE_INDEXED,
     },
-    xml, Parse,
+    xml, Parse, ParseResult,
 };
 
 macro_rules! match_text_view{
@@ -31,11 +31,11 @@ macro_rules! match_text_view{
         $($s:expr => $var:expr,)*
     ) => {
         if $text == $s1 {
-            $var1
+            Ok($var1)
         } $(else if $text == $s {
-            $var
+            Ok($var)
         })* else {
-            unreachable!()
+            Err($text.error(format!("unexpected value `{}`", $text)))
         }
     }
 }
@@ -46,13 +46,11 @@ impl Default for NameSpace {
     }
 }
 
-impl From<&str> for NameSpace {
-    fn from(value: &str) -> Self {
-        match value {
-            "Standard" => Self::Standard,
-            "Custom" => Self::Custom,
-            _ => unreachable!(),
-        }
+pub(super) fn convert_to_name_space(value: &str) -> Option<NameSpace> {
+    match value {
+        "Standard" => Some(NameSpace::Standard),
+        "Custom" => Some(NameSpace::Custom),
+        _ => None,
     }
 }
 
@@ -62,8 +60,8 @@ impl Parse for NameSpace {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
+    ) -> ParseResult<Self> {
+        let text = node.next_text()?;
         match_text_view!(text,
             "Standard" => Self::Standard,
             "Custom" => Self::Custom,
@@ -83,8 +81,8 @@ impl Parse for Visibility {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
+    ) -> ParseResult<Self> {
+        let text = node.next_text()?;
         match_text_view!(text,
             "Beginner" => Self::Beginner,
             "Expert" => Self::Expert,
@@ -94,14 +92,12 @@ impl Parse for Visibility {
     }
 }
 
-impl From<&str> for MergePriority {
-    fn from(value: &str) -> Self {
-        match value {
-            "1" => Self::High,
-            "0" => Self::Mid,
-            "-1" => Self::Low,
-            _ => unreachable!(),
-        }
+pub(super) fn convert_to_merge_priority(value: &str) -> Option<MergePriority> {
+    match value {
+        "1" => Some(MergePriority::High),
+        "0" => Some(MergePriority::Mid),
+        "-1" => Some(MergePriority::Low),
+        _ => None,
     }
 }
 
@@ -117,8 +113,8 @@ impl Parse for MergePriority {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
+    ) -> ParseResult<Self> {
+        let text = node.next_text()?;
         match_text_view!(text,
             "1" => Self::High,
             "0" => Self::Mid,
@@ -133,8 +129,8 @@ impl Parse for AccessMode {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
+    ) -> ParseResult<Self> {
+        let text = node.next_text()?;
         match_text_view!(text,
             "RO" => Self::RO,
             "WO" => Self::WO,
@@ -143,18 +139,34 @@ impl Parse for AccessMode {
     }
 }
 
+/// Returns `true` if the text looks like a node name rather than an immediate value.
+fn is_node_name(text: &xml::TextView) -> bool {
+    text.view()
+        .chars()
+        .next()
+        .map_or(false, char::is_alphabetic)
+}
+
 impl Parse for ImmOrPNode<i64> {
     fn parse(
         node: &mut xml::Node,
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let peeked_text = node.peek().unwrap().text();
-        if peeked_text.view().chars().next().unwrap().is_alphabetic() {
-            Self::PNode(node.parse(node_builder, value_builder, cache_builder))
+    ) -> ParseResult<Self> {
+        let peeked_text = node.expect_peek()?.text();
+        if is_node_name(&peeked_text) {
+            Ok(Self::PNode(node.parse(
+                node_builder,
+                value_builder,
+                cache_builder,
+            )?))
         } else {
-            Self::Imm(node.parse(node_builder, value_builder, cache_builder))
+            Ok(Self::Imm(node.parse(
+                node_builder,
+                value_builder,
+                cache_builder,
+            )?))
         }
     }
 }
@@ -165,17 +177,25 @@ impl Parse for ImmOrPNode<f64> {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let peeked_text = node.peek().unwrap().text();
+    ) -> ParseResult<Self> {
+        let peeked_text = node.expect_peek()?.text();
 
         if peeked_text == "INF"
             || peeked_text == "-INF"
             || peeked_text == "NaN"
-            || !peeked_text.view().chars().next().unwrap().is_alphabetic()
+            || !is_node_name(&peeked_text)
         {
-            Self::Imm(node.parse(node_builder, value_builder, cache_builder))
+            Ok(Self::Imm(node.parse(
+                node_builder,
+                value_builder,
+                cache_builder,
+            )?))
         } else {
-            Self::PNode(node.parse(node_builder, value_builder, cache_builder))
+            Ok(Self::PNode(node.parse(
+                node_builder,
+                value_builder,
+                cache_builder,
+            )?))
         }
     }
 }
@@ -186,11 +206,19 @@ impl Parse for ImmOrPNode<bool> {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        if convert_to_bool_opt(&node.peek().unwrap().text().view()).is_some() {
-            Self::Imm(node.parse(node_builder, value_builder, cache_builder))
+    ) -> ParseResult<Self> {
+        if convert_to_bool(&node.expect_peek()?.text().view()).is_some() {
+            Ok(Self::Imm(node.parse(
+                node_builder,
+                value_builder,
+                cache_builder,
+            )?))
         } else {
-            Self::PNode(node.parse(node_builder, value_builder, cache_builder))
+            Ok(Self::PNode(node.parse(
+                node_builder,
+                value_builder,
+                cache_builder,
+            )?))
         }
     }
 }
@@ -203,16 +231,16 @@ macro_rules! impl_parse_for_imm_or_pnode_id {
                 node_builder: &mut impl NodeStoreBuilder,
                 value_builder: &mut impl ValueStoreBuilder,
                 cache_builder: &mut impl CacheStoreBuilder,
-            ) -> Self {
+            ) -> ParseResult<Self> {
                 let node: ImmOrPNode<$value_ty> =
-                    node.parse(node_builder, value_builder, cache_builder);
-                match node {
+                    node.parse(node_builder, value_builder, cache_builder)?;
+                Ok(match node {
                     ImmOrPNode::Imm(i) => {
                         let id = value_builder.store(i);
                         ImmOrPNode::Imm(id)
                     }
                     ImmOrPNode::PNode(id) => ImmOrPNode::PNode(id),
-                }
+                })
             }
         }
     };
@@ -233,12 +261,12 @@ impl Parse for IntegerRepresentation {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         use IntegerRepresentation::{
             Boolean, HexNumber, IpV4Address, Linear, Logarithmic, MacAddress, PureNumber,
         };
 
-        let value = node.next_text().unwrap();
+        let value = node.next_text()?;
         match_text_view!(value,
             "Linear" => Linear,
             "Logarithmic" => Logarithmic,
@@ -257,8 +285,8 @@ impl Parse for FloatRepresentation {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
+    ) -> ParseResult<Self> {
+        let text = node.next_text()?;
         match_text_view! {text,
             "Linear" => Self::Linear,
             "Logarithmic" => Self::Logarithmic,
@@ -279,8 +307,8 @@ impl Parse for Slope {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
+    ) -> ParseResult<Self> {
+        let text = node.next_text()?;
         match_text_view! {text,
             "Increasing" => Self::Increasing,
             "Decreasing" => Self::Decreasing,
@@ -308,8 +336,8 @@ impl Parse for DisplayNotation {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
+    ) -> ParseResult<Self> {
+        let text = node.next_text()?;
         match_text_view! {text,
             "Automatic" => Self::Automatic,
             "Fixed" => Self::Fixed,
@@ -318,16 +346,14 @@ impl Parse for DisplayNotation {
     }
 }
 
-impl From<&str> for StandardNameSpace {
-    fn from(value: &str) -> Self {
-        match value {
-            "None" => Self::None,
-            "IIDC" => Self::IIDC,
-            "GEV" => Self::GEV,
-            "CL" => Self::CL,
-            "USB" => Self::USB,
-            _ => unreachable!(),
-        }
+pub(super) fn convert_to_standard_name_space(value: &str) -> Option<StandardNameSpace> {
+    match value {
+        "None" => Some(StandardNameSpace::None),
+        "IIDC" => Some(StandardNameSpace::IIDC),
+        "GEV" => Some(StandardNameSpace::GEV),
+        "CL" => Some(StandardNameSpace::CL),
+        "USB" => Some(StandardNameSpace::USB),
+        _ => None,
     }
 }
 
@@ -343,8 +369,8 @@ impl Parse for CachingMode {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
+    ) -> ParseResult<Self> {
+        let text = node.next_text()?;
         match_text_view! {text,
             "WriteThrough" => Self::WriteThrough,
             "WriteAround" => Self::WriteAround,
@@ -362,21 +388,14 @@ where
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let name = node.peek().unwrap().attribute_of(NAME).unwrap().into();
-        let value = node.parse(node_builder, value_builder, cache_builder);
-        Self { name, value }
-    }
-}
-
-pub(super) fn convert_to_bool(value: &str) -> bool {
-    match convert_to_bool_opt(value) {
-        Some(b) => b,
-        _ => unreachable!(),
+    ) -> ParseResult<Self> {
+        let name = node.expect_peek()?.expect_attribute(NAME)?.into();
+        let value = node.parse(node_builder, value_builder, cache_builder)?;
+        Ok(Self { name, value })
     }
 }
 
-pub(super) fn convert_to_bool_opt(value: &str) -> Option<bool> {
+pub(super) fn convert_to_bool(value: &str) -> Option<bool> {
     match value {
         "Yes" | "true" => Some(true),
         "No" | "false" => Some(false),
@@ -390,37 +409,44 @@ impl Parse for bool {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
+    ) -> ParseResult<Self> {
+        let text = node.next_text()?;
         convert_to_bool(&text.view())
+            .ok_or_else(|| text.error(format!("invalid boolean `{}`", text)))
     }
 }
 
-pub(super) fn convert_to_int(value: &str) -> i64 {
+pub(super) fn convert_to_int(value: &str) -> Option<i64> {
     if value.starts_with("0x") || value.starts_with("0X") {
-        i64::from_str_radix(&value[2..], 16).unwrap()
+        i64::from_str_radix(&value[2..], 16).ok()
     } else {
-        value.parse().unwrap()
+        value.parse().ok()
     }
 }
 
-pub(super) fn convert_to_uint(value: &str) -> u64 {
+pub(super) fn convert_to_uint(value: &str) -> Option<u64> {
     if value.starts_with("0x") || value.starts_with("0X") {
-        u64::from_str_radix(&value[2..], 16).unwrap()
+        u64::from_str_radix(&value[2..], 16).ok()
     } else {
-        value.parse().unwrap()
+        value.parse().ok()
     }
 }
 
+/// Converts hexadecimal text without `0x` prefix, e.g. `EventID` and `ChunkID`.
+pub(super) fn convert_to_hex(value: &str) -> Option<u64> {
+    u64::from_str_radix(value, 16).ok()
+}
+
 impl Parse for i64 {
     fn parse(
         node: &mut xml::Node,
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let value = node.next_text().unwrap();
+    ) -> ParseResult<Self> {
+        let value = node.next_text()?;
         convert_to_int(&value.view())
+            .ok_or_else(|| value.error(format!("invalid integer `{}`", value)))
     }
 }
 
@@ -430,9 +456,10 @@ impl Parse for u64 {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let value = node.next_text().unwrap();
+    ) -> ParseResult<Self> {
+        let value = node.next_text()?;
         convert_to_uint(&value.view())
+            .ok_or_else(|| value.error(format!("invalid unsigned integer `{}`", value)))
     }
 }
 
@@ -442,14 +469,17 @@ impl Parse for f64 {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let value = node.next_text().unwrap().view();
+    ) -> ParseResult<Self> {
+        let text = node.next_text()?;
+        let value = text.view();
         if value == "INF" {
-            f64::INFINITY
+            Ok(f64::INFINITY)
         } else if value == "-INF" {
-            f64::NEG_INFINITY
+            Ok(f64::NEG_INFINITY)
         } else {
-            value.parse().unwrap()
+            value
+                .parse()
+                .map_err(|_| text.error(format!("invalid float `{}`", value)))
         }
     }
 }
@@ -460,8 +490,8 @@ impl Parse for String {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        node.next_text().unwrap().view().into()
+    ) -> ParseResult<Self> {
+        Ok(node.next_text()?.view().into())
     }
 }
 
@@ -471,9 +501,9 @@ impl Parse for NodeId {
         node_builder: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
-        node_builder.get_or_intern(&text.view())
+    ) -> ParseResult<Self> {
+        let text = node.next_text()?;
+        Ok(node_builder.get_or_intern(text.view()))
     }
 }
 
@@ -485,10 +515,10 @@ macro_rules! impl_parse_for_value_id {
                 node_builder: &mut impl NodeStoreBuilder,
                 value_builder: &mut impl ValueStoreBuilder,
                 cache_builder: &mut impl CacheStoreBuilder,
-            ) -> Self {
-                let value: $value_ty = node.parse(node_builder, value_builder, cache_builder);
+            ) -> ParseResult<Self> {
+                let value: $value_ty = node.parse(node_builder, value_builder, cache_builder)?;
                 let id = value_builder.store(value);
-                id
+                Ok(id)
             }
         }
     };
@@ -507,20 +537,20 @@ where
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let peek = node.peek().unwrap();
-        match peek.tag_name() {
-            VALUE => ValueKind::Value(node.parse(node_builder, value_builder, cache_builder)),
+    ) -> ParseResult<Self> {
+        let peek = node.expect_peek()?;
+        Ok(match peek.tag_name() {
+            VALUE => ValueKind::Value(node.parse(node_builder, value_builder, cache_builder)?),
             P_VALUE_COPY | P_VALUE => {
-                let p_value = node.parse(node_builder, value_builder, cache_builder);
+                let p_value = node.parse(node_builder, value_builder, cache_builder)?;
                 ValueKind::PValue(p_value)
             }
             P_INDEX => {
-                let p_index = node.parse(node_builder, value_builder, cache_builder);
+                let p_index = node.parse(node_builder, value_builder, cache_builder)?;
                 ValueKind::PIndex(p_index)
             }
-            _ => unreachable!(),
-        }
+            _ => return Err(peek.error("expected `Value`, `pValue` or `pIndex` element")),
+        })
     }
 }
 
@@ -530,22 +560,22 @@ impl<T> Parse for PValue<T> {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         // NOTE: The pValue can be sandwiched between two pValueCopy sequence.
         let mut p_value_copies =
-            node.parse_while(P_VALUE_COPY, node_builder, value_builder, cache_builder);
+            node.parse_while(P_VALUE_COPY, node_builder, value_builder, cache_builder)?;
 
-        let p_value = node.parse(node_builder, value_builder, cache_builder);
+        let p_value = node.parse(node_builder, value_builder, cache_builder)?;
 
         let node_ids: Vec<NodeId> =
-            node.parse_while(P_VALUE_COPY, node_builder, value_builder, cache_builder);
+            node.parse_while(P_VALUE_COPY, node_builder, value_builder, cache_builder)?;
         p_value_copies.extend(node_ids);
 
-        Self {
+        Ok(Self {
             p_value,
             p_value_copies,
             phantom: PhantomData,
-        }
+        })
     }
 }
 
@@ -559,24 +589,34 @@ where
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let p_index = node.parse(node_builder, value_builder, cache_builder);
+    ) -> ParseResult<Self> {
+        let p_index = node.parse(node_builder, value_builder, cache_builder)?;
 
         let mut value_indexed = vec![];
-        while let Some(indexed) = node
-            .parse_if(VALUE_INDEXED, node_builder, value_builder, cache_builder)
-            .or_else(|| node.parse_if(P_VALUE_INDEXED, node_builder, value_builder, cache_builder))
-        {
+        loop {
+            let indexed =
+                match node.parse_if(VALUE_INDEXED, node_builder, value_builder, cache_builder)? {
+                    Some(indexed) => indexed,
+                    None => match node.parse_if(
+                        P_VALUE_INDEXED,
+                        node_builder,
+                        value_builder,
+                        cache_builder,
+                    )? {
+                        Some(indexed) => indexed,
+                        None => break,
+                    },
+                };
             value_indexed.push(indexed);
         }
 
-        let value_default = node.parse(node_builder, value_builder, cache_builder);
+        let value_default = node.parse(node_builder, value_builder, cache_builder)?;
 
-        Self {
+        Ok(Self {
             p_index,
             value_indexed,
             value_default,
-        }
+        })
     }
 }
 
@@ -590,10 +630,12 @@ where
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let index = convert_to_int(node.peek().unwrap().attribute_of(INDEX).unwrap());
-        let indexed = node.parse(node_builder, value_builder, cache_builder);
-        Self { index, indexed }
+    ) -> ParseResult<Self> {
+        let index = node
+            .expect_peek()?
+            .expect_attribute_with(INDEX, convert_to_int)?;
+        let indexed = node.parse(node_builder, value_builder, cache_builder)?;
+        Ok(Self { index, indexed })
     }
 }
 
@@ -603,24 +645,26 @@ impl Parse for AddressKind {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let peeked_node = node.peek().unwrap();
-        match peeked_node.tag_name() {
+    ) -> ParseResult<Self> {
+        let peeked_node = node.expect_peek()?;
+        Ok(match peeked_node.tag_name() {
             ADDRESS | P_ADDRESS => {
-                Self::Address(node.parse(node_builder, value_builder, cache_builder))
+                Self::Address(node.parse(node_builder, value_builder, cache_builder)?)
             }
             INT_SWISS_KNIFE => {
                 let swiss_knife: IntSwissKnifeNode =
-                    node.next()
-                        .unwrap()
-                        .parse(node_builder, value_builder, cache_builder);
+                    node.expect_next()?
+                        .parse(node_builder, value_builder, cache_builder)?;
                 let id = swiss_knife.node_base().id();
                 node_builder.store_node(id, NodeData::IntSwissKnife(swiss_knife.into()));
                 Self::IntSwissKnife(id)
             }
-            P_INDEX => Self::PIndex(node.parse(node_builder, value_builder, cache_builder)),
-            _ => unreachable!(),
-        }
+            P_INDEX => Self::PIndex(node.parse(node_builder, value_builder, cache_builder)?),
+            _ => {
+                return Err(peeked_node
+                    .error("expected `Address`, `pAddress`, `IntSwissKnife` or `pIndex` element"))
+            }
+        })
     }
 }
 
@@ -630,20 +674,20 @@ impl Parse for RegPIndex {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let next_node = node.peek().unwrap();
+    ) -> ParseResult<Self> {
+        let next_node = node.expect_peek()?;
 
         let imm_offset = next_node
-            .attribute_of(OFFSET)
-            .map(|s| ImmOrPNode::Imm(convert_to_int(s)));
+            .attribute_with(OFFSET, convert_to_int)?
+            .map(ImmOrPNode::Imm);
         let pnode_offset = next_node
             .attribute_of(P_OFFSET)
             .map(|s| ImmOrPNode::PNode(node_builder.get_or_intern(s)));
         let offset = imm_offset.xor(pnode_offset);
 
-        let p_index = node.parse(node_builder, value_builder, cache_builder);
+        let p_index = node.parse(node_builder, value_builder, cache_builder)?;
 
-        Self { offset, p_index }
+        Ok(Self { offset, p_index })
     }
 }
 
@@ -659,8 +703,8 @@ impl Parse for Endianness {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
+    ) -> ParseResult<Self> {
+        let text = node.next_text()?;
         match_text_view! {text,
             "LittleEndian" => Self::LE,
             "BigEndian" => Self::BE,
@@ -680,8 +724,8 @@ impl Parse for Sign {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
+    ) -> ParseResult<Self> {
+        let text = node.next_text()?;
         match_text_view! {text,
             "Signed" => Self::Signed,
             "Unsigned" => Self::Unsigned,
@@ -695,15 +739,13 @@ impl Parse for BitMask {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        node.parse_if(BIT, node_builder, value_builder, cache_builder)
-            .map_or_else(
-                || {
-                    let lsb = node.parse(node_builder, value_builder, cache_builder);
-                    let msb = node.parse(node_builder, value_builder, cache_builder);
-                    Self::Range { lsb, msb }
-                },
-                Self::SingleBit,
-            )
+    ) -> ParseResult<Self> {
+        if let Some(bit) = node.parse_if(BIT, node_builder, value_builder, cache_builder)? {
+            Ok(Self::SingleBit(bit))
+        } else {
+            let lsb = node.parse(node_builder, value_builder, cache_builder)?;
+            let msb = node.parse(node_builder, value_builder, cache_builder)?;
+            Ok(Self::Range { lsb, msb })
+        }
     }
 }
@@ -14,10 +14,10 @@ use crate::{
 use super::{
     elem_name::{
         ENUMERATION, ENUM_ENTRY, EXPOSE_STATIC, IS_SELF_CLEARING, MERGE_PRIORITY, NAME, NAME_SPACE,
-        NUMERIC_VALUE, POLLING_TIME, P_SELECTED, STREAMABLE,
+        NODE_ATTRIBUTES, NUMERIC_VALUE, POLLING_TIME, P_SELECTED, STREAMABLE,
     },
-    elem_type::convert_to_bool,
-    xml, Parse,
+    elem_type::{convert_to_bool, convert_to_merge_priority, convert_to_name_space},
+    xml, Parse, ParseResult,
 };
 
 impl Parse for EnumerationNode {
@@ -27,28 +27,31 @@ impl Parse for EnumerationNode {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         debug!("start parsing `EnumerationNode`");
         debug_assert_eq!(node.tag_name(), ENUMERATION);
 
-        let attr_base = node.parse(node_builder, value_builder, cache_builder);
-        let elem_base = node.parse(node_builder, value_builder, cache_builder);
+        let attr_base = node.parse(node_builder, value_builder, cache_builder)?;
+        let elem_base = node.parse(node_builder, value_builder, cache_builder)?;
 
         let streamable = node
-            .parse_if(STREAMABLE, node_builder, value_builder, cache_builder)
+            .parse_if(STREAMABLE, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
         let mut entries = vec![];
         while let Some(mut ent_node) = node.next_if(ENUM_ENTRY) {
-            let entry: EnumEntryNode = ent_node.parse(node_builder, value_builder, cache_builder);
+            let entry: EnumEntryNode =
+                ent_node.parse(node_builder, value_builder, cache_builder)?;
             let nid = entry.attr_base.id;
             node_builder.store_node(nid, NodeData::EnumEntry(entry.into()));
             entries.push(nid);
         }
-        let value = node.parse(node_builder, value_builder, cache_builder);
-        let p_selected = node.parse_while(P_SELECTED, node_builder, value_builder, cache_builder);
-        let polling_time = node.parse_if(POLLING_TIME, node_builder, value_builder, cache_builder);
+        let value = node.parse(node_builder, value_builder, cache_builder)?;
+        let p_selected =
+            node.parse_while(P_SELECTED, node_builder, value_builder, cache_builder)?;
+        let polling_time =
+            node.parse_if(POLLING_TIME, node_builder, value_builder, cache_builder)?;
 
-        Self {
+        Ok(Self {
             attr_base,
             elem_base,
             streamable,
@@ -56,7 +59,7 @@ impl Parse for EnumerationNode {
             value,
             p_selected,
             polling_time,
-        }
+        })
     }
 }
 
@@ -67,47 +70,47 @@ impl Parse for EnumEntryNode {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         debug!("start parsing `EnumEntryNode`");
         debug_assert_eq!(node.tag_name(), ENUM_ENTRY);
 
         // We can't use `NodeAttributeBase::parse` for needs of generating fresh symbol.
-        let symbolic = node.attribute_of(NAME).unwrap().to_string();
+        let symbolic = node.expect_attribute(NAME)?.to_string();
         let name = format!("${}_{}", symbolic, node_builder.fresh_id());
         let id = node_builder.get_or_intern(&name);
         let name_space = node
-            .attribute_of(NAME_SPACE)
-            .map(|text| text.into())
+            .attribute_with(NAME_SPACE, convert_to_name_space)?
             .unwrap_or_default();
         let merge_priority = node
-            .attribute_of(MERGE_PRIORITY)
-            .map(|text| text.into())
+            .attribute_with(MERGE_PRIORITY, convert_to_merge_priority)?
             .unwrap_or_default();
-        let expose_static = node.attribute_of(EXPOSE_STATIC).map(convert_to_bool);
+        let expose_static = node.attribute_with(EXPOSE_STATIC, convert_to_bool)?;
+        let extensions = node.extensions(NODE_ATTRIBUTES);
 
         let attr_base = NodeAttributeBase {
             id,
             name_space,
             merge_priority,
             expose_static,
+            extensions,
         };
-        let elem_base = node.parse(node_builder, value_builder, cache_builder);
+        let elem_base = node.parse(node_builder, value_builder, cache_builder)?;
 
-        let value = node.parse(node_builder, value_builder, cache_builder);
+        let value = node.parse(node_builder, value_builder, cache_builder)?;
         let numeric_value =
-            node.parse_if(NUMERIC_VALUE, node_builder, value_builder, cache_builder);
+            node.parse_if(NUMERIC_VALUE, node_builder, value_builder, cache_builder)?;
         let is_self_clearing = node
-            .parse_if(IS_SELF_CLEARING, node_builder, value_builder, cache_builder)
+            .parse_if(IS_SELF_CLEARING, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
 
-        Self {
+        Ok(Self {
             attr_base,
             elem_base,
             value,
             numeric_value,
             symbolic,
             is_self_clearing,
-        }
+        })
     }
 }
 
@@ -15,7 +15,7 @@ use super::{
         DISPLAY_NOTATION, DISPLAY_PRECISION, FLOAT, INC, MAX, MIN, P_INC, P_MAX, P_MIN,
         REPRESENTATION, STREAMABLE, UNIT,
     },
-    xml, Parse,
+    xml, Parse, ParseResult,
 };
 
 impl Parse for FloatNode {
@@ -25,40 +25,43 @@ impl Parse for FloatNode {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         debug!("start parsing `FloatNode`");
         debug_assert_eq!(node.tag_name(), FLOAT);
 
-        let attr_base = node.parse(node_builder, value_builder, cache_builder);
-        let elem_base = node.parse(node_builder, value_builder, cache_builder);
+        let attr_base = node.parse(node_builder, value_builder, cache_builder)?;
+        let elem_base = node.parse(node_builder, value_builder, cache_builder)?;
 
         let streamable = node
-            .parse_if(STREAMABLE, node_builder, value_builder, cache_builder)
+            .parse_if(STREAMABLE, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
-        let value_kind = node.parse(node_builder, value_builder, cache_builder);
-        let min = node
-            .parse_if(MIN, node_builder, value_builder, cache_builder)
-            .or_else(|| node.parse_if(P_MIN, node_builder, value_builder, cache_builder))
-            .unwrap_or_else(|| {
-                let id = value_builder.store(f64::MIN);
-                ImmOrPNode::Imm(id)
-            });
-        let max = node
-            .parse_if(MAX, node_builder, value_builder, cache_builder)
-            .or_else(|| node.parse_if(P_MAX, node_builder, value_builder, cache_builder))
-            .unwrap_or_else(|| {
-                let id = value_builder.store(f64::MAX);
-                ImmOrPNode::Imm(id)
-            });
-        let inc = node
-            .parse_if(INC, node_builder, value_builder, cache_builder)
-            .or_else(|| node.parse_if(P_INC, node_builder, value_builder, cache_builder));
-        let unit = node.parse_if(UNIT, node_builder, value_builder, cache_builder);
+        let value_kind = node.parse(node_builder, value_builder, cache_builder)?;
+        let min = match node.parse_if(MIN, node_builder, value_builder, cache_builder)? {
+            Some(min) => Some(min),
+            None => node.parse_if(P_MIN, node_builder, value_builder, cache_builder)?,
+        }
+        .unwrap_or_else(|| {
+            let id = value_builder.store(f64::MIN);
+            ImmOrPNode::Imm(id)
+        });
+        let max = match node.parse_if(MAX, node_builder, value_builder, cache_builder)? {
+            Some(max) => Some(max),
+            None => node.parse_if(P_MAX, node_builder, value_builder, cache_builder)?,
+        }
+        .unwrap_or_else(|| {
+            let id = value_builder.store(f64::MAX);
+            ImmOrPNode::Imm(id)
+        });
+        let inc = match node.parse_if(INC, node_builder, value_builder, cache_builder)? {
+            Some(inc) => Some(inc),
+            None => node.parse_if(P_INC, node_builder, value_builder, cache_builder)?,
+        };
+        let unit = node.parse_if(UNIT, node_builder, value_builder, cache_builder)?;
         let representation = node
-            .parse_if(REPRESENTATION, node_builder, value_builder, cache_builder)
+            .parse_if(REPRESENTATION, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
         let display_notation = node
-            .parse_if(DISPLAY_NOTATION, node_builder, value_builder, cache_builder)
+            .parse_if(DISPLAY_NOTATION, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
         let display_precision = node
             .parse_if(
@@ -66,10 +69,10 @@ impl Parse for FloatNode {
                 node_builder,
                 value_builder,
                 cache_builder,
-            )
+            )?
             .unwrap_or(6);
 
-        Self {
+        Ok(Self {
             attr_base,
             elem_base,
             streamable,
@@ -81,7 +84,7 @@ impl Parse for FloatNode {
             representation,
             display_notation,
             display_precision,
-        }
+        })
     }
 }
 
@@ -4,10 +4,10 @@
 
 use crate::{
     builder::{CacheStoreBuilder, NodeStoreBuilder, ValueStoreBuilder},
-    formula::{parse, Expr, Formula},
+    formula::{parse_formula, Expr, Formula},
 };
 
-use super::{xml, Parse};
+use super::{xml, Parse, ParseResult};
 
 impl Parse for Formula {
     fn parse(
@@ -15,9 +15,12 @@ impl Parse for Formula {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let expr = node.parse(node_builder, value_builder, cache_builder);
-        Formula { expr }
+    ) -> ParseResult<Self> {
+        let expr = node.parse(node_builder, value_builder, cache_builder)?;
+        Ok(Formula {
+            expr,
+            compiled: None,
+        })
     }
 }
 
@@ -27,8 +30,8 @@ impl Parse for Expr {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
-        parse(&text.view())
+    ) -> ParseResult<Self> {
+        let text = node.next_text()?;
+        parse_formula(&text.view()).map_err(|e| text.error(e))
     }
 }
@@ -6,7 +6,7 @@ use tracing::debug;
 
 use crate::builder::{CacheStoreBuilder, NodeStoreBuilder, ValueStoreBuilder};
 
-use super::{elem_name::GROUP, xml, NodeData, Parse};
+use super::{elem_name::GROUP, xml, NodeData, Parse, ParseResult};
 
 #[derive(Debug, Clone)]
 pub(super) struct GroupNode {
@@ -20,19 +20,20 @@ impl Parse for GroupNode {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         debug!("start parsing `GroupNode`");
         debug_assert_eq!(node.tag_name(), GROUP);
 
         let mut nodes = vec![];
         while let Some(ref mut child) = node.next() {
-            let children: Vec<NodeData> = child.parse(node_builder, value_builder, cache_builder);
+            let children: Vec<NodeData> =
+                child.parse(node_builder, value_builder, cache_builder)?;
             for data in children {
                 nodes.push(data);
             }
         }
 
-        Self { nodes }
+        Ok(Self { nodes })
     }
 }
 
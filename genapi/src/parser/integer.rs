@@ -15,7 +15,7 @@ use super::{
     elem_name::{
         INC, INTEGER, MAX, MIN, P_INC, P_MAX, P_MIN, P_SELECTED, REPRESENTATION, STREAMABLE, UNIT,
     },
-    xml, Parse,
+    xml, Parse, ParseResult,
 };
 
 impl Parse for IntegerNode {
@@ -25,33 +25,36 @@ impl Parse for IntegerNode {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         debug!("start parsing `IntegerNode`");
         debug_assert_eq!(node.tag_name(), INTEGER);
 
-        let attr_base = node.parse(node_builder, value_builder, cache_builder);
-        let elem_base = node.parse(node_builder, value_builder, cache_builder);
+        let attr_base = node.parse(node_builder, value_builder, cache_builder)?;
+        let elem_base = node.parse(node_builder, value_builder, cache_builder)?;
 
         let streamable = node
-            .parse_if(STREAMABLE, node_builder, value_builder, cache_builder)
+            .parse_if(STREAMABLE, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
-        let value_kind = node.parse(node_builder, value_builder, cache_builder);
-        let min = node
-            .parse_if(MIN, node_builder, value_builder, cache_builder)
-            .or_else(|| node.parse_if(P_MIN, node_builder, value_builder, cache_builder));
-        let max = node
-            .parse_if(MAX, node_builder, value_builder, cache_builder)
-            .or_else(|| node.parse_if(P_MAX, node_builder, value_builder, cache_builder));
-        let inc = node
-            .parse_if(INC, node_builder, value_builder, cache_builder)
-            .or_else(|| node.parse_if(P_INC, node_builder, value_builder, cache_builder))
-            .unwrap_or(ImmOrPNode::Imm(10));
-        let unit = node.parse_if(UNIT, node_builder, value_builder, cache_builder);
+        let value_kind = node.parse(node_builder, value_builder, cache_builder)?;
+        let min = match node.parse_if(MIN, node_builder, value_builder, cache_builder)? {
+            Some(min) => Some(min),
+            None => node.parse_if(P_MIN, node_builder, value_builder, cache_builder)?,
+        };
+        let max = match node.parse_if(MAX, node_builder, value_builder, cache_builder)? {
+            Some(max) => Some(max),
+            None => node.parse_if(P_MAX, node_builder, value_builder, cache_builder)?,
+        };
+        let inc = match node.parse_if(INC, node_builder, value_builder, cache_builder)? {
+            Some(inc) => Some(inc),
+            None => node.parse_if(P_INC, node_builder, value_builder, cache_builder)?,
+        }
+        .unwrap_or(ImmOrPNode::Imm(10));
+        let unit = node.parse_if(UNIT, node_builder, value_builder, cache_builder)?;
         let representation: IntegerRepresentation = node
-            .parse_if(REPRESENTATION, node_builder, value_builder, cache_builder)
+            .parse_if(REPRESENTATION, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
         let p_selected: Vec<NodeId> =
-            node.parse_while(P_SELECTED, node_builder, value_builder, cache_builder);
+            node.parse_while(P_SELECTED, node_builder, value_builder, cache_builder)?;
 
         // Deduce min and max value based on representation if not specified.
         let min = min.unwrap_or_else(|| {
@@ -63,7 +66,7 @@ impl Parse for IntegerNode {
             ImmOrPNode::Imm(id)
         });
 
-        Self {
+        Ok(Self {
             attr_base,
             elem_base,
             streamable,
@@ -74,7 +77,7 @@ impl Parse for IntegerNode {
             unit,
             representation,
             p_selected,
-        }
+        })
     }
 }
 
@@ -31,13 +31,17 @@ mod swiss_knife;
 mod utils;
 mod xml;
 
+use std::collections::HashMap;
+
 use group::GroupNode;
 use struct_reg::StructRegNode;
 use thiserror::Error;
+use tracing::debug;
 
 use crate::{
     builder::{CacheStoreBuilder, NodeStoreBuilder, ValueStoreBuilder},
-    store::NodeData,
+    elem_type::MergePriority,
+    store::{NodeData, NodeId},
     RegisterDescription,
 };
 
@@ -54,37 +58,257 @@ pub enum ParseError {
 
     #[error("invalid XML syntax: {0}")]
     InvalidSyntax(#[from] roxmltree::Error),
+
+    #[error("no XML is given to be merged")]
+    EmptyInput,
+
+    #[error("{message}: `{path}` at line {line}, column {column}")]
+    InvalidElement {
+        /// Path of the element from the root element, e.g.
+        /// `RegisterDescription/Integer[Width]/Value`.
+        path: String,
+        line: u32,
+        column: u32,
+        message: String,
+    },
 }
 
 pub type ParseResult<T> = std::result::Result<T, ParseError>;
 
+/// Options to control how XMLs are parsed.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParseOptions {
+    tolerant: bool,
+    streaming: bool,
+}
+
+impl ParseOptions {
+    /// If `tolerant` is `true`, elements and attributes which are not defined in the `GenApi`
+    /// schema, e.g. vendor specific extensions, are skipped instead of breaking parsing.
+    ///
+    /// The skipped ones are kept in [`crate::elem_type::Extensions`] of each node so that they
+    /// survive a round trip through [`crate::writer::write`].
+    #[must_use]
+    pub fn tolerant(mut self, tolerant: bool) -> Self {
+        self.tolerant = tolerant;
+        self
+    }
+
+    /// If `streaming` is `true`, top-level elements of `RegisterDescription` are parsed one by
+    /// one instead of building the DOM of the whole XML at once, which lowers peak memory usage
+    /// for large XMLs.
+    ///
+    /// The result is the same as the one of the default mode. XMLs which can't be split into
+    /// top-level elements, e.g. XMLs with DTD, are parsed in the default mode.
+    #[must_use]
+    pub fn streaming(mut self, streaming: bool) -> Self {
+        self.streaming = streaming;
+        self
+    }
+}
+
 pub fn parse(
     xml: &impl AsRef<str>,
     node_builder: &mut impl NodeStoreBuilder,
     value_builder: &mut impl ValueStoreBuilder,
     cache_builder: &mut impl CacheStoreBuilder,
 ) -> ParseResult<RegisterDescription> {
-    let document = xml::Document::from_str(xml.as_ref())?;
+    parse_with_options(
+        xml,
+        ParseOptions::default(),
+        node_builder,
+        value_builder,
+        cache_builder,
+    )
+}
+
+/// Same as [`parse`], but parses the XML with the given options.
+pub fn parse_with_options(
+    xml: &impl AsRef<str>,
+    options: ParseOptions,
+    node_builder: &mut impl NodeStoreBuilder,
+    value_builder: &mut impl ValueStoreBuilder,
+    cache_builder: &mut impl CacheStoreBuilder,
+) -> ParseResult<RegisterDescription> {
+    parse_document(
+        xml.as_ref(),
+        options,
+        node_builder,
+        value_builder,
+        cache_builder,
+        |node_builder, data| {
+            let id = data.node_base().id();
+            node_builder.store_node(id, data);
+        },
+    )
+}
+
+/// Parses multiple XMLs and merges their nodes into a single node store, e.g. a standard XML and
+/// its vendor extension.
+///
+/// [`RegisterDescription`] of the first XML is returned.
+///
+/// When nodes with the same name are defined in multiple XMLs, the node with the higher
+/// `MergePriority` is kept. If the priorities are the same, the node defined later overrides
+/// the former one. `pFeature`s of overridden `Category` nodes are appended to the kept node so
+/// that features added by each XML remain reachable.
+pub fn parse_merged<T: AsRef<str>>(
+    xmls: &[T],
+    node_builder: &mut impl NodeStoreBuilder,
+    value_builder: &mut impl ValueStoreBuilder,
+    cache_builder: &mut impl CacheStoreBuilder,
+) -> ParseResult<RegisterDescription> {
+    parse_merged_with_options(
+        xmls,
+        ParseOptions::default(),
+        node_builder,
+        value_builder,
+        cache_builder,
+    )
+}
+
+/// Same as [`parse_merged`], but parses the XMLs with the given options.
+pub fn parse_merged_with_options<T: AsRef<str>>(
+    xmls: &[T],
+    options: ParseOptions,
+    node_builder: &mut impl NodeStoreBuilder,
+    value_builder: &mut impl ValueStoreBuilder,
+    cache_builder: &mut impl CacheStoreBuilder,
+) -> ParseResult<RegisterDescription> {
+    let mut reg_desc = None;
+    let mut nodes: Vec<NodeData> = vec![];
+    let mut positions: HashMap<NodeId, usize> = HashMap::new();
+
+    for xml in xmls {
+        let desc = parse_document(
+            xml.as_ref(),
+            options,
+            node_builder,
+            value_builder,
+            cache_builder,
+            |_, data| {
+                let id = data.node_base().id();
+                if let Some(&pos) = positions.get(&id) {
+                    merge_node(&mut nodes[pos], data);
+                } else {
+                    positions.insert(id, nodes.len());
+                    nodes.push(data);
+                }
+            },
+        )?;
+        reg_desc.get_or_insert(desc);
+    }
+
+    for node in nodes {
+        let id = node.node_base().id();
+        node_builder.store_node(id, node);
+    }
+
+    reg_desc.ok_or(ParseError::EmptyInput)
+}
+
+/// Parses a single XML and passes each parsed node to `store`.
+fn parse_document<B: NodeStoreBuilder>(
+    xml: &str,
+    options: ParseOptions,
+    node_builder: &mut B,
+    value_builder: &mut impl ValueStoreBuilder,
+    cache_builder: &mut impl CacheStoreBuilder,
+    mut store: impl FnMut(&mut B, NodeData),
+) -> ParseResult<RegisterDescription> {
+    if options.streaming {
+        if let Some(fragments) = xml::Fragments::scan(xml) {
+            let root = fragments.root();
+            let document = xml::Document::from_fragment(&root, xml, options)?;
+            let mut reg_desc: RegisterDescription =
+                document
+                    .root_node()
+                    .parse(node_builder, value_builder, cache_builder)?;
+
+            for fragment in fragments.elements() {
+                let document = xml::Document::from_fragment(&fragment, xml, options)?;
+                let mut node = document.root_node();
+                reg_desc
+                    .extensions
+                    .elements
+                    .extend(node.extension_elements());
+                parse_children(
+                    &mut node,
+                    node_builder,
+                    value_builder,
+                    cache_builder,
+                    &mut store,
+                )?;
+            }
+            return Ok(reg_desc);
+        }
+        debug!("failed to split XML into fragments, fall back to DOM parsing");
+    }
+
+    let document = xml::Document::from_str_with_options(xml, options)?;
     let mut node = document.root_node();
-    let reg_desc = node.parse(node_builder, value_builder, cache_builder);
+    let reg_desc = node.parse(node_builder, value_builder, cache_builder)?;
+    parse_children(
+        &mut node,
+        node_builder,
+        value_builder,
+        cache_builder,
+        &mut store,
+    )?;
+    Ok(reg_desc)
+}
+
+fn parse_children<B: NodeStoreBuilder>(
+    node: &mut xml::Node,
+    node_builder: &mut B,
+    value_builder: &mut impl ValueStoreBuilder,
+    cache_builder: &mut impl CacheStoreBuilder,
+    store: &mut impl FnMut(&mut B, NodeData),
+) -> ParseResult<()> {
     while let Some(ref mut child) = node.next() {
-        let children: Vec<NodeData> = child.parse(node_builder, value_builder, cache_builder);
+        let children: Vec<NodeData> = child.parse(node_builder, value_builder, cache_builder)?;
         for child in children {
-            let id = child.node_base().id();
-            node_builder.store_node(id, child);
+            store(node_builder, child);
         }
     }
+    Ok(())
+}
 
-    Ok(reg_desc)
+fn merge_node(current: &mut NodeData, new: NodeData) {
+    fn priority(data: &NodeData) -> i8 {
+        match data.node_base().merge_priority() {
+            MergePriority::High => 1,
+            MergePriority::Mid => 0,
+            MergePriority::Low => -1,
+        }
+    }
+
+    if priority(&new) < priority(current) {
+        merge_features(current, &new);
+    } else {
+        let old = std::mem::replace(current, new);
+        merge_features(current, &old);
+    }
 }
 
-trait Parse {
+fn merge_features(into: &mut NodeData, from: &NodeData) {
+    if let (NodeData::Category(into), NodeData::Category(from)) = (into, from) {
+        for feature in &from.p_features {
+            if !into.p_features.contains(feature) {
+                into.p_features.push(*feature);
+            }
+        }
+    }
+}
+
+trait Parse: Sized {
     fn parse(
         node: &mut xml::Node,
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self;
+    ) -> ParseResult<Self>;
 }
 
 impl Parse for Vec<NodeData> {
@@ -94,112 +318,423 @@ impl Parse for Vec<NodeData> {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        match node.tag_name() {
+    ) -> ParseResult<Self> {
+        Ok(match node.tag_name() {
             NODE => vec![NodeData::Node(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             CATEGORY => vec![NodeData::Category(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             INTEGER => vec![NodeData::Integer(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             INT_REG => vec![NodeData::IntReg(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             MASKED_INT_REG => vec![NodeData::MaskedIntReg(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             BOOLEAN => vec![NodeData::Boolean(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             COMMAND => vec![NodeData::Command(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             ENUMERATION => vec![NodeData::Enumeration(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             FLOAT => vec![NodeData::Float(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             FLOAT_REG => vec![NodeData::FloatReg(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             STRING => vec![NodeData::String(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             STRING_REG => vec![NodeData::StringReg(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             REGISTER => vec![NodeData::Register(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             CONVERTER => vec![NodeData::Converter(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             INT_CONVERTER => vec![NodeData::IntConverter(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             SWISS_KNIFE => vec![NodeData::SwissKnife(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             INT_SWISS_KNIFE => vec![NodeData::IntSwissKnife(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             PORT => vec![NodeData::Port(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             STRUCT_REG => {
-                let node: StructRegNode = node.parse(node_builder, value_builder, cache_builder);
+                let node: StructRegNode = node.parse(node_builder, value_builder, cache_builder)?;
                 node.into_masked_int_regs(cache_builder)
                     .into_iter()
                     .map(|node| NodeData::MaskedIntReg(node.into()))
                     .collect()
             }
             GROUP => {
-                let node: GroupNode = node.parse(node_builder, value_builder, cache_builder);
+                let node: GroupNode = node.parse(node_builder, value_builder, cache_builder)?;
                 node.nodes
             }
             // TODO: Implement DCAM specific ndoes.
-            CONF_ROM | TEXT_DESC | INT_KEY | ADV_FEATURE_LOCK | SMART_FEATURE => todo!(),
-            _ => unreachable!(),
+            CONF_ROM | TEXT_DESC | INT_KEY | ADV_FEATURE_LOCK | SMART_FEATURE => {
+                return Err(node.error("DCAM specific nodes are not supported"))
+            }
+            name => return Err(node.error(format!("unknown element `{}`", name))),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        store::{DefaultCacheStore, DefaultNodeStore, DefaultValueStore, NodeStore, ValueStore},
+        IntegerNode,
+    };
+
+    use super::*;
+
+    fn register_description(model_name: &str, nodes: &str) -> String {
+        format!(
+            r#"
+            <RegisterDescription
+              ModelName="{}"
+              VendorName="CameleonVendor"
+              StandardNameSpace="None"
+              SchemaMajorVersion="1"
+              SchemaMinorVersion="1"
+              SchemaSubMinorVersion="0"
+              MajorVersion="1"
+              MinorVersion="0"
+              SubMinorVersion="0"
+              ProductGuid="01234567-0123-0123-0123-0123456789ab"
+              VersionGuid="76543210-3210-3210-3210-ba9876543210">
+              {}
+            </RegisterDescription>
+            "#,
+            model_name, nodes
+        )
+    }
+
+    fn integer_value(
+        name: &str,
+        node_store: &DefaultNodeStore,
+        value_store: &DefaultValueStore,
+    ) -> i64 {
+        let node: &IntegerNode = match node_store.node(node_store.id_by_name(name).unwrap()) {
+            NodeData::Integer(node) => node,
+            _ => panic!(),
+        };
+        value_store
+            .integer_value(node.value_kind.imm().unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_merged() {
+        let standard = register_description(
+            "Standard",
+            r#"
+            <Category Name="Root">
+                <pFeature>Width</pFeature>
+                <pFeature>Height</pFeature>
+            </Category>
+            <Integer Name="Width">
+                <Value>640</Value>
+            </Integer>
+            <Integer Name="Height" MergePriority="1">
+                <Value>480</Value>
+            </Integer>
+            "#,
+        );
+        let extension = register_description(
+            "Extension",
+            r#"
+            <Category Name="Root">
+                <pFeature>Width</pFeature>
+                <pFeature>VendorFeature</pFeature>
+            </Category>
+            <Integer Name="Width">
+                <Value>1280</Value>
+            </Integer>
+            <Integer Name="Height">
+                <Value>960</Value>
+            </Integer>
+            <Integer Name="VendorFeature">
+                <Value>1</Value>
+            </Integer>
+            "#,
+        );
+
+        let mut node_builder = DefaultNodeStore::new();
+        let mut value_builder = DefaultValueStore::new();
+        let mut cache_builder = DefaultCacheStore::new();
+        let reg_desc = parse_merged(
+            &[standard, extension],
+            &mut node_builder,
+            &mut value_builder,
+            &mut cache_builder,
+        )
+        .unwrap();
+        assert_eq!(reg_desc.model_name(), "Standard");
+
+        // The later definition overrides the former one.
+        assert_eq!(integer_value("Width", &node_builder, &value_builder), 1280);
+        // The definition with higher priority is kept.
+        assert_eq!(integer_value("Height", &node_builder, &value_builder), 480);
+        assert_eq!(
+            integer_value("VendorFeature", &node_builder, &value_builder),
+            1
+        );
+
+        let root = match node_builder.node(node_builder.id_by_name("Root").unwrap()) {
+            NodeData::Category(node) => node,
+            _ => panic!(),
+        };
+        let features: Vec<_> = root
+            .p_features
+            .iter()
+            .map(|nid| nid.name(&node_builder))
+            .collect();
+        assert_eq!(features, &["Width", "VendorFeature", "Height"]);
+    }
+
+    #[test]
+    fn test_parse_merged_empty() {
+        let xmls: &[&str] = &[];
+        let res = parse_merged(
+            xmls,
+            &mut DefaultNodeStore::new(),
+            &mut DefaultValueStore::new(),
+            &mut DefaultCacheStore::new(),
+        );
+        assert!(matches!(res, Err(ParseError::EmptyInput)));
+    }
+
+    #[test]
+    fn test_parse_error_position() {
+        let xml = register_description(
+            "Invalid",
+            r#"<Integer Name="Width"><Value>1.5</Value></Integer>"#,
+        );
+        let res = parse(
+            &xml,
+            &mut DefaultNodeStore::new(),
+            &mut DefaultValueStore::new(),
+            &mut DefaultCacheStore::new(),
+        );
+
+        match res {
+            Err(ParseError::InvalidElement {
+                path, line, column, ..
+            }) => {
+                assert_eq!(path, "RegisterDescription/Integer[Width]/Value");
+                assert_eq!(line, 14);
+                assert_eq!(column, 37);
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn test_parse_tolerant() {
+        let xml = register_description(
+            "Tolerant",
+            r#"<Integer Name="Width" VendorAttr="1">
+                <VendorInfo>vendor specific</VendorInfo>
+                <Value>640</Value>
+            </Integer>"#,
+        );
+
+        // Non-standard elements break parsing unless tolerant mode is enabled.
+        assert!(parse(
+            &xml,
+            &mut DefaultNodeStore::new(),
+            &mut DefaultValueStore::new(),
+            &mut DefaultCacheStore::new(),
+        )
+        .is_err());
+
+        let mut node_builder = DefaultNodeStore::new();
+        let mut value_builder = DefaultValueStore::new();
+        parse_with_options(
+            &xml,
+            ParseOptions::default().tolerant(true),
+            &mut node_builder,
+            &mut value_builder,
+            &mut DefaultCacheStore::new(),
+        )
+        .unwrap();
+        assert_eq!(integer_value("Width", &node_builder, &value_builder), 640);
+
+        let extensions = node_builder
+            .node(node_builder.id_by_name("Width").unwrap())
+            .node_base()
+            .extensions();
+        assert_eq!(
+            extensions.attributes(),
+            &[("VendorAttr".to_string(), "1".to_string())]
+        );
+        assert_eq!(extensions.elements().len(), 1);
+        assert_eq!(extensions.elements()[0].name(), "VendorInfo");
+        assert_eq!(
+            extensions.elements()[0].source(),
+            "<VendorInfo>vendor specific</VendorInfo>"
+        );
+    }
+
+    #[test]
+    fn test_parse_streaming() {
+        let xml = register_description(
+            "Streaming",
+            r#"
+            <Category Name="Root">
+                <pFeature>Width</pFeature>
+            </Category>
+            <!-- comment between nodes -->
+            <Integer Name="Width">
+                <Value>640</Value>
+            </Integer>
+            <VendorInfo>vendor specific</VendorInfo>
+            "#,
+        );
+        let options = ParseOptions::default().tolerant(true);
+
+        let mut node_builder = DefaultNodeStore::new();
+        let mut value_builder = DefaultValueStore::new();
+        let expected = parse_with_options(
+            &xml,
+            options,
+            &mut node_builder,
+            &mut value_builder,
+            &mut DefaultCacheStore::new(),
+        )
+        .unwrap();
+
+        let mut streaming_node_builder = DefaultNodeStore::new();
+        let mut streaming_value_builder = DefaultValueStore::new();
+        let reg_desc = parse_with_options(
+            &xml,
+            options.streaming(true),
+            &mut streaming_node_builder,
+            &mut streaming_value_builder,
+            &mut DefaultCacheStore::new(),
+        )
+        .unwrap();
+
+        assert_eq!(reg_desc.model_name(), expected.model_name());
+        assert_eq!(reg_desc.extensions(), expected.extensions());
+        assert_eq!(reg_desc.extensions().elements().len(), 1);
+        assert_eq!(
+            integer_value("Width", &streaming_node_builder, &streaming_value_builder),
+            integer_value("Width", &node_builder, &value_builder),
+        );
+        assert!(streaming_node_builder.id_by_name("Root").is_some());
+    }
+
+    #[test]
+    fn test_parse_streaming_error_position() {
+        let xml = register_description(
+            "Invalid",
+            r#"<Integer Name="Width"><Value>1.5</Value></Integer>"#,
+        );
+        let res = parse_with_options(
+            &xml,
+            ParseOptions::default().streaming(true),
+            &mut DefaultNodeStore::new(),
+            &mut DefaultValueStore::new(),
+            &mut DefaultCacheStore::new(),
+        );
+
+        // Positions must point to the original XML, not to the fragment.
+        match res {
+            Err(ParseError::InvalidElement {
+                path, line, column, ..
+            }) => {
+                assert_eq!(path, "RegisterDescription/Integer[Width]/Value");
+                assert_eq!(line, 14);
+                assert_eq!(column, 37);
+            }
+            _ => panic!(),
         }
     }
+
+    #[test]
+    fn test_alias() {
+        let xml = register_description(
+            "Alias",
+            r#"
+            <Integer Name="ExposureTimeRaw">
+                <pAlias>ExposureTime</pAlias>
+                <Value>100</Value>
+            </Integer>
+            <Float Name="ExposureTime">
+                <pCastAlias>ExposureTimeRaw</pCastAlias>
+                <Value>100.0</Value>
+            </Float>
+            "#,
+        );
+
+        let mut node_builder = DefaultNodeStore::new();
+        parse(
+            &xml,
+            &mut node_builder,
+            &mut DefaultValueStore::new(),
+            &mut DefaultCacheStore::new(),
+        )
+        .unwrap();
+
+        let raw = node_builder.id_by_name("ExposureTimeRaw").unwrap();
+        let float = node_builder.id_by_name("ExposureTime").unwrap();
+        assert_eq!(node_builder.alias(raw), Some(float));
+        assert_eq!(node_builder.cast_alias(raw), None);
+        assert_eq!(node_builder.cast_alias(float), Some(raw));
+        assert_eq!(node_builder.aliased_by(float), &[raw]);
+        assert_eq!(node_builder.aliased_by(raw), &[float]);
+    }
 }
@@ -10,7 +10,7 @@ use crate::{
     Node,
 };
 
-use super::{elem_name::NODE, xml, Parse};
+use super::{elem_name::NODE, xml, Parse, ParseResult};
 
 impl Parse for Node {
     #[tracing::instrument(level = "trace", skip(node_builder, value_builder, cache_builder))]
@@ -19,17 +19,17 @@ impl Parse for Node {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         debug!("start parsing `Node`");
         debug_assert_eq!(node.tag_name(), NODE);
 
-        let attr_base = NodeAttributeBase::parse(node, node_builder, value_builder, cache_builder);
-        let elem_base = NodeElementBase::parse(node, node_builder, value_builder, cache_builder);
+        let attr_base = NodeAttributeBase::parse(node, node_builder, value_builder, cache_builder)?;
+        let elem_base = NodeElementBase::parse(node, node_builder, value_builder, cache_builder)?;
 
-        Self {
+        Ok(Self {
             attr_base,
             elem_base,
-        }
+        })
     }
 }
 
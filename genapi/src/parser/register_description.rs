@@ -6,17 +6,19 @@ use tracing::debug;
 
 use crate::{
     builder::{CacheStoreBuilder, NodeStoreBuilder, ValueStoreBuilder},
+    elem_type::StandardNameSpace,
     RegisterDescription,
 };
 
 use super::{
     elem_name::{
         MAJOR_VERSION, MINOR_VERSION, MODEL_NAME, PRODUCT_GUID, REGISTER_DESCRIPTION,
-        SCHEMA_MAJOR_VERSION, SCHEMA_MINOR_VERSION, SCHEMA_SUB_MINOR_VERSION, STANDARD_NAME_SPCACE,
-        SUB_MINOR_VERSION, TOOL_TIP, VENDOR_NAME, VERSION_GUID,
+        REGISTER_DESCRIPTION_ATTRIBUTES, SCHEMA_MAJOR_VERSION, SCHEMA_MINOR_VERSION,
+        SCHEMA_SUB_MINOR_VERSION, STANDARD_NAME_SPCACE, SUB_MINOR_VERSION, TOOL_TIP, VENDOR_NAME,
+        VERSION_GUID,
     },
-    elem_type::convert_to_uint,
-    xml, Parse,
+    elem_type::{convert_to_standard_name_space, convert_to_uint},
+    xml, Parse, ParseResult,
 };
 
 impl Parse for RegisterDescription {
@@ -26,27 +28,47 @@ impl Parse for RegisterDescription {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         debug!("start parsing `RegisterDescription`");
         debug_assert_eq!(node.tag_name(), REGISTER_DESCRIPTION);
 
-        let model_name = node.attribute_of(MODEL_NAME).unwrap().into();
-        let vendor_name = node.attribute_of(VENDOR_NAME).unwrap().into();
+        let model_name = node.expect_attribute(MODEL_NAME)?.into();
+        let vendor_name = node.expect_attribute(VENDOR_NAME)?.into();
         let tooltip = node.attribute_of(TOOL_TIP).map(Into::into);
-        let standard_name_space = node.attribute_of(STANDARD_NAME_SPCACE).unwrap().into();
-        let schema_major_version =
-            convert_to_uint(node.attribute_of(SCHEMA_MAJOR_VERSION).unwrap());
-        let schema_minor_version =
-            convert_to_uint(node.attribute_of(SCHEMA_MINOR_VERSION).unwrap());
-        let schema_subminor_version =
-            convert_to_uint(node.attribute_of(SCHEMA_SUB_MINOR_VERSION).unwrap());
-        let major_version = convert_to_uint(node.attribute_of(MAJOR_VERSION).unwrap());
-        let minor_version = convert_to_uint(node.attribute_of(MINOR_VERSION).unwrap());
-        let subminor_version = convert_to_uint(node.attribute_of(SUB_MINOR_VERSION).unwrap());
-        let product_guid = node.attribute_of(PRODUCT_GUID).unwrap().into();
-        let version_guid = node.attribute_of(VERSION_GUID).unwrap().into();
-
-        Self {
+        let schema_version = node.schema_version();
+        let (
+            standard_name_space,
+            schema_major_version,
+            schema_minor_version,
+            schema_subminor_version,
+        ) = if schema_version.is_1_0() {
+            // `StandardNameSpace` and `SchemaSubMinorVersion` were introduced in schema 1.1.
+            let standard_name_space = node
+                .attribute_with(STANDARD_NAME_SPCACE, convert_to_standard_name_space)?
+                .unwrap_or(StandardNameSpace::None);
+            (
+                standard_name_space,
+                schema_version.major(),
+                schema_version.minor(),
+                schema_version.subminor(),
+            )
+        } else {
+            (
+                node.expect_attribute_with(STANDARD_NAME_SPCACE, convert_to_standard_name_space)?,
+                node.expect_attribute_with(SCHEMA_MAJOR_VERSION, convert_to_uint)?,
+                node.expect_attribute_with(SCHEMA_MINOR_VERSION, convert_to_uint)?,
+                node.expect_attribute_with(SCHEMA_SUB_MINOR_VERSION, convert_to_uint)?,
+            )
+        };
+        let major_version = node.expect_attribute_with(MAJOR_VERSION, convert_to_uint)?;
+        let minor_version = node.expect_attribute_with(MINOR_VERSION, convert_to_uint)?;
+        let subminor_version = node.expect_attribute_with(SUB_MINOR_VERSION, convert_to_uint)?;
+        let product_guid = node.expect_attribute(PRODUCT_GUID)?.into();
+        let version_guid = node.expect_attribute(VERSION_GUID)?.into();
+        let extensions = node.extensions(REGISTER_DESCRIPTION_ATTRIBUTES);
+        let name_spaces = node.prefixed_name_spaces();
+
+        Ok(Self {
             model_name,
             vendor_name,
             tooltip,
@@ -59,13 +81,18 @@ impl Parse for RegisterDescription {
             subminor_version,
             product_guid,
             version_guid,
-        }
+            extensions,
+            name_spaces,
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::elem_type::StandardNameSpace;
+    use crate::{
+        elem_type::SchemaVersion,
+        store::{DefaultCacheStore, DefaultNodeStore, DefaultValueStore, NodeStore},
+    };
 
     use super::{super::utils::tests::parse_default, *};
 
@@ -260,4 +287,42 @@ mod tests {
             "76543210-3210-3210-3210-ba9876543210"
         );
     }
+
+    #[test]
+    fn test_register_description_schema_1_0() {
+        // Schema version is deduced from the namespace, and attributes introduced in schema 1.1
+        // are missing.
+        let xml = r#"
+        <RegisterDescription
+          ModelName="CameleonModel"
+          VendorName="CameleonVendor"
+          MajorVersion="1"
+          MinorVersion="0"
+          SubMinorVersion="0"
+          ProductGuid="01234567-0123-0123-0123-0123456789ab"
+          VersionGuid="76543210-3210-3210-3210-ba9876543210"
+          xmlns="http://www.genicam.org/GenApi/Version_1_0">
+
+            <Integer Name="MyInt">
+                <IsObsolete>Yes</IsObsolete>
+                <Value>10</Value>
+            </Integer>
+        </RegisterDescription>
+        "#;
+
+        let (reg_desc, ..): (RegisterDescription, _, _, _) = parse_default(xml);
+        assert_eq!(reg_desc.schema_version(), SchemaVersion::V1_0);
+        assert_eq!(reg_desc.standard_name_space(), StandardNameSpace::None);
+
+        // Elements which are not defined in the current schema are skipped in schema 1.0.
+        let mut node_builder = DefaultNodeStore::new();
+        crate::parser::parse(
+            &xml,
+            &mut node_builder,
+            &mut DefaultValueStore::new(),
+            &mut DefaultCacheStore::new(),
+        )
+        .unwrap();
+        assert!(node_builder.id_by_name("MyInt").is_some());
+    }
 }
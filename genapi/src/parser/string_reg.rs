@@ -9,7 +9,7 @@ use crate::{
     StringRegNode,
 };
 
-use super::{xml, Parse};
+use super::{xml, Parse, ParseResult};
 
 impl Parse for StringRegNode {
     #[tracing::instrument(level = "trace", skip(node_builder, value_builder, cache_builder))]
@@ -18,12 +18,12 @@ impl Parse for StringRegNode {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         debug!("start parsing `StringRegNode`");
         debug_assert!(node.tag_name() == "StringReg");
 
-        let attr_base = node.parse(node_builder, value_builder, cache_builder);
-        let register_base = node.parse(node_builder, value_builder, cache_builder);
+        let attr_base = node.parse(node_builder, value_builder, cache_builder)?;
+        let register_base = node.parse(node_builder, value_builder, cache_builder)?;
 
         let node = Self {
             attr_base,
@@ -31,6 +31,6 @@ impl Parse for StringRegNode {
         };
         node.register_base
             .store_invalidators(node.attr_base.id, cache_builder);
-        node
+        Ok(node)
     }
 }
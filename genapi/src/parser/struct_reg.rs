@@ -18,7 +18,7 @@ use super::{
         ACCESS_MODE, CACHEABLE, ENDIANNESS, POLLING_TIME, P_INVALIDATOR, P_SELECTED,
         REPRESENTATION, SIGN, STREAMABLE, STRUCT_ENTRY, STRUCT_REG, UNIT,
     },
-    xml, Parse,
+    xml, Parse, ParseResult,
 };
 
 #[derive(Debug, Clone)]
@@ -51,26 +51,26 @@ impl Parse for StructRegNode {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         debug!("start parsing `StructRegNode`");
         debug_assert_eq!(node.tag_name(), STRUCT_REG);
 
-        let register_base = node.parse(node_builder, value_builder, cache_builder);
+        let register_base = node.parse(node_builder, value_builder, cache_builder)?;
 
         let endianness = node
-            .parse_if(ENDIANNESS, node_builder, value_builder, cache_builder)
+            .parse_if(ENDIANNESS, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
         let mut entries = vec![];
         while let Some(mut entry_node) = node.next() {
-            let entry = entry_node.parse(node_builder, value_builder, cache_builder);
+            let entry = entry_node.parse(node_builder, value_builder, cache_builder)?;
             entries.push(entry);
         }
 
-        Self {
+        Ok(Self {
             register_base,
             endianness,
             entries,
-        }
+        })
     }
 }
 
@@ -92,6 +92,12 @@ struct StructEntryNode {
 }
 
 /// See "2.8.7 StructReg" in GenICam Standard v2.1.1.
+// Compares with the default value through a typed helper so that the comparison is not ambiguous
+// when other crates add `PartialEq` impls for primitive types, e.g. `serde_json`.
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
 macro_rules! merge_impl {
     ($lhs:ident, $rhs:ident, $name:ident) => {
         if $rhs.$name.is_some() {
@@ -100,8 +106,7 @@ macro_rules! merge_impl {
     };
 
     ($lhs:ident, $rhs:ident, $name:ident, default) => {
-        #[allow(clippy::default_trait_access)]
-        if $rhs.$name != Default::default() {
+        if !is_default(&$rhs.$name) {
             $lhs.$name = $rhs.$name;
         }
     };
@@ -177,35 +182,37 @@ impl Parse for StructEntryNode {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         debug_assert_eq!(node.tag_name(), STRUCT_ENTRY);
 
-        let attr_base = node.parse(node_builder, value_builder, cache_builder);
-        let elem_base = node.parse(node_builder, value_builder, cache_builder);
+        let attr_base = node.parse(node_builder, value_builder, cache_builder)?;
+        let elem_base = node.parse(node_builder, value_builder, cache_builder)?;
 
         let p_invalidators =
-            node.parse_while(P_INVALIDATOR, node_builder, value_builder, cache_builder);
+            node.parse_while(P_INVALIDATOR, node_builder, value_builder, cache_builder)?;
         let access_mode = node
-            .parse_if(ACCESS_MODE, node_builder, value_builder, cache_builder)
+            .parse_if(ACCESS_MODE, node_builder, value_builder, cache_builder)?
             .unwrap_or(AccessMode::RO);
         let cacheable = node
-            .parse_if(CACHEABLE, node_builder, value_builder, cache_builder)
+            .parse_if(CACHEABLE, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
-        let polling_time = node.parse_if(POLLING_TIME, node_builder, value_builder, cache_builder);
+        let polling_time =
+            node.parse_if(POLLING_TIME, node_builder, value_builder, cache_builder)?;
         let streamable = node
-            .parse_if(STREAMABLE, node_builder, value_builder, cache_builder)
+            .parse_if(STREAMABLE, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
-        let bit_mask = node.parse(node_builder, value_builder, cache_builder);
+        let bit_mask = node.parse(node_builder, value_builder, cache_builder)?;
         let sign = node
-            .parse_if(SIGN, node_builder, value_builder, cache_builder)
+            .parse_if(SIGN, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
-        let unit = node.parse_if(UNIT, node_builder, value_builder, cache_builder);
+        let unit = node.parse_if(UNIT, node_builder, value_builder, cache_builder)?;
         let representation = node
-            .parse_if(REPRESENTATION, node_builder, value_builder, cache_builder)
+            .parse_if(REPRESENTATION, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
-        let p_selected = node.parse_while(P_SELECTED, node_builder, value_builder, cache_builder);
+        let p_selected =
+            node.parse_while(P_SELECTED, node_builder, value_builder, cache_builder)?;
 
-        Self {
+        Ok(Self {
             attr_base,
             elem_base,
             p_invalidators,
@@ -218,7 +225,7 @@ impl Parse for StructEntryNode {
             unit,
             representation,
             p_selected,
-        }
+        })
     }
 }
 
@@ -6,7 +6,7 @@ use tracing::debug;
 
 use crate::{
     builder::{CacheStoreBuilder, NodeStoreBuilder, ValueStoreBuilder},
-    SwissKnifeNode,
+    utils, SwissKnifeNode,
 };
 
 use super::{
@@ -14,7 +14,7 @@ use super::{
         CONSTANT, DISPLAY_NOTATION, DISPLAY_PRECISION, EXPRESSION, P_VARIABLE, REPRESENTATION,
         STREAMABLE, SWISS_KNIFE, UNIT,
     },
-    xml, Parse,
+    xml, Parse, ParseResult,
 };
 
 impl Parse for SwissKnifeNode {
@@ -24,26 +24,29 @@ impl Parse for SwissKnifeNode {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         debug!("start parsing `SwissKnifeNode`");
         debug_assert_eq!(node.tag_name(), SWISS_KNIFE);
 
-        let attr_base = node.parse(node_builder, value_builder, cache_builder);
-        let elem_base = node.parse(node_builder, value_builder, cache_builder);
+        let attr_base = node.parse(node_builder, value_builder, cache_builder)?;
+        let elem_base = node.parse(node_builder, value_builder, cache_builder)?;
 
         let streamable = node
-            .parse_if(STREAMABLE, node_builder, value_builder, cache_builder)
+            .parse_if(STREAMABLE, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
-        let p_variables = node.parse_while(P_VARIABLE, node_builder, value_builder, cache_builder);
-        let constants = node.parse_while(CONSTANT, node_builder, value_builder, cache_builder);
-        let expressions = node.parse_while(EXPRESSION, node_builder, value_builder, cache_builder);
-        let formula = node.parse(node_builder, value_builder, cache_builder);
-        let unit = node.parse_if(UNIT, node_builder, value_builder, cache_builder);
+        let p_variables =
+            node.parse_while(P_VARIABLE, node_builder, value_builder, cache_builder)?;
+        let constants = node.parse_while(CONSTANT, node_builder, value_builder, cache_builder)?;
+        let expressions =
+            node.parse_while(EXPRESSION, node_builder, value_builder, cache_builder)?;
+        let mut formula = node.parse(node_builder, value_builder, cache_builder)?;
+        utils::compile_formula(&mut formula, &p_variables, &constants, &expressions, &[]);
+        let unit = node.parse_if(UNIT, node_builder, value_builder, cache_builder)?;
         let representation = node
-            .parse_if(REPRESENTATION, node_builder, value_builder, cache_builder)
+            .parse_if(REPRESENTATION, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
         let display_notation = node
-            .parse_if(DISPLAY_NOTATION, node_builder, value_builder, cache_builder)
+            .parse_if(DISPLAY_NOTATION, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
         let display_precision = node
             .parse_if(
@@ -51,10 +54,10 @@ impl Parse for SwissKnifeNode {
                 node_builder,
                 value_builder,
                 cache_builder,
-            )
+            )?
             .unwrap_or(6);
 
-        Self {
+        Ok(Self {
             attr_base,
             elem_base,
             streamable,
@@ -66,7 +69,7 @@ impl Parse for SwissKnifeNode {
             representation,
             display_notation,
             display_precision,
-        }
+        })
     }
 }
 
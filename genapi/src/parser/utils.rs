@@ -18,7 +18,8 @@ pub(super) mod tests {
         (
             document
                 .root_node()
-                .parse(&mut node_builder, &mut value_builder, &mut cache_builder),
+                .parse(&mut node_builder, &mut value_builder, &mut cache_builder)
+                .unwrap(),
             node_builder,
             value_builder,
             cache_builder,
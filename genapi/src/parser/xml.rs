@@ -2,29 +2,213 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use std::{fmt, iter::Peekable};
+use std::{fmt, iter::Peekable, ops::Range};
 
-use crate::builder::{CacheStoreBuilder, NodeStoreBuilder, ValueStoreBuilder};
+use tracing::debug;
 
-use super::{Parse, ParseResult};
+use crate::{
+    builder::{CacheStoreBuilder, NodeStoreBuilder, ValueStoreBuilder},
+    elem_type::{ExtensionElement, Extensions, SchemaVersion},
+};
+
+use super::{
+    elem_name::{
+        is_standard_element, NAME, SCHEMA_MAJOR_VERSION, SCHEMA_MINOR_VERSION,
+        SCHEMA_SUB_MINOR_VERSION,
+    },
+    elem_type::convert_to_uint,
+    Parse, ParseError, ParseOptions, ParseResult,
+};
+
+const XSI_NAME_SPACE: &str = "http://www.w3.org/2001/XMLSchema-instance";
+const XML_NAME_SPACE: &str = "http://www.w3.org/XML/1998/namespace";
+const GENAPI_NAME_SPACE_PREFIX: &str = "http://www.genicam.org/GenApi/Version_";
 
 pub(super) struct Document<'input> {
     document: roxmltree::Document<'input>,
+    cx: Context<'input>,
 }
 
 impl<'input> Document<'input> {
+    #[cfg(test)]
     pub(super) fn from_str(s: &'input str) -> ParseResult<Self> {
+        Self::from_str_with_options(s, ParseOptions::default())
+    }
+
+    pub(super) fn from_str_with_options(
+        s: &'input str,
+        options: ParseOptions,
+    ) -> ParseResult<Self> {
         let document = roxmltree::Document::parse(s)?;
-        Ok(Self { document })
+        Ok(Self::new(document, s, 0, options))
+    }
+
+    /// Parses a fragment of `origin` which is made by [`Fragments`].
+    pub(super) fn from_fragment(
+        fragment: &'input Fragment,
+        origin: &'input str,
+        options: ParseOptions,
+    ) -> ParseResult<Self> {
+        let document = roxmltree::Document::parse(&fragment.text).map_err(|err| {
+            // Report the error of the whole XML if any so that the position points to `origin`.
+            roxmltree::Document::parse(origin).err().unwrap_or(err)
+        })?;
+        Ok(Self::new(document, origin, fragment.offset, options))
     }
 
     pub(super) fn root_node<'a>(&'a self) -> Node<'a, 'input> {
         let root = self.document.root_element();
-        Node::from_xmltree_node(root, self.inner_str())
+        Node::from_xmltree_node(root, self.cx)
+    }
+
+    fn new(
+        document: roxmltree::Document<'input>,
+        origin: &'input str,
+        offset: usize,
+        options: ParseOptions,
+    ) -> Self {
+        let schema_version = detect_schema_version(document.root_element());
+        let cx = Context {
+            src: document.input_text(),
+            origin,
+            offset,
+            tolerant: options.tolerant,
+            schema_version,
+        };
+        Self { document, cx }
+    }
+}
+
+/// Top-level elements of a document.
+///
+/// Each element is parsed as a separate [`Fragment`] so that the DOM of the whole document is
+/// never built at once.
+pub(super) struct Fragments<'input> {
+    src: &'input str,
+    root_start: usize,
+    /// Start tag of the root element, including its attributes and namespace declarations.
+    root_tag: &'input str,
+    /// Qualified name of the root element.
+    root_name: &'input str,
+    is_root_empty: bool,
+    elements: Vec<Range<usize>>,
+}
+
+impl<'input> Fragments<'input> {
+    /// Scans the document and returns the spans of top-level elements.
+    ///
+    /// Returns `None` if the document can't be split into fragments, e.g. the document is
+    /// malformed or has DTD, which fragments can't refer to.
+    pub(super) fn scan(src: &'input str) -> Option<Self> {
+        use xmlparser::{ElementEnd, Token};
+
+        let mut depth = 0_usize;
+        let mut root = None;
+        let mut root_tag_end = None;
+        let mut is_root_empty = false;
+        let mut element_start = 0;
+        let mut elements = vec![];
+
+        for token in xmlparser::Tokenizer::from(src) {
+            match token.ok()? {
+                Token::DtdStart { .. } | Token::EmptyDtd { .. } => return None,
+                Token::ElementStart { span, .. } => {
+                    if depth == 0 {
+                        // `span` is `<` followed by the qualified name.
+                        root = Some((span.start(), &span.as_str()[1..]));
+                    } else if depth == 1 {
+                        element_start = span.start();
+                    }
+                    depth += 1;
+                }
+                Token::ElementEnd {
+                    end: ElementEnd::Open,
+                    span,
+                } => {
+                    if depth == 1 {
+                        root_tag_end = Some(span.end());
+                    }
+                }
+                Token::ElementEnd { end, span } => {
+                    depth = depth.checked_sub(1)?;
+                    if depth == 1 {
+                        elements.push(element_start..span.end());
+                    } else if depth == 0 && end == ElementEnd::Empty {
+                        root_tag_end = Some(span.end());
+                        is_root_empty = true;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let (root_start, root_name) = root?;
+        let root_tag = &src[root_start..root_tag_end?];
+        Some(Self {
+            src,
+            root_start,
+            root_tag,
+            root_name,
+            is_root_empty,
+            elements,
+        })
+    }
+
+    /// Returns the fragment which contains only the root element without its children.
+    pub(super) fn root(&self) -> Fragment {
+        self.fragment("", self.root_start)
+    }
+
+    /// Returns fragments of top-level elements, each of them is wrapped by the root element.
+    pub(super) fn elements(&self) -> impl Iterator<Item = Fragment> + '_ {
+        self.elements.iter().map(move |span| {
+            let offset = span.start - self.root_tag.len();
+            self.fragment(&self.src[span.clone()], offset)
+        })
+    }
+
+    fn fragment(&self, content: &str, offset: usize) -> Fragment {
+        let text = if self.is_root_empty {
+            self.root_tag.to_string()
+        } else {
+            format!("{}{}</{}>", self.root_tag, content, self.root_name)
+        };
+        Fragment { text, offset }
     }
+}
 
-    pub(super) fn inner_str(&self) -> &'input str {
-        self.document.input_text()
+/// A part of a document which is a well-formed XML by itself.
+pub(super) struct Fragment {
+    text: String,
+    /// Offset to convert a position in `text` to the position in the original document.
+    offset: usize,
+}
+
+/// Properties of the document shared by all nodes in it.
+#[derive(Clone, Copy)]
+struct Context<'input> {
+    /// Text of the parsed document.
+    src: &'input str,
+    /// Text of the original document, which differs from `src` if the document is a fragment.
+    origin: &'input str,
+    /// Offset of `src` in `origin`.
+    offset: usize,
+    /// If `true`, non-standard child elements are skipped and collected by
+    /// [`Node::extensions`].
+    tolerant: bool,
+    schema_version: SchemaVersion,
+}
+
+impl<'input> Context<'input> {
+    fn error_at(&self, node: roxmltree::Node, message: impl Into<String>) -> ParseError {
+        let pos = xmlparser::Stream::from(self.origin)
+            .gen_text_pos_from(node.range().start + self.offset);
+        ParseError::InvalidElement {
+            path: element_path(node),
+            line: pos.row,
+            column: pos.col,
+            message: message.into(),
+        }
     }
 }
 
@@ -32,7 +216,7 @@ pub(super) struct Node<'a, 'input> {
     inner: roxmltree::Node<'a, 'input>,
     children: Peekable<roxmltree::Children<'a, 'input>>,
     attributes: Attributes<'a, 'input>,
-    src: &'input str,
+    cx: Context<'input>,
 }
 
 impl<'a, 'input> Node<'a, 'input> {
@@ -41,7 +225,7 @@ impl<'a, 'input> Node<'a, 'input> {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> T {
+    ) -> ParseResult<T> {
         T::parse(self, node_builder, value_builder, cache_builder)
     }
 
@@ -51,11 +235,12 @@ impl<'a, 'input> Node<'a, 'input> {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Option<T> {
-        if self.peek()?.tag_name() == tag_name {
-            Some(self.parse(node_builder, value_builder, cache_builder))
-        } else {
-            None
+    ) -> ParseResult<Option<T>> {
+        match self.peek() {
+            Some(peeked) if peeked.tag_name() == tag_name => self
+                .parse(node_builder, value_builder, cache_builder)
+                .map(Some),
+            _ => Ok(None),
         }
     }
 
@@ -65,13 +250,14 @@ impl<'a, 'input> Node<'a, 'input> {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Vec<T> {
+    ) -> ParseResult<Vec<T>> {
         let mut res = vec![];
-        while let Some(parsed) = self.parse_if(tag_name, node_builder, value_builder, cache_builder)
+        while let Some(parsed) =
+            self.parse_if(tag_name, node_builder, value_builder, cache_builder)?
         {
             res.push(parsed);
         }
-        res
+        Ok(res)
     }
 
     pub(super) fn next(&mut self) -> Option<Self> {
@@ -89,24 +275,36 @@ impl<'a, 'input> Node<'a, 'input> {
         }
     }
 
-    pub(super) fn next_text(&mut self) -> Option<TextView<'a, 'input>> {
-        Some(self.next()?.text())
+    /// Same as [`Self::next`], but returns an error if there is no child element left.
+    pub(super) fn expect_next(&mut self) -> ParseResult<Self> {
+        self.next()
+            .ok_or_else(|| self.error("a child element is missing"))
+    }
+
+    pub(super) fn next_text(&mut self) -> ParseResult<TextView<'a, 'input>> {
+        Ok(self.expect_next()?.text())
     }
 
     pub(super) fn peek(&mut self) -> Option<Self> {
         let mut inner;
         loop {
-            inner = self.children.peek()?;
-            if inner.node_type() == roxmltree::NodeType::Element {
+            inner = *self.children.peek()?;
+            if inner.node_type() == roxmltree::NodeType::Element && !self.skips(inner) {
                 break;
             }
             self.children.next();
         }
-        let node = Self::from_xmltree_node(*inner, self.src);
+        let node = Self::from_xmltree_node(inner, self.cx);
 
         Some(node)
     }
 
+    /// Same as [`Self::peek`], but returns an error if there is no child element left.
+    pub(super) fn expect_peek(&mut self) -> ParseResult<Self> {
+        self.peek()
+            .ok_or_else(|| self.error("a child element is missing"))
+    }
+
     pub(super) fn tag_name(&self) -> &str {
         self.inner.tag_name().name()
     }
@@ -115,11 +313,145 @@ impl<'a, 'input> Node<'a, 'input> {
         self.attributes.attribute_of(name)
     }
 
+    /// Same as [`Self::attribute_of`], but returns an error if the attribute is missing.
+    pub(super) fn expect_attribute(&self, name: &str) -> ParseResult<&str> {
+        self.attribute_of(name)
+            .ok_or_else(|| self.error(format!("attribute `{}` is missing", name)))
+    }
+
+    /// Returns the value of the attribute converted by `convert`, or an error if the conversion
+    /// fails.
+    pub(super) fn attribute_with<T>(
+        &self,
+        name: &str,
+        convert: impl FnOnce(&str) -> Option<T>,
+    ) -> ParseResult<Option<T>> {
+        self.attribute_of(name)
+            .map(|value| {
+                convert(value).ok_or_else(|| {
+                    self.error(format!(
+                        "invalid value of attribute `{}`: `{}`",
+                        name, value
+                    ))
+                })
+            })
+            .transpose()
+    }
+
+    /// Same as [`Self::attribute_with`], but returns an error if the attribute is missing.
+    pub(super) fn expect_attribute_with<T>(
+        &self,
+        name: &str,
+        convert: impl FnOnce(&str) -> Option<T>,
+    ) -> ParseResult<T> {
+        self.attribute_with(name, convert)?
+            .ok_or_else(|| self.error(format!("attribute `{}` is missing", name)))
+    }
+
+    /// Returns the schema version of the document which the element belongs to.
+    pub(super) fn schema_version(&self) -> SchemaVersion {
+        self.cx.schema_version
+    }
+
+    /// Returns non-standard attributes and child elements of the element.
+    ///
+    /// Attributes in `standard_attrs` and child elements defined in the `GenApi` schema are
+    /// excluded. An empty [`Extensions`] is returned unless the document is parsed in tolerant
+    /// mode.
+    pub(super) fn extensions(&self, standard_attrs: &[&str]) -> Extensions {
+        if !self.cx.tolerant {
+            return Extensions::default();
+        }
+
+        let attributes = self
+            .inner
+            .attributes()
+            .iter()
+            .filter(|attr| match attr.namespace() {
+                Some(ns) => ns != XSI_NAME_SPACE,
+                None => !standard_attrs.contains(&attr.name()),
+            })
+            .map(|attr| {
+                let name = qualified_name(self.inner, attr.namespace(), attr.name());
+                (name, attr.value().to_string())
+            })
+            .collect();
+
+        Extensions {
+            attributes,
+            elements: self.extension_elements(),
+        }
+    }
+
+    /// Returns non-standard child elements of the element, see [`Self::extensions`].
+    pub(super) fn extension_elements(&self) -> Vec<ExtensionElement> {
+        if !self.cx.tolerant {
+            return vec![];
+        }
+
+        self.inner
+            .children()
+            .filter(|child| child.is_element() && !is_standard(*child))
+            .map(|child| {
+                let tag = child.tag_name();
+                ExtensionElement {
+                    name: qualified_name(child, tag.namespace(), tag.name()),
+                    source: self.cx.src[child.range()].to_string(),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns namespace declarations with a prefix which are in scope of the element, e.g.
+    /// `xmlns:vendor="http://example.com/vendor"`.
+    ///
+    /// Extension elements may use the prefixes, so they must be kept to write the elements
+    /// back. An empty vector is returned unless the document is parsed in tolerant mode.
+    pub(super) fn prefixed_name_spaces(&self) -> Vec<(String, String)> {
+        if !self.cx.tolerant {
+            return vec![];
+        }
+
+        self.inner
+            .namespaces()
+            .iter()
+            .filter(|ns| ns.uri() != XML_NAME_SPACE)
+            .filter_map(|ns| Some((format!("xmlns:{}", ns.name()?), ns.uri().to_string())))
+            .collect()
+    }
+
+    /// Returns an error that points to the element.
+    pub(super) fn error(&self, message: impl Into<String>) -> ParseError {
+        self.cx.error_at(self.inner, message)
+    }
+
     pub(super) fn text(&self) -> TextView<'a, 'input> {
-        TextView { inner: self.inner }
+        TextView {
+            inner: self.inner,
+            cx: self.cx,
+        }
     }
 
-    fn from_xmltree_node(node: roxmltree::Node<'a, 'input>, src: &'input str) -> Self {
+    /// Returns `true` if the child element should be skipped by [`Self::peek`].
+    ///
+    /// Schema 1.0 documents may contain elements which were removed in later schemas, so
+    /// non-standard elements are always skipped for them.
+    fn skips(&self, child: roxmltree::Node) -> bool {
+        let skips_non_standard = self.cx.tolerant || self.cx.schema_version.is_1_0();
+        if skips_non_standard && !is_standard(child) {
+            if !self.cx.tolerant {
+                debug!(
+                    "skip element `{}` which is not defined in the current schema",
+                    child.tag_name().name()
+                );
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    fn from_xmltree_node(node: roxmltree::Node<'a, 'input>, cx: Context<'input>) -> Self {
         debug_assert!(node.node_type() == roxmltree::NodeType::Element);
         let children = node.children().peekable();
         let attributes = Attributes::from_xmltree_attrs(node.attributes());
@@ -128,7 +460,7 @@ impl<'a, 'input> Node<'a, 'input> {
             inner: node,
             children,
             attributes,
-            src,
+            cx,
         }
     }
 }
@@ -136,7 +468,7 @@ impl<'a, 'input> Node<'a, 'input> {
 impl<'a, 'input> fmt::Debug for Node<'a, 'input> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let span = self.inner.range();
-        let node_src = std::str::from_utf8(&self.src.as_bytes()[span]).unwrap();
+        let node_src = std::str::from_utf8(&self.cx.src.as_bytes()[span]).unwrap();
         write!(f, "{}", node_src)
     }
 }
@@ -163,11 +495,15 @@ impl<'a, 'input> Attributes<'a, 'input> {
 
 pub(super) struct TextView<'a, 'input> {
     inner: roxmltree::Node<'a, 'input>,
+    cx: Context<'input>,
 }
 
 impl<'a, 'input> TextView<'a, 'input> {
     pub(super) fn view(&self) -> std::borrow::Cow<'a, str> {
-        let first_child = self.inner.first_child().unwrap();
+        let first_child = match self.inner.first_child() {
+            Some(child) => child,
+            None => return "".into(),
+        };
         if first_child.has_siblings() {
             let mut s = String::new();
             for child in self.inner.children() {
@@ -180,9 +516,20 @@ impl<'a, 'input> TextView<'a, 'input> {
             }
             s.into()
         } else {
-            first_child.text().unwrap().into()
+            first_child.text().unwrap_or_default().into()
         }
     }
+
+    /// Returns an error that points to the element of the text.
+    pub(super) fn error(&self, message: impl Into<String>) -> ParseError {
+        self.cx.error_at(self.inner, message)
+    }
+}
+
+impl<'a, 'input> fmt::Display for TextView<'a, 'input> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.view())
+    }
 }
 
 impl<'a, 'input> PartialEq<&str> for TextView<'a, 'input> {
@@ -190,3 +537,52 @@ impl<'a, 'input> PartialEq<&str> for TextView<'a, 'input> {
         &self.view() == rhs
     }
 }
+
+/// Detects the schema version from the attributes of `RegisterDescription`. If the attributes are
+/// missing, the version is deduced from the namespace, e.g.
+/// `http://www.genicam.org/GenApi/Version_1_0`. Schema 1.1 is assumed if neither is available.
+fn detect_schema_version(root: roxmltree::Node) -> SchemaVersion {
+    let attr = |name: &str| root.attribute(name).and_then(convert_to_uint);
+    if let (Some(major), Some(minor)) = (attr(SCHEMA_MAJOR_VERSION), attr(SCHEMA_MINOR_VERSION)) {
+        let subminor = attr(SCHEMA_SUB_MINOR_VERSION).unwrap_or_default();
+        return SchemaVersion::new(major, minor, subminor);
+    }
+
+    root.tag_name()
+        .namespace()
+        .and_then(|ns| ns.strip_prefix(GENAPI_NAME_SPACE_PREFIX))
+        .and_then(|version| {
+            let mut iter = version.split('_').map(convert_to_uint);
+            Some(SchemaVersion::new(iter.next()??, iter.next()??, 0))
+        })
+        .unwrap_or(SchemaVersion::V1_1)
+}
+
+/// Returns `true` if the element is defined in the `GenApi` schema, i.e. it's in the same
+/// namespace as the root element and its name is a standard one.
+fn is_standard(node: roxmltree::Node) -> bool {
+    let root_ns = node.document().root_element().tag_name().namespace();
+    node.tag_name().namespace() == root_ns && is_standard_element(node.tag_name().name())
+}
+
+fn qualified_name(node: roxmltree::Node, ns: Option<&str>, name: &str) -> String {
+    match ns.and_then(|ns| node.lookup_prefix(ns)) {
+        Some(prefix) => format!("{}:{}", prefix, name),
+        None => name.to_string(),
+    }
+}
+
+/// Returns the path of the element from the root element, e.g.
+/// `RegisterDescription/Integer[Width]/Value`.
+fn element_path(node: roxmltree::Node) -> String {
+    let mut elems: Vec<String> = node
+        .ancestors()
+        .filter(roxmltree::Node::is_element)
+        .map(|n| match n.attribute(NAME) {
+            Some(name) => format!("{}[{}]", n.tag_name().name(), name),
+            None => n.tag_name().name().to_string(),
+        })
+        .collect();
+    elems.reverse();
+    elems.join("/")
+}
@@ -2,15 +2,18 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use std::convert::TryFrom;
+
 use super::{
     elem_type::ImmOrPNode,
-    interface::{INode, IPort},
+    interface::{IInteger, INode, IPort},
     node_base::{NodeAttributeBase, NodeBase, NodeElementBase},
     store::{CacheStore, NodeStore, ValueStore},
     Device, GenApiError, GenApiResult, ValueCtxt,
 };
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PortNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -48,7 +51,7 @@ impl INode for PortNode {
 }
 
 impl IPort for PortNode {
-    #[tracing::instrument(skip(self, device, store),
+    #[tracing::instrument(skip(self, device, store, cx),
                           level = "trace",
                           fields(node = store.name_by_id(self.node_base().id()).unwrap()))]
     fn read<T: ValueStore, U: CacheStore>(
@@ -57,10 +60,29 @@ impl IPort for PortNode {
         buf: &mut [u8],
         device: &mut impl Device,
         store: &impl NodeStore,
-        _: &mut ValueCtxt<T, U>,
+        cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<()> {
-        if self.chunk_id.is_some() {
-            Err(GenApiError::chunk_data_missing())
+        if let Some(chunk_id) = &self.chunk_id {
+            let chunk_id = match chunk_id {
+                ImmOrPNode::Imm(id) => *id,
+                ImmOrPNode::PNode(nid) => {
+                    let id = nid.expect_iinteger_kind(store)?.value(device, store, cx)?;
+                    u64::try_from(id).map_err(|_| {
+                        GenApiError::invalid_node("`ChunkID` must not be negative".into())
+                    })?
+                }
+            };
+            let data = cx
+                .chunk_data(chunk_id)
+                .ok_or_else(GenApiError::chunk_data_missing)?;
+            let chunk = usize::try_from(address)
+                .ok()
+                .and_then(|start| data.get(start..start.checked_add(buf.len())?))
+                .ok_or_else(|| {
+                    GenApiError::invalid_buffer("the register is out of the chunk data".into())
+                })?;
+            buf.copy_from_slice(chunk);
+            Ok(())
         } else {
             device
                 .read_mem(address, buf)
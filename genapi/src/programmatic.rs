@@ -0,0 +1,1708 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module provides a builder to construct `GenApi` nodes in Rust code without XML.
+//!
+//! It's useful for emulators and tests, or for devices whose XML is too broken to be parsed.
+//!
+//! # Examples
+//! ```rust
+//! use cameleon_genapi::{builder::GenApiBuilder, elem_type::AccessMode, store::DefaultNodeStore};
+//!
+//! let mut builder =
+//!     GenApiBuilder::<DefaultNodeStore>::default().programmatic("MyModel", "MyVendor");
+//! builder.port("Device").finish();
+//! builder
+//!     .int_reg("WidthReg", "Device")
+//!     .address(0x1000)
+//!     .length(4)
+//!     .access_mode(AccessMode::RW)
+//!     .finish();
+//! builder
+//!     .integer("Width")
+//!     .p_value("WidthReg")
+//!     .min(16)
+//!     .max(1920)
+//!     .inc(16)
+//!     .finish();
+//! builder
+//!     .enumeration("PixelFormat")
+//!     .entry("Mono8", 0x0108_0001)
+//!     .entry("Mono16", 0x0110_0007)
+//!     .value(0x0108_0001)
+//!     .finish();
+//! builder.category("Root").feature("Width").feature("PixelFormat").finish();
+//!
+//! let (reg_desc, node_store, value_ctxt) = builder.build();
+//! ```
+
+use std::marker::PhantomData;
+
+use super::{
+    builder::{CacheStoreBuilder, NodeStoreBuilder, ValueStoreBuilder},
+    elem_type::{
+        AccessMode, AddressKind, CachingMode, DisplayNotation, Endianness, Extensions,
+        FloatRepresentation, ImmOrPNode, IntegerRepresentation, NamedValue, PValue, Sign, Slope,
+        StandardNameSpace, ValueKind, Visibility,
+    },
+    formula::{self, Expr, Formula},
+    node_base::{NodeAttributeBase, NodeElementBase},
+    store::{FloatId, IntegerId, NodeData, NodeId},
+    utils, BooleanNode, CategoryNode, CommandNode, ConverterNode, EnumEntryNode, EnumerationNode,
+    FloatNode, FloatRegNode, IntConverterNode, IntRegNode, IntSwissKnifeNode, IntegerNode,
+    PortNode, RegisterBase, RegisterDescription, SwissKnifeNode, ValueCtxt,
+};
+
+/// A builder to construct `GenApi` nodes in Rust code.
+///
+/// Use [`GenApiBuilder::programmatic`](crate::builder::GenApiBuilder::programmatic) to construct
+/// the builder.
+///
+/// Nodes can refer to other nodes by name before the referred nodes are defined, same as XML.
+/// Each node must be defined at most once.
+pub struct ProgrammaticBuilder<T, U, S> {
+    node_builder: T,
+    value_builder: U,
+    cache_builder: S,
+    reg_desc: RegisterDescription,
+}
+
+impl<T, U, S> ProgrammaticBuilder<T, U, S> {
+    pub(crate) fn new(
+        node_builder: T,
+        value_builder: U,
+        cache_builder: S,
+        model_name: String,
+        vendor_name: String,
+    ) -> Self {
+        let reg_desc = RegisterDescription {
+            model_name,
+            vendor_name,
+            tooltip: None,
+            standard_name_space: StandardNameSpace::None,
+            schema_major_version: 1,
+            schema_minor_version: 1,
+            schema_subminor_version: 0,
+            major_version: 1,
+            minor_version: 0,
+            subminor_version: 0,
+            product_guid: String::new(),
+            version_guid: String::new(),
+            extensions: Extensions::default(),
+            name_spaces: vec![],
+        };
+
+        Self {
+            node_builder,
+            value_builder,
+            cache_builder,
+            reg_desc,
+        }
+    }
+}
+
+impl<T, U, S> ProgrammaticBuilder<T, U, S>
+where
+    T: NodeStoreBuilder,
+    U: ValueStoreBuilder,
+    S: CacheStoreBuilder,
+{
+    /// Builds stores. The returned values are the same as
+    /// [`GenApiBuilder::build`](crate::builder::GenApiBuilder::build).
+    pub fn build(self) -> (RegisterDescription, T::Store, ValueCtxt<U::Store, S::Store>) {
+        (
+            self.reg_desc,
+            self.node_builder.build(),
+            ValueCtxt::new(self.value_builder.build(), self.cache_builder.build()),
+        )
+    }
+
+    /// Sets versions of the register description.
+    pub fn version(&mut self, major: u64, minor: u64, subminor: u64) -> &mut Self {
+        self.reg_desc.major_version = major;
+        self.reg_desc.minor_version = minor;
+        self.reg_desc.subminor_version = subminor;
+        self
+    }
+
+    /// Sets tooltip of the register description.
+    pub fn tooltip(&mut self, tooltip: impl Into<String>) -> &mut Self {
+        self.reg_desc.tooltip = Some(tooltip.into());
+        self
+    }
+
+    /// Returns [`NodeId`] of the node with the given name.
+    pub fn id(&mut self, name: impl AsRef<str>) -> NodeId {
+        self.node_builder.get_or_intern(name)
+    }
+
+    /// Starts to define a `Category` node.
+    pub fn category(&mut self, name: impl AsRef<str>) -> CategoryBuilder<'_, T, U, S> {
+        let (attr_base, elem_base) = self.node_base(name);
+        CategoryBuilder {
+            builder: self,
+            attr_base,
+            elem_base,
+            p_features: vec![],
+        }
+    }
+
+    /// Starts to define an `Integer` node. The value is `0` unless specified.
+    pub fn integer(&mut self, name: impl AsRef<str>) -> IntegerBuilder<'_, T, U, S> {
+        let (attr_base, elem_base) = self.node_base(name);
+        IntegerBuilder {
+            builder: self,
+            attr_base,
+            elem_base,
+            value: ImmOrPNode::Imm(0),
+            min: None,
+            max: None,
+            inc: ImmOrPNode::Imm(1),
+            unit: None,
+            representation: IntegerRepresentation::default(),
+            p_selected: vec![],
+        }
+    }
+
+    /// Starts to define a `Float` node. The value is `0.0` unless specified.
+    pub fn float(&mut self, name: impl AsRef<str>) -> FloatBuilder<'_, T, U, S> {
+        let (attr_base, elem_base) = self.node_base(name);
+        FloatBuilder {
+            builder: self,
+            attr_base,
+            elem_base,
+            value: ImmOrPNode::Imm(0.0),
+            min: ImmOrPNode::Imm(f64::MIN),
+            max: ImmOrPNode::Imm(f64::MAX),
+            inc: None,
+            unit: None,
+            representation: FloatRepresentation::default(),
+            display_notation: DisplayNotation::default(),
+            display_precision: 6,
+        }
+    }
+
+    /// Starts to define a `Boolean` node. The value is `false` unless specified.
+    pub fn boolean(&mut self, name: impl AsRef<str>) -> BooleanBuilder<'_, T, U, S> {
+        let (attr_base, elem_base) = self.node_base(name);
+        BooleanBuilder {
+            builder: self,
+            attr_base,
+            elem_base,
+            value: ImmOrPNode::Imm(false),
+            on_value: 1,
+            off_value: 0,
+            p_selected: vec![],
+        }
+    }
+
+    /// Starts to define a `Command` node that writes `command_value` to `p_value` node on
+    /// execution.
+    pub fn command(
+        &mut self,
+        name: impl AsRef<str>,
+        p_value: impl AsRef<str>,
+        command_value: i64,
+    ) -> CommandBuilder<'_, T, U, S> {
+        let (attr_base, elem_base) = self.node_base(name);
+        let p_value = self.id(p_value);
+        CommandBuilder {
+            builder: self,
+            attr_base,
+            elem_base,
+            p_value,
+            command_value,
+            polling_time: None,
+        }
+    }
+
+    /// Starts to define an `Enumeration` node. The value is `0` unless specified.
+    pub fn enumeration(&mut self, name: impl AsRef<str>) -> EnumerationBuilder<'_, T, U, S> {
+        let (attr_base, elem_base) = self.node_base(name);
+        EnumerationBuilder {
+            builder: self,
+            attr_base,
+            elem_base,
+            entries: vec![],
+            value: ImmOrPNode::Imm(0),
+            p_selected: vec![],
+            polling_time: None,
+        }
+    }
+
+    /// Starts to define a `Converter` node that converts the value of `p_value` node.
+    pub fn converter(
+        &mut self,
+        name: impl AsRef<str>,
+        p_value: impl AsRef<str>,
+    ) -> ConverterBuilder<'_, T, U, S> {
+        let (attr_base, elem_base) = self.node_base(name);
+        let p_value = self.id(p_value);
+        ConverterBuilder {
+            builder: self,
+            attr_base,
+            elem_base,
+            formula_env: FormulaEnv::default(),
+            formula_to: Expr::Ident("FROM".into()),
+            formula_from: Expr::Ident("TO".into()),
+            p_value,
+            unit: None,
+            representation: FloatRepresentation::default(),
+            display_notation: DisplayNotation::default(),
+            display_precision: 6,
+            slope: Slope::default(),
+        }
+    }
+
+    /// Starts to define an `IntConverter` node that converts the value of `p_value` node.
+    pub fn int_converter(
+        &mut self,
+        name: impl AsRef<str>,
+        p_value: impl AsRef<str>,
+    ) -> IntConverterBuilder<'_, T, U, S> {
+        let (attr_base, elem_base) = self.node_base(name);
+        let p_value = self.id(p_value);
+        IntConverterBuilder {
+            builder: self,
+            attr_base,
+            elem_base,
+            formula_env: FormulaEnv::default(),
+            formula_to: Expr::Ident("FROM".into()),
+            formula_from: Expr::Ident("TO".into()),
+            p_value,
+            unit: None,
+            representation: IntegerRepresentation::default(),
+            slope: Slope::default(),
+        }
+    }
+
+    /// Starts to define a `SwissKnife` node with the formula.
+    ///
+    /// # Panics
+    /// Panics if the formula is malformed.
+    pub fn swiss_knife(
+        &mut self,
+        name: impl AsRef<str>,
+        formula: &str,
+    ) -> SwissKnifeBuilder<'_, T, U, S> {
+        let (attr_base, elem_base) = self.node_base(name);
+        SwissKnifeBuilder {
+            builder: self,
+            attr_base,
+            elem_base,
+            formula_env: FormulaEnv::default(),
+            formula: formula::parse(formula),
+            unit: None,
+            representation: FloatRepresentation::default(),
+        }
+    }
+
+    /// Starts to define an `IntSwissKnife` node with the formula.
+    ///
+    /// # Panics
+    /// Panics if the formula is malformed.
+    pub fn int_swiss_knife(
+        &mut self,
+        name: impl AsRef<str>,
+        formula: &str,
+    ) -> IntSwissKnifeBuilder<'_, T, U, S> {
+        let (attr_base, elem_base) = self.node_base(name);
+        IntSwissKnifeBuilder {
+            builder: self,
+            attr_base,
+            elem_base,
+            formula_env: FormulaEnv::default(),
+            formula: formula::parse(formula),
+            unit: None,
+            representation: IntegerRepresentation::default(),
+        }
+    }
+
+    /// Starts to define an `IntReg` node that is accessed through `p_port` node.
+    ///
+    /// The register is located at address `0` and its length is `4` unless specified.
+    pub fn int_reg(
+        &mut self,
+        name: impl AsRef<str>,
+        p_port: impl AsRef<str>,
+    ) -> IntRegBuilder<'_, T, U, S> {
+        let (attr_base, register_base) = self.register_base(name, p_port);
+        IntRegBuilder {
+            builder: self,
+            attr_base,
+            register_base,
+            sign: Sign::default(),
+            endianness: Endianness::default(),
+            unit: None,
+            representation: IntegerRepresentation::default(),
+            p_selected: vec![],
+        }
+    }
+
+    /// Starts to define a `FloatReg` node that is accessed through `p_port` node.
+    ///
+    /// The register is located at address `0` and its length is `4` unless specified.
+    pub fn float_reg(
+        &mut self,
+        name: impl AsRef<str>,
+        p_port: impl AsRef<str>,
+    ) -> FloatRegBuilder<'_, T, U, S> {
+        let (attr_base, register_base) = self.register_base(name, p_port);
+        FloatRegBuilder {
+            builder: self,
+            attr_base,
+            register_base,
+            endianness: Endianness::default(),
+            unit: None,
+            representation: FloatRepresentation::default(),
+            display_notation: DisplayNotation::default(),
+            display_precision: 6,
+        }
+    }
+
+    /// Starts to define a `Port` node.
+    pub fn port(&mut self, name: impl AsRef<str>) -> PortBuilder<'_, T, U, S> {
+        let (attr_base, elem_base) = self.node_base(name);
+        PortBuilder {
+            builder: self,
+            attr_base,
+            elem_base,
+            chunk_id: None,
+            swap_endianness: false,
+        }
+    }
+
+    fn node_base(&mut self, name: impl AsRef<str>) -> (NodeAttributeBase, NodeElementBase) {
+        let id = self.id(name);
+        let attr_base = NodeAttributeBase {
+            id,
+            name_space: Default::default(),
+            merge_priority: Default::default(),
+            expose_static: None,
+            extensions: Extensions::default(),
+        };
+        let elem_base = NodeElementBase {
+            tooltip: None,
+            description: None,
+            display_name: None,
+            visibility: Visibility::default(),
+            docu_url: None,
+            is_deprecated: false,
+            event_id: None,
+            p_is_implemented: None,
+            p_is_available: None,
+            p_is_locked: None,
+            p_block_polling: None,
+            imposed_access_mode: AccessMode::RW,
+            p_errors: vec![],
+            p_alias: None,
+            p_cast_alias: None,
+            p_invalidators: vec![],
+        };
+        (attr_base, elem_base)
+    }
+
+    fn register_base(
+        &mut self,
+        name: impl AsRef<str>,
+        p_port: impl AsRef<str>,
+    ) -> (NodeAttributeBase, RegisterBase) {
+        let (attr_base, elem_base) = self.node_base(name);
+        let p_port = self.id(p_port);
+        let register_base = RegisterBase {
+            elem_base,
+            streamable: false,
+            address_kinds: vec![],
+            length: ImmOrPNode::Imm(4),
+            access_mode: AccessMode::RO,
+            p_port,
+            cacheable: CachingMode::default(),
+            polling_time: None,
+            p_invalidators: vec![],
+        };
+        (attr_base, register_base)
+    }
+
+    fn store_node(&mut self, nid: NodeId, data: NodeData) -> NodeId {
+        self.node_builder.store_node(nid, data);
+        nid
+    }
+
+    fn store_integer(&mut self, value: ImmOrPNode<i64>) -> ImmOrPNode<IntegerId> {
+        match value {
+            ImmOrPNode::Imm(i) => ImmOrPNode::Imm(self.value_builder.store(i)),
+            ImmOrPNode::PNode(nid) => ImmOrPNode::PNode(nid),
+        }
+    }
+
+    fn store_float(&mut self, value: ImmOrPNode<f64>) -> ImmOrPNode<FloatId> {
+        match value {
+            ImmOrPNode::Imm(f) => ImmOrPNode::Imm(self.value_builder.store(f)),
+            ImmOrPNode::PNode(nid) => ImmOrPNode::PNode(nid),
+        }
+    }
+}
+
+fn value_kind<T>(value: ImmOrPNode<T>) -> ValueKind<T> {
+    match value {
+        ImmOrPNode::Imm(v) => ValueKind::Value(v),
+        ImmOrPNode::PNode(p_value) => ValueKind::PValue(PValue {
+            p_value,
+            p_value_copies: vec![],
+            phantom: PhantomData,
+        }),
+    }
+}
+
+/// Setters of elements that all nodes have.
+macro_rules! impl_elem_base_setters {
+    ($builder:ident, $($elem_base:ident).+) => {
+        impl<'a, T, U, S> $builder<'a, T, U, S>
+        where
+            T: NodeStoreBuilder,
+            U: ValueStoreBuilder,
+            S: CacheStoreBuilder,
+        {
+            /// Sets `ToolTip` of the node.
+            pub fn tooltip(mut self, tooltip: impl Into<String>) -> Self {
+                self.$($elem_base).+.tooltip = Some(tooltip.into());
+                self
+            }
+
+            /// Sets `Description` of the node.
+            pub fn description(mut self, description: impl Into<String>) -> Self {
+                self.$($elem_base).+.description = Some(description.into());
+                self
+            }
+
+            /// Sets `DisplayName` of the node.
+            pub fn display_name(mut self, display_name: impl Into<String>) -> Self {
+                self.$($elem_base).+.display_name = Some(display_name.into());
+                self
+            }
+
+            /// Sets `Visibility` of the node.
+            pub fn visibility(mut self, visibility: Visibility) -> Self {
+                self.$($elem_base).+.visibility = visibility;
+                self
+            }
+
+            /// Sets `ImposedAccessMode` of the node.
+            pub fn imposed_access_mode(mut self, access_mode: AccessMode) -> Self {
+                self.$($elem_base).+.imposed_access_mode = access_mode;
+                self
+            }
+
+            /// Sets `pIsImplemented` of the node.
+            pub fn p_is_implemented(mut self, node: impl AsRef<str>) -> Self {
+                self.$($elem_base).+.p_is_implemented = Some(self.builder.id(node));
+                self
+            }
+
+            /// Sets `pIsAvailable` of the node.
+            pub fn p_is_available(mut self, node: impl AsRef<str>) -> Self {
+                self.$($elem_base).+.p_is_available = Some(self.builder.id(node));
+                self
+            }
+
+            /// Sets `pIsLocked` of the node.
+            pub fn p_is_locked(mut self, node: impl AsRef<str>) -> Self {
+                self.$($elem_base).+.p_is_locked = Some(self.builder.id(node));
+                self
+            }
+        }
+    };
+}
+
+/// Setters of formula environment.
+macro_rules! impl_formula_env_setters {
+    ($builder:ident, $constant_ty:ty) => {
+        impl<'a, T, U, S> $builder<'a, T, U, S>
+        where
+            T: NodeStoreBuilder,
+            U: ValueStoreBuilder,
+            S: CacheStoreBuilder,
+        {
+            /// Adds `pVariable` that binds the value of `node` to `name` in formulas.
+            ///
+            /// `name` can have a suffix to refer to other values of the node, e.g. `Var.Max`.
+            pub fn p_variable(mut self, name: impl Into<String>, node: impl AsRef<str>) -> Self {
+                let value = self.builder.id(node);
+                self.formula_env.p_variables.push(NamedValue {
+                    name: name.into(),
+                    value,
+                });
+                self
+            }
+
+            /// Adds `Constant` that binds `value` to `name` in formulas.
+            pub fn constant(mut self, name: impl Into<String>, value: $constant_ty) -> Self {
+                self.formula_env.constants.push(NamedValue {
+                    name: name.into(),
+                    value,
+                });
+                self
+            }
+
+            /// Adds `Expression` that binds `expr` to `name` in formulas.
+            ///
+            /// # Panics
+            /// Panics if the expression is malformed.
+            pub fn expression(mut self, name: impl Into<String>, expr: &str) -> Self {
+                self.formula_env.expressions.push(NamedValue {
+                    name: name.into(),
+                    value: formula::parse(expr),
+                });
+                self
+            }
+        }
+    };
+}
+
+/// Setters of elements that all register nodes have.
+macro_rules! impl_register_base_setters {
+    ($builder:ident) => {
+        impl<'a, T, U, S> $builder<'a, T, U, S>
+        where
+            T: NodeStoreBuilder,
+            U: ValueStoreBuilder,
+            S: CacheStoreBuilder,
+        {
+            /// Adds `Address` of the register. The address of the register is the sum of all
+            /// addresses.
+            pub fn address(mut self, address: i64) -> Self {
+                self.register_base
+                    .address_kinds
+                    .push(AddressKind::Address(ImmOrPNode::Imm(address)));
+                self
+            }
+
+            /// Adds `pAddress` of the register. The address of the register is the sum of all
+            /// addresses.
+            pub fn p_address(mut self, node: impl AsRef<str>) -> Self {
+                let nid = self.builder.id(node);
+                self.register_base
+                    .address_kinds
+                    .push(AddressKind::Address(ImmOrPNode::PNode(nid)));
+                self
+            }
+
+            /// Sets `Length` of the register.
+            pub fn length(mut self, length: i64) -> Self {
+                self.register_base.length = ImmOrPNode::Imm(length);
+                self
+            }
+
+            /// Sets `AccessMode` of the register. Default is [`AccessMode::RO`].
+            pub fn access_mode(mut self, access_mode: AccessMode) -> Self {
+                self.register_base.access_mode = access_mode;
+                self
+            }
+
+            /// Sets `Cachable` of the register.
+            pub fn cacheable(mut self, cacheable: CachingMode) -> Self {
+                self.register_base.cacheable = cacheable;
+                self
+            }
+
+            /// Sets `PollingTime` of the register.
+            pub fn polling_time(mut self, polling_time: u64) -> Self {
+                self.register_base.polling_time = Some(polling_time);
+                self
+            }
+
+            /// Adds `pInvalidator` of the register.
+            pub fn p_invalidator(mut self, node: impl AsRef<str>) -> Self {
+                let nid = self.builder.id(node);
+                self.register_base.p_invalidators.push(nid);
+                self
+            }
+        }
+    };
+}
+
+/// A builder of `Category` node.
+pub struct CategoryBuilder<'a, T, U, S> {
+    builder: &'a mut ProgrammaticBuilder<T, U, S>,
+    attr_base: NodeAttributeBase,
+    elem_base: NodeElementBase,
+    p_features: Vec<NodeId>,
+}
+
+impl<'a, T, U, S> CategoryBuilder<'a, T, U, S>
+where
+    T: NodeStoreBuilder,
+    U: ValueStoreBuilder,
+    S: CacheStoreBuilder,
+{
+    /// Adds `pFeature` to the category.
+    pub fn feature(mut self, node: impl AsRef<str>) -> Self {
+        let nid = self.builder.id(node);
+        self.p_features.push(nid);
+        self
+    }
+
+    /// Stores the node and returns its [`NodeId`].
+    pub fn finish(self) -> NodeId {
+        let nid = self.attr_base.id;
+        let node = CategoryNode {
+            attr_base: self.attr_base,
+            elem_base: self.elem_base,
+            p_features: self.p_features,
+        };
+        self.builder
+            .store_node(nid, NodeData::Category(node.into()))
+    }
+}
+
+impl_elem_base_setters!(CategoryBuilder, elem_base);
+
+/// A builder of `Integer` node.
+pub struct IntegerBuilder<'a, T, U, S> {
+    builder: &'a mut ProgrammaticBuilder<T, U, S>,
+    attr_base: NodeAttributeBase,
+    elem_base: NodeElementBase,
+    value: ImmOrPNode<i64>,
+    min: Option<ImmOrPNode<i64>>,
+    max: Option<ImmOrPNode<i64>>,
+    inc: ImmOrPNode<i64>,
+    unit: Option<String>,
+    representation: IntegerRepresentation,
+    p_selected: Vec<NodeId>,
+}
+
+impl<'a, T, U, S> IntegerBuilder<'a, T, U, S>
+where
+    T: NodeStoreBuilder,
+    U: ValueStoreBuilder,
+    S: CacheStoreBuilder,
+{
+    /// Sets `Value` of the node.
+    pub fn value(mut self, value: i64) -> Self {
+        self.value = ImmOrPNode::Imm(value);
+        self
+    }
+
+    /// Sets `pValue` of the node.
+    pub fn p_value(mut self, node: impl AsRef<str>) -> Self {
+        self.value = ImmOrPNode::PNode(self.builder.id(node));
+        self
+    }
+
+    /// Sets `Min` of the node. Default is deduced from the representation.
+    pub fn min(mut self, min: i64) -> Self {
+        self.min = Some(ImmOrPNode::Imm(min));
+        self
+    }
+
+    /// Sets `pMin` of the node.
+    pub fn p_min(mut self, node: impl AsRef<str>) -> Self {
+        self.min = Some(ImmOrPNode::PNode(self.builder.id(node)));
+        self
+    }
+
+    /// Sets `Max` of the node. Default is deduced from the representation.
+    pub fn max(mut self, max: i64) -> Self {
+        self.max = Some(ImmOrPNode::Imm(max));
+        self
+    }
+
+    /// Sets `pMax` of the node.
+    pub fn p_max(mut self, node: impl AsRef<str>) -> Self {
+        self.max = Some(ImmOrPNode::PNode(self.builder.id(node)));
+        self
+    }
+
+    /// Sets `Inc` of the node. Default is `1`.
+    pub fn inc(mut self, inc: i64) -> Self {
+        self.inc = ImmOrPNode::Imm(inc);
+        self
+    }
+
+    /// Sets `Unit` of the node.
+    pub fn unit(mut self, unit: impl Into<String>) -> Self {
+        self.unit = Some(unit.into());
+        self
+    }
+
+    /// Sets `Representation` of the node.
+    pub fn representation(mut self, representation: IntegerRepresentation) -> Self {
+        self.representation = representation;
+        self
+    }
+
+    /// Adds `pSelected` of the node.
+    pub fn p_selected(mut self, node: impl AsRef<str>) -> Self {
+        let nid = self.builder.id(node);
+        self.p_selected.push(nid);
+        self
+    }
+
+    /// Stores the node and returns its [`NodeId`].
+    pub fn finish(self) -> NodeId {
+        let nid = self.attr_base.id;
+        let representation = self.representation;
+        let builder = self.builder;
+        let value_kind = value_kind(builder.store_integer(self.value));
+        let min = builder.store_integer(
+            self.min
+                .unwrap_or_else(|| ImmOrPNode::Imm(representation.deduce_min())),
+        );
+        let max = builder.store_integer(
+            self.max
+                .unwrap_or_else(|| ImmOrPNode::Imm(representation.deduce_max())),
+        );
+        let node = IntegerNode {
+            attr_base: self.attr_base,
+            elem_base: self.elem_base,
+            streamable: false,
+            value_kind,
+            min,
+            max,
+            inc: self.inc,
+            unit: self.unit,
+            representation: self.representation,
+            p_selected: self.p_selected,
+        };
+        builder.store_node(nid, NodeData::Integer(node.into()))
+    }
+}
+
+impl_elem_base_setters!(IntegerBuilder, elem_base);
+
+/// A builder of `Float` node.
+pub struct FloatBuilder<'a, T, U, S> {
+    builder: &'a mut ProgrammaticBuilder<T, U, S>,
+    attr_base: NodeAttributeBase,
+    elem_base: NodeElementBase,
+    value: ImmOrPNode<f64>,
+    min: ImmOrPNode<f64>,
+    max: ImmOrPNode<f64>,
+    inc: Option<ImmOrPNode<f64>>,
+    unit: Option<String>,
+    representation: FloatRepresentation,
+    display_notation: DisplayNotation,
+    display_precision: i64,
+}
+
+impl<'a, T, U, S> FloatBuilder<'a, T, U, S>
+where
+    T: NodeStoreBuilder,
+    U: ValueStoreBuilder,
+    S: CacheStoreBuilder,
+{
+    /// Sets `Value` of the node.
+    pub fn value(mut self, value: f64) -> Self {
+        self.value = ImmOrPNode::Imm(value);
+        self
+    }
+
+    /// Sets `pValue` of the node.
+    pub fn p_value(mut self, node: impl AsRef<str>) -> Self {
+        self.value = ImmOrPNode::PNode(self.builder.id(node));
+        self
+    }
+
+    /// Sets `Min` of the node. Default is [`f64::MIN`].
+    pub fn min(mut self, min: f64) -> Self {
+        self.min = ImmOrPNode::Imm(min);
+        self
+    }
+
+    /// Sets `pMin` of the node.
+    pub fn p_min(mut self, node: impl AsRef<str>) -> Self {
+        self.min = ImmOrPNode::PNode(self.builder.id(node));
+        self
+    }
+
+    /// Sets `Max` of the node. Default is [`f64::MAX`].
+    pub fn max(mut self, max: f64) -> Self {
+        self.max = ImmOrPNode::Imm(max);
+        self
+    }
+
+    /// Sets `pMax` of the node.
+    pub fn p_max(mut self, node: impl AsRef<str>) -> Self {
+        self.max = ImmOrPNode::PNode(self.builder.id(node));
+        self
+    }
+
+    /// Sets `Inc` of the node.
+    pub fn inc(mut self, inc: f64) -> Self {
+        self.inc = Some(ImmOrPNode::Imm(inc));
+        self
+    }
+
+    /// Sets `Unit` of the node.
+    pub fn unit(mut self, unit: impl Into<String>) -> Self {
+        self.unit = Some(unit.into());
+        self
+    }
+
+    /// Sets `Representation` of the node.
+    pub fn representation(mut self, representation: FloatRepresentation) -> Self {
+        self.representation = representation;
+        self
+    }
+
+    /// Sets `DisplayNotation` of the node.
+    pub fn display_notation(mut self, display_notation: DisplayNotation) -> Self {
+        self.display_notation = display_notation;
+        self
+    }
+
+    /// Sets `DisplayPrecision` of the node. Default is `6`.
+    pub fn display_precision(mut self, display_precision: i64) -> Self {
+        self.display_precision = display_precision;
+        self
+    }
+
+    /// Stores the node and returns its [`NodeId`].
+    pub fn finish(self) -> NodeId {
+        let nid = self.attr_base.id;
+        let builder = self.builder;
+        let value_kind = value_kind(builder.store_float(self.value));
+        let min = builder.store_float(self.min);
+        let max = builder.store_float(self.max);
+        let node = FloatNode {
+            attr_base: self.attr_base,
+            elem_base: self.elem_base,
+            streamable: false,
+            value_kind,
+            min,
+            max,
+            inc: self.inc,
+            unit: self.unit,
+            representation: self.representation,
+            display_notation: self.display_notation,
+            display_precision: self.display_precision,
+        };
+        builder.store_node(nid, NodeData::Float(node.into()))
+    }
+}
+
+impl_elem_base_setters!(FloatBuilder, elem_base);
+
+/// A builder of `Boolean` node.
+pub struct BooleanBuilder<'a, T, U, S> {
+    builder: &'a mut ProgrammaticBuilder<T, U, S>,
+    attr_base: NodeAttributeBase,
+    elem_base: NodeElementBase,
+    value: ImmOrPNode<bool>,
+    on_value: i64,
+    off_value: i64,
+    p_selected: Vec<NodeId>,
+}
+
+impl<'a, T, U, S> BooleanBuilder<'a, T, U, S>
+where
+    T: NodeStoreBuilder,
+    U: ValueStoreBuilder,
+    S: CacheStoreBuilder,
+{
+    /// Sets `Value` of the node.
+    pub fn value(mut self, value: bool) -> Self {
+        self.value = ImmOrPNode::Imm(value);
+        self
+    }
+
+    /// Sets `pValue` of the node.
+    pub fn p_value(mut self, node: impl AsRef<str>) -> Self {
+        self.value = ImmOrPNode::PNode(self.builder.id(node));
+        self
+    }
+
+    /// Sets `OnValue` of the node. Default is `1`.
+    pub fn on_value(mut self, on_value: i64) -> Self {
+        self.on_value = on_value;
+        self
+    }
+
+    /// Sets `OffValue` of the node. Default is `0`.
+    pub fn off_value(mut self, off_value: i64) -> Self {
+        self.off_value = off_value;
+        self
+    }
+
+    /// Adds `pSelected` of the node.
+    pub fn p_selected(mut self, node: impl AsRef<str>) -> Self {
+        let nid = self.builder.id(node);
+        self.p_selected.push(nid);
+        self
+    }
+
+    /// Stores the node and returns its [`NodeId`].
+    pub fn finish(self) -> NodeId {
+        let nid = self.attr_base.id;
+        let builder = self.builder;
+        let value = match self.value {
+            ImmOrPNode::Imm(imm) => builder.store_integer(ImmOrPNode::Imm(if imm {
+                self.on_value
+            } else {
+                self.off_value
+            })),
+            ImmOrPNode::PNode(pnode) => ImmOrPNode::PNode(pnode),
+        };
+        let node = BooleanNode {
+            attr_base: self.attr_base,
+            elem_base: self.elem_base,
+            streamable: false,
+            value,
+            on_value: self.on_value,
+            off_value: self.off_value,
+            p_selected: self.p_selected,
+        };
+        builder.store_node(nid, NodeData::Boolean(node.into()))
+    }
+}
+
+impl_elem_base_setters!(BooleanBuilder, elem_base);
+
+/// A builder of `Command` node.
+pub struct CommandBuilder<'a, T, U, S> {
+    builder: &'a mut ProgrammaticBuilder<T, U, S>,
+    attr_base: NodeAttributeBase,
+    elem_base: NodeElementBase,
+    p_value: NodeId,
+    command_value: i64,
+    polling_time: Option<u64>,
+}
+
+impl<'a, T, U, S> CommandBuilder<'a, T, U, S>
+where
+    T: NodeStoreBuilder,
+    U: ValueStoreBuilder,
+    S: CacheStoreBuilder,
+{
+    /// Sets `PollingTime` of the node.
+    pub fn polling_time(mut self, polling_time: u64) -> Self {
+        self.polling_time = Some(polling_time);
+        self
+    }
+
+    /// Stores the node and returns its [`NodeId`].
+    pub fn finish(self) -> NodeId {
+        let nid = self.attr_base.id;
+        let builder = self.builder;
+        let command_value = builder.store_integer(ImmOrPNode::Imm(self.command_value));
+        let node = CommandNode {
+            attr_base: self.attr_base,
+            elem_base: self.elem_base,
+            value: ImmOrPNode::PNode(self.p_value),
+            command_value,
+            polling_time: self.polling_time,
+        };
+        builder.store_node(nid, NodeData::Command(node.into()))
+    }
+}
+
+impl_elem_base_setters!(CommandBuilder, elem_base);
+
+/// A builder of `Enumeration` node.
+pub struct EnumerationBuilder<'a, T, U, S> {
+    builder: &'a mut ProgrammaticBuilder<T, U, S>,
+    attr_base: NodeAttributeBase,
+    elem_base: NodeElementBase,
+    entries: Vec<NodeId>,
+    value: ImmOrPNode<i64>,
+    p_selected: Vec<NodeId>,
+    polling_time: Option<u64>,
+}
+
+impl<'a, T, U, S> EnumerationBuilder<'a, T, U, S>
+where
+    T: NodeStoreBuilder,
+    U: ValueStoreBuilder,
+    S: CacheStoreBuilder,
+{
+    /// Adds `EnumEntry` of the node.
+    pub fn entry(mut self, symbolic: impl Into<String>, value: i64) -> Self {
+        let symbolic = symbolic.into();
+        let name = format!("${}_{}", symbolic, self.builder.node_builder.fresh_id());
+        let (attr_base, elem_base) = self.builder.node_base(name);
+        let nid = attr_base.id;
+        let entry = EnumEntryNode {
+            attr_base,
+            elem_base,
+            value,
+            numeric_value: None,
+            symbolic,
+            is_self_clearing: false,
+        };
+        self.builder
+            .store_node(nid, NodeData::EnumEntry(entry.into()));
+        self.entries.push(nid);
+        self
+    }
+
+    /// Sets `Value` of the node.
+    pub fn value(mut self, value: i64) -> Self {
+        self.value = ImmOrPNode::Imm(value);
+        self
+    }
+
+    /// Sets `pValue` of the node.
+    pub fn p_value(mut self, node: impl AsRef<str>) -> Self {
+        self.value = ImmOrPNode::PNode(self.builder.id(node));
+        self
+    }
+
+    /// Adds `pSelected` of the node.
+    pub fn p_selected(mut self, node: impl AsRef<str>) -> Self {
+        let nid = self.builder.id(node);
+        self.p_selected.push(nid);
+        self
+    }
+
+    /// Sets `PollingTime` of the node.
+    pub fn polling_time(mut self, polling_time: u64) -> Self {
+        self.polling_time = Some(polling_time);
+        self
+    }
+
+    /// Stores the node and returns its [`NodeId`].
+    pub fn finish(self) -> NodeId {
+        let nid = self.attr_base.id;
+        let builder = self.builder;
+        let value = builder.store_integer(self.value);
+        let node = EnumerationNode {
+            attr_base: self.attr_base,
+            elem_base: self.elem_base,
+            streamable: false,
+            entries: self.entries,
+            value,
+            p_selected: self.p_selected,
+            polling_time: self.polling_time,
+        };
+        builder.store_node(nid, NodeData::Enumeration(node.into()))
+    }
+}
+
+impl_elem_base_setters!(EnumerationBuilder, elem_base);
+
+/// Variables, constants and expressions which formulas of a node can refer to.
+struct FormulaEnv<C> {
+    p_variables: Vec<NamedValue<NodeId>>,
+    constants: Vec<NamedValue<C>>,
+    expressions: Vec<NamedValue<Expr>>,
+}
+
+impl<C> Default for FormulaEnv<C> {
+    fn default() -> Self {
+        Self {
+            p_variables: vec![],
+            constants: vec![],
+            expressions: vec![],
+        }
+    }
+}
+
+impl<C: Copy + Into<Expr>> FormulaEnv<C> {
+    fn formula(&self, expr: Expr, imm_names: &[&str]) -> Formula {
+        let mut formula = Formula {
+            expr,
+            compiled: None,
+        };
+        utils::compile_formula(
+            &mut formula,
+            &self.p_variables,
+            &self.constants,
+            &self.expressions,
+            imm_names,
+        );
+        formula
+    }
+}
+
+/// A builder of `Converter` node.
+pub struct ConverterBuilder<'a, T, U, S> {
+    builder: &'a mut ProgrammaticBuilder<T, U, S>,
+    attr_base: NodeAttributeBase,
+    elem_base: NodeElementBase,
+    formula_env: FormulaEnv<f64>,
+    formula_to: Expr,
+    formula_from: Expr,
+    p_value: NodeId,
+    unit: Option<String>,
+    representation: FloatRepresentation,
+    display_notation: DisplayNotation,
+    display_precision: i64,
+    slope: Slope,
+}
+
+impl<'a, T, U, S> ConverterBuilder<'a, T, U, S>
+where
+    T: NodeStoreBuilder,
+    U: ValueStoreBuilder,
+    S: CacheStoreBuilder,
+{
+    /// Sets `FormulaTo` of the node, which converts `FROM` to the value of `pValue` node.
+    /// Default is `FROM`.
+    ///
+    /// # Panics
+    /// Panics if the formula is malformed.
+    pub fn formula_to(mut self, formula: &str) -> Self {
+        self.formula_to = formula::parse(formula);
+        self
+    }
+
+    /// Sets `FormulaFrom` of the node, which converts `TO`, the value of `pValue` node, to the
+    /// value of the node. Default is `TO`.
+    ///
+    /// # Panics
+    /// Panics if the formula is malformed.
+    pub fn formula_from(mut self, formula: &str) -> Self {
+        self.formula_from = formula::parse(formula);
+        self
+    }
+
+    /// Sets `Unit` of the node.
+    pub fn unit(mut self, unit: impl Into<String>) -> Self {
+        self.unit = Some(unit.into());
+        self
+    }
+
+    /// Sets `Representation` of the node.
+    pub fn representation(mut self, representation: FloatRepresentation) -> Self {
+        self.representation = representation;
+        self
+    }
+
+    /// Sets `DisplayNotation` of the node.
+    pub fn display_notation(mut self, display_notation: DisplayNotation) -> Self {
+        self.display_notation = display_notation;
+        self
+    }
+
+    /// Sets `DisplayPrecision` of the node. Default is `6`.
+    pub fn display_precision(mut self, display_precision: i64) -> Self {
+        self.display_precision = display_precision;
+        self
+    }
+
+    /// Sets `Slope` of the node.
+    pub fn slope(mut self, slope: Slope) -> Self {
+        self.slope = slope;
+        self
+    }
+
+    /// Stores the node and returns its [`NodeId`].
+    pub fn finish(self) -> NodeId {
+        let nid = self.attr_base.id;
+        let formula_to = self.formula_env.formula(self.formula_to, &["FROM"]);
+        let formula_from = self.formula_env.formula(self.formula_from, &["TO"]);
+        let node = ConverterNode {
+            attr_base: self.attr_base,
+            elem_base: self.elem_base,
+            streamable: false,
+            p_variables: self.formula_env.p_variables,
+            constants: self.formula_env.constants,
+            expressions: self.formula_env.expressions,
+            formula_to,
+            formula_from,
+            p_value: self.p_value,
+            unit: self.unit,
+            representation: self.representation,
+            display_notation: self.display_notation,
+            display_precision: self.display_precision,
+            slope: self.slope,
+            is_linear: false,
+        };
+        self.builder
+            .store_node(nid, NodeData::Converter(node.into()))
+    }
+}
+
+impl_elem_base_setters!(ConverterBuilder, elem_base);
+impl_formula_env_setters!(ConverterBuilder, f64);
+
+/// A builder of `IntConverter` node.
+pub struct IntConverterBuilder<'a, T, U, S> {
+    builder: &'a mut ProgrammaticBuilder<T, U, S>,
+    attr_base: NodeAttributeBase,
+    elem_base: NodeElementBase,
+    formula_env: FormulaEnv<i64>,
+    formula_to: Expr,
+    formula_from: Expr,
+    p_value: NodeId,
+    unit: Option<String>,
+    representation: IntegerRepresentation,
+    slope: Slope,
+}
+
+impl<'a, T, U, S> IntConverterBuilder<'a, T, U, S>
+where
+    T: NodeStoreBuilder,
+    U: ValueStoreBuilder,
+    S: CacheStoreBuilder,
+{
+    /// Sets `FormulaTo` of the node, which converts `FROM` to the value of `pValue` node.
+    /// Default is `FROM`.
+    ///
+    /// # Panics
+    /// Panics if the formula is malformed.
+    pub fn formula_to(mut self, formula: &str) -> Self {
+        self.formula_to = formula::parse(formula);
+        self
+    }
+
+    /// Sets `FormulaFrom` of the node, which converts `TO`, the value of `pValue` node, to the
+    /// value of the node. Default is `TO`.
+    ///
+    /// # Panics
+    /// Panics if the formula is malformed.
+    pub fn formula_from(mut self, formula: &str) -> Self {
+        self.formula_from = formula::parse(formula);
+        self
+    }
+
+    /// Sets `Unit` of the node.
+    pub fn unit(mut self, unit: impl Into<String>) -> Self {
+        self.unit = Some(unit.into());
+        self
+    }
+
+    /// Sets `Representation` of the node.
+    pub fn representation(mut self, representation: IntegerRepresentation) -> Self {
+        self.representation = representation;
+        self
+    }
+
+    /// Sets `Slope` of the node.
+    pub fn slope(mut self, slope: Slope) -> Self {
+        self.slope = slope;
+        self
+    }
+
+    /// Stores the node and returns its [`NodeId`].
+    pub fn finish(self) -> NodeId {
+        let nid = self.attr_base.id;
+        let formula_to = self.formula_env.formula(self.formula_to, &["FROM"]);
+        let formula_from = self.formula_env.formula(self.formula_from, &["TO"]);
+        let node = IntConverterNode {
+            attr_base: self.attr_base,
+            elem_base: self.elem_base,
+            streamable: false,
+            p_variables: self.formula_env.p_variables,
+            constants: self.formula_env.constants,
+            expressions: self.formula_env.expressions,
+            formula_to,
+            formula_from,
+            p_value: self.p_value,
+            unit: self.unit,
+            representation: self.representation,
+            slope: self.slope,
+        };
+        self.builder
+            .store_node(nid, NodeData::IntConverter(node.into()))
+    }
+}
+
+impl_elem_base_setters!(IntConverterBuilder, elem_base);
+impl_formula_env_setters!(IntConverterBuilder, i64);
+
+/// A builder of `SwissKnife` node.
+pub struct SwissKnifeBuilder<'a, T, U, S> {
+    builder: &'a mut ProgrammaticBuilder<T, U, S>,
+    attr_base: NodeAttributeBase,
+    elem_base: NodeElementBase,
+    formula_env: FormulaEnv<f64>,
+    formula: Expr,
+    unit: Option<String>,
+    representation: FloatRepresentation,
+}
+
+impl<'a, T, U, S> SwissKnifeBuilder<'a, T, U, S>
+where
+    T: NodeStoreBuilder,
+    U: ValueStoreBuilder,
+    S: CacheStoreBuilder,
+{
+    /// Sets `Unit` of the node.
+    pub fn unit(mut self, unit: impl Into<String>) -> Self {
+        self.unit = Some(unit.into());
+        self
+    }
+
+    /// Sets `Representation` of the node.
+    pub fn representation(mut self, representation: FloatRepresentation) -> Self {
+        self.representation = representation;
+        self
+    }
+
+    /// Stores the node and returns its [`NodeId`].
+    pub fn finish(self) -> NodeId {
+        let nid = self.attr_base.id;
+        let formula = self.formula_env.formula(self.formula, &[]);
+        let node = SwissKnifeNode {
+            attr_base: self.attr_base,
+            elem_base: self.elem_base,
+            streamable: false,
+            p_variables: self.formula_env.p_variables,
+            constants: self.formula_env.constants,
+            expressions: self.formula_env.expressions,
+            formula,
+            unit: self.unit,
+            representation: self.representation,
+            display_notation: DisplayNotation::default(),
+            display_precision: 6,
+        };
+        self.builder
+            .store_node(nid, NodeData::SwissKnife(node.into()))
+    }
+}
+
+impl_elem_base_setters!(SwissKnifeBuilder, elem_base);
+impl_formula_env_setters!(SwissKnifeBuilder, f64);
+
+/// A builder of `IntSwissKnife` node.
+pub struct IntSwissKnifeBuilder<'a, T, U, S> {
+    builder: &'a mut ProgrammaticBuilder<T, U, S>,
+    attr_base: NodeAttributeBase,
+    elem_base: NodeElementBase,
+    formula_env: FormulaEnv<i64>,
+    formula: Expr,
+    unit: Option<String>,
+    representation: IntegerRepresentation,
+}
+
+impl<'a, T, U, S> IntSwissKnifeBuilder<'a, T, U, S>
+where
+    T: NodeStoreBuilder,
+    U: ValueStoreBuilder,
+    S: CacheStoreBuilder,
+{
+    /// Sets `Unit` of the node.
+    pub fn unit(mut self, unit: impl Into<String>) -> Self {
+        self.unit = Some(unit.into());
+        self
+    }
+
+    /// Sets `Representation` of the node.
+    pub fn representation(mut self, representation: IntegerRepresentation) -> Self {
+        self.representation = representation;
+        self
+    }
+
+    /// Stores the node and returns its [`NodeId`].
+    pub fn finish(self) -> NodeId {
+        let nid = self.attr_base.id;
+        let formula = self.formula_env.formula(self.formula, &[]);
+        let node = IntSwissKnifeNode {
+            attr_base: self.attr_base,
+            elem_base: self.elem_base,
+            streamable: false,
+            p_variables: self.formula_env.p_variables,
+            constants: self.formula_env.constants,
+            expressions: self.formula_env.expressions,
+            formula,
+            unit: self.unit,
+            representation: self.representation,
+        };
+        self.builder
+            .store_node(nid, NodeData::IntSwissKnife(node.into()))
+    }
+}
+
+impl_elem_base_setters!(IntSwissKnifeBuilder, elem_base);
+impl_formula_env_setters!(IntSwissKnifeBuilder, i64);
+
+/// A builder of `IntReg` node.
+pub struct IntRegBuilder<'a, T, U, S> {
+    builder: &'a mut ProgrammaticBuilder<T, U, S>,
+    attr_base: NodeAttributeBase,
+    register_base: RegisterBase,
+    sign: Sign,
+    endianness: Endianness,
+    unit: Option<String>,
+    representation: IntegerRepresentation,
+    p_selected: Vec<NodeId>,
+}
+
+impl<'a, T, U, S> IntRegBuilder<'a, T, U, S>
+where
+    T: NodeStoreBuilder,
+    U: ValueStoreBuilder,
+    S: CacheStoreBuilder,
+{
+    /// Sets `Sign` of the register.
+    pub fn sign(mut self, sign: Sign) -> Self {
+        self.sign = sign;
+        self
+    }
+
+    /// Sets `Endianess` of the register.
+    pub fn endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    /// Sets `Unit` of the node.
+    pub fn unit(mut self, unit: impl Into<String>) -> Self {
+        self.unit = Some(unit.into());
+        self
+    }
+
+    /// Sets `Representation` of the node.
+    pub fn representation(mut self, representation: IntegerRepresentation) -> Self {
+        self.representation = representation;
+        self
+    }
+
+    /// Adds `pSelected` of the node.
+    pub fn p_selected(mut self, node: impl AsRef<str>) -> Self {
+        let nid = self.builder.id(node);
+        self.p_selected.push(nid);
+        self
+    }
+
+    /// Stores the node and returns its [`NodeId`].
+    pub fn finish(self) -> NodeId {
+        let nid = self.attr_base.id;
+        let builder = self.builder;
+        for invalidator in &self.register_base.p_invalidators {
+            builder.cache_builder.store_invalidator(*invalidator, nid);
+        }
+        let node = IntRegNode {
+            attr_base: self.attr_base,
+            register_base: self.register_base,
+            sign: self.sign,
+            endianness: self.endianness,
+            unit: self.unit,
+            representation: self.representation,
+            p_selected: self.p_selected,
+        };
+        builder.store_node(nid, NodeData::IntReg(node.into()))
+    }
+}
+
+impl_elem_base_setters!(IntRegBuilder, register_base.elem_base);
+impl_register_base_setters!(IntRegBuilder);
+
+/// A builder of `FloatReg` node.
+pub struct FloatRegBuilder<'a, T, U, S> {
+    builder: &'a mut ProgrammaticBuilder<T, U, S>,
+    attr_base: NodeAttributeBase,
+    register_base: RegisterBase,
+    endianness: Endianness,
+    unit: Option<String>,
+    representation: FloatRepresentation,
+    display_notation: DisplayNotation,
+    display_precision: i64,
+}
+
+impl<'a, T, U, S> FloatRegBuilder<'a, T, U, S>
+where
+    T: NodeStoreBuilder,
+    U: ValueStoreBuilder,
+    S: CacheStoreBuilder,
+{
+    /// Sets `Endianess` of the register.
+    pub fn endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    /// Sets `Unit` of the node.
+    pub fn unit(mut self, unit: impl Into<String>) -> Self {
+        self.unit = Some(unit.into());
+        self
+    }
+
+    /// Sets `Representation` of the node.
+    pub fn representation(mut self, representation: FloatRepresentation) -> Self {
+        self.representation = representation;
+        self
+    }
+
+    /// Sets `DisplayNotation` of the node.
+    pub fn display_notation(mut self, display_notation: DisplayNotation) -> Self {
+        self.display_notation = display_notation;
+        self
+    }
+
+    /// Sets `DisplayPrecision` of the node. Default is `6`.
+    pub fn display_precision(mut self, display_precision: i64) -> Self {
+        self.display_precision = display_precision;
+        self
+    }
+
+    /// Stores the node and returns its [`NodeId`].
+    pub fn finish(self) -> NodeId {
+        let nid = self.attr_base.id;
+        let builder = self.builder;
+        for invalidator in &self.register_base.p_invalidators {
+            builder.cache_builder.store_invalidator(*invalidator, nid);
+        }
+        let node = FloatRegNode {
+            attr_base: self.attr_base,
+            register_base: self.register_base,
+            endianness: self.endianness,
+            unit: self.unit,
+            representation: self.representation,
+            display_notation: self.display_notation,
+            display_precision: self.display_precision,
+        };
+        builder.store_node(nid, NodeData::FloatReg(node.into()))
+    }
+}
+
+impl_elem_base_setters!(FloatRegBuilder, register_base.elem_base);
+impl_register_base_setters!(FloatRegBuilder);
+
+/// A builder of `Port` node.
+pub struct PortBuilder<'a, T, U, S> {
+    builder: &'a mut ProgrammaticBuilder<T, U, S>,
+    attr_base: NodeAttributeBase,
+    elem_base: NodeElementBase,
+    chunk_id: Option<ImmOrPNode<u64>>,
+    swap_endianness: bool,
+}
+
+impl<'a, T, U, S> PortBuilder<'a, T, U, S>
+where
+    T: NodeStoreBuilder,
+    U: ValueStoreBuilder,
+    S: CacheStoreBuilder,
+{
+    /// Sets `ChunkID` of the port.
+    pub fn chunk_id(mut self, chunk_id: u64) -> Self {
+        self.chunk_id = Some(ImmOrPNode::Imm(chunk_id));
+        self
+    }
+
+    /// Sets `SwapEndianess` of the port.
+    pub fn swap_endianness(mut self, swap_endianness: bool) -> Self {
+        self.swap_endianness = swap_endianness;
+        self
+    }
+
+    /// Stores the node and returns its [`NodeId`].
+    pub fn finish(self) -> NodeId {
+        let nid = self.attr_base.id;
+        let node = PortNode {
+            attr_base: self.attr_base,
+            elem_base: self.elem_base,
+            chunk_id: self.chunk_id,
+            swap_endianness: self.swap_endianness,
+            cache_chunk_data: false,
+        };
+        self.builder.store_node(nid, NodeData::Port(node.into()))
+    }
+}
+
+impl_elem_base_setters!(PortBuilder, elem_base);
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        builder::GenApiBuilder,
+        prelude::*,
+        store::{DefaultNodeStore, NodeStore},
+        utils::tests::Memory,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_programmatic_builder() {
+        let mut builder =
+            GenApiBuilder::<DefaultNodeStore>::default().programmatic("Model", "Vendor");
+        builder.port("Device").finish();
+        builder
+            .int_reg("WidthReg", "Device")
+            .address(0x10)
+            .length(4)
+            .access_mode(AccessMode::RW)
+            .finish();
+        builder
+            .integer("Width")
+            .p_value("WidthReg")
+            .min(16)
+            .max(1920)
+            .inc(16)
+            .display_name("Image Width")
+            .finish();
+        builder
+            .converter("WidthInKilo", "Width")
+            .constant("K", 1000.0)
+            .formula_to("FROM * K")
+            .formula_from("TO / K")
+            .finish();
+        builder
+            .enumeration("PixelFormat")
+            .entry("Mono8", 1)
+            .entry("Mono16", 2)
+            .value(2)
+            .finish();
+        builder
+            .category("Root")
+            .feature("Width")
+            .feature("PixelFormat")
+            .finish();
+
+        let (reg_desc, store, mut cx) = builder.build();
+        let mut device = Memory(vec![0; 0x20]);
+        assert_eq!(reg_desc.model_name(), "Model");
+        assert_eq!(reg_desc.vendor_name(), "Vendor");
+
+        let width = store.id_by_name("Width").unwrap();
+        let width = width.expect_iinteger_kind(&store).unwrap();
+        width.set_value(640, &mut device, &store, &mut cx).unwrap();
+        assert_eq!(width.value(&mut device, &store, &mut cx).unwrap(), 640);
+        assert_eq!(width.min(&mut device, &store, &mut cx).unwrap(), 16);
+        assert_eq!(width.max(&mut device, &store, &mut cx).unwrap(), 1920);
+        assert_eq!(&device.0[0x10..0x14], &640_u32.to_le_bytes());
+
+        let kilo = store.id_by_name("WidthInKilo").unwrap();
+        let kilo = kilo.expect_ifloat_kind(&store).unwrap();
+        assert!((kilo.value(&mut device, &store, &mut cx).unwrap() - 0.64).abs() < f64::EPSILON);
+        kilo.set_value(1.28, &mut device, &store, &mut cx).unwrap();
+        assert_eq!(width.value(&mut device, &store, &mut cx).unwrap(), 1280);
+
+        let pixel_format = store.id_by_name("PixelFormat").unwrap();
+        let pixel_format = pixel_format.expect_ienumeration_kind(&store).unwrap();
+        let current = pixel_format
+            .current_entry(&mut device, &store, &mut cx)
+            .unwrap();
+        assert_eq!(
+            current.expect_enum_entry(&store).unwrap().symbolic(),
+            "Mono16"
+        );
+
+        let root = store.id_by_name("Root").unwrap();
+        let root = root.expect_icategory_kind(&store).unwrap();
+        assert_eq!(root.nodes(&store).len(), 2);
+    }
+
+    #[test]
+    fn test_chunk_port() {
+        let mut builder =
+            GenApiBuilder::<DefaultNodeStore>::default().programmatic("Model", "Vendor");
+        builder.port("ChunkPort").chunk_id(0x1234).finish();
+        builder
+            .int_reg("ChunkWidth", "ChunkPort")
+            .address(4)
+            .length(4)
+            .finish();
+
+        let (_, store, mut cx) = builder.build();
+        let mut device = Memory(vec![]);
+        let width = store.id_by_name("ChunkWidth").unwrap();
+        let width = width.expect_iinteger_kind(&store).unwrap();
+        assert!(matches!(
+            width.value(&mut device, &store, &mut cx),
+            Err(crate::GenApiError::ChunkDataMissing)
+        ));
+
+        cx.set_chunk_data(0x1234, vec![0, 0, 0, 0, 32, 0, 0, 0]);
+        assert_eq!(width.value(&mut device, &store, &mut cx).unwrap(), 32);
+        // Values read from chunk data are not cached.
+        cx.set_chunk_data(0x1234, vec![0, 0, 0, 0, 64, 0, 0, 0]);
+        assert_eq!(width.value(&mut device, &store, &mut cx).unwrap(), 64);
+
+        cx.set_chunk_data(0x1234, vec![0; 6]);
+        assert!(width.value(&mut device, &store, &mut cx).is_err());
+    }
+}
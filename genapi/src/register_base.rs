@@ -4,7 +4,7 @@
 
 use super::{
     elem_type::{AccessMode, AddressKind, CachingMode, ImmOrPNode},
-    interface::IPort,
+    interface::{IPort, IPortKind},
     ivalue::IValue,
     node_base::NodeElementBase,
     store::{CacheStore, NodeId, NodeStore, ValueStore},
@@ -12,6 +12,7 @@ use super::{
 };
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RegisterBase {
     pub(crate) elem_base: NodeElementBase,
 
@@ -77,8 +78,11 @@ impl RegisterBase {
         let length = self.length(device, store, cx)?;
         let address = self.address(device, store, cx)?;
         if let Some(cache) = cx.get_cache(nid, length, address) {
-            f(cache)
+            let res = f(cache);
+            cx.record_cache_access(true);
+            res
         } else {
+            cx.record_cache_access(false);
             let mut buf = vec![0; length as usize];
             self.read_and_cache(nid, address, length, &mut buf, device, store, cx)?;
             f(&buf)
@@ -101,10 +105,10 @@ impl RegisterBase {
                 "given buffer length doesn't same as the register length".into(),
             ));
         }
-        self.p_port
-            .expect_iport_kind(store)?
-            .read(address, buf, device, store, cx)?;
-        if self.cacheable != CachingMode::NoCache {
+        let IPortKind::Port(port) = self.p_port.expect_iport_kind(store)?;
+        port.read(address, buf, device, store, cx)?;
+        // Chunk data change for every payload, so they must not be cached.
+        if self.cacheable != CachingMode::NoCache && port.chunk_id().is_none() {
             cx.cache_data(nid, address, length, buf);
         }
 
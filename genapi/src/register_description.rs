@@ -2,9 +2,10 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use super::elem_type::StandardNameSpace;
+use super::elem_type::{Extensions, SchemaVersion, StandardNameSpace};
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RegisterDescription {
     pub(crate) model_name: String,
     pub(crate) vendor_name: String,
@@ -18,6 +19,9 @@ pub struct RegisterDescription {
     pub(crate) subminor_version: u64,
     pub(crate) product_guid: String,
     pub(crate) version_guid: String,
+    pub(crate) extensions: Extensions,
+    /// Prefixed namespace declarations of the root element, which extensions may refer to.
+    pub(crate) name_spaces: Vec<(String, String)>,
 }
 
 impl RegisterDescription {
@@ -41,6 +45,15 @@ impl RegisterDescription {
         self.standard_name_space
     }
 
+    #[must_use]
+    pub fn schema_version(&self) -> SchemaVersion {
+        SchemaVersion::new(
+            self.schema_major_version,
+            self.schema_minor_version,
+            self.schema_subminor_version,
+        )
+    }
+
     #[must_use]
     pub fn schema_major_version(&self) -> u64 {
         self.schema_major_version
@@ -80,4 +93,12 @@ impl RegisterDescription {
     pub fn version_guid(&self) -> &str {
         &self.version_guid
     }
+
+    /// Returns non-standard attributes and top-level elements of the XML.
+    ///
+    /// Always empty unless the XML is parsed in tolerant mode.
+    #[must_use]
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
 }
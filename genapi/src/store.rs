@@ -21,9 +21,11 @@ use super::{
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NodeId(u32);
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NodeData {
     Node(Box<Node>),
     Category(Box<CategoryNode>),
@@ -70,6 +72,30 @@ pub trait NodeStore {
     fn visit_nodes<F>(&self, f: F)
     where
         F: FnMut(&NodeData);
+
+    /// Returns the node which provides the same feature as `nid` through another interface, i.e.
+    /// the node referred by `pAlias` of `nid`.
+    fn alias(&self, nid: NodeId) -> Option<NodeId> {
+        self.node_opt(nid)?.node_base().p_alias()
+    }
+
+    /// Returns the node referred by `pCastAlias` of `nid`, which provides the same feature
+    /// through another interface with a type cast.
+    fn cast_alias(&self, nid: NodeId) -> Option<NodeId> {
+        self.node_opt(nid)?.node_base().p_cast_alias()
+    }
+
+    /// Returns nodes which refer to `nid` by `pAlias` or `pCastAlias`.
+    fn aliased_by(&self, nid: NodeId) -> Vec<NodeId> {
+        let mut nodes = vec![];
+        self.visit_nodes(|data| {
+            let node_base = data.node_base();
+            if node_base.p_alias() == Some(nid) || node_base.p_cast_alias() == Some(nid) {
+                nodes.push(node_base.id());
+            }
+        });
+        nodes
+    }
 }
 
 #[auto_impl(&mut, Box)]
@@ -281,6 +307,7 @@ impl NodeData {
             Self::Boolean(node) => node.node_base(),
             Self::Command(node) => node.node_base(),
             Self::Enumeration(node) => node.node_base(),
+            Self::EnumEntry(node) => node.node_base(),
             Self::Float(node) => node.node_base(),
             Self::FloatReg(node) => node.node_base(),
             Self::String(node) => node.node_base(),
@@ -377,7 +404,64 @@ impl Default for DefaultNodeStore {
     }
 }
 
+/// The interner is serialized as names ordered by [`NodeId`], interning them in the same order on
+/// deserialization reproduces the same ids.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct SerializedNodeStore<'a> {
+    names: Vec<&'a str>,
+    store: &'a [Option<NodeData>],
+    fresh_id: u32,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct DeserializedNodeStore {
+    names: Vec<String>,
+    store: Vec<Option<NodeData>>,
+    fresh_id: u32,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for DefaultNodeStore {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let ser = SerializedNodeStore {
+            names: self.interner.into_iter().map(|(_, name)| name).collect(),
+            store: &self.store,
+            fresh_id: self.fresh_id,
+        };
+        serde::Serialize::serialize(&ser, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for DefaultNodeStore {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let de: DeserializedNodeStore = serde::Deserialize::deserialize(deserializer)?;
+        let mut interner = StringInterner::<DefaultBackend<NodeId>>::new();
+        for (i, name) in de.names.iter().enumerate() {
+            if interner.get_or_intern(name).to_usize() != i {
+                return Err(D::Error::custom(format!("duplicated node name: {}", name)));
+            }
+        }
+        Ok(Self {
+            interner,
+            store: de.store,
+            fresh_id: de.fresh_id,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ValueId(u32);
 
 impl ValueId {
@@ -390,6 +474,7 @@ impl ValueId {
 macro_rules! declare_value_id {
     ($name:ident) => {
         #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
         pub struct $name(u32);
 
         impl From<$name> for ValueId {
@@ -410,6 +495,7 @@ declare_value_id!(FloatId);
 declare_value_id!(StringId);
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ValueData {
     Integer(i64),
     Float(f64),
@@ -431,7 +517,8 @@ impl_value_data_conversion!(f64, Self::Float);
 impl_value_data_conversion!(String, Self::Str);
 impl_value_data_conversion!(bool, Self::Boolean);
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DefaultValueStore(Vec<ValueData>);
 
 impl DefaultValueStore {
@@ -480,8 +567,11 @@ impl ValueStore for DefaultValueStore {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DefaultCacheStore {
+    /// Cached values are valid only while the device state is kept, so they are never persisted.
+    #[cfg_attr(feature = "serde", serde(skip))]
     store: HashMap<NodeId, HashMap<(i64, i64), Vec<u8>>>,
     invalidators: HashMap<NodeId, Vec<NodeId>>,
 }
@@ -549,6 +639,7 @@ impl CacheStore for DefaultCacheStore {
 }
 
 #[derive(Default, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CacheSink {
     _priv: (),
 }
@@ -584,3 +675,104 @@ impl CacheStore for CacheSink {
 
     fn clear(&mut self) {}
 }
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use crate::{
+        builder::GenApiBuilder,
+        utils::tests::{register_description, Memory},
+        writer,
+    };
+
+    use super::*;
+
+    fn xml() -> String {
+        register_description(
+            r#"
+                <Category Name="Root" NameSpace="Standard">
+                    <pFeature>Width</pFeature>
+                    <pFeature>PixelFormat</pFeature>
+                </Category>
+
+                <Integer Name="Width" NameSpace="Standard">
+                    <pValue>WidthReg</pValue>
+                    <Min>16</Min>
+                    <Max>1280</Max>
+                </Integer>
+
+                <IntReg Name="WidthReg">
+                    <Address>0x1000</Address>
+                    <Length>4</Length>
+                    <AccessMode>RW</AccessMode>
+                    <pPort>Device</pPort>
+                    <Endianess>BigEndian</Endianess>
+                </IntReg>
+
+                <Enumeration Name="PixelFormat">
+                    <EnumEntry Name="Mono8">
+                        <Value>1</Value>
+                    </EnumEntry>
+                    <Value>1</Value>
+                </Enumeration>
+
+                <IntSwissKnife Name="DoubleWidth">
+                    <pVariable Name="W">Width</pVariable>
+                    <Formula>W * 2</Formula>
+                </IntSwissKnife>
+
+                <Port Name="Device"/>
+            "#,
+        )
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let (reg_desc, node_store, value_ctxt) = GenApiBuilder::<DefaultNodeStore>::default()
+            .build(&xml())
+            .unwrap();
+
+        let reg_desc_json = serde_json::to_string(&reg_desc).unwrap();
+        let node_store_json = serde_json::to_string(&node_store).unwrap();
+        let value_ctxt_json = serde_json::to_string(&value_ctxt).unwrap();
+
+        let reg_desc2: crate::RegisterDescription = serde_json::from_str(&reg_desc_json).unwrap();
+        let node_store2: DefaultNodeStore = serde_json::from_str(&node_store_json).unwrap();
+        let value_ctxt2: crate::ValueCtxt<DefaultValueStore, DefaultCacheStore> =
+            serde_json::from_str(&value_ctxt_json).unwrap();
+
+        for name in &[
+            "Root",
+            "Width",
+            "WidthReg",
+            "PixelFormat",
+            "DoubleWidth",
+            "Device",
+        ] {
+            assert_eq!(node_store.id_by_name(name), node_store2.id_by_name(name));
+        }
+        assert_eq!(
+            writer::write(&reg_desc, &node_store, &value_ctxt.value_store),
+            writer::write(&reg_desc2, &node_store2, &value_ctxt2.value_store)
+        );
+    }
+
+    #[test]
+    fn test_serde_dump_node() {
+        let (_, node_store, mut value_ctxt) = GenApiBuilder::<DefaultNodeStore>::default()
+            .build(&xml())
+            .unwrap();
+
+        // Each node is dumped as an object tagged with its kind.
+        let width = node_store.node(node_store.id_by_name("Width").unwrap());
+        let json = serde_json::to_value(width).unwrap();
+        assert!(json["Integer"]["elem_base"].is_object());
+        let width2: NodeData = serde_json::from_value(json).unwrap();
+        assert!(matches!(width2, NodeData::Integer(..)));
+
+        let tree = crate::CategoryTree::new(&mut Memory(vec![]), &node_store, &mut value_ctxt)
+            .unwrap()
+            .unwrap();
+        let json = serde_json::to_value(&tree).unwrap();
+        assert_eq!(json["root"]["name"], "Root");
+    }
+}
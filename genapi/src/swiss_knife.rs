@@ -12,6 +12,7 @@ use super::{
 };
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SwissKnifeNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -89,10 +90,9 @@ impl IFloat for SwissKnifeNode {
         store: &impl NodeStore,
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<f64> {
-        let var_env =
+        let eval_result =
             utils::FormulaEnvCollector::new(&self.p_variables, &self.constants, &self.expressions)
-                .collect(device, store, cx)?;
-        let eval_result = self.formula.eval(&var_env)?;
+                .eval(&self.formula, device, store, cx)?;
         Ok(eval_result.as_float())
     }
 
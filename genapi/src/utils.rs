@@ -6,9 +6,8 @@ use std::{borrow::Cow, collections::HashMap, convert::TryInto};
 
 use super::{
     elem_type::{Endianness, NamedValue, Sign},
-    formula::EvaluationResult,
-    formula::Expr,
-    interface::{IBoolean, IEnumeration, IFloat, IInteger},
+    formula::{EvaluationResult, Expr, Formula},
+    interface::{IBoolean, ICommand, IEnumeration, IFloat, IInteger},
     store::{CacheStore, NodeId, NodeStore, ValueStore},
     Device, GenApiError, GenApiResult, ValueCtxt,
 };
@@ -30,6 +29,38 @@ pub(super) fn bool_from_id<T: ValueStore, U: CacheStore>(
     }
 }
 
+/// Returns an error if any command node selected by `selector` is still executing on the device.
+///
+/// This must be called before writing a value to a selector, otherwise the selector may be
+/// changed while the device is still processing the command selected by it.
+pub(super) fn verify_no_busy_command<T: ValueStore, U: CacheStore>(
+    selector: NodeId,
+    selected: &[NodeId],
+    device: &mut impl Device,
+    store: &impl NodeStore,
+    cx: &mut ValueCtxt<T, U>,
+) -> GenApiResult<()> {
+    for nid in selected {
+        if !cx.is_command_busy(*nid) {
+            continue;
+        }
+
+        if let Some(node) = nid.as_icommand_kind(store) {
+            if !node.is_done(device, store, cx)? {
+                return Err(GenApiError::busy(
+                    format!(
+                        "`{}` can't be changed while `{}` is executing",
+                        selector.name(store),
+                        nid.name(store)
+                    )
+                    .into(),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
 pub(super) fn int_from_slice(
     slice: &[u8],
     endianness: Endianness,
@@ -120,6 +151,9 @@ pub(super) struct FormulaEnvCollector<'a, T> {
     constants: &'a [NamedValue<T>],
     expressions: &'a [NamedValue<Expr>],
     var_env: HashMap<&'a str, Cow<'a, Expr>>,
+    /// Values inserted by `insert` or `insert_imm` in insertion order, which are passed to a
+    /// compiled formula as slots following `p_variables`.
+    imms: Vec<EvaluationResult>,
 }
 
 impl<'a, T: Copy + Into<Expr>> FormulaEnvCollector<'a, T> {
@@ -133,6 +167,38 @@ impl<'a, T: Copy + Into<Expr>> FormulaEnvCollector<'a, T> {
             constants,
             expressions,
             var_env: HashMap::new(),
+            imms: vec![],
+        }
+    }
+
+    /// Evaluates the `formula`.
+    ///
+    /// If the formula is compiled, the compiled program is evaluated with values of `p_variables`
+    /// and inserted values, otherwise this falls back to evaluation with the collected variable
+    /// environment.
+    pub(super) fn eval<U: ValueStore, S: CacheStore>(
+        self,
+        formula: &Formula,
+        device: &mut impl Device,
+        store: &impl NodeStore,
+        cx: &mut ValueCtxt<U, S>,
+    ) -> GenApiResult<EvaluationResult> {
+        if let Some(compiled) = formula.compiled() {
+            let mut slots = Vec::with_capacity(self.p_variables.len() + self.imms.len());
+            for variable in self.p_variables {
+                let expr = VariableKind::from_str(variable.name())?.get_value(
+                    variable.value(),
+                    device,
+                    store,
+                    cx,
+                )?;
+                slots.push(eval_result_from_expr(&expr));
+            }
+            slots.extend(self.imms);
+            compiled.eval(&slots)
+        } else {
+            let var_env = self.collect(device, store, cx)?;
+            formula.eval(&var_env)
         }
     }
     pub(super) fn collect<U: ValueStore, S: CacheStore>(
@@ -175,7 +241,9 @@ impl<'a, T: Copy + Into<Expr>> FormulaEnvCollector<'a, T> {
     }
 
     pub(super) fn insert_imm(&mut self, name: &'a str, imm: impl Into<Expr>) {
-        self.var_env.insert(name, Cow::Owned(imm.into()));
+        let imm = imm.into();
+        self.imms.push(eval_result_from_expr(&imm));
+        self.var_env.insert(name, Cow::Owned(imm));
     }
 
     pub(super) fn is_readable<U: ValueStore, S: CacheStore>(
@@ -207,6 +275,44 @@ impl<'a, T: Copy + Into<Expr>> FormulaEnvCollector<'a, T> {
     }
 }
 
+/// Compiles the `formula` of a node so that [`FormulaEnvCollector::eval`] can evaluate it without
+/// building a variable environment.
+///
+/// `imm_names` must be in the same order as values inserted to the collector by `insert` or
+/// `insert_imm`. The formula is left uncompiled if it contains an unresolvable identifier, in
+/// which case the formula is evaluated with a variable environment.
+pub(super) fn compile_formula<T: Copy + Into<Expr>>(
+    formula: &mut Formula,
+    p_variables: &[NamedValue<NodeId>],
+    constants: &[NamedValue<T>],
+    expressions: &[NamedValue<Expr>],
+    imm_names: &[&str],
+) {
+    let slots: Vec<&str> = p_variables
+        .iter()
+        .map(NamedValue::name)
+        .chain(imm_names.iter().copied())
+        .collect();
+
+    let mut bindings: HashMap<&str, Cow<Expr>> = HashMap::new();
+    for constant in constants {
+        bindings.insert(constant.name(), Cow::Owned(constant.value().into()));
+    }
+    for expr in expressions {
+        bindings.insert(expr.name(), Cow::Borrowed(expr.value_ref()));
+    }
+
+    formula.compiled = formula.compile(&slots, &bindings).ok();
+}
+
+fn eval_result_from_expr(expr: &Expr) -> EvaluationResult {
+    match expr {
+        Expr::Integer(i) => (*i).into(),
+        Expr::Float(f) => (*f).into(),
+        _ => unreachable!("a value of node must be either integer or float"),
+    }
+}
+
 #[derive(Debug)]
 enum VariableKind<'a> {
     Value,
@@ -384,9 +490,136 @@ fn expr_from_nid<T: ValueStore, U: CacheStore>(
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
+    use crate::{builder::GenApiBuilder, store::DefaultNodeStore};
+
     use super::*;
 
+    /// Wraps `nodes` in `RegisterDescription` of a test device.
+    pub(crate) fn register_description(nodes: &str) -> String {
+        format!(
+            r#"
+            <RegisterDescription
+              ModelName="CameleonModel"
+              VendorName="CameleonVendor"
+              StandardNameSpace="None"
+              SchemaMajorVersion="1"
+              SchemaMinorVersion="1"
+              SchemaSubMinorVersion="0"
+              MajorVersion="1"
+              MinorVersion="0"
+              SubMinorVersion="0"
+              ProductGuid="01234567-0123-0123-0123-0123456789ab"
+              VersionGuid="76543210-3210-3210-3210-ba9876543210">
+              {}
+            </RegisterDescription>
+            "#,
+            nodes
+        )
+    }
+
+    /// A device whose registers are backed by the memory.
+    pub(crate) struct Memory(pub(crate) Vec<u8>);
+
+    impl Memory {
+        fn range(
+            &self,
+            address: i64,
+            len: usize,
+        ) -> Result<std::ops::Range<usize>, Box<dyn std::error::Error + Send + Sync>> {
+            let start: usize = address.try_into()?;
+            match start.checked_add(len) {
+                Some(end) if end <= self.0.len() => Ok(start..end),
+                _ => Err("address is out of range".into()),
+            }
+        }
+    }
+
+    impl Device for Memory {
+        fn read_mem(
+            &mut self,
+            address: i64,
+            buf: &mut [u8],
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            let range = self.range(address, buf.len())?;
+            buf.copy_from_slice(&self.0[range]);
+            Ok(())
+        }
+
+        fn write_mem(
+            &mut self,
+            address: i64,
+            data: &[u8],
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            let range = self.range(address, data.len())?;
+            self.0[range].copy_from_slice(data);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_busy_command() {
+        let xml = register_description(
+            r#"
+            <Command Name="Execute">
+                <pValue>ExecuteReg</pValue>
+                <CommandValue>1</CommandValue>
+            </Command>
+            <IntReg Name="ExecuteReg">
+                <Address>0</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <pPort>Device</pPort>
+                <Cachable>NoCache</Cachable>
+            </IntReg>
+            <Integer Name="Selector">
+                <Value>0</Value>
+                <Min>0</Min>
+                <Max>10</Max>
+                <Inc>1</Inc>
+                <pSelected>Execute</pSelected>
+            </Integer>
+            <Port Name="Device"/>
+            "#,
+        );
+        let (_, store, mut cx) = GenApiBuilder::<DefaultNodeStore>::default()
+            .build(&xml)
+            .unwrap();
+        let execute = store.id_by_name("Execute").unwrap();
+        let mut device = Memory(vec![0; 4]);
+        let command = execute.expect_icommand_kind(&store).unwrap();
+        let selector = store.id_by_name("Selector").unwrap();
+        let selector = selector.expect_iinteger_kind(&store).unwrap();
+
+        // The device keeps the command value in the register while the command is executing.
+        command.execute(&mut device, &store, &mut cx).unwrap();
+        assert!(cx.is_command_busy(execute));
+        assert!(matches!(
+            selector.set_value(1, &mut device, &store, &mut cx),
+            Err(GenApiError::Busy(_))
+        ));
+        assert!(matches!(
+            command.execute(&mut device, &store, &mut cx),
+            Err(GenApiError::Busy(_))
+        ));
+        assert!(!command.is_done(&mut device, &store, &mut cx).unwrap());
+        assert!(cx.is_command_busy(execute));
+        assert_eq!(selector.value(&mut device, &store, &mut cx).unwrap(), 0);
+
+        // The device clears the register when the command completes.
+        device.0.fill(0);
+        assert!(command.is_done(&mut device, &store, &mut cx).unwrap());
+        assert!(!cx.is_command_busy(execute));
+        selector.set_value(1, &mut device, &store, &mut cx).unwrap();
+        assert_eq!(selector.value(&mut device, &store, &mut cx).unwrap(), 1);
+
+        // Writing to the selector checks the completion by itself.
+        command.execute(&mut device, &store, &mut cx).unwrap();
+        device.0.fill(0);
+        selector.set_value(2, &mut device, &store, &mut cx).unwrap();
+        assert!(!cx.is_command_busy(execute));
+    }
+
     #[test]
     fn test_bytes_from_float() {
         let mut f32_output = vec![0; 4];
@@ -0,0 +1,656 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains [`validate`] that checks the consistency of a node store eagerly.
+//!
+//! Nodes refer to each other by name, and the references are resolved lazily when a node is
+//! evaluated. Validating the store right after it's built reports the problems of a device
+//! description file before they're hit at evaluation time.
+//!
+//! # Examples
+//! ```rust
+//! use cameleon_genapi::{builder::GenApiBuilder, store::DefaultNodeStore, validation};
+//!
+//! let mut builder = GenApiBuilder::<DefaultNodeStore>::default().programmatic("Model", "Vendor");
+//! builder.category("Root").feature("Width").finish();
+//! // `WidthReg` is never defined.
+//! builder.integer("Width").p_value("WidthReg").finish();
+//! let (_, node_store, _) = builder.build();
+//!
+//! let diagnostics = validation::validate(&node_store);
+//! assert_eq!(diagnostics.len(), 1);
+//! assert_eq!(
+//!     diagnostics[0].to_string(),
+//!     "`Width` refers to undefined node `WidthReg` in `pValue`"
+//! );
+//! ```
+
+use std::{collections::HashMap, fmt};
+
+use string_interner::Symbol;
+
+use super::{
+    elem_type::{AddressKind, ImmOrPNode, ValueKind},
+    node_base::NodeElementBase,
+    store::{NodeData, NodeId, NodeStore},
+    RegisterBase,
+};
+
+/// A problem found by [`validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Diagnostic {
+    /// `Root` category is not defined.
+    MissingRoot,
+
+    /// A node refers to a node which is not defined.
+    DanglingReference {
+        /// Name of the node which has the reference.
+        node: String,
+        /// Element name of the reference, e.g. `pValue`.
+        element: &'static str,
+        /// Name of the referred node.
+        target: String,
+    },
+
+    /// A node refers to a node which doesn't implement the expected interface.
+    TypeMismatch {
+        /// Name of the node which has the reference.
+        node: String,
+        /// Element name of the reference, e.g. `pValue`.
+        element: &'static str,
+        /// Name of the referred node.
+        target: String,
+        /// Description of the expected interface, e.g. `IInteger`.
+        expected: &'static str,
+    },
+
+    /// Values of the nodes depend on each other circularly.
+    CircularDependency {
+        /// Names of the nodes in the cycle, each node depends on the next one and the last node
+        /// depends on the first one.
+        cycle: Vec<String>,
+    },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingRoot => write!(f, "`Root` category is not defined"),
+            Self::DanglingReference {
+                node,
+                element,
+                target,
+            } => write!(
+                f,
+                "`{}` refers to undefined node `{}` in `{}`",
+                node, target, element
+            ),
+            Self::TypeMismatch {
+                node,
+                element,
+                target,
+                expected,
+            } => write!(
+                f,
+                "`{}` refers to `{}` in `{}`, but it doesn't implement {}",
+                node, target, element, expected
+            ),
+            Self::CircularDependency { cycle } => {
+                write!(f, "circular dependency: ")?;
+                for name in cycle {
+                    write!(f, "`{}` -> ", name)?;
+                }
+                write!(f, "`{}`", cycle[0])
+            }
+        }
+    }
+}
+
+/// Validates the nodes in `node_store` and returns the found problems.
+///
+/// The following problems are reported.
+/// * `Root` category is missing.
+/// * A node refers to a node which is not defined in the store.
+/// * A node refers to a node which doesn't implement the interface required by the element, e.g.
+///   `pValue` of `Integer` refers to a `String` node.
+/// * Values of nodes depend on each other circularly. References which don't affect the value of
+///   the node, e.g. `pInvalidator`, `pSelected` and `pFeature`, are not considered.
+///
+/// An empty vector is returned if no problem is found.
+pub fn validate(node_store: &impl NodeStore) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+
+    let has_root = node_store
+        .id_by_name("Root")
+        .and_then(|nid| nid.as_icategory_kind(node_store))
+        .is_some();
+    if !has_root {
+        diagnostics.push(Diagnostic::MissingRoot);
+    }
+
+    let mut ids = vec![];
+    node_store.visit_nodes(|data| ids.push(data.node_base().id()));
+    // Report in the order in which the nodes are interned, which is the order of the original
+    // XML if the store is built by the parser.
+    ids.sort_by_key(|nid| nid.to_usize());
+
+    let mut graph = DependencyGraph::default();
+    for nid in ids {
+        for reference in references(node_store.node(nid)) {
+            let target = reference.target;
+            if node_store.node_opt(target).is_none() {
+                diagnostics.push(Diagnostic::DanglingReference {
+                    node: nid.name(node_store).into(),
+                    element: reference.element,
+                    target: target.name(node_store).into(),
+                });
+                continue;
+            }
+
+            if !reference.expected.is_satisfied_by(target, node_store) {
+                diagnostics.push(Diagnostic::TypeMismatch {
+                    node: nid.name(node_store).into(),
+                    element: reference.element,
+                    target: target.name(node_store).into(),
+                    expected: reference.expected.description(),
+                });
+            }
+
+            if reference.is_dependency {
+                graph.add_edge(nid, target);
+            }
+        }
+    }
+
+    for cycle in graph.cycles() {
+        diagnostics.push(Diagnostic::CircularDependency {
+            cycle: cycle
+                .into_iter()
+                .map(|nid| nid.name(node_store).into())
+                .collect(),
+        });
+    }
+
+    diagnostics
+}
+
+/// An interface which a referred node is expected to implement.
+#[derive(Debug, Clone, Copy)]
+enum Expected {
+    Node,
+    Integer,
+    Float,
+    String,
+    Port,
+    Enumeration,
+    EnumEntry,
+    /// `IInteger` or `IBoolean`, e.g. `pIsAvailable`.
+    Flag,
+    /// A node which can be used as a variable of formulas.
+    Numeric,
+}
+
+impl Expected {
+    fn is_satisfied_by(self, nid: NodeId, store: &impl NodeStore) -> bool {
+        match self {
+            Self::Node => true,
+            Self::Integer => nid.as_iinteger_kind(store).is_some(),
+            Self::Float => nid.as_ifloat_kind(store).is_some(),
+            Self::String => nid.as_istring_kind(store).is_some(),
+            Self::Port => nid.as_iport_kind(store).is_some(),
+            Self::Enumeration => nid.as_ienumeration_kind(store).is_some(),
+            Self::EnumEntry => nid.as_enum_entry(store).is_some(),
+            Self::Flag => {
+                nid.as_iinteger_kind(store).is_some() || nid.as_iboolean_kind(store).is_some()
+            }
+            Self::Numeric => {
+                nid.as_iinteger_kind(store).is_some()
+                    || nid.as_ifloat_kind(store).is_some()
+                    || nid.as_iboolean_kind(store).is_some()
+                    || nid.as_ienumeration_kind(store).is_some()
+            }
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Self::Node => "`INode`",
+            Self::Integer => "`IInteger`",
+            Self::Float => "`IFloat`",
+            Self::String => "`IString`",
+            Self::Port => "`IPort`",
+            Self::Enumeration => "`IEnumeration`",
+            Self::EnumEntry => "`EnumEntry`",
+            Self::Flag => "either `IInteger` or `IBoolean`",
+            Self::Numeric => "any of `IInteger`, `IFloat`, `IBoolean` or `IEnumeration`",
+        }
+    }
+}
+
+struct Reference {
+    element: &'static str,
+    target: NodeId,
+    expected: Expected,
+    /// `true` if the value of the node depends on the referred node.
+    is_dependency: bool,
+}
+
+/// Returns the references to other nodes of the node.
+fn references(data: &NodeData) -> Vec<Reference> {
+    let mut c = ReferenceCollector::default();
+    match data {
+        NodeData::Node(node) => c.elem_base(&node.elem_base),
+        NodeData::Category(node) => {
+            c.elem_base(&node.elem_base);
+            c.refs("pFeature", &node.p_features, Expected::Node, false);
+        }
+        NodeData::Integer(node) => {
+            c.elem_base(&node.elem_base);
+            c.value_kind(&node.value_kind, Expected::Integer);
+            c.imm_or_pnode("pMin", node.min, Expected::Integer);
+            c.imm_or_pnode("pMax", node.max, Expected::Integer);
+            c.imm_or_pnode("pInc", node.inc, Expected::Integer);
+            c.refs("pSelected", &node.p_selected, Expected::Node, false);
+        }
+        NodeData::IntReg(node) => {
+            c.register_base(&node.register_base);
+            c.refs("pSelected", &node.p_selected, Expected::Node, false);
+        }
+        NodeData::MaskedIntReg(node) => {
+            c.register_base(&node.register_base);
+            c.refs("pSelected", &node.p_selected, Expected::Node, false);
+        }
+        NodeData::Boolean(node) => {
+            c.elem_base(&node.elem_base);
+            c.imm_or_pnode("pValue", node.value, Expected::Integer);
+            c.refs("pSelected", &node.p_selected, Expected::Node, false);
+        }
+        NodeData::Command(node) => {
+            c.elem_base(&node.elem_base);
+            c.imm_or_pnode("pValue", node.value, Expected::Integer);
+            c.imm_or_pnode("pCommandValue", node.command_value, Expected::Integer);
+        }
+        NodeData::Enumeration(node) => {
+            c.elem_base(&node.elem_base);
+            c.refs("EnumEntry", &node.entries, Expected::EnumEntry, false);
+            c.imm_or_pnode("pValue", node.value, Expected::Integer);
+            c.refs("pSelected", &node.p_selected, Expected::Node, false);
+        }
+        NodeData::EnumEntry(node) => c.elem_base(&node.elem_base),
+        NodeData::Float(node) => {
+            c.elem_base(&node.elem_base);
+            c.value_kind(&node.value_kind, Expected::Float);
+            c.imm_or_pnode("pMin", node.min, Expected::Float);
+            c.imm_or_pnode("pMax", node.max, Expected::Float);
+            if let Some(inc) = node.inc {
+                c.imm_or_pnode("pInc", inc, Expected::Float);
+            }
+        }
+        NodeData::FloatReg(node) => c.register_base(&node.register_base),
+        NodeData::String(node) => {
+            c.elem_base(&node.elem_base);
+            c.imm_or_pnode("pValue", node.value, Expected::String);
+        }
+        NodeData::StringReg(node) => c.register_base(&node.register_base),
+        NodeData::Register(node) => c.register_base(&node.register_base),
+        NodeData::Converter(node) => {
+            c.elem_base(&node.elem_base);
+            c.p_variables(node.p_variables.iter().map(|var| var.value));
+            c.add("pValue", node.p_value, Expected::Numeric, true);
+        }
+        NodeData::IntConverter(node) => {
+            c.elem_base(&node.elem_base);
+            c.p_variables(node.p_variables.iter().map(|var| var.value));
+            c.add("pValue", node.p_value, Expected::Numeric, true);
+        }
+        NodeData::SwissKnife(node) => {
+            c.elem_base(&node.elem_base);
+            c.p_variables(node.p_variables.iter().map(|var| var.value));
+        }
+        NodeData::IntSwissKnife(node) => {
+            c.elem_base(&node.elem_base);
+            c.p_variables(node.p_variables.iter().map(|var| var.value));
+        }
+        NodeData::Port(node) => {
+            c.elem_base(&node.elem_base);
+            if let Some(chunk_id) = node.chunk_id {
+                c.imm_or_pnode("pChunkID", chunk_id, Expected::Integer);
+            }
+        }
+        NodeData::ConfRom(..)
+        | NodeData::TextDesc(..)
+        | NodeData::IntKey(..)
+        | NodeData::AdvFeatureLock(..)
+        | NodeData::SmartFeature(..) => {}
+    }
+    c.refs
+}
+
+#[derive(Default)]
+struct ReferenceCollector {
+    refs: Vec<Reference>,
+}
+
+impl ReferenceCollector {
+    fn elem_base(&mut self, elem_base: &NodeElementBase) {
+        self.opt("pIsImplemented", elem_base.p_is_implemented, Expected::Flag);
+        self.opt("pIsAvailable", elem_base.p_is_available, Expected::Flag);
+        self.opt("pIsLocked", elem_base.p_is_locked, Expected::Flag);
+        self.opt("pBlockPolling", elem_base.p_block_polling, Expected::Flag);
+        self.refs("pError", &elem_base.p_errors, Expected::Enumeration, false);
+        if let Some(p_alias) = elem_base.p_alias {
+            self.add("pAlias", p_alias, Expected::Node, false);
+        }
+        if let Some(p_cast_alias) = elem_base.p_cast_alias {
+            self.add("pCastAlias", p_cast_alias, Expected::Node, false);
+        }
+        self.refs(
+            "pInvalidator",
+            &elem_base.p_invalidators,
+            Expected::Node,
+            false,
+        );
+    }
+
+    fn register_base(&mut self, register_base: &RegisterBase) {
+        self.elem_base(&register_base.elem_base);
+        for kind in &register_base.address_kinds {
+            match kind {
+                AddressKind::Address(address) => {
+                    self.imm_or_pnode("pAddress", *address, Expected::Integer);
+                }
+                AddressKind::IntSwissKnife(nid) => {
+                    self.add("IntSwissKnife", *nid, Expected::Integer, true);
+                }
+                AddressKind::PIndex(p_index) => {
+                    self.add("pIndex", p_index.p_index, Expected::Integer, true);
+                    if let Some(offset) = p_index.offset {
+                        self.imm_or_pnode("pOffset", offset, Expected::Integer);
+                    }
+                }
+            }
+        }
+        self.imm_or_pnode("pLength", register_base.length, Expected::Integer);
+        self.add("pPort", register_base.p_port, Expected::Port, true);
+        self.refs(
+            "pInvalidator",
+            &register_base.p_invalidators,
+            Expected::Node,
+            false,
+        );
+    }
+
+    fn value_kind<T: Copy>(&mut self, value_kind: &ValueKind<T>, expected: Expected) {
+        match value_kind {
+            ValueKind::Value(_) => {}
+            ValueKind::PValue(p_value) => {
+                self.add("pValue", p_value.p_value, expected, true);
+                self.refs("pValueCopy", &p_value.p_value_copies, expected, false);
+            }
+            ValueKind::PIndex(p_index) => {
+                self.add("pIndex", p_index.p_index, Expected::Integer, true);
+                for indexed in &p_index.value_indexed {
+                    self.imm_or_pnode("pValueIndexed", indexed.indexed, expected);
+                }
+                self.imm_or_pnode("pValueDefault", p_index.value_default, expected);
+            }
+        }
+    }
+
+    fn p_variables(&mut self, p_variables: impl Iterator<Item = NodeId>) {
+        for nid in p_variables {
+            self.add("pVariable", nid, Expected::Numeric, true);
+        }
+    }
+
+    fn imm_or_pnode<T>(&mut self, element: &'static str, value: ImmOrPNode<T>, expected: Expected) {
+        if let ImmOrPNode::PNode(nid) = value {
+            self.add(element, nid, expected, true);
+        }
+    }
+
+    fn opt(&mut self, element: &'static str, nid: Option<NodeId>, expected: Expected) {
+        if let Some(nid) = nid {
+            self.add(element, nid, expected, true);
+        }
+    }
+
+    fn refs(
+        &mut self,
+        element: &'static str,
+        nids: &[NodeId],
+        expected: Expected,
+        is_dependency: bool,
+    ) {
+        for nid in nids {
+            self.add(element, *nid, expected, is_dependency);
+        }
+    }
+
+    fn add(
+        &mut self,
+        element: &'static str,
+        target: NodeId,
+        expected: Expected,
+        is_dependency: bool,
+    ) {
+        self.refs.push(Reference {
+            element,
+            target,
+            expected,
+            is_dependency,
+        });
+    }
+}
+
+#[derive(Default)]
+struct DependencyGraph {
+    /// Nodes in the order of insertion to report cycles deterministically.
+    nodes: Vec<NodeId>,
+    edges: HashMap<NodeId, Vec<NodeId>>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum VisitState {
+    InProgress,
+    Done,
+}
+
+impl DependencyGraph {
+    fn add_edge(&mut self, from: NodeId, to: NodeId) {
+        if !self.edges.contains_key(&from) {
+            self.nodes.push(from);
+        }
+        let edges = self.edges.entry(from).or_default();
+        if !edges.contains(&to) {
+            edges.push(to);
+        }
+    }
+
+    /// Returns the cycles in the graph. Each cycle is reported once even if it's reachable from
+    /// multiple nodes.
+    fn cycles(&self) -> Vec<Vec<NodeId>> {
+        let mut cycles = vec![];
+        let mut states = HashMap::new();
+        for &start in &self.nodes {
+            if states.contains_key(&start) {
+                continue;
+            }
+
+            // Depth first search without recursion, because dependency chains of some device
+            // description files are fairly deep.
+            let mut path = vec![start];
+            let mut stack = vec![(start, 0)];
+            states.insert(start, VisitState::InProgress);
+            while let Some((nid, edge_idx)) = stack.last_mut() {
+                let nid = *nid;
+                let next = self
+                    .edges
+                    .get(&nid)
+                    .and_then(|edges| edges.get(*edge_idx))
+                    .copied();
+                *edge_idx += 1;
+
+                match next {
+                    Some(next) => match states.get(&next) {
+                        Some(VisitState::InProgress) => {
+                            let pos = path.iter().position(|nid| *nid == next).unwrap();
+                            cycles.push(path[pos..].to_vec());
+                        }
+                        Some(VisitState::Done) => {}
+                        None => {
+                            states.insert(next, VisitState::InProgress);
+                            path.push(next);
+                            stack.push((next, 0));
+                        }
+                    },
+                    None => {
+                        states.insert(nid, VisitState::Done);
+                        path.pop();
+                        stack.pop();
+                    }
+                }
+            }
+        }
+        cycles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        builder::GenApiBuilder, store::DefaultNodeStore, utils::tests::register_description,
+    };
+
+    use super::*;
+
+    fn validate_xml(nodes: &str) -> Vec<Diagnostic> {
+        let (_, node_store, _) = GenApiBuilder::<DefaultNodeStore>::default()
+            .build(&register_description(nodes))
+            .unwrap();
+        validate(&node_store)
+    }
+
+    #[test]
+    fn test_valid() {
+        let diagnostics = validate_xml(
+            r#"
+            <Category Name="Root">
+                <pFeature>Width</pFeature>
+            </Category>
+            <Integer Name="Width">
+                <pValue>WidthReg</pValue>
+                <pMax>WidthMax</pMax>
+            </Integer>
+            <Integer Name="WidthMax">
+                <Value>1280</Value>
+            </Integer>
+            <IntReg Name="WidthReg">
+                <Address>0x1000</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <pPort>Device</pPort>
+                <pInvalidator>Width</pInvalidator>
+            </IntReg>
+            <Port Name="Device">
+            </Port>
+            "#,
+        );
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_missing_root() {
+        let diagnostics = validate_xml(
+            r#"
+            <Integer Name="Width">
+                <Value>640</Value>
+            </Integer>
+            "#,
+        );
+        assert_eq!(diagnostics, vec![Diagnostic::MissingRoot]);
+    }
+
+    #[test]
+    fn test_dangling_reference() {
+        let diagnostics = validate_xml(
+            r#"
+            <Category Name="Root">
+                <pFeature>Width</pFeature>
+            </Category>
+            <Integer Name="Width">
+                <pIsAvailable>WidthAvailable</pIsAvailable>
+                <Value>640</Value>
+            </Integer>
+            "#,
+        );
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic::DanglingReference {
+                node: "Width".into(),
+                element: "pIsAvailable",
+                target: "WidthAvailable".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        let diagnostics = validate_xml(
+            r#"
+            <Category Name="Root">
+                <pFeature>Width</pFeature>
+            </Category>
+            <Integer Name="Width">
+                <pValue>DeviceName</pValue>
+            </Integer>
+            <String Name="DeviceName">
+                <Value>Cameleon</Value>
+            </String>
+            "#,
+        );
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic::TypeMismatch {
+                node: "Width".into(),
+                element: "pValue",
+                target: "DeviceName".into(),
+                expected: "`IInteger`",
+            }]
+        );
+    }
+
+    #[test]
+    fn test_circular_dependency() {
+        let diagnostics = validate_xml(
+            r#"
+            <Category Name="Root">
+                <pFeature>Width</pFeature>
+            </Category>
+            <Integer Name="Width">
+                <pValue>Height</pValue>
+                <pSelected>Height</pSelected>
+            </Integer>
+            <Integer Name="Height">
+                <pValue>Stride</pValue>
+            </Integer>
+            <IntSwissKnife Name="Stride">
+                <pVariable Name="W">Width</pVariable>
+                <Formula>W * 2</Formula>
+            </IntSwissKnife>
+            "#,
+        );
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic::CircularDependency {
+                cycle: vec!["Width".into(), "Height".into(), "Stride".into()],
+            }]
+        );
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "circular dependency: `Width` -> `Height` -> `Stride` -> `Width`"
+        );
+    }
+}
@@ -0,0 +1,1179 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains [`write`] that serializes a node store back to `GenApi` XML.
+//!
+//! Combined with [`crate::parser`] and [`crate::programmatic`], this allows editing device
+//! description files programmatically.
+//!
+//! # Examples
+//! ```rust
+//! use cameleon_genapi::{builder::GenApiBuilder, store::DefaultNodeStore, writer};
+//!
+//! let (reg_desc, node_store, value_ctxt) = GenApiBuilder::<DefaultNodeStore>::default()
+//!     .programmatic("Model", "Vendor")
+//!     .build();
+//! let xml = writer::write(&reg_desc, &node_store, &value_ctxt.value_store);
+//!
+//! // The written XML can be parsed again.
+//! let (reg_desc2, _, _) = GenApiBuilder::<DefaultNodeStore>::default()
+//!     .build(&xml)
+//!     .unwrap();
+//! assert_eq!(reg_desc.model_name(), reg_desc2.model_name());
+//! ```
+//!
+//! Extensions kept by the tolerant parser are written back at the end of their parent element.
+//!
+//! Elements whose values are the defaults of the `GenApi` schema are omitted. Nodes which are
+//! not supported by this crate, e.g. `ConfRom`, are not written.
+//! `StructReg` and `Group` are written as flattened `MaskedIntReg` and their member nodes
+//! respectively because they are expanded when parsed.
+
+use std::{collections::HashSet, fmt::Write as _};
+
+use string_interner::Symbol;
+
+use super::{
+    elem_type::{
+        AccessMode, AddressKind, BitMask, CachingMode, DisplayNotation, Endianness, Extensions,
+        FloatRepresentation, ImmOrPNode, IntegerRepresentation, MergePriority, NameSpace,
+        NamedValue, Sign, Slope, StandardNameSpace, ValueKind, Visibility,
+    },
+    formula::Expr,
+    node_base::{NodeAttributeBase, NodeElementBase},
+    store::{FloatId, IntegerId, NodeData, NodeId, NodeStore, StringId, ValueStore},
+    BooleanNode, CategoryNode, CommandNode, ConverterNode, EnumEntryNode, EnumerationNode,
+    FloatNode, FloatRegNode, IntConverterNode, IntRegNode, IntSwissKnifeNode, IntegerNode,
+    MaskedIntRegNode, Node, PortNode, RegisterBase, RegisterDescription, StringNode,
+    SwissKnifeNode,
+};
+
+const INDENT: &str = "    ";
+
+/// Serializes the register description and nodes to `GenApi` XML.
+///
+/// Immediate values of nodes are read from `value_store`, so the current values are written if
+/// the values have been modified after the store was built.
+///
+/// # Panics
+/// Panics if `node_store` or `value_store` doesn't contain a node or value referred by a node,
+/// i.e. the stores are not built by the same builder.
+pub fn write(
+    reg_desc: &RegisterDescription,
+    node_store: &impl NodeStore,
+    value_store: &impl ValueStore,
+) -> String {
+    let mut ids = vec![];
+    let mut embedded = HashSet::new();
+    node_store.visit_nodes(|data| match data {
+        NodeData::EnumEntry(..)
+        | NodeData::ConfRom(..)
+        | NodeData::TextDesc(..)
+        | NodeData::IntKey(..)
+        | NodeData::AdvFeatureLock(..)
+        | NodeData::SmartFeature(..) => {}
+        _ => {
+            ids.push(data.node_base().id());
+            if let Some(register_base) = register_base(data) {
+                // `IntSwissKnife`s in address elements are written inside the register.
+                for kind in &register_base.address_kinds {
+                    if let AddressKind::IntSwissKnife(nid) = kind {
+                        embedded.insert(*nid);
+                    }
+                }
+            }
+        }
+    });
+    // Keep the order in which the nodes are interned, which is the order of the original XML if
+    // the store is built by the parser.
+    ids.sort_by_key(|nid| nid.to_usize());
+
+    let mut w = XmlWriter {
+        buf: String::new(),
+        depth: 0,
+        node_store,
+        value_store,
+    };
+
+    w.buf
+        .push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    let tag = "RegisterDescription";
+    w.start(tag, &register_description_attrs(reg_desc));
+    for nid in ids {
+        if !embedded.contains(&nid) {
+            w.node_data(node_store.node(nid));
+        }
+    }
+    w.extension_elements(&reg_desc.extensions);
+    w.end(tag);
+
+    w.buf
+}
+
+/// Serializes a single node to `GenApi` XML element.
+///
+/// Returns an empty string for nodes which [`write`] doesn't write, e.g. `EnumEntry`.
+pub(crate) fn write_node(
+    data: &NodeData,
+    node_store: &impl NodeStore,
+    value_store: &impl ValueStore,
+) -> String {
+    let mut w = XmlWriter {
+        buf: String::new(),
+        depth: 0,
+        node_store,
+        value_store,
+    };
+    w.node_data(data);
+    w.buf
+}
+
+fn register_base(data: &NodeData) -> Option<&RegisterBase> {
+    match data {
+        NodeData::IntReg(node) => Some(&node.register_base),
+        NodeData::MaskedIntReg(node) => Some(&node.register_base),
+        NodeData::FloatReg(node) => Some(&node.register_base),
+        NodeData::StringReg(node) => Some(&node.register_base),
+        NodeData::Register(node) => Some(&node.register_base),
+        _ => None,
+    }
+}
+
+pub(crate) fn register_description_attrs(reg_desc: &RegisterDescription) -> Vec<(&str, String)> {
+    let mut attrs = vec![
+        (
+            "xmlns",
+            format!(
+                "http://www.genicam.org/GenApi/Version_{}_{}",
+                reg_desc.schema_major_version, reg_desc.schema_minor_version
+            ),
+        ),
+        ("ModelName", reg_desc.model_name.clone()),
+        ("VendorName", reg_desc.vendor_name.clone()),
+    ];
+    if let Some(tooltip) = &reg_desc.tooltip {
+        attrs.push(("ToolTip", tooltip.clone()));
+    }
+    attrs.extend(vec![
+        (
+            "StandardNameSpace",
+            reg_desc.standard_name_space.xml_text().into(),
+        ),
+        (
+            "SchemaMajorVersion",
+            reg_desc.schema_major_version.to_string(),
+        ),
+        (
+            "SchemaMinorVersion",
+            reg_desc.schema_minor_version.to_string(),
+        ),
+        (
+            "SchemaSubMinorVersion",
+            reg_desc.schema_subminor_version.to_string(),
+        ),
+        ("MajorVersion", reg_desc.major_version.to_string()),
+        ("MinorVersion", reg_desc.minor_version.to_string()),
+        ("SubMinorVersion", reg_desc.subminor_version.to_string()),
+        ("ProductGuid", reg_desc.product_guid.clone()),
+        ("VersionGuid", reg_desc.version_guid.clone()),
+    ]);
+    attrs.extend(
+        reg_desc
+            .name_spaces
+            .iter()
+            .map(|(name, uri)| (name.as_str(), uri.clone())),
+    );
+    attrs.extend(extension_attrs(&reg_desc.extensions));
+    attrs
+}
+
+struct XmlWriter<'a, T, U> {
+    buf: String,
+    depth: usize,
+    node_store: &'a T,
+    value_store: &'a U,
+}
+
+impl<'a, T: NodeStore, U: ValueStore> XmlWriter<'a, T, U> {
+    fn node_data(&mut self, data: &NodeData) {
+        match data {
+            NodeData::Node(node) => self.node(node),
+            NodeData::Category(node) => self.category(node),
+            NodeData::Integer(node) => self.integer(node),
+            NodeData::IntReg(node) => self.int_reg(node),
+            NodeData::MaskedIntReg(node) => self.masked_int_reg(node),
+            NodeData::Boolean(node) => self.boolean(node),
+            NodeData::Command(node) => self.command(node),
+            NodeData::Enumeration(node) => self.enumeration(node),
+            NodeData::Float(node) => self.float(node),
+            NodeData::FloatReg(node) => self.float_reg(node),
+            NodeData::String(node) => self.string(node),
+            NodeData::StringReg(node) => {
+                self.register("StringReg", &node.attr_base, &node.register_base);
+            }
+            NodeData::Register(node) => {
+                self.register("Register", &node.attr_base, &node.register_base);
+            }
+            NodeData::Converter(node) => self.converter(node),
+            NodeData::IntConverter(node) => self.int_converter(node),
+            NodeData::SwissKnife(node) => self.swiss_knife(node),
+            NodeData::IntSwissKnife(node) => self.int_swiss_knife(node),
+            NodeData::Port(node) => self.port(node),
+            NodeData::EnumEntry(..)
+            | NodeData::ConfRom(..)
+            | NodeData::TextDesc(..)
+            | NodeData::IntKey(..)
+            | NodeData::AdvFeatureLock(..)
+            | NodeData::SmartFeature(..) => {}
+        }
+    }
+
+    fn node(&mut self, node: &Node) {
+        let tag = "Node";
+        self.start_node(tag, &node.attr_base);
+        self.elem_base(&node.elem_base);
+        self.end_node(tag, &node.attr_base);
+    }
+
+    fn category(&mut self, node: &CategoryNode) {
+        let tag = "Category";
+        self.start_node(tag, &node.attr_base);
+        self.elem_base(&node.elem_base);
+        self.node_refs("pFeature", &node.p_features);
+        self.end_node(tag, &node.attr_base);
+    }
+
+    fn integer(&mut self, node: &IntegerNode) {
+        let tag = "Integer";
+        self.start_node(tag, &node.attr_base);
+        self.elem_base(&node.elem_base);
+        self.streamable(node.streamable);
+        self.value_kind(&node.value_kind);
+        self.imm_or_pnode("Min", "pMin", node.min);
+        self.imm_or_pnode("Max", "pMax", node.max);
+        self.imm_or_pnode("Inc", "pInc", node.inc);
+        self.opt_elem("Unit", node.unit.as_deref());
+        self.integer_representation(node.representation);
+        self.node_refs("pSelected", &node.p_selected);
+        self.end_node(tag, &node.attr_base);
+    }
+
+    fn int_reg(&mut self, node: &IntRegNode) {
+        let tag = "IntReg";
+        self.start_node(tag, &node.attr_base);
+        self.register_base(&node.register_base);
+        self.sign(node.sign);
+        self.endianness(node.endianness);
+        self.opt_elem("Unit", node.unit.as_deref());
+        self.integer_representation(node.representation);
+        self.node_refs("pSelected", &node.p_selected);
+        self.end_node(tag, &node.attr_base);
+    }
+
+    fn masked_int_reg(&mut self, node: &MaskedIntRegNode) {
+        let tag = "MaskedIntReg";
+        self.start_node(tag, &node.attr_base);
+        self.register_base(&node.register_base);
+        match node.bit_mask {
+            BitMask::SingleBit(bit) => self.elem("Bit", bit),
+            BitMask::Range { lsb, msb } => {
+                self.elem("LSB", lsb);
+                self.elem("MSB", msb);
+            }
+        }
+        self.sign(node.sign);
+        self.endianness(node.endianness);
+        self.opt_elem("Unit", node.unit.as_deref());
+        self.integer_representation(node.representation);
+        self.node_refs("pSelected", &node.p_selected);
+        self.end_node(tag, &node.attr_base);
+    }
+
+    fn boolean(&mut self, node: &BooleanNode) {
+        let tag = "Boolean";
+        self.start_node(tag, &node.attr_base);
+        self.elem_base(&node.elem_base);
+        self.streamable(node.streamable);
+        match node.value {
+            ImmOrPNode::Imm(id) => {
+                let value = self.integer_value(id) == node.on_value;
+                self.elem("Value", value);
+            }
+            ImmOrPNode::PNode(nid) => self.node_ref("pValue", nid),
+        }
+        if node.on_value != 1 {
+            self.elem("OnValue", node.on_value);
+        }
+        if node.off_value != 0 {
+            self.elem("OffValue", node.off_value);
+        }
+        self.node_refs("pSelected", &node.p_selected);
+        self.end_node(tag, &node.attr_base);
+    }
+
+    fn command(&mut self, node: &CommandNode) {
+        let tag = "Command";
+        self.start_node(tag, &node.attr_base);
+        self.elem_base(&node.elem_base);
+        self.imm_or_pnode("Value", "pValue", node.value);
+        self.imm_or_pnode("CommandValue", "pCommandValue", node.command_value);
+        self.opt_elem("PollingTime", node.polling_time);
+        self.end_node(tag, &node.attr_base);
+    }
+
+    fn enumeration(&mut self, node: &EnumerationNode) {
+        let tag = "Enumeration";
+        self.start_node(tag, &node.attr_base);
+        self.elem_base(&node.elem_base);
+        self.streamable(node.streamable);
+        for nid in &node.entries {
+            let entry = nid.expect_enum_entry(self.node_store).unwrap();
+            self.enum_entry(entry);
+        }
+        self.imm_or_pnode("Value", "pValue", node.value);
+        self.node_refs("pSelected", &node.p_selected);
+        self.opt_elem("PollingTime", node.polling_time);
+        self.end_node(tag, &node.attr_base);
+    }
+
+    fn enum_entry(&mut self, entry: &EnumEntryNode) {
+        let tag = "EnumEntry";
+        // The interned name of an entry is generated by the parser, use the symbolic name
+        // instead.
+        let attrs = node_attrs(&entry.symbolic, &entry.attr_base);
+        self.start(tag, &attrs);
+        self.elem_base(&entry.elem_base);
+        self.elem("Value", entry.value);
+        if let Some(numeric_value) = entry.numeric_value {
+            self.elem("NumericValue", float_text(numeric_value));
+        }
+        if entry.is_self_clearing {
+            self.elem("IsSelfClearing", "Yes");
+        }
+        self.end_node(tag, &entry.attr_base);
+    }
+
+    fn float(&mut self, node: &FloatNode) {
+        let tag = "Float";
+        self.start_node(tag, &node.attr_base);
+        self.elem_base(&node.elem_base);
+        self.streamable(node.streamable);
+        self.value_kind(&node.value_kind);
+        self.imm_or_pnode("Min", "pMin", node.min);
+        self.imm_or_pnode("Max", "pMax", node.max);
+        if let Some(inc) = node.inc {
+            self.imm_or_pnode("Inc", "pInc", inc);
+        }
+        self.opt_elem("Unit", node.unit.as_deref());
+        self.float_representation(node.representation);
+        self.display_notation(node.display_notation);
+        self.display_precision(node.display_precision);
+        self.end_node(tag, &node.attr_base);
+    }
+
+    fn float_reg(&mut self, node: &FloatRegNode) {
+        let tag = "FloatReg";
+        self.start_node(tag, &node.attr_base);
+        self.register_base(&node.register_base);
+        self.endianness(node.endianness);
+        self.opt_elem("Unit", node.unit.as_deref());
+        self.float_representation(node.representation);
+        self.display_notation(node.display_notation);
+        self.display_precision(node.display_precision);
+        self.end_node(tag, &node.attr_base);
+    }
+
+    fn string(&mut self, node: &StringNode) {
+        let tag = "String";
+        self.start_node(tag, &node.attr_base);
+        self.elem_base(&node.elem_base);
+        self.streamable(node.streamable);
+        self.imm_or_pnode("Value", "pValue", node.value);
+        self.end_node(tag, &node.attr_base);
+    }
+
+    fn register(&mut self, tag: &str, attr_base: &NodeAttributeBase, register_base: &RegisterBase) {
+        self.start_node(tag, attr_base);
+        self.register_base(register_base);
+        self.end_node(tag, attr_base);
+    }
+
+    fn converter(&mut self, node: &ConverterNode) {
+        let tag = "Converter";
+        self.start_node(tag, &node.attr_base);
+        self.elem_base(&node.elem_base);
+        self.streamable(node.streamable);
+        self.formula_env(&node.p_variables, &node.constants, &node.expressions);
+        self.elem("FormulaTo", &node.formula_to);
+        self.elem("FormulaFrom", &node.formula_from);
+        self.node_ref("pValue", node.p_value);
+        self.opt_elem("Unit", node.unit.as_deref());
+        self.float_representation(node.representation);
+        self.display_notation(node.display_notation);
+        self.display_precision(node.display_precision);
+        self.slope(node.slope);
+        if node.is_linear {
+            self.elem("IsLinear", "Yes");
+        }
+        self.end_node(tag, &node.attr_base);
+    }
+
+    fn int_converter(&mut self, node: &IntConverterNode) {
+        let tag = "IntConverter";
+        self.start_node(tag, &node.attr_base);
+        self.elem_base(&node.elem_base);
+        self.streamable(node.streamable);
+        self.formula_env(&node.p_variables, &node.constants, &node.expressions);
+        self.elem("FormulaTo", &node.formula_to);
+        self.elem("FormulaFrom", &node.formula_from);
+        self.node_ref("pValue", node.p_value);
+        self.opt_elem("Unit", node.unit.as_deref());
+        self.integer_representation(node.representation);
+        self.slope(node.slope);
+        self.end_node(tag, &node.attr_base);
+    }
+
+    fn swiss_knife(&mut self, node: &SwissKnifeNode) {
+        let tag = "SwissKnife";
+        self.start_node(tag, &node.attr_base);
+        self.elem_base(&node.elem_base);
+        self.streamable(node.streamable);
+        self.formula_env(&node.p_variables, &node.constants, &node.expressions);
+        self.elem("Formula", &node.formula);
+        self.opt_elem("Unit", node.unit.as_deref());
+        self.float_representation(node.representation);
+        self.display_notation(node.display_notation);
+        self.display_precision(node.display_precision);
+        self.end_node(tag, &node.attr_base);
+    }
+
+    fn int_swiss_knife(&mut self, node: &IntSwissKnifeNode) {
+        let tag = "IntSwissKnife";
+        self.start_node(tag, &node.attr_base);
+        self.elem_base(&node.elem_base);
+        self.streamable(node.streamable);
+        self.formula_env(&node.p_variables, &node.constants, &node.expressions);
+        self.elem("Formula", &node.formula);
+        self.opt_elem("Unit", node.unit.as_deref());
+        self.integer_representation(node.representation);
+        self.end_node(tag, &node.attr_base);
+    }
+
+    fn port(&mut self, node: &PortNode) {
+        let tag = "Port";
+        self.start_node(tag, &node.attr_base);
+        self.elem_base(&node.elem_base);
+        match node.chunk_id {
+            Some(ImmOrPNode::Imm(id)) => self.elem("ChunkID", format!("{:X}", id)),
+            Some(ImmOrPNode::PNode(nid)) => self.node_ref("pChunkID", nid),
+            None => {}
+        }
+        if node.swap_endianness {
+            self.elem("SwapEndianess", "Yes");
+        }
+        if node.cache_chunk_data {
+            self.elem("CacheChunkData", "Yes");
+        }
+        self.end_node(tag, &node.attr_base);
+    }
+
+    fn elem_base(&mut self, elem_base: &NodeElementBase) {
+        self.opt_elem("ToolTip", elem_base.tooltip.as_deref());
+        self.opt_elem("Description", elem_base.description.as_deref());
+        self.opt_elem("DisplayName", elem_base.display_name.as_deref());
+        if elem_base.visibility != Visibility::Beginner {
+            self.elem("Visibility", elem_base.visibility.xml_text());
+        }
+        self.opt_elem("DocuURL", elem_base.docu_url.as_deref());
+        if elem_base.is_deprecated {
+            self.elem("IsDeprecated", "Yes");
+        }
+        if let Some(event_id) = elem_base.event_id {
+            self.elem("EventID", format!("{:X}", event_id));
+        }
+        self.opt_node_ref("pIsImplemented", elem_base.p_is_implemented);
+        self.opt_node_ref("pIsAvailable", elem_base.p_is_available);
+        self.opt_node_ref("pIsLocked", elem_base.p_is_locked);
+        self.opt_node_ref("pBlockPolling", elem_base.p_block_polling);
+        if elem_base.imposed_access_mode != AccessMode::RW {
+            self.elem(
+                "ImposedAccessMode",
+                elem_base.imposed_access_mode.xml_text(),
+            );
+        }
+        self.node_refs("pError", &elem_base.p_errors);
+        self.opt_node_ref("pAlias", elem_base.p_alias);
+        self.opt_node_ref("pCastAlias", elem_base.p_cast_alias);
+        self.node_refs("pInvalidator", &elem_base.p_invalidators);
+    }
+
+    fn register_base(&mut self, register_base: &RegisterBase) {
+        self.elem_base(&register_base.elem_base);
+        self.streamable(register_base.streamable);
+        for kind in &register_base.address_kinds {
+            match kind {
+                AddressKind::Address(ImmOrPNode::Imm(address)) => {
+                    self.elem("Address", hex_text(*address));
+                }
+                AddressKind::Address(ImmOrPNode::PNode(nid)) => self.node_ref("pAddress", *nid),
+                AddressKind::IntSwissKnife(nid) => match self.node_store.node(*nid) {
+                    NodeData::IntSwissKnife(node) => self.int_swiss_knife(node),
+                    _ => unreachable!(),
+                },
+                AddressKind::PIndex(p_index) => {
+                    let attrs: Vec<_> = p_index
+                        .offset
+                        .map(|offset| match offset {
+                            ImmOrPNode::Imm(i) => ("Offset", i.to_string()),
+                            ImmOrPNode::PNode(nid) => ("pOffset", self.name(nid).to_string()),
+                        })
+                        .into_iter()
+                        .collect();
+                    let name = self.name(p_index.p_index).to_string();
+                    self.elem_with_attrs("pIndex", &attrs, name);
+                }
+            }
+        }
+        match register_base.length {
+            ImmOrPNode::Imm(length) => self.elem("Length", length),
+            ImmOrPNode::PNode(nid) => self.node_ref("pLength", nid),
+        }
+        if register_base.access_mode != AccessMode::RO {
+            self.elem("AccessMode", register_base.access_mode.xml_text());
+        }
+        self.node_ref("pPort", register_base.p_port);
+        if register_base.cacheable != CachingMode::WriteThrough {
+            self.elem("Cachable", register_base.cacheable.xml_text());
+        }
+        self.opt_elem("PollingTime", register_base.polling_time);
+        self.node_refs("pInvalidator", &register_base.p_invalidators);
+    }
+
+    fn formula_env<C: XmlValue>(
+        &mut self,
+        p_variables: &[NamedValue<NodeId>],
+        constants: &[NamedValue<C>],
+        expressions: &[NamedValue<Expr>],
+    ) {
+        for var in p_variables {
+            let name = self.name(var.value).to_string();
+            self.elem_with_attrs("pVariable", &[("Name", var.name.clone())], name);
+        }
+        for constant in constants {
+            let value = constant.value.xml_value(self.value_store);
+            self.elem_with_attrs("Constant", &[("Name", constant.name.clone())], value);
+        }
+        for expr in expressions {
+            self.elem_with_attrs("Expression", &[("Name", expr.name.clone())], &expr.value);
+        }
+    }
+
+    fn value_kind<V: XmlValue>(&mut self, value_kind: &ValueKind<V>) {
+        match value_kind {
+            ValueKind::Value(v) => {
+                let value = v.xml_value(self.value_store);
+                self.elem("Value", value);
+            }
+            ValueKind::PValue(p_value) => {
+                self.node_ref("pValue", p_value.p_value);
+                self.node_refs("pValueCopy", &p_value.p_value_copies);
+            }
+            ValueKind::PIndex(p_index) => {
+                self.node_ref("pIndex", p_index.p_index);
+                for indexed in &p_index.value_indexed {
+                    let attrs = [("Index", indexed.index.to_string())];
+                    match indexed.indexed {
+                        ImmOrPNode::Imm(v) => {
+                            let value = v.xml_value(self.value_store);
+                            self.elem_with_attrs("ValueIndexed", &attrs, value);
+                        }
+                        ImmOrPNode::PNode(nid) => {
+                            let name = self.name(nid).to_string();
+                            self.elem_with_attrs("pValueIndexed", &attrs, name);
+                        }
+                    }
+                }
+                self.imm_or_pnode("ValueDefault", "pValueDefault", p_index.value_default);
+            }
+        }
+    }
+
+    fn imm_or_pnode<V: XmlValue>(&mut self, imm_tag: &str, p_tag: &str, value: ImmOrPNode<V>) {
+        match value {
+            ImmOrPNode::Imm(v) => {
+                let value = v.xml_value(self.value_store);
+                self.elem(imm_tag, value);
+            }
+            ImmOrPNode::PNode(nid) => self.node_ref(p_tag, nid),
+        }
+    }
+
+    fn streamable(&mut self, streamable: bool) {
+        if streamable {
+            self.elem("Streamable", "Yes");
+        }
+    }
+
+    fn sign(&mut self, sign: Sign) {
+        if sign != Sign::Unsigned {
+            self.elem("Sign", sign.xml_text());
+        }
+    }
+
+    fn endianness(&mut self, endianness: Endianness) {
+        if endianness != Endianness::LE {
+            self.elem("Endianess", endianness.xml_text());
+        }
+    }
+
+    fn integer_representation(&mut self, repr: IntegerRepresentation) {
+        if repr != IntegerRepresentation::PureNumber {
+            self.elem("Representation", repr.xml_text());
+        }
+    }
+
+    fn float_representation(&mut self, repr: FloatRepresentation) {
+        if repr != FloatRepresentation::PureNumber {
+            self.elem("Representation", repr.xml_text());
+        }
+    }
+
+    fn display_notation(&mut self, notation: DisplayNotation) {
+        if notation != DisplayNotation::Automatic {
+            self.elem("DisplayNotation", notation.xml_text());
+        }
+    }
+
+    fn display_precision(&mut self, precision: i64) {
+        if precision != 6 {
+            self.elem("DisplayPrecision", precision);
+        }
+    }
+
+    fn slope(&mut self, slope: Slope) {
+        if slope != Slope::Automatic {
+            self.elem("Slope", slope.xml_text());
+        }
+    }
+
+    fn integer_value(&self, id: IntegerId) -> i64 {
+        self.value_store.integer_value(id).unwrap()
+    }
+
+    fn name(&self, nid: NodeId) -> &'a str {
+        nid.name(self.node_store)
+    }
+
+    fn node_ref(&mut self, tag: &str, nid: NodeId) {
+        let name = self.name(nid);
+        self.elem(tag, name);
+    }
+
+    fn opt_node_ref(&mut self, tag: &str, nid: Option<NodeId>) {
+        if let Some(nid) = nid {
+            self.node_ref(tag, nid);
+        }
+    }
+
+    fn node_refs(&mut self, tag: &str, nids: &[NodeId]) {
+        for nid in nids {
+            self.node_ref(tag, *nid);
+        }
+    }
+
+    fn start_node(&mut self, tag: &str, attr_base: &NodeAttributeBase) {
+        let attrs = node_attrs(self.name(attr_base.id), attr_base);
+        self.start(tag, &attrs);
+    }
+
+    fn end_node(&mut self, tag: &str, attr_base: &NodeAttributeBase) {
+        self.extension_elements(&attr_base.extensions);
+        self.end(tag);
+    }
+
+    /// Writes extension elements as they are in the original XML.
+    fn extension_elements(&mut self, extensions: &Extensions) {
+        for elem in &extensions.elements {
+            self.indent();
+            self.buf.push_str(&elem.source);
+            self.buf.push('\n');
+        }
+    }
+
+    fn start(&mut self, tag: &str, attrs: &[(&str, String)]) {
+        self.indent();
+        write!(self.buf, "<{}", tag).unwrap();
+        self.attrs(attrs);
+        self.buf.push_str(">\n");
+        self.depth += 1;
+    }
+
+    fn end(&mut self, tag: &str) {
+        self.depth -= 1;
+        self.indent();
+        writeln!(self.buf, "</{}>", tag).unwrap();
+    }
+
+    fn elem(&mut self, tag: &str, text: impl ToString) {
+        self.elem_with_attrs(tag, &[], text);
+    }
+
+    fn opt_elem(&mut self, tag: &str, text: Option<impl ToString>) {
+        if let Some(text) = text {
+            self.elem(tag, text);
+        }
+    }
+
+    fn elem_with_attrs(&mut self, tag: &str, attrs: &[(&str, String)], text: impl ToString) {
+        self.indent();
+        write!(self.buf, "<{}", tag).unwrap();
+        self.attrs(attrs);
+        writeln!(self.buf, ">{}</{}>", escape(&text.to_string()), tag).unwrap();
+    }
+
+    fn attrs(&mut self, attrs: &[(&str, String)]) {
+        for (name, value) in attrs {
+            write!(self.buf, " {}=\"{}\"", name, escape(value)).unwrap();
+        }
+    }
+
+    fn indent(&mut self) {
+        for _ in 0..self.depth {
+            self.buf.push_str(INDENT);
+        }
+    }
+}
+
+fn node_attrs<'a>(name: &str, attr_base: &'a NodeAttributeBase) -> Vec<(&'a str, String)> {
+    let mut attrs = vec![("Name", name.to_string())];
+    if attr_base.name_space != NameSpace::Custom {
+        attrs.push(("NameSpace", attr_base.name_space.xml_text().into()));
+    }
+    if attr_base.merge_priority != MergePriority::Mid {
+        attrs.push(("MergePriority", attr_base.merge_priority.xml_text().into()));
+    }
+    if let Some(expose_static) = attr_base.expose_static {
+        let text = if expose_static { "Yes" } else { "No" };
+        attrs.push(("ExposeStatic", text.into()));
+    }
+    attrs.extend(extension_attrs(&attr_base.extensions));
+    attrs
+}
+
+fn extension_attrs(extensions: &Extensions) -> impl Iterator<Item = (&str, String)> {
+    extensions
+        .attributes
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.clone()))
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn hex_text(i: i64) -> String {
+    if i < 0 {
+        i.to_string()
+    } else {
+        format!("0x{:X}", i)
+    }
+}
+
+fn float_text(f: f64) -> String {
+    if f.is_nan() {
+        "NaN".into()
+    } else if f == f64::INFINITY {
+        "INF".into()
+    } else if f == f64::NEG_INFINITY {
+        "-INF".into()
+    } else {
+        // `Debug` format is the shortest representation that is parsed to the same value.
+        format!("{:?}", f)
+    }
+}
+
+/// A value which is written as a text of an element.
+trait XmlValue: Copy {
+    fn xml_value(self, value_store: &impl ValueStore) -> String;
+}
+
+impl XmlValue for i64 {
+    fn xml_value(self, _: &impl ValueStore) -> String {
+        self.to_string()
+    }
+}
+
+impl XmlValue for f64 {
+    fn xml_value(self, _: &impl ValueStore) -> String {
+        float_text(self)
+    }
+}
+
+impl XmlValue for IntegerId {
+    fn xml_value(self, value_store: &impl ValueStore) -> String {
+        value_store.integer_value(self).unwrap().to_string()
+    }
+}
+
+impl XmlValue for FloatId {
+    fn xml_value(self, value_store: &impl ValueStore) -> String {
+        float_text(value_store.float_value(self).unwrap())
+    }
+}
+
+impl XmlValue for StringId {
+    fn xml_value(self, value_store: &impl ValueStore) -> String {
+        value_store.str_value(self).unwrap().clone()
+    }
+}
+
+/// An enumerated value which is written as a text of an element or an attribute.
+trait XmlText {
+    fn xml_text(self) -> &'static str;
+}
+
+macro_rules! impl_xml_text {
+    ($ty:ty, $($variant:ident => $text:expr,)*) => {
+        impl XmlText for $ty {
+            fn xml_text(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $text,)*
+                }
+            }
+        }
+    };
+}
+
+impl_xml_text! {NameSpace,
+    Standard => "Standard",
+    Custom => "Custom",
+}
+
+impl_xml_text! {MergePriority,
+    High => "1",
+    Mid => "0",
+    Low => "-1",
+}
+
+impl_xml_text! {Visibility,
+    Beginner => "Beginner",
+    Expert => "Expert",
+    Guru => "Guru",
+    Invisible => "Invisible",
+}
+
+impl_xml_text! {AccessMode,
+    RO => "RO",
+    WO => "WO",
+    RW => "RW",
+}
+
+impl_xml_text! {IntegerRepresentation,
+    Linear => "Linear",
+    Logarithmic => "Logarithmic",
+    Boolean => "Boolean",
+    PureNumber => "PureNumber",
+    HexNumber => "HexNumber",
+    IpV4Address => "IPV4Address",
+    MacAddress => "MACAddress",
+}
+
+impl_xml_text! {FloatRepresentation,
+    Linear => "Linear",
+    Logarithmic => "Logarithmic",
+    PureNumber => "PureNumber",
+}
+
+impl_xml_text! {Slope,
+    Increasing => "Increasing",
+    Decreasing => "Decreasing",
+    Varying => "Varying",
+    Automatic => "Automatic",
+}
+
+impl_xml_text! {DisplayNotation,
+    Automatic => "Automatic",
+    Fixed => "Fixed",
+    Scientific => "Scientific",
+}
+
+impl_xml_text! {StandardNameSpace,
+    None => "None",
+    IIDC => "IIDC",
+    GEV => "GEV",
+    CL => "CL",
+    USB => "USB",
+}
+
+impl_xml_text! {CachingMode,
+    WriteThrough => "WriteThrough",
+    WriteAround => "WriteAround",
+    NoCache => "NoCache",
+}
+
+impl_xml_text! {Endianness,
+    LE => "LittleEndian",
+    BE => "BigEndian",
+}
+
+impl_xml_text! {Sign,
+    Signed => "Signed",
+    Unsigned => "Unsigned",
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        builder::GenApiBuilder,
+        store::{DefaultNodeStore, NodeStore, ValueStore},
+    };
+
+    use super::*;
+
+    const XML: &str = r#"
+        <RegisterDescription
+          ModelName="CameleonModel"
+          VendorName="CameleonVendor"
+          ToolTip="Tooltip &amp; description"
+          StandardNameSpace="None"
+          SchemaMajorVersion="1"
+          SchemaMinorVersion="1"
+          SchemaSubMinorVersion="0"
+          MajorVersion="1"
+          MinorVersion="2"
+          SubMinorVersion="3"
+          ProductGuid="01234567-0123-0123-0123-0123456789ab"
+          VersionGuid="76543210-3210-3210-3210-ba9876543210"
+          xmlns="http://www.genicam.org/GenApi/Version_1_1">
+
+            <Category Name="Root" NameSpace="Standard">
+                <pFeature>Width</pFeature>
+                <pFeature>Gain</pFeature>
+                <pFeature>PixelFormat</pFeature>
+            </Category>
+
+            <Integer Name="Width" NameSpace="Standard">
+                <Visibility>Expert</Visibility>
+                <pValue>WidthReg</pValue>
+                <Min>16</Min>
+                <pMax>SensorWidth</pMax>
+                <Inc>4</Inc>
+            </Integer>
+
+            <IntReg Name="WidthReg">
+                <Address>0x1000</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <pPort>Device</pPort>
+                <Endianess>BigEndian</Endianess>
+            </IntReg>
+
+            <Integer Name="SensorWidth">
+                <ImposedAccessMode>RO</ImposedAccessMode>
+                <Value>1280</Value>
+            </Integer>
+
+            <Float Name="Gain">
+                <Value>1.5</Value>
+                <Min>-INF</Min>
+                <Max>24</Max>
+                <Unit>dB</Unit>
+                <DisplayPrecision>2</DisplayPrecision>
+            </Float>
+
+            <Enumeration Name="PixelFormat">
+                <EnumEntry Name="Mono8">
+                    <Value>1</Value>
+                </EnumEntry>
+                <EnumEntry Name="Mono16">
+                    <Value>2</Value>
+                    <NumericValue>16</NumericValue>
+                </EnumEntry>
+                <Value>2</Value>
+            </Enumeration>
+
+            <IntSwissKnife Name="DoubleWidth">
+                <pVariable Name="W">Width</pVariable>
+                <Constant Name="C">2</Constant>
+                <Formula>W * C &lt; 0 ? 0 : W * C</Formula>
+            </IntSwissKnife>
+
+            <Port Name="Device" NameSpace="Standard">
+            </Port>
+        </RegisterDescription>
+        "#;
+
+    #[test]
+    fn test_round_trip() {
+        let (reg_desc, node_store, value_ctxt) = GenApiBuilder::<DefaultNodeStore>::default()
+            .build(&XML)
+            .unwrap();
+        let xml = write(&reg_desc, &node_store, &value_ctxt.value_store);
+
+        let (reg_desc2, node_store2, value_ctxt2) = GenApiBuilder::<DefaultNodeStore>::default()
+            .build(&xml)
+            .unwrap();
+        assert_eq!(reg_desc2.model_name(), "CameleonModel");
+        assert_eq!(reg_desc2.tooltip(), Some("Tooltip & description"));
+        assert_eq!(reg_desc2.minor_version(), 2);
+
+        let node_id = |name| node_store2.id_by_name(name).unwrap();
+        let node = |name| node_store2.node(node_id(name));
+
+        match node("Root") {
+            NodeData::Category(node) => {
+                assert_eq!(node.attr_base.name_space, NameSpace::Standard);
+                assert_eq!(node.p_features.len(), 3);
+            }
+            _ => panic!(),
+        }
+
+        match node("Width") {
+            NodeData::Integer(node) => {
+                assert_eq!(node.elem_base.visibility, Visibility::Expert);
+                assert_eq!(
+                    node.value_kind.p_value().unwrap().p_value(),
+                    node_id("WidthReg")
+                );
+                let min = node.min.imm().unwrap();
+                assert_eq!(value_ctxt2.value_store.integer_value(min), Some(16));
+                assert_eq!(node.max.pnode(), Some(node_id("SensorWidth")));
+                assert_eq!(node.inc, ImmOrPNode::Imm(4));
+            }
+            _ => panic!(),
+        }
+
+        match node("WidthReg") {
+            NodeData::IntReg(node) => {
+                assert!(matches!(
+                    node.register_base.address_kinds[0],
+                    AddressKind::Address(ImmOrPNode::Imm(0x1000))
+                ));
+                assert_eq!(node.register_base.access_mode, AccessMode::RW);
+                assert_eq!(node.register_base.p_port, node_id("Device"));
+                assert_eq!(node.endianness, Endianness::BE);
+            }
+            _ => panic!(),
+        }
+
+        match node("Gain") {
+            NodeData::Float(node) => {
+                let min = node.min.imm().unwrap();
+                assert_eq!(
+                    value_ctxt2.value_store.float_value(min),
+                    Some(f64::NEG_INFINITY)
+                );
+                let value = node.value_kind.imm().unwrap();
+                assert_eq!(value_ctxt2.value_store.float_value(value), Some(1.5));
+                assert_eq!(node.unit.as_deref(), Some("dB"));
+                assert_eq!(node.display_precision, 2);
+            }
+            _ => panic!(),
+        }
+
+        match node("PixelFormat") {
+            NodeData::Enumeration(node) => {
+                assert_eq!(node.entries.len(), 2);
+                let entry = node.entries[1].expect_enum_entry(&node_store2).unwrap();
+                assert_eq!(entry.symbolic, "Mono16");
+                assert_eq!(entry.value, 2);
+                assert_eq!(entry.numeric_value, Some(16.0));
+            }
+            _ => panic!(),
+        }
+
+        match node("DoubleWidth") {
+            NodeData::IntSwissKnife(node) => {
+                assert_eq!(node.p_variables[0].value, node_id("Width"));
+                assert_eq!(node.constants[0].value, 2);
+                assert_eq!(node.formula.to_string(), "W * C < 0 ? 0 : W * C");
+            }
+            _ => panic!(),
+        }
+
+        // Writing the parsed XML again yields the identical XML.
+        assert_eq!(
+            write(&reg_desc2, &node_store2, &value_ctxt2.value_store),
+            xml
+        );
+    }
+
+    #[test]
+    fn test_round_trip_extensions() {
+        let xml = r#"
+            <RegisterDescription
+              ModelName="CameleonModel"
+              VendorName="CameleonVendor"
+              StandardNameSpace="None"
+              SchemaMajorVersion="1"
+              SchemaMinorVersion="1"
+              SchemaSubMinorVersion="0"
+              MajorVersion="1"
+              MinorVersion="0"
+              SubMinorVersion="0"
+              ProductGuid="01234567-0123-0123-0123-0123456789ab"
+              VersionGuid="76543210-3210-3210-3210-ba9876543210"
+              xmlns="http://www.genicam.org/GenApi/Version_1_1"
+              xmlns:vendor="http://example.com/vendor"
+              vendor:Revision="3">
+
+                <Integer Name="Width" vendor:Group="Image">
+                    <vendor:Info Level="2">Sensor width</vendor:Info>
+                    <Value>640</Value>
+                </Integer>
+
+                <vendor:Global>Global setting</vendor:Global>
+            </RegisterDescription>
+            "#;
+
+        let (reg_desc, node_store, value_ctxt) = GenApiBuilder::<DefaultNodeStore>::default()
+            .tolerant()
+            .build(&xml)
+            .unwrap();
+        let written = write(&reg_desc, &node_store, &value_ctxt.value_store);
+
+        let (reg_desc2, node_store2, value_ctxt2) = GenApiBuilder::<DefaultNodeStore>::default()
+            .tolerant()
+            .build(&written)
+            .unwrap();
+        assert_eq!(reg_desc2.extensions(), reg_desc.extensions());
+        assert_eq!(
+            reg_desc2.extensions().attributes(),
+            &[("vendor:Revision".to_string(), "3".to_string())]
+        );
+        assert_eq!(reg_desc2.extensions().elements()[0].name(), "vendor:Global");
+
+        let width = node_store2.node(node_store2.id_by_name("Width").unwrap());
+        let extensions = width.node_base().extensions();
+        assert_eq!(
+            extensions.attributes(),
+            &[("vendor:Group".to_string(), "Image".to_string())]
+        );
+        assert_eq!(
+            extensions.elements()[0].source(),
+            r#"<vendor:Info Level="2">Sensor width</vendor:Info>"#
+        );
+
+        assert_eq!(
+            write(&reg_desc2, &node_store2, &value_ctxt2.value_store),
+            written
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape(r#"<a & "b">"#), "&lt;a &amp; &quot;b&quot;&gt;");
+    }
+}
@@ -0,0 +1,83 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{
+    env,
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+fn main() {
+    println!("cargo:rerun-if-changed=src/ffi");
+
+    let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap();
+    let target_env = env::var("CARGO_CFG_TARGET_ENV").unwrap();
+    let target_arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap();
+
+    match target_os.as_str() {
+        "windows" if target_env == "msvc" && target_arch == "x86" => export_undecorated_names(),
+        "linux" => set_soname(),
+        _ => {}
+    }
+}
+
+/// `__stdcall` functions are exported with decorated names like `_GCInitLib@0` on 32 bit Windows,
+/// but consumers look up undecorated names. A module definition file makes the linker export
+/// them as is.
+fn export_undecorated_names() {
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+
+    let mut def = String::from("EXPORTS\n");
+    for name in exported_functions(&crate_dir.join("src").join("ffi")) {
+        writeln!(def, "    {}", name).unwrap();
+    }
+
+    let def_path = out_dir.join("cameleon_gentl.def");
+    fs::write(&def_path, def).expect("failed to write module definition file");
+    println!("cargo:rustc-cdylib-link-arg=/DEF:{}", def_path.display());
+}
+
+/// Collects names of functions defined with `gentl_api!`.
+fn exported_functions(ffi_dir: &Path) -> Vec<String> {
+    let mut names = vec![];
+    for entry in fs::read_dir(ffi_dir).unwrap() {
+        let src = fs::read_to_string(entry.unwrap().path()).unwrap();
+        for line in src.lines() {
+            let line = line.trim_start();
+            let line = line.strip_prefix("no_assert ").unwrap_or(line);
+            if let Some(sig) = line.strip_prefix("pub fn ") {
+                let name: String = sig
+                    .chars()
+                    .take_while(char::is_ascii_alphanumeric)
+                    .collect();
+                // All `GenTL` functions are in PascalCase.
+                if name.starts_with(|c: char| c.is_ascii_uppercase()) {
+                    names.push(name);
+                }
+            }
+        }
+    }
+
+    names.sort();
+    names
+}
+
+/// Versioned soname so that incompatible producers can be installed side by side.
+fn set_soname() {
+    let major = env::var("CARGO_PKG_VERSION_MAJOR").unwrap();
+    let minor = env::var("CARGO_PKG_VERSION_MINOR").unwrap();
+    // Follow cargo's compatibility rule, minor versions are incompatible before 1.0.0.
+    let version = if major == "0" {
+        format!("{}.{}", major, minor)
+    } else {
+        major
+    };
+
+    println!(
+        "cargo:rustc-cdylib-link-arg=-Wl,-soname,libcameleon_gentl.so.{}",
+        version
+    );
+}
@@ -0,0 +1,1377 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{ops::Deref, sync::Mutex};
+
+use cameleon::payload::PayloadType;
+
+use crate::imp::{
+    self,
+    data_stream::{
+        Buffer, BufferId, BufferState, FlushOperation, GenDcPart, PartKind, BUFFER_ALIGNMENT,
+    },
+    event::EventType,
+};
+
+use super::{
+    bool8_t, copy_info, device::DEV_HANDLE, event, GenTlError, GenTlResult, ModuleHandle, GC_ERROR,
+    GENTL_INFINITE, INFO_DATATYPE,
+};
+
+pub(super) type DS_HANDLE = *mut libc::c_void;
+pub(super) type BUFFER_HANDLE = *mut libc::c_void;
+
+#[repr(C)]
+pub struct DS_BUFFER_INFO_STACKED {
+    iInfoCmd: BUFFER_INFO_CMD,
+    iType: INFO_DATATYPE,
+    pBuffer: *mut libc::c_void,
+    iSize: libc::size_t,
+    iResult: GC_ERROR,
+}
+
+#[repr(C)]
+pub struct DS_BUFFER_PART_INFO_STACKED {
+    iPartIndex: u32,
+    iInfoCmd: BUFFER_PART_INFO_CMD,
+    iType: INFO_DATATYPE,
+    pBuffer: *mut libc::c_void,
+    iSize: libc::size_t,
+    iResult: GC_ERROR,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct SINGLE_CHUNK_DATA {
+    ChunkID: u64,
+    ChunkOffset: libc::ptrdiff_t,
+    ChunkLength: libc::size_t,
+}
+
+#[derive(Clone, Copy)]
+pub(super) struct DataStreamModuleRef<'a> {
+    inner: &'a Mutex<imp::data_stream::DataStreamModule>,
+    /// Index of the data stream in the parent device.
+    index: usize,
+    parent_dev: DEV_HANDLE,
+}
+
+impl<'a> DataStreamModuleRef<'a> {
+    pub(super) fn new(
+        inner: &'a Mutex<imp::data_stream::DataStreamModule>,
+        index: usize,
+        parent_dev: DEV_HANDLE,
+    ) -> Self {
+        Self {
+            inner,
+            index,
+            parent_dev,
+        }
+    }
+}
+
+impl<'a> Deref for DataStreamModuleRef<'a> {
+    type Target = Mutex<imp::data_stream::DataStreamModule>;
+
+    fn deref(&self) -> &Self::Target {
+        self.inner
+    }
+}
+
+/// Converts [`BufferId`] to the handle passed to the consumer.
+/// The handle is offset by one so that a valid handle never be null.
+pub(super) fn buffer_handle(id: BufferId) -> BUFFER_HANDLE {
+    (id.as_raw() + 1) as BUFFER_HANDLE
+}
+
+fn buffer_id(handle: BUFFER_HANDLE) -> GenTlResult<BufferId> {
+    if handle.is_null() {
+        Err(GenTlError::InvalidHandle)
+    } else {
+        Ok(BufferId::new(handle as usize - 1))
+    }
+}
+
+newtype_enum! {
+    pub enum STREAM_INFO_CMD {
+        /// Unique ID of the data stream.
+        STREAM_INFO_ID = 0,
+
+        /// Number of delivered buffers since last acquisition start.
+        STREAM_INFO_NUM_DELIVERED = 1,
+
+        /// Number of lost frames due to queue underrun.
+        STREAM_INFO_NUM_UNDERRUN = 2,
+
+        /// Number of announced buffers.
+        STREAM_INFO_NUM_ANNOUNCED = 3,
+
+        /// Number of buffers in the input pool.
+        STREAM_INFO_NUM_QUEUED = 4,
+
+        /// Number of buffers in the output queue.
+        STREAM_INFO_NUM_AWAIT_DELIVERY = 5,
+
+        /// Number of frames started in the acquisition engine.
+        STREAM_INFO_NUM_STARTED = 6,
+
+        /// Size of the expected data in bytes.
+        STREAM_INFO_PAYLOAD_SIZE = 7,
+
+        /// Flag indicating whether the acquisition engine is started or not.
+        STREAM_INFO_IS_GRABBING = 8,
+
+        /// Flag that indicated that this data stream defines a payload size independent from the
+        /// remote device.
+        STREAM_INFO_DEFINES_PAYLOADSIZE = 9,
+
+        /// Transport layer technology that is supported.
+        STREAM_INFO_TLTYPE = 10,
+
+        /// Max number of chunks in a buffer, if known.
+        STREAM_INFO_NUM_CHUNKS_MAX = 11,
+
+        /// Min number of buffers to announce before acq can start, if known.
+        STREAM_INFO_BUF_ANNOUNCE_MIN = 12,
+
+        /// Buffer alignment in bytes.
+        STREAM_INFO_BUF_ALIGNMENT = 13,
+
+        /// Flow table with stream channel IDs of the flows used by the stream.
+        STREAM_INFO_FLOW_TABLE = 14,
+
+        /// GenDC descriptor prefetched from the device.
+        STREAM_INFO_GENDC_PREFETCH_DESCRIPTOR = 15,
+
+        /// Starting value for GenTL Producer custom IDs.
+        STREAM_INFO_CUSTOM_ID = 1000,
+
+        /// Number of frames dropped due to transfer errors since last acquisition start.
+        STREAM_INFO_CUSTOM_NUM_DROPPED = 1001,
+
+        /// Number of frames delivered with missing data since last acquisition start.
+        STREAM_INFO_CUSTOM_NUM_INCOMPLETE = 1002,
+
+        /// Number of bytes received per second, averaged over the last second.
+        STREAM_INFO_CUSTOM_THROUGHPUT = 1003,
+    }
+}
+
+newtype_enum! {
+    pub enum BUFFER_INFO_CMD {
+        /// Base address of the buffer memory.
+        BUFFER_INFO_BASE = 0,
+
+        /// Size of the buffer in bytes.
+        BUFFER_INFO_SIZE = 1,
+
+        /// Private data pointer of the GenTL Consumer.
+        BUFFER_INFO_USER_PTR = 2,
+
+        /// Timestamp the buffer was acquired.
+        BUFFER_INFO_TIMESTAMP = 3,
+
+        /// Flag to indicate that the buffer contains new data since the last call.
+        BUFFER_INFO_NEW_DATA = 4,
+
+        /// Flag to indicate if the buffer is in the input pool or output queue.
+        BUFFER_INFO_IS_QUEUED = 5,
+
+        /// Flag to indicate that the buffer is currently being filled with data.
+        BUFFER_INFO_IS_ACQUIRING = 6,
+
+        /// Flag to indicate that a buffer was filled but an error occurred during that process.
+        BUFFER_INFO_IS_INCOMPLETE = 7,
+
+        /// Transport layer technology that is supported.
+        BUFFER_INFO_TLTYPE = 8,
+
+        /// Number of bytes written into the buffer last time it has been filled.
+        BUFFER_INFO_SIZE_FILLED = 9,
+
+        /// Width of the data in the buffer in number of pixels.
+        BUFFER_INFO_WIDTH = 10,
+
+        /// Height of the data in the buffer in number of pixels.
+        BUFFER_INFO_HEIGHT = 11,
+
+        /// XOffset of the data in the buffer in number of pixels from the image origin.
+        BUFFER_INFO_XOFFSET = 12,
+
+        /// YOffset of the data in the buffer in number of lines from the image origin.
+        BUFFER_INFO_YOFFSET = 13,
+
+        /// XPadding of the data in the buffer in number of bytes.
+        BUFFER_INFO_XPADDING = 14,
+
+        /// YPadding of the data in the buffer in number of bytes.
+        BUFFER_INFO_YPADDING = 15,
+
+        /// A sequentially incremented number of the frame.
+        BUFFER_INFO_FRAMEID = 16,
+
+        /// Flag to indicate if the current data in the buffer contains image data.
+        BUFFER_INFO_IMAGEPRESENT = 17,
+
+        /// Offset of the image data from the beginning of the delivered buffer in bytes.
+        BUFFER_INFO_IMAGEOFFSET = 18,
+
+        /// Payload type of the data.
+        BUFFER_INFO_PAYLOADTYPE = 19,
+
+        /// Pixel format of the data.
+        BUFFER_INFO_PIXELFORMAT = 20,
+
+        /// Pixel format namespace of the `BUFFER_INFO_PIXELFORMAT`.
+        BUFFER_INFO_PIXELFORMAT_NAMESPACE = 21,
+
+        /// The number of lines in the current buffer as delivered by the transport mechanism.
+        BUFFER_INFO_DELIVERED_IMAGEHEIGHT = 22,
+
+        /// The number of bytes of chunk data in the current buffer as delivered by the transport
+        /// mechanism.
+        BUFFER_INFO_DELIVERED_CHUNKPAYLOADSIZE = 23,
+
+        /// The chunk layout ID of the chunk data.
+        BUFFER_INFO_CHUNKLAYOUTID = 24,
+
+        /// The file name of the file data.
+        BUFFER_INFO_FILENAME = 25,
+
+        /// Endianness of the multi-byte pixel data in the buffer.
+        BUFFER_INFO_PIXEL_ENDIANNESS = 26,
+
+        /// Size of the data intended to the written to the buffer last time it has been filled.
+        BUFFER_INFO_DATA_SIZE = 27,
+
+        /// Timestamp the buffer was acquired in units of nanoseconds.
+        BUFFER_INFO_TIMESTAMP_NS = 28,
+
+        /// Flag to indicate if the data is larger than the buffer.
+        BUFFER_INFO_DATA_LARGER_THAN_BUFFER = 29,
+
+        /// Flag to indicate if the data contain chunk data.
+        BUFFER_INFO_CONTAINS_CHUNKDATA = 30,
+
+        /// Flag to indicate if the buffer is composed of multiple segments.
+        BUFFER_INFO_IS_COMPOSITE = 31,
+
+        /// Starting value for GenTL Producer custom IDs.
+        BUFFER_INFO_CUSTOM_ID = 1000,
+
+        /// Timestamp of the host when the buffer was filled in units of nanoseconds since the
+        /// UNIX epoch.
+        BUFFER_INFO_CUSTOM_HOST_TIMESTAMP_NS = 1001,
+
+        /// Container descriptor at the head of a GenDC payload.
+        BUFFER_INFO_CUSTOM_GENDC_DESCRIPTOR = 1002,
+    }
+}
+
+newtype_enum! {
+    pub enum PAYLOADTYPE_INFO_IDS {
+        /// The payload type is unknown.
+        PAYLOAD_TYPE_UNKNOWN = 0,
+
+        /// The buffer contains image data.
+        PAYLOAD_TYPE_IMAGE = 1,
+
+        /// The buffer contains raw data.
+        PAYLOAD_TYPE_RAW_DATA = 2,
+
+        /// The buffer contains file data.
+        PAYLOAD_TYPE_FILE = 3,
+
+        /// The buffer contains chunk data, the first chunk may be an image.
+        PAYLOAD_TYPE_EXTENDED_CHUNK = 4,
+
+        /// The buffer contains JPEG data.
+        PAYLOAD_TYPE_JPEG = 5,
+
+        /// The buffer contains JPEG 2000 data.
+        PAYLOAD_TYPE_JPEG2000 = 6,
+
+        /// The buffer contains H.264 data.
+        PAYLOAD_TYPE_H264 = 7,
+
+        /// The buffer contains chunk data only.
+        PAYLOAD_TYPE_CHUNK_ONLY = 8,
+
+        /// The buffer contains device specific data.
+        PAYLOAD_TYPE_DEVICE_SPECIFIC = 9,
+
+        /// The buffer contains multi-part data.
+        PAYLOAD_TYPE_MULTI_PART = 10,
+
+        /// The buffer contains GenDC data.
+        PAYLOAD_TYPE_GENDC = 11,
+    }
+}
+
+impl From<Option<PayloadType>> for PAYLOADTYPE_INFO_IDS {
+    fn from(payload_type: Option<PayloadType>) -> Self {
+        match payload_type {
+            Some(PayloadType::Image) => Self::PAYLOAD_TYPE_IMAGE,
+            Some(PayloadType::ImageExtendedChunk) => Self::PAYLOAD_TYPE_EXTENDED_CHUNK,
+            Some(PayloadType::Chunk) => Self::PAYLOAD_TYPE_CHUNK_ONLY,
+            Some(PayloadType::GenDC) => Self::PAYLOAD_TYPE_GENDC,
+            None => Self::PAYLOAD_TYPE_UNKNOWN,
+        }
+    }
+}
+
+newtype_enum! {
+    pub enum PIXELFORMAT_NAMESPACE_IDS {
+        /// The pixel format namespace is unknown.
+        PIXELFORMAT_NAMESPACE_UNKNOWN = 0,
+
+        /// GigE Vision 1.x pixel format.
+        PIXELFORMAT_NAMESPACE_GEV = 1,
+
+        /// IIDC 1394 pixel format.
+        PIXELFORMAT_NAMESPACE_IIDC = 2,
+
+        /// PFNC 16 bit pixel format.
+        PIXELFORMAT_NAMESPACE_PFNC_16BIT = 3,
+
+        /// PFNC 32 bit pixel format.
+        PIXELFORMAT_NAMESPACE_PFNC_32BIT = 4,
+    }
+}
+
+newtype_enum! {
+    pub enum PIXELENDIANNESS_IDS {
+        /// Endianness of the pixel data is unknown.
+        PIXELENDIANNESS_UNKNOWN = 0,
+
+        /// Pixel data is stored in little endian.
+        PIXELENDIANNESS_LITTLE = 1,
+
+        /// Pixel data is stored in big endian.
+        PIXELENDIANNESS_BIG = 2,
+    }
+}
+
+newtype_enum! {
+    pub enum ACQ_START_FLAGS {
+        ACQ_START_FLAGS_DEFAULT = 0,
+    }
+}
+
+newtype_enum! {
+    pub enum ACQ_STOP_FLAGS {
+        /// Stop the acquisition engine when the currently running tasks like filling a buffer are
+        /// completed.
+        ACQ_STOP_FLAGS_DEFAULT = 0,
+
+        /// Stop the acquisition engine immediately and leave buffers currently being filled in the
+        /// input pool.
+        ACQ_STOP_FLAGS_KILL = 1,
+    }
+}
+
+newtype_enum! {
+    pub enum ACQ_QUEUE_TYPE {
+        /// Flushes the input pool to the output queue.
+        ACQ_QUEUE_INPUT_TO_OUTPUT = 0,
+
+        /// Discards all buffers in the output queue.
+        ACQ_QUEUE_OUTPUT_DISCARD = 1,
+
+        /// Puts all buffers in the input pool.
+        ACQ_QUEUE_ALL_TO_INPUT = 2,
+
+        /// Puts all buffers that are not in the input pool or the output queue in the input pool.
+        ACQ_QUEUE_UNQUEUED_TO_INPUT = 3,
+
+        /// Discards all buffers in the input pool and output queue.
+        ACQ_QUEUE_ALL_DISCARD = 4,
+    }
+}
+
+impl std::convert::TryFrom<ACQ_QUEUE_TYPE> for FlushOperation {
+    type Error = GenTlError;
+
+    fn try_from(value: ACQ_QUEUE_TYPE) -> GenTlResult<Self> {
+        match value {
+            ACQ_QUEUE_TYPE::ACQ_QUEUE_INPUT_TO_OUTPUT => Ok(Self::InputToOutput),
+            ACQ_QUEUE_TYPE::ACQ_QUEUE_OUTPUT_DISCARD => Ok(Self::OutputDiscard),
+            ACQ_QUEUE_TYPE::ACQ_QUEUE_ALL_TO_INPUT => Ok(Self::AllToInput),
+            ACQ_QUEUE_TYPE::ACQ_QUEUE_UNQUEUED_TO_INPUT => Ok(Self::UnqueuedToInput),
+            ACQ_QUEUE_TYPE::ACQ_QUEUE_ALL_DISCARD => Ok(Self::AllDiscard),
+            _ => Err(GenTlError::InvalidParameter),
+        }
+    }
+}
+
+gentl_api! {
+    pub fn DSClose(hDataStream: DS_HANDLE) -> GenTlResult<()> {
+        let mut handle = unsafe { ModuleHandle::from_raw_manually_drop(hDataStream)? };
+        let ds_handle = handle.data_stream()?;
+        let dev_handle = unsafe { ModuleHandle::from_raw(ds_handle.parent_dev)? };
+        let dev = dev_handle.device()?;
+
+        // The parent device is locked first to keep the lock order.
+        let mut dev_guard = dev.lock().unwrap();
+        let mut ds_guard = ds_handle.lock().unwrap();
+        if ds_guard.is_grabbing() {
+            ds_guard.stop_acquisition()?;
+            dev_guard.stop_streaming(ds_handle.index)?;
+        }
+        drop(dev_guard);
+
+        // Release event handles, events are unregistered in closing the module.
+        for event_type in &[EventType::NewBuffer, EventType::Error] {
+            event::release_event_handle(&ds_guard.event(*event_type)?)?;
+        }
+        // Close the data stream module.
+        ds_guard.close()?;
+        drop(ds_guard);
+        // Drop its handle.
+        unsafe {
+            std::mem::ManuallyDrop::drop(&mut handle);
+        }
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn DSGetInfo(
+        hDataStream: DS_HANDLE,
+        iInfoCmd: STREAM_INFO_CMD,
+        piType: *mut INFO_DATATYPE,
+        pBuffer: *mut libc::c_void,
+        piSize: *mut libc::size_t,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hDataStream)? };
+        let ds_guard = handle.data_stream()?.inner.lock().unwrap();
+
+        let info_data_type = match iInfoCmd {
+            STREAM_INFO_CMD::STREAM_INFO_ID => copy_info(ds_guard.id(), pBuffer, piSize),
+
+            STREAM_INFO_CMD::STREAM_INFO_NUM_DELIVERED => {
+                copy_info(ds_guard.num_delivered(), pBuffer, piSize)
+            }
+
+            STREAM_INFO_CMD::STREAM_INFO_NUM_UNDERRUN => {
+                copy_info(ds_guard.num_underrun(), pBuffer, piSize)
+            }
+
+            STREAM_INFO_CMD::STREAM_INFO_NUM_ANNOUNCED => {
+                copy_info(ds_guard.num_announced(), pBuffer, piSize)
+            }
+
+            STREAM_INFO_CMD::STREAM_INFO_NUM_QUEUED => {
+                copy_info(ds_guard.num_queued(), pBuffer, piSize)
+            }
+
+            STREAM_INFO_CMD::STREAM_INFO_NUM_AWAIT_DELIVERY => {
+                copy_info(ds_guard.num_await_delivery(), pBuffer, piSize)
+            }
+
+            STREAM_INFO_CMD::STREAM_INFO_IS_GRABBING => {
+                copy_info(bool8_t::from(ds_guard.is_grabbing()), pBuffer, piSize)
+            }
+
+            // Payload size is defined by the remote device.
+            STREAM_INFO_CMD::STREAM_INFO_DEFINES_PAYLOADSIZE => {
+                copy_info(bool8_t::false_(), pBuffer, piSize)
+            }
+
+            STREAM_INFO_CMD::STREAM_INFO_TLTYPE => copy_info(ds_guard.tl_type(), pBuffer, piSize),
+
+            STREAM_INFO_CMD::STREAM_INFO_BUF_ANNOUNCE_MIN => copy_info(1_usize, pBuffer, piSize),
+
+            STREAM_INFO_CMD::STREAM_INFO_BUF_ALIGNMENT => {
+                copy_info(BUFFER_ALIGNMENT, pBuffer, piSize)
+            }
+
+            // All flows of a GenDC container are delivered through a single stream channel, and
+            // descriptors are only available in each payload, see
+            // `BUFFER_INFO_CUSTOM_GENDC_DESCRIPTOR`.
+            STREAM_INFO_CMD::STREAM_INFO_FLOW_TABLE
+            | STREAM_INFO_CMD::STREAM_INFO_GENDC_PREFETCH_DESCRIPTOR => {
+                Err(GenTlError::NotAvailable)
+            }
+
+            STREAM_INFO_CMD::STREAM_INFO_CUSTOM_NUM_DROPPED => {
+                copy_info(ds_guard.num_dropped(), pBuffer, piSize)
+            }
+
+            STREAM_INFO_CMD::STREAM_INFO_CUSTOM_NUM_INCOMPLETE => {
+                copy_info(ds_guard.num_incomplete(), pBuffer, piSize)
+            }
+
+            STREAM_INFO_CMD::STREAM_INFO_CUSTOM_THROUGHPUT => {
+                copy_info(ds_guard.throughput(), pBuffer, piSize)
+            }
+
+            _ => Err(GenTlError::InvalidParameter),
+        }?;
+
+        unsafe {
+            *piType = info_data_type;
+        }
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn DSAnnounceBuffer(
+        hDataStream: DS_HANDLE,
+        pBuffer: *mut libc::c_void,
+        iSize: libc::size_t,
+        pPrivate: *mut libc::c_void,
+        phBuffer: *mut BUFFER_HANDLE,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hDataStream)? };
+        let ds_handle = handle.data_stream()?;
+
+        // The consumer guarantees that the memory is valid until the buffer is revoked.
+        let id = unsafe {
+            ds_handle
+                .lock()
+                .unwrap()
+                .announce_buffer(pBuffer.cast::<u8>(), iSize, pPrivate as usize)?
+        };
+        unsafe {
+            *phBuffer = buffer_handle(id);
+        }
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn DSAllocAndAnnounceBuffer(
+        hDataStream: DS_HANDLE,
+        iBufferSize: libc::size_t,
+        pPrivate: *mut libc::c_void,
+        phBuffer: *mut BUFFER_HANDLE,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hDataStream)? };
+        let ds_handle = handle.data_stream()?;
+
+        let id = ds_handle
+            .lock()
+            .unwrap()
+            .alloc_and_announce_buffer(iBufferSize, pPrivate as usize)?;
+        unsafe {
+            *phBuffer = buffer_handle(id);
+        }
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn DSRevokeBuffer(
+        hDataStream: DS_HANDLE,
+        hBuffer: BUFFER_HANDLE,
+        pBuffer: *mut *mut libc::c_void,
+        pPrivate: *mut *mut libc::c_void,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hDataStream)? };
+        let ds_handle = handle.data_stream()?;
+
+        let buffer = ds_handle.lock().unwrap().revoke_buffer(buffer_id(hBuffer)?)?;
+        unsafe {
+            // Memory allocated by the producer is released along with `buffer`.
+            if !pBuffer.is_null() {
+                *pBuffer = buffer
+                    .user_memory()
+                    .map_or(std::ptr::null_mut(), |ptr| ptr.cast::<libc::c_void>());
+            }
+            if !pPrivate.is_null() {
+                *pPrivate = buffer.user_ptr() as *mut libc::c_void;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn DSQueueBuffer(hDataStream: DS_HANDLE, hBuffer: BUFFER_HANDLE) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hDataStream)? };
+        let ds_handle = handle.data_stream()?;
+
+        let res = ds_handle.lock().unwrap().queue_buffer(buffer_id(hBuffer)?);
+        res
+    }
+}
+
+gentl_api! {
+    pub fn DSFlushQueue(hDataStream: DS_HANDLE, iOperation: ACQ_QUEUE_TYPE) -> GenTlResult<()> {
+        use std::convert::TryInto;
+
+        let handle = unsafe { ModuleHandle::from_raw(hDataStream)? };
+        let ds_handle = handle.data_stream()?;
+
+        let res = ds_handle.lock().unwrap().flush_queue(iOperation.try_into()?);
+        res
+    }
+}
+
+gentl_api! {
+    pub fn DSStartAcquisition(
+        hDataStream: DS_HANDLE,
+        iStartFlags: ACQ_START_FLAGS,
+        iNumToAcquire: u64,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hDataStream)? };
+        let ds_handle = handle.data_stream()?;
+        let dev_handle = unsafe { ModuleHandle::from_raw(ds_handle.parent_dev)? };
+        let dev = dev_handle.device()?;
+
+        // Both modules are kept locked so that concurrent calls can't start the acquisition
+        // twice. The parent device is locked first to keep the lock order.
+        let mut dev_guard = dev.lock().unwrap();
+        let mut ds_guard = ds_handle.lock().unwrap();
+        if ds_guard.is_grabbing() {
+            return Err(GenTlError::ResourceInUse);
+        }
+
+        let receiver = dev_guard.start_streaming(ds_handle.index)?;
+        let num_to_acquire = if iNumToAcquire == GENTL_INFINITE {
+            None
+        } else {
+            Some(iNumToAcquire)
+        };
+        let res = ds_guard.start_acquisition(receiver, num_to_acquire);
+        if res.is_err() {
+            dev_guard.stop_streaming(ds_handle.index).ok();
+        }
+
+        res
+    }
+}
+
+gentl_api! {
+    pub fn DSStopAcquisition(hDataStream: DS_HANDLE, iStopFlags: ACQ_STOP_FLAGS) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hDataStream)? };
+        let ds_handle = handle.data_stream()?;
+        let dev_handle = unsafe { ModuleHandle::from_raw(ds_handle.parent_dev)? };
+        let dev = dev_handle.device()?;
+
+        // The parent device is locked first to keep the lock order.
+        let mut dev_guard = dev.lock().unwrap();
+        // Buffers are filled only by the acquisition thread, so both flags are handled in the same
+        // way.
+        ds_handle.lock().unwrap().stop_acquisition()?;
+        dev_guard.stop_streaming(ds_handle.index)
+    }
+}
+
+gentl_api! {
+    pub fn DSGetBufferID(
+        hDataStream: DS_HANDLE,
+        iIndex: u32,
+        phBuffer: *mut BUFFER_HANDLE,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hDataStream)? };
+        let ds_handle = handle.data_stream()?;
+
+        let id = ds_handle.lock().unwrap().buffer_id(iIndex as usize)?;
+        unsafe {
+            *phBuffer = buffer_handle(id);
+        }
+
+        Ok(())
+    }
+}
+
+fn ds_get_buffer_info(
+    ds: &imp::data_stream::DataStreamModule,
+    hBuffer: BUFFER_HANDLE,
+    iInfoCmd: BUFFER_INFO_CMD,
+    piType: *mut INFO_DATATYPE,
+    pBuffer: *mut libc::c_void,
+    piSize: *mut libc::size_t,
+) -> GenTlResult<()> {
+    let is_grabbing = ds.is_grabbing();
+    let tl_type = ds.tl_type();
+
+    let info_data_type = ds.with_buffer(buffer_id(hBuffer)?, |buffer| {
+        let info = buffer.info();
+        // Image related info isn't available unless the payload contains an image.
+        let image_info = || info.image_info.as_ref().ok_or(GenTlError::NotAvailable);
+        match iInfoCmd {
+            BUFFER_INFO_CMD::BUFFER_INFO_BASE => {
+                copy_info(buffer.data().as_ptr() as *mut libc::c_void, pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_SIZE => copy_info(buffer.data().len(), pBuffer, piSize),
+
+            BUFFER_INFO_CMD::BUFFER_INFO_USER_PTR => {
+                copy_info(buffer.user_ptr() as *mut libc::c_void, pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_TIMESTAMP | BUFFER_INFO_CMD::BUFFER_INFO_TIMESTAMP_NS => {
+                copy_info(info.timestamp.as_nanos() as u64, pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_NEW_DATA => {
+                copy_info(bool8_t::from(info.new_data), pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_IS_QUEUED => copy_info(
+                bool8_t::from(buffer.state() == BufferState::Queued),
+                pBuffer,
+                piSize,
+            ),
+
+            BUFFER_INFO_CMD::BUFFER_INFO_IS_ACQUIRING => copy_info(
+                bool8_t::from(is_grabbing && buffer.state() == BufferState::Queued),
+                pBuffer,
+                piSize,
+            ),
+
+            BUFFER_INFO_CMD::BUFFER_INFO_IS_INCOMPLETE => {
+                copy_info(bool8_t::from(info.is_incomplete), pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_TLTYPE => copy_info(tl_type, pBuffer, piSize),
+
+            BUFFER_INFO_CMD::BUFFER_INFO_SIZE_FILLED => {
+                copy_info(info.size_filled, pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_FRAMEID => copy_info(info.frame_id, pBuffer, piSize),
+
+            BUFFER_INFO_CMD::BUFFER_INFO_WIDTH => copy_info(image_info()?.width, pBuffer, piSize),
+
+            BUFFER_INFO_CMD::BUFFER_INFO_HEIGHT => copy_info(image_info()?.height, pBuffer, piSize),
+
+            BUFFER_INFO_CMD::BUFFER_INFO_XOFFSET => {
+                copy_info(image_info()?.x_offset, pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_YOFFSET => {
+                copy_info(image_info()?.y_offset, pBuffer, piSize)
+            }
+
+            // `ImageInfo` doesn't carry line padding, image lines are delivered without padding.
+            BUFFER_INFO_CMD::BUFFER_INFO_XPADDING => {
+                image_info()?;
+                copy_info(0_usize, pBuffer, piSize)
+            }
+
+            // Bytes following the image data in the payload.
+            BUFFER_INFO_CMD::BUFFER_INFO_YPADDING => {
+                let image_info = image_info()?;
+                let padding = if info.payload_type == Some(PayloadType::Image) {
+                    info.data_size.saturating_sub(image_info.image_size)
+                } else {
+                    0
+                };
+                copy_info(padding, pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_IMAGEPRESENT => {
+                copy_info(bool8_t::from(info.image_info.is_some()), pBuffer, piSize)
+            }
+
+            // Image data is always placed at the head of the payload.
+            BUFFER_INFO_CMD::BUFFER_INFO_IMAGEOFFSET => {
+                image_info()?;
+                copy_info(0_usize, pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_PAYLOADTYPE => {
+                let payload_type = PAYLOADTYPE_INFO_IDS::from(info.payload_type);
+                copy_info(payload_type.0 as usize, pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_PIXELFORMAT => copy_info(
+                u64::from(u32::from(image_info()?.pixel_format)),
+                pBuffer,
+                piSize,
+            ),
+
+            BUFFER_INFO_CMD::BUFFER_INFO_PIXELFORMAT_NAMESPACE => {
+                image_info()?;
+                let namespace = PIXELFORMAT_NAMESPACE_IDS::PIXELFORMAT_NAMESPACE_PFNC_32BIT;
+                copy_info(namespace.0 as u64, pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_DELIVERED_IMAGEHEIGHT => {
+                let height = info
+                    .delivered_image_height()
+                    .ok_or(GenTlError::NotAvailable)?;
+                copy_info(height, pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_DELIVERED_CHUNKPAYLOADSIZE => {
+                let size = if info.contains_chunk_data() {
+                    info.size_filled
+                } else {
+                    0
+                };
+                copy_info(size, pBuffer, piSize)
+            }
+
+            // Neither chunk layout ID nor file payloads are delivered by cameleon.
+            BUFFER_INFO_CMD::BUFFER_INFO_CHUNKLAYOUTID | BUFFER_INFO_CMD::BUFFER_INFO_FILENAME => {
+                Err(GenTlError::NotAvailable)
+            }
+
+            // Both U3V and GEV transfer multi-byte pixels in little endian.
+            BUFFER_INFO_CMD::BUFFER_INFO_PIXEL_ENDIANNESS => {
+                image_info()?;
+                let endianness = PIXELENDIANNESS_IDS::PIXELENDIANNESS_LITTLE;
+                copy_info(endianness.0, pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_DATA_SIZE => copy_info(info.data_size, pBuffer, piSize),
+
+            BUFFER_INFO_CMD::BUFFER_INFO_DATA_LARGER_THAN_BUFFER => copy_info(
+                bool8_t::from(info.data_size > buffer.data().len()),
+                pBuffer,
+                piSize,
+            ),
+
+            BUFFER_INFO_CMD::BUFFER_INFO_CONTAINS_CHUNKDATA => {
+                copy_info(bool8_t::from(info.contains_chunk_data()), pBuffer, piSize)
+            }
+
+            // Composite buffers can't be announced, see `DSAnnounceCompositeBuffer`.
+            BUFFER_INFO_CMD::BUFFER_INFO_IS_COMPOSITE => {
+                copy_info(bool8_t::false_(), pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_CUSTOM_HOST_TIMESTAMP_NS => {
+                copy_info(info.host_timestamp.as_nanos() as u64, pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_CUSTOM_GENDC_DESCRIPTOR => {
+                let descriptor_size = buffer.gendc_container()?.descriptor_size;
+                copy_info(&buffer.data()[..descriptor_size], pBuffer, piSize)
+            }
+
+            _ => Err(GenTlError::InvalidParameter),
+        }
+    })??;
+
+    unsafe {
+        *piType = info_data_type;
+    }
+
+    Ok(())
+}
+
+gentl_api! {
+    pub fn DSGetBufferInfo(
+        hDataStream: DS_HANDLE,
+        hBuffer: BUFFER_HANDLE,
+        iInfoCmd: BUFFER_INFO_CMD,
+        piType: *mut INFO_DATATYPE,
+        pBuffer: *mut libc::c_void,
+        piSize: *mut libc::size_t,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hDataStream)? };
+        let ds_guard = handle.data_stream()?.inner.lock().unwrap();
+
+        ds_get_buffer_info(&ds_guard, hBuffer, iInfoCmd, piType, pBuffer, piSize)
+    }
+}
+
+gentl_api! {
+    pub fn DSGetBufferInfoStacked(
+        hDataStream: DS_HANDLE,
+        hBuffer: BUFFER_HANDLE,
+        pInfoStacked: *mut DS_BUFFER_INFO_STACKED,
+        iNumInfos: libc::size_t,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hDataStream)? };
+        let ds_guard = handle.data_stream()?.inner.lock().unwrap();
+        let infos = unsafe { std::slice::from_raw_parts_mut(pInfoStacked, iNumInfos) };
+
+        // Each entry has its own result, the function itself fails only if any of them fails.
+        let mut result = Ok(());
+        for info in infos {
+            let res = ds_get_buffer_info(
+                &ds_guard,
+                hBuffer,
+                info.iInfoCmd,
+                &mut info.iType,
+                info.pBuffer,
+                &mut info.iSize,
+            );
+            info.iResult = (&res).into();
+            if result.is_ok() {
+                result = res;
+            }
+        }
+
+        result
+    }
+}
+
+gentl_api! {
+    pub fn DSGetParentDev(hDataStream: DS_HANDLE, phDevice: *mut DEV_HANDLE) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hDataStream)? };
+        let ds_handle = handle.data_stream()?;
+
+        unsafe {
+            *phDevice = ds_handle.parent_dev;
+        }
+
+        Ok(())
+    }
+}
+
+newtype_enum! {
+    pub enum BUFFER_PART_INFO_CMD {
+        /// Base address of the buffer part memory.
+        BUFFER_PART_INFO_BASE = 0,
+
+        /// Size of the buffer part in bytes.
+        BUFFER_PART_INFO_DATA_SIZE = 1,
+
+        /// Type of the data in given part.
+        BUFFER_PART_INFO_DATA_TYPE = 2,
+
+        /// Format of the individual items (such as pixels) in the buffer part.
+        BUFFER_PART_INFO_DATA_FORMAT = 3,
+
+        /// Allows interpretation of `BUFFER_PART_INFO_DATA_FORMAT`.
+        BUFFER_PART_INFO_DATA_FORMAT_NAMESPACE = 4,
+
+        /// Width of data in the buffer part in pixels.
+        BUFFER_PART_INFO_WIDTH = 5,
+
+        /// Expected height of data in the buffer part in pixels.
+        BUFFER_PART_INFO_HEIGHT = 6,
+
+        /// Horizontal offset of data in the buffer part in pixels.
+        BUFFER_PART_INFO_XOFFSET = 7,
+
+        /// Vertical offset of data in the buffer part in pixels.
+        BUFFER_PART_INFO_YOFFSET = 8,
+
+        /// Horizontal padding of data in the buffer part in pixels.
+        BUFFER_PART_INFO_XPADDING = 9,
+
+        /// Identifier allowing to group data parts belonging to the same source.
+        BUFFER_PART_INFO_SOURCE_ID = 10,
+
+        /// Height of data delivered in the buffer part in pixels.
+        BUFFER_PART_INFO_DELIVERED_IMAGEHEIGHT = 11,
+
+        /// Identifier allowing to group data parts belonging to the same region.
+        BUFFER_PART_INFO_REGION_ID = 12,
+
+        /// Identifier allowing to group data parts belonging to the same purpose.
+        BUFFER_PART_INFO_DATA_PURPOSE_ID = 13,
+
+        /// Starting value for GenTL Producer custom IDs.
+        BUFFER_PART_INFO_CUSTOM_ID = 1000,
+
+        /// ID of the GenDC flow the buffer part is transferred through.
+        BUFFER_PART_INFO_CUSTOM_FLOW_ID = 1001,
+
+        /// Offset of the buffer part data in the GenDC flow in bytes.
+        BUFFER_PART_INFO_CUSTOM_FLOW_OFFSET = 1002,
+    }
+}
+
+newtype_enum! {
+    pub enum PARTDATATYPE_IDS {
+        /// The data type is unknown.
+        PART_DATATYPE_UNKNOWN = 0,
+
+        /// Uncompressed 2D image.
+        PART_DATATYPE_2D_IMAGE = 1,
+
+        /// Single color plane of a planar 2D image consisting of 2 planes.
+        PART_DATATYPE_2D_PLANE_BIPLANAR = 2,
+
+        /// Single color plane of a planar 2D image consisting of 3 planes.
+        PART_DATATYPE_2D_PLANE_TRIPLANAR = 3,
+
+        /// Single color plane of a planar 2D image consisting of 4 planes.
+        PART_DATATYPE_2D_PLANE_QUADPLANAR = 4,
+
+        /// 3D image.
+        PART_DATATYPE_3D_IMAGE = 5,
+
+        /// Single plane of a planar 3D image consisting of 2 planes.
+        PART_DATATYPE_3D_PLANE_BIPLANAR = 6,
+
+        /// Single plane of a planar 3D image consisting of 3 planes.
+        PART_DATATYPE_3D_PLANE_TRIPLANAR = 7,
+
+        /// Single plane of a planar 3D image consisting of 4 planes.
+        PART_DATATYPE_3D_PLANE_QUADPLANAR = 8,
+
+        /// Confidence of individual pixel values.
+        PART_DATATYPE_CONFIDENCE_MAP = 9,
+
+        /// Chunk data type.
+        PART_DATATYPE_CHUNKDATA = 10,
+
+        /// JPEG compressed image.
+        PART_DATATYPE_JPEG = 11,
+
+        /// JPEG 2000 compressed image.
+        PART_DATATYPE_JPEG2000 = 12,
+    }
+}
+
+impl From<PartKind> for PARTDATATYPE_IDS {
+    fn from(kind: PartKind) -> Self {
+        match kind {
+            PartKind::Data2D => Self::PART_DATATYPE_2D_IMAGE,
+            PartKind::Metadata => Self::PART_DATATYPE_CHUNKDATA,
+            PartKind::Jpeg => Self::PART_DATATYPE_JPEG,
+            PartKind::Jpeg2000 => Self::PART_DATATYPE_JPEG2000,
+            PartKind::Data1D | PartKind::H264 | PartKind::Unknown => Self::PART_DATATYPE_UNKNOWN,
+        }
+    }
+}
+
+gentl_api! {
+    pub fn DSGetNumBufferParts(
+        hDataStream: DS_HANDLE,
+        hBuffer: BUFFER_HANDLE,
+        piNumParts: *mut u32,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hDataStream)? };
+        let ds_guard = handle.data_stream()?.inner.lock().unwrap();
+
+        // Only GenDC payloads are split into parts, and GenTL specifies that the number of parts
+        // is zero for the other buffers.
+        let num_parts = ds_guard.with_buffer(buffer_id(hBuffer)?, |buffer| {
+            buffer
+                .gendc_container()
+                .map_or(0, |container| container.parts.len())
+        })?;
+        unsafe {
+            *piNumParts = num_parts as u32;
+        }
+
+        Ok(())
+    }
+}
+
+fn ds_get_buffer_part_info(
+    ds: &imp::data_stream::DataStreamModule,
+    hBuffer: BUFFER_HANDLE,
+    iPartIndex: u32,
+    iInfoCmd: BUFFER_PART_INFO_CMD,
+    piType: *mut INFO_DATATYPE,
+    pBuffer: *mut libc::c_void,
+    piSize: *mut libc::size_t,
+) -> GenTlResult<()> {
+    let info_data_type = ds.with_buffer(buffer_id(hBuffer)?, |buffer| {
+        // See `DSGetNumBufferParts`, any part index is out of range unless the buffer is filled
+        // with a GenDC container.
+        let part = buffer
+            .gendc_container()
+            .ok()
+            .and_then(|container| container.parts.get(iPartIndex as usize).copied())
+            .ok_or(GenTlError::InvalidIndex)?;
+        // Dimension related info is available only for 2D parts.
+        let dims = || part.dims.ok_or(GenTlError::NotAvailable);
+
+        match iInfoCmd {
+            // The part may be located beyond the buffer if the payload didn't fit to the buffer.
+            BUFFER_PART_INFO_CMD::BUFFER_PART_INFO_BASE => {
+                let data = buffer
+                    .data()
+                    .get(part.offset..)
+                    .ok_or(GenTlError::NotAvailable)?;
+                copy_info(data.as_ptr() as *mut libc::c_void, pBuffer, piSize)
+            }
+
+            BUFFER_PART_INFO_CMD::BUFFER_PART_INFO_DATA_SIZE => {
+                copy_info(part.len, pBuffer, piSize)
+            }
+
+            BUFFER_PART_INFO_CMD::BUFFER_PART_INFO_DATA_TYPE => copy_info(
+                PARTDATATYPE_IDS::from(part.kind).0 as usize,
+                pBuffer,
+                piSize,
+            ),
+
+            BUFFER_PART_INFO_CMD::BUFFER_PART_INFO_DATA_FORMAT => {
+                copy_info(u64::from(part.format), pBuffer, piSize)
+            }
+
+            BUFFER_PART_INFO_CMD::BUFFER_PART_INFO_DATA_FORMAT_NAMESPACE => {
+                let namespace = PIXELFORMAT_NAMESPACE_IDS::PIXELFORMAT_NAMESPACE_PFNC_32BIT;
+                copy_info(namespace.0 as u64, pBuffer, piSize)
+            }
+
+            BUFFER_PART_INFO_CMD::BUFFER_PART_INFO_WIDTH => {
+                copy_info(dims()?.width as usize, pBuffer, piSize)
+            }
+
+            BUFFER_PART_INFO_CMD::BUFFER_PART_INFO_HEIGHT => {
+                copy_info(dims()?.height as usize, pBuffer, piSize)
+            }
+
+            BUFFER_PART_INFO_CMD::BUFFER_PART_INFO_XOFFSET => {
+                copy_info(part.x_offset as usize, pBuffer, piSize)
+            }
+
+            BUFFER_PART_INFO_CMD::BUFFER_PART_INFO_YOFFSET => {
+                copy_info(part.y_offset as usize, pBuffer, piSize)
+            }
+
+            BUFFER_PART_INFO_CMD::BUFFER_PART_INFO_XPADDING => {
+                copy_info(dims()?.x_padding as usize, pBuffer, piSize)
+            }
+
+            BUFFER_PART_INFO_CMD::BUFFER_PART_INFO_SOURCE_ID => {
+                copy_info(u64::from(part.source_id), pBuffer, piSize)
+            }
+
+            BUFFER_PART_INFO_CMD::BUFFER_PART_INFO_DELIVERED_IMAGEHEIGHT => {
+                let height = delivered_part_height(&part, buffer.info().size_filled)
+                    .ok_or(GenTlError::NotAvailable)?;
+                copy_info(height, pBuffer, piSize)
+            }
+
+            BUFFER_PART_INFO_CMD::BUFFER_PART_INFO_REGION_ID => {
+                copy_info(u64::from(part.region_id), pBuffer, piSize)
+            }
+
+            BUFFER_PART_INFO_CMD::BUFFER_PART_INFO_DATA_PURPOSE_ID => {
+                copy_info(part.type_id, pBuffer, piSize)
+            }
+
+            BUFFER_PART_INFO_CMD::BUFFER_PART_INFO_CUSTOM_FLOW_ID => {
+                copy_info(u32::from(part.flow_id), pBuffer, piSize)
+            }
+
+            BUFFER_PART_INFO_CMD::BUFFER_PART_INFO_CUSTOM_FLOW_OFFSET => {
+                copy_info(part.flow_offset, pBuffer, piSize)
+            }
+
+            _ => Err(GenTlError::InvalidParameter),
+        }
+    })??;
+
+    unsafe {
+        *piType = info_data_type;
+    }
+
+    Ok(())
+}
+
+/// Number of lines of a 2D part written to the buffer, `None` if the part isn't two dimensional.
+fn delivered_part_height(part: &GenDcPart, size_filled: usize) -> Option<usize> {
+    let dims = part.dims?;
+    let height = dims.height as usize;
+    let filled = size_filled.saturating_sub(part.offset).min(part.len);
+    if filled == part.len || height == 0 {
+        return Some(height);
+    }
+
+    let line_len = part.len / height;
+    if line_len == 0 {
+        Some(0)
+    } else {
+        Some((filled / line_len).min(height))
+    }
+}
+
+gentl_api! {
+    pub fn DSGetBufferPartInfo(
+        hDataStream: DS_HANDLE,
+        hBuffer: BUFFER_HANDLE,
+        iPartIndex: u32,
+        iInfoCmd: BUFFER_PART_INFO_CMD,
+        piType: *mut INFO_DATATYPE,
+        pBuffer: *mut libc::c_void,
+        piSize: *mut libc::size_t,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hDataStream)? };
+        let ds_guard = handle.data_stream()?.inner.lock().unwrap();
+
+        ds_get_buffer_part_info(&ds_guard, hBuffer, iPartIndex, iInfoCmd, piType, pBuffer, piSize)
+    }
+}
+
+gentl_api! {
+    pub fn DSGetBufferPartInfoStacked(
+        hDataStream: DS_HANDLE,
+        hBuffer: BUFFER_HANDLE,
+        pInfoStacked: *mut DS_BUFFER_PART_INFO_STACKED,
+        iNumInfos: libc::size_t,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hDataStream)? };
+        let ds_guard = handle.data_stream()?.inner.lock().unwrap();
+        let infos = unsafe { std::slice::from_raw_parts_mut(pInfoStacked, iNumInfos) };
+
+        let mut result = Ok(());
+        for info in infos {
+            let res = ds_get_buffer_part_info(
+                &ds_guard,
+                hBuffer,
+                info.iPartIndex,
+                info.iInfoCmd,
+                &mut info.iType,
+                info.pBuffer,
+                &mut info.iSize,
+            );
+            info.iResult = (&res).into();
+            if result.is_ok() {
+                result = res;
+            }
+        }
+
+        result
+    }
+}
+
+gentl_api! {
+    pub fn DSGetBufferChunkData(
+        hDataStream: DS_HANDLE,
+        hBuffer: BUFFER_HANDLE,
+        pChunkData: *mut SINGLE_CHUNK_DATA,
+        piNumChunks: *mut libc::size_t,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hDataStream)? };
+        let ds_guard = handle.data_stream()?.inner.lock().unwrap();
+        let chunks = ds_guard.with_buffer(buffer_id(hBuffer)?, Buffer::chunks)??;
+
+        // Only the number of chunks is requested if `pChunkData` is null.
+        if pChunkData.is_null() {
+            unsafe {
+                *piNumChunks = chunks.len();
+            }
+            return Ok(());
+        }
+
+        unsafe {
+            if *piNumChunks < chunks.len() {
+                *piNumChunks = chunks.len();
+                return Err(GenTlError::BufferTooSmall);
+            }
+
+            for (i, chunk) in chunks.iter().enumerate() {
+                *pChunkData.add(i) = SINGLE_CHUNK_DATA {
+                    ChunkID: chunk.id as u64,
+                    ChunkOffset: chunk.offset as libc::ptrdiff_t,
+                    ChunkLength: chunk.len,
+                };
+            }
+            *piNumChunks = chunks.len();
+        }
+
+        Ok(())
+    }
+}
+
+newtype_enum! {
+    pub enum FLOW_INFO_CMD {
+        /// Size of the flow in bytes.
+        FLOW_INFO_SIZE = 0,
+    }
+}
+
+newtype_enum! {
+    pub enum SEGMENT_INFO_CMD {
+        /// Base address of the segment memory.
+        SEGMENT_INFO_BASE = 0,
+
+        /// Size of the segment in bytes.
+        SEGMENT_INFO_SIZE = 1,
+    }
+}
+
+gentl_api! {
+    pub fn DSGetNumFlows(hDataStream: DS_HANDLE, piNumFlows: *mut u32) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hDataStream)? };
+        let ds_guard = handle.data_stream()?.inner.lock().unwrap();
+        if !ds_guard.is_opened() {
+            return Err(GenTlError::NotInitialized);
+        }
+
+        // Payloads are delivered through a single stream channel, so the stream never has
+        // multiple flows.
+        unsafe {
+            *piNumFlows = 0;
+        }
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn DSGetFlowInfo(
+        hDataStream: DS_HANDLE,
+        iFlowIndex: u32,
+        iInfoCmd: FLOW_INFO_CMD,
+        piType: *mut INFO_DATATYPE,
+        pBuffer: *mut libc::c_void,
+        piSize: *mut libc::size_t,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hDataStream)? };
+        handle.data_stream()?;
+
+        // See `DSGetNumFlows`, any flow index is out of range.
+        Err(GenTlError::InvalidIndex)
+    }
+}
+
+gentl_api! {
+    pub fn DSGetNumBufferSegments(
+        hDataStream: DS_HANDLE,
+        hBuffer: BUFFER_HANDLE,
+        piNumSegments: *mut u32,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hDataStream)? };
+        let ds_guard = handle.data_stream()?.inner.lock().unwrap();
+
+        // Only non-composite buffers can be announced and they have no segments.
+        ds_guard.with_buffer(buffer_id(hBuffer)?, |_| ())?;
+        unsafe {
+            *piNumSegments = 0;
+        }
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn DSGetBufferSegmentInfo(
+        hDataStream: DS_HANDLE,
+        hBuffer: BUFFER_HANDLE,
+        iSegmentIndex: u32,
+        iInfoCmd: SEGMENT_INFO_CMD,
+        piType: *mut INFO_DATATYPE,
+        pBuffer: *mut libc::c_void,
+        piSize: *mut libc::size_t,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hDataStream)? };
+        let ds_guard = handle.data_stream()?.inner.lock().unwrap();
+
+        // See `DSGetNumBufferSegments`, any segment index is out of range.
+        ds_guard.with_buffer(buffer_id(hBuffer)?, |_| ())?;
+        Err(GenTlError::InvalidIndex)
+    }
+}
+
+gentl_api! {
+    pub fn DSAnnounceCompositeBuffer(
+        hDataStream: DS_HANDLE,
+        iNumSegments: libc::size_t,
+        ppSegments: *mut *mut libc::c_void,
+        piSizes: *mut libc::size_t,
+        pPrivate: *mut libc::c_void,
+        phBuffer: *mut BUFFER_HANDLE,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hDataStream)? };
+        handle.data_stream()?;
+
+        // Composite buffers are meaningful only for multi-flow streams, which aren't supported.
+        Err(GenTlError::NotImplemented)
+    }
+}
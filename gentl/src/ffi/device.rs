@@ -2,15 +2,18 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use std::{convert::TryInto, ops::Deref, sync::Mutex};
+use std::{convert::TryInto, ffi::CStr, ops::Deref, sync::Mutex};
+
+use crate::imp::event::EventType;
 
 use super::{
-    copy_info, imp, interface, GenTlError, GenTlResult, ModuleHandle, GC_ERROR, INFO_DATATYPE,
+    copy_info,
+    data_stream::{DataStreamModuleRef, DS_HANDLE},
+    event, imp, interface, CopyTo, GenTlError, GenTlResult, ModuleHandle, GC_ERROR, INFO_DATATYPE,
 };
 
 pub(super) type DEV_HANDLE = *mut libc::c_void;
 pub(super) type PORT_HANDLE = *mut libc::c_void;
-pub(super) type DS_HANDLE = *mut libc::c_void;
 
 #[derive(Clone, Copy)]
 pub(super) struct DeviceModuleRef<'a> {
@@ -163,8 +166,20 @@ gentl_api! {
         let mut handle = unsafe { ModuleHandle::from_raw_manually_drop(hDevice)? };
         let dev_handle = handle.device()?;
 
+        let mut dev_guard = dev_handle.lock().unwrap();
+        // Unregister events so that consumers waiting for them are aborted, then release their
+        // handles.
+        for event_type in &[EventType::FeatureInvalidate, EventType::RemoteDevice] {
+            if let Ok(queue) = dev_guard.event(*event_type) {
+                if queue.is_registered() {
+                    queue.unregister()?;
+                }
+                event::release_event_handle(&queue)?;
+            }
+        }
         // Close the device module.
-        dev_handle.lock().unwrap().close()?;
+        dev_guard.close()?;
+        drop(dev_guard);
 
         // Drop remote device handle.
         // This seems weired but there is no function to close remote device in GenTL API.
@@ -190,7 +205,7 @@ gentl_api! {
         pBuffer: *mut libc::c_void,
         piSize: *mut libc::size_t,
     ) -> GenTlResult<()> {
-        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hDevice)? };
+        let handle = unsafe { ModuleHandle::from_raw(hDevice)? };
         let dev_handle = handle.device()?;
 
         dev_get_info(dev_handle, iInfoCmd, piType, pBuffer, piSize)
@@ -204,20 +219,35 @@ gentl_api! {
         sDataStreamID: *mut libc::c_char,
         piSize: *mut libc::size_t,
     ) -> GenTlResult<()> {
-        todo!()
+        let handle = unsafe { ModuleHandle::from_raw(hDevice)? };
+        let dev_handle = handle.device()?;
+
+        let dev_guard = dev_handle.lock().unwrap();
+        let ds = dev_guard.data_stream(iIndex as usize)?;
+        ds.lock().unwrap().id().copy_to(sDataStreamID, piSize)?;
+
+        Ok(())
     }
 }
 
 gentl_api! {
     pub fn DevGetNumDataStreams(hDevice: DEV_HANDLE, piNumDataStreams: *mut u32) -> GenTlResult<()>
     {
-        todo!()
+        let handle = unsafe { ModuleHandle::from_raw(hDevice)? };
+        let dev_handle = handle.device()?;
+
+        let num = dev_handle.lock().unwrap().num_data_streams()?;
+        unsafe {
+            *piNumDataStreams = num as u32;
+        }
+
+        Ok(())
     }
 }
 
 gentl_api! {
     pub fn DevGetPort(hDevice: DEV_HANDLE, phRemoteDevice: *mut PORT_HANDLE) -> GenTlResult<()> {
-        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hDevice)? };
+        let handle = unsafe { ModuleHandle::from_raw(hDevice)? };
         let dev_handle = handle.device()?;
 
         unsafe {
@@ -234,13 +264,32 @@ gentl_api! {
         sDataStreamID: *const ::std::os::raw::c_char,
         phDataStream: *mut DS_HANDLE,
     ) -> GenTlResult<()> {
-        todo!()
+        let handle = unsafe { ModuleHandle::from_raw(hDevice)? };
+        let dev_handle = handle.device()?;
+
+        let dev_guard = dev_handle.lock().unwrap();
+        let id = unsafe { CStr::from_ptr(sDataStreamID) }.to_string_lossy();
+        let (index, ds) = (0..dev_guard.num_data_streams()?)
+            .map(|index| dev_guard.data_stream(index).map(|ds| (index, ds)))
+            .collect::<GenTlResult<Vec<_>>>()?
+            .into_iter()
+            .find(|(_, ds)| ds.lock().unwrap().id() == id)
+            .ok_or_else(|| GenTlError::InvalidId(id.into()))?;
+
+        ds.lock().unwrap().open()?;
+        let ds = DataStreamModuleRef::new(ds, index, hDevice);
+        let ds_handle = Box::new(ModuleHandle::DataStream(ds));
+        unsafe {
+            *phDataStream = ds_handle.into_raw();
+        }
+
+        Ok(())
     }
 }
 
 gentl_api! {
     pub fn DevGetParentIF(hDevice: DEV_HANDLE, phIface: *mut interface::IF_HANDLE) -> GenTlResult<()> {
-        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hDevice)? };
+        let handle = unsafe { ModuleHandle::from_raw(hDevice)? };
         let dev_handle = handle.device()?;
 
         unsafe {
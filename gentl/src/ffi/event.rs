@@ -0,0 +1,442 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::imp::event::{
+    EventData, EventQueue, EventType, MAX_ERROR_MESSAGE_LEN, MAX_FEATURE_NAME_LEN,
+    MAX_REMOTE_DEVICE_EVENT_DATA_LEN,
+};
+
+use super::{
+    copy_info, data_stream, CopyTo, GenTlError, GenTlResult, ModuleHandle, GC_ERROR,
+    GENTL_INFINITE, INFO_DATATYPE,
+};
+
+pub(super) type EVENTSRC_HANDLE = *mut libc::c_void;
+pub(super) type EVENT_HANDLE = *mut libc::c_void;
+
+/// Length of the event ID placed at the head of the data of `EVENT_REMOTE_DEVICE`.
+const REMOTE_DEVICE_EVENT_ID_LEN: usize = std::mem::size_of::<u64>();
+
+/// Length of the error code placed at the head of the data of `EVENT_ERROR`.
+const ERROR_CODE_LEN: usize = std::mem::size_of::<GC_ERROR>();
+
+lazy_static::lazy_static! {
+    /// Handles of registered events, keyed by the address of their queues.
+    /// `GCUnregisterEvent` doesn't take the event handle, so the handle is looked up from here to
+    /// release it.
+    static ref EVENT_HANDLES: Mutex<HashMap<usize, usize>> = Mutex::new(HashMap::new());
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct EVENT_NEW_BUFFER_DATA {
+    BufferHandle: data_stream::BUFFER_HANDLE,
+    pUserPointer: *mut libc::c_void,
+}
+
+newtype_enum! {
+    pub enum EVENT_TYPE {
+        /// Notification on module errors.
+        EVENT_ERROR = 0,
+
+        /// Notification on newly filled buffers.
+        EVENT_NEW_BUFFER = 1,
+
+        /// Notification if a feature was changed by the GenTL Producer library and thus needs to
+        /// be invalidated in the GenICam GenApi instance using the module.
+        EVENT_FEATURE_INVALIDATE = 2,
+
+        /// Notification if the GenTL Producer library wants to manually set a feature in the
+        /// GenICam GenApi instance using the module.
+        EVENT_FEATURE_CHANGE = 3,
+
+        /// Notification if the GenTL Producer wants to inform the GenICam GenApi instance of the
+        /// remote device that a GenApi compatible event was fired.
+        EVENT_REMOTE_DEVICE = 4,
+
+        /// Notification if the GenTL Producer wants to inform the GenICam GenApi instance of the
+        /// module that a GenApi compatible event was fired.
+        EVENT_MODULE = 5,
+    }
+}
+
+impl std::convert::TryFrom<EVENT_TYPE> for EventType {
+    type Error = GenTlError;
+
+    fn try_from(value: EVENT_TYPE) -> GenTlResult<Self> {
+        match value {
+            EVENT_TYPE::EVENT_ERROR => Ok(Self::Error),
+            EVENT_TYPE::EVENT_NEW_BUFFER => Ok(Self::NewBuffer),
+            EVENT_TYPE::EVENT_FEATURE_INVALIDATE => Ok(Self::FeatureInvalidate),
+            EVENT_TYPE::EVENT_FEATURE_CHANGE => Ok(Self::FeatureChange),
+            EVENT_TYPE::EVENT_REMOTE_DEVICE => Ok(Self::RemoteDevice),
+            EVENT_TYPE::EVENT_MODULE => Ok(Self::Module),
+            _ => Err(GenTlError::InvalidParameter),
+        }
+    }
+}
+
+impl CopyTo for EventType {
+    type Destination = i32;
+
+    fn copy_to(&self, dst: *mut Self::Destination, dst_size: *mut libc::size_t) -> GenTlResult<()> {
+        let val = match self {
+            Self::Error => EVENT_TYPE::EVENT_ERROR,
+            Self::NewBuffer => EVENT_TYPE::EVENT_NEW_BUFFER,
+            Self::FeatureInvalidate => EVENT_TYPE::EVENT_FEATURE_INVALIDATE,
+            Self::FeatureChange => EVENT_TYPE::EVENT_FEATURE_CHANGE,
+            Self::RemoteDevice => EVENT_TYPE::EVENT_REMOTE_DEVICE,
+            Self::Module => EVENT_TYPE::EVENT_MODULE,
+        };
+
+        val.0.copy_to(dst, dst_size)
+    }
+
+    fn info_data_type() -> INFO_DATATYPE {
+        INFO_DATATYPE::INFO_DATATYPE_INT32
+    }
+}
+
+newtype_enum! {
+    pub enum EVENT_INFO_CMD {
+        /// The event type of the event handle.
+        EVENT_EVENT_TYPE = 0,
+
+        /// Number of events in the event data queue.
+        EVENT_NUM_IN_QUEUE = 1,
+
+        /// Number of events that were fired since the creation of the module.
+        EVENT_NUM_FIRED = 2,
+
+        /// Max size of data carried with an event in bytes.
+        EVENT_SIZE_MAX = 3,
+
+        /// Max size of data provided through EventGetDataInfo in bytes.
+        EVENT_INFO_DATA_SIZE_MAX = 4,
+    }
+}
+
+newtype_enum! {
+    pub enum EVENT_DATA_INFO_CMD {
+        /// Event ID.
+        EVENT_DATA_ID = 0,
+
+        /// Event data.
+        EVENT_DATA_VALUE = 1,
+
+        /// Numeric representation of event ID.
+        EVENT_DATA_NUMID = 2,
+    }
+}
+
+/// Max size of the data written by `EventGetData`.
+///
+/// The data of `EVENT_FEATURE_INVALIDATE` is a null terminated feature name, the data of
+/// `EVENT_REMOTE_DEVICE` is the event ID in little endian followed by the event data, and the data
+/// of `EVENT_ERROR` is the error code in little endian followed by a null terminated message.
+fn event_data_size(event_type: EventType) -> GenTlResult<usize> {
+    match event_type {
+        EventType::Error => Ok(ERROR_CODE_LEN + MAX_ERROR_MESSAGE_LEN + 1),
+        EventType::NewBuffer => Ok(std::mem::size_of::<EVENT_NEW_BUFFER_DATA>()),
+        EventType::FeatureInvalidate => Ok(MAX_FEATURE_NAME_LEN + 1),
+        EventType::RemoteDevice => {
+            Ok(REMOTE_DEVICE_EVENT_ID_LEN + MAX_REMOTE_DEVICE_EVENT_DATA_LEN)
+        }
+        _ => Err(GenTlError::NotImplemented),
+    }
+}
+
+/// Max size of the data written by `EventGetDataInfo`.
+fn event_data_info_size(event_type: EventType) -> usize {
+    match event_type {
+        EventType::FeatureInvalidate => MAX_FEATURE_NAME_LEN + 1,
+        // The event data is always longer than the event ID formatted as a hex string.
+        EventType::RemoteDevice => MAX_REMOTE_DEVICE_EVENT_DATA_LEN,
+        EventType::Error => MAX_ERROR_MESSAGE_LEN + 1,
+        _ => 0,
+    }
+}
+
+/// Parses a null terminated string, e.g. the data of `EVENT_FEATURE_INVALIDATE`.
+fn parse_c_str(data: &[u8]) -> GenTlResult<&str> {
+    let len = data
+        .iter()
+        .position(|b| *b == 0)
+        .ok_or(GenTlError::InvalidParameter)?;
+    std::str::from_utf8(&data[..len]).map_err(|_| GenTlError::InvalidParameter)
+}
+
+/// Parses the data of `EVENT_REMOTE_DEVICE` and returns the event ID and the event data.
+fn parse_remote_device_event(data: &[u8]) -> GenTlResult<(u64, &[u8])> {
+    use std::convert::TryInto;
+
+    if data.len() < REMOTE_DEVICE_EVENT_ID_LEN {
+        return Err(GenTlError::InvalidParameter);
+    }
+    let (id, value) = data.split_at(REMOTE_DEVICE_EVENT_ID_LEN);
+    Ok((u64::from_le_bytes(id.try_into().unwrap()), value))
+}
+
+/// Parses the data of `EVENT_ERROR` and returns the error code and the message.
+fn parse_error_event(data: &[u8]) -> GenTlResult<(i32, &str)> {
+    use std::convert::TryInto;
+
+    if data.len() < ERROR_CODE_LEN {
+        return Err(GenTlError::InvalidParameter);
+    }
+    let (code, message) = data.split_at(ERROR_CODE_LEN);
+    let code = i32::from_le_bytes(code.try_into().unwrap());
+    Ok((code, parse_c_str(message)?))
+}
+
+/// Returns the event queue of `event_type` of the module.
+fn event_queue(handle: &ModuleHandle, event_type: EventType) -> GenTlResult<Arc<EventQueue>> {
+    match handle {
+        ModuleHandle::Device(dev) => dev.lock().unwrap().event(event_type),
+        ModuleHandle::DataStream(ds) => ds.lock().unwrap().event(event_type),
+        ModuleHandle::Event(..) => Err(GenTlError::InvalidHandle),
+        _ => Err(GenTlError::NotImplemented),
+    }
+}
+
+fn queue_key(queue: &Arc<EventQueue>) -> usize {
+    Arc::as_ptr(queue) as usize
+}
+
+/// Releases the event handle of `queue` if it's registered through `GCRegisterEvent`.
+pub(super) fn release_event_handle(queue: &Arc<EventQueue>) -> GenTlResult<()> {
+    if let Some(event_handle) = EVENT_HANDLES.lock().unwrap().remove(&queue_key(queue)) {
+        unsafe {
+            let mut event_handle =
+                ModuleHandle::from_raw_manually_drop(event_handle as *mut libc::c_void)?;
+            std::mem::ManuallyDrop::drop(&mut event_handle);
+        }
+    }
+
+    Ok(())
+}
+
+gentl_api! {
+    pub fn GCRegisterEvent(
+        hModule: EVENTSRC_HANDLE,
+        iEventID: EVENT_TYPE,
+        phEvent: *mut EVENT_HANDLE,
+    ) -> GenTlResult<()> {
+        use std::convert::TryInto;
+
+        let handle = unsafe { ModuleHandle::from_raw(hModule)? };
+        let queue = event_queue(handle, iEventID.try_into()?)?;
+
+        queue.register()?;
+        let key = queue_key(&queue);
+        let event_handle = unsafe { Box::new(ModuleHandle::Event(queue)).into_raw() };
+        EVENT_HANDLES
+            .lock()
+            .unwrap()
+            .insert(key, event_handle as usize);
+        unsafe {
+            *phEvent = event_handle;
+        }
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn GCUnregisterEvent(hModule: EVENTSRC_HANDLE, iEventID: EVENT_TYPE) -> GenTlResult<()> {
+        use std::convert::TryInto;
+
+        let handle = unsafe { ModuleHandle::from_raw(hModule)? };
+        let queue = event_queue(handle, iEventID.try_into()?)?;
+
+        queue.unregister()?;
+        release_event_handle(&queue)
+    }
+}
+
+gentl_api! {
+    pub fn EventGetData(
+        hEvent: EVENT_HANDLE,
+        pBuffer: *mut libc::c_void,
+        piSize: *mut libc::size_t,
+        iTimeout: u64,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hEvent)? };
+        let queue = handle.event()?;
+
+        // Check the buffer size before popping the event not to lose it.
+        let size = event_data_size(queue.event_type())?;
+        unsafe {
+            if pBuffer.is_null() || *piSize < size {
+                return Err(GenTlError::BufferTooSmall);
+            }
+        }
+
+        let timeout = if iTimeout == GENTL_INFINITE {
+            None
+        } else {
+            Some(Duration::from_millis(iTimeout))
+        };
+        match queue.wait(timeout)? {
+            EventData::NewBuffer { buffer, user_ptr } => unsafe {
+                pBuffer.cast::<EVENT_NEW_BUFFER_DATA>().write_unaligned(EVENT_NEW_BUFFER_DATA {
+                    BufferHandle: data_stream::buffer_handle(buffer),
+                    pUserPointer: user_ptr as *mut libc::c_void,
+                });
+                *piSize = size;
+                Ok(())
+            },
+
+            EventData::FeatureInvalidate { feature } => {
+                feature.as_str().copy_to(pBuffer.cast(), piSize)
+            }
+
+            EventData::RemoteDevice { event_id, data } => {
+                let mut raw = event_id.to_le_bytes().to_vec();
+                raw.extend_from_slice(&data);
+                raw.as_slice().copy_to(pBuffer.cast(), piSize)
+            }
+
+            EventData::Error { code, message } => {
+                let mut raw = code.to_le_bytes().to_vec();
+                raw.extend_from_slice(message.as_bytes());
+                raw.push(0);
+                raw.as_slice().copy_to(pBuffer.cast(), piSize)
+            }
+        }
+    }
+}
+
+gentl_api! {
+    pub fn EventGetDataInfo(
+        hEvent: EVENT_HANDLE,
+        pInBuffer: *const libc::c_void,
+        iInSize: libc::size_t,
+        iInfoCmd: EVENT_DATA_INFO_CMD,
+        piType: *mut INFO_DATATYPE,
+        pOutBuffer: *mut libc::c_void,
+        piOutSize: *mut libc::size_t,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hEvent)? };
+        let queue = handle.event()?;
+
+        if pInBuffer.is_null() {
+            return Err(GenTlError::InvalidParameter);
+        }
+        let data = unsafe { std::slice::from_raw_parts(pInBuffer.cast::<u8>(), iInSize) };
+
+        let info_data_type = match queue.event_type() {
+            EventType::FeatureInvalidate => {
+                let feature = parse_c_str(data)?;
+                match iInfoCmd {
+                    EVENT_DATA_INFO_CMD::EVENT_DATA_ID => copy_info(feature, pOutBuffer, piOutSize),
+                    EVENT_DATA_INFO_CMD::EVENT_DATA_VALUE
+                    | EVENT_DATA_INFO_CMD::EVENT_DATA_NUMID => Err(GenTlError::NotAvailable),
+                    _ => Err(GenTlError::InvalidParameter),
+                }
+            }
+
+            EventType::RemoteDevice => {
+                let (event_id, value) = parse_remote_device_event(data)?;
+                match iInfoCmd {
+                    // GenApi expects the event ID as a hex string.
+                    EVENT_DATA_INFO_CMD::EVENT_DATA_ID => {
+                        copy_info(format!("{:X}", event_id).as_str(), pOutBuffer, piOutSize)
+                    }
+                    EVENT_DATA_INFO_CMD::EVENT_DATA_VALUE => {
+                        copy_info(value, pOutBuffer, piOutSize)
+                    }
+                    EVENT_DATA_INFO_CMD::EVENT_DATA_NUMID => {
+                        copy_info(event_id, pOutBuffer, piOutSize)
+                    }
+                    _ => Err(GenTlError::InvalidParameter),
+                }
+            }
+
+            EventType::Error => {
+                let (code, message) = parse_error_event(data)?;
+                match iInfoCmd {
+                    EVENT_DATA_INFO_CMD::EVENT_DATA_ID => copy_info(code, pOutBuffer, piOutSize),
+                    EVENT_DATA_INFO_CMD::EVENT_DATA_VALUE => {
+                        copy_info(message, pOutBuffer, piOutSize)
+                    }
+                    EVENT_DATA_INFO_CMD::EVENT_DATA_NUMID => Err(GenTlError::NotAvailable),
+                    _ => Err(GenTlError::InvalidParameter),
+                }
+            }
+
+            // No data info is defined for the other events.
+            _ => Err(GenTlError::NotImplemented),
+        }?;
+
+        unsafe {
+            *piType = info_data_type;
+        }
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn EventGetInfo(
+        hEvent: EVENT_HANDLE,
+        iInfoCmd: EVENT_INFO_CMD,
+        piType: *mut INFO_DATATYPE,
+        pBuffer: *mut libc::c_void,
+        piSize: *mut libc::size_t,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hEvent)? };
+        let queue = handle.event()?;
+
+        let info_data_type = match iInfoCmd {
+            EVENT_INFO_CMD::EVENT_EVENT_TYPE => copy_info(queue.event_type(), pBuffer, piSize),
+
+            EVENT_INFO_CMD::EVENT_NUM_IN_QUEUE => {
+                copy_info(queue.num_in_queue() as u64, pBuffer, piSize)
+            }
+
+            EVENT_INFO_CMD::EVENT_NUM_FIRED => copy_info(queue.num_fired(), pBuffer, piSize),
+
+            EVENT_INFO_CMD::EVENT_SIZE_MAX => {
+                copy_info(event_data_size(queue.event_type())?, pBuffer, piSize)
+            }
+
+            EVENT_INFO_CMD::EVENT_INFO_DATA_SIZE_MAX => {
+                copy_info(event_data_info_size(queue.event_type()), pBuffer, piSize)
+            }
+
+            _ => Err(GenTlError::InvalidParameter),
+        }?;
+
+        unsafe {
+            *piType = info_data_type;
+        }
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn EventFlush(hEvent: EVENT_HANDLE) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hEvent)? };
+        handle.event()?.flush();
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn EventKill(hEvent: EVENT_HANDLE) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hEvent)? };
+        handle.event()?.kill();
+
+        Ok(())
+    }
+}
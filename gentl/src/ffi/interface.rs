@@ -104,7 +104,7 @@ gentl_api! {
         pBuffer: *mut libc::c_void,
         piSize: *mut libc::size_t,
     ) -> GenTlResult<()> {
-        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hIface)? };
+        let handle = unsafe { ModuleHandle::from_raw(hIface)? };
         let iface = handle.interface()?;
 
         if_get_info(iface, iInfoCmd, piType, pBuffer, piSize)
@@ -118,7 +118,7 @@ gentl_api! {
         sIDeviceID: *mut libc::c_char,
         piSize: *mut libc::size_t,
     ) -> GenTlResult<()> {
-        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hIface)? };
+        let handle = unsafe { ModuleHandle::from_raw(hIface)? };
         let iface = handle.interface()?;
 
         let iface_guard = iface.lock().unwrap();
@@ -142,7 +142,7 @@ gentl_api! {
         pBuffer: *mut libc::c_void,
         piSize: *mut libc::size_t,
     ) -> GenTlResult<()> {
-        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hIface)? };
+        let handle = unsafe { ModuleHandle::from_raw(hIface)? };
         let iface = handle.interface()?;
 
         let iface_guard = iface.lock().unwrap();
@@ -155,7 +155,7 @@ gentl_api! {
 
 gentl_api! {
     pub fn IFGetNumDevices(hIface: IF_HANDLE, piNumDevices: *mut u32) -> GenTlResult<()> {
-        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hIface)? };
+        let handle = unsafe { ModuleHandle::from_raw(hIface)? };
         let iface = handle.interface()?;
 
         let device_num = iface.lock().unwrap().devices().len();
@@ -174,7 +174,7 @@ gentl_api! {
         iOpenFlag: device::DEVICE_ACCESS_FLAGS,
         phDevice: *mut device::DEV_HANDLE,
     ) -> GenTlResult<()> {
-        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hIface)? };
+        let handle = unsafe { ModuleHandle::from_raw(hIface)? };
         let iface = handle.interface()?;
 
         let iface_guard = iface.lock().unwrap();
@@ -198,7 +198,7 @@ gentl_api! {
         pbChanged: *mut bool8_t,
         iTimeout: u64,
     ) -> GenTlResult<()> {
-        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hIface)? };
+        let handle = unsafe { ModuleHandle::from_raw(hIface)? };
         let iface = handle.interface()?;
 
         let mut iface_guard = iface.lock().unwrap();
@@ -215,7 +215,7 @@ gentl_api! {
 
 gentl_api! {
     pub fn IFGetParentTL(hIface: IF_HANDLE, phSystem: *mut system::TL_HANDLE) -> GenTlResult<()> {
-        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hIface)? };
+        let handle = unsafe { ModuleHandle::from_raw(hIface)? };
         let iface = handle.interface()?;
 
         unsafe {
@@ -2,13 +2,16 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+/// Defines an exported `GenTL` function.
+/// `GC_CALLTYPE` is `__stdcall` on Windows and the platform default elsewhere, which is what
+/// `extern "system"` means.
 macro_rules! gentl_api {
     (
         pub fn $name:ident($($arg:ident: $ty:ty),*$(,)?) -> GenTlResult<()> $body:tt
     )
     => {
         #[no_mangle]
-        pub extern "C" fn $name($($arg: $ty),*) -> GC_ERROR {
+        pub extern "system" fn $name($($arg: $ty),*) -> GC_ERROR {
             #[inline(always)]
             fn inner($($arg: $ty),*) -> GenTlResult<()> {
                 crate::ffi::assert_lib_initialized()?;
@@ -27,7 +30,7 @@ macro_rules! gentl_api {
     )
     => {
         #[no_mangle]
-        pub extern "C" fn $name($($arg: $ty),*) -> GC_ERROR {
+        pub extern "system" fn $name($($arg: $ty),*) -> GC_ERROR {
             #[inline(always)]
             fn inner($($arg: $ty),*) -> GenTlResult<()> {
                 $body
@@ -5,12 +5,18 @@
 #[macro_use]
 mod macros;
 
+pub mod data_stream;
 pub mod device;
+pub mod event;
 pub mod interface;
 pub mod port;
 pub mod system;
 
-use std::{cell::RefCell, mem::ManuallyDrop, sync::RwLock};
+use std::{
+    cell::RefCell,
+    mem::ManuallyDrop,
+    sync::{Arc, RwLock},
+};
 
 use crate::{imp, GenTlError, GenTlResult};
 
@@ -50,38 +56,7 @@ impl From<bool> for bool8_t {
 
 impl From<&GenTlError> for GC_ERROR {
     fn from(val: &GenTlError) -> Self {
-        use GenTlError::{
-            Abort, AccessDenied, Ambiguous, BufferTooSmall, Busy, Error, InvalidAddress,
-            InvalidBuffer, InvalidHandle, InvalidId, InvalidIndex, InvalidParameter, InvalidValue,
-            Io, NoData, NotAvailable, NotImplemented, NotInitialized, OutOfMemory,
-            ParsingChunkData, ResourceExhausted, ResourceInUse, Timeout,
-        };
-        let code: i32 = match val {
-            Error(..) => -1001,
-            NotInitialized => -1002,
-            NotImplemented => -1003,
-            ResourceInUse => -1004,
-            AccessDenied => -1005,
-            InvalidHandle => -1006,
-            InvalidId(..) => -1007,
-            NoData => -1008,
-            InvalidParameter => -1009,
-            Io(..) => -1010,
-            Timeout => -1011,
-            Abort => -1012,
-            InvalidBuffer => -1013,
-            NotAvailable => -1014,
-            InvalidAddress => -1015,
-            BufferTooSmall => -1016,
-            InvalidIndex => -1017,
-            ParsingChunkData => -1018,
-            InvalidValue(..) => -1019,
-            ResourceExhausted => -1020,
-            OutOfMemory => -1021,
-            Busy => -1022,
-            Ambiguous => -1023,
-        };
-        GC_ERROR(code)
+        GC_ERROR(val.code())
     }
 }
 
@@ -113,11 +88,20 @@ struct LastError {
     err: Option<GenTlError>,
 }
 
+/// Handle of a module passed to the consumer.
+///
+/// The consumer may call functions with the same handle from multiple threads at the same time,
+/// so handles are accessed through shared references and modules are guarded by their own mutexes.
+/// To avoid deadlocks, module mutexes must be locked in the order of
+/// System -> Interface -> Device -> DataStream, and the mutex of a child module must be released
+/// before locking its parent. Event queues are never locked while waiting for another mutex.
 enum ModuleHandle<'a> {
     System(system::SystemModuleRef<'a>),
     Interface(interface::InterfaceModuleRef<'a>),
     Device(device::DeviceModuleRef<'a>),
     RemoteDevice(device::RemoteDeviceRef<'a>),
+    DataStream(data_stream::DataStreamModuleRef<'a>),
+    Event(Arc<imp::event::EventQueue>),
 }
 
 impl<'a> ModuleHandle<'a> {
@@ -142,6 +126,30 @@ impl<'a> ModuleHandle<'a> {
         }
     }
 
+    fn data_stream(&self) -> GenTlResult<data_stream::DataStreamModuleRef<'a>> {
+        match self {
+            ModuleHandle::DataStream(ds) => Ok(*ds),
+            _ => Err(GenTlError::InvalidHandle),
+        }
+    }
+
+    fn event(&self) -> GenTlResult<Arc<imp::event::EventQueue>> {
+        match self {
+            ModuleHandle::Event(event) => Ok(event.clone()),
+            _ => Err(GenTlError::InvalidHandle),
+        }
+    }
+
+    /// Returns a shared reference to the handle.
+    ///
+    /// Use [`ModuleHandle::from_raw_manually_drop`] only when the handle is released.
+    unsafe fn from_raw(raw_handle: *mut libc::c_void) -> GenTlResult<&'a ModuleHandle<'a>> {
+        raw_handle
+            .cast::<ModuleHandle>()
+            .as_ref()
+            .ok_or(GenTlError::InvalidHandle)
+    }
+
     unsafe fn from_raw_manually_drop(
         raw_handle: *mut libc::c_void,
     ) -> GenTlResult<ManuallyDrop<Box<ModuleHandle<'a>>>> {
@@ -407,6 +415,12 @@ impl_copy_to_for_numeric!(i32, INFO_DATATYPE::INFO_DATATYPE_INT32);
 impl_copy_to_for_numeric!(u32, INFO_DATATYPE::INFO_DATATYPE_UINT32);
 impl_copy_to_for_numeric!(i64, INFO_DATATYPE::INFO_DATATYPE_INT64);
 impl_copy_to_for_numeric!(u64, INFO_DATATYPE::INFO_DATATYPE_UINT64);
+impl_copy_to_for_numeric!(usize, INFO_DATATYPE::INFO_DATATYPE_SIZET);
+impl_copy_to_for_numeric!(f64, INFO_DATATYPE::INFO_DATATYPE_FLOAT64);
+impl_copy_to_for_numeric!(*mut libc::c_void, INFO_DATATYPE::INFO_DATATYPE_PTR);
+
+/// Timeout value which means waiting infinitely.
+const GENTL_INFINITE: u64 = 0xFFFF_FFFF_FFFF_FFFF;
 
 fn assert_lib_initialized() -> GenTlResult<()> {
     if *IS_LIB_INITIALIZED.read().unwrap() {
@@ -423,6 +437,7 @@ gentl_api!(
         if *is_init {
             Err(GenTlError::ResourceInUse)
         } else {
+            imp::config::init()?;
             *is_init = true;
             Ok(())
         }
@@ -441,17 +456,6 @@ gentl_api!(
     }
 );
 
-gentl_api!(
-    pub fn CGCGetInfo(
-        _iInfoCmd: i32,
-        _piType: i32,
-        _pBuffer: *mut libc::c_void,
-        _piSize: *mut libc::size_t,
-    ) -> GenTlResult<()> {
-        Err(GenTlError::NotImplemented)
-    }
-);
-
 gentl_api!(
     pub fn GCGetLastError(
         piErrorCode: *mut GC_ERROR,
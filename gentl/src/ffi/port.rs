@@ -58,7 +58,7 @@ newtype_enum! {
 
 macro_rules! with_port {
     ($handle:ident, |$port:ident| $body: tt) => {
-        match &*$handle.as_ref() {
+        match $handle {
             ModuleHandle::System(handle) => {
                 #[allow(unused_mut)]
                 let mut $port = handle.lock().unwrap();
@@ -79,6 +79,13 @@ macro_rules! with_port {
                 let mut $port = handle.lock().unwrap();
                 $body
             }
+
+            ModuleHandle::DataStream(handle) => {
+                let mut $port = handle.lock().unwrap();
+                $body
+            }
+
+            _ => return Err(GenTlError::InvalidHandle),
         }
     };
 }
@@ -91,6 +98,23 @@ pub struct PORT_REGISTER_STACK_ENTRY {
     Size: libc::size_t,
 }
 
+/// File name of the xml on the register, `{vendor}_{model}_{file_version}.{extension}` where
+/// extension is "zip" if compressed else "xml".
+fn register_file_name(xml_info: &imp::port::XmlInfo, port_info: &imp::port::PortInfo) -> String {
+    let extension = match xml_info.compressed {
+        cameleon::genapi::CompressionType::Uncompressed => "xml",
+        cameleon::genapi::CompressionType::Zip => "zip",
+    };
+
+    format!(
+        "{vendor}_{model}_{file_version}.{extension}",
+        vendor = port_info.vendor,
+        model = port_info.model,
+        file_version = xml_info.file_version,
+        extension = extension,
+    )
+}
+
 fn file_location_to_url(xml_info: &imp::port::XmlInfo, port_info: &imp::port::PortInfo) -> String {
     use imp::port::XmlLocation;
     match &xml_info.location {
@@ -100,23 +124,14 @@ fn file_location_to_url(xml_info: &imp::port::XmlInfo, port_info: &imp::port::Po
             //
             // local:{filename}.{extension};{address};{length}[?SchemaVersion={major}.(minor).{subminor}]
             //
-            // filename: {vendor}_{model}_{file_version}.
-            // extension: "zip" if compressed else "xml".
             // address: Start address of the xml, must be expressed in hexademical
             // without prefix.
             // length: Byte length  of the xml, must be expressed in hexademical
             // without prefix.
-            let extension = match xml_info.compressed {
-                cameleon::genapi::CompressionType::Uncompressed => "xml",
-                cameleon::genapi::CompressionType::Zip => "zip",
-            };
             let schema_version = &xml_info.schema_version;
 
-            format!("local:{vendor}_{model}_{file_version}.{extension};{address:X};{size:X}?SchemaVersion={schema_major}.{schema_minor}.{schema_subminor}",
-                            vendor = port_info.vendor,
-                            model = port_info.model,
-                            file_version = xml_info.file_version,
-                            extension = extension,
+            format!("local:{file_name};{address:X};{size:X}?SchemaVersion={schema_major}.{schema_minor}.{schema_subminor}",
+                            file_name = register_file_name(xml_info, port_info),
                             address = address,
                             size = size,
                             schema_major=schema_version.major,
@@ -125,11 +140,15 @@ fn file_location_to_url(xml_info: &imp::port::XmlInfo, port_info: &imp::port::Po
                         )
         }
         XmlLocation::LocalFile(path) => {
-            // file:{filepath}[?SchemaVersion={major}.{minor}.{subminor}]
+            // file:///{filepath}[?SchemaVersion={major}.{minor}.{subminor}]
             let schema_version = &xml_info.schema_version;
+            let file_url = url::Url::from_file_path(path).map_or_else(
+                |_| format!("file:///{}", path.to_string_lossy()),
+                String::from,
+            );
             format!(
-                "file:{filepath}?SchemaVersion={schema_major}.{schema_minor}.{schema_subminor}",
-                filepath = path.to_string_lossy(),
+                "{file_url}?SchemaVersion={schema_major}.{schema_minor}.{schema_subminor}",
+                file_url = file_url,
                 schema_major = schema_version.major,
                 schema_minor = schema_version.minor,
                 schema_subminor = schema_version.patch,
@@ -157,7 +176,7 @@ gentl_api! {
         pBuffer: *mut libc::c_void,
         piSize: *mut libc::size_t,
     ) -> GenTlResult<()> {
-        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hPort)? };
+        let handle = unsafe { ModuleHandle::from_raw(hPort)? };
 
         let info_data_type = with_port!(handle, |port| {
             let info = port.port_info()?;
@@ -229,7 +248,7 @@ gentl_api! {
         sURL: *mut libc::c_char,
         piSize: *mut libc::size_t,
     ) -> GenTlResult<()> {
-        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hPort)? };
+        let handle = unsafe { ModuleHandle::from_raw(hPort)? };
         let url = with_port!(handle, |port| {
             // Use first  info.
             let xml_info = port.xml_infos()?.get(0).ok_or_else(|| GenTlError::Error("no xml information in the device".into()))?;
@@ -243,7 +262,7 @@ gentl_api! {
 
 gentl_api! {
     pub fn GCGetNumPortURLs(hPort: PORT_HANDLE, piNumURLs: *mut u32) -> GenTlResult<()> {
-        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hPort)? };
+        let handle = unsafe { ModuleHandle::from_raw(hPort)? };
         let num_port = with_port!{handle, |port| {
             let xml_infos = port.xml_infos()?;
             xml_infos.len()
@@ -266,7 +285,7 @@ gentl_api! {
         pBuffer: *mut libc::c_void,
         piSize: *mut libc::size_t,
     ) -> GenTlResult<()> {
-        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hPort)? };
+        let handle = unsafe { ModuleHandle::from_raw(hPort)? };
         let info_data_type = with_port!(handle, |port| {
             let info = port
                 .xml_infos()?
@@ -342,6 +361,10 @@ gentl_api! {
                 URL_INFO_CMD::URL_INFO_FILENAME => {
                     use imp::port::XmlLocation;
                     let file_name = match &info.location {
+                        XmlLocation::RegisterMap { .. } => {
+                            register_file_name(info, port.port_info()?)
+                        }
+
                         XmlLocation::LocalFile(path) => {
                             let err_msg = "local file name is invalid";
                             let file_name =
@@ -349,12 +372,18 @@ gentl_api! {
                             file_name
                                 .to_str()
                                 .ok_or_else(|| GenTlError::Error(err_msg.into()))?
+                                .to_string()
                         }
 
-                        _ => return Err(GenTlError::NotAvailable),
+                        XmlLocation::Url(url) => {
+                            match url.path_segments().and_then(Iterator::last) {
+                                Some(file_name) if !file_name.is_empty() => file_name.to_string(),
+                                _ => return Err(GenTlError::NotAvailable),
+                            }
+                        }
                     };
 
-                    copy_info(file_name, pBuffer, piSize)
+                    copy_info(file_name.as_str(), pBuffer, piSize)
                 }
 
                 _ => Err(GenTlError::InvalidParameter),
@@ -377,7 +406,7 @@ gentl_api! {
         piSize: *mut libc::size_t,
     ) -> GenTlResult<()> {
         unsafe {
-            let handle = ModuleHandle::from_raw_manually_drop(hPort)?;
+            let handle = ModuleHandle::from_raw(hPort)?;
             let buffer = std::slice::from_raw_parts_mut(pBuffer.cast::<u8>(), *piSize);
 
             let read_len = with_port!(handle, |port| {
@@ -399,7 +428,7 @@ gentl_api! {
         piSize: *mut libc::size_t,
     ) -> GenTlResult<()> {
         unsafe {
-            let handle = ModuleHandle::from_raw_manually_drop(hPort)?;
+            let handle = ModuleHandle::from_raw(hPort)?;
             let data = std::slice::from_raw_parts(pBuffer.cast::<u8>(), *piSize);
 
             let written_len = with_port!(handle, |port| {
@@ -419,7 +448,7 @@ gentl_api! {
         piNumEntries: *mut libc::size_t,
     ) -> GenTlResult<()> {
         unsafe {
-            let handle = ModuleHandle::from_raw_manually_drop(hPort)?;
+            let handle = ModuleHandle::from_raw(hPort)?;
 
             let mut entries: Vec<_> = (0..*piNumEntries)
                 .map(|i| {
@@ -445,7 +474,7 @@ gentl_api! {
         piNumEntries: *mut libc::size_t,
     ) -> GenTlResult<()> {
         unsafe {
-            let handle = ModuleHandle::from_raw_manually_drop(hPort)?;
+            let handle = ModuleHandle::from_raw(hPort)?;
 
             let entries: Vec<_> = (0..*piNumEntries)
                 .map(|i| {
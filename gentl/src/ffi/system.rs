@@ -47,65 +47,87 @@ gentl_api!(
     }
 );
 
-gentl_api!(
-    pub fn TLGetInfo(
-        hSystem: TL_HANDLE,
-        iInfoCmd: TL_INFO_CMD,
-        piType: *mut INFO_DATATYPE,
-        pBuffer: *mut libc::c_void,
-        piSize: *mut libc::size_t,
-    ) -> GenTlResult<()> {
-        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hSystem)? };
-        let system_handle = handle.system()?;
-        let handle_guard = system_handle.lock().unwrap();
-        let system_info = handle_guard.system_info();
-
-        let info_data_type = match iInfoCmd {
-            TL_INFO_CMD::TL_INFO_ID => copy_info(system_info.id.as_str(), pBuffer, piSize),
+pub(super) fn tl_get_info(
+    system: SystemModuleRef,
+    iInfoCmd: TL_INFO_CMD,
+    piType: *mut INFO_DATATYPE,
+    pBuffer: *mut libc::c_void,
+    piSize: *mut libc::size_t,
+) -> GenTlResult<()> {
+    let system_guard = system.lock().unwrap();
+    let system_info = system_guard.system_info();
 
-            TL_INFO_CMD::TL_INFO_VENDOR => copy_info(system_info.vendor.as_str(), pBuffer, piSize),
+    let info_data_type = match iInfoCmd {
+        TL_INFO_CMD::TL_INFO_ID => copy_info(system_info.id.as_str(), pBuffer, piSize),
 
-            TL_INFO_CMD::TL_INFO_MODEL => copy_info(system_info.model.as_str(), pBuffer, piSize),
+        TL_INFO_CMD::TL_INFO_VENDOR => copy_info(system_info.vendor.as_str(), pBuffer, piSize),
 
-            TL_INFO_CMD::TL_INFO_VERSION => {
-                copy_info(system_info.version.as_str(), pBuffer, piSize)
-            }
+        TL_INFO_CMD::TL_INFO_MODEL => copy_info(system_info.model.as_str(), pBuffer, piSize),
 
-            TL_INFO_CMD::TL_INFO_TLTYPE => copy_info(system_info.tl_type.as_str(), pBuffer, piSize),
+        TL_INFO_CMD::TL_INFO_VERSION => copy_info(system_info.version.as_str(), pBuffer, piSize),
 
-            TL_INFO_CMD::TL_INFO_NAME => copy_info(
-                &*system_info.full_path.file_name().unwrap().to_string_lossy(),
-                pBuffer,
-                piSize,
-            ),
+        TL_INFO_CMD::TL_INFO_TLTYPE => copy_info(system_info.tl_type.as_str(), pBuffer, piSize),
 
-            TL_INFO_CMD::TL_INFO_PATHNAME => {
-                copy_info(&*system_info.full_path.to_string_lossy(), pBuffer, piSize)
-            }
+        TL_INFO_CMD::TL_INFO_NAME => copy_info(
+            &*system_info.full_path.file_name().unwrap().to_string_lossy(),
+            pBuffer,
+            piSize,
+        ),
 
-            TL_INFO_CMD::TL_INFO_DISPLAYNAME => {
-                copy_info(system_info.display_name.as_str(), pBuffer, piSize)
-            }
+        TL_INFO_CMD::TL_INFO_PATHNAME => {
+            copy_info(&*system_info.full_path.to_string_lossy(), pBuffer, piSize)
+        }
 
-            TL_INFO_CMD::TL_INFO_CHAR_ENCODING => {
-                copy_info(system_info.encoding.as_raw(), pBuffer, piSize)
-            }
+        TL_INFO_CMD::TL_INFO_DISPLAYNAME => {
+            copy_info(system_info.display_name.as_str(), pBuffer, piSize)
+        }
 
-            TL_INFO_CMD::TL_INFO_GENTL_VER_MAJOR => {
-                copy_info(system_info.gentl_version_major, pBuffer, piSize)
-            }
+        TL_INFO_CMD::TL_INFO_CHAR_ENCODING => {
+            copy_info(system_info.encoding.as_raw(), pBuffer, piSize)
+        }
 
-            TL_INFO_CMD::TL_INFO_GENTL_VER_MINOR => {
-                copy_info(system_info.gentl_version_minor, pBuffer, piSize)
-            }
-            _ => return Err(GenTlError::InvalidParameter),
-        }?;
+        TL_INFO_CMD::TL_INFO_GENTL_VER_MAJOR => {
+            copy_info(system_info.gentl_version_major, pBuffer, piSize)
+        }
 
-        unsafe {
-            *piType = info_data_type;
+        TL_INFO_CMD::TL_INFO_GENTL_VER_MINOR => {
+            copy_info(system_info.gentl_version_minor, pBuffer, piSize)
         }
+        _ => return Err(GenTlError::InvalidParameter),
+    }?;
 
-        Ok(())
+    unsafe {
+        *piType = info_data_type;
+    }
+
+    Ok(())
+}
+
+gentl_api!(
+    pub fn TLGetInfo(
+        hSystem: TL_HANDLE,
+        iInfoCmd: TL_INFO_CMD,
+        piType: *mut INFO_DATATYPE,
+        pBuffer: *mut libc::c_void,
+        piSize: *mut libc::size_t,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw(hSystem)? };
+        let system_handle = handle.system()?;
+
+        tl_get_info(system_handle, iInfoCmd, piType, pBuffer, piSize)
+    }
+);
+
+gentl_api!(
+    pub fn GCGetInfo(
+        iInfoCmd: TL_INFO_CMD,
+        piType: *mut INFO_DATATYPE,
+        pBuffer: *mut libc::c_void,
+        piSize: *mut libc::size_t,
+    ) -> GenTlResult<()> {
+        // Consumers query the information before opening the system module, e.g. to check the
+        // GenTL version the producer complies with.
+        tl_get_info(SYSTEM_MODULE.as_ref(), iInfoCmd, piType, pBuffer, piSize)
     }
 );
 
@@ -116,7 +138,7 @@ gentl_api! {
         sIfaceID: *mut libc::c_char,
         piSize: *mut libc::size_t,
     ) -> GenTlResult<()> {
-        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hSystem)? };
+        let handle = unsafe { ModuleHandle::from_raw(hSystem)? };
         let system_handle = handle.system()?;
         let handle_guard = system_handle.lock().unwrap();
 
@@ -138,7 +160,7 @@ gentl_api! {
         pBuffer: *mut libc::c_void,
         piSize: *mut libc::size_t,
     ) -> GenTlResult<()> {
-        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hSystem)? };
+        let handle = unsafe { ModuleHandle::from_raw(hSystem)? };
         let system_handle = handle.system()?;
         let handle_guard = system_handle.lock().unwrap();
         let id = unsafe { CStr::from_ptr(sIfaceID) }.to_string_lossy();
@@ -154,7 +176,7 @@ gentl_api! {
 
 gentl_api! {
     pub fn TLGetNumInterfaces(hSystem: TL_HANDLE, piNumIfaces: *mut u32) -> GenTlResult<()> {
-        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hSystem)? };
+        let handle = unsafe { ModuleHandle::from_raw(hSystem)? };
         let system_handle = handle.system()?;
         let handle_guard = system_handle.lock().unwrap();
 
@@ -172,7 +194,7 @@ gentl_api! {
         sIfaceID: *const libc::c_char,
         phIface: *mut super::interface::IF_HANDLE,
     ) -> GenTlResult<()> {
-        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hSystem)? };
+        let handle = unsafe { ModuleHandle::from_raw(hSystem)? };
         let system_handle = handle.system()?;
         let handle_guard = system_handle.lock().unwrap();
 
@@ -196,13 +218,14 @@ gentl_api! {
         pbChanged: *mut bool8_t,
         _iTimeout: u64,
     ) -> GenTlResult<()> {
-        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hSystem)? };
+        let handle = unsafe { ModuleHandle::from_raw(hSystem)? };
         let system_handle = handle.system()?;
-        let handle_guard = system_handle.lock().unwrap();
+        let mut handle_guard = system_handle.lock().unwrap();
 
         if handle_guard.is_opened() {
+            let changed = handle_guard.update_interface_list()?;
             unsafe {
-                *pbChanged = bool8_t::false_();
+                *pbChanged = changed.into();
             }
 
             Ok(())
@@ -0,0 +1,375 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Runtime configuration of the producer.
+//!
+//! The configuration is read from a TOML file specified by [`CONFIG_ENV_VAR`] when the library is
+//! initialized. All fields are optional, e.g.
+//!
+//! ```toml
+//! [interface]
+//! include = ["eth*", "enp3s0"]
+//! exclude = ["docker*"]
+//!
+//! [stream]
+//! buffer_count = 8
+//!
+//! [log]
+//! path = "/var/log/cameleon-gentl.log"
+//! level = "debug"
+//!
+//! [gige]
+//! discovery_timeout_ms = 1000
+//!
+//! [device]
+//! xml_urls = ["file:///opt/camera/device.zip"]
+//! ```
+//!
+//! The interface filters, the log level, and the discovery timeout can also be changed at runtime
+//! through the node map of the system module.
+
+use std::{
+    fs::OpenOptions,
+    path::{Path, PathBuf},
+    sync::{Mutex, RwLock, RwLockReadGuard},
+    time::Duration,
+};
+
+use serde::Deserialize;
+use tracing_subscriber::{filter::LevelFilter, fmt, prelude::*, reload, Registry};
+
+use crate::{GenTlError, GenTlResult};
+
+use super::port::XmlInfo;
+
+/// Environment variable holding the path to the configuration file.
+pub(crate) const CONFIG_ENV_VAR: &str = "CAMELEON_GENTL_CONFIG";
+
+lazy_static::lazy_static! {
+    static ref CONFIG: RwLock<Config> = RwLock::new(Config::default());
+    static ref LOG_FILTER: Mutex<Option<reload::Handle<LevelFilter, Registry>>> = Mutex::new(None);
+}
+
+/// Returns the current configuration.
+pub(crate) fn config() -> RwLockReadGuard<'static, Config> {
+    CONFIG.read().unwrap()
+}
+
+/// Modifies the current configuration, e.g. from the node map of the system module.
+///
+/// Each setting takes effect when the producer next refers to it.
+pub(crate) fn update(f: impl FnOnce(&mut Config)) {
+    f(&mut CONFIG.write().unwrap());
+}
+
+/// Changes the log level. Unlike other settings, the change takes effect immediately.
+pub(crate) fn set_log_level(level: LogLevel) {
+    update(|config| config.log.level = level);
+    if let Some(handle) = LOG_FILTER.lock().unwrap().as_ref() {
+        if let Err(e) = handle.reload(level.as_filter()) {
+            tracing::warn!("failed to change log level: {}", e);
+        }
+    }
+}
+
+/// Loads the configuration file specified by [`CONFIG_ENV_VAR`], then sets up logging.
+/// The default configuration is used if the variable isn't set.
+pub(crate) fn init() -> GenTlResult<()> {
+    let config = match std::env::var_os(CONFIG_ENV_VAR) {
+        Some(path) => Config::from_file(path)?,
+        None => Config::default(),
+    };
+
+    config.log.init()?;
+    tracing::debug!("producer configuration: {:?}", config);
+    *CONFIG.write().unwrap() = config;
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Config {
+    pub(crate) interface: InterfaceConfig,
+    pub(crate) stream: StreamConfig,
+    pub(crate) log: LogConfig,
+    pub(crate) gige: GigEConfig,
+    pub(crate) device: DeviceConfig,
+}
+
+impl Config {
+    fn from_file(path: impl AsRef<Path>) -> GenTlResult<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            GenTlError::Error(format!(
+                "failed to read configuration file `{}`: {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        Self::parse(&content)
+    }
+
+    fn parse(content: &str) -> GenTlResult<Self> {
+        let config: Self = toml::from_str(content)
+            .map_err(|e| GenTlError::Error(format!("invalid configuration: {}", e)))?;
+
+        if config.stream.buffer_count == 0 {
+            return Err(GenTlError::Error(
+                "invalid configuration: `stream.buffer_count` must be positive".into(),
+            ));
+        }
+        for url in &config.device.xml_urls {
+            XmlInfo::from_url(url).map_err(|e| {
+                GenTlError::Error(format!("invalid configuration: `device.xml_urls`: {}", e))
+            })?;
+        }
+
+        Ok(config)
+    }
+}
+
+/// Filters of interfaces exposed by the system module.
+///
+/// Patterns are matched against interface IDs and may contain `*` wildcards.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct InterfaceConfig {
+    /// Interfaces to expose, all interfaces are exposed if empty.
+    pub(crate) include: Vec<String>,
+
+    /// Interfaces to hide, takes precedence over `include`.
+    pub(crate) exclude: Vec<String>,
+}
+
+impl InterfaceConfig {
+    pub(crate) fn is_enabled(&self, id: &str) -> bool {
+        let is_included =
+            self.include.is_empty() || self.include.iter().any(|pat| wildcard_match(pat, id));
+        is_included && !self.exclude.iter().any(|pat| wildcard_match(pat, id))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct StreamConfig {
+    /// Number of payload buffers held by the streaming loop of a device.
+    pub(crate) buffer_count: usize,
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        Self { buffer_count: 3 }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct LogConfig {
+    /// Log file, logging is disabled if `None`.
+    pub(crate) path: Option<PathBuf>,
+
+    pub(crate) level: LogLevel,
+}
+
+impl LogConfig {
+    fn init(&self) -> GenTlResult<()> {
+        let path = if let Some(path) = &self.path {
+            path
+        } else {
+            return Ok(());
+        };
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| {
+                GenTlError::Error(format!(
+                    "failed to open log file `{}`: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+
+        // The subscriber is set up even if logging is off so that the level can be raised later.
+        let (filter, handle) = reload::Layer::new(self.level.as_filter());
+        let subscriber = tracing_subscriber::registry()
+            .with(filter)
+            .with(fmt::layer().with_writer(Mutex::new(file)).with_ansi(false));
+
+        // The global subscriber can be set only once in a process, so logging set up by the first
+        // initialization of the library remains even if the library is initialized again.
+        if subscriber.try_init().is_ok() {
+            *LOG_FILTER.lock().unwrap() = Some(handle);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    /// All levels in the order of their raw values.
+    const ALL: [Self; 6] = [
+        Self::Off,
+        Self::Error,
+        Self::Warn,
+        Self::Info,
+        Self::Debug,
+        Self::Trace,
+    ];
+
+    pub(crate) fn from_raw(raw: u32) -> Option<Self> {
+        Self::ALL.get(raw as usize).copied()
+    }
+
+    pub(crate) fn as_raw(self) -> u32 {
+        Self::ALL.iter().position(|level| *level == self).unwrap() as u32
+    }
+
+    fn as_filter(self) -> LevelFilter {
+        match self {
+            Self::Off => LevelFilter::OFF,
+            Self::Error => LevelFilter::ERROR,
+            Self::Warn => LevelFilter::WARN,
+            Self::Info => LevelFilter::INFO,
+            Self::Debug => LevelFilter::DEBUG,
+            Self::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+impl Default for LogLevel {
+    fn default() -> Self {
+        Self::Info
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct GigEConfig {
+    /// Timeout of GEV device discovery in milliseconds.
+    pub(crate) discovery_timeout_ms: u64,
+}
+
+impl GigEConfig {
+    pub(crate) fn discovery_timeout(&self) -> Duration {
+        Duration::from_millis(self.discovery_timeout_ms)
+    }
+}
+
+impl Default for GigEConfig {
+    fn default() -> Self {
+        Self {
+            discovery_timeout_ms: 500,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct DeviceConfig {
+    /// URLs of device description files offered after those of the remote device, so that
+    /// consumers can still build a node map when none of the files in the device is usable.
+    pub(crate) xml_urls: Vec<String>,
+}
+
+impl DeviceConfig {
+    pub(crate) fn xml_infos(&self) -> impl Iterator<Item = XmlInfo> + '_ {
+        // URLs are validated when the configuration is loaded.
+        self.xml_urls
+            .iter()
+            .filter_map(|url| XmlInfo::from_url(url).ok())
+    }
+}
+
+/// Matches `text` with `pattern` where `*` matches any sequence of characters.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    match pattern.find('*') {
+        None => pattern == text,
+        Some(pos) => {
+            let (prefix, rest) = (&pattern[..pos], &pattern[pos + 1..]);
+            text.starts_with(prefix)
+                && (prefix.len()..=text.len())
+                    .any(|i| text.is_char_boundary(i) && wildcard_match(rest, &text[i..]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let config = Config::parse(
+            r#"
+            [interface]
+            exclude = ["docker*"]
+
+            [stream]
+            buffer_count = 8
+
+            [log]
+            level = "debug"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.interface.exclude, vec!["docker*".to_string()]);
+        assert_eq!(config.stream.buffer_count, 8);
+        assert_eq!(config.log.level, LogLevel::Debug);
+        assert!(config.log.path.is_none());
+        assert_eq!(config.gige, GigEConfig::default());
+
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+        assert!(Config::parse("[stream]\nbuffer_num = 8").is_err());
+        assert!(Config::parse("[stream]\nbuffer_count = 0").is_err());
+        let config = Config::parse("[device]\nxml_urls = [\"local:device.xml;1000;10\"]").unwrap();
+        assert_eq!(config.device.xml_infos().count(), 1);
+        assert!(Config::parse("[device]\nxml_urls = [\"device.xml\"]").is_err());
+    }
+
+    #[test]
+    fn test_interface_filter() {
+        let config = InterfaceConfig {
+            include: vec!["eth*".into(), "enp3s0".into()],
+            exclude: vec!["*1".into()],
+        };
+
+        assert!(config.is_enabled("eth0"));
+        assert!(config.is_enabled("enp3s0"));
+        assert!(!config.is_enabled("eth1"));
+        assert!(!config.is_enabled("wlan0"));
+        assert!(InterfaceConfig::default().is_enabled("wlan0"));
+    }
+
+    #[test]
+    fn test_log_level_raw() {
+        for raw in 0..6 {
+            assert_eq!(LogLevel::from_raw(raw).unwrap().as_raw(), raw);
+        }
+        assert_eq!(LogLevel::Info.as_raw(), 3);
+        assert!(LogLevel::from_raw(6).is_none());
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("a*c*e", "abcde"));
+        assert!(!wildcard_match("a*c", "abcd"));
+    }
+}
@@ -0,0 +1,113 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::convert::TryInto;
+
+use crate::{GenTlError, GenTlResult};
+
+const CHUNK_ID_LEN: usize = 4;
+const CHUNK_SIZE_LEN: usize = 4;
+
+/// Location of a chunk in a buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct ChunkLayout {
+    /// Chunk ID.
+    pub(crate) id: u32,
+
+    /// Offset of the chunk data from the start of the buffer.
+    pub(crate) offset: usize,
+
+    /// Length of the chunk data in bytes, the trailing chunk ID and length fields are excluded.
+    pub(crate) len: usize,
+}
+
+/// Parses chunks in `data` and returns them in the order they appear in `data`.
+///
+/// Chunk data is designed to be decoded from the last byte to the first byte, each chunk is laid
+/// out as `[data][chunk ID (4 bytes)][data length (4 bytes)]` and fields are in big endian.
+pub(crate) fn parse_chunks(data: &[u8]) -> GenTlResult<Vec<ChunkLayout>> {
+    let mut chunks = vec![];
+    let mut current_offset = data.len();
+
+    while current_offset > 0 {
+        current_offset = current_offset
+            .checked_sub(CHUNK_SIZE_LEN)
+            .ok_or(GenTlError::ParsingChunkData)?;
+        let len = read_u32(data, current_offset) as usize;
+
+        current_offset = current_offset
+            .checked_sub(CHUNK_ID_LEN)
+            .ok_or(GenTlError::ParsingChunkData)?;
+        let id = read_u32(data, current_offset);
+
+        current_offset = current_offset
+            .checked_sub(len)
+            .ok_or(GenTlError::ParsingChunkData)?;
+        chunks.push(ChunkLayout {
+            id,
+            offset: current_offset,
+            len,
+        });
+    }
+
+    chunks.reverse();
+    Ok(chunks)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push_chunk(buf: &mut Vec<u8>, id: u32, data: &[u8]) {
+        buf.extend_from_slice(data);
+        buf.extend_from_slice(&id.to_be_bytes());
+        buf.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    }
+
+    #[test]
+    fn test_parse_chunks() {
+        let mut buf = vec![];
+        push_chunk(&mut buf, 0x10, &[1, 2, 3, 4, 5, 6]);
+        push_chunk(&mut buf, 0x20, &[]);
+        push_chunk(&mut buf, 0x30, &[7, 8]);
+
+        let chunks = parse_chunks(&buf).unwrap();
+        assert_eq!(
+            chunks,
+            vec![
+                ChunkLayout {
+                    id: 0x10,
+                    offset: 0,
+                    len: 6
+                },
+                ChunkLayout {
+                    id: 0x20,
+                    offset: 14,
+                    len: 0
+                },
+                ChunkLayout {
+                    id: 0x30,
+                    offset: 22,
+                    len: 2
+                },
+            ]
+        );
+        assert_eq!(&buf[22..24], &[7, 8]);
+    }
+
+    #[test]
+    fn test_parse_broken_chunks() {
+        let mut buf = vec![];
+        push_chunk(&mut buf, 0x10, &[1, 2, 3, 4]);
+        // Data length exceeds the buffer.
+        assert!(parse_chunks(&buf[2..]).is_err());
+        // Chunk ID and length fields are truncated.
+        assert!(parse_chunks(&buf[..buf.len() - 1]).is_err());
+        assert!(parse_chunks(&[]).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,267 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use cameleon_impl::memory::{memory, prelude::*, register_map};
+use const_format::formatcp;
+
+use GenApiReg::{
+    StreamAnnouncedBufferCount, StreamDeliveredFrameCount, StreamDroppedFrameCount, StreamID,
+    StreamIncompleteFrameCount, StreamInputBufferCount, StreamLostFrameCount,
+    StreamOutputBufferCount, StreamThroughput,
+};
+
+use crate::imp::genapi_common::{
+    SCHEME_MAJOR_VERSION, SCHEME_MINOR_VERSION, SCHEME_SUBMINOR_VERSION,
+};
+
+#[memory]
+pub(super) struct Memory {
+    genapi_reg: GenApiReg,
+    genapi_xml: GenApiXml,
+}
+
+#[register_map(base=0, endianness=LE)]
+pub(super) enum GenApiReg {
+    /// Device unique ID of the stream.
+    #[register(len = 64, access = RO, ty = String)]
+    StreamID,
+
+    /// Number of announced buffers.
+    #[register(len = 8, access = RO, ty = u64)]
+    StreamAnnouncedBufferCount,
+
+    /// Number of buffers in the input pool.
+    #[register(len = 8, access = RO, ty = u64)]
+    StreamInputBufferCount,
+
+    /// Number of buffers in the output queue.
+    #[register(len = 8, access = RO, ty = u64)]
+    StreamOutputBufferCount,
+
+    /// Number of delivered frames since the last acquisition start.
+    #[register(len = 8, access = RO, ty = u64)]
+    StreamDeliveredFrameCount,
+
+    /// Number of frames lost due to input pool underrun since the last acquisition start.
+    #[register(len = 8, access = RO, ty = u64)]
+    StreamLostFrameCount,
+
+    /// Number of frames delivered with missing data since the last acquisition start.
+    #[register(len = 8, access = RO, ty = u64)]
+    StreamIncompleteFrameCount,
+
+    /// Number of frames dropped due to transfer errors since the last acquisition start.
+    #[register(len = 8, access = RO, ty = u64)]
+    StreamDroppedFrameCount,
+
+    /// Number of bytes received per second.
+    #[register(len = 8, access = RO, ty = f64)]
+    StreamThroughput,
+}
+
+#[register_map(base=GENAPI_XML_ADDRESS, endianness=LE)]
+pub(super) enum GenApiXml {
+    #[register(len = GENAPI_XML_LENGTH, access = RO, ty = String)]
+    Xml = GENAPI_XML,
+}
+
+pub(super) const MODEL_NAME: &str = "CameleonGenTLDataStreamModule";
+pub(super) const VENDOR_NAME: &str = "CameleonProjectDevelopers";
+pub(super) const TOOL_TIP: &str = "GenTL Data Stream Module";
+
+pub(super) const XML_MAJOR_VERSION: u64 = 1;
+pub(super) const XML_MINOR_VERSION: u64 = 0;
+pub(super) const XML_SUBMINOR_VERSION: u64 = 0;
+
+pub(super) const PORT_NAME: &str = "StreamPort";
+
+pub(super) const GENAPI_XML_LENGTH: usize = GENAPI_XML.len();
+pub(super) const GENAPI_XML_ADDRESS: usize = GenApiReg::base() + GenApiReg::size();
+
+const PRODUCT_GUID: &str = "6A3C5F1E-8B0D-4E7A-9C21-3F5D7B9E0A42";
+const VERSION_GUID: &str = "D2E8B4C6-1A7F-4B3D-8E59-0C6A2F4B7D13";
+
+const GENAPI_XML: &str = formatcp!(
+    r#"<?xml version="1.0" encoding="UTF-8"?>
+<RegisterDescription
+ModelName="{MODEL_NAME}"
+VendorName="{VENDOR_NAME}"
+StandardNameSpace="None"
+SchemaMajorVersion="{SCHEME_MAJOR_VERSION}"
+SchemaMinorVersion="{SCHEME_MINOR_VERSION}"
+SchemaSubMinorVersion="{SCHEME_SUBMINOR_VERSION}"
+MajorVersion="{XML_MAJOR_VERSION}"
+MinorVersion="{XML_MINOR_VERSION}"
+SubMinorVersion="{XML_SUBMINOR_VERSION}"
+ToolTip="{TOOL_TIP}"
+ProductGuid="{PRODUCT_GUID}"
+VersionGuid="{VERSION_GUID}"
+xmlns="http://www.genicam.org/GenApi/Version_1_1"
+xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+xsi:schemaLocation="http://www.genicam.org/GenApi/Version_1_1 http://www.genicam.org/GenApi/GenApiSchema_Version_1_1.xsd">
+
+    <Category Name="Root" NameSpace="Standard">
+        <Description>Provides the Root of the GenICam features tree.</Description>
+        <Visibility>Beginner</Visibility>
+        <pFeature>StreamInformation</pFeature>
+        <pFeature>BufferHandlingControl</pFeature>
+        <pFeature>StreamDiagnostics</pFeature>
+    </Category>
+
+    <Port Name="{PORT_NAME}" NameSpace="Standard">
+        <Description>The GenICam port through which the Data Stream module is accessed.</Description>
+        <Visibility>Invisible</Visibility>
+    </Port>
+
+    <Category Name="StreamInformation" NameSpace="Standard">
+        <Description>Category that contains all Stream Information features of the Data Stream module.</Description>
+        <Visibility>Beginner</Visibility>
+        <pFeature>StreamID</pFeature>
+    </Category>
+
+    <StringReg Name="StreamID" NameSpace="Standard">
+        <Description>Device unique ID for the data stream.</Description>
+        <Visibility>Expert</Visibility>
+        <Address>{stream_id_addr}</Address>
+        <Length>{stream_id_len}</Length>
+        <AccessMode>{stream_id_access}</AccessMode>
+        <pPort>{PORT_NAME}</pPort>
+    </StringReg>
+
+    <Category Name="BufferHandlingControl" NameSpace="Standard">
+        <Description>Category that contains the Buffer Handling features of the Data Stream module.</Description>
+        <Visibility>Expert</Visibility>
+        <pFeature>StreamAnnouncedBufferCount</pFeature>
+        <pFeature>StreamInputBufferCount</pFeature>
+        <pFeature>StreamOutputBufferCount</pFeature>
+    </Category>
+
+    <IntReg Name="StreamAnnouncedBufferCount" NameSpace="Standard">
+        <Description>Number of announced buffers.</Description>
+        <Visibility>Expert</Visibility>
+        <Address>{announced_addr}</Address>
+        <Length>{announced_len}</Length>
+        <AccessMode>{announced_access}</AccessMode>
+        <pPort>{PORT_NAME}</pPort>
+        <Cachable>NoCache</Cachable>
+        <Endianess>LittleEndian</Endianess>
+    </IntReg>
+
+    <IntReg Name="StreamInputBufferCount" NameSpace="Standard">
+        <Description>Number of buffers in the input pool.</Description>
+        <Visibility>Expert</Visibility>
+        <Address>{input_addr}</Address>
+        <Length>{input_len}</Length>
+        <AccessMode>{input_access}</AccessMode>
+        <pPort>{PORT_NAME}</pPort>
+        <Cachable>NoCache</Cachable>
+        <Endianess>LittleEndian</Endianess>
+    </IntReg>
+
+    <IntReg Name="StreamOutputBufferCount" NameSpace="Standard">
+        <Description>Number of buffers in the output queue.</Description>
+        <Visibility>Expert</Visibility>
+        <Address>{output_addr}</Address>
+        <Length>{output_len}</Length>
+        <AccessMode>{output_access}</AccessMode>
+        <pPort>{PORT_NAME}</pPort>
+        <Cachable>NoCache</Cachable>
+        <Endianess>LittleEndian</Endianess>
+    </IntReg>
+
+    <Category Name="StreamDiagnostics" NameSpace="Standard">
+        <Description>Category that contains the diagnostic features of the Data Stream module.</Description>
+        <Visibility>Expert</Visibility>
+        <pFeature>StreamDeliveredFrameCount</pFeature>
+        <pFeature>StreamLostFrameCount</pFeature>
+        <pFeature>StreamIncompleteFrameCount</pFeature>
+        <pFeature>StreamDroppedFrameCount</pFeature>
+        <pFeature>StreamThroughput</pFeature>
+    </Category>
+
+    <IntReg Name="StreamDeliveredFrameCount" NameSpace="Standard">
+        <Description>Number of delivered frames since the last acquisition start.</Description>
+        <Visibility>Expert</Visibility>
+        <Address>{delivered_addr}</Address>
+        <Length>{delivered_len}</Length>
+        <AccessMode>{delivered_access}</AccessMode>
+        <pPort>{PORT_NAME}</pPort>
+        <Cachable>NoCache</Cachable>
+        <Endianess>LittleEndian</Endianess>
+    </IntReg>
+
+    <IntReg Name="StreamLostFrameCount" NameSpace="Standard">
+        <Description>Number of frames lost because the input pool was empty since the last acquisition start.</Description>
+        <Visibility>Expert</Visibility>
+        <Address>{lost_addr}</Address>
+        <Length>{lost_len}</Length>
+        <AccessMode>{lost_access}</AccessMode>
+        <pPort>{PORT_NAME}</pPort>
+        <Cachable>NoCache</Cachable>
+        <Endianess>LittleEndian</Endianess>
+    </IntReg>
+
+    <IntReg Name="StreamIncompleteFrameCount" NameSpace="Standard">
+        <Description>Number of frames delivered with missing data since the last acquisition start.</Description>
+        <Visibility>Expert</Visibility>
+        <Address>{incomplete_addr}</Address>
+        <Length>{incomplete_len}</Length>
+        <AccessMode>{incomplete_access}</AccessMode>
+        <pPort>{PORT_NAME}</pPort>
+        <Cachable>NoCache</Cachable>
+        <Endianess>LittleEndian</Endianess>
+    </IntReg>
+
+    <IntReg Name="StreamDroppedFrameCount" NameSpace="Custom">
+        <Description>Number of frames dropped due to transfer errors since the last acquisition start.</Description>
+        <Visibility>Expert</Visibility>
+        <Address>{dropped_addr}</Address>
+        <Length>{dropped_len}</Length>
+        <AccessMode>{dropped_access}</AccessMode>
+        <pPort>{PORT_NAME}</pPort>
+        <Cachable>NoCache</Cachable>
+        <Endianess>LittleEndian</Endianess>
+    </IntReg>
+
+    <FloatReg Name="StreamThroughput" NameSpace="Custom">
+        <Description>Number of bytes received per second, averaged over the last second.</Description>
+        <Visibility>Expert</Visibility>
+        <Address>{throughput_addr}</Address>
+        <Length>{throughput_len}</Length>
+        <AccessMode>{throughput_access}</AccessMode>
+        <pPort>{PORT_NAME}</pPort>
+        <Cachable>NoCache</Cachable>
+        <Endianess>LittleEndian</Endianess>
+        <Unit>B/s</Unit>
+    </FloatReg>
+
+</RegisterDescription>"#,
+    stream_id_addr = StreamID::ADDRESS,
+    stream_id_len = StreamID::LENGTH,
+    stream_id_access = StreamID::ACCESS_RIGHT.as_str(),
+    announced_addr = StreamAnnouncedBufferCount::ADDRESS,
+    announced_len = StreamAnnouncedBufferCount::LENGTH,
+    announced_access = StreamAnnouncedBufferCount::ACCESS_RIGHT.as_str(),
+    input_addr = StreamInputBufferCount::ADDRESS,
+    input_len = StreamInputBufferCount::LENGTH,
+    input_access = StreamInputBufferCount::ACCESS_RIGHT.as_str(),
+    output_addr = StreamOutputBufferCount::ADDRESS,
+    output_len = StreamOutputBufferCount::LENGTH,
+    output_access = StreamOutputBufferCount::ACCESS_RIGHT.as_str(),
+    delivered_addr = StreamDeliveredFrameCount::ADDRESS,
+    delivered_len = StreamDeliveredFrameCount::LENGTH,
+    delivered_access = StreamDeliveredFrameCount::ACCESS_RIGHT.as_str(),
+    lost_addr = StreamLostFrameCount::ADDRESS,
+    lost_len = StreamLostFrameCount::LENGTH,
+    lost_access = StreamLostFrameCount::ACCESS_RIGHT.as_str(),
+    incomplete_addr = StreamIncompleteFrameCount::ADDRESS,
+    incomplete_len = StreamIncompleteFrameCount::LENGTH,
+    incomplete_access = StreamIncompleteFrameCount::ACCESS_RIGHT.as_str(),
+    dropped_addr = StreamDroppedFrameCount::ADDRESS,
+    dropped_len = StreamDroppedFrameCount::LENGTH,
+    dropped_access = StreamDroppedFrameCount::ACCESS_RIGHT.as_str(),
+    throughput_addr = StreamThroughput::ADDRESS,
+    throughput_len = StreamThroughput::LENGTH,
+    throughput_access = StreamThroughput::ACCESS_RIGHT.as_str(),
+);
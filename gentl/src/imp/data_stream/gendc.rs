@@ -0,0 +1,341 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::convert::TryInto;
+
+use crate::{GenTlError, GenTlResult};
+
+const SIGNATURE: u32 = 0x4344_4E47; // "GNDC"
+const CONTAINER_HEADER_TYPE: u16 = 0x1000;
+const COMPONENT_HEADER_TYPE: u16 = 0x2000;
+
+const CONTAINER_HEADER_LEN: usize = 56;
+const COMPONENT_HEADER_LEN: usize = 48;
+const PART_HEADER_LEN: usize = 40;
+const PART_2D_HEADER_LEN: usize = PART_HEADER_LEN + 16;
+
+/// Kind of a GenDC part, determined by the header type of the part.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PartKind {
+    /// Metadata such as GenICam chunk data.
+    Metadata,
+    /// One dimensional data.
+    Data1D,
+    /// Uncompressed two dimensional data.
+    Data2D,
+    Jpeg,
+    Jpeg2000,
+    H264,
+    /// Part header type which cameleon doesn't know.
+    Unknown,
+}
+
+impl From<u16> for PartKind {
+    fn from(header_type: u16) -> Self {
+        match header_type {
+            0x4000 => Self::Metadata,
+            0x4100 => Self::Data1D,
+            0x4200 => Self::Data2D,
+            0x4201 => Self::Jpeg,
+            0x4202 => Self::Jpeg2000,
+            0x4203 => Self::H264,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Dimensions of a two dimensional part.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct PartDims {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    /// Padding at the end of each line in bytes.
+    pub(crate) x_padding: u16,
+}
+
+/// Layout of a part of a GenDC container in a buffer.
+///
+/// Component level fields are copied to each part so that a part is self-contained.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct GenDcPart {
+    pub(crate) kind: PartKind,
+
+    /// Pixel format or metadata format of the part in PFNC.
+    pub(crate) format: u32,
+
+    pub(crate) source_id: u16,
+    pub(crate) region_id: u16,
+
+    /// Type ID of the component, which GenTL calls a data purpose.
+    pub(crate) type_id: u64,
+
+    pub(crate) x_offset: u32,
+    pub(crate) y_offset: u32,
+
+    /// Flow the part is transferred through, and its offset in the flow.
+    pub(crate) flow_id: u16,
+    pub(crate) flow_offset: u64,
+
+    /// Offset of the part data from the start of the buffer.
+    pub(crate) offset: usize,
+
+    /// Length of the part data in bytes.
+    pub(crate) len: usize,
+
+    /// `None` unless the part is two dimensional.
+    pub(crate) dims: Option<PartDims>,
+}
+
+/// Layout of a GenDC container in a buffer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct GenDcContainer {
+    /// Size of the container descriptor placed at the head of the buffer.
+    pub(crate) descriptor_size: usize,
+
+    /// Parts in the order they appear in the descriptor.
+    pub(crate) parts: Vec<GenDcPart>,
+}
+
+/// Parses the container descriptor at the head of `data`.
+///
+/// All fields are in little endian, and offsets of components and parts are relative to the start
+/// of the container while offsets of part data are relative to the start of the container data.
+pub(crate) fn parse_container(data: &[u8]) -> GenTlResult<GenDcContainer> {
+    if read_u32(data, 0)? != SIGNATURE || read_u16(data, 8)? != CONTAINER_HEADER_TYPE {
+        return Err(GenTlError::NoData);
+    }
+
+    let data_size = to_usize(read_u64(data, 32)?)?;
+    let data_offset = to_usize(read_u64(data, 40)?)?;
+    let descriptor_size = read_u32(data, 48)? as usize;
+    let component_count = read_u32(data, 52)? as usize;
+    if descriptor_size > data.len() {
+        return Err(GenTlError::NoData);
+    }
+    // Only the descriptor is allowed to be read while parsing headers.
+    let descriptor = &data[..descriptor_size];
+
+    let mut parts = vec![];
+    for i in 0..component_count {
+        let component_offset = to_usize(read_u64(descriptor, CONTAINER_HEADER_LEN + i * 8)?)?;
+        let component = descriptor
+            .get(component_offset..)
+            .ok_or(GenTlError::NoData)?;
+        if read_u16(component, 0)? != COMPONENT_HEADER_TYPE {
+            return Err(GenTlError::NoData);
+        }
+
+        let source_id = read_u16(component, 12)?;
+        let region_id = read_u16(component, 14)?;
+        let x_offset = read_u32(component, 16)?;
+        let y_offset = read_u32(component, 20)?;
+        let type_id = read_u64(component, 32)?;
+        let part_count = read_u16(component, 46)? as usize;
+
+        for j in 0..part_count {
+            let part_offset = to_usize(read_u64(component, COMPONENT_HEADER_LEN + j * 8)?)?;
+            let part = descriptor.get(part_offset..).ok_or(GenTlError::NoData)?;
+
+            let kind = PartKind::from(read_u16(part, 0)?);
+            let header_size = read_u32(part, 4)? as usize;
+            let len = to_usize(read_u64(part, 24)?)?;
+            let offset = to_usize(read_u64(part, 32)?)?;
+            if offset.checked_add(len).ok_or(GenTlError::NoData)? > data_size {
+                return Err(GenTlError::NoData);
+            }
+
+            let dims = if header_size >= PART_2D_HEADER_LEN {
+                match kind {
+                    PartKind::Data2D | PartKind::Jpeg | PartKind::Jpeg2000 | PartKind::H264 => {
+                        Some(PartDims {
+                            width: read_u32(part, 40)?,
+                            height: read_u32(part, 44)?,
+                            x_padding: read_u16(part, 48)?,
+                        })
+                    }
+                    _ => None,
+                }
+            } else {
+                None
+            };
+
+            parts.push(GenDcPart {
+                kind,
+                format: read_u32(part, 8)?,
+                source_id,
+                region_id,
+                type_id,
+                x_offset,
+                y_offset,
+                flow_id: read_u16(part, 14)?,
+                flow_offset: read_u64(part, 16)?,
+                offset: data_offset.checked_add(offset).ok_or(GenTlError::NoData)?,
+                len,
+                dims,
+            });
+        }
+    }
+
+    Ok(GenDcContainer {
+        descriptor_size,
+        parts,
+    })
+}
+
+fn read_u16(data: &[u8], offset: usize) -> GenTlResult<u16> {
+    let bytes = data.get(offset..offset + 2).ok_or(GenTlError::NoData)?;
+    Ok(u16::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> GenTlResult<u32> {
+    let bytes = data.get(offset..offset + 4).ok_or(GenTlError::NoData)?;
+    Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> GenTlResult<u64> {
+    let bytes = data.get(offset..offset + 8).ok_or(GenTlError::NoData)?;
+    Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn to_usize(val: u64) -> GenTlResult<usize> {
+    val.try_into().map_err(|_| GenTlError::NoData)
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    /// Builds a container which has a 2D image part and a metadata part in a single component.
+    pub(crate) fn container_bytes(image: &[u8], metadata: &[u8]) -> Vec<u8> {
+        let component_offset = CONTAINER_HEADER_LEN + 8;
+        let part_offsets = [
+            component_offset + COMPONENT_HEADER_LEN + 16,
+            component_offset + COMPONENT_HEADER_LEN + 16 + PART_2D_HEADER_LEN,
+        ];
+        let descriptor_size = part_offsets[1] + PART_HEADER_LEN;
+        let data_size = image.len() + metadata.len();
+
+        let mut buf = vec![];
+        // Container header.
+        buf.extend_from_slice(&SIGNATURE.to_le_bytes());
+        buf.extend_from_slice(&[1, 0, 0, 0]);
+        buf.extend_from_slice(&CONTAINER_HEADER_TYPE.to_le_bytes());
+        buf.extend_from_slice(&0_u16.to_le_bytes());
+        buf.extend_from_slice(&(CONTAINER_HEADER_LEN as u32 + 8).to_le_bytes());
+        buf.extend_from_slice(&7_u64.to_le_bytes());
+        buf.extend_from_slice(&0_u64.to_le_bytes());
+        buf.extend_from_slice(&(data_size as u64).to_le_bytes());
+        buf.extend_from_slice(&(descriptor_size as u64).to_le_bytes());
+        buf.extend_from_slice(&(descriptor_size as u32).to_le_bytes());
+        buf.extend_from_slice(&1_u32.to_le_bytes());
+        buf.extend_from_slice(&(component_offset as u64).to_le_bytes());
+
+        // Component header.
+        buf.extend_from_slice(&COMPONENT_HEADER_TYPE.to_le_bytes());
+        buf.extend_from_slice(&0_u16.to_le_bytes());
+        buf.extend_from_slice(&(COMPONENT_HEADER_LEN as u32 + 16).to_le_bytes());
+        buf.extend_from_slice(&0_u16.to_le_bytes());
+        buf.extend_from_slice(&0_u16.to_le_bytes()); // Group ID.
+        buf.extend_from_slice(&2_u16.to_le_bytes()); // Source ID.
+        buf.extend_from_slice(&3_u16.to_le_bytes()); // Region ID.
+        buf.extend_from_slice(&16_u32.to_le_bytes()); // Region offset X.
+        buf.extend_from_slice(&8_u32.to_le_bytes()); // Region offset Y.
+        buf.extend_from_slice(&100_u64.to_le_bytes()); // Timestamp.
+        buf.extend_from_slice(&1_u64.to_le_bytes()); // Type ID.
+        buf.extend_from_slice(&0x0108_0001_u32.to_le_bytes()); // Format.
+        buf.extend_from_slice(&0_u16.to_le_bytes());
+        buf.extend_from_slice(&2_u16.to_le_bytes());
+        for offset in &part_offsets {
+            buf.extend_from_slice(&(*offset as u64).to_le_bytes());
+        }
+
+        // 2D part header.
+        buf.extend_from_slice(&0x4200_u16.to_le_bytes());
+        buf.extend_from_slice(&0_u16.to_le_bytes());
+        buf.extend_from_slice(&(PART_2D_HEADER_LEN as u32).to_le_bytes());
+        buf.extend_from_slice(&0x0108_0001_u32.to_le_bytes());
+        buf.extend_from_slice(&0_u16.to_le_bytes());
+        buf.extend_from_slice(&0_u16.to_le_bytes()); // Flow ID.
+        buf.extend_from_slice(&0_u64.to_le_bytes()); // Flow offset.
+        buf.extend_from_slice(&(image.len() as u64).to_le_bytes());
+        buf.extend_from_slice(&0_u64.to_le_bytes());
+        buf.extend_from_slice(&4_u32.to_le_bytes()); // Size X.
+        buf.extend_from_slice(&((image.len() / 4) as u32).to_le_bytes()); // Size Y.
+        buf.extend_from_slice(&0_u16.to_le_bytes()); // Padding X.
+        buf.extend_from_slice(&0_u16.to_le_bytes()); // Padding Y.
+        buf.extend_from_slice(&0_u32.to_le_bytes());
+
+        // Metadata part header.
+        buf.extend_from_slice(&0x4000_u16.to_le_bytes());
+        buf.extend_from_slice(&0_u16.to_le_bytes());
+        buf.extend_from_slice(&(PART_HEADER_LEN as u32).to_le_bytes());
+        buf.extend_from_slice(&0_u32.to_le_bytes());
+        buf.extend_from_slice(&0_u16.to_le_bytes());
+        buf.extend_from_slice(&1_u16.to_le_bytes()); // Flow ID.
+        buf.extend_from_slice(&0_u64.to_le_bytes()); // Flow offset.
+        buf.extend_from_slice(&(metadata.len() as u64).to_le_bytes());
+        buf.extend_from_slice(&(image.len() as u64).to_le_bytes());
+        assert_eq!(buf.len(), descriptor_size);
+
+        buf.extend_from_slice(image);
+        buf.extend_from_slice(metadata);
+        buf
+    }
+
+    #[test]
+    fn test_parse_container() {
+        let image = [1; 16];
+        let metadata = [2; 8];
+        let buf = container_bytes(&image, &metadata);
+
+        let container = parse_container(&buf).unwrap();
+        assert_eq!(container.parts.len(), 2);
+        let descriptor_size = container.descriptor_size;
+        assert_eq!(descriptor_size, buf.len() - image.len() - metadata.len());
+
+        let image_part = container.parts[0];
+        assert_eq!(image_part.kind, PartKind::Data2D);
+        assert_eq!(image_part.format, 0x0108_0001);
+        assert_eq!(image_part.source_id, 2);
+        assert_eq!(image_part.region_id, 3);
+        assert_eq!(image_part.type_id, 1);
+        assert_eq!((image_part.x_offset, image_part.y_offset), (16, 8));
+        assert_eq!(image_part.flow_id, 0);
+        assert_eq!(image_part.offset, descriptor_size);
+        assert_eq!(image_part.len, image.len());
+        assert_eq!(
+            image_part.dims,
+            Some(PartDims {
+                width: 4,
+                height: 4,
+                x_padding: 0
+            })
+        );
+
+        let metadata_part = container.parts[1];
+        assert_eq!(metadata_part.kind, PartKind::Metadata);
+        assert_eq!(metadata_part.flow_id, 1);
+        assert_eq!(metadata_part.dims, None);
+        let range = metadata_part.offset..metadata_part.offset + metadata_part.len;
+        assert_eq!(&buf[range], &metadata);
+    }
+
+    #[test]
+    fn test_parse_broken_container() {
+        let buf = container_bytes(&[1; 16], &[2; 8]);
+        let descriptor_size = parse_container(&buf).unwrap().descriptor_size;
+
+        // The descriptor is truncated.
+        assert!(parse_container(&buf[..descriptor_size - 1]).is_err());
+        // Signature mismatch.
+        let mut broken = buf.clone();
+        broken[0] = 0;
+        assert!(parse_container(&broken).is_err());
+        // Part data exceeds the container data.
+        let mut broken = buf;
+        broken[32..40].copy_from_slice(&8_u64.to_le_bytes());
+        assert!(parse_container(&broken).is_err());
+        assert!(parse_container(&[]).is_err());
+    }
+}
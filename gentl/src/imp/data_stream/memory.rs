@@ -0,0 +1,114 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{alloc::Layout, ptr::NonNull};
+
+use crate::{GenTlError, GenTlResult};
+
+/// Alignment of buffer memory in bytes.
+/// Memory announced by the consumer must also be aligned to this value.
+pub(crate) const BUFFER_ALIGNMENT: usize = 64;
+
+/// Memory of an announced buffer.
+pub(super) enum BufferMemory {
+    /// Memory allocated by the producer through `DSAllocAndAnnounceBuffer`.
+    Allocated { ptr: NonNull<u8>, layout: Layout },
+
+    /// Memory provided by the consumer through `DSAnnounceBuffer`.
+    User { ptr: NonNull<u8>, len: usize },
+}
+
+// The memory is accessed only through `&self` or `&mut self`, so it's safe to send it to the
+// acquisition thread.
+unsafe impl Send for BufferMemory {}
+
+impl BufferMemory {
+    pub(super) fn alloc(size: usize) -> GenTlResult<Self> {
+        if size == 0 {
+            return Err(GenTlError::InvalidParameter);
+        }
+
+        let layout = Layout::from_size_align(size, BUFFER_ALIGNMENT)
+            .map_err(|_| GenTlError::InvalidParameter)?;
+        let ptr = NonNull::new(unsafe { std::alloc::alloc_zeroed(layout) })
+            .ok_or(GenTlError::OutOfMemory)?;
+
+        Ok(Self::Allocated { ptr, layout })
+    }
+
+    /// # Safety
+    /// `ptr` must be valid for reads and writes of `len` bytes while the returned value is alive.
+    pub(super) unsafe fn from_user(ptr: *mut u8, len: usize) -> GenTlResult<Self> {
+        let ptr = NonNull::new(ptr).ok_or(GenTlError::InvalidParameter)?;
+        if len == 0 || ptr.as_ptr() as usize % BUFFER_ALIGNMENT != 0 {
+            return Err(GenTlError::InvalidParameter);
+        }
+
+        Ok(Self::User { ptr, len })
+    }
+
+    pub(super) fn as_slice(&self) -> &[u8] {
+        let (ptr, len) = self.raw_parts();
+        unsafe { std::slice::from_raw_parts(ptr.as_ptr(), len) }
+    }
+
+    pub(super) fn as_mut_slice(&mut self) -> &mut [u8] {
+        let (ptr, len) = self.raw_parts();
+        unsafe { std::slice::from_raw_parts_mut(ptr.as_ptr(), len) }
+    }
+
+    /// Returns the pointer to the memory if it's provided by the consumer.
+    pub(super) fn user_memory(&self) -> Option<*mut u8> {
+        match self {
+            Self::Allocated { .. } => None,
+            Self::User { ptr, .. } => Some(ptr.as_ptr()),
+        }
+    }
+
+    fn raw_parts(&self) -> (NonNull<u8>, usize) {
+        match self {
+            Self::Allocated { ptr, layout } => (*ptr, layout.size()),
+            Self::User { ptr, len } => (*ptr, *len),
+        }
+    }
+}
+
+impl Drop for BufferMemory {
+    fn drop(&mut self) {
+        if let Self::Allocated { ptr, layout } = self {
+            unsafe { std::alloc::dealloc(ptr.as_ptr(), *layout) }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alloc() {
+        let mut memory = BufferMemory::alloc(100).unwrap();
+        assert_eq!(memory.as_slice().len(), 100);
+        assert_eq!(memory.as_slice().as_ptr() as usize % BUFFER_ALIGNMENT, 0);
+        assert!(memory.user_memory().is_none());
+
+        memory.as_mut_slice()[99] = 1;
+        assert_eq!(memory.as_slice()[99], 1);
+    }
+
+    #[test]
+    fn test_user_memory() {
+        let mut backing = vec![0_u8; BUFFER_ALIGNMENT * 2];
+        let offset = backing.as_ptr().align_offset(BUFFER_ALIGNMENT);
+        let ptr = unsafe { backing.as_mut_ptr().add(offset) };
+
+        let memory = unsafe { BufferMemory::from_user(ptr, BUFFER_ALIGNMENT) }.unwrap();
+        assert_eq!(memory.user_memory(), Some(ptr));
+        assert_eq!(memory.as_slice().len(), BUFFER_ALIGNMENT);
+
+        let misaligned = unsafe { ptr.add(1) };
+        assert!(unsafe { BufferMemory::from_user(misaligned, 1) }.is_err());
+        assert!(unsafe { BufferMemory::from_user(std::ptr::null_mut(), 1) }.is_err());
+    }
+}
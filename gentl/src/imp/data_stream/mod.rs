@@ -0,0 +1,1122 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Mutex},
+    thread::JoinHandle,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use async_std::task;
+use cameleon::{
+    cancel::CancellationToken,
+    genapi::CompressionType,
+    payload::{ImageInfo, Payload, PayloadReceiver, PayloadType},
+    StreamError,
+};
+use cameleon_impl::memory::prelude::*;
+
+use crate::{GenTlError, GenTlResult};
+
+use super::{
+    event::{EventData, EventQueue, EventType},
+    genapi_common,
+    port::{Endianness, ModuleType, Port, PortAccess, PortInfo, TlType, XmlInfo, XmlLocation},
+};
+
+mod chunk;
+mod genapi;
+mod gendc;
+mod memory;
+mod statistics;
+
+pub(crate) use chunk::ChunkLayout;
+pub(crate) use gendc::{GenDcContainer, GenDcPart, PartKind};
+pub(crate) use memory::BUFFER_ALIGNMENT;
+
+use genapi::GenApiReg;
+use memory::BufferMemory;
+use statistics::StreamStatistics;
+
+/// Identifier of a buffer announced to a data stream module.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct BufferId(usize);
+
+impl BufferId {
+    pub(crate) fn new(raw: usize) -> Self {
+        Self(raw)
+    }
+
+    pub(crate) fn as_raw(self) -> usize {
+        self.0
+    }
+}
+
+/// State of an announced buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum BufferState {
+    /// The buffer is announced, but not queued to the input pool.
+    Announced,
+
+    /// The buffer is in the input pool and waits to be filled.
+    Queued,
+
+    /// The buffer is filled. The buffer is in the output queue or already delivered to the
+    /// consumer.
+    Filled,
+}
+
+/// Information of the data filled in a buffer.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct BufferInfo {
+    /// Number of bytes written to the buffer.
+    pub(crate) size_filled: usize,
+
+    /// Size of the payload, larger than `size_filled` if the payload didn't fit to the buffer.
+    pub(crate) data_size: usize,
+
+    /// Frame ID of the payload.
+    pub(crate) frame_id: u64,
+
+    /// Timestamp of the device when the payload is generated.
+    pub(crate) timestamp: Duration,
+
+    /// Time of the host when the buffer is filled, elapsed since the UNIX epoch.
+    pub(crate) host_timestamp: Duration,
+
+    /// `true` if the payload didn't fit to the buffer.
+    pub(crate) is_incomplete: bool,
+
+    /// `true` if the buffer is filled with new data since it was queued.
+    pub(crate) new_data: bool,
+
+    /// Type of the payload, `None` if the buffer isn't filled with a payload.
+    pub(crate) payload_type: Option<PayloadType>,
+
+    /// Information of the image in the payload, `None` if the payload doesn't contain an image.
+    pub(crate) image_info: Option<ImageInfo>,
+}
+
+impl BufferInfo {
+    pub(crate) fn contains_chunk_data(&self) -> bool {
+        matches!(
+            self.payload_type,
+            Some(PayloadType::ImageExtendedChunk) | Some(PayloadType::Chunk)
+        )
+    }
+
+    /// Number of image lines written to the buffer, `None` if the payload doesn't contain an
+    /// image.
+    pub(crate) fn delivered_image_height(&self) -> Option<usize> {
+        let image_info = self.image_info.as_ref()?;
+        if !self.is_incomplete || image_info.height == 0 {
+            return Some(image_info.height);
+        }
+
+        let line_len = image_info.image_size / image_info.height;
+        if line_len == 0 {
+            Some(0)
+        } else {
+            Some((self.size_filled / line_len).min(image_info.height))
+        }
+    }
+}
+
+pub(crate) struct Buffer {
+    memory: BufferMemory,
+    user_ptr: usize,
+    state: BufferState,
+    info: BufferInfo,
+}
+
+impl Buffer {
+    pub(crate) fn data(&self) -> &[u8] {
+        self.memory.as_slice()
+    }
+
+    /// Returns the pointer to the buffer memory if it's provided by the consumer.
+    pub(crate) fn user_memory(&self) -> Option<*mut u8> {
+        self.memory.user_memory()
+    }
+
+    /// User pointer passed when the buffer is announced.
+    pub(crate) fn user_ptr(&self) -> usize {
+        self.user_ptr
+    }
+
+    pub(crate) fn state(&self) -> BufferState {
+        self.state
+    }
+
+    pub(crate) fn info(&self) -> &BufferInfo {
+        &self.info
+    }
+
+    /// Returns chunks contained in the filled data.
+    ///
+    /// Returns [`GenTlError::NoData`] if the payload doesn't contain chunk data, and
+    /// [`GenTlError::ParsingChunkData`] if the chunk data is broken or truncated.
+    pub(crate) fn chunks(&self) -> GenTlResult<Vec<ChunkLayout>> {
+        if !self.info.contains_chunk_data() {
+            return Err(GenTlError::NoData);
+        }
+        if self.info.is_incomplete {
+            return Err(GenTlError::ParsingChunkData);
+        }
+
+        chunk::parse_chunks(&self.data()[..self.info.size_filled])
+    }
+
+    /// Returns the layout of the GenDC container in the filled data.
+    ///
+    /// Returns [`GenTlError::NoData`] if the payload isn't a GenDC container or its descriptor is
+    /// broken or truncated.
+    pub(crate) fn gendc_container(&self) -> GenTlResult<GenDcContainer> {
+        if self.info.payload_type != Some(PayloadType::GenDC) {
+            return Err(GenTlError::NoData);
+        }
+
+        gendc::parse_container(&self.data()[..self.info.size_filled])
+    }
+}
+
+/// Operations of [`DataStreamModule::flush_queue`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum FlushOperation {
+    /// Flushes the input pool to the output queue.
+    InputToOutput,
+
+    /// Discards all buffers in the output queue.
+    OutputDiscard,
+
+    /// Puts all buffers in the input pool.
+    AllToInput,
+
+    /// Puts all buffers that are neither in the input pool nor in the output queue in the input
+    /// pool.
+    UnqueuedToInput,
+
+    /// Discards all buffers in the input pool and output queue.
+    AllDiscard,
+}
+
+#[derive(Default)]
+struct BufferStore {
+    buffers: BTreeMap<BufferId, Buffer>,
+    input_pool: VecDeque<BufferId>,
+    next_id: usize,
+    statistics: StreamStatistics,
+}
+
+impl BufferStore {
+    fn get_mut(&mut self, id: BufferId) -> GenTlResult<&mut Buffer> {
+        self.buffers.get_mut(&id).ok_or(GenTlError::InvalidHandle)
+    }
+
+    /// Fills the first buffer in the input pool with `payload`.
+    /// Returns `None` if the input pool is empty.
+    fn fill(&mut self, payload: &Payload) -> Option<(BufferId, usize)> {
+        let data = payload.payload();
+        self.statistics.record_payload(Instant::now(), data.len());
+
+        let id = if let Some(id) = self.input_pool.pop_front() {
+            id
+        } else {
+            self.statistics.num_underrun += 1;
+            return None;
+        };
+
+        let buffer = self.buffers.get_mut(&id).unwrap();
+        let dst = buffer.memory.as_mut_slice();
+        let len = data.len().min(dst.len());
+        dst[..len].copy_from_slice(&data[..len]);
+        buffer.state = BufferState::Filled;
+        buffer.info = BufferInfo {
+            size_filled: len,
+            data_size: data.len(),
+            frame_id: payload.id(),
+            timestamp: payload.timestamp(),
+            host_timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default(),
+            is_incomplete: len < data.len(),
+            new_data: true,
+            payload_type: Some(payload.payload_type()),
+            image_info: payload.image_info().cloned(),
+        };
+        let user_ptr = buffer.user_ptr;
+        self.statistics.num_delivered += 1;
+        if buffer.info.is_incomplete {
+            self.statistics.num_incomplete += 1;
+        }
+
+        Some((id, user_ptr))
+    }
+}
+
+pub(crate) struct DataStreamModule {
+    id: String,
+    tl_type: TlType,
+    is_opened: bool,
+
+    /// Guarded by `Mutex` because [`Port::read`] refreshes the statistics registers.
+    vm: Mutex<genapi::Memory>,
+    port_info: PortInfo,
+    xml_infos: Vec<XmlInfo>,
+
+    store: Arc<Mutex<BufferStore>>,
+    /// Output queue of filled buffers.
+    new_buffer_event: Arc<EventQueue>,
+    /// Notified when the acquisition fails.
+    error_event: Arc<EventQueue>,
+    /// Remote device event queue of the parent device, notified when the device is lost.
+    remote_device_event: Arc<EventQueue>,
+    acquisition: Option<Acquisition>,
+}
+
+struct Acquisition {
+    cancellation: CancellationToken,
+    handle: JoinHandle<()>,
+}
+
+impl DataStreamModule {
+    pub(crate) fn new(id: String, tl_type: TlType, remote_device_event: Arc<EventQueue>) -> Self {
+        let port_info = PortInfo {
+            id: id.clone(),
+            vendor: genapi::VENDOR_NAME.into(),
+            model: genapi::MODEL_NAME.into(),
+            tl_type,
+            module_type: ModuleType::DataStream,
+            endianness: Endianness::LE,
+            access: PortAccess::RO,
+            version: semver::Version::new(
+                genapi::XML_MAJOR_VERSION,
+                genapi::XML_MINOR_VERSION,
+                genapi::XML_SUBMINOR_VERSION,
+            ),
+            port_name: genapi::PORT_NAME.into(),
+        };
+
+        let xml_info = XmlInfo {
+            location: XmlLocation::RegisterMap {
+                address: genapi::GENAPI_XML_ADDRESS as u64,
+                size: genapi::GENAPI_XML_LENGTH,
+            },
+            schema_version: semver::Version::new(
+                genapi_common::SCHEME_MAJOR_VERSION,
+                genapi_common::SCHEME_MINOR_VERSION,
+                genapi_common::SCHEME_SUBMINOR_VERSION,
+            ),
+            file_version: semver::Version::new(
+                genapi::XML_MAJOR_VERSION,
+                genapi::XML_MINOR_VERSION,
+                genapi::XML_SUBMINOR_VERSION,
+            ),
+            sha1_hash: None,
+            compressed: CompressionType::Uncompressed,
+        };
+
+        let mut vm = genapi::Memory::new();
+        // Ok to unwrap because stream IDs are much shorter than the register.
+        vm.write::<GenApiReg::StreamID>(id.clone()).unwrap();
+
+        Self {
+            id,
+            tl_type,
+            is_opened: false,
+
+            vm: Mutex::new(vm),
+            port_info,
+            xml_infos: vec![xml_info],
+
+            store: Arc::new(Mutex::new(BufferStore::default())),
+            new_buffer_event: Arc::new(EventQueue::new(EventType::NewBuffer)),
+            error_event: Arc::new(EventQueue::new(EventType::Error)),
+            remote_device_event,
+            acquisition: None,
+        }
+    }
+
+    pub(crate) fn open(&mut self) -> GenTlResult<()> {
+        if self.is_opened {
+            Err(GenTlError::ResourceInUse)
+        } else {
+            self.is_opened = true;
+            Ok(())
+        }
+    }
+
+    /// Closes the module. Acquisition is stopped and all announced buffers are revoked.
+    pub(crate) fn close(&mut self) -> GenTlResult<()> {
+        self.assert_open()?;
+
+        self.stop_acquisition().ok();
+        for event in &[&self.new_buffer_event, &self.error_event] {
+            if event.is_registered() {
+                event.unregister()?;
+            }
+            event.flush();
+        }
+        *self.store.lock().unwrap() = BufferStore::default();
+
+        self.is_opened = false;
+        Ok(())
+    }
+
+    pub(crate) fn is_opened(&self) -> bool {
+        self.is_opened
+    }
+
+    pub(crate) fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub(crate) fn tl_type(&self) -> TlType {
+        self.tl_type
+    }
+
+    /// Returns the event queue of `event_type`.
+    pub(crate) fn event(&self, event_type: EventType) -> GenTlResult<Arc<EventQueue>> {
+        self.assert_open()?;
+
+        match event_type {
+            EventType::NewBuffer => Ok(self.new_buffer_event.clone()),
+            EventType::Error => Ok(self.error_event.clone()),
+            _ => Err(GenTlError::NotImplemented),
+        }
+    }
+
+    /// Allocates a buffer of `size` bytes and announces it.
+    pub(crate) fn alloc_and_announce_buffer(
+        &mut self,
+        size: usize,
+        user_ptr: usize,
+    ) -> GenTlResult<BufferId> {
+        self.assert_open()?;
+
+        let memory = BufferMemory::alloc(size)?;
+        Ok(self.announce(memory, user_ptr))
+    }
+
+    /// Announces memory provided by the consumer as a buffer.
+    ///
+    /// `ptr` must be aligned to [`BUFFER_ALIGNMENT`].
+    ///
+    /// # Safety
+    /// `ptr` must be valid for reads and writes of `size` bytes until the buffer is revoked or the
+    /// module is closed.
+    pub(crate) unsafe fn announce_buffer(
+        &mut self,
+        ptr: *mut u8,
+        size: usize,
+        user_ptr: usize,
+    ) -> GenTlResult<BufferId> {
+        self.assert_open()?;
+
+        let is_announced = self
+            .store
+            .lock()
+            .unwrap()
+            .buffers
+            .values()
+            .any(|buffer| buffer.user_memory() == Some(ptr));
+        if is_announced {
+            return Err(GenTlError::ResourceInUse);
+        }
+
+        let memory = BufferMemory::from_user(ptr, size)?;
+        Ok(self.announce(memory, user_ptr))
+    }
+
+    /// Revokes the buffer, then returns it.
+    pub(crate) fn revoke_buffer(&mut self, id: BufferId) -> GenTlResult<Buffer> {
+        self.assert_open()?;
+
+        let mut store = self.store.lock().unwrap();
+        if store.get_mut(id)?.state == BufferState::Queued {
+            return Err(GenTlError::Busy);
+        }
+        self.new_buffer_event
+            .remove_if(|data| is_event_of(data, id));
+
+        Ok(store.buffers.remove(&id).unwrap())
+    }
+
+    /// Puts the buffer to the input pool.
+    pub(crate) fn queue_buffer(&mut self, id: BufferId) -> GenTlResult<()> {
+        self.assert_open()?;
+
+        let mut store = self.store.lock().unwrap();
+        let buffer = store.get_mut(id)?;
+        match buffer.state {
+            BufferState::Queued => return Err(GenTlError::InvalidParameter),
+            BufferState::Filled => self
+                .new_buffer_event
+                .remove_if(|data| is_event_of(data, id)),
+            BufferState::Announced => {}
+        }
+        buffer.state = BufferState::Queued;
+        buffer.info.new_data = false;
+        store.input_pool.push_back(id);
+
+        Ok(())
+    }
+
+    pub(crate) fn flush_queue(&mut self, op: FlushOperation) -> GenTlResult<()> {
+        self.assert_open()?;
+
+        let mut store = self.store.lock().unwrap();
+        let store = &mut *store;
+        match op {
+            FlushOperation::InputToOutput => {
+                for id in store.input_pool.drain(..) {
+                    let buffer = store.buffers.get_mut(&id).unwrap();
+                    buffer.state = BufferState::Filled;
+                    buffer.info = BufferInfo::default();
+                    self.new_buffer_event.notify(EventData::NewBuffer {
+                        buffer: id,
+                        user_ptr: buffer.user_ptr,
+                    });
+                }
+            }
+
+            FlushOperation::OutputDiscard => {
+                self.discard_output(store);
+            }
+
+            FlushOperation::AllToInput => {
+                self.new_buffer_event.flush();
+                store.input_pool.clear();
+                for (id, buffer) in &mut store.buffers {
+                    buffer.state = BufferState::Queued;
+                    buffer.info.new_data = false;
+                    store.input_pool.push_back(*id);
+                }
+            }
+
+            FlushOperation::UnqueuedToInput => {
+                for (id, buffer) in &mut store.buffers {
+                    // Buffers delivered to the consumer are also regarded as unqueued.
+                    if buffer.state != BufferState::Queued
+                        && !self
+                            .new_buffer_event
+                            .contains(|data| is_event_of(data, *id))
+                    {
+                        buffer.state = BufferState::Queued;
+                        buffer.info.new_data = false;
+                        store.input_pool.push_back(*id);
+                    }
+                }
+            }
+
+            FlushOperation::AllDiscard => {
+                self.discard_output(store);
+                for id in store.input_pool.drain(..) {
+                    store.buffers.get_mut(&id).unwrap().state = BufferState::Announced;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Starts filling queued buffers with payloads sent through `receiver`.
+    ///
+    /// `num_to_acquire` is the number of buffers to be filled, `None` means infinite.
+    pub(crate) fn start_acquisition(
+        &mut self,
+        receiver: PayloadReceiver,
+        num_to_acquire: Option<u64>,
+    ) -> GenTlResult<()> {
+        self.assert_open()?;
+        if self.acquisition.is_some() {
+            return Err(GenTlError::ResourceInUse);
+        }
+
+        // Statistics are counted from the latest acquisition start.
+        self.store.lock().unwrap().statistics = StreamStatistics::default();
+
+        let cancellation = CancellationToken::new();
+        let acquisition_loop = AcquisitionLoop {
+            store: self.store.clone(),
+            new_buffer_event: self.new_buffer_event.clone(),
+            error_event: self.error_event.clone(),
+            remote_device_event: self.remote_device_event.clone(),
+            receiver,
+            cancellation: cancellation.clone(),
+            num_to_acquire,
+        };
+        let handle = std::thread::spawn(move || acquisition_loop.run());
+
+        self.acquisition = Some(Acquisition {
+            cancellation,
+            handle,
+        });
+        Ok(())
+    }
+
+    pub(crate) fn stop_acquisition(&mut self) -> GenTlResult<()> {
+        self.assert_open()?;
+
+        let acquisition = self.acquisition.take().ok_or(GenTlError::NotAvailable)?;
+        acquisition.cancellation.cancel();
+        acquisition
+            .handle
+            .join()
+            .map_err(|_| GenTlError::Error("acquisition thread panicked".into()))
+    }
+
+    pub(crate) fn is_grabbing(&self) -> bool {
+        self.acquisition.is_some()
+    }
+
+    /// Returns the id of the `index`-th announced buffer.
+    pub(crate) fn buffer_id(&self, index: usize) -> GenTlResult<BufferId> {
+        self.assert_open()?;
+
+        let store = self.store.lock().unwrap();
+        store
+            .buffers
+            .keys()
+            .nth(index)
+            .copied()
+            .ok_or(GenTlError::InvalidIndex)
+    }
+
+    /// Calls `f` with the buffer of `id`.
+    ///
+    /// The buffer must not be used outside of `f` because the acquisition thread may write to it.
+    pub(crate) fn with_buffer<R>(
+        &self,
+        id: BufferId,
+        f: impl FnOnce(&Buffer) -> R,
+    ) -> GenTlResult<R> {
+        self.assert_open()?;
+
+        let store = self.store.lock().unwrap();
+        let buffer = store.buffers.get(&id).ok_or(GenTlError::InvalidHandle)?;
+        Ok(f(buffer))
+    }
+
+    pub(crate) fn num_announced(&self) -> usize {
+        self.store.lock().unwrap().buffers.len()
+    }
+
+    pub(crate) fn num_queued(&self) -> usize {
+        self.store.lock().unwrap().input_pool.len()
+    }
+
+    pub(crate) fn num_await_delivery(&self) -> usize {
+        self.new_buffer_event.num_in_queue()
+    }
+
+    pub(crate) fn num_delivered(&self) -> u64 {
+        self.store.lock().unwrap().statistics.num_delivered
+    }
+
+    pub(crate) fn num_underrun(&self) -> u64 {
+        self.store.lock().unwrap().statistics.num_underrun
+    }
+
+    pub(crate) fn num_dropped(&self) -> u64 {
+        self.store.lock().unwrap().statistics.num_dropped
+    }
+
+    pub(crate) fn num_incomplete(&self) -> u64 {
+        self.store.lock().unwrap().statistics.num_incomplete
+    }
+
+    /// Returns the number of bytes received per second.
+    pub(crate) fn throughput(&self) -> f64 {
+        self.store
+            .lock()
+            .unwrap()
+            .statistics
+            .throughput(Instant::now())
+    }
+
+    fn announce(&self, memory: BufferMemory, user_ptr: usize) -> BufferId {
+        let mut store = self.store.lock().unwrap();
+        let id = BufferId(store.next_id);
+        store.next_id += 1;
+        store.buffers.insert(
+            id,
+            Buffer {
+                memory,
+                user_ptr,
+                state: BufferState::Announced,
+                info: BufferInfo::default(),
+            },
+        );
+
+        id
+    }
+
+    fn discard_output(&self, store: &mut BufferStore) {
+        for data in self.new_buffer_event.flush() {
+            if let Some(buffer) = data.buffer().and_then(|id| store.buffers.get_mut(&id)) {
+                buffer.state = BufferState::Announced;
+            }
+        }
+    }
+
+    /// Writes the current buffer counts and statistics to the statistics registers.
+    fn reflect_statistics(&self, vm: &mut genapi::Memory) -> GenTlResult<()> {
+        let (num_announced, num_queued, statistics) = {
+            let store = self.store.lock().unwrap();
+            (
+                store.buffers.len(),
+                store.input_pool.len(),
+                store.statistics.clone(),
+            )
+        };
+
+        vm.write::<GenApiReg::StreamAnnouncedBufferCount>(num_announced as u64)?;
+        vm.write::<GenApiReg::StreamInputBufferCount>(num_queued as u64)?;
+        vm.write::<GenApiReg::StreamOutputBufferCount>(self.num_await_delivery() as u64)?;
+        vm.write::<GenApiReg::StreamDeliveredFrameCount>(statistics.num_delivered)?;
+        vm.write::<GenApiReg::StreamLostFrameCount>(statistics.num_underrun)?;
+        vm.write::<GenApiReg::StreamIncompleteFrameCount>(statistics.num_incomplete)?;
+        vm.write::<GenApiReg::StreamDroppedFrameCount>(statistics.num_dropped)?;
+        vm.write::<GenApiReg::StreamThroughput>(statistics.throughput(Instant::now()))?;
+        Ok(())
+    }
+
+    fn assert_open(&self) -> GenTlResult<()> {
+        if self.is_opened {
+            Ok(())
+        } else {
+            Err(GenTlError::NotInitialized)
+        }
+    }
+}
+
+impl Port for DataStreamModule {
+    fn read(&self, address: u64, buf: &mut [u8]) -> GenTlResult<usize> {
+        self.assert_open()?;
+
+        let address = address as usize;
+        let len = buf.len();
+
+        let mut vm = self.vm.lock().unwrap();
+        self.reflect_statistics(&mut vm)?;
+        let data = vm.read_raw(address..address + len)?;
+        buf.copy_from_slice(data);
+
+        Ok(len)
+    }
+
+    fn write(&mut self, address: u64, data: &[u8]) -> GenTlResult<usize> {
+        self.assert_open()?;
+
+        self.vm
+            .get_mut()
+            .unwrap()
+            .write_raw(address as usize, data)?;
+
+        Ok(data.len())
+    }
+
+    fn port_info(&self) -> GenTlResult<&PortInfo> {
+        self.assert_open()?;
+
+        Ok(&self.port_info)
+    }
+
+    fn xml_infos(&self) -> GenTlResult<&[XmlInfo]> {
+        self.assert_open()?;
+
+        Ok(&self.xml_infos)
+    }
+}
+
+impl Drop for DataStreamModule {
+    fn drop(&mut self) {
+        if self.is_opened {
+            self.close().ok();
+        }
+    }
+}
+
+fn is_event_of(data: &EventData, id: BufferId) -> bool {
+    data.buffer() == Some(id)
+}
+
+struct AcquisitionLoop {
+    store: Arc<Mutex<BufferStore>>,
+    new_buffer_event: Arc<EventQueue>,
+    error_event: Arc<EventQueue>,
+    remote_device_event: Arc<EventQueue>,
+    receiver: PayloadReceiver,
+    cancellation: CancellationToken,
+    num_to_acquire: Option<u64>,
+}
+
+impl AcquisitionLoop {
+    fn run(self) {
+        let mut num_acquired = 0;
+
+        while self.num_to_acquire.map_or(true, |num| num_acquired < num) {
+            let payload = match task::block_on(self.receiver.recv_with_cancel(&self.cancellation)) {
+                Ok(payload) => payload,
+                // The acquisition is stopped or the streaming loop of the device is finished.
+                Err(StreamError::Cancelled) | Err(StreamError::ReceiveError(..)) => break,
+                // No more payloads arrive once the device is lost.
+                Err(err @ StreamError::Disconnected) => {
+                    self.notify_error(err);
+                    self.remote_device_event
+                        .notify_if_registered(EventData::device_lost());
+                    break;
+                }
+                // The streaming loop can't continue after a panic.
+                Err(err @ StreamError::Poisoned(..)) => {
+                    self.notify_error(err);
+                    break;
+                }
+                // The streaming loop keeps running after an I/O error, so report it and wait the
+                // next payload.
+                Err(err @ StreamError::Io(..)) => {
+                    self.count_dropped();
+                    self.notify_error(err);
+                    continue;
+                }
+                // Broken payloads are dropped in the streaming loop, so just wait the next one.
+                Err(_) => {
+                    self.count_dropped();
+                    continue;
+                }
+            };
+
+            let filled = self.store.lock().unwrap().fill(&payload);
+            self.receiver.send_back(payload);
+
+            if let Some((buffer, user_ptr)) = filled {
+                self.new_buffer_event
+                    .notify(EventData::NewBuffer { buffer, user_ptr });
+                num_acquired += 1;
+            }
+        }
+    }
+
+    fn count_dropped(&self) {
+        self.store.lock().unwrap().statistics.num_dropped += 1;
+    }
+
+    fn notify_error(&self, err: StreamError) {
+        self.error_event
+            .notify_if_registered(EventData::error(&err.into()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opened_module() -> DataStreamModule {
+        let remote_device_event = Arc::new(EventQueue::new(EventType::RemoteDevice));
+        let mut module =
+            DataStreamModule::new("Stream0".into(), TlType::USB3Vision, remote_device_event);
+        module.open().unwrap();
+        module
+    }
+
+    #[test]
+    fn test_queue_buffer() {
+        let mut module = opened_module();
+        let first = module.alloc_and_announce_buffer(16, 1).unwrap();
+        let second = module.alloc_and_announce_buffer(16, 2).unwrap();
+        assert_eq!(module.num_announced(), 2);
+        assert_eq!(module.buffer_id(1).unwrap(), second);
+
+        module.queue_buffer(first).unwrap();
+        assert!(module.queue_buffer(first).is_err());
+        assert!(module.revoke_buffer(first).is_err());
+        assert_eq!(module.num_queued(), 1);
+
+        module.flush_queue(FlushOperation::UnqueuedToInput).unwrap();
+        assert_eq!(module.num_queued(), 2);
+
+        module.flush_queue(FlushOperation::AllDiscard).unwrap();
+        assert_eq!(module.num_queued(), 0);
+        assert_eq!(module.revoke_buffer(second).unwrap().user_ptr(), 2);
+        assert_eq!(module.num_announced(), 1);
+    }
+
+    #[test]
+    fn test_flush_input_to_output() {
+        let mut module = opened_module();
+        let id = module.alloc_and_announce_buffer(16, 1).unwrap();
+        let event = module.event(EventType::NewBuffer).unwrap();
+        event.register().unwrap();
+
+        module.queue_buffer(id).unwrap();
+        module.flush_queue(FlushOperation::InputToOutput).unwrap();
+        assert_eq!(module.num_queued(), 0);
+        assert_eq!(module.num_await_delivery(), 1);
+
+        assert_eq!(
+            event.wait(Some(Duration::from_millis(10))).unwrap(),
+            EventData::NewBuffer {
+                buffer: id,
+                user_ptr: 1
+            }
+        );
+        let state = module.with_buffer(id, Buffer::state).unwrap();
+        assert_eq!(state, BufferState::Filled);
+    }
+
+    #[test]
+    fn test_announce_user_buffer() {
+        let mut module = opened_module();
+        let mut backing = vec![0_u8; BUFFER_ALIGNMENT * 2];
+        let offset = backing.as_ptr().align_offset(BUFFER_ALIGNMENT);
+        let ptr = unsafe { backing.as_mut_ptr().add(offset) };
+
+        let id = unsafe { module.announce_buffer(ptr, BUFFER_ALIGNMENT, 1) }.unwrap();
+        assert!(unsafe { module.announce_buffer(ptr, BUFFER_ALIGNMENT, 2) }.is_err());
+        let base = module
+            .with_buffer(id, |buffer| buffer.data().as_ptr())
+            .unwrap();
+        assert_eq!(base, ptr as *const u8);
+
+        let buffer = module.revoke_buffer(id).unwrap();
+        assert_eq!(buffer.user_memory(), Some(ptr));
+        assert_eq!(buffer.user_ptr(), 1);
+    }
+
+    #[test]
+    fn test_buffer_chunks() {
+        let mut module = opened_module();
+        let id = module.alloc_and_announce_buffer(16, 0).unwrap();
+        assert!(matches!(
+            module.with_buffer(id, Buffer::chunks).unwrap(),
+            Err(GenTlError::NoData)
+        ));
+
+        {
+            let mut store = module.store.lock().unwrap();
+            let buffer = store.get_mut(id).unwrap();
+            buffer.memory.as_mut_slice()[..12]
+                .copy_from_slice(&[1, 2, 3, 4, 0, 0, 0, 0x10, 0, 0, 0, 4]);
+            buffer.state = BufferState::Filled;
+            buffer.info.size_filled = 12;
+            buffer.info.payload_type = Some(PayloadType::Chunk);
+        }
+
+        let chunks = module.with_buffer(id, Buffer::chunks).unwrap().unwrap();
+        assert_eq!(
+            chunks,
+            vec![ChunkLayout {
+                id: 0x10,
+                offset: 0,
+                len: 4
+            }]
+        );
+    }
+
+    #[test]
+    fn test_buffer_gendc_container() {
+        let mut module = opened_module();
+        let container = gendc::tests::container_bytes(&[1; 16], &[2; 8]);
+        let id = module
+            .alloc_and_announce_buffer(container.len(), 0)
+            .unwrap();
+        assert!(matches!(
+            module.with_buffer(id, Buffer::gendc_container).unwrap(),
+            Err(GenTlError::NoData)
+        ));
+
+        {
+            let mut store = module.store.lock().unwrap();
+            let buffer = store.get_mut(id).unwrap();
+            buffer.memory.as_mut_slice().copy_from_slice(&container);
+            buffer.state = BufferState::Filled;
+            buffer.info.size_filled = container.len();
+            buffer.info.payload_type = Some(PayloadType::GenDC);
+        }
+
+        let parsed = module
+            .with_buffer(id, Buffer::gendc_container)
+            .unwrap()
+            .unwrap();
+        assert_eq!(parsed.parts.len(), 2);
+        assert_eq!(parsed.parts[1].kind, PartKind::Metadata);
+        assert!(!module
+            .with_buffer(id, |buffer| buffer.info().contains_chunk_data())
+            .unwrap());
+    }
+
+    #[test]
+    fn test_delivered_image_height() {
+        let mut info = BufferInfo {
+            size_filled: 40,
+            data_size: 64,
+            is_incomplete: true,
+            payload_type: Some(PayloadType::Image),
+            image_info: Some(ImageInfo {
+                width: 8,
+                height: 8,
+                x_offset: 0,
+                y_offset: 0,
+                pixel_format: cameleon::payload::PixelFormat::Mono8,
+                image_size: 64,
+            }),
+            ..BufferInfo::default()
+        };
+        assert_eq!(info.delivered_image_height(), Some(5));
+
+        info.is_incomplete = false;
+        assert_eq!(info.delivered_image_height(), Some(8));
+
+        info.image_info = None;
+        assert_eq!(info.delivered_image_height(), None);
+    }
+
+    #[test]
+    fn test_close() {
+        let mut module = opened_module();
+        let event = module.event(EventType::NewBuffer).unwrap();
+        event.register().unwrap();
+        module.alloc_and_announce_buffer(16, 0).unwrap();
+
+        module.close().unwrap();
+        assert!(!event.is_registered());
+        assert_eq!(module.num_announced(), 0);
+        assert!(module.alloc_and_announce_buffer(16, 0).is_err());
+    }
+
+    #[test]
+    fn test_concurrent_access() {
+        const NUM_BUFFERS: usize = 8;
+        const NUM_CONSUMERS: usize = 4;
+        const NUM_TO_RECEIVE: usize = 100;
+
+        let module = Arc::new(Mutex::new(opened_module()));
+        let event = {
+            let mut module = module.lock().unwrap();
+            for _ in 0..NUM_BUFFERS {
+                let id = module.alloc_and_announce_buffer(16, 0).unwrap();
+                module.queue_buffer(id).unwrap();
+            }
+            module.event(EventType::NewBuffer).unwrap()
+        };
+        event.register().unwrap();
+
+        // Each consumer fills buffers by flushing the input pool, then receives and requeues
+        // buffers filled by any consumer while the others access the module at the same time.
+        let consumers: Vec<_> = (0..NUM_CONSUMERS)
+            .map(|_| {
+                let module = module.clone();
+                let event = event.clone();
+                std::thread::spawn(move || {
+                    let mut num_received = 0;
+                    while num_received < NUM_TO_RECEIVE {
+                        module
+                            .lock()
+                            .unwrap()
+                            .flush_queue(FlushOperation::InputToOutput)
+                            .unwrap();
+
+                        let id = match event.wait(Some(Duration::from_millis(10))) {
+                            Ok(data) => data.buffer().unwrap(),
+                            Err(GenTlError::Timeout) => continue,
+                            Err(e) => panic!("{}", e),
+                        };
+
+                        let mut module = module.lock().unwrap();
+                        assert_eq!(
+                            module.with_buffer(id, Buffer::state).unwrap(),
+                            BufferState::Filled
+                        );
+                        module.queue_buffer(id).unwrap();
+                        num_received += 1;
+                    }
+                })
+            })
+            .collect();
+
+        for consumer in consumers {
+            consumer.join().unwrap();
+        }
+
+        let module = module.lock().unwrap();
+        assert_eq!(module.num_announced(), NUM_BUFFERS);
+        assert_eq!(
+            module.num_queued() + module.num_await_delivery(),
+            NUM_BUFFERS
+        );
+        assert_eq!(
+            event.num_fired() as usize,
+            NUM_CONSUMERS * NUM_TO_RECEIVE + module.num_await_delivery()
+        );
+    }
+
+    #[test]
+    fn test_statistics_registers() {
+        let mut module = opened_module();
+        let id = module.alloc_and_announce_buffer(16, 1).unwrap();
+        module.alloc_and_announce_buffer(16, 2).unwrap();
+        module.queue_buffer(id).unwrap();
+        module.store.lock().unwrap().statistics.num_underrun = 3;
+
+        let read_u64 = |address: usize| {
+            let mut buf = [0; 8];
+            module.read(address as u64, &mut buf).unwrap();
+            u64::from_le_bytes(buf)
+        };
+        assert_eq!(read_u64(GenApiReg::StreamAnnouncedBufferCount::ADDRESS), 2);
+        assert_eq!(read_u64(GenApiReg::StreamInputBufferCount::ADDRESS), 1);
+        assert_eq!(read_u64(GenApiReg::StreamLostFrameCount::ADDRESS), 3);
+        assert_eq!(read_u64(GenApiReg::StreamDeliveredFrameCount::ADDRESS), 0);
+
+        let address = GenApiReg::StreamDeliveredFrameCount::ADDRESS as u64;
+        assert!(module.write(address, &[0; 8]).is_err());
+    }
+
+    #[test]
+    fn test_device_lost() {
+        let remote_device_event = Arc::new(EventQueue::new(EventType::RemoteDevice));
+        remote_device_event.register().unwrap();
+        let mut module = DataStreamModule::new(
+            "Stream0".into(),
+            TlType::USB3Vision,
+            remote_device_event.clone(),
+        );
+        module.open().unwrap();
+
+        let (sender, receiver) = cameleon::payload::channel(1, 1);
+        sender.try_send(Err(StreamError::Disconnected)).unwrap();
+        module.start_acquisition(receiver, None).unwrap();
+
+        let data = remote_device_event
+            .wait(Some(Duration::from_secs(1)))
+            .unwrap();
+        assert_eq!(data, EventData::device_lost());
+        module.stop_acquisition().unwrap();
+    }
+
+    #[test]
+    fn test_error_event() {
+        let mut module = opened_module();
+        let event = module.event(EventType::Error).unwrap();
+        event.register().unwrap();
+
+        let (sender, receiver) = cameleon::payload::channel(2, 2);
+        let err = std::io::Error::new(std::io::ErrorKind::Other, "transfer failed");
+        sender.try_send(Err(StreamError::Io(err.into()))).unwrap();
+        sender.try_send(Err(StreamError::Disconnected)).unwrap();
+        module.start_acquisition(receiver, None).unwrap();
+
+        let timeout = Some(Duration::from_secs(1));
+        for _ in 0..2 {
+            match event.wait(timeout).unwrap() {
+                EventData::Error { code, .. } => assert_eq!(code, GenTlError::Io("".into()).code()),
+                data => panic!("unexpected event: {:?}", data),
+            }
+        }
+        module.stop_acquisition().unwrap();
+        assert_eq!(module.num_dropped(), 1);
+    }
+}
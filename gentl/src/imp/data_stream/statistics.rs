@@ -0,0 +1,85 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Period over which the throughput is averaged.
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(1);
+
+/// Statistics of a data stream since the acquisition started.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct StreamStatistics {
+    /// Number of buffers filled and delivered to the output queue.
+    pub(crate) num_delivered: u64,
+
+    /// Number of payloads discarded because the input pool was empty.
+    pub(crate) num_underrun: u64,
+
+    /// Number of payloads dropped due to transfer errors.
+    pub(crate) num_dropped: u64,
+
+    /// Number of delivered buffers which were too small to hold the whole payload.
+    pub(crate) num_incomplete: u64,
+
+    /// Arrival time and size of the payloads received in the last [`THROUGHPUT_WINDOW`].
+    recent_payloads: VecDeque<(Instant, usize)>,
+}
+
+impl StreamStatistics {
+    /// Records a payload of `len` bytes received from the device at `now`, regardless of whether
+    /// it was delivered.
+    pub(crate) fn record_payload(&mut self, now: Instant, len: usize) {
+        self.recent_payloads.push_back((now, len));
+        self.expire(now);
+    }
+
+    /// Returns the number of bytes received per second, averaged over the last second.
+    pub(crate) fn throughput(&self, now: Instant) -> f64 {
+        let bytes: usize = self
+            .recent_payloads
+            .iter()
+            .filter(|(time, _)| now.saturating_duration_since(*time) < THROUGHPUT_WINDOW)
+            .map(|(_, len)| len)
+            .sum();
+        bytes as f64 / THROUGHPUT_WINDOW.as_secs_f64()
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some((time, _)) = self.recent_payloads.front() {
+            if now.saturating_duration_since(*time) < THROUGHPUT_WINDOW {
+                break;
+            }
+            self.recent_payloads.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throughput() {
+        let mut stats = StreamStatistics::default();
+        let start = Instant::now();
+        assert_eq!(stats.throughput(start), 0.0);
+
+        stats.record_payload(start, 1000);
+        stats.record_payload(start + Duration::from_millis(500), 3000);
+        assert_eq!(stats.throughput(start + Duration::from_millis(900)), 4000.0);
+
+        // The first payload is out of the window.
+        assert_eq!(
+            stats.throughput(start + Duration::from_millis(1200)),
+            3000.0
+        );
+
+        stats.record_payload(start + Duration::from_secs(2), 500);
+        assert_eq!(stats.recent_payloads.len(), 1);
+        assert_eq!(stats.throughput(start + Duration::from_secs(2)), 500.0);
+    }
+}
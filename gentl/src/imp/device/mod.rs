@@ -2,17 +2,29 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use std::{convert::TryFrom, sync::Mutex};
+use std::{
+    convert::TryFrom,
+    sync::{Arc, Mutex},
+};
 
-use crate::{GenTlError, GenTlResult};
+use cameleon::payload::PayloadReceiver;
+
+use crate::{
+    imp::{
+        data_stream::DataStreamModule,
+        event::{EventQueue, EventType},
+    },
+    GenTlError, GenTlResult,
+};
 
 pub(crate) mod u3v;
-pub(crate) mod gige;
+// GigE Vision devices need the GigE Vision control of `cameleon`, which isn't built yet.
+//pub(crate) mod gige;
 
 use crate::imp::port::{Port, TlType};
 
 mod u3v_genapi;
-mod gige_genapi;
+//mod gige_genapi;
 
 /// The current accessibility of the device.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -133,4 +145,25 @@ pub(crate) trait Device: Port {
 
     /// Tick frequency of the device’s timestamp counter in ticks per second
     fn timespamp_frequency(&self) -> GenTlResult<u64>;
+
+    /// The number of data streams of the device.
+    fn num_data_streams(&self) -> GenTlResult<usize>;
+
+    /// Data stream module of `index`.
+    fn data_stream(&self, index: usize) -> GenTlResult<&Mutex<DataStreamModule>>;
+
+    /// Starts the streaming loop of the data stream of `index`, then returns the receiver of
+    /// payloads.
+    /// Acquisition of the remote device isn't started, the consumer is responsible for executing
+    /// `AcquisitionStart` as the GenTL specification describes.
+    fn start_streaming(&mut self, index: usize) -> GenTlResult<PayloadReceiver>;
+
+    /// Stops the streaming loop of the data stream of `index`.
+    fn stop_streaming(&mut self, index: usize) -> GenTlResult<()>;
+
+    /// Returns the event queue of `event_type`.
+    ///
+    /// [`EventType::FeatureInvalidate`] is fired when the module changes its own features, and
+    /// [`EventType::RemoteDevice`] is fired when the remote device is lost.
+    fn event(&self, event_type: EventType) -> GenTlResult<Arc<EventQueue>>;
 }
@@ -2,38 +2,67 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use std::{convert::TryFrom, sync::Mutex};
+use std::{
+    collections::VecDeque,
+    convert::TryFrom,
+    sync::{Arc, Mutex},
+};
 
 use cameleon::{
     genapi::{CompressionType, SharedDefaultGenApiCtxt},
-    u3v::{self, SharedControlHandle, StreamHandle},
+    payload::{self, PayloadReceiver},
+    u3v::{
+        self,
+        register_map::{Abrm, GenICamFileType, ManifestEntry},
+        SharedControlHandle, StreamHandle,
+    },
+    ControlError, DeviceControl, PayloadStream,
 };
-use cameleon_impl::memory::prelude::*;
+use cameleon_impl::memory::{prelude::*, MemoryObserver};
 
 use crate::{
     imp::{
+        config,
+        data_stream::DataStreamModule,
+        event::{EventData, EventQueue, EventType},
         genapi_common,
-        port::{Endianness, ModuleType, Port, PortAccess, PortInfo, TlType, XmlInfo, XmlLocation},
+        port::{
+            self, Endianness, ModuleType, Port, PortAccess, PortInfo, TlType, XmlInfo, XmlLocation,
+        },
     },
     GenTlError, GenTlResult,
 };
 
-use super::{u3v_genapi as genapi, Device, DeviceAccessStatus};
+use super::{u3v_genapi as genapi, Device, DeviceAccessFlag, DeviceAccessStatus};
 use genapi::GenApiReg;
 
 type Camera = cameleon::Camera<SharedControlHandle, StreamHandle, SharedDefaultGenApiCtxt>;
 
 pub(crate) fn enumerate_u3v_device() -> GenTlResult<Vec<U3VDeviceModule>> {
-    todo!()
+    u3v::enumerate_cameras()?
+        .into_iter()
+        .map(|camera| {
+            let mut dev = U3VDeviceModule::new(camera.convert_into())?;
+            dev.probe_status();
+            Ok(dev)
+        })
+        .collect()
 }
 
+#[allow(clippy::vec_box)]
 pub(crate) struct U3VDeviceModule {
     vm: genapi::Memory,
     port_info: PortInfo,
     xml_infos: Vec<XmlInfo>,
 
+    device_info: u3v::DeviceInfo,
     camera: Camera,
     remote_device: Option<Box<Mutex<U3VRemoteDevice>>>,
+    data_streams: Vec<Box<Mutex<DataStreamModule>>>,
+
+    feature_invalidate_event: Arc<EventQueue>,
+    remote_device_event: Arc<EventQueue>,
+    memory_event_queue: Arc<Mutex<VecDeque<MemoryEvent>>>,
 
     /// Current status of the device.  
     /// `DeviceAccessStatus` and `DeviceAccessStatusReg` in VM doesn't reflect this value while
@@ -41,13 +70,13 @@ pub(crate) struct U3VDeviceModule {
     current_status: super::DeviceAccessStatus,
 }
 
-// TODO: Implement methods for stream and event channel.
+// TODO: Implement methods for event channel.
 impl U3VDeviceModule {
     pub(crate) fn new(camera: Camera) -> GenTlResult<Self> {
         let device_info = camera.ctrl.device_info();
 
         let port_info = PortInfo {
-            id: device_info.guid,
+            id: device_info.guid.clone(),
             vendor: genapi::VENDOR_NAME.into(),
             model: genapi::MODEL_NAME.into(),
             tl_type: genapi::DEVICE_TYPE,
@@ -81,23 +110,41 @@ impl U3VDeviceModule {
             compressed: CompressionType::Uncompressed,
         };
 
+        let remote_device_event = Arc::new(EventQueue::new(EventType::RemoteDevice));
+        let data_streams = (0..camera.stream_channel_len())
+            .map(|index| {
+                Box::new(Mutex::new(DataStreamModule::new(
+                    format!("Stream{}", index),
+                    TlType::USB3Vision,
+                    remote_device_event.clone(),
+                )))
+            })
+            .collect();
+
         let mut dev = Self {
             vm: genapi::Memory::new(),
             port_info,
             xml_infos: vec![xml_info],
 
+            device_info,
             camera,
             remote_device: None,
+            data_streams,
+
+            feature_invalidate_event: Arc::new(EventQueue::new(EventType::FeatureInvalidate)),
+            remote_device_event,
+            memory_event_queue: Arc::new(Mutex::new(VecDeque::new())),
 
             current_status: super::DeviceAccessStatus::Unknown,
         };
 
+        dev.register_observers();
         dev.initialize_vm()?;
         Ok(dev)
     }
 
     pub(crate) fn device_info(&self) -> &u3v::DeviceInfo {
-        todo!()
+        &self.device_info
     }
 
     /// Reflect current_status to `DeviceAccessStatusReg` in VM.
@@ -128,6 +175,24 @@ impl U3VDeviceModule {
         self.reflect_status();
     }
 
+    /// Checks whether the device can be opened by trying to open it.
+    /// USB3 Vision devices can't be shared, so the device is `Busy` if another process holds it.
+    fn probe_status(&mut self) {
+        if self.is_opened() {
+            return;
+        }
+
+        let status = match self.camera.ctrl.open() {
+            Ok(()) => {
+                self.camera.ctrl.close().ok();
+                DeviceAccessStatus::ReadWrite
+            }
+            Err(ControlError::Busy) => DeviceAccessStatus::Busy,
+            Err(_) => DeviceAccessStatus::NoAccess,
+        };
+        self.current_status = status;
+    }
+
     fn assert_open(&self) -> GenTlResult<()> {
         if self.is_opened() {
             Ok(())
@@ -136,18 +201,85 @@ impl U3VDeviceModule {
         }
     }
 
+    /// Streaming requires writes to the remote device, so it's denied in read only access.
+    fn assert_writable(&self) -> GenTlResult<()> {
+        self.assert_open()?;
+
+        if self.current_status == DeviceAccessStatus::OpenReadOnly {
+            Err(GenTlError::AccessDenied)
+        } else {
+            Ok(())
+        }
+    }
+
     fn is_opened(&self) -> bool {
         let current_status: DeviceAccessStatus = self.current_status;
         current_status.is_opened()
     }
 
-    #[allow(clippy::unused_self)]
-    fn handle_events(&mut self) {
-        // TODO: Handle stream related events.
+    fn register_observers(&mut self) {
+        let stream_selector_observer = StreamSelectorRegObserver(self.memory_event_queue.clone());
+        self.vm
+            .register_observer::<GenApiReg::StreamSelector, _>(stream_selector_observer);
+    }
+
+    fn handle_events(&mut self) -> GenTlResult<()> {
+        loop {
+            // Drop mutex guard in every iteration to avoid deadlock possibility.
+            let event = self.memory_event_queue.lock().unwrap().pop_front();
+
+            match event {
+                Some(MemoryEvent::StreamSelector) => self.handle_stream_selector_change()?,
+                None => break,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_stream_selector_change(&mut self) -> GenTlResult<()> {
+        let stream_idx = self.vm.read::<GenApiReg::StreamSelector>().unwrap() as usize;
+
+        let stream_id = self
+            .data_streams
+            .get(stream_idx)
+            .ok_or(GenTlError::InvalidIndex)?
+            .lock()
+            .unwrap()
+            .id()
+            .to_string();
+        self.vm.write::<GenApiReg::StreamID>(stream_id)?;
+
+        // `StreamID` is changed behind the consumer, so let it invalidate the cached value.
+        self.feature_invalidate_event
+            .notify_if_registered(EventData::FeatureInvalidate {
+                feature: "StreamID".into(),
+            });
+
+        Ok(())
     }
 
     fn initialize_vm(&mut self) -> GenTlResult<()> {
-        todo!()
+        self.vm
+            .write::<GenApiReg::DeviceID>(self.device_info.guid.clone())?;
+        self.vm
+            .write::<GenApiReg::DeviceVendorName>(self.device_info.vendor_name.clone())?;
+        self.vm
+            .write::<GenApiReg::DeviceModelName>(self.device_info.model_name.clone())?;
+        self.reflect_status();
+
+        let stream_selector_max = self.data_streams.len().saturating_sub(1) as u32;
+        self.vm
+            .write::<GenApiReg::StreamSelectorMax>(stream_selector_max)?;
+        self.vm.write::<GenApiReg::StreamSelector>(0)?;
+        // Clear events caused by the initialization.
+        self.memory_event_queue.lock().unwrap().clear();
+        if let Some(stream) = self.data_streams.first() {
+            let stream_id = stream.lock().unwrap().id().to_string();
+            self.vm.write::<GenApiReg::StreamID>(stream_id)?;
+        }
+
+        Ok(())
     }
 }
 
@@ -174,7 +306,7 @@ impl Port for U3VDeviceModule {
         self.assert_open()?;
 
         self.vm.write_raw(address as usize, data)?;
-        self.handle_events();
+        self.handle_events()?;
 
         Ok(data.len())
     }
@@ -193,12 +325,51 @@ impl Port for U3VDeviceModule {
 }
 
 impl Device for U3VDeviceModule {
-    fn open(&mut self, access_flag: super::DeviceAccessFlag) -> GenTlResult<()> {
-        todo!()
+    fn open(&mut self, access_flag: DeviceAccessFlag) -> GenTlResult<()> {
+        if self.is_opened() {
+            return Err(GenTlError::ResourceInUse);
+        }
+
+        // USB3 Vision devices can't be shared among hosts or processes, so `Control` is the same
+        // as `Exclusive`.
+        let (access, status) = match access_flag {
+            DeviceAccessFlag::ReadOnly => (PortAccess::RO, DeviceAccessStatus::OpenReadOnly),
+            DeviceAccessFlag::Control | DeviceAccessFlag::Exclusive => {
+                (PortAccess::RW, DeviceAccessStatus::OpenReadWrite)
+            }
+        };
+
+        if let Err(err) = self.camera.open() {
+            let err = GenTlError::from(err);
+            if matches!(err, GenTlError::ResourceInUse) {
+                self.current_status = DeviceAccessStatus::Busy;
+            }
+            return Err(err);
+        }
+
+        match U3VRemoteDevice::new(self.camera.ctrl.clone(), access) {
+            Ok(remote_device) => {
+                self.remote_device = Some(Box::new(Mutex::new(remote_device)));
+                self.current_status = status;
+                Ok(())
+            }
+            Err(err) => {
+                self.camera.close().ok();
+                Err(err)
+            }
+        }
     }
 
     fn close(&mut self) -> GenTlResult<()> {
-        todo!()
+        if !self.is_opened() {
+            return Ok(());
+        }
+
+        self.remote_device = None;
+        self.current_status = DeviceAccessStatus::ReadWrite;
+        self.camera.close()?;
+
+        Ok(())
     }
 
     fn device_id(&self) -> &str {
@@ -212,11 +383,11 @@ impl Device for U3VDeviceModule {
     }
 
     fn vendor_name(&self) -> GenTlResult<String> {
-        todo!()
+        Ok(self.device_info.vendor_name.clone())
     }
 
     fn model_name(&self) -> GenTlResult<String> {
-        todo!()
+        Ok(self.device_info.model_name.clone())
     }
 
     fn display_name(&self) -> GenTlResult<String> {
@@ -231,11 +402,14 @@ impl Device for U3VDeviceModule {
     }
 
     fn device_access_status(&self) -> DeviceAccessStatus {
-        todo!()
+        self.current_status
     }
 
     fn user_defined_name(&self) -> GenTlResult<String> {
-        todo!()
+        self.device_info
+            .user_defined_name
+            .clone()
+            .ok_or(GenTlError::NotAvailable)
     }
 
     fn serial_number(&self) -> GenTlResult<String> {
@@ -243,44 +417,212 @@ impl Device for U3VDeviceModule {
     }
 
     fn device_version(&self) -> GenTlResult<String> {
-        todo!()
+        Ok(self.device_info.device_version.clone())
     }
 
     fn timespamp_frequency(&self) -> GenTlResult<u64> {
-        todo!()
+        // Timestamps of USB3 Vision devices are always in nanoseconds.
+        Ok(1_000_000_000)
+    }
+
+    fn num_data_streams(&self) -> GenTlResult<usize> {
+        self.assert_open()?;
+
+        Ok(self.data_streams.len())
+    }
+
+    fn data_stream(&self, index: usize) -> GenTlResult<&Mutex<DataStreamModule>> {
+        self.assert_open()?;
+
+        self.data_streams
+            .get(index)
+            .map(AsRef::as_ref)
+            .ok_or(GenTlError::InvalidIndex)
+    }
+
+    fn start_streaming(&mut self, index: usize) -> GenTlResult<PayloadReceiver> {
+        self.assert_writable()?;
+
+        let camera = &mut self.camera;
+        let strm = match index {
+            0 => &mut camera.strm,
+            _ => camera
+                .extra_strms
+                .get_mut(index - 1)
+                .ok_or(GenTlError::InvalidIndex)?,
+        };
+
+        // The channel capacity is the number of payload buffers held by the streaming loop.
+        let capacity = config::config().stream.buffer_count;
+        let (sender, receiver) = payload::channel(capacity, capacity);
+        camera.ctrl.enable_streaming_channel(index)?;
+        strm.start_streaming_loop(sender, &mut camera.ctrl)?;
+
+        Ok(receiver)
+    }
+
+    fn stop_streaming(&mut self, index: usize) -> GenTlResult<()> {
+        self.assert_writable()?;
+
+        let camera = &mut self.camera;
+        let strm = match index {
+            0 => &mut camera.strm,
+            _ => camera
+                .extra_strms
+                .get_mut(index - 1)
+                .ok_or(GenTlError::InvalidIndex)?,
+        };
+
+        strm.stop_streaming_loop()?;
+        camera.ctrl.disable_streaming_channel(index)?;
+
+        Ok(())
+    }
+
+    fn event(&self, event_type: EventType) -> GenTlResult<Arc<EventQueue>> {
+        self.assert_open()?;
+
+        match event_type {
+            EventType::FeatureInvalidate => Ok(self.feature_invalidate_event.clone()),
+            EventType::RemoteDevice => Ok(self.remote_device_event.clone()),
+            _ => Err(GenTlError::NotImplemented),
+        }
     }
 }
 
-pub(crate) struct U3VRemoteDevice {}
+#[derive(Clone, Copy)]
+enum MemoryEvent {
+    StreamSelector,
+}
+
+#[derive(Clone)]
+struct StreamSelectorRegObserver(Arc<Mutex<VecDeque<MemoryEvent>>>);
+impl MemoryObserver for StreamSelectorRegObserver {
+    fn update(&self) {
+        self.0
+            .lock()
+            .unwrap()
+            .push_back(MemoryEvent::StreamSelector)
+    }
+}
+
+pub(crate) struct U3VRemoteDevice {
+    handle: SharedControlHandle,
+    port_info: PortInfo,
+    xml_infos: Vec<XmlInfo>,
+}
 
 impl U3VRemoteDevice {
-    fn new(handle: u3v::ControlHandle) -> GenTlResult<Self> {
-        todo!()
+    fn new(mut handle: SharedControlHandle, access: PortAccess) -> GenTlResult<Self> {
+        let port_info = Self::port_info(&handle, access);
+        let xml_infos = Self::xml_infos(&mut handle)?;
+
+        Ok(Self {
+            handle,
+            port_info,
+            xml_infos,
+        })
     }
 
-    fn port_info(handle: &u3v::ControlHandle) -> GenTlResult<PortInfo> {
-        todo!()
+    fn port_info(handle: &SharedControlHandle, access: PortAccess) -> PortInfo {
+        let device_info = handle.device_info();
+
+        PortInfo {
+            id: device_info.guid,
+            vendor: device_info.vendor_name,
+            model: device_info.model_name,
+            tl_type: TlType::USB3Vision,
+            module_type: ModuleType::RemoteDevice,
+            endianness: Endianness::LE,
+            access,
+            version: device_info.u3v_version,
+            port_name: "Device".into(),
+        }
     }
 
-    fn xml_infos(handle: &u3v::ControlHandle) -> GenTlResult<Vec<XmlInfo>> {
-        todo!()
+    /// Collects device description files in the manifest table, followed by the ones specified
+    /// in the configuration.
+    ///
+    /// Consumers usually load the first file only, so unusable manifest entries are skipped
+    /// rather than failing the whole port.
+    fn xml_infos(handle: &mut SharedControlHandle) -> GenTlResult<Vec<XmlInfo>> {
+        let abrm = Abrm::new(handle)?;
+        let manifest_table = abrm.manifest_table(handle)?;
+
+        let mut xml_infos = vec![];
+        for entry in manifest_table.entries(handle)? {
+            match Self::xml_info(handle, entry) {
+                Ok(Some(xml_info)) => xml_infos.push(xml_info),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("skipped a manifest entry of the device: {}", e),
+            }
+        }
+        xml_infos.extend(config::config().device.xml_infos());
+
+        if xml_infos.is_empty() {
+            Err(GenTlError::Error(
+                "the device has no usable device description file".into(),
+            ))
+        } else {
+            Ok(xml_infos)
+        }
+    }
+
+    /// Returns `None` if the entry isn't a device description file.
+    fn xml_info(
+        handle: &mut SharedControlHandle,
+        entry: ManifestEntry,
+    ) -> GenTlResult<Option<XmlInfo>> {
+        const HEAD_LEN: usize = 16;
+
+        let file_info = entry.file_info(handle)?;
+        if file_info.file_type()? != GenICamFileType::DeviceXml {
+            return Ok(None);
+        }
+
+        let address = entry.file_address(handle)?;
+        let size = entry.file_size(handle)? as usize;
+
+        // Some devices report a wrong compression type, so the actual type is detected from the
+        // content. This also filters out entries pointing to uninitialized memory.
+        let mut head = [0; HEAD_LEN];
+        let head = &mut head[..size.min(HEAD_LEN)];
+        handle.read(address, head)?;
+        let compressed = port::detect_xml_file(head).ok_or_else(|| {
+            GenTlError::Error(format!("the file at {:#X} is neither XML nor ZIP", address))
+        })?;
+
+        Ok(Some(XmlInfo {
+            location: XmlLocation::RegisterMap { address, size },
+            schema_version: file_info.schema_version(),
+            file_version: entry.genicam_file_version(handle)?,
+            sha1_hash: entry.sha1_hash(handle)?,
+            compressed,
+        }))
     }
 }
 
 impl Port for U3VRemoteDevice {
     fn read(&self, address: u64, buf: &mut [u8]) -> GenTlResult<usize> {
-        todo!()
+        // `DeviceControl::read` requires `&mut self`, but the handle is shared anyway.
+        self.handle.clone().read(address, buf)?;
+        Ok(buf.len())
     }
 
     fn write(&mut self, address: u64, data: &[u8]) -> GenTlResult<usize> {
-        todo!()
+        if !self.port_info.access.is_writable() {
+            return Err(GenTlError::AccessDenied);
+        }
+
+        self.handle.write(address, data)?;
+        Ok(data.len())
     }
 
     fn port_info(&self) -> GenTlResult<&PortInfo> {
-        todo!()
+        Ok(&self.port_info)
     }
 
     fn xml_infos(&self) -> GenTlResult<&[XmlInfo]> {
-        todo!()
+        Ok(&self.xml_infos)
     }
 }
@@ -0,0 +1,394 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{
+    collections::VecDeque,
+    sync::{Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{GenTlError, GenTlResult};
+
+use super::data_stream::BufferId;
+
+/// Event types defined in GenTL specification.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum EventType {
+    /// Notification on module errors.
+    Error,
+
+    /// Notification on newly filled buffers.
+    NewBuffer,
+
+    /// Notification if a feature was changed by the GenTL Producer library and thus needs to be
+    /// invalidated in the GenICam GenApi instance using the module.
+    FeatureInvalidate,
+
+    /// Notification if the GenTL Producer library wants to manually set a feature in the GenICam
+    /// GenApi instance using the module.
+    FeatureChange,
+
+    /// Notification if the GenTL Producer wants to inform the GenICam GenApi instance of the
+    /// remote device that a GenApi compatible event was fired.
+    RemoteDevice,
+
+    /// Notification if the GenTL Producer wants to inform the GenICam GenApi instance of the
+    /// module that a GenApi compatible event was fired.
+    Module,
+}
+
+/// ID of the remote device event fired when the device is lost.
+/// The ID is out of the range of event IDs sent from devices, which are 16 bit.
+pub(crate) const DEVICE_LOST_EVENT_ID: u64 = 0x1_0000;
+
+/// Max length of a feature name carried by [`EventData::FeatureInvalidate`].
+pub(crate) const MAX_FEATURE_NAME_LEN: usize = 256;
+
+/// Max length of data carried by [`EventData::RemoteDevice`].
+pub(crate) const MAX_REMOTE_DEVICE_EVENT_DATA_LEN: usize = 1024;
+
+/// Max length of a message carried by [`EventData::Error`].
+pub(crate) const MAX_ERROR_MESSAGE_LEN: usize = 256;
+
+/// Data carried by an event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum EventData {
+    /// A buffer is filled and ready to be delivered to the consumer.
+    NewBuffer {
+        buffer: BufferId,
+        /// User pointer passed when the buffer was announced.
+        user_ptr: usize,
+    },
+
+    /// A feature of the module is changed by the producer, the consumer needs to invalidate the
+    /// cache of the feature.
+    FeatureInvalidate { feature: String },
+
+    /// An event is sent from the remote device.
+    RemoteDevice { event_id: u64, data: Vec<u8> },
+
+    /// An error occurred in the module.
+    Error {
+        /// Error code defined in GenTL specification.
+        code: i32,
+        /// ASCII description of the error.
+        message: String,
+    },
+}
+
+impl EventData {
+    /// Returns the event notifying that the device is lost.
+    pub(crate) fn device_lost() -> Self {
+        Self::RemoteDevice {
+            event_id: DEVICE_LOST_EVENT_ID,
+            data: vec![],
+        }
+    }
+
+    /// Returns the event notifying `err`.
+    pub(crate) fn error(err: &GenTlError) -> Self {
+        // Messages are passed to the consumer as ASCII strings.
+        let message = err
+            .to_string()
+            .chars()
+            .map(|c| if c.is_ascii() && c != '\0' { c } else { '?' })
+            .take(MAX_ERROR_MESSAGE_LEN)
+            .collect();
+
+        Self::Error {
+            code: err.code(),
+            message,
+        }
+    }
+
+    /// Returns the buffer of [`EventData::NewBuffer`].
+    pub(crate) fn buffer(&self) -> Option<BufferId> {
+        match self {
+            Self::NewBuffer { buffer, .. } => Some(*buffer),
+            _ => None,
+        }
+    }
+}
+
+/// Queue of fired events.
+///
+/// Events are pushed by the producer and popped by the consumer through `EventGetData`, which may
+/// block until an event arrives.
+pub(crate) struct EventQueue {
+    event_type: EventType,
+    inner: Mutex<EventQueueInner>,
+    cond: Condvar,
+}
+
+struct EventQueueInner {
+    events: VecDeque<EventData>,
+    is_registered: bool,
+    num_fired: u64,
+    /// The number of pending `kill` requests, each of them aborts exactly one wait.
+    num_kill: usize,
+}
+
+impl EventQueue {
+    pub(crate) fn new(event_type: EventType) -> Self {
+        Self {
+            event_type,
+            inner: Mutex::new(EventQueueInner {
+                events: VecDeque::new(),
+                is_registered: false,
+                num_fired: 0,
+                num_kill: 0,
+            }),
+            cond: Condvar::new(),
+        }
+    }
+
+    pub(crate) fn event_type(&self) -> EventType {
+        self.event_type
+    }
+
+    /// Registers the event.
+    ///
+    /// Returns [`GenTlError::ResourceInUse`] if the event is already registered.
+    pub(crate) fn register(&self) -> GenTlResult<()> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.is_registered {
+            Err(GenTlError::ResourceInUse)
+        } else {
+            inner.is_registered = true;
+            Ok(())
+        }
+    }
+
+    /// Unregisters the event. Waiting consumers are aborted.
+    pub(crate) fn unregister(&self) -> GenTlResult<()> {
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.is_registered {
+            return Err(GenTlError::NotInitialized);
+        }
+        inner.is_registered = false;
+        inner.num_kill = 0;
+        self.cond.notify_all();
+        Ok(())
+    }
+
+    pub(crate) fn is_registered(&self) -> bool {
+        self.inner.lock().unwrap().is_registered
+    }
+
+    /// Fires the event.
+    pub(crate) fn notify(&self, data: EventData) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.events.push_back(data);
+        inner.num_fired += 1;
+        self.cond.notify_one();
+    }
+
+    /// Fires the event only if the event is registered, so that notifications nobody waits for
+    /// don't pile up in the queue.
+    pub(crate) fn notify_if_registered(&self, data: EventData) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.is_registered {
+            inner.events.push_back(data);
+            inner.num_fired += 1;
+            self.cond.notify_one();
+        }
+    }
+
+    /// Waits until an event is fired, then pops it from the queue.
+    ///
+    /// `None` means waiting infinitely.
+    pub(crate) fn wait(&self, timeout: Option<Duration>) -> GenTlResult<EventData> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut inner = self.inner.lock().unwrap();
+
+        loop {
+            if !inner.is_registered {
+                return Err(GenTlError::Abort);
+            }
+            if inner.num_kill > 0 {
+                inner.num_kill -= 1;
+                return Err(GenTlError::Abort);
+            }
+            if let Some(data) = inner.events.pop_front() {
+                return Ok(data);
+            }
+
+            inner = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(GenTlError::Timeout);
+                    }
+                    self.cond.wait_timeout(inner, deadline - now).unwrap().0
+                }
+                None => self.cond.wait(inner).unwrap(),
+            };
+        }
+    }
+
+    /// Aborts one wait of the event, the next wait is aborted if no one waits for the event.
+    pub(crate) fn kill(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.num_kill += 1;
+        self.cond.notify_one();
+    }
+
+    /// Discards all events in the queue and returns them.
+    pub(crate) fn flush(&self) -> Vec<EventData> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.events.drain(..).collect()
+    }
+
+    /// Returns `true` if any event in the queue satisfies `f`.
+    pub(crate) fn contains(&self, f: impl Fn(&EventData) -> bool) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner.events.iter().any(f)
+    }
+
+    /// Removes events which satisfy `f` from the queue.
+    pub(crate) fn remove_if(&self, f: impl Fn(&EventData) -> bool) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.events.retain(|data| !f(data));
+    }
+
+    /// The number of events in the queue.
+    pub(crate) fn num_in_queue(&self) -> usize {
+        self.inner.lock().unwrap().events.len()
+    }
+
+    /// The number of events fired since the queue is created.
+    pub(crate) fn num_fired(&self) -> u64 {
+        self.inner.lock().unwrap().num_fired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread};
+
+    use super::*;
+
+    fn new_buffer(id: usize) -> EventData {
+        EventData::NewBuffer {
+            buffer: BufferId::new(id),
+            user_ptr: 0,
+        }
+    }
+
+    #[test]
+    fn test_wait_event() {
+        let queue = Arc::new(EventQueue::new(EventType::NewBuffer));
+        queue.register().unwrap();
+        assert!(queue.register().is_err());
+
+        let notifier = queue.clone();
+        let handle = thread::spawn(move || notifier.notify(new_buffer(1)));
+
+        assert_eq!(queue.wait(None).unwrap(), new_buffer(1));
+        handle.join().unwrap();
+        assert_eq!(queue.num_fired(), 1);
+        assert_eq!(queue.num_in_queue(), 0);
+    }
+
+    #[test]
+    fn test_wait_timeout() {
+        let queue = EventQueue::new(EventType::NewBuffer);
+        queue.register().unwrap();
+
+        assert!(matches!(
+            queue.wait(Some(Duration::from_millis(10))),
+            Err(GenTlError::Timeout)
+        ));
+    }
+
+    #[test]
+    fn test_kill() {
+        let queue = EventQueue::new(EventType::NewBuffer);
+        queue.register().unwrap();
+        queue.notify(new_buffer(1));
+        queue.kill();
+
+        assert!(matches!(queue.wait(None), Err(GenTlError::Abort)));
+        assert_eq!(queue.wait(None).unwrap(), new_buffer(1));
+    }
+
+    #[test]
+    fn test_concurrent_wait() {
+        const NUM_WAITERS: usize = 4;
+        const NUM_EVENTS: usize = 400;
+
+        let queue = Arc::new(EventQueue::new(EventType::NewBuffer));
+        queue.register().unwrap();
+
+        // Each waiter receives events until the event is unregistered.
+        let waiters: Vec<_> = (0..NUM_WAITERS)
+            .map(|_| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    let mut num_received = 0;
+                    while queue.wait(None).is_ok() {
+                        num_received += 1;
+                    }
+                    num_received
+                })
+            })
+            .collect();
+
+        for i in 0..NUM_EVENTS {
+            queue.notify(new_buffer(i));
+        }
+        while queue.num_in_queue() > 0 {
+            thread::yield_now();
+        }
+        queue.unregister().unwrap();
+
+        let num_received: usize = waiters.into_iter().map(|w| w.join().unwrap()).sum();
+        assert_eq!(num_received, NUM_EVENTS);
+    }
+
+    #[test]
+    fn test_notify_if_registered() {
+        let queue = EventQueue::new(EventType::FeatureInvalidate);
+        let data = EventData::FeatureInvalidate {
+            feature: "StreamID".into(),
+        };
+        queue.notify_if_registered(data.clone());
+        assert_eq!(queue.num_in_queue(), 0);
+
+        queue.register().unwrap();
+        queue.notify_if_registered(data.clone());
+        assert_eq!(queue.wait(None).unwrap(), data);
+    }
+
+    #[test]
+    fn test_error_event() {
+        let err = GenTlError::InvalidValue("caf\u{e9}".into());
+        let data = EventData::error(&err);
+        assert_eq!(
+            data,
+            EventData::Error {
+                code: -1019,
+                message: "an invalid value has been written: caf?".into(),
+            }
+        );
+
+        let err = GenTlError::InvalidValue("a".repeat(MAX_ERROR_MESSAGE_LEN).into());
+        match EventData::error(&err) {
+            EventData::Error { message, .. } => assert_eq!(message.len(), MAX_ERROR_MESSAGE_LEN),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_flush() {
+        let queue = EventQueue::new(EventType::NewBuffer);
+        queue.register().unwrap();
+        queue.notify(new_buffer(1));
+        queue.notify(new_buffer(2));
+
+        assert_eq!(queue.flush(), vec![new_buffer(1), new_buffer(2)]);
+        assert_eq!(queue.num_in_queue(), 0);
+        assert_eq!(queue.num_fired(), 2);
+    }
+}
@@ -5,6 +5,7 @@
  use std::{
     collections::VecDeque,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use cameleon::genapi::CompressionType;
@@ -12,33 +13,31 @@ use cameleon_impl::memory::{prelude::*, MemoryObserver};
 
 use crate::{
     imp::{
-        device::{
-            gev::{enumerate_gev_device, GEVDeviceModule},
-            Device, DeviceAccessStatus,
-        },
+        config,
+        device::Device,
         genapi_common,
         port::{Endianness, ModuleType, Port, PortAccess, PortInfo, TlType, XmlInfo, XmlLocation},
     },
     GenTlError, GenTlResult,
 };
 
-use super::{gev_genapi as genapi, Interface};
+use super::{gige_genapi as genapi, nic::NetworkInterface, Interface};
 use genapi::GenApiReg;
 
-#[allow(clippy::vec_box)]
 pub(crate) struct GEVInterfaceModule {
     vm: genapi::Memory,
+    /// Host network interface the module represents.
+    nic: NetworkInterface,
     port_info: PortInfo,
     xml_infos: Vec<XmlInfo>,
     is_opened: bool,
-    devices: Vec<Box<Mutex<GEVDeviceModule>>>,
     event_queue: Arc<Mutex<VecDeque<MemoryEvent>>>,
 }
 
 impl GEVInterfaceModule {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(nic: NetworkInterface) -> Self {
         let port_info = PortInfo {
-            id: genapi::INTERFACE_ID.into(),
+            id: nic.name.clone(),
             vendor: genapi::VENDOR_NAME.into(),
             model: genapi::MODEL_NAME.into(),
             tl_type: genapi::INTERFACE_TYPE,
@@ -74,11 +73,10 @@ impl GEVInterfaceModule {
 
         let mut module = Self {
             vm: genapi::Memory::new(),
+            nic,
             port_info,
             xml_infos: vec![xml_info],
             is_opened: false,
-
-            devices: vec![],
             event_queue: Arc::new(Mutex::new(VecDeque::new())),
         };
 
@@ -86,55 +84,13 @@ impl GEVInterfaceModule {
         module
     }
 
-    fn update_device_list(&mut self) -> GenTlResult<bool> {
+    /// Discovers devices within `timeout`.
+    // TODO: Discover devices once the GigE Vision control of `cameleon` is built. Until then, no
+    // device is connected to the interface.
+    fn update_device_list(&mut self, _timeout: Duration) -> GenTlResult<bool> {
         self.assert_open()?;
 
-        // First, reflect current device status.
-        for device in &self.devices {
-            device.lock().unwrap().reflect_status();
-        }
-
-        // Enumerate devices connected to the interface.
-        let found_devices = enumerate_gev_device()?
-            .into_iter()
-            .map(|dev| Box::new(Mutex::new(dev)));
-
-        let mut changed = false;
-
-        for found_device in found_devices {
-            let found_device_guard = found_device.lock().unwrap();
-            let id = found_device_guard.device_id();
-
-            if let Some(device) = self.find_device_by_id(id)? {
-                // If device has already been found and its current status is NoAccess, then close
-                // it and change its status to Unknown(initial state).
-                let mut device_guard = device.lock().unwrap();
-                if device_guard.access_status() == DeviceAccessStatus::NoAccess {
-                    device_guard.close().ok();
-                    device_guard.force_access_status(DeviceAccessStatus::Unknown);
-                    changed = true;
-                }
-            } else {
-                // If device hasn't been found, then just add it to device pool.
-                drop(found_device_guard);
-                self.devices.push(found_device);
-                changed = true;
-            }
-        }
-
-        if changed {
-            for device in &self.devices {
-                let mut device_guard = device.lock().unwrap();
-                device_guard.reflect_status();
-            }
-
-            self.vm
-                .write::<GenApiReg::DeviceSelectorMax>(self.devices.len() as u32 - 1)
-                .unwrap();
-            self.handle_device_selector_change()?;
-        }
-
-        Ok(changed)
+        Ok(false)
     }
 
     fn assert_open(&self) -> GenTlResult<()> {
@@ -145,16 +101,6 @@ impl GEVInterfaceModule {
         }
     }
 
-    fn find_device_by_id(&self, id: &str) -> GenTlResult<Option<&Mutex<GEVDeviceModule>>> {
-        for dev in &self.devices {
-            if dev.lock().unwrap().port_info()?.id == id {
-                return Ok(Some(dev.as_ref()));
-            }
-        }
-
-        Ok(None)
-    }
-
     fn initialize_vm(&mut self) {
         self.vm.write::<GenApiReg::DeviceSelectorMax>(0).unwrap();
         self.vm.write::<GenApiReg::DeviceSelector>(0).unwrap();
@@ -179,7 +125,7 @@ impl GEVInterfaceModule {
 
             match event {
                 Some(MemoryEvent::DeviceUpdateList) => {
-                    self.update_device_list()?;
+                    self.update_device_list(config::config().gige.discovery_timeout())?;
                 }
                 Some(MemoryEvent::DeviceSelector) => self.handle_device_selector_change()?,
                 None => break,
@@ -190,30 +136,8 @@ impl GEVInterfaceModule {
     }
 
     fn handle_device_selector_change(&mut self) -> GenTlResult<()> {
-        let device_idx = self.vm.read::<GenApiReg::DeviceSelector>().unwrap() as usize;
-
-        if device_idx >= self.devices.len() {
-            return Err(GenTlError::InvalidIndex);
-        }
-
-        let device = self.devices[device_idx].lock().unwrap();
-        let device_info = device.device_info();
-
-        self.vm
-            .write::<GenApiReg::DeviceID>(device.port_info()?.id.clone())?;
-
-        self.vm
-            .write::<GenApiReg::DeviceVendorName>(device_info.vendor_name.clone())
-            .unwrap();
-
-        self.vm
-            .write::<GenApiReg::DeviceModelName>(device_info.model_name.clone())?;
-
-        let status: DeviceAccessStatus = device.access_status();
-        self.vm
-            .write::<GenApiReg::DeviceAccessStatus>(status as u32)?;
-
-        Ok(())
+        // No device is connected to the interface, so any index is out of range.
+        Err(GenTlError::InvalidIndex)
     }
 }
 
@@ -288,20 +212,17 @@ impl Interface for GEVInterfaceModule {
     }
 
     fn close(&mut self) -> GenTlResult<()> {
-        for dev in &self.devices {
-            dev.lock().unwrap().close()?;
-        }
-
         self.is_opened = false;
         Ok(())
     }
 
+    // Interface names are unique in the host, so they are used as interface IDs.
     fn interface_id(&self) -> &str {
-        genapi::INTERFACE_ID
+        &self.nic.name
     }
 
     fn display_name(&self) -> &str {
-        "GEV Interface Module"
+        &self.nic.name
     }
 
     fn tl_type(&self) -> TlType {
@@ -309,39 +230,28 @@ impl Interface for GEVInterfaceModule {
     }
 
     fn mac_addr(&self) -> Option<[u8; 6]> {
-        None
+        self.nic.mac_addr
     }
 
     fn ip_addr(&self) -> Option<std::net::Ipv4Addr> {
-        None
+        Some(self.nic.ip_addr)
     }
 
     fn subnet_mask(&self) -> Option<std::net::Ipv4Addr> {
-        None
+        Some(self.nic.subnet_mask)
     }
 
     fn gateway_addr(&self) -> Option<std::net::Ipv4Addr> {
-        None
+        self.nic.gateway_addr
     }
 
     fn devices(&self) -> Vec<&Mutex<dyn Device>> {
-        let mut dyn_devices: Vec<&Mutex<dyn Device>> = Vec::with_capacity(self.devices.len());
-        for dev in &self.devices {
-            dyn_devices.push(dev.as_ref());
-        }
-        dyn_devices
+        vec![]
     }
 
-    // NOTE: We ignore timeout for now.
-    fn update_device_list(&mut self, _timeout: std::time::Duration) -> GenTlResult<bool> {
+    fn update_device_list(&mut self, timeout: std::time::Duration) -> GenTlResult<bool> {
         self.assert_open()?;
 
-        self.update_device_list()
-    }
-}
-
-impl Default for GEVInterfaceModule {
-    fn default() -> Self {
-        Self::new()
+        self.update_device_list(timeout)
     }
 }
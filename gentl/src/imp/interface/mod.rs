@@ -12,6 +12,7 @@ use crate::{
 
 pub(crate) mod u3v;
 pub(crate) mod gige;
+pub(crate) mod nic;
 
 mod u3v_genapi;
 mod gige_genapi;
@@ -0,0 +1,240 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Enumeration of host network interfaces which GEV devices can be connected to.
+
+use std::net::Ipv4Addr;
+
+use crate::GenTlResult;
+
+/// A host network interface with an IPv4 address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct NetworkInterface {
+    /// Name of the interface, e.g. `eth0`.
+    pub(crate) name: String,
+
+    pub(crate) mac_addr: Option<[u8; 6]>,
+
+    pub(crate) ip_addr: Ipv4Addr,
+
+    pub(crate) subnet_mask: Ipv4Addr,
+
+    /// Gateway of the default route through the interface.
+    pub(crate) gateway_addr: Option<Ipv4Addr>,
+}
+
+/// Enumerates network interfaces that are up and have an IPv4 address.
+/// Loopback interfaces are excluded.
+pub(crate) fn enumerate_network_interfaces() -> GenTlResult<Vec<NetworkInterface>> {
+    let mut nics = sys::enumerate()?;
+    let gateways = sys::default_gateways();
+    for nic in &mut nics {
+        nic.gateway_addr = gateways
+            .iter()
+            .find(|(name, _)| name == &nic.name)
+            .map(|(_, gateway)| *gateway);
+    }
+
+    Ok(nics)
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::{ffi::CStr, net::Ipv4Addr};
+
+    use crate::{GenTlError, GenTlResult};
+
+    use super::NetworkInterface;
+
+    pub(super) fn enumerate() -> GenTlResult<Vec<NetworkInterface>> {
+        let mut ifap: *mut libc::ifaddrs = std::ptr::null_mut();
+        if unsafe { libc::getifaddrs(&mut ifap) } != 0 {
+            return Err(GenTlError::Io(std::io::Error::last_os_error().into()));
+        }
+
+        let mut nics = vec![];
+        let mut macs = vec![];
+        let mut cur = ifap;
+        while let Some(ifa) = unsafe { cur.as_ref() } {
+            cur = ifa.ifa_next;
+
+            let flags = ifa.ifa_flags as libc::c_int;
+            if ifa.ifa_addr.is_null()
+                || flags & libc::IFF_UP == 0
+                || flags & libc::IFF_LOOPBACK != 0
+            {
+                continue;
+            }
+            let name = unsafe { CStr::from_ptr(ifa.ifa_name) }
+                .to_string_lossy()
+                .into_owned();
+
+            let family = libc::c_int::from(unsafe { (*ifa.ifa_addr).sa_family });
+            if family == libc::AF_INET {
+                let ip_addr = unsafe { ipv4_addr(ifa.ifa_addr) };
+                let subnet_mask = if ifa.ifa_netmask.is_null() {
+                    Ipv4Addr::UNSPECIFIED
+                } else {
+                    unsafe { ipv4_addr(ifa.ifa_netmask) }
+                };
+                nics.push(NetworkInterface {
+                    name,
+                    mac_addr: None,
+                    ip_addr,
+                    subnet_mask,
+                    gateway_addr: None,
+                });
+            } else if let Some(mac_addr) = unsafe { mac_addr(ifa.ifa_addr) } {
+                macs.push((name, mac_addr));
+            }
+        }
+        unsafe { libc::freeifaddrs(ifap) };
+
+        for nic in &mut nics {
+            nic.mac_addr = macs
+                .iter()
+                .find(|(name, _)| name == &nic.name)
+                .map(|(_, mac)| *mac);
+        }
+
+        Ok(nics)
+    }
+
+    unsafe fn ipv4_addr(addr: *const libc::sockaddr) -> Ipv4Addr {
+        let addr = &*addr.cast::<libc::sockaddr_in>();
+        Ipv4Addr::from(addr.sin_addr.s_addr.to_ne_bytes())
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    unsafe fn mac_addr(addr: *const libc::sockaddr) -> Option<[u8; 6]> {
+        if libc::c_int::from((*addr).sa_family) != libc::AF_PACKET {
+            return None;
+        }
+        let addr = &*addr.cast::<libc::sockaddr_ll>();
+        if addr.sll_halen != 6 {
+            return None;
+        }
+
+        let mut mac = [0; 6];
+        mac.copy_from_slice(&addr.sll_addr[..6]);
+        Some(mac)
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+    unsafe fn mac_addr(addr: *const libc::sockaddr) -> Option<[u8; 6]> {
+        if libc::c_int::from((*addr).sa_family) != libc::AF_LINK {
+            return None;
+        }
+        let addr = &*addr.cast::<libc::sockaddr_dl>();
+        if addr.sdl_alen != 6 {
+            return None;
+        }
+
+        // The link layer address follows the interface name in `sdl_data`.
+        let data = addr
+            .sdl_data
+            .as_ptr()
+            .add(addr.sdl_nlen as usize)
+            .cast::<u8>();
+        let mut mac = [0; 6];
+        mac.copy_from_slice(std::slice::from_raw_parts(data, 6));
+        Some(mac)
+    }
+
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd"
+    )))]
+    unsafe fn mac_addr(_addr: *const libc::sockaddr) -> Option<[u8; 6]> {
+        None
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(super) fn default_gateways() -> Vec<(String, Ipv4Addr)> {
+        std::fs::read_to_string("/proc/net/route")
+            .map(|table| super::parse_route_table(&table))
+            .unwrap_or_default()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(super) fn default_gateways() -> Vec<(String, Ipv4Addr)> {
+        vec![]
+    }
+}
+
+// TODO: Enumerate interfaces with `GetAdaptersAddresses` on Windows.
+#[cfg(not(unix))]
+mod sys {
+    use std::net::Ipv4Addr;
+
+    use crate::GenTlResult;
+
+    use super::NetworkInterface;
+
+    pub(super) fn enumerate() -> GenTlResult<Vec<NetworkInterface>> {
+        Ok(vec![])
+    }
+
+    pub(super) fn default_gateways() -> Vec<(String, Ipv4Addr)> {
+        vec![]
+    }
+}
+
+/// Parses `/proc/net/route` and returns pairs of an interface name and the gateway of its default
+/// route.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_route_table(table: &str) -> Vec<(String, Ipv4Addr)> {
+    // Fields are `Iface Destination Gateway Flags ...`, and addresses are hex dumps of values in
+    // network byte order.
+    const RTF_GATEWAY: u32 = 0x2;
+
+    let parse_addr = |field: &str| {
+        u32::from_str_radix(field, 16)
+            .ok()
+            .map(|raw| Ipv4Addr::from(raw.to_ne_bytes()))
+    };
+
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?;
+            let destination = parse_addr(fields.next()?)?;
+            let gateway = parse_addr(fields.next()?)?;
+            let flags = u32::from_str_radix(fields.next()?, 16).ok()?;
+
+            if destination.is_unspecified() && flags & RTF_GATEWAY != 0 {
+                Some((name.to_string(), gateway))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // The table is dumped on a little endian host.
+    #[cfg(target_endian = "little")]
+    fn test_parse_route_table() {
+        let table = "\
+Iface	Destination	Gateway 	Flags	RefCnt	Use	Metric	Mask		MTU	Window	IRTT
+eth0	00000000	0101A8C0	0003	0	0	100	00000000	0	0	0
+eth0	0001A8C0	00000000	0001	0	0	100	00FFFFFF	0	0	0
+eth1	0000FEA9	00000000	0001	0	0	0	0000FFFF	0	0	0
+";
+
+        assert_eq!(
+            parse_route_table(table),
+            vec![("eth0".to_string(), Ipv4Addr::new(192, 168, 1, 1))]
+        );
+    }
+}
@@ -113,6 +113,13 @@ impl U3VInterfaceModule {
                     device_guard.close().ok();
                     device_guard.force_access_status(DeviceAccessStatus::Unknown);
                     changed = true;
+                } else if !device_guard.device_access_status().is_opened() {
+                    // Another process may have opened or released the device in the meantime.
+                    let status = found_device_guard.device_access_status();
+                    if device_guard.access_status() != status {
+                        device_guard.force_access_status(status);
+                        changed = true;
+                    }
                 }
             } else {
                 // If device hasn't been found, then just add it to device pool.
@@ -2,14 +2,17 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+pub(super) mod config;
+pub(super) mod data_stream;
 pub(super) mod device;
+pub(super) mod event;
 pub(super) mod interface;
 pub(super) mod port;
 pub(super) mod system;
 
 mod genapi_common;
 
-use cameleon::ControlError;
+use cameleon::{CameleonError, ControlError, StreamError};
 use cameleon_impl::memory::MemoryError;
 
 use super::GenTlError;
@@ -26,8 +29,10 @@ impl From<MemoryError> for GenTlError {
 
 impl From<ControlError> for GenTlError {
     fn from(err: ControlError) -> Self {
+        use cameleon::StatusKind;
         use GenTlError::{
-            BufferTooSmall, InvalidValue, Io, NotInitialized, ResourceInUse, Timeout,
+            Abort, AccessDenied, BufferTooSmall, InvalidAddress, InvalidParameter, InvalidValue,
+            Io, NotImplemented, NotInitialized, ResourceInUse, Timeout,
         };
 
         match err {
@@ -39,6 +44,39 @@ impl From<ControlError> for GenTlError {
             ControlError::InvalidData(..) => InvalidValue(format!("{}", err).into()),
             ControlError::Timeout => Timeout,
             ControlError::BufferTooSmall => BufferTooSmall,
+            ControlError::Cancelled => Abort,
+            ControlError::DeviceStatus(status) => match status.kind() {
+                StatusKind::NotImplemented => NotImplemented,
+                StatusKind::InvalidParameter => InvalidParameter,
+                StatusKind::InvalidAddress | StatusKind::BadAlignment => InvalidAddress,
+                StatusKind::WriteProtect | StatusKind::AccessDenied => AccessDenied,
+                StatusKind::Timeout => Timeout,
+                _ => Io(err.into()),
+            },
+        }
+    }
+}
+
+impl From<CameleonError> for GenTlError {
+    fn from(err: CameleonError) -> Self {
+        match err {
+            CameleonError::ControlError(err) => err.into(),
+            CameleonError::StreamError(err) => err.into(),
+            _ => Self::Error(err.to_string()),
+        }
+    }
+}
+
+impl From<StreamError> for GenTlError {
+    fn from(err: StreamError) -> Self {
+        use GenTlError::{Abort, BufferTooSmall, Io, ResourceInUse, Timeout};
+
+        match err {
+            StreamError::InStreaming => ResourceInUse,
+            StreamError::Timeout => Timeout,
+            StreamError::BufferTooSmall => BufferTooSmall,
+            StreamError::Cancelled => Abort,
+            _ => Io(err.into()),
         }
     }
 }
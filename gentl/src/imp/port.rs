@@ -5,7 +5,7 @@
 use cameleon::genapi::CompressionType;
 use semver::Version;
 
-use crate::GenTlResult;
+use crate::{GenTlError, GenTlResult};
 
 pub(crate) trait Port {
     /// Reads a number of bytes from a given address from the Port. This is the global
@@ -192,9 +192,170 @@ pub(crate) struct XmlInfo {
     pub(crate) compressed: CompressionType,
 }
 
-#[derive(Clone)]
+impl XmlInfo {
+    /// `GenApi` schema version assumed when a URL doesn't specify it.
+    const DEFAULT_SCHEMA_VERSION: Version = Version::new(1, 1, 0);
+
+    /// Parses a URL of a device description file, which is one of
+    ///
+    /// * `local:[///]{filename}.{extension};{address};{length}[?SchemaVersion=x.x.x]`, where
+    ///   `address` and `length` are hexadecimal.
+    /// * `file:///{path}[?SchemaVersion=x.x.x]`
+    /// * `http://{host}/{path}[?SchemaVersion=x.x.x]`
+    ///
+    /// Schemes are case insensitive, and the file is regarded as zipped if its extension is `zip`.
+    pub(crate) fn from_url(url: &str) -> GenTlResult<Self> {
+        let invalid = || GenTlError::InvalidValue(format!("invalid xml URL: {}", url).into());
+
+        let (body, query) = match url.split_once('?') {
+            Some((body, query)) => (body, Some(query)),
+            None => (url, None),
+        };
+        let schema_version = match query {
+            Some(query) => query
+                .split('&')
+                .find_map(|param| param.strip_prefix("SchemaVersion="))
+                .map_or(Ok(Self::DEFAULT_SCHEMA_VERSION), Version::parse)
+                .map_err(|_| invalid())?,
+            None => Self::DEFAULT_SCHEMA_VERSION,
+        };
+
+        let (scheme, rest) = body.split_once(':').ok_or_else(invalid)?;
+        let mut file_name = body;
+        let location = match scheme.to_ascii_lowercase().as_str() {
+            "local" => {
+                let mut fields = rest.trim_start_matches('/').split(';');
+                let (address, size) = match (fields.next(), fields.next(), fields.next()) {
+                    (Some(name), Some(address), Some(size)) if fields.next().is_none() => {
+                        file_name = name;
+                        (address, size)
+                    }
+                    _ => return Err(invalid()),
+                };
+                let hex = |s: &str| {
+                    let s = s.trim_start_matches("0x").trim_start_matches("0X");
+                    u64::from_str_radix(s, 16).map_err(|_| invalid())
+                };
+                XmlLocation::RegisterMap {
+                    address: hex(address)?,
+                    size: hex(size)? as usize,
+                }
+            }
+            "file" => {
+                let url = url::Url::parse(body).map_err(|_| invalid())?;
+                XmlLocation::LocalFile(url.to_file_path().map_err(|_| invalid())?)
+            }
+            "http" | "https" => XmlLocation::Url(url::Url::parse(body).map_err(|_| invalid())?),
+            _ => return Err(invalid()),
+        };
+
+        let compressed = if file_name.to_ascii_lowercase().ends_with(".zip") {
+            CompressionType::Zip
+        } else {
+            CompressionType::Uncompressed
+        };
+
+        Ok(Self {
+            location,
+            schema_version,
+            file_version: Version::new(0, 0, 0),
+            sha1_hash: None,
+            compressed,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) enum XmlLocation {
     RegisterMap { address: u64, size: usize },
     LocalFile(std::path::PathBuf),
     Url(url::Url),
 }
+
+/// Detects the compression type of a device description file from its first bytes.
+///
+/// Returns `None` if the file is neither a ZIP archive nor an XML document, e.g. the memory
+/// isn't initialized.
+pub(crate) fn detect_xml_file(head: &[u8]) -> Option<CompressionType> {
+    const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+    if CompressionType::detect(head) == CompressionType::Zip {
+        return Some(CompressionType::Zip);
+    }
+
+    let text = head.strip_prefix(UTF8_BOM).unwrap_or(head);
+    match text.iter().find(|b| !b.is_ascii_whitespace()) {
+        Some(b'<') => Some(CompressionType::Uncompressed),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_url() {
+        let info = XmlInfo::from_url("Local:///device.zip;1000;2a0?SchemaVersion=1.0.0").unwrap();
+        assert_eq!(
+            info.location,
+            XmlLocation::RegisterMap {
+                address: 0x1000,
+                size: 0x2a0
+            }
+        );
+        assert_eq!(info.schema_version, Version::new(1, 0, 0));
+        assert_eq!(info.compressed, CompressionType::Zip);
+
+        let info = XmlInfo::from_url("local:device.xml;0x8000;0X100").unwrap();
+        assert_eq!(
+            info.location,
+            XmlLocation::RegisterMap {
+                address: 0x8000,
+                size: 0x100
+            }
+        );
+        assert_eq!(info.schema_version, XmlInfo::DEFAULT_SCHEMA_VERSION);
+        assert_eq!(info.compressed, CompressionType::Uncompressed);
+
+        assert!(XmlInfo::from_url("local:///device.xml;1000").is_err());
+        assert!(XmlInfo::from_url("local:///device.xml;1000;zz").is_err());
+        assert!(XmlInfo::from_url("local:///device.xml;1000;10;10").is_err());
+    }
+
+    #[test]
+    fn test_file_and_http_url() {
+        let info = XmlInfo::from_url("File:///tmp/device.ZIP?SchemaVersion=1.1.0").unwrap();
+        assert_eq!(
+            info.location,
+            XmlLocation::LocalFile("/tmp/device.ZIP".into())
+        );
+        assert_eq!(info.compressed, CompressionType::Zip);
+
+        let info = XmlInfo::from_url("http://www.example.com/xml/device.xml").unwrap();
+        assert_eq!(
+            info.location,
+            XmlLocation::Url("http://www.example.com/xml/device.xml".parse().unwrap())
+        );
+        assert_eq!(info.compressed, CompressionType::Uncompressed);
+
+        assert!(XmlInfo::from_url("ftp://www.example.com/device.xml").is_err());
+        assert!(XmlInfo::from_url("http://www.example.com/device.xml?SchemaVersion=1").is_err());
+        assert!(XmlInfo::from_url("device.xml").is_err());
+    }
+
+    #[test]
+    fn test_detect_xml_file() {
+        assert_eq!(detect_xml_file(b"PK\x03\x04"), Some(CompressionType::Zip));
+        assert_eq!(
+            detect_xml_file(b"<?xml version"),
+            Some(CompressionType::Uncompressed)
+        );
+        assert_eq!(
+            detect_xml_file(b"\xEF\xBB\xBF\r\n <Reg"),
+            Some(CompressionType::Uncompressed)
+        );
+        assert_eq!(detect_xml_file(&[0; 16]), None);
+        assert_eq!(detect_xml_file(&[]), None);
+    }
+}
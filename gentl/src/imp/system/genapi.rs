@@ -14,9 +14,10 @@ use crate::imp::{
 };
 
 use GenApiReg::{
-    GevInterfaceDefaultGateway, GevInterfaceDefaultIPAddress, GevInterfaceDefaultSubnetMask,
-    GevInterfaceMACAddress, InterfaceID, InterfaceSelector, InterfaceSelectorMax,
-    InterfaceUpdateList, TlPath,
+    GevDiscoveryTimeout, GevInterfaceDefaultGateway, GevInterfaceDefaultIPAddress,
+    GevInterfaceDefaultSubnetMask, GevInterfaceMACAddress, InterfaceExcludeFilter, InterfaceID,
+    InterfaceIncludeFilter, InterfaceSelector, InterfaceSelectorMax, InterfaceUpdateList, LogLevel,
+    TlPath,
 };
 
 #[memory]
@@ -33,7 +34,7 @@ pub(super) enum GenApiReg {
 
     /// Updates the internal list of the interfaces when non zero value is wrritten to this
     /// register.
-    #[register(len = 4, access = RO, ty = u32)]
+    #[register(len = 4, access = WO, ty = u32)]
     InterfaceUpdateList,
 
     /// Selector for the different GenTL Producer interfaces.
@@ -63,6 +64,22 @@ pub(super) enum GenApiReg {
     /// Gateway of the selected interface.
     #[register(len = 4, access = RO, ty = u32)]
     GevInterfaceDefaultGateway,
+
+    /// Comma separated patterns of interface IDs to expose.
+    #[register(len = 256, access = RW, ty = String)]
+    InterfaceIncludeFilter,
+
+    /// Comma separated patterns of interface IDs to hide.
+    #[register(len = 256, access = RW, ty = String)]
+    InterfaceExcludeFilter,
+
+    /// Timeout of GEV device discovery in milliseconds.
+    #[register(len = 4, access = RW, ty = u32)]
+    GevDiscoveryTimeout,
+
+    /// Log level of the producer.
+    #[register(len = 4, access = RW, ty = u32)]
+    LogLevel,
 }
 
 #[register_map(base=GENAPI_XML_ADDRESS, endianness=LE)]
@@ -114,6 +131,7 @@ xsi:schemaLocation="http://www.genicam.org/GenApi/Version_1_1 http://www.genicam
 
         <pFeature>SystemInformation</pFeature>
         <pFeature>InterfaceEnumeration</pFeature>
+        <pFeature>ProducerSettings</pFeature>
     </Category>
 
     <Port Name="{PORT_NAME}" NameSpace="Standard">
@@ -316,6 +334,84 @@ xsi:schemaLocation="http://www.genicam.org/GenApi/Version_1_1 http://www.genicam
         <Endianess>LittleEndian</Endianess>
         <Representation>IPV4Address</Representation>
     </IntReg>
+    <Category Name="ProducerSettings" NameSpace="Custom">
+        <Description>Category that contains runtime settings of the GenTL Producer.</Description>
+        <Visibility>Expert</Visibility>
+
+        <pFeature>InterfaceIncludeFilter</pFeature>
+        <pFeature>InterfaceExcludeFilter</pFeature>
+        <pFeature>GevDiscoveryTimeout</pFeature>
+        <pFeature>LogLevel</pFeature>
+    </Category>
+
+    <StringReg Name="InterfaceIncludeFilter" NameSpace="Custom">
+        <Description>Comma separated patterns of interface IDs to expose, where '*' matches any sequence of characters. All interfaces are exposed if empty. The filter is applied by the next InterfaceUpdateList.</Description>
+        <Visibility>Expert</Visibility>
+        <Address>{include_filter_addr}</Address>
+        <Length>{include_filter_len}</Length>
+        <AccessMode>{include_filter_access}</AccessMode>
+        <pPort>{PORT_NAME}</pPort>
+    </StringReg>
+
+    <StringReg Name="InterfaceExcludeFilter" NameSpace="Custom">
+        <Description>Comma separated patterns of interface IDs to hide, which take precedence over InterfaceIncludeFilter. The filter is applied by the next InterfaceUpdateList.</Description>
+        <Visibility>Expert</Visibility>
+        <Address>{exclude_filter_addr}</Address>
+        <Length>{exclude_filter_len}</Length>
+        <AccessMode>{exclude_filter_access}</AccessMode>
+        <pPort>{PORT_NAME}</pPort>
+    </StringReg>
+
+    <Integer Name="GevDiscoveryTimeout" NameSpace="Custom">
+        <Description>Timeout of GigE Vision device discovery in milliseconds.</Description>
+        <Visibility>Expert</Visibility>
+        <pValue>GevDiscoveryTimeoutReg</pValue>
+        <Min>0</Min>
+        <Max>4294967295</Max>
+        <Unit>ms</Unit>
+    </Integer>
+
+    <IntReg Name="GevDiscoveryTimeoutReg" NameSpace="Custom">
+        <Visibility>Invisible</Visibility>
+        <Address>{discovery_timeout_addr}</Address>
+        <Length>{discovery_timeout_len}</Length>
+        <AccessMode>{discovery_timeout_access}</AccessMode>
+        <pPort>{PORT_NAME}</pPort>
+        <Endianess>LittleEndian</Endianess>
+    </IntReg>
+
+    <Enumeration Name="LogLevel" NameSpace="Custom">
+        <Description>Level of the log written to the log file of the GenTL Producer. Logging is available only if the log file is specified in the configuration file.</Description>
+        <Visibility>Expert</Visibility>
+        <EnumEntry Name="Off" NameSpace="Custom">
+            <Value>0</Value>
+        </EnumEntry>
+        <EnumEntry Name="Error" NameSpace="Custom">
+            <Value>1</Value>
+        </EnumEntry>
+        <EnumEntry Name="Warn" NameSpace="Custom">
+            <Value>2</Value>
+        </EnumEntry>
+        <EnumEntry Name="Info" NameSpace="Custom">
+            <Value>3</Value>
+        </EnumEntry>
+        <EnumEntry Name="Debug" NameSpace="Custom">
+            <Value>4</Value>
+        </EnumEntry>
+        <EnumEntry Name="Trace" NameSpace="Custom">
+            <Value>5</Value>
+        </EnumEntry>
+        <pValue>LogLevelReg</pValue>
+    </Enumeration>
+
+    <IntReg Name="LogLevelReg" NameSpace="Custom">
+        <Visibility>Invisible</Visibility>
+        <Address>{log_level_addr}</Address>
+        <Length>{log_level_len}</Length>
+        <AccessMode>{log_level_access}</AccessMode>
+        <pPort>{PORT_NAME}</pPort>
+        <Endianess>LittleEndian</Endianess>
+    </IntReg>
 </RegisterDescription>"#,
     tl_type = TL_TYPE.as_str(),
     tl_path_addr = TlPath::ADDRESS,
@@ -347,4 +443,16 @@ xsi:schemaLocation="http://www.genicam.org/GenApi/Version_1_1 http://www.genicam
     default_gateway_addr = GevInterfaceDefaultGateway::ADDRESS,
     default_gateway_len = GevInterfaceDefaultGateway::LENGTH,
     default_gateway_access = GevInterfaceDefaultGateway::ACCESS_RIGHT.as_str(),
+    include_filter_addr = InterfaceIncludeFilter::ADDRESS,
+    include_filter_len = InterfaceIncludeFilter::LENGTH,
+    include_filter_access = InterfaceIncludeFilter::ACCESS_RIGHT.as_str(),
+    exclude_filter_addr = InterfaceExcludeFilter::ADDRESS,
+    exclude_filter_len = InterfaceExcludeFilter::LENGTH,
+    exclude_filter_access = InterfaceExcludeFilter::ACCESS_RIGHT.as_str(),
+    discovery_timeout_addr = GevDiscoveryTimeout::ADDRESS,
+    discovery_timeout_len = GevDiscoveryTimeout::LENGTH,
+    discovery_timeout_access = GevDiscoveryTimeout::ACCESS_RIGHT.as_str(),
+    log_level_addr = LogLevel::ADDRESS,
+    log_level_len = LogLevel::LENGTH,
+    log_level_access = LogLevel::ACCESS_RIGHT.as_str(),
 );
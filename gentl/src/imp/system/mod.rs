@@ -4,6 +4,7 @@
 
 use std::{
     collections::VecDeque,
+    net::Ipv4Addr,
     path::Path,
     sync::{Arc, Mutex},
 };
@@ -13,8 +14,8 @@ use cameleon_impl::memory::{prelude::*, MemoryObserver};
 
 use crate::{
     imp::{
-        genapi_common,
-        interface::{u3v::U3VInterfaceModule, Interface},
+        config, genapi_common,
+        interface::{gige::GEVInterfaceModule, nic, u3v::U3VInterfaceModule, Interface},
     },
     GenTlResult,
 };
@@ -26,8 +27,6 @@ use super::{
 
 mod genapi;
 
-const NUM_INTERFACE: usize = 1;
-
 pub(crate) struct SystemModule {
     vm: genapi::Memory,
     port_info: PortInfo,
@@ -35,7 +34,11 @@ pub(crate) struct SystemModule {
     system_info: SystemInfo,
     is_opened: bool,
 
-    interfaces: [Box<Mutex<dyn Interface + Send>>; NUM_INTERFACE],
+    /// All interfaces found on initialization. Interfaces are never removed from the list so that
+    /// handles of them stay valid even after they are filtered out.
+    interfaces: Vec<Box<Mutex<dyn Interface + Send>>>,
+    /// Indices of interfaces which pass the interface filters of the configuration.
+    visible_interfaces: Vec<usize>,
     event_queue: Arc<Mutex<VecDeque<MemoryEvent>>>,
 }
 
@@ -102,7 +105,8 @@ impl SystemModule {
             system_info,
             is_opened: false,
 
-            interfaces: [Box::new(Mutex::new(U3VInterfaceModule::new()))],
+            interfaces: Self::create_interfaces(),
+            visible_interfaces: vec![],
             event_queue: Arc::new(Mutex::new(VecDeque::new())),
         };
 
@@ -121,7 +125,7 @@ impl SystemModule {
 
     pub(crate) fn close(&mut self) -> GenTlResult<()> {
         self.assert_open()?;
-        for iface in &mut self.interfaces() {
+        for iface in &self.interfaces {
             let _res = iface.lock().unwrap().close();
         }
 
@@ -133,7 +137,45 @@ impl SystemModule {
     }
 
     pub(crate) fn interfaces(&self) -> impl Iterator<Item = &Mutex<dyn Interface + Send>> {
-        self.interfaces.iter().map(std::convert::AsRef::as_ref)
+        self.visible_interfaces
+            .iter()
+            .map(move |&idx| self.interfaces[idx].as_ref())
+    }
+
+    /// Applies the interface filters of the configuration to the interface list.
+    ///
+    /// Returns `true` if the list is changed.
+    pub(crate) fn update_interface_list(&mut self) -> GenTlResult<bool> {
+        use genapi::GenApiReg;
+
+        let visible_interfaces: Vec<usize> = {
+            let config = config::config();
+            self.interfaces
+                .iter()
+                .enumerate()
+                .filter(|(_, iface)| {
+                    config
+                        .interface
+                        .is_enabled(iface.lock().unwrap().interface_id())
+                })
+                .map(|(idx, _)| idx)
+                .collect()
+        };
+        if visible_interfaces == self.visible_interfaces {
+            return Ok(false);
+        }
+        self.visible_interfaces = visible_interfaces;
+
+        // Interfaces may be all filtered out.
+        self.vm.write::<GenApiReg::InterfaceSelector>(0)?;
+        if !self.visible_interfaces.is_empty() {
+            self.handle_interface_selector_change()?;
+        }
+        self.vm.write::<GenApiReg::InterfaceSelectorMax>(
+            (self.visible_interfaces.len() as u32).saturating_sub(1),
+        )?;
+
+        Ok(true)
     }
 
     pub(crate) fn interface_of(&self, id: &str) -> Option<&Mutex<dyn Interface + Send>> {
@@ -162,11 +204,21 @@ impl SystemModule {
             .map_err(|e| GenTlError::Error(format!("{:?}", e)))?;
         self.vm.write::<GenApiReg::TlPath>(full_path)?;
 
+        // Initialize registers of settings with the configuration.
+        {
+            let config = config::config();
+            self.vm
+                .write::<GenApiReg::InterfaceIncludeFilter>(config.interface.include.join(","))?;
+            self.vm
+                .write::<GenApiReg::InterfaceExcludeFilter>(config.interface.exclude.join(","))?;
+            let timeout = config.gige.discovery_timeout_ms.min(u32::MAX.into()) as u32;
+            self.vm.write::<GenApiReg::GevDiscoveryTimeout>(timeout)?;
+            self.vm
+                .write::<GenApiReg::LogLevel>(config.log.level.as_raw())?;
+        }
+
         // Initialize registers related to interface.
-        self.vm.write::<GenApiReg::InterfaceSelector>(0)?;
-        self.handle_interface_selector_change()?;
-        self.vm
-            .write::<GenApiReg::InterfaceSelectorMax>(NUM_INTERFACE as u32 - 1)?;
+        self.update_interface_list()?;
 
         // Register observers that trigger events in response to memory write.
         self.register_observers();
@@ -174,6 +226,24 @@ impl SystemModule {
         Ok(())
     }
 
+    /// Creates an U3V interface and a GEV interface for each host network interface.
+    fn create_interfaces() -> Vec<Box<Mutex<dyn Interface + Send>>> {
+        let mut interfaces: Vec<Box<Mutex<dyn Interface + Send>>> =
+            vec![Box::new(Mutex::new(U3VInterfaceModule::new()))];
+
+        // Failure of network interface enumeration shouldn't prevent U3V devices from being used.
+        match nic::enumerate_network_interfaces() {
+            Ok(nics) => {
+                for nic in nics {
+                    interfaces.push(Box::new(Mutex::new(GEVInterfaceModule::new(nic))));
+                }
+            }
+            Err(e) => tracing::warn!("failed to enumerate network interfaces: {}", e),
+        }
+
+        interfaces
+    }
+
     fn full_path() -> std::path::PathBuf {
         let path = Path::new("../").join(file!());
         std::fs::canonicalize(path).unwrap()
@@ -189,6 +259,16 @@ impl SystemModule {
         let interface_selector_observer = InterfaceSelectorRegObserver(self.event_queue.clone());
         self.vm
             .register_observer::<GenApiReg::InterfaceSelector, _>(interface_selector_observer);
+
+        let settings_observer = SettingsRegObserver(self.event_queue.clone());
+        self.vm
+            .register_observer::<GenApiReg::InterfaceIncludeFilter, _>(settings_observer.clone());
+        self.vm
+            .register_observer::<GenApiReg::InterfaceExcludeFilter, _>(settings_observer.clone());
+        self.vm
+            .register_observer::<GenApiReg::GevDiscoveryTimeout, _>(settings_observer.clone());
+        self.vm
+            .register_observer::<GenApiReg::LogLevel, _>(settings_observer);
     }
 
     fn handle_events(&mut self) -> GenTlResult<()> {
@@ -197,9 +277,11 @@ impl SystemModule {
             let event = self.event_queue.lock().unwrap().pop_front();
 
             match event {
-                // We don't implement dynamic update of interfaces.
-                Some(MemoryEvent::InterfaceUpdateList) => {}
+                Some(MemoryEvent::InterfaceUpdateList) => {
+                    self.update_interface_list()?;
+                }
                 Some(MemoryEvent::InterfaceSelector) => self.handle_interface_selector_change()?,
+                Some(MemoryEvent::Settings) => self.handle_settings_change()?,
                 None => break,
             }
         }
@@ -213,9 +295,10 @@ impl SystemModule {
         let interface_idx = self.vm.read::<GenApiReg::InterfaceSelector>()? as usize;
 
         // Specified interface doesn't exist. In that case, just ignore.
-        if interface_idx >= self.interfaces.len() {
-            return Err(GenTlError::InvalidIndex);
-        }
+        let interface_idx = *self
+            .visible_interfaces
+            .get(interface_idx)
+            .ok_or(GenTlError::InvalidIndex)?;
 
         let interface = &self.interfaces[interface_idx].lock().unwrap();
 
@@ -234,30 +317,58 @@ impl SystemModule {
             }};
         }
 
-        if let Some(mac_addr) = interface.mac_addr() {
-            let mac_addr = byte_array_to_int!(mac_addr, 6, u64);
-            self.vm
-                .write::<GenApiReg::GevInterfaceMACAddress>(mac_addr)?
-        }
+        // Clear registers of the previously selected interface if the interface doesn't have them.
+        let mac_addr = interface.mac_addr().unwrap_or_default();
+        let mac_addr = byte_array_to_int!(mac_addr, 6, u64);
+        self.vm
+            .write::<GenApiReg::GevInterfaceMACAddress>(mac_addr)?;
 
-        if let Some(ip_addr) = interface.ip_addr() {
-            let ip_addr = byte_array_to_int!(ip_addr.octets(), 4, u32);
-            self.vm
-                .write::<GenApiReg::GevInterfaceDefaultIPAddress>(ip_addr)?
-        }
+        let ip_addr = interface.ip_addr().unwrap_or(Ipv4Addr::UNSPECIFIED);
+        let ip_addr = byte_array_to_int!(ip_addr.octets(), 4, u32);
+        self.vm
+            .write::<GenApiReg::GevInterfaceDefaultIPAddress>(ip_addr)?;
 
-        if let Some(subnet_mask) = interface.subnet_mask() {
-            let subnet_mask = byte_array_to_int!(subnet_mask.octets(), 4, u32);
-            self.vm
-                .write::<GenApiReg::GevInterfaceDefaultSubnetMask>(subnet_mask)?
-        }
+        let subnet_mask = interface.subnet_mask().unwrap_or(Ipv4Addr::UNSPECIFIED);
+        let subnet_mask = byte_array_to_int!(subnet_mask.octets(), 4, u32);
+        self.vm
+            .write::<GenApiReg::GevInterfaceDefaultSubnetMask>(subnet_mask)?;
 
-        if let Some(gateway_addr) = interface.gateway_addr() {
-            let gateway_addr = byte_array_to_int!(gateway_addr.octets(), 4, u32);
-            self.vm
-                .write::<GenApiReg::GevInterfaceDefaultGateway>(gateway_addr)?
+        let gateway_addr = interface.gateway_addr().unwrap_or(Ipv4Addr::UNSPECIFIED);
+        let gateway_addr = byte_array_to_int!(gateway_addr.octets(), 4, u32);
+        self.vm
+            .write::<GenApiReg::GevInterfaceDefaultGateway>(gateway_addr)?;
+
+        Ok(())
+    }
+
+    /// Reflects the registers of settings to the configuration.
+    fn handle_settings_change(&mut self) -> GenTlResult<()> {
+        use genapi::GenApiReg;
+
+        fn split_patterns(patterns: &str) -> Vec<String> {
+            patterns
+                .split(',')
+                .map(str::trim)
+                .filter(|pat| !pat.is_empty())
+                .map(Into::into)
+                .collect()
         }
 
+        let include = split_patterns(&self.vm.read::<GenApiReg::InterfaceIncludeFilter>()?);
+        let exclude = split_patterns(&self.vm.read::<GenApiReg::InterfaceExcludeFilter>()?);
+        let timeout = self.vm.read::<GenApiReg::GevDiscoveryTimeout>()?;
+        let raw_level = self.vm.read::<GenApiReg::LogLevel>()?;
+        let log_level = config::LogLevel::from_raw(raw_level).ok_or_else(|| {
+            GenTlError::InvalidValue(format!("invalid log level {}", raw_level).into())
+        })?;
+
+        config::update(|config| {
+            config.interface.include = include;
+            config.interface.exclude = exclude;
+            config.gige.discovery_timeout_ms = timeout.into();
+        });
+        config::set_log_level(log_level);
+
         Ok(())
     }
 }
@@ -324,6 +435,7 @@ pub(crate) struct SystemInfo {
 enum MemoryEvent {
     InterfaceUpdateList,
     InterfaceSelector,
+    Settings,
 }
 
 #[derive(Clone)]
@@ -348,6 +460,14 @@ impl MemoryObserver for InterfaceSelectorRegObserver {
     }
 }
 
+#[derive(Clone)]
+struct SettingsRegObserver(Arc<Mutex<VecDeque<MemoryEvent>>>);
+impl MemoryObserver for SettingsRegObserver {
+    fn update(&self) {
+        self.0.lock().unwrap().push_back(MemoryEvent::Settings)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,229 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Development tasks of cameleon.
+//!
+//! ```sh
+//! # Builds `cameleon-gentl` and bundles it into `target/cti/<PLATFORM>/cameleon_gentl.cti`.
+//! cargo xtask cti [--target <TRIPLE>] [--debug]
+//! ```
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{self, Command},
+};
+
+type Result<T> = std::result::Result<T, String>;
+
+const USAGE: &str = "\
+USAGE:
+    cargo xtask cti [--target <TRIPLE>] [--debug]
+
+TASKS:
+    cti    Builds cameleon-gentl and bundles it as a GenTL producer (.cti)";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let res = match args.first().map(String::as_str) {
+        Some("cti") => cti(&args[1..]),
+        _ => Err(USAGE.into()),
+    };
+
+    if let Err(err) = res {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
+
+fn cti(args: &[String]) -> Result<()> {
+    let mut target = None;
+    let mut release = true;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--target" => target = Some(args.next().ok_or("`--target` requires a triple")?),
+            "--debug" => release = false,
+            _ => return Err(format!("unknown argument `{}`\n\n{}", arg, USAGE)),
+        }
+    }
+
+    let mut cmd = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".into()));
+    cmd.current_dir(workspace_root())
+        .args(["build", "--package", "cameleon-gentl"]);
+    if release {
+        cmd.arg("--release");
+    }
+    if let Some(target) = target {
+        cmd.args(["--target", target]);
+    }
+    let status = cmd
+        .status()
+        .map_err(|e| format!("failed to run cargo: {}", e))?;
+    if !status.success() {
+        return Err("failed to build cameleon-gentl".into());
+    }
+
+    let platform = match target {
+        Some(target) => Platform::from_triple(target)?,
+        None => Platform::host()?,
+    };
+
+    let mut artifact_dir = target_dir();
+    if let Some(target) = target {
+        artifact_dir.push(target);
+    }
+    artifact_dir.push(if release { "release" } else { "debug" });
+    let artifact = artifact_dir.join(platform.library_name());
+
+    let bundle_dir = target_dir().join("cti").join(platform.dir_name());
+    fs::create_dir_all(&bundle_dir)
+        .map_err(|e| format!("failed to create {}: {}", bundle_dir.display(), e))?;
+    let bundle = bundle_dir.join("cameleon_gentl.cti");
+    fs::copy(&artifact, &bundle).map_err(|e| {
+        format!(
+            "failed to copy {} to {}: {}",
+            artifact.display(),
+            bundle.display(),
+            e
+        )
+    })?;
+
+    println!("created {}", bundle.display());
+    println!(
+        "add {} to `{}` to let consumers find the producer",
+        bundle_dir.display(),
+        platform.path_env()
+    );
+    Ok(())
+}
+
+fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .to_path_buf()
+}
+
+fn target_dir() -> PathBuf {
+    env::var_os("CARGO_TARGET_DIR").map_or_else(|| workspace_root().join("target"), PathBuf::from)
+}
+
+/// Platform of a producer, which determines its installation directory.
+/// See "GenTL Producer Installation" of GenTL specification for details.
+struct Platform {
+    os: Os,
+    arch: Arch,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Os {
+    Windows,
+    Linux,
+    Mac,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Arch {
+    X86,
+    X86_64,
+    Arm,
+    Aarch64,
+}
+
+impl Platform {
+    fn host() -> Result<Self> {
+        Self::new(env::consts::OS, env::consts::ARCH)
+    }
+
+    fn from_triple(triple: &str) -> Result<Self> {
+        let arch = triple.split('-').next().unwrap_or_default();
+        let os = match triple {
+            _ if triple.contains("windows") => "windows",
+            _ if triple.contains("linux") => "linux",
+            _ if triple.contains("darwin") => "macos",
+            _ => return Err(format!("unsupported target: {}", triple)),
+        };
+        let arch = match arch {
+            "i586" | "i686" => "x86",
+            _ if arch.starts_with("arm") => "arm",
+            _ => arch,
+        };
+        Self::new(os, arch)
+    }
+
+    fn new(os: &str, arch: &str) -> Result<Self> {
+        let os = match os {
+            "windows" => Os::Windows,
+            "linux" => Os::Linux,
+            "macos" => Os::Mac,
+            _ => return Err(format!("unsupported OS: {}", os)),
+        };
+        let arch = match arch {
+            "x86" => Arch::X86,
+            "x86_64" => Arch::X86_64,
+            "arm" => Arch::Arm,
+            "aarch64" => Arch::Aarch64,
+            _ => return Err(format!("unsupported architecture: {}", arch)),
+        };
+        Ok(Self { os, arch })
+    }
+
+    fn library_name(&self) -> &'static str {
+        match self.os {
+            Os::Windows => "cameleon_gentl.dll",
+            Os::Linux => "libcameleon_gentl.so",
+            Os::Mac => "libcameleon_gentl.dylib",
+        }
+    }
+
+    fn dir_name(&self) -> &'static str {
+        match (self.os, self.arch) {
+            (Os::Windows, Arch::X86) => "Win32_i86",
+            (Os::Windows, Arch::X86_64) => "Win64_x64",
+            (Os::Windows, Arch::Arm) => "Win32_ARM",
+            (Os::Windows, Arch::Aarch64) => "Win64_ARM",
+            (Os::Linux, Arch::X86) => "Linux32_i86",
+            (Os::Linux, Arch::X86_64) => "Linux64_x64",
+            (Os::Linux, Arch::Arm) => "Linux32_ARMhf",
+            (Os::Linux, Arch::Aarch64) => "Linux64_ARM",
+            (Os::Mac, Arch::X86) => "Maci32_i86",
+            (Os::Mac, Arch::X86_64) => "Maci64_x64",
+            (Os::Mac, Arch::Arm) => "Maci32_ARM",
+            (Os::Mac, Arch::Aarch64) => "Maci64_ARM",
+        }
+    }
+
+    fn path_env(&self) -> &'static str {
+        match self.arch {
+            Arch::X86 | Arch::Arm => "GENICAM_GENTL32_PATH",
+            Arch::X86_64 | Arch::Aarch64 => "GENICAM_GENTL64_PATH",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_platform_from_triple() {
+        let platform = Platform::from_triple("x86_64-unknown-linux-gnu").unwrap();
+        assert_eq!(platform.dir_name(), "Linux64_x64");
+        assert_eq!(platform.path_env(), "GENICAM_GENTL64_PATH");
+
+        let platform = Platform::from_triple("i686-pc-windows-msvc").unwrap();
+        assert_eq!(platform.dir_name(), "Win32_i86");
+        assert_eq!(platform.library_name(), "cameleon_gentl.dll");
+        assert_eq!(platform.path_env(), "GENICAM_GENTL32_PATH");
+
+        let platform = Platform::from_triple("armv7-unknown-linux-gnueabihf").unwrap();
+        assert_eq!(platform.dir_name(), "Linux32_ARMhf");
+
+        let platform = Platform::from_triple("aarch64-apple-darwin").unwrap();
+        assert_eq!(platform.dir_name(), "Maci64_ARM");
+
+        assert!(Platform::from_triple("wasm32-unknown-unknown").is_err());
+    }
+}